@@ -0,0 +1,16 @@
+//! Generates the gRPC control API code when the `grpc` feature is enabled
+
+fn main() {
+    println!("cargo:rerun-if-changed=proto/sumi_control.proto");
+
+    #[cfg(feature = "grpc")]
+    {
+        // A bundled protoc, so building does not depend on a system install
+        std::env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc for this platform"),
+        );
+        tonic_build::compile_protos("proto/sumi_control.proto")
+            .expect("failed to compile proto/sumi_control.proto");
+    }
+}
@@ -0,0 +1,334 @@
+//! Read-only HTTP API over the crawl database
+//!
+//! Serves crawl results as JSON so dashboards can be built without direct
+//! database access. Every request opens the database read-only, so the API
+//! can run next to a crawl writing to it. Only available when built with the
+//! `api` feature.
+//!
+//! Endpoints (all `GET`):
+//!
+//! - `/summary` - the crawl summary of the latest run
+//! - `/domains?limit=&offset=` - page counts per domain, largest first
+//! - `/pages?domain=&state=&limit=&offset=` - the pages of a domain,
+//!   optionally in one state
+//! - `/links?from=` - the domain link graph, optionally from one domain
+
+use crate::config::DatabaseConfig;
+use crate::output::generate_summary;
+use crate::state::PageState;
+use crate::storage::{SqliteStorage, Storage};
+use crate::SumiError;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Rows returned by a listing endpoint without a `limit` parameter
+pub const API_DEFAULT_LIMIT: usize = 100;
+
+/// Most rows returned by a listing endpoint
+pub const API_MAX_LIMIT: usize = 1000;
+
+/// The database the API serves
+struct ApiDatabase {
+    path: PathBuf,
+    config: DatabaseConfig,
+}
+
+/// Serves the API until Ctrl-C is pressed
+///
+/// # Arguments
+///
+/// * `addr` - Address to listen on, e.g. 127.0.0.1:8080
+/// * `database_path` - Path to the crawl database
+/// * `config` - SQLite tuning for the read-only connections
+///
+/// # Returns
+///
+/// * `Ok(())` - The server shut down
+/// * `Err(SumiError)` - The address could not be bound or the server failed
+pub async fn serve_api(
+    addr: SocketAddr,
+    database_path: PathBuf,
+    config: DatabaseConfig,
+) -> Result<(), SumiError> {
+    let database = Arc::new(ApiDatabase {
+        path: database_path,
+        config,
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let database = Arc::clone(&database);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(request, Arc::clone(&database))
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)
+        .map_err(|e| SumiError::Io(std::io::Error::other(e.to_string())))?
+        .serve(make_service);
+    tracing::info!(
+        "Serving the crawl database on http://{}",
+        server.local_addr()
+    );
+
+    server
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| SumiError::Io(std::io::Error::other(e.to_string())))
+}
+
+async fn handle_request(
+    request: Request<Body>,
+    database: Arc<ApiDatabase>,
+) -> Result<Response<Body>, Infallible> {
+    let (status, body) = if request.method() != Method::GET {
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({ "error": "only GET is supported" }),
+        )
+    } else {
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().map(str::to_string);
+        // SQLite calls block, keep them off the async workers
+        tokio::task::spawn_blocking(move || {
+            match SqliteStorage::open_read_only(&database.path, &database.config) {
+                Ok(storage) => respond(&storage, &path, query.as_deref()),
+                Err(e) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    json!({ "error": e.to_string() }),
+                ),
+            }
+        })
+        .await
+        .unwrap_or_else(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({ "error": e.to_string() }),
+            )
+        })
+    };
+
+    tracing::debug!("{} {} -> {}", request.method(), request.uri(), status);
+    let response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_else(|_| Response::new(Body::empty()));
+    Ok(response)
+}
+
+/// Answers an API request
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `path` - The request path, e.g. "/pages"
+/// * `query` - The query string, if any
+///
+/// # Returns
+///
+/// The status code and the JSON body; errors are `{"error": "..."}`
+pub fn respond(storage: &dyn Storage, path: &str, query: Option<&str>) -> (StatusCode, Value) {
+    let params: HashMap<String, String> = query
+        .map(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let result = match path.trim_end_matches('/') {
+        "/summary" => summary(storage),
+        "/domains" => domains(storage, &params),
+        "/pages" => pages(storage, &params),
+        "/links" => links(storage, &params),
+        _ => Err((StatusCode::NOT_FOUND, format!("unknown endpoint {}", path))),
+    };
+
+    match result {
+        Ok(body) => (StatusCode::OK, body),
+        Err((status, message)) => (status, json!({ "error": message })),
+    }
+}
+
+type ApiResult = Result<Value, (StatusCode, String)>;
+
+fn internal(e: impl std::fmt::Display) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn summary(storage: &dyn Storage) -> ApiResult {
+    if storage.get_latest_run().map_err(internal)?.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "no crawl runs in the database".to_string(),
+        ));
+    }
+    let summary = generate_summary(storage).map_err(internal)?;
+    serde_json::to_value(summary).map_err(internal)
+}
+
+fn domains(storage: &dyn Storage, params: &HashMap<String, String>) -> ApiResult {
+    let (limit, offset) = paging(params)?;
+    let total = storage.count_unique_domains().map_err(internal)?;
+    let domains = storage
+        .get_domain_summaries_paged(limit, offset)
+        .map_err(internal)?;
+    Ok(json!({ "total": total, "domains": domains }))
+}
+
+fn pages(storage: &dyn Storage, params: &HashMap<String, String>) -> ApiResult {
+    let (limit, offset) = paging(params)?;
+    let domain = params.get("domain").ok_or((
+        StatusCode::BAD_REQUEST,
+        "the domain parameter is required".to_string(),
+    ))?;
+    let state = match params.get("state") {
+        Some(state) => Some(
+            PageState::from_db_string(state)
+                .ok_or((StatusCode::BAD_REQUEST, format!("unknown state {}", state)))?,
+        ),
+        None => None,
+    };
+
+    let total = storage
+        .count_domain_pages(domain, state)
+        .map_err(internal)?;
+    let pages = storage
+        .get_domain_pages_paged(domain, state, limit, offset)
+        .map_err(internal)?;
+    Ok(json!({ "domain": domain, "total": total, "pages": pages }))
+}
+
+fn links(storage: &dyn Storage, params: &HashMap<String, String>) -> ApiResult {
+    let from = params.get("from");
+    let links: Vec<Value> = storage
+        .get_domain_links()
+        .map_err(internal)?
+        .into_iter()
+        .filter(|(source, _, _)| from.is_none_or(|from| source == from))
+        .map(
+            |(source, target, links)| json!({ "source": source, "target": target, "links": links }),
+        )
+        .collect();
+    Ok(json!({ "links": links }))
+}
+
+/// Reads the `limit` and `offset` parameters
+fn paging(params: &HashMap<String, String>) -> Result<(usize, usize), (StatusCode, String)> {
+    let number = |name: &str, default: usize| match params.get(name) {
+        Some(value) => value.parse::<usize>().map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("{} must be a non-negative integer", name),
+            )
+        }),
+        None => Ok(default),
+    };
+    Ok((
+        number("limit", API_DEFAULT_LIMIT)?.min(API_MAX_LIMIT),
+        number("offset", 0)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SqliteStorage {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://a.com/about", "a.com", run_id)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://b.org/", "b.org", run_id)
+            .unwrap();
+        storage
+            .update_page_state(
+                home,
+                PageState::Processed,
+                Some("Home"),
+                Some(200),
+                None,
+                None,
+            )
+            .unwrap();
+        storage.insert_link(home, about, run_id).unwrap();
+        storage.insert_link(home, other, run_id).unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_pages() {
+        let storage = storage();
+        let (status, body) = respond(&storage, "/pages", Some("domain=a.com"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["pages"][0]["url"], "https://a.com/");
+        assert_eq!(body["pages"][0]["state"], "processed");
+        assert_eq!(body["pages"][0]["title"], "Home");
+
+        let (_, body) = respond(&storage, "/pages", Some("domain=a.com&state=processed"));
+        assert_eq!(body["total"], 1);
+        let (_, body) = respond(&storage, "/pages", Some("domain=a.com&limit=1&offset=1"));
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["pages"][0]["url"], "https://a.com/about");
+
+        assert_eq!(respond(&storage, "/pages", None).0, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            respond(&storage, "/pages", Some("domain=a.com&state=bogus")).0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            respond(&storage, "/pages", Some("domain=a.com&limit=-1")).0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_domains_and_links() {
+        let storage = storage();
+        let (status, body) = respond(&storage, "/domains", None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["domains"][0]["domain"], "a.com");
+        assert_eq!(body["domains"][0]["pages"], 2);
+        let (_, body) = respond(&storage, "/domains", Some("limit=1&offset=1"));
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["domains"].as_array().unwrap().len(), 1);
+        assert_eq!(body["domains"][0]["domain"], "b.org");
+
+        let (_, body) = respond(&storage, "/links", Some("from=a.com"));
+        assert_eq!(
+            body["links"],
+            json!([{ "source": "a.com", "target": "b.org", "links": 1 }])
+        );
+        let (_, body) = respond(&storage, "/links", Some("from=b.org"));
+        assert_eq!(body["links"], json!([]));
+    }
+
+    #[test]
+    fn test_summary_and_unknown_endpoint() {
+        let (status, body) = respond(&storage(), "/summary", None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total_pages"], 3);
+
+        let empty = SqliteStorage::new_in_memory().unwrap();
+        assert_eq!(respond(&empty, "/summary", None).0, StatusCode::NOT_FOUND);
+        assert_eq!(respond(&empty, "/graph", None).0, StatusCode::NOT_FOUND);
+    }
+}
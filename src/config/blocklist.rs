@@ -0,0 +1,289 @@
+//! Import of external blocklists into the blacklist
+//!
+//! Public blocklists are commonly distributed as hosts files, plain
+//! domain-per-line lists, or AdBlock filter lists. Files referenced from the
+//! config with `[[blacklist-file]]` are read every time the configuration is
+//! loaded and their domains are appended to the blacklist, so the lists can be
+//! refreshed by replacing the files and restarting (or reloading) the crawl.
+
+use crate::config::types::{BlocklistFile, BlocklistFormat, Config, DomainEntry};
+use crate::config::validation::validate_domain_pattern;
+use crate::ConfigError;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Hostnames in hosts files that refer to the local machine, not a blocked site
+const LOCAL_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+/// Appends the domains from every `[[blacklist-file]]` to the blacklist
+///
+/// Entries that are not valid domain patterns are skipped, as are domains
+/// already on the blacklist.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to extend
+/// * `base_dir` - Directory that relative blocklist paths are resolved against
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of domains added to the blacklist
+/// * `Err(ConfigError)` - A blocklist file could not be read
+pub fn import_blocklists(config: &mut Config, base_dir: &Path) -> Result<usize, ConfigError> {
+    let mut known: HashSet<String> = config
+        .blacklist
+        .iter()
+        .map(|entry| entry.domain.clone())
+        .collect();
+    let mut added = 0;
+
+    for file in &config.blacklist_files {
+        let path = base_dir.join(&file.path);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ConfigError::Blocklist(format!("{}: {}", path.display(), e)))?;
+
+        let domains = parse_blocklist(&content, file);
+        let before = added;
+        for domain in domains {
+            if known.insert(domain.clone()) {
+                config.blacklist.push(DomainEntry { domain });
+                added += 1;
+            }
+        }
+
+        tracing::info!(
+            "Imported {} blacklist domains from {}",
+            added - before,
+            path.display()
+        );
+    }
+
+    Ok(added)
+}
+
+/// Extracts domain patterns from blocklist content
+///
+/// # Arguments
+///
+/// * `content` - The blocklist file content
+/// * `file` - The blocklist settings (format and subdomain handling)
+///
+/// # Returns
+///
+/// Valid, lowercase domain patterns in file order, without duplicates
+pub fn parse_blocklist(content: &str, file: &BlocklistFile) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut domains = Vec::new();
+
+    for line in content.lines() {
+        for domain in parse_line(line.trim(), file.format) {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            let pattern = if file.include_subdomains && !domain.starts_with("*.") {
+                format!("*.{}", domain)
+            } else {
+                domain
+            };
+
+            if validate_domain_pattern(&pattern).is_ok() && seen.insert(pattern.clone()) {
+                domains.push(pattern);
+            }
+        }
+    }
+
+    domains
+}
+
+/// Extracts domain patterns from a single line
+fn parse_line(line: &str, format: BlocklistFormat) -> Vec<String> {
+    if line.is_empty() {
+        return Vec::new();
+    }
+
+    match format {
+        BlocklistFormat::Hosts => parse_hosts_line(line),
+        BlocklistFormat::Domains => parse_domain_line(line),
+        BlocklistFormat::Adblock => parse_adblock_line(line).into_iter().collect(),
+        BlocklistFormat::Auto => {
+            if line.starts_with("||") || line.starts_with('!') || line.starts_with('[') {
+                parse_adblock_line(line).into_iter().collect()
+            } else if line
+                .split_whitespace()
+                .next()
+                .is_some_and(|first| first.parse::<IpAddr>().is_ok())
+            {
+                parse_hosts_line(line)
+            } else {
+                parse_domain_line(line)
+            }
+        }
+    }
+}
+
+/// Parses "0.0.0.0 ads.example.com tracker.example.com # comment"
+fn parse_hosts_line(line: &str) -> Vec<String> {
+    let line = strip_comment(line);
+    let mut fields = line.split_whitespace();
+
+    match fields.next() {
+        Some(address) if address.parse::<IpAddr>().is_ok() => fields
+            .filter(|host| !LOCAL_HOSTNAMES.contains(&host.to_lowercase().as_str()))
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses "ads.example.com # comment"
+fn parse_domain_line(line: &str) -> Vec<String> {
+    strip_comment(line)
+        .split_whitespace()
+        .next()
+        .map(|domain| vec![domain.to_string()])
+        .unwrap_or_default()
+}
+
+/// Parses an AdBlock domain rule such as "||ads.example.com^$third-party"
+///
+/// Only whole-domain blocking rules are used. Comments, exceptions (`@@`),
+/// element hiding rules and rules with paths are ignored. A domain rule blocks
+/// the domain and all of its subdomains, so it becomes a wildcard pattern.
+fn parse_adblock_line(line: &str) -> Option<String> {
+    let rule = line.strip_prefix("||")?;
+    let end = rule.find(['^', '$']).unwrap_or(rule.len());
+    let (domain, rest) = rule.split_at(end);
+
+    // "||example.com^" or "||example.com^$options", but not "||example.com/ads"
+    let rest = rest.strip_prefix('^').unwrap_or(rest);
+    if !(rest.is_empty() || rest.starts_with('$')) || domain.contains(['/', '*']) {
+        return None;
+    }
+
+    Some(format!("*.{}", domain))
+}
+
+/// Removes a trailing `#` comment
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+
+    fn settings(format: BlocklistFormat, include_subdomains: bool) -> BlocklistFile {
+        BlocklistFile {
+            path: String::new(),
+            format,
+            include_subdomains,
+        }
+    }
+
+    #[test]
+    fn test_parse_hosts_file() {
+        let content = "\
+# Ad servers
+127.0.0.1 localhost
+::1 ip6-localhost
+0.0.0.0 ads.example.com tracker.example.com # trackers
+0.0.0.0 ADS.example.com
+";
+        let domains = parse_blocklist(content, &settings(BlocklistFormat::Hosts, false));
+        assert_eq!(domains, vec!["ads.example.com", "tracker.example.com"]);
+    }
+
+    #[test]
+    fn test_parse_domain_list() {
+        let content = "spam.com\n\n# comment\nbad.net   # inline\nnot a domain!\n*.farm.org\n";
+        let domains = parse_blocklist(content, &settings(BlocklistFormat::Domains, false));
+        assert_eq!(domains, vec!["spam.com", "bad.net", "*.farm.org"]);
+    }
+
+    #[test]
+    fn test_parse_adblock_list() {
+        let content = "\
+[Adblock Plus 2.0]
+! Title: test list
+||ads.example.com^
+||tracker.net^$third-party
+@@||allowed.com^
+||example.org/banner
+##.ad-banner
+";
+        let domains = parse_blocklist(content, &settings(BlocklistFormat::Adblock, false));
+        assert_eq!(domains, vec!["*.ads.example.com", "*.tracker.net"]);
+    }
+
+    #[test]
+    fn test_auto_detects_mixed_formats() {
+        let content = "0.0.0.0 hosts.example.com\n||adblock.example.com^\nplain.example.com\n";
+        let domains = parse_blocklist(content, &settings(BlocklistFormat::Auto, false));
+        assert_eq!(
+            domains,
+            vec![
+                "hosts.example.com",
+                "*.adblock.example.com",
+                "plain.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_subdomains() {
+        let domains = parse_blocklist(
+            "spam.com\n*.farm.org\n",
+            &settings(BlocklistFormat::Domains, true),
+        );
+        assert_eq!(domains, vec!["*.spam.com", "*.farm.org"]);
+    }
+
+    #[test]
+    fn test_import_skips_existing_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("list.txt"), "spam.com\nnew.com\n").unwrap();
+
+        let mut config: Config = toml::from_str(&test_config_toml(
+            r#"
+[[blacklist]]
+domain = "spam.com"
+
+[[blacklist-file]]
+path = "list.txt"
+"#,
+        ))
+        .unwrap();
+
+        let added = import_blocklists(&mut config, dir.path()).unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(config.blacklist.len(), 2);
+        assert_eq!(config.blacklist[1].domain, "new.com");
+    }
+
+    #[test]
+    fn test_import_missing_file() {
+        let mut config: Config = toml::from_str(&test_config_toml(
+            "\n[[blacklist-file]]\npath = \"does-not-exist.txt\"\n",
+        ))
+        .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(matches!(
+            import_blocklists(&mut config, dir.path()),
+            Err(ConfigError::Blocklist(_))
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+//! Detection of duplicate and conflicting domain list entries
+//!
+//! At runtime a domain is classified by the first list that matches it, in
+//! priority order blacklist, stub, quality. That makes conflicting entries
+//! legal but easy to get wrong, so they are reported when the configuration is
+//! validated:
+//!
+//! - the same pattern listed twice in one list (warning)
+//! - the same pattern in several lists (warning, or an error if a quality
+//!   entry loses, because its seeds could never be crawled)
+//! - an entry covered by a wildcard in the same list (warning)
+//! - an entry covered by a wildcard in a higher-priority list, so it never
+//!   applies (warning, or an error for quality entries)
+//! - a narrower entry carving an exception out of a lower-priority wildcard
+//!   (note)
+//! - a seed URL on a blacklisted or stubbed domain (error)
+//!
+//! Lookups go through an index of patterns and the wildcard patterns that
+//! could cover them, so imported blocklists with many thousands of entries
+//! are checked in linear time.
+
+use crate::config::diagnostics::{Diagnostic, Severity};
+use crate::config::types::Config;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use url::Url;
+
+/// A domain list, ordered by classification priority (highest first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum ListKind {
+    Blacklist,
+    Stub,
+    Quality,
+}
+
+impl ListKind {
+    const ALL: [ListKind; 3] = [ListKind::Blacklist, ListKind::Stub, ListKind::Quality];
+
+    /// How a domain matched by this list is treated
+    fn outcome(&self) -> &'static str {
+        match self {
+            ListKind::Blacklist => "blacklisted",
+            ListKind::Stub => "stubbed",
+            ListKind::Quality => "crawled as a quality domain",
+        }
+    }
+}
+
+impl fmt::Display for ListKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ListKind::Blacklist => "[[blacklist]]",
+            ListKind::Stub => "[[stub]]",
+            ListKind::Quality => "[[quality]]",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Finds duplicate and conflicting entries in the domain lists
+///
+/// # Arguments
+///
+/// * `config` - The configuration to check
+///
+/// # Returns
+///
+/// Diagnostics in a stable order (by pattern); empty if there are no conflicts
+pub fn find_conflicts(config: &Config) -> Vec<Diagnostic> {
+    let index = build_index(config);
+    let mut diagnostics = Vec::new();
+    let mut shadowed_quality = HashSet::new();
+
+    let mut patterns: Vec<&String> = index.keys().collect();
+    patterns.sort();
+
+    for pattern in patterns {
+        let kinds = &index[pattern];
+        let lists = distinct(kinds);
+
+        for kind in ListKind::ALL {
+            let count = kinds.iter().filter(|k| **k == kind).count();
+            if count > 1 {
+                let note = if kind == ListKind::Quality {
+                    "; their seeds are combined"
+                } else {
+                    ""
+                };
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    "duplicate-entry",
+                    format!("'{}' appears {} times in {}{}", pattern, count, kind, note),
+                ));
+            }
+        }
+
+        if lists.len() > 1 {
+            let winner = lists[0];
+            let losers: Vec<String> = lists[1..].iter().map(|k| k.to_string()).collect();
+            let severity = if lists.contains(&ListKind::Quality) {
+                shadowed_quality.insert(pattern.clone());
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            diagnostics.push(Diagnostic::new(
+                severity,
+                "conflicting-entry",
+                format!(
+                    "'{}' is listed in both {} and {}; {} takes priority, so it is {}",
+                    pattern,
+                    winner,
+                    losers.join(" and "),
+                    winner,
+                    winner.outcome()
+                ),
+            ));
+        }
+
+        for covering in covering_patterns(pattern) {
+            let Some(covering_kinds) = index.get(&covering) else {
+                continue;
+            };
+
+            for &kind in &lists {
+                for &other in &distinct(covering_kinds) {
+                    if other == kind {
+                        diagnostics.push(Diagnostic::new(
+                            Severity::Warning,
+                            "redundant-entry",
+                            format!(
+                                "'{}' in {} is already covered by '{}'",
+                                pattern, kind, covering
+                            ),
+                        ));
+                    } else if other < kind {
+                        let severity = if kind == ListKind::Quality {
+                            shadowed_quality.insert(pattern.clone());
+                            Severity::Error
+                        } else {
+                            Severity::Warning
+                        };
+                        diagnostics.push(Diagnostic::new(
+                            severity,
+                            "shadowed-entry",
+                            format!(
+                                "'{}' in {} is covered by '{}' in {}, which takes priority, so it is {} and the {} entry never applies",
+                                pattern, kind, covering, other, other.outcome(), kind
+                            ),
+                        ));
+                    } else {
+                        diagnostics.push(Diagnostic::new(
+                            Severity::Note,
+                            "overlapping-entry",
+                            format!(
+                                "'{}' in {} overlaps '{}' in {}; {} takes priority, so '{}' is {}",
+                                pattern,
+                                kind,
+                                covering,
+                                other,
+                                kind,
+                                pattern,
+                                kind.outcome()
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for entry in &config.quality {
+        if shadowed_quality.contains(&entry.domain.to_lowercase()) {
+            continue;
+        }
+
+        for seed in &entry.seeds {
+            let Some(host) = Url::parse(seed)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_lowercase))
+            else {
+                continue;
+            };
+
+            if let Some((kind, pattern)) = excluding_entry(&index, &host) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    "seed-excluded",
+                    format!(
+                        "Seed URL '{}' for quality domain '{}' is {} by '{}' in {} and would never be crawled",
+                        seed,
+                        entry.domain,
+                        kind.outcome(),
+                        pattern,
+                        kind
+                    ),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Maps each lowercase pattern to the lists it appears in (with repeats)
+fn build_index(config: &Config) -> HashMap<String, Vec<ListKind>> {
+    let mut index: HashMap<String, Vec<ListKind>> = HashMap::new();
+
+    let entries = config
+        .blacklist
+        .iter()
+        .map(|e| (ListKind::Blacklist, &e.domain))
+        .chain(config.stub.iter().map(|e| (ListKind::Stub, &e.domain)))
+        .chain(
+            config
+                .quality
+                .iter()
+                .map(|e| (ListKind::Quality, &e.domain)),
+        );
+
+    for (kind, domain) in entries {
+        index.entry(domain.to_lowercase()).or_default().push(kind);
+    }
+
+    index
+}
+
+/// Returns the distinct lists, highest priority first
+fn distinct(kinds: &[ListKind]) -> Vec<ListKind> {
+    let mut kinds = kinds.to_vec();
+    kinds.sort();
+    kinds.dedup();
+    kinds
+}
+
+/// Returns the wildcard patterns that match everything `pattern` matches,
+/// other than `pattern` itself
+///
+/// For "blog.example.com" these are "*.blog.example.com", "*.example.com" and
+/// "*.com"; for "*.example.com" only "*.com".
+fn covering_patterns(pattern: &str) -> Vec<String> {
+    let (base, is_wildcard) = match pattern.strip_prefix("*.") {
+        Some(base) => (base, true),
+        None => (pattern, false),
+    };
+
+    let mut patterns = Vec::new();
+    if !is_wildcard {
+        patterns.push(format!("*.{}", base));
+    }
+
+    let mut rest = base;
+    while let Some((_, parent)) = rest.split_once('.') {
+        patterns.push(format!("*.{}", parent));
+        rest = parent;
+    }
+
+    patterns
+}
+
+/// Finds the blacklist or stub entry that would exclude a host, if any
+fn excluding_entry(
+    index: &HashMap<String, Vec<ListKind>>,
+    host: &str,
+) -> Option<(ListKind, String)> {
+    std::iter::once(host.to_string())
+        .chain(covering_patterns(host))
+        .filter_map(|pattern| {
+            let kind = *index.get(&pattern)?.iter().min()?;
+            (kind != ListKind::Quality).then_some((kind, pattern))
+        })
+        .min_by_key(|(kind, _)| *kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use crate::config::types::{DomainEntry, QualityEntry};
+
+    fn config(quality: &[(&str, &str)], blacklist: &[&str], stub: &[&str]) -> Config {
+        let mut config: Config = toml::from_str(&test_config_toml("")).unwrap();
+
+        config.quality = quality
+            .iter()
+            .map(|(domain, seed)| QualityEntry {
+                domain: domain.to_string(),
+                seeds: vec![seed.to_string()],
+                seeds_file: None,
+                seeds_from_sitemap: false,
+            })
+            .collect();
+        let entries = |domains: &[&str]| {
+            domains
+                .iter()
+                .map(|d| DomainEntry {
+                    domain: d.to_string(),
+                })
+                .collect()
+        };
+        config.blacklist = entries(blacklist);
+        config.stub = entries(stub);
+        config
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<(Severity, &'static str)> {
+        diagnostics.iter().map(|d| (d.severity, d.code)).collect()
+    }
+
+    #[test]
+    fn test_covering_patterns() {
+        assert_eq!(
+            covering_patterns("blog.example.com"),
+            vec!["*.blog.example.com", "*.example.com", "*.com"]
+        );
+        assert_eq!(covering_patterns("*.example.com"), vec!["*.com"]);
+    }
+
+    #[test]
+    fn test_no_conflicts() {
+        let config = config(
+            &[("example.com", "https://example.com/")],
+            &["ads.net"],
+            &["github.com"],
+        );
+        assert!(find_conflicts(&config).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_entries() {
+        let config = config(&[], &["spam.com", "SPAM.com"], &[]);
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![(Severity::Warning, "duplicate-entry")]
+        );
+    }
+
+    #[test]
+    fn test_quality_and_blacklist_conflict() {
+        let config = config(
+            &[("example.com", "https://example.com/")],
+            &["example.com"],
+            &[],
+        );
+        let diagnostics = find_conflicts(&config);
+
+        // The seed check is not repeated for an entry that is already an error
+        assert_eq!(
+            codes(&diagnostics),
+            vec![(Severity::Error, "conflicting-entry")]
+        );
+        assert!(diagnostics[0].message.contains("blacklisted"));
+    }
+
+    #[test]
+    fn test_blacklist_and_stub_conflict_is_warning() {
+        let config = config(&[], &["spam.com"], &["spam.com"]);
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![(Severity::Warning, "conflicting-entry")]
+        );
+    }
+
+    #[test]
+    fn test_redundant_wildcard_entry() {
+        let config = config(&[], &["*.ads.net", "tracker.ads.net"], &[]);
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![(Severity::Warning, "redundant-entry")]
+        );
+    }
+
+    #[test]
+    fn test_shadowed_quality_entry() {
+        let config = config(
+            &[("docs.github.com", "https://docs.github.com/")],
+            &[],
+            &["*.github.com"],
+        );
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![(Severity::Error, "shadowed-entry")]
+        );
+    }
+
+    #[test]
+    fn test_carve_out_is_note() {
+        let config = config(
+            &[("*.example.com", "https://www.example.com/")],
+            &["ads.example.com"],
+            &[],
+        );
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![(Severity::Note, "overlapping-entry")]
+        );
+    }
+
+    #[test]
+    fn test_seed_on_excluded_domain() {
+        let config = config(
+            &[("*.example.com", "https://ads.example.com/")],
+            &["ads.example.com"],
+            &[],
+        );
+        assert_eq!(
+            codes(&find_conflicts(&config)),
+            vec![
+                (Severity::Note, "overlapping-entry"),
+                (Severity::Error, "seed-excluded")
+            ]
+        );
+    }
+}
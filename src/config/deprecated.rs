@@ -0,0 +1,120 @@
+//! Deprecated configuration keys
+//!
+//! Renamed settings keep working under their old key. When a configuration
+//! is loaded the old keys are moved to their new names with a warning, and
+//! overrides (`--set`, environment variables) addressing an old key set the
+//! new one.
+
+/// Deprecated keys and the keys that replaced them, as dotted TOML paths
+pub const DEPRECATED_KEYS: &[(&str, &str)] = &[(
+    "crawler.minimum-time-on-page",
+    "crawler.per-domain-delay-ms",
+)];
+
+/// Returns the current name of a dotted key
+///
+/// # Arguments
+///
+/// * `key` - Dotted TOML key, e.g. "crawler.minimum-time-on-page"
+///
+/// # Returns
+///
+/// The key that replaced a deprecated key, or the key itself
+pub fn current_key(key: &str) -> &str {
+    DEPRECATED_KEYS
+        .iter()
+        .find(|(old, _)| *old == key)
+        .map_or(key, |(_, new)| new)
+}
+
+/// Moves the deprecated keys of a configuration document to their new names
+///
+/// A deprecated key is dropped if the document also sets its replacement.
+/// Either way a warning is logged.
+///
+/// # Arguments
+///
+/// * `document` - The parsed configuration file
+///
+/// # Returns
+///
+/// The deprecated keys found, in the order of `DEPRECATED_KEYS`
+pub fn migrate_deprecated_keys(document: &mut toml::Value) -> Vec<&'static str> {
+    let mut found = Vec::new();
+
+    for (old, new) in DEPRECATED_KEYS {
+        let Some((table_path, old_field)) = old.rsplit_once('.') else {
+            continue;
+        };
+        let new_field = new.rsplit_once('.').map_or(*new, |(_, field)| field);
+
+        let Some(table) = table_path
+            .split('.')
+            .try_fold(&mut *document, |value, part| value.get_mut(part))
+            .and_then(toml::Value::as_table_mut)
+        else {
+            continue;
+        };
+        let Some(value) = table.remove(old_field) else {
+            continue;
+        };
+
+        if table.contains_key(new_field) {
+            tracing::warn!("Ignoring deprecated {} since {} is also set", old, new);
+        } else {
+            tracing::warn!("{} is deprecated, use {} instead", old, new);
+            table.insert(new_field.to_string(), value);
+        }
+        found.push(*old);
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_deprecated_keys() {
+        let mut document: toml::Value = toml::from_str(
+            r#"
+[crawler]
+max-depth = 3
+minimum-time-on-page = 1500
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            migrate_deprecated_keys(&mut document),
+            vec!["crawler.minimum-time-on-page"]
+        );
+        let crawler = document["crawler"].as_table().unwrap();
+        assert!(!crawler.contains_key("minimum-time-on-page"));
+        assert_eq!(crawler["per-domain-delay-ms"].as_integer(), Some(1500));
+
+        // The new key wins over the old one
+        let mut document: toml::Value = toml::from_str(
+            r#"
+[crawler]
+minimum-time-on-page = 1500
+per-domain-delay-ms = 2000
+"#,
+        )
+        .unwrap();
+        migrate_deprecated_keys(&mut document);
+        let crawler = document["crawler"].as_table().unwrap();
+        assert!(!crawler.contains_key("minimum-time-on-page"));
+        assert_eq!(crawler["per-domain-delay-ms"].as_integer(), Some(2000));
+    }
+
+    #[test]
+    fn test_current_key() {
+        assert_eq!(
+            current_key("crawler.minimum-time-on-page"),
+            "crawler.per-domain-delay-ms"
+        );
+        assert_eq!(current_key("crawler.max-depth"), "crawler.max-depth");
+    }
+}
@@ -0,0 +1,69 @@
+//! Configuration diagnostics
+//!
+//! Checks that go beyond "is this value well-formed" report their findings as
+//! diagnostics with a severity. Errors make the configuration invalid; warnings
+//! and notes are logged so the user can see how conflicts will be resolved.
+
+use serde::Serialize;
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Expected usage worth knowing about (e.g. a carve-out from a wildcard)
+    Note,
+
+    /// Probably a mistake, but the crawl can run
+    Warning,
+
+    /// The configuration cannot be used
+    Error,
+}
+
+impl Severity {
+    /// Returns the lowercase name of the severity
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single finding about a configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// How serious the finding is
+    pub severity: Severity,
+
+    /// Stable identifier for the kind of finding, e.g. "duplicate-entry"
+    pub code: &'static str,
+
+    /// Human-readable explanation, including how a conflict is resolved
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic
+    pub fn new(severity: Severity, code: &'static str, message: String) -> Self {
+        Self {
+            severity,
+            code,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.severity, self.code, self.message)
+    }
+}
@@ -0,0 +1,246 @@
+//! Differences between two serialized configurations
+//!
+//! Each run stores the effective configuration it was started with. Comparing
+//! two of those snapshots shows which limits and domain list entries changed
+//! between the datasets. Both snapshots are flattened to dotted keys first;
+//! entries of the domain lists are keyed by their domain rather than their
+//! position, so reordering a list is not reported as a change. Deprecated keys
+//! of older snapshots are compared under their current name.
+
+use crate::config::deprecated::current_key;
+use crate::ConfigError;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single difference between two configurations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChange {
+    /// A setting or list entry only present in the newer configuration
+    Added { key: String, value: String },
+
+    /// A setting or list entry only present in the older configuration
+    Removed { key: String, value: String },
+
+    /// A setting or list entry whose value differs
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { key, value } if value.is_empty() => write!(f, "+ {}", key),
+            Self::Added { key, value } => write!(f, "+ {} = {}", key, value),
+            Self::Removed { key, value } if value.is_empty() => write!(f, "- {}", key),
+            Self::Removed { key, value } => write!(f, "- {} = {}", key, value),
+            Self::Changed { key, old, new } => write!(f, "~ {}: {} -> {}", key, old, new),
+        }
+    }
+}
+
+/// Compares two serialized (TOML) configurations
+///
+/// # Arguments
+///
+/// * `old` - The earlier configuration
+/// * `new` - The later configuration
+///
+/// # Returns
+///
+/// * `Ok(Vec<ConfigChange>)` - The differences, ordered by key
+/// * `Err(ConfigError)` - One of the configurations is not valid TOML
+pub fn diff_configs(old: &str, new: &str) -> Result<Vec<ConfigChange>, ConfigError> {
+    let old = flatten_config(old)?;
+    let mut new = flatten_config(new)?;
+
+    let mut changes = Vec::new();
+    for (key, old_value) in old {
+        match new.remove(&key) {
+            Some(new_value) if new_value == old_value => {}
+            Some(new_value) => changes.push(ConfigChange::Changed {
+                key,
+                old: old_value,
+                new: new_value,
+            }),
+            None => changes.push(ConfigChange::Removed {
+                key,
+                value: old_value,
+            }),
+        }
+    }
+    changes.extend(
+        new.into_iter()
+            .map(|(key, value)| ConfigChange::Added { key, value }),
+    );
+
+    changes.sort_by(|a, b| change_key(a).cmp(change_key(b)));
+    Ok(changes)
+}
+
+/// The key a change applies to
+fn change_key(change: &ConfigChange) -> &str {
+    match change {
+        ConfigChange::Added { key, .. }
+        | ConfigChange::Removed { key, .. }
+        | ConfigChange::Changed { key, .. } => key,
+    }
+}
+
+/// Flattens a TOML document into dotted keys and inline values
+fn flatten_config(toml_str: &str) -> Result<BTreeMap<String, String>, ConfigError> {
+    let table: toml::Table = toml::from_str(toml_str)?;
+    let mut flat = BTreeMap::new();
+    flatten_table("", &table, &mut flat);
+    Ok(flat)
+}
+
+/// Adds every value of a table to `flat`, prefixing keys with `prefix`
+fn flatten_table(prefix: &str, table: &toml::Table, flat: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(inner) => flatten_table(&format!("{}.", path), inner, flat),
+            toml::Value::Array(entries) if entries.iter().all(|e| e.is_table()) => {
+                for (index, entry) in entries.iter().enumerate() {
+                    let mut entry = entry.as_table().cloned().unwrap_or_default();
+                    // Domain list entries are identified by their domain
+                    let id = match entry.remove("domain") {
+                        Some(toml::Value::String(domain)) => domain,
+                        Some(other) => {
+                            entry.insert("domain".to_string(), other);
+                            index.to_string()
+                        }
+                        None => index.to_string(),
+                    };
+                    let value = if entry.is_empty() {
+                        String::new()
+                    } else {
+                        inline(&toml::Value::Table(entry))
+                    };
+                    flat.insert(format!("{}[{}]", path, id), value);
+                }
+            }
+            _ => {
+                flat.insert(current_key(&path).to_string(), inline(value));
+            }
+        }
+    }
+}
+
+/// Formats a value on a single line
+fn inline(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Table(table) => {
+            let fields: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, inline(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        toml::Value::Array(values) => {
+            let values: Vec<String> = values.iter().map(inline).collect();
+            format!("[{}]", values.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+[crawler]
+max-depth = 3
+max-domain-requests = 500
+
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+
+[[blacklist]]
+domain = "ads.example.net"
+
+[[blacklist]]
+domain = "tracker.example.org"
+"#;
+
+    #[test]
+    fn test_identical_configs() {
+        assert!(diff_configs(BASE, BASE).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reordered_list_is_unchanged() {
+        let reordered = r#"
+[crawler]
+max-domain-requests = 500
+max-depth = 3
+
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+
+[[blacklist]]
+domain = "tracker.example.org"
+
+[[blacklist]]
+domain = "ads.example.net"
+"#;
+        assert!(diff_configs(BASE, reordered).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_limit_and_list_changes() {
+        let changed = r#"
+[crawler]
+max-depth = 5
+max-domain-requests = 500
+
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/", "https://example.com/blog/"]
+
+[[blacklist]]
+domain = "ads.example.net"
+
+[[stub]]
+domain = "social.example.com"
+"#;
+        let changes = diff_configs(BASE, changed).unwrap();
+        let lines: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "- blacklist[tracker.example.org]",
+                "~ crawler.max-depth: 3 -> 5",
+                "~ quality[example.com]: { seeds = [\"https://example.com/\"] } -> { seeds = [\"https://example.com/\", \"https://example.com/blog/\"] }",
+                "+ stub[social.example.com]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deprecated_keys_compare_under_current_name() {
+        let old = "[crawler]\nminimum-time-on-page = 1000\n";
+        let new = "[crawler]\nper-domain-delay-ms = 2000\n";
+        let lines: Vec<String> = diff_configs(old, new)
+            .unwrap()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(lines, vec!["~ crawler.per-domain-delay-ms: 1000 -> 2000"]);
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        assert!(matches!(
+            diff_configs(BASE, "not toml ["),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Config file includes
+//!
+//! A configuration file may pull in other TOML files with a top-level
+//! `include = ["blacklist-common.toml"]` key, so large shared domain lists can
+//! be maintained once and reused between crawl configs. Included paths are
+//! resolved relative to the including file and may themselves include files.
+//!
+//! Documents are merged before deserialization: lists (`[[quality]]`,
+//! `[[blacklist]]`, `[[stub]]`, ...) are concatenated in include order, tables
+//! are merged key by key, and scalar values in the including file take
+//! precedence over values from included files.
+
+use crate::ConfigError;
+use std::path::{Path, PathBuf};
+
+/// Top-level key listing files to include
+pub const INCLUDE_KEY: &str = "include";
+
+/// A configuration document with all includes resolved
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    /// The merged TOML document
+    pub document: toml::Value,
+
+    /// Every file that contributed to the document and its content, with the
+    /// main file first and includes in the order they were loaded
+    pub files: Vec<(PathBuf, String)>,
+}
+
+/// Reads a configuration file and resolves its includes
+///
+/// # Arguments
+///
+/// * `path` - Path to the main TOML configuration file
+///
+/// # Returns
+///
+/// * `Ok(ConfigSource)` - The merged document and the files it came from
+/// * `Err(ConfigError)` - A file could not be read or parsed, an `include`
+///   value is malformed, or the includes form a cycle
+pub fn read_config_source(path: &Path) -> Result<ConfigSource, ConfigError> {
+    // The main file's own I/O and parse errors keep their usual variants
+    let content = std::fs::read_to_string(path)?;
+    let document: toml::Value = toml::from_str(&content)?;
+
+    let mut files = vec![(path.to_path_buf(), content)];
+    let mut stack = vec![canonical(path)?];
+    let document = resolve_includes(path, document, &mut stack, &mut files)?;
+
+    Ok(ConfigSource { document, files })
+}
+
+/// Merges the files included by `document` underneath it
+fn resolve_includes(
+    path: &Path,
+    mut document: toml::Value,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<(PathBuf, String)>,
+) -> Result<toml::Value, ConfigError> {
+    let includes = take_includes(path, &mut document)?;
+    if includes.is_empty() {
+        return Ok(document);
+    }
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut merged = toml::Value::Table(toml::Table::new());
+
+    for include in includes {
+        let include_path = base_dir.join(&include);
+        let canonical_path = canonical(&include_path)?;
+
+        if let Some(start) = stack.iter().position(|p| *p == canonical_path) {
+            let cycle: Vec<String> = stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical_path))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(ConfigError::Include(format!(
+                "include cycle detected: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let content = std::fs::read_to_string(&include_path)
+            .map_err(|e| ConfigError::Include(format!("{}: {}", include_path.display(), e)))?;
+        let included: toml::Value = toml::from_str(&content)
+            .map_err(|e| ConfigError::Include(format!("{}: {}", include_path.display(), e)))?;
+        files.push((include_path.clone(), content));
+
+        stack.push(canonical_path);
+        let included = resolve_includes(&include_path, included, stack, files)?;
+        stack.pop();
+
+        merge_documents(&mut merged, included);
+    }
+
+    // The including file wins over everything it includes
+    merge_documents(&mut merged, document);
+    Ok(merged)
+}
+
+/// Removes and returns the `include` list from a document
+fn take_includes(path: &Path, document: &mut toml::Value) -> Result<Vec<String>, ConfigError> {
+    let invalid = || {
+        ConfigError::Include(format!(
+            "{}: '{}' must be an array of file paths",
+            path.display(),
+            INCLUDE_KEY
+        ))
+    };
+
+    let Some(value) = document
+        .as_table_mut()
+        .and_then(|table| table.remove(INCLUDE_KEY))
+    else {
+        return Ok(Vec::new());
+    };
+
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+        .collect()
+}
+
+/// Merges `overlay` into `base`
+///
+/// Arrays are concatenated, tables are merged recursively, and any other
+/// value in `overlay` replaces the one in `base`.
+fn merge_documents(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_documents(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(base), toml::Value::Array(overlay)) => base.extend(overlay),
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Canonicalizes a path for cycle detection, reporting missing files clearly
+fn canonical(path: &Path) -> Result<PathBuf, ConfigError> {
+    path.canonicalize()
+        .map_err(|e| ConfigError::Include(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_no_includes() {
+        let dir = TempDir::new().unwrap();
+        let main = write(&dir, "main.toml", "[crawler]\nmax-depth = 3\n");
+
+        let source = read_config_source(&main).unwrap();
+        assert_eq!(source.files.len(), 1);
+        assert_eq!(
+            source.document["crawler"]["max-depth"].as_integer(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_lists_are_concatenated() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "common.toml",
+            "[[blacklist]]\ndomain = \"spam.com\"\n\n[[blacklist]]\ndomain = \"ads.net\"\n",
+        );
+        let main = write(
+            &dir,
+            "main.toml",
+            "include = [\"common.toml\"]\n\n[[blacklist]]\ndomain = \"local.org\"\n",
+        );
+
+        let source = read_config_source(&main).unwrap();
+        let blacklist = source.document["blacklist"].as_array().unwrap();
+        let domains: Vec<&str> = blacklist
+            .iter()
+            .map(|entry| entry["domain"].as_str().unwrap())
+            .collect();
+        assert_eq!(domains, vec!["spam.com", "ads.net", "local.org"]);
+        assert_eq!(source.files.len(), 2);
+        assert!(source.document.get(INCLUDE_KEY).is_none());
+    }
+
+    #[test]
+    fn test_including_file_wins_for_scalars() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "base.toml",
+            "[crawler]\nmax-depth = 1\nmax-domain-requests = 50\n",
+        );
+        let main = write(
+            &dir,
+            "main.toml",
+            "include = [\"base.toml\"]\n\n[crawler]\nmax-depth = 5\n",
+        );
+
+        let source = read_config_source(&main).unwrap();
+        assert_eq!(
+            source.document["crawler"]["max-depth"].as_integer(),
+            Some(5)
+        );
+        assert_eq!(
+            source.document["crawler"]["max-domain-requests"].as_integer(),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn test_nested_includes_resolve_relative_to_includer() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("lists")).unwrap();
+        write(&dir, "lists/stub.toml", "[[stub]]\ndomain = \"big.com\"\n");
+        write(&dir, "lists/all.toml", "include = [\"stub.toml\"]\n");
+        let main = write(&dir, "main.toml", "include = [\"lists/all.toml\"]\n");
+
+        let source = read_config_source(&main).unwrap();
+        assert_eq!(source.document["stub"].as_array().unwrap().len(), 1);
+        assert_eq!(source.files.len(), 3);
+    }
+
+    #[test]
+    fn test_cycle_is_detected() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.toml", "include = [\"b.toml\"]\n");
+        write(&dir, "b.toml", "include = [\"a.toml\"]\n");
+        let main = dir.path().join("a.toml");
+
+        let err = read_config_source(&main).unwrap_err();
+        assert!(matches!(&err, ConfigError::Include(msg) if msg.contains("cycle")));
+    }
+
+    #[test]
+    fn test_missing_include_names_file() {
+        let dir = TempDir::new().unwrap();
+        let main = write(&dir, "main.toml", "include = [\"missing.toml\"]\n");
+
+        let err = read_config_source(&main).unwrap_err();
+        assert!(matches!(&err, ConfigError::Include(msg) if msg.contains("missing.toml")));
+    }
+
+    #[test]
+    fn test_include_must_be_array_of_strings() {
+        let dir = TempDir::new().unwrap();
+        let main = write(&dir, "main.toml", "include = \"common.toml\"\n");
+
+        assert!(matches!(
+            read_config_source(&main).unwrap_err(),
+            ConfigError::Include(_)
+        ));
+    }
+}
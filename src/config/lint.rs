@@ -0,0 +1,423 @@
+//! Full configuration checks for `--validate-config`
+//!
+//! Loading a configuration stops at the first error. The report built here
+//! instead collects every finding: load and validation errors, the list
+//! conflicts from [`find_conflicts`], and lints for settings that are valid
+//! but probably unintended. The report serializes to JSON so CI pipelines can
+//! gate crawl configs on it.
+
+use crate::config::conflicts::find_conflicts;
+use crate::config::diagnostics::{Diagnostic, Severity};
+use crate::config::overrides::ConfigOverrides;
+use crate::config::parser::{build_env_config, build_layered_config, CONFIG_JSON_ENV};
+use crate::config::profiles::unowned_quality_domains;
+use crate::config::types::Config;
+use crate::config::validation::validate_fields;
+use crate::ConfigError;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::path::Path;
+use url::{Host, Url};
+
+/// Delays below this between requests to one domain are flagged (ms)
+pub const MIN_RECOMMENDED_DELAY_MS: u64 = 1000;
+
+/// Suffixes under which unrelated sites are hosted, so a quality wildcard on
+/// one of them crawls far more than a single site
+const SHARED_SUFFIXES: &[&str] = &[
+    // Registry second-level domains
+    "co.uk",
+    "org.uk",
+    "ac.uk",
+    "gov.uk",
+    "com.au",
+    "net.au",
+    "org.au",
+    "co.nz",
+    "co.jp",
+    "ne.jp",
+    "or.jp",
+    "co.in",
+    "co.za",
+    "com.br",
+    "com.cn",
+    "com.mx",
+    // Multi-tenant hosting
+    "github.io",
+    "gitlab.io",
+    "blogspot.com",
+    "wordpress.com",
+    "tumblr.com",
+    "substack.com",
+    "herokuapp.com",
+    "netlify.app",
+    "vercel.app",
+    "pages.dev",
+    "web.app",
+    "firebaseapp.com",
+    "appspot.com",
+    "azurewebsites.net",
+    "cloudfront.net",
+    "amazonaws.com",
+];
+
+/// Top-level domains reserved for local or documentation use
+const RESERVED_TLDS: &[&str] = &[
+    "localhost",
+    "local",
+    "test",
+    "invalid",
+    "internal",
+    "example",
+];
+
+/// The outcome of checking a configuration file
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    /// Path of the checked configuration file
+    pub config: String,
+
+    /// Whether the configuration has no errors
+    pub valid: bool,
+
+    /// Number of error diagnostics
+    pub errors: usize,
+
+    /// Number of warning diagnostics
+    pub warnings: usize,
+
+    /// Number of note diagnostics
+    pub notes: usize,
+
+    /// All findings, most severe first
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Builds a report from a list of diagnostics
+    fn new(path: &Path, mut diagnostics: Vec<Diagnostic>) -> Self {
+        // Stable sort keeps the order within each severity
+        diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+        let count = |severity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let errors = count(Severity::Error);
+
+        Self {
+            config: path.display().to_string(),
+            valid: errors == 0,
+            errors,
+            warnings: count(Severity::Warning),
+            notes: count(Severity::Note),
+            diagnostics,
+        }
+    }
+}
+
+/// Loads and checks a configuration file, collecting every diagnostic
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML configuration file
+/// * `overrides` - Environment and command-line overrides to apply
+///
+/// # Returns
+///
+/// A report of all findings. A file that cannot be loaded at all yields a
+/// single error diagnostic.
+pub fn validate_config_file(path: &Path, overrides: &ConfigOverrides) -> ValidationReport {
+    check_config(path, build_layered_config(path, overrides))
+}
+
+/// Checks a configuration loaded from the environment, collecting every
+/// diagnostic
+///
+/// The environment counterpart of [`validate_config_file`]; the report names
+/// `SUMI_CONFIG_JSON` as the checked configuration.
+///
+/// # Arguments
+///
+/// * `overrides` - Environment and command-line overrides to apply
+///
+/// # Returns
+///
+/// A report of all findings
+pub fn validate_env_config(overrides: &ConfigOverrides) -> ValidationReport {
+    check_config(Path::new(CONFIG_JSON_ENV), build_env_config(overrides))
+}
+
+/// Builds the report of an assembled configuration
+fn check_config(path: &Path, built: Result<(Config, String), ConfigError>) -> ValidationReport {
+    let config = match built {
+        Ok((config, _)) => config,
+        Err(e) => return ValidationReport::new(path, vec![error_diagnostic(&e)]),
+    };
+
+    let mut diagnostics = Vec::new();
+    if let Err(e) = validate_fields(&config) {
+        diagnostics.push(error_diagnostic(&e));
+    }
+    diagnostics.extend(lint_config(&config));
+
+    ValidationReport::new(path, diagnostics)
+}
+
+/// Runs the conflict checks and lints on a loaded configuration
+///
+/// # Arguments
+///
+/// * `config` - The configuration to check
+///
+/// # Returns
+///
+/// Conflict diagnostics followed by lint diagnostics
+pub fn lint_config(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = find_conflicts(config);
+    diagnostics.extend(lint_seeds(config));
+    diagnostics.extend(lint_wildcards(config));
+    diagnostics.extend(lint_delays(config));
+    diagnostics
+}
+
+/// Converts a load or validation error into a diagnostic
+fn error_diagnostic(error: &ConfigError) -> Diagnostic {
+    let code = match error {
+        ConfigError::Io(_) => "io-error",
+        ConfigError::Parse(_) => "parse-error",
+        ConfigError::Validation(_) => "invalid-value",
+        ConfigError::InvalidUrl(_) => "invalid-url",
+        ConfigError::InvalidPattern(_) => "invalid-pattern",
+        ConfigError::Include(_) => "include-error",
+        ConfigError::Blocklist(_) => "blocklist-error",
+        ConfigError::SeedsFile(_) => "seeds-file-error",
+        ConfigError::Serialize(_) => "serialize-error",
+    };
+    Diagnostic::new(Severity::Error, code, error.to_string())
+}
+
+/// Flags seeds that cannot be reached from the public internet
+fn lint_seeds(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for entry in &config.quality {
+        for seed in &entry.seeds {
+            let Ok(url) = Url::parse(seed) else {
+                continue;
+            };
+            if let Some(reason) = unreachable_reason(&url) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    "seed-unreachable",
+                    format!(
+                        "Seed URL '{}' for quality domain '{}' {} and will not be reachable from the public internet",
+                        seed, entry.domain, reason
+                    ),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Explains why a URL's host is not publicly reachable, if it isn't
+pub(crate) fn unreachable_reason(url: &Url) -> Option<String> {
+    match url.host()? {
+        Host::Ipv4(ip) => private_ip(IpAddr::V4(ip)),
+        Host::Ipv6(ip) => private_ip(IpAddr::V6(ip)),
+        Host::Domain(domain) => {
+            let domain = domain.to_lowercase();
+            let tld = domain.rsplit('.').next().unwrap_or(&domain);
+            RESERVED_TLDS
+                .contains(&tld)
+                .then(|| format!("uses the reserved top-level domain '.{}'", tld))
+        }
+    }
+}
+
+/// Describes an IP address that is not publicly routable
+fn private_ip(ip: IpAddr) -> Option<String> {
+    let private = match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_documentation()
+        }
+        IpAddr::V6(ip) => ip.is_loopback() || ip.is_unspecified(),
+    };
+    private.then(|| format!("points at the non-public address {}", ip))
+}
+
+/// Flags wildcard patterns that probably match more than intended
+fn lint_wildcards(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let lists = config
+        .quality
+        .iter()
+        .map(|e| ("[[quality]]", &e.domain))
+        .chain(
+            config
+                .blacklist
+                .iter()
+                .map(|e| ("[[blacklist]]", &e.domain)),
+        )
+        .chain(config.stub.iter().map(|e| ("[[stub]]", &e.domain)));
+
+    for (list, pattern) in lists {
+        let Some(base) = pattern.strip_prefix("*.") else {
+            continue;
+        };
+        let base = base.to_lowercase();
+
+        if list == "[[quality]]" && SHARED_SUFFIXES.contains(&base.as_str()) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "suspicious-wildcard",
+                format!(
+                    "'{}' in {} matches every site registered or hosted under '{}', not a single site",
+                    pattern, list, base
+                ),
+            ));
+        } else if let Some(site) = base.strip_prefix("www.") {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "suspicious-wildcard",
+                format!(
+                    "'{}' in {} only matches hosts under '{}'; did you mean '*.{}'?",
+                    pattern, list, base, site
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Flags politeness settings that are likely to overload sites
+fn lint_delays(config: &Config) -> Vec<Diagnostic> {
+    let delay = config.crawler.per_domain_delay_ms;
+    if delay >= MIN_RECOMMENDED_DELAY_MS {
+        return Vec::new();
+    }
+    // Short delays are the point of an aggressive profile on owned sites
+    if config
+        .profile
+        .name
+        .is_some_and(|profile| profile.is_aggressive())
+        && unowned_quality_domains(config).is_empty()
+    {
+        return Vec::new();
+    }
+
+    vec![Diagnostic::new(
+        Severity::Warning,
+        "tiny-delay",
+        format!(
+            "per-domain-delay-ms is {}ms; delays under {}ms between requests to the same domain can overload small sites",
+            delay, MIN_RECOMMENDED_DELAY_MS
+        ),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_config(extra: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", test_config_toml(extra)).unwrap();
+        file
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<&'static str> {
+        report.diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn test_clean_config() {
+        let file = write_config(
+            "[[quality]]\ndomain = \"example.com\"\nseeds = [\"https://example.com/\"]\n",
+        );
+        let report = validate_config_file(file.path(), &ConfigOverrides::new());
+        assert!(report.valid);
+        assert!(report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_unparseable_config() {
+        let file = write_config("this is not toml [");
+        let report = validate_config_file(file.path(), &ConfigOverrides::new());
+        assert!(!report.valid);
+        assert_eq!(codes(&report), vec!["parse-error"]);
+    }
+
+    #[test]
+    fn test_collects_all_findings() {
+        let file = write_config(
+            r#"
+[[quality]]
+domain = "*.github.io"
+seeds = ["https://someone.github.io/"]
+
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+
+[[blacklist]]
+domain = "example.com"
+
+[[stub]]
+domain = "*.www.example.org"
+"#,
+        );
+        let mut overrides = ConfigOverrides::new();
+        overrides.push_cli("crawler.per-domain-delay-ms", "200");
+
+        let report = validate_config_file(file.path(), &overrides);
+        assert!(!report.valid);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.warnings, 3);
+        assert_eq!(
+            codes(&report),
+            vec![
+                "conflicting-entry",
+                "suspicious-wildcard",
+                "suspicious-wildcard",
+                "tiny-delay"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_seeds() {
+        let reason = |url: &str| unreachable_reason(&Url::parse(url).unwrap());
+
+        assert!(reason("https://example.com/").is_none());
+        assert!(reason("https://8.8.8.8/").is_none());
+        assert!(reason("https://127.0.0.1:8080/").is_some());
+        assert!(reason("https://192.168.1.10/").is_some());
+        assert!(reason("https://[::1]/").is_some());
+        assert!(reason("https://wiki.internal/").is_some());
+        assert!(reason("https://site.test/").is_some());
+    }
+
+    #[test]
+    fn test_report_serializes_to_json() {
+        let file = write_config("[[quality]]\ndomain = \"example.com\"\nseeds = []\n");
+        let report = validate_config_file(file.path(), &ConfigOverrides::new());
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["diagnostics"][0]["severity"], "error");
+        assert_eq!(json["diagnostics"][0]["code"], "invalid-value");
+    }
+}
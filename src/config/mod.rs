@@ -12,12 +12,94 @@
 //! println!("Crawler will use max depth: {}", config.crawler.max_depth);
 //! ```
 
+mod blocklist;
+mod conflicts;
+mod deprecated;
+mod diagnostics;
+mod diff;
+mod include;
+mod lint;
+mod overrides;
 mod parser;
+mod profiles;
+mod seeds;
+mod template;
 mod types;
 mod validation;
 
 // Re-export types
-pub use types::{Config, CrawlerConfig, DomainEntry, OutputConfig, QualityEntry, UserAgentConfig};
+pub use types::{
+    BlocklistFile, BlocklistFormat, Config, CrawlProfile, CrawlWindow, CrawlerConfig,
+    DatabaseConfig, DomainEntry, EmbeddedLinkSource, OutputConfig, PolicyConfig, PriorityPolicy,
+    ProfileConfig, PromotionConfig, QualityEntry, RetentionConfig, RetryConfig, ScheduleConfig,
+    SynchronousMode, TimeoutEntry, UserAgentConfig, DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS,
+    DEFAULT_CIRCUIT_BREAKER_THRESHOLD, DEFAULT_CONTENT_TYPES,
+    DEFAULT_MAX_DISCOVERED_DOMAIN_REQUESTS, DEFAULT_MAX_PAGE_RETRIES, DEFAULT_MAX_TITLE_LENGTH,
+    DEFAULT_PROGRESS_SNAPSHOT_MINUTES, DEFAULT_REQUEST_TIMEOUT_SECS,
+};
 
 // Re-export parser functions
-pub use parser::{compute_config_hash, load_config, load_config_with_hash};
+pub use parser::{
+    compute_config_hash, load_config, load_config_with_hash, load_env_config, load_layered_config,
+    serialize_config, CONFIG_JSON_ENV,
+};
+
+// Re-export deprecated key handling
+pub use deprecated::{current_key, migrate_deprecated_keys, DEPRECATED_KEYS};
+
+// Re-export diagnostics and conflict detection
+pub use conflicts::find_conflicts;
+pub use diagnostics::{Diagnostic, Severity};
+pub use lint::{
+    lint_config, validate_config_file, validate_env_config, ValidationReport,
+    MIN_RECOMMENDED_DELAY_MS,
+};
+
+// Re-export configuration diffs
+pub use diff::{diff_configs, ConfigChange};
+
+// Re-export blocklist import
+pub use blocklist::{import_blocklists, parse_blocklist};
+
+// Re-export crawl profiles
+pub use profiles::{apply_profile, profile_settings, unowned_quality_domains};
+
+// Re-export seed file loading
+pub use seeds::{load_seed_files, parse_seed_list};
+
+// Re-export the example configuration
+pub use template::{write_example_config, EXAMPLE_CONFIG};
+
+// Re-export include handling
+pub use include::{read_config_source, ConfigSource, INCLUDE_KEY};
+
+// Re-export override types
+pub use overrides::{ConfigOverride, ConfigOverrides, OverrideSource};
+
+/// Builds a minimal valid configuration followed by `sections`
+///
+/// Tests append only the sections they exercise instead of repeating the
+/// required `[crawler]`, `[user-agent]` and `[output]` tables.
+#[cfg(test)]
+pub(crate) fn test_config_toml(sections: &str) -> String {
+    format!(
+        r#"
+[crawler]
+max-depth = 3
+max-concurrent-pages-open = 10
+per-domain-delay-ms = 1000
+max-domain-requests = 500
+
+[user-agent]
+crawler-name = "TestCrawler"
+crawler-version = "1.0"
+contact-url = "https://example.com/about"
+contact-email = "admin@example.com"
+
+[output]
+database-path = "./test.db"
+summary-path = "./summary.md"
+{}"#,
+        sections
+    )
+}
@@ -0,0 +1,294 @@
+//! Environment-variable and command-line overrides for configuration values
+//!
+//! Configuration is assembled in layers, each taking precedence over the one
+//! before it:
+//!
+//! 1. Built-in defaults (fields with `#[serde(default)]`)
+//! 2. The TOML configuration file
+//! 3. Environment variables such as `SUMI_CRAWLER__MAX_DEPTH=4`
+//! 4. Command-line flags such as `--max-depth 4` or `--set crawler.max-depth=4`
+//!
+//! Overrides address a value by its dotted TOML key (`crawler.max-depth`).
+//! Environment variable names map onto keys by stripping the `SUMI_` prefix,
+//! separating sections with `__`, lowercasing, and replacing `_` with `-`.
+//! Deprecated keys address the setting that replaced them.
+
+use crate::config::deprecated::current_key;
+use crate::ConfigError;
+
+/// Prefix for configuration environment variables
+pub const ENV_PREFIX: &str = "SUMI_";
+
+/// Separator between TOML table names in environment variable names
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Where an override came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverrideSource {
+    /// An environment variable (with its name)
+    Env(String),
+
+    /// A command-line flag
+    Cli,
+}
+
+/// A single configuration value override
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigOverride {
+    /// Dotted TOML key, e.g. "crawler.max-depth"
+    pub key: String,
+
+    /// Raw value as given by the user
+    pub value: String,
+
+    /// Where the override came from
+    pub source: OverrideSource,
+}
+
+/// An ordered set of configuration overrides
+///
+/// Overrides are applied in insertion order, so later entries win. Build the
+/// set with environment overrides first and command-line overrides last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    entries: Vec<ConfigOverride>,
+}
+
+impl ConfigOverrides {
+    /// Creates an empty set of overrides
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects overrides from the process environment
+    pub fn from_env() -> Self {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Collects overrides from `SUMI_SECTION__KEY=value` style variables
+    ///
+    /// Variables without the `SUMI_` prefix or without a `__` separator are
+    /// ignored. Variables are sorted by name so the result is deterministic.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - (name, value) pairs, e.g. from `std::env::vars()`
+    pub fn from_vars<I>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX) && name.contains(ENV_SEPARATOR))
+            .collect();
+        vars.sort();
+
+        let entries = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key = env_var_to_key(&name)?;
+                Some(ConfigOverride {
+                    key,
+                    value,
+                    source: OverrideSource::Env(name),
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Adds a command-line override
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Dotted TOML key, e.g. "crawler.max-depth"
+    /// * `value` - Raw value
+    pub fn push_cli(&mut self, key: &str, value: &str) {
+        self.entries.push(ConfigOverride {
+            key: key.to_string(),
+            value: value.to_string(),
+            source: OverrideSource::Cli,
+        });
+    }
+
+    /// Returns the overrides in the order they are applied
+    pub fn entries(&self) -> &[ConfigOverride] {
+        &self.entries
+    }
+
+    /// Returns true if there are no overrides
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Applies all overrides to a parsed TOML document
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The parsed configuration file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - All overrides applied
+    /// * `Err(ConfigError)` - An override addresses a key that cannot be set
+    pub fn apply(&self, document: &mut toml::Value) -> Result<(), ConfigError> {
+        for entry in &self.entries {
+            apply_override(document, entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts `SUMI_USER_AGENT__CRAWLER_NAME` to `user-agent.crawler-name`
+fn env_var_to_key(name: &str) -> Option<String> {
+    let rest = name.strip_prefix(ENV_PREFIX)?;
+    let parts: Vec<String> = rest
+        .split(ENV_SEPARATOR)
+        .map(|part| part.to_lowercase().replace('_', "-"))
+        .collect();
+
+    if parts.iter().any(|part| part.is_empty()) {
+        return None;
+    }
+    Some(parts.join("."))
+}
+
+/// Sets a single value in the TOML document
+fn apply_override(document: &mut toml::Value, entry: &ConfigOverride) -> Result<(), ConfigError> {
+    let describe = || match &entry.source {
+        OverrideSource::Env(name) => format!("environment variable {}", name),
+        OverrideSource::Cli => "command-line override".to_string(),
+    };
+
+    let mut parts: Vec<&str> = current_key(&entry.key).split('.').collect();
+    let field = parts.pop().filter(|f| !f.is_empty()).ok_or_else(|| {
+        ConfigError::Validation(format!("Invalid key '{}' in {}", entry.key, describe()))
+    })?;
+
+    let mut table = document
+        .as_table_mut()
+        .ok_or_else(|| ConfigError::Validation("Configuration root is not a table".to_string()))?;
+    for part in parts {
+        table = table
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| {
+                ConfigError::Validation(format!(
+                    "Cannot override '{}' from {}: '{}' is not a table",
+                    entry.key,
+                    describe(),
+                    part
+                ))
+            })?;
+    }
+
+    let value = parse_override_value(&entry.value, table.get(field));
+    table.insert(field.to_string(), value);
+    Ok(())
+}
+
+/// Interprets a raw override as a TOML value
+///
+/// If the key currently holds a string the raw text is kept as a string, so
+/// values like `crawler-version = "1.0"` are not turned into numbers.
+/// Otherwise the text is parsed as a TOML value (integer, boolean, array, ...),
+/// falling back to a plain string.
+fn parse_override_value(raw: &str, current: Option<&toml::Value>) -> toml::Value {
+    if matches!(current, Some(toml::Value::String(_))) {
+        return toml::Value::String(raw.to_string());
+    }
+
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn document() -> toml::Value {
+        toml::from_str(
+            r#"
+[crawler]
+max-depth = 3
+
+[user-agent]
+crawler-version = "1.0"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_env_var_to_key() {
+        assert_eq!(
+            env_var_to_key("SUMI_CRAWLER__MAX_DEPTH").as_deref(),
+            Some("crawler.max-depth")
+        );
+        assert_eq!(
+            env_var_to_key("SUMI_USER_AGENT__CRAWLER_NAME").as_deref(),
+            Some("user-agent.crawler-name")
+        );
+        assert_eq!(env_var_to_key("SUMI_CRAWLER__"), None);
+    }
+
+    #[test]
+    fn test_from_vars_filters_unrelated() {
+        let overrides = ConfigOverrides::from_vars(vars(&[
+            ("PATH", "/usr/bin"),
+            ("SUMI_HOME", "/tmp"),
+            ("SUMI_CRAWLER__MAX_DEPTH", "4"),
+        ]));
+        assert_eq!(overrides.entries().len(), 1);
+        assert_eq!(overrides.entries()[0].key, "crawler.max-depth");
+    }
+
+    #[test]
+    fn test_apply_typed_values() {
+        let mut doc = document();
+        let mut overrides = ConfigOverrides::from_vars(vars(&[
+            ("SUMI_CRAWLER__MAX_DEPTH", "4"),
+            ("SUMI_CRAWLER__HONOR_VISIT_TIME", "true"),
+            ("SUMI_USER_AGENT__CRAWLER_VERSION", "2.0"),
+        ]));
+        overrides.push_cli("output.database-path", "/data/crawl.db");
+        overrides.apply(&mut doc).unwrap();
+
+        assert_eq!(doc["crawler"]["max-depth"].as_integer(), Some(4));
+        assert_eq!(doc["crawler"]["honor-visit-time"].as_bool(), Some(true));
+        assert_eq!(doc["user-agent"]["crawler-version"].as_str(), Some("2.0"));
+        assert_eq!(
+            doc["output"]["database-path"].as_str(),
+            Some("/data/crawl.db")
+        );
+    }
+
+    #[test]
+    fn test_cli_overrides_take_precedence() {
+        let mut doc = document();
+        let mut overrides = ConfigOverrides::from_vars(vars(&[("SUMI_CRAWLER__MAX_DEPTH", "4")]));
+        overrides.push_cli("crawler.max-depth", "7");
+        overrides.apply(&mut doc).unwrap();
+
+        assert_eq!(doc["crawler"]["max-depth"].as_integer(), Some(7));
+    }
+
+    #[test]
+    fn test_cannot_override_inside_non_table() {
+        let mut doc = document();
+        let mut overrides = ConfigOverrides::new();
+        overrides.push_cli("crawler.max-depth.inner", "1");
+        assert!(overrides.apply(&mut doc).is_err());
+    }
+}
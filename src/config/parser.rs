@@ -1,9 +1,18 @@
+use crate::config::blocklist::import_blocklists;
+use crate::config::deprecated::migrate_deprecated_keys;
+use crate::config::include::read_config_source;
+use crate::config::overrides::ConfigOverrides;
+use crate::config::profiles::apply_profile;
+use crate::config::seeds::load_seed_files;
 use crate::config::types::Config;
 use crate::config::validation::validate;
 use crate::ConfigError;
 use sha2::{Digest, Sha256};
 use std::path::Path;
 
+/// Environment variable holding a whole configuration as JSON
+pub const CONFIG_JSON_ENV: &str = "SUMI_CONFIG_JSON";
+
 /// Loads and parses a configuration file from the given path
 ///
 /// # Arguments
@@ -25,15 +34,7 @@ use std::path::Path;
 /// println!("Max depth: {}", config.crawler.max_depth);
 /// ```
 pub fn load_config(path: &Path) -> Result<Config, ConfigError> {
-    // Read the configuration file
-    let content = std::fs::read_to_string(path)?;
-
-    // Parse TOML
-    let config: Config = toml::from_str(&content)?;
-
-    // Validate the configuration
-    validate(&config)?;
-
+    let (config, _) = load_layered_config(path, &ConfigOverrides::new())?;
     Ok(config)
 }
 
@@ -57,6 +58,25 @@ pub fn compute_config_hash(path: &Path) -> Result<String, ConfigError> {
     Ok(hex::encode(result))
 }
 
+/// Serializes a configuration as TOML
+///
+/// Every setting is written out, including defaults, so the result shows
+/// exactly what a run used. Blocklist imports and seed files have already
+/// been expanded into the blacklist and seed lists. The output loads back to
+/// an equivalent configuration.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to serialize
+///
+/// # Returns
+///
+/// * `Ok(String)` - The configuration as a TOML document
+/// * `Err(ConfigError)` - The configuration could not be serialized
+pub fn serialize_config(config: &Config) -> Result<String, ConfigError> {
+    Ok(toml::to_string(config)?)
+}
+
 /// Loads a configuration and returns both the config and its hash
 ///
 /// # Arguments
@@ -73,9 +93,159 @@ pub fn load_config_with_hash(path: &Path) -> Result<(Config, String), ConfigErro
     Ok((config, hash))
 }
 
+/// Loads a configuration file and applies environment and command-line overrides
+///
+/// Files listed under `include` are merged in first. The overrides are then
+/// applied to the merged TOML before it is deserialized and validated, so
+/// overridden and included values go through the same validation as values
+/// from the main file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the TOML configuration file
+/// * `overrides` - Overrides to apply, in precedence order
+///
+/// # Returns
+///
+/// * `Ok((Config, String))` - The effective configuration and its hash
+/// * `Err(ConfigError)` - Failed to load, override, parse, or validate
+pub fn load_layered_config(
+    path: &Path,
+    overrides: &ConfigOverrides,
+) -> Result<(Config, String), ConfigError> {
+    let (config, hash) = build_layered_config(path, overrides)?;
+    validate(&config)?;
+    Ok((config, hash))
+}
+
+/// Assembles the effective configuration without validating it
+///
+/// Shared by [`load_layered_config`] and the `--validate-config` report, which
+/// validates separately so it can collect every finding instead of stopping
+/// at the first error.
+pub(crate) fn build_layered_config(
+    path: &Path,
+    overrides: &ConfigOverrides,
+) -> Result<(Config, String), ConfigError> {
+    let source = read_config_source(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let contents: Vec<&str> = source
+        .files
+        .iter()
+        .map(|(_, content)| content.as_str())
+        .collect();
+    finish_config(source.document, &contents, base_dir, overrides)
+}
+
+/// Loads a configuration from the environment alone, without a file
+///
+/// The whole configuration can be given as JSON in `SUMI_CONFIG_JSON`, with
+/// the same structure and keys as the TOML file. `SUMI_SECTION__KEY`
+/// variables and command-line overrides are applied on top, so a
+/// configuration can also be built from individual variables. Relative paths
+/// (blocklist and seed files) are resolved against the working directory.
+///
+/// # Arguments
+///
+/// * `overrides` - Overrides to apply, in precedence order
+///
+/// # Returns
+///
+/// * `Ok((Config, String))` - The effective configuration and its hash
+/// * `Err(ConfigError)` - Failed to parse, override, or validate
+pub fn load_env_config(overrides: &ConfigOverrides) -> Result<(Config, String), ConfigError> {
+    let (config, hash) = build_env_config(overrides)?;
+    validate(&config)?;
+    Ok((config, hash))
+}
+
+/// Assembles the configuration of [`load_env_config`] without validating it
+pub(crate) fn build_env_config(
+    overrides: &ConfigOverrides,
+) -> Result<(Config, String), ConfigError> {
+    let json = std::env::var(CONFIG_JSON_ENV).ok();
+    build_json_config(json.as_deref(), overrides)
+}
+
+/// Assembles a configuration from an optional JSON document and overrides
+fn build_json_config(
+    json: Option<&str>,
+    overrides: &ConfigOverrides,
+) -> Result<(Config, String), ConfigError> {
+    let document = match json {
+        Some(json) => {
+            let value: serde_json::Value = serde_json::from_str(json).map_err(|e| {
+                ConfigError::Validation(format!("{} is not valid JSON: {}", CONFIG_JSON_ENV, e))
+            })?;
+            if !value.is_object() {
+                return Err(ConfigError::Validation(format!(
+                    "{} must be a JSON object",
+                    CONFIG_JSON_ENV
+                )));
+            }
+            toml::Value::try_from(value).map_err(|e| {
+                ConfigError::Validation(format!(
+                    "{} cannot be read as a configuration: {}",
+                    CONFIG_JSON_ENV, e
+                ))
+            })?
+        }
+        None => toml::Value::Table(toml::Table::new()),
+    };
+
+    finish_config(
+        document,
+        &[json.unwrap_or_default()],
+        Path::new("."),
+        overrides,
+    )
+}
+
+/// Applies the overrides to a configuration document, deserializes it and
+/// expands the external lists it names
+///
+/// # Arguments
+///
+/// * `document` - The parsed configuration
+/// * `contents` - The source texts the document was read from, for the hash
+/// * `base_dir` - Directory relative list paths are resolved against
+/// * `overrides` - Overrides to apply, in precedence order
+fn finish_config(
+    mut document: toml::Value,
+    contents: &[&str],
+    base_dir: &Path,
+    overrides: &ConfigOverrides,
+) -> Result<(Config, String), ConfigError> {
+    migrate_deprecated_keys(&mut document);
+    overrides.apply(&mut document)?;
+    apply_profile(&mut document)?;
+    let mut config: Config = document.try_into()?;
+
+    // Expand external blocklists and seed lists before validation
+    import_blocklists(&mut config, base_dir)?;
+    load_seed_files(&mut config, base_dir)?;
+
+    // The hash covers included files and overrides so runs with different
+    // effective settings are distinguishable
+    let mut hasher = Sha256::new();
+    for (i, content) in contents.iter().enumerate() {
+        if i > 0 {
+            hasher.update(b"\n");
+        }
+        hasher.update(content.as_bytes());
+    }
+    for entry in overrides.entries() {
+        hasher.update(format!("\n{}={}", entry.key, entry.value).as_bytes());
+    }
+    let hash = hex::encode(hasher.finalize());
+
+    Ok((config, hash))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config_toml;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -88,29 +258,15 @@ mod tests {
 
     #[test]
     fn test_load_valid_config() {
-        let config_content = r#"
-[crawler]
-max-depth = 3
-max-concurrent-pages-open = 10
-minimum-time-on-page = 1000
-max-domain-requests = 500
-
-[user-agent]
-crawler-name = "TestCrawler"
-crawler-version = "1.0"
-contact-url = "https://example.com/about"
-contact-email = "admin@example.com"
-
-[output]
-database-path = "./test.db"
-summary-path = "./summary.md"
-
+        let config_content = test_config_toml(
+            r#"
 [[quality]]
 domain = "example.com"
 seeds = ["https://example.com/"]
-"#;
+"#,
+        );
 
-        let file = create_temp_config(config_content);
+        let file = create_temp_config(&config_content);
         let config = load_config(file.path()).unwrap();
 
         assert_eq!(config.crawler.max_depth, 3);
@@ -135,25 +291,12 @@ seeds = ["https://example.com/"]
 
     #[test]
     fn test_load_config_with_validation_error() {
-        let config_content = r#"
-[crawler]
-max-depth = 3
-max-concurrent-pages-open = 0
-minimum-time-on-page = 1000
-max-domain-requests = 500
-
-[user-agent]
-crawler-name = "TestCrawler"
-crawler-version = "1.0"
-contact-url = "https://example.com/about"
-contact-email = "admin@example.com"
-
-[output]
-database-path = "./test.db"
-summary-path = "./summary.md"
-"#;
+        let config_content = test_config_toml("").replace(
+            "max-concurrent-pages-open = 10",
+            "max-concurrent-pages-open = 0",
+        );
 
-        let file = create_temp_config(config_content);
+        let file = create_temp_config(&config_content);
         let result = load_config(file.path());
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ConfigError::Validation(_)));
@@ -182,4 +325,163 @@ summary-path = "./summary.md"
 
         assert_ne!(hash1, hash2);
     }
+
+    #[test]
+    fn test_load_layered_config() {
+        let file = create_temp_config(&test_config_toml(""));
+
+        let (_, plain_hash) = load_layered_config(file.path(), &ConfigOverrides::new()).unwrap();
+        assert_eq!(plain_hash, compute_config_hash(file.path()).unwrap());
+
+        let mut overrides = ConfigOverrides::new();
+        overrides.push_cli("crawler.max-depth", "5");
+        let (config, hash) = load_layered_config(file.path(), &overrides).unwrap();
+        assert_eq!(config.crawler.max_depth, 5);
+        assert_ne!(hash, plain_hash);
+
+        // Overridden values are validated like file values
+        let mut overrides = ConfigOverrides::new();
+        overrides.push_cli("crawler.max-concurrent-pages-open", "0");
+        let result = load_layered_config(file.path(), &overrides);
+        assert!(matches!(result.unwrap_err(), ConfigError::Validation(_)));
+    }
+
+    #[test]
+    fn test_load_config_with_deprecated_key() {
+        let config_content = test_config_toml("")
+            .replace("per-domain-delay-ms = 1000", "minimum-time-on-page = 1500");
+        let file = create_temp_config(&config_content);
+        let config = load_config(file.path()).unwrap();
+        assert_eq!(config.crawler.per_domain_delay_ms, 1500);
+
+        // Overrides of the old key set the new one
+        let file = create_temp_config(
+            &config_content.replace("minimum-time-on-page", "per-domain-delay-ms"),
+        );
+        let mut overrides = ConfigOverrides::new();
+        overrides.push_cli("crawler.minimum-time-on-page", "2500");
+        let (config, _) = load_layered_config(file.path(), &overrides).unwrap();
+        assert_eq!(config.crawler.per_domain_delay_ms, 2500);
+    }
+
+    #[test]
+    fn test_load_config_with_include() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("blacklist-common.toml"),
+            "[[blacklist]]\ndomain = \"spam.com\"\n",
+        )
+        .unwrap();
+        let main = dir.path().join("config.toml");
+        std::fs::write(
+            &main,
+            format!(
+                "include = [\"blacklist-common.toml\"]\n{}",
+                test_config_toml("\n[[blacklist]]\ndomain = \"ads.net\"\n")
+            ),
+        )
+        .unwrap();
+
+        let (config, hash) = load_layered_config(&main, &ConfigOverrides::new()).unwrap();
+        assert_eq!(config.blacklist.len(), 2);
+        assert_eq!(config.blacklist[0].domain, "spam.com");
+
+        // Changing an included file changes the hash
+        std::fs::write(
+            dir.path().join("blacklist-common.toml"),
+            "[[blacklist]]\ndomain = \"other.com\"\n",
+        )
+        .unwrap();
+        let (_, changed_hash) = load_layered_config(&main, &ConfigOverrides::new()).unwrap();
+        assert_ne!(hash, changed_hash);
+    }
+
+    #[test]
+    fn test_included_entries_are_validated() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("bad.toml"),
+            "[[stub]]\ndomain = \"not a domain!\"\n",
+        )
+        .unwrap();
+        let main = dir.path().join("config.toml");
+        std::fs::write(
+            &main,
+            format!("include = [\"bad.toml\"]\n{}", test_config_toml("")),
+        )
+        .unwrap();
+
+        assert!(load_config(&main).is_err());
+    }
+
+    #[test]
+    fn test_serialize_config_round_trip() {
+        let file = create_temp_config(&test_config_toml(
+            r#"
+[[quality]]
+domain = "*.example.com"
+seeds = ["https://example.com/"]
+
+[[stub]]
+domain = "github.com"
+
+[[schedule.window]]
+start = "01:00"
+end = "06:00"
+"#,
+        ));
+        let config = load_config(file.path()).unwrap();
+        let serialized = serialize_config(&config).unwrap();
+
+        // Defaults are written out explicitly
+        assert!(serialized.contains("honor-visit-time = false"));
+        assert!(serialized.contains("seeds-from-sitemap = false"));
+
+        let reloaded = create_temp_config(&serialized);
+        let config = load_config(reloaded.path()).unwrap();
+        assert_eq!(serialize_config(&config).unwrap(), serialized);
+    }
+
+    #[test]
+    fn test_json_config() {
+        let json = r#"{
+            "crawler": {
+                "max-depth": 3,
+                "max-concurrent-pages-open": 10,
+                "per-domain-delay-ms": 1000,
+                "max-domain-requests": 500
+            },
+            "user-agent": {
+                "crawler-name": "TestCrawler",
+                "crawler-version": "1.0",
+                "contact-url": "https://example.com/about",
+                "contact-email": "admin@example.com"
+            },
+            "output": {"database-path": "/data/crawl.db", "summary-path": "/data/summary.md"},
+            "quality": [{"domain": "example.com", "seeds": ["https://example.com/"]}]
+        }"#;
+        let overrides = ConfigOverrides::from_vars(vec![(
+            "SUMI_CRAWLER__MAX_DEPTH".to_string(),
+            "5".to_string(),
+        )]);
+
+        let (config, hash) = build_json_config(Some(json), &overrides).unwrap();
+        validate(&config).unwrap();
+        assert_eq!(config.crawler.max_depth, 5);
+        assert_eq!(config.output.database_path, "/data/crawl.db");
+        assert_eq!(config.quality[0].seeds, vec!["https://example.com/"]);
+        let (_, unchanged) = build_json_config(Some(json), &overrides).unwrap();
+        assert_eq!(hash, unchanged);
+
+        assert!(matches!(
+            build_json_config(Some("{\"crawler\": "), &overrides),
+            Err(ConfigError::Validation(_))
+        ));
+        assert!(matches!(
+            build_json_config(Some("[1, 2]"), &overrides),
+            Err(ConfigError::Validation(_))
+        ));
+        // Without JSON the variables alone must form a configuration
+        assert!(build_json_config(None, &overrides).is_err());
+    }
 }
@@ -0,0 +1,245 @@
+//! Crawl profiles
+//!
+//! A profile presets the concurrency, delays, retries and request budgets of
+//! a crawl, so operators pick how hard to press on sites instead of tuning
+//! each setting. `[profile] name` (or `--profile`) selects one; its values
+//! are written into the configuration document before it is deserialized,
+//! only where the configuration does not set the value itself.
+//!
+//! `aggressive-internal` would overload sites run by others, so validation
+//! refuses it for quality domains that are neither internal hosts nor listed
+//! in `owned-domains`, unless `allow-unowned` is set.
+
+use crate::config::lint::unreachable_reason;
+use crate::config::types::{Config, CrawlProfile};
+use crate::ConfigError;
+use serde::Deserialize;
+use url::Url;
+
+/// The settings a profile presets, as dotted keys and values
+///
+/// # Arguments
+///
+/// * `profile` - The profile
+///
+/// # Returns
+///
+/// (key, value) pairs, e.g. ("crawler.per-domain-delay-ms", 5000)
+pub fn profile_settings(profile: CrawlProfile) -> &'static [(&'static str, i64)] {
+    match profile {
+        CrawlProfile::Gentle => &[
+            ("crawler.max-concurrent-pages-open", 2),
+            ("crawler.per-domain-delay-ms", 5000),
+            ("crawler.per-request-jitter-ms", 2000),
+            ("crawler.max-domain-requests", 200),
+            ("crawler.max-discovered-domain-requests", 5),
+            ("retry.max-retries", 1),
+            ("retry.base-delay-ms", 10_000),
+        ],
+        CrawlProfile::Standard => &[
+            ("crawler.max-concurrent-pages-open", 10),
+            ("crawler.per-domain-delay-ms", 1000),
+            ("crawler.per-request-jitter-ms", 250),
+            ("crawler.max-domain-requests", 500),
+            ("crawler.max-discovered-domain-requests", 20),
+            ("retry.max-retries", 3),
+            ("retry.base-delay-ms", 5000),
+        ],
+        // Stays on the quality domains: other domains get the same short delay
+        CrawlProfile::AggressiveInternal => &[
+            ("crawler.max-concurrent-pages-open", 50),
+            ("crawler.per-domain-delay-ms", 100),
+            ("crawler.per-request-jitter-ms", 0),
+            ("crawler.max-domain-requests", 100_000),
+            ("crawler.max-discovered-domain-requests", 1),
+            ("crawler.max-cross-domain-hops", 0),
+            ("retry.max-retries", 5),
+            ("retry.base-delay-ms", 500),
+            ("retry.max-delay-ms", 10_000),
+        ],
+    }
+}
+
+/// Fills in the settings of the selected profile
+///
+/// Does nothing when `profile.name` is not set. Keys the document already
+/// sets are left alone.
+///
+/// # Arguments
+///
+/// * `document` - The configuration, with overrides applied
+///
+/// # Returns
+///
+/// * `Ok(())` - The profile was applied
+/// * `Err(ConfigError)` - The profile is unknown or a section is not a table
+pub fn apply_profile(document: &mut toml::Value) -> Result<(), ConfigError> {
+    let Some(name) = document
+        .get("profile")
+        .and_then(|profile| profile.get("name"))
+    else {
+        return Ok(());
+    };
+    let profile = CrawlProfile::deserialize(name.clone()).map_err(|_| {
+        ConfigError::Validation(format!(
+            "Unknown profile {}, expected one of: {}",
+            name,
+            CrawlProfile::NAMES.join(", ")
+        ))
+    })?;
+
+    let root = document
+        .as_table_mut()
+        .ok_or_else(|| ConfigError::Validation("Configuration root is not a table".to_string()))?;
+    for (key, value) in profile_settings(profile) {
+        let (section, field) = key.split_once('.').unwrap_or(("", key));
+        let table = root
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| {
+                ConfigError::Validation(format!(
+                    "Cannot apply the profile: '{}' is not a table",
+                    section
+                ))
+            })?;
+        table.entry(field).or_insert(toml::Value::Integer(*value));
+    }
+
+    Ok(())
+}
+
+/// Lists the quality domains an aggressive profile may not crawl
+///
+/// A domain is owned when it is an internal host (a non-public address or a
+/// reserved top-level domain such as `.internal` or `.local`) or lies within
+/// an entry of `profile.owned-domains`.
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+///
+/// # Returns
+///
+/// The patterns of the quality domains that are not owned
+pub fn unowned_quality_domains(config: &Config) -> Vec<&str> {
+    config
+        .quality
+        .iter()
+        .map(|entry| entry.domain.as_str())
+        .filter(|pattern| !is_owned(pattern, &config.profile.owned_domains))
+        .collect()
+}
+
+fn is_owned(pattern: &str, owned_domains: &[String]) -> bool {
+    let domain = pattern.trim_start_matches("*.").to_lowercase();
+    let internal = Url::parse(&format!("http://{}/", domain))
+        .ok()
+        .and_then(|url| unreachable_reason(&url))
+        .is_some();
+
+    internal
+        || owned_domains.iter().any(|owned| {
+            let owned = owned.trim_start_matches("*.").to_lowercase();
+            domain == owned || domain.ends_with(&format!(".{}", owned))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+
+    fn document(toml: &str) -> toml::Value {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut doc = document(
+            r#"
+[crawler]
+max-depth = 3
+per-domain-delay-ms = 3000
+
+[profile]
+name = "gentle"
+"#,
+        );
+        apply_profile(&mut doc).unwrap();
+
+        // Explicit settings win over the profile
+        assert_eq!(
+            doc["crawler"]["per-domain-delay-ms"].as_integer(),
+            Some(3000)
+        );
+        assert_eq!(
+            doc["crawler"]["max-concurrent-pages-open"].as_integer(),
+            Some(2)
+        );
+        assert_eq!(doc["retry"]["max-retries"].as_integer(), Some(1));
+
+        let mut unknown = document("[profile]\nname = \"reckless\"\n");
+        assert!(matches!(
+            apply_profile(&mut unknown),
+            Err(ConfigError::Validation(_))
+        ));
+
+        // Without a profile nothing is added
+        let mut plain = document("[crawler]\nmax-depth = 3\n");
+        apply_profile(&mut plain).unwrap();
+        assert_eq!(plain, document("[crawler]\nmax-depth = 3\n"));
+    }
+
+    #[test]
+    fn test_is_owned() {
+        let owned = vec!["example.com".to_string()];
+        assert!(is_owned("docs.example.com", &owned));
+        assert!(is_owned("*.example.com", &owned));
+        assert!(is_owned("wiki.corp.internal", &[]));
+        assert!(is_owned("10.0.0.5", &[]));
+        assert!(!is_owned("notexample.com", &owned));
+        assert!(!is_owned("example.org", &owned));
+    }
+
+    #[test]
+    fn test_aggressive_profile_requires_owned_domains() {
+        let load = |profile: &str| -> Result<Config, ConfigError> {
+            let mut doc = document(&test_config_toml(&format!(
+                r#"
+[profile]
+{}
+
+[[quality]]
+domain = "docs.example.com"
+seeds = ["https://docs.example.com/"]
+"#,
+                profile
+            )));
+            // Leave the crawler settings to the profile
+            doc["crawler"]
+                .as_table_mut()
+                .unwrap()
+                .retain(|key, _| key == "max-depth");
+            apply_profile(&mut doc)?;
+            let config: Config = doc.try_into()?;
+            crate::config::validation::validate(&config)?;
+            Ok(config)
+        };
+
+        let refused = load("name = \"aggressive-internal\"");
+        assert!(
+            matches!(refused, Err(ConfigError::Validation(ref e)) if e.contains("'docs.example.com'"))
+        );
+        let owned =
+            load("name = \"aggressive-internal\"\nowned-domains = [\"example.com\"]").unwrap();
+        assert_eq!(owned.crawler.max_concurrent_pages_open, 50);
+        assert_eq!(owned.crawler.max_cross_domain_hops, Some(0));
+        assert!(load("name = \"aggressive-internal\"\nallow-unowned = true").is_ok());
+
+        // Other profiles may crawl any domain
+        let gentle = load("name = \"gentle\"").unwrap();
+        assert_eq!(gentle.crawler.per_domain_delay_ms, 5000);
+        assert_eq!(gentle.retry.max_retries, 1);
+    }
+}
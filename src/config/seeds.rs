@@ -0,0 +1,122 @@
+//! Seed URL lists stored outside the configuration file
+//!
+//! Quality entries with thousands of seeds can list them in a plain text file
+//! (`seeds-file = "seeds.txt"`, one URL per line) instead of a TOML array.
+//! The file's URLs are appended to the entry's `seeds` when the configuration
+//! is loaded, so they are validated like inline seeds.
+
+use crate::config::types::Config;
+use crate::ConfigError;
+use std::path::Path;
+
+/// Appends the URLs from every quality entry's `seeds-file` to its seeds
+///
+/// # Arguments
+///
+/// * `config` - The configuration to extend
+/// * `base_dir` - Directory that relative seed file paths are resolved against
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of seed URLs added
+/// * `Err(ConfigError)` - A seeds file could not be read
+pub fn load_seed_files(config: &mut Config, base_dir: &Path) -> Result<usize, ConfigError> {
+    let mut added = 0;
+
+    for entry in &mut config.quality {
+        let Some(file) = &entry.seeds_file else {
+            continue;
+        };
+
+        let path = base_dir.join(file);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ConfigError::SeedsFile(format!("{}: {}", path.display(), e)))?;
+
+        let seeds = parse_seed_list(&content);
+        tracing::info!(
+            "Loaded {} seed URLs for {} from {}",
+            seeds.len(),
+            entry.domain,
+            path.display()
+        );
+
+        for seed in seeds {
+            if !entry.seeds.contains(&seed) {
+                entry.seeds.push(seed);
+                added += 1;
+            }
+        }
+    }
+
+    Ok(added)
+}
+
+/// Extracts seed URLs from a seeds file
+///
+/// Blank lines and lines starting with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `content` - The seeds file content
+///
+/// # Returns
+///
+/// The URLs in file order
+pub fn parse_seed_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+
+    const QUALITY: &str = r#"
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+seeds-file = "seeds.txt"
+"#;
+
+    #[test]
+    fn test_parse_seed_list() {
+        let seeds = parse_seed_list("# seeds\nhttps://a.com/\n\n  https://b.com/x  \n");
+        assert_eq!(seeds, vec!["https://a.com/", "https://b.com/x"]);
+    }
+
+    #[test]
+    fn test_load_seed_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("seeds.txt"),
+            "https://example.com/\nhttps://example.com/about\n",
+        )
+        .unwrap();
+
+        let mut config: Config = toml::from_str(&test_config_toml(QUALITY)).unwrap();
+        let added = load_seed_files(&mut config, dir.path()).unwrap();
+
+        // The inline seed is not duplicated
+        assert_eq!(added, 1);
+        assert_eq!(
+            config.quality[0].seeds,
+            vec!["https://example.com/", "https://example.com/about"]
+        );
+    }
+
+    #[test]
+    fn test_missing_seeds_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config: Config = toml::from_str(&test_config_toml(QUALITY)).unwrap();
+
+        assert!(matches!(
+            load_seed_files(&mut config, dir.path()),
+            Err(ConfigError::SeedsFile(_))
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! Example configuration written by `--init-config`
+//!
+//! The template is `examples/sample_config.toml`, embedded at build time, so
+//! the binary and the repository ship the same documented example. The tests
+//! below load it (with every commented-out option enabled as well) to keep it
+//! in step with the configuration schema.
+
+use crate::ConfigError;
+use std::io::Write;
+use std::path::Path;
+
+/// A fully commented example configuration
+pub const EXAMPLE_CONFIG: &str = include_str!("../../examples/sample_config.toml");
+
+/// Writes the example configuration to a new file
+///
+/// Existing files are never overwritten.
+///
+/// # Arguments
+///
+/// * `path` - Where to write the configuration
+///
+/// # Returns
+///
+/// * `Ok(())` - The file was created
+/// * `Err(ConfigError)` - The file already exists or could not be written
+pub fn write_example_config(path: &Path) -> Result<(), ConfigError> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(EXAMPLE_CONFIG.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Config;
+    use crate::config::validation::validate;
+
+    /// Enables every commented-out option ("# key = value" and "# [[table]]")
+    fn uncomment_options(template: &str) -> String {
+        template
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(rest) if rest.starts_with('[') || is_assignment(rest) => rest,
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Whether a line looks like "key = value" rather than prose
+    fn is_assignment(line: &str) -> bool {
+        line.split_once(" = ").is_some_and(|(key, _)| {
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    }
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config: Config = toml::from_str(EXAMPLE_CONFIG).unwrap();
+        validate(&config).unwrap();
+    }
+
+    #[test]
+    fn test_commented_options_are_valid() {
+        let enabled = uncomment_options(EXAMPLE_CONFIG);
+        assert_ne!(enabled, EXAMPLE_CONFIG);
+
+        let config: Config = toml::from_str(&enabled).unwrap();
+        assert!(!config.blacklist_files.is_empty());
+        assert!(!config.schedule.windows.is_empty());
+        assert!(!config.timeouts.is_empty());
+        assert!(config.quality.iter().any(|q| q.seeds_from_sitemap));
+        validate(&config).unwrap();
+    }
+
+    #[test]
+    fn test_example_documents_every_setting() {
+        let enabled = uncomment_options(EXAMPLE_CONFIG);
+        let config: Config = toml::from_str(&enabled).unwrap();
+        let template: toml::Value = toml::from_str(&enabled).unwrap();
+        let serialized = toml::Value::try_from(&config).unwrap();
+
+        let mut missing = Vec::new();
+        collect_missing_keys("", &serialized, &template, &mut missing);
+        assert!(
+            missing.is_empty(),
+            "settings missing from examples/sample_config.toml: {:?}",
+            missing
+        );
+    }
+
+    /// Records keys present in `schema` but absent from `template`
+    fn collect_missing_keys(
+        prefix: &str,
+        schema: &toml::Value,
+        template: &toml::Value,
+        missing: &mut Vec<String>,
+    ) {
+        match (schema, template) {
+            (toml::Value::Table(schema), toml::Value::Table(template)) => {
+                for (key, value) in schema {
+                    let path = format!("{}{}", prefix, key);
+                    match template.get(key) {
+                        Some(documented) => {
+                            collect_missing_keys(&format!("{}.", path), value, documented, missing)
+                        }
+                        None => missing.push(path),
+                    }
+                }
+            }
+            // Every key used by any entry of a list must be documented somewhere
+            (toml::Value::Array(schema), toml::Value::Array(template)) => {
+                let documented: toml::Table = template
+                    .iter()
+                    .filter_map(|entry| entry.as_table())
+                    .flat_map(|entry| entry.clone())
+                    .collect();
+                for entry in schema {
+                    collect_missing_keys(
+                        prefix,
+                        entry,
+                        &toml::Value::Table(documented.clone()),
+                        missing,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_write_example_config_does_not_overwrite() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        write_example_config(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), EXAMPLE_CONFIG);
+
+        std::fs::write(&path, "edited").unwrap();
+        assert!(write_example_config(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "edited");
+    }
+}
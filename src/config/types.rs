@@ -1,22 +1,83 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// Default interval between run progress snapshots (minutes)
+pub const DEFAULT_PROGRESS_SNAPSHOT_MINUTES: u64 = 5;
+
+/// Default maximum length of a stored page title (characters)
+pub const DEFAULT_MAX_TITLE_LENGTH: usize = 512;
+
+/// Default number of retries per page, over all runs, before a failing page is given up
+pub const DEFAULT_MAX_PAGE_RETRIES: u32 = 10;
+
+/// Default number of requests per discovered (unlisted) domain
+pub const DEFAULT_MAX_DISCOVERED_DOMAIN_REQUESTS: u32 = 20;
+
+/// Default number of links followed per page
+pub const DEFAULT_MAX_LINKS_PER_PAGE: usize = 1000;
+
+/// Default number of pages on one domain sharing a body template that mark it
+/// as an error or interstitial page template
+pub const DEFAULT_ERROR_TEMPLATE_MIN_PAGES: u32 = 5;
+
+/// Default number of directory listing or calendar pages per domain whose
+/// links are followed
+pub const DEFAULT_TRAP_PAGE_LIMIT: u32 = 50;
+
+/// Default number of fetches that include at least one beyond the first
+/// priority class, when such URLs are queued
+pub const DEFAULT_LOWER_TIER_FETCH_INTERVAL: u32 = 10;
+
+/// Default consecutive failures of a domain that open its circuit
+pub const DEFAULT_CIRCUIT_BREAKER_THRESHOLD: u32 = 5;
+
+/// Default time an open circuit holds back requests to its domain (seconds)
+pub const DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS: u64 = 300;
+
+/// Default time a request may take before it fails (seconds)
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Media types crawled by default
+pub const DEFAULT_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
 
 /// Main configuration structure for Sumi-Ripple
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub crawler: CrawlerConfig,
     #[serde(rename = "user-agent")]
     pub user_agent: UserAgentConfig,
     pub output: OutputConfig,
     #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub promotion: PromotionConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub profile: ProfileConfig,
+    #[serde(default)]
     pub quality: Vec<QualityEntry>,
     #[serde(default)]
     pub blacklist: Vec<DomainEntry>,
     #[serde(default)]
     pub stub: Vec<DomainEntry>,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(
+        default,
+        rename = "blacklist-file",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub blacklist_files: Vec<BlocklistFile>,
+    #[serde(default, rename = "timeout", skip_serializing_if = "Vec::is_empty")]
+    pub timeouts: Vec<TimeoutEntry>,
 }
 
 /// Crawler behavior configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CrawlerConfig {
     /// Maximum depth to crawl from seed URLs
     #[serde(rename = "max-depth")]
@@ -27,16 +88,245 @@ pub struct CrawlerConfig {
     pub max_concurrent_pages_open: u32,
 
     /// Minimum time between requests to the same domain (milliseconds)
-    #[serde(rename = "minimum-time-on-page")]
-    pub minimum_time_on_page: u64,
+    ///
+    /// Formerly `minimum-time-on-page`, which is still accepted; see
+    /// [`crate::config::DEPRECATED_KEYS`].
+    #[serde(rename = "per-domain-delay-ms", alias = "minimum-time-on-page")]
+    pub per_domain_delay_ms: u64,
+
+    /// Random extra wait of up to this many milliseconds added to the
+    /// per-domain delay after each request (0 disables)
+    #[serde(default, rename = "per-request-jitter-ms")]
+    pub per_request_jitter_ms: u64,
+
+    /// Maximum number of domain boundaries a path may cross from its quality
+    /// origin, independent of `max-depth` (unlimited if unset)
+    #[serde(
+        default,
+        rename = "max-cross-domain-hops",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_cross_domain_hops: Option<u32>,
 
     /// Maximum number of requests per domain
     #[serde(rename = "max-domain-requests")]
     pub max_domain_requests: u32,
+
+    /// Maximum number of requests per discovered domain, so domains outside
+    /// the quality list are only sampled
+    #[serde(
+        default = "default_max_discovered_domain_requests",
+        rename = "max-discovered-domain-requests"
+    )]
+    pub max_discovered_domain_requests: u32,
+
+    /// Whether to defer requests to a domain until its robots.txt Visit-time window
+    #[serde(default, rename = "honor-visit-time")]
+    pub honor_visit_time: bool,
+
+    /// Minutes between progress snapshots recorded for the run (0 disables them)
+    #[serde(
+        default = "default_progress_snapshot_minutes",
+        rename = "progress-snapshot-minutes"
+    )]
+    pub progress_snapshot_minutes: u64,
+
+    /// Maximum length of a stored page title (characters); longer titles are truncated
+    #[serde(default = "default_max_title_length", rename = "max-title-length")]
+    pub max_title_length: usize,
+
+    /// Days after which an incremental crawl refetches a processed page
+    #[serde(
+        default,
+        rename = "recrawl-after-days",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recrawl_after_days: Option<u32>,
+
+    /// How queued URLs are ordered beyond their classification
+    #[serde(default, rename = "priority-policy")]
+    pub priority_policy: PriorityPolicy,
+
+    /// Crawled pages between reordering queued URLs by in-link count (0 disables)
+    #[serde(default, rename = "inlink-priority-interval")]
+    pub inlink_priority_interval: u64,
+
+    /// Every Nth fetch goes to a URL beyond the first priority class (such as
+    /// a discovered domain's) if one is ready, so a steady stream of quality
+    /// URLs cannot hold them back forever (0 disables)
+    #[serde(
+        default = "default_lower_tier_fetch_interval",
+        rename = "lower-tier-fetch-interval"
+    )]
+    pub lower_tier_fetch_interval: u32,
+
+    /// Whether to check the Content-Type with a HEAD request before each GET
+    ///
+    /// Off by default: the GET response is checked before its body is read.
+    /// Even when enabled, domains that repeatedly mishandle HEAD are fetched
+    /// with GET only.
+    #[serde(default, rename = "head-requests")]
+    pub head_requests: bool,
+
+    /// Media types that count as crawlable ("type/subtype" or "type/*");
+    /// responses of other types become ContentMismatch without being downloaded
+    #[serde(default = "default_content_types", rename = "content-types")]
+    pub content_types: Vec<String>,
+
+    /// Retries per page, summed over all runs, after which a page in an error
+    /// state is no longer fetched
+    #[serde(default = "default_max_page_retries", rename = "max-page-retries")]
+    pub max_page_retries: u32,
+
+    /// Consecutive network failures or 5xx responses from a domain after
+    /// which its queued URLs are held back for a cooldown (0 disables)
+    #[serde(
+        default = "default_circuit_breaker_threshold",
+        rename = "circuit-breaker-threshold"
+    )]
+    pub circuit_breaker_threshold: u32,
+
+    /// Seconds a domain's circuit stays open before requests are tried again
+    #[serde(
+        default = "default_circuit_breaker_cooldown_secs",
+        rename = "circuit-breaker-cooldown-secs"
+    )]
+    pub circuit_breaker_cooldown_secs: u64,
+
+    /// Links followed per page; pages with more are flagged and only their
+    /// first links are followed (0 disables the limit)
+    #[serde(default = "default_max_links_per_page", rename = "max-links-per-page")]
+    pub max_links_per_page: usize,
+
+    /// Pages of one domain sharing a body (once the requested URL is blanked
+    /// out) from which they count as an error or interstitial template, such
+    /// as a soft 404; the links of all but one of them are left out of the
+    /// link graph (0 disables)
+    #[serde(
+        default = "default_error_template_min_pages",
+        rename = "error-template-min-pages"
+    )]
+    pub error_template_min_pages: u32,
+
+    /// Directory listing or calendar pages (of each kind) per domain whose
+    /// links are followed; such pages are tagged, and the links of further
+    /// ones are dropped (0 follows them all)
+    #[serde(default = "default_trap_page_limit", rename = "trap-page-limit")]
+    pub trap_page_limit: u32,
+
+    /// Link sources followed besides `<a href>` and canonical links
+    #[serde(default = "default_embedded_links", rename = "embedded-links")]
+    pub embedded_links: Vec<EmbeddedLinkSource>,
+
+    /// Whether to record the email addresses and social profiles linked from
+    /// quality-domain pages
+    #[serde(default, rename = "harvest-contacts")]
+    pub harvest_contacts: bool,
+
+    /// Whether to drop links from a page to itself (after normalization)
+    #[serde(default = "default_skip_self_links", rename = "skip-self-links")]
+    pub skip_self_links: bool,
+
+    /// Whether to also drop links to the page's own path with another query
+    /// string, such as sort orders or in-page views
+    #[serde(default, rename = "skip-same-path-links")]
+    pub skip_same_path_links: bool,
+
+    /// Domain patterns of single-page applications routing with `#!/path` or
+    /// `#/path` fragments, which are kept during normalization so each view
+    /// is its own page
+    #[serde(default, rename = "hash-route-domains")]
+    pub hash_route_domains: Vec<String>,
+}
+
+fn default_max_discovered_domain_requests() -> u32 {
+    DEFAULT_MAX_DISCOVERED_DOMAIN_REQUESTS
+}
+
+fn default_progress_snapshot_minutes() -> u64 {
+    DEFAULT_PROGRESS_SNAPSHOT_MINUTES
+}
+
+fn default_max_title_length() -> usize {
+    DEFAULT_MAX_TITLE_LENGTH
+}
+
+fn default_max_page_retries() -> u32 {
+    DEFAULT_MAX_PAGE_RETRIES
+}
+
+fn default_lower_tier_fetch_interval() -> u32 {
+    DEFAULT_LOWER_TIER_FETCH_INTERVAL
+}
+
+fn default_max_links_per_page() -> usize {
+    DEFAULT_MAX_LINKS_PER_PAGE
+}
+
+fn default_error_template_min_pages() -> u32 {
+    DEFAULT_ERROR_TEMPLATE_MIN_PAGES
+}
+
+fn default_trap_page_limit() -> u32 {
+    DEFAULT_TRAP_PAGE_LIMIT
+}
+
+fn default_circuit_breaker_threshold() -> u32 {
+    DEFAULT_CIRCUIT_BREAKER_THRESHOLD
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS
+}
+
+fn default_embedded_links() -> Vec<EmbeddedLinkSource> {
+    vec![
+        EmbeddedLinkSource::Iframe,
+        EmbeddedLinkSource::Area,
+        EmbeddedLinkSource::MetaRefresh,
+    ]
+}
+
+fn default_skip_self_links() -> bool {
+    true
+}
+
+fn default_content_types() -> Vec<String> {
+    DEFAULT_CONTENT_TYPES
+        .iter()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Frontier ordering policies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriorityPolicy {
+    /// Order by classification only (quality domains first)
+    #[default]
+    Standard,
+
+    /// Queue the first URL of each domain ahead of further URLs from
+    /// domains already queued or crawled
+    NewDomainsFirst,
+}
+
+/// Elements besides `<a href>` through which a page links to other pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmbeddedLinkSource {
+    /// `<iframe src="...">`
+    Iframe,
+
+    /// `<area href="...">` of client-side image maps
+    Area,
+
+    /// `<meta http-equiv="refresh" content="0; url=...">`
+    MetaRefresh,
 }
 
 /// User agent identification configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UserAgentConfig {
     /// Name of the crawler
     #[serde(rename = "crawler-name")]
@@ -56,7 +346,7 @@ pub struct UserAgentConfig {
 }
 
 /// Output configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OutputConfig {
     /// Path to the SQLite database file
     #[serde(rename = "database-path")]
@@ -65,21 +355,397 @@ pub struct OutputConfig {
     /// Path to the markdown summary file
     #[serde(rename = "summary-path")]
     pub summary_path: String,
+
+    /// Handlebars template used instead of the built-in markdown summary layout
+    #[serde(
+        default,
+        rename = "summary-template",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub summary_template: Option<String>,
+
+    /// Handlebars template used instead of the built-in HTML report layout
+    #[serde(
+        default,
+        rename = "html-template",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub html_template: Option<String>,
+
+    /// WARC file every fetched page is archived to (gzip-compressed if it ends in .gz)
+    #[serde(default, rename = "warc-path", skip_serializing_if = "Option::is_none")]
+    pub warc_path: Option<String>,
+}
+
+/// SQLite tuning for the crawl database
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DatabaseConfig {
+    /// How long to wait for a lock held by another connection (milliseconds)
+    #[serde(default = "default_busy_timeout_ms", rename = "busy-timeout-ms")]
+    pub busy_timeout_ms: u64,
+
+    /// WAL size in pages after which SQLite checkpoints automatically (0 disables)
+    #[serde(default = "default_wal_autocheckpoint", rename = "wal-autocheckpoint")]
+    pub wal_autocheckpoint: u32,
+
+    /// How often SQLite syncs to disk
+    #[serde(default)]
+    pub synchronous: SynchronousMode,
+
+    /// Page cache size per connection (KiB)
+    #[serde(default = "default_cache_size_kib", rename = "cache-size-kib")]
+    pub cache_size_kib: u32,
+
+    /// Keep personal data out of the database: scrub sensitive query
+    /// parameters, hash referrer URLs and skip harvested email addresses
+    ///
+    /// Pages are stored under their scrubbed URL, so a resumed run fetches
+    /// the URL without the dropped parameters.
+    #[serde(default, rename = "scrub-pii")]
+    pub scrub_pii: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            busy_timeout_ms: default_busy_timeout_ms(),
+            wal_autocheckpoint: default_wal_autocheckpoint(),
+            synchronous: SynchronousMode::default(),
+            cache_size_kib: default_cache_size_kib(),
+            scrub_pii: false,
+        }
+    }
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_wal_autocheckpoint() -> u32 {
+    1000
+}
+
+fn default_cache_size_kib() -> u32 {
+    8192
+}
+
+/// Retry behavior for transient fetch failures (timeouts, 5xx, connection errors)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryConfig {
+    /// Retries per fetch before the failure is recorded
+    #[serde(default = "default_max_retries", rename = "max-retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubles with every further retry (milliseconds)
+    #[serde(default = "default_base_delay_ms", rename = "base-delay-ms")]
+    pub base_delay_ms: u64,
+
+    /// Upper bound of the delay between retries (milliseconds)
+    #[serde(default = "default_max_delay_ms", rename = "max-delay-ms")]
+    pub max_delay_ms: u64,
+
+    /// Fraction of each delay that is randomized (0 = fixed delays, 1 = anywhere
+    /// between zero and the full delay), so crawlers do not retry in lockstep
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            base_delay_ms: default_base_delay_ms(),
+            max_delay_ms: default_max_delay_ms(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_base_delay_ms() -> u64 {
+    5000
+}
+
+fn default_max_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_jitter() -> f64 {
+    0.5
+}
+
+/// Automatic promotion of discovered domains that many quality pages link to
+///
+/// Promoted domains are crawled `extra-depth` levels deeper than `max-depth`
+/// and with the request budget of quality domains, so the crawl grows toward
+/// the sites the quality domains point at most.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PromotionConfig {
+    /// Whether discovered domains are promoted
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Distinct quality-domain pages that must link to a discovered domain
+    /// before it is promoted
+    #[serde(
+        default = "default_min_quality_referrers",
+        rename = "min-quality-referrers"
+    )]
+    pub min_quality_referrers: u64,
+
+    /// Levels added to `max-depth` for the pages of promoted domains
+    #[serde(default = "default_extra_depth", rename = "extra-depth")]
+    pub extra_depth: u32,
+
+    /// Most domains promoted, counted over all runs
+    #[serde(default = "default_max_promoted_domains", rename = "max-domains")]
+    pub max_domains: u32,
+}
+
+impl Default for PromotionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_quality_referrers: default_min_quality_referrers(),
+            extra_depth: default_extra_depth(),
+            max_domains: default_max_promoted_domains(),
+        }
+    }
+}
+
+/// The operator's own rules on what may be fetched
+///
+/// Applied to every URL before it is fetched, whatever the classification of
+/// its domain, so compliance or ethics rules need not be expressed as domain
+/// lists.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PolicyConfig {
+    /// Regular expressions matched against the full URL; matching URLs are
+    /// recorded as `policy_denied` and never fetched
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// How long crawl history is kept in the database
+///
+/// Enforced when a crawl starts and by `--prune`. Every limit is off (0) by
+/// default, so nothing is deleted unless configured.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    /// Runs kept, newest first; older runs are deleted (0 keeps all)
+    #[serde(default, rename = "keep-runs")]
+    pub keep_runs: u32,
+
+    /// Age after which page observations are deleted (days, 0 keeps them)
+    #[serde(default, rename = "observation-max-age-days")]
+    pub observation_max_age_days: u32,
+
+    /// Age after which page state transitions and progress snapshots are
+    /// deleted (days, 0 keeps them)
+    #[serde(default, rename = "event-max-age-days")]
+    pub event_max_age_days: u32,
+}
+
+impl RetentionConfig {
+    /// Whether any retention limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.keep_runs > 0 || self.observation_max_age_days > 0 || self.event_max_age_days > 0
+    }
+}
+
+/// Preset bundles of politeness settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrawlProfile {
+    /// Few connections and long delays, for small or fragile sites
+    Gentle,
+
+    /// The settings of the example configuration
+    Standard,
+
+    /// Many connections and short delays, only for sites you operate
+    AggressiveInternal,
+}
+
+impl CrawlProfile {
+    /// Profile names as written in the configuration
+    pub const NAMES: &'static [&'static str] = &["gentle", "standard", "aggressive-internal"];
+
+    /// The profile name as written in the configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrawlProfile::Gentle => "gentle",
+            CrawlProfile::Standard => "standard",
+            CrawlProfile::AggressiveInternal => "aggressive-internal",
+        }
+    }
+
+    /// Whether the profile is only allowed on owned domains
+    pub fn is_aggressive(&self) -> bool {
+        matches!(self, CrawlProfile::AggressiveInternal)
+    }
+}
+
+/// The politeness profile of the crawl
+///
+/// A profile fills in the concurrency, delay, retry and budget settings the
+/// configuration leaves out; settings given explicitly always win.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileConfig {
+    /// The profile to apply, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<CrawlProfile>,
+
+    /// Domains you operate, which aggressive profiles may crawl besides
+    /// internal hosts (subdomains included)
+    #[serde(default, rename = "owned-domains")]
+    pub owned_domains: Vec<String>,
+
+    /// Allows aggressive profiles on quality domains that are not owned
+    #[serde(default, rename = "allow-unowned")]
+    pub allow_unowned: bool,
+}
+
+fn default_min_quality_referrers() -> u64 {
+    10
+}
+
+fn default_extra_depth() -> u32 {
+    2
+}
+
+fn default_max_promoted_domains() -> u32 {
+    20
+}
+
+/// SQLite `synchronous` levels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SynchronousMode {
+    /// Leave syncing to the operating system
+    Off,
+
+    /// Sync at checkpoints; safe with WAL except on power loss
+    #[default]
+    Normal,
+
+    /// Sync on every commit
+    Full,
+
+    /// Like `Full`, also syncing the directory on commit
+    Extra,
+}
+
+impl SynchronousMode {
+    /// The value for `PRAGMA synchronous`
+    pub fn as_pragma(&self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Normal => "NORMAL",
+            Self::Full => "FULL",
+            Self::Extra => "EXTRA",
+        }
+    }
 }
 
 /// Quality domain entry with seed URLs
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct QualityEntry {
     /// Domain pattern (e.g., "example.com" or "*.example.com")
     pub domain: String,
 
     /// List of seed URLs to start crawling from
+    #[serde(default)]
     pub seeds: Vec<String>,
+
+    /// File with additional seed URLs, one per line (relative to the config file)
+    #[serde(
+        default,
+        rename = "seeds-file",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub seeds_file: Option<String>,
+
+    /// Also seed with the URLs listed in the domain's sitemap.xml
+    #[serde(default, rename = "seeds-from-sitemap")]
+    pub seeds_from_sitemap: bool,
 }
 
 /// Simple domain entry for blacklist and stub lists
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DomainEntry {
     /// Domain pattern (e.g., "example.com" or "*.example.com")
     pub domain: String,
 }
+
+/// Request timeout for the domains matching a pattern
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TimeoutEntry {
+    /// Domain pattern (e.g., "example.com" or "*.example.com")
+    pub domain: String,
+
+    /// Seconds a request to a matching domain may take, replacing the
+    /// default of 30s
+    #[serde(rename = "timeout-secs")]
+    pub timeout_secs: u64,
+}
+
+/// External blocklist imported into the blacklist at load time
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlocklistFile {
+    /// Path to the blocklist, relative to the main config file
+    pub path: String,
+
+    /// Format of the file; detected per line when omitted
+    #[serde(default)]
+    pub format: BlocklistFormat,
+
+    /// Also block every subdomain of each listed domain
+    #[serde(default, rename = "include-subdomains")]
+    pub include_subdomains: bool,
+}
+
+/// Supported external blocklist formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistFormat {
+    /// Detect the format of each line
+    #[default]
+    Auto,
+
+    /// hosts file lines ("0.0.0.0 ads.example.com")
+    Hosts,
+
+    /// One domain per line
+    Domains,
+
+    /// AdBlock-style domain rules ("||ads.example.com^")
+    Adblock,
+}
+
+/// Crawl calendar configuration (quiet hours)
+///
+/// When no windows are configured the crawler may fetch at any time.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScheduleConfig {
+    /// Time windows during which the crawler actively fetches pages
+    #[serde(default, rename = "window")]
+    pub windows: Vec<CrawlWindow>,
+}
+
+/// A daily time window (UTC) during which crawling is allowed
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CrawlWindow {
+    /// Start of the window in UTC ("HH:MM")
+    pub start: String,
+
+    /// End of the window in UTC ("HH:MM"); may be earlier than `start` to span midnight
+    pub end: String,
+
+    /// Days of the week the window opens on (e.g. "mon", "sat"); empty means every day
+    #[serde(default)]
+    pub days: Vec<String>,
+}
@@ -1,15 +1,35 @@
-use crate::config::types::{Config, CrawlerConfig, DomainEntry, QualityEntry, UserAgentConfig};
+use crate::config::conflicts::find_conflicts;
+use crate::config::diagnostics::Severity;
+use crate::config::profiles::unowned_quality_domains;
+use crate::config::types::{
+    Config, CrawlerConfig, DomainEntry, PolicyConfig, PromotionConfig, QualityEntry, RetryConfig,
+    ScheduleConfig, TimeoutEntry, UserAgentConfig,
+};
+use crate::url::{extract_domain, matches_wildcard};
 use crate::ConfigError;
 use url::Url;
 
 /// Validates the entire configuration
 pub fn validate(config: &Config) -> Result<(), ConfigError> {
+    validate_fields(config)?;
+    validate_list_conflicts(config)?;
+    Ok(())
+}
+
+/// Validates individual settings and entries, without cross-entry checks
+pub(crate) fn validate_fields(config: &Config) -> Result<(), ConfigError> {
     validate_crawler_config(&config.crawler)?;
+    validate_retry_config(&config.retry)?;
+    validate_promotion_config(&config.promotion)?;
     validate_user_agent_config(&config.user_agent)?;
     validate_output_config(&config.output)?;
     validate_quality_domains(&config.quality)?;
     validate_blacklist_domains(&config.blacklist)?;
     validate_stub_domains(&config.stub)?;
+    validate_schedule_config(&config.schedule)?;
+    validate_policy_config(&config.policy)?;
+    validate_timeouts(&config.timeouts)?;
+    validate_profile_config(config)?;
     Ok(())
 }
 
@@ -24,10 +44,10 @@ fn validate_crawler_config(config: &CrawlerConfig) -> Result<(), ConfigError> {
         )));
     }
 
-    if config.minimum_time_on_page < 100 {
+    if config.per_domain_delay_ms < 100 {
         return Err(ConfigError::Validation(format!(
-            "minimum_time_on_page must be >= 100ms, got {}ms",
-            config.minimum_time_on_page
+            "per_domain_delay_ms must be >= 100ms, got {}ms",
+            config.per_domain_delay_ms
         )));
     }
 
@@ -38,6 +58,97 @@ fn validate_crawler_config(config: &CrawlerConfig) -> Result<(), ConfigError> {
         )));
     }
 
+    if config.max_discovered_domain_requests < 1 {
+        return Err(ConfigError::Validation(format!(
+            "max_discovered_domain_requests must be >= 1, got {}",
+            config.max_discovered_domain_requests
+        )));
+    }
+
+    if config.max_title_length < 1 {
+        return Err(ConfigError::Validation(format!(
+            "max_title_length must be >= 1, got {}",
+            config.max_title_length
+        )));
+    }
+
+    if config.recrawl_after_days == Some(0) {
+        return Err(ConfigError::Validation(
+            "recrawl_after_days must be >= 1".to_string(),
+        ));
+    }
+
+    if config.content_types.is_empty() {
+        return Err(ConfigError::Validation(
+            "content_types must list at least one media type".to_string(),
+        ));
+    }
+
+    for content_type in &config.content_types {
+        validate_content_type(content_type)?;
+    }
+
+    if config.circuit_breaker_threshold > 0 && config.circuit_breaker_cooldown_secs == 0 {
+        return Err(ConfigError::Validation(
+            "circuit_breaker_cooldown_secs must be >= 1 when the circuit breaker is enabled"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates the retry policy
+fn validate_retry_config(config: &RetryConfig) -> Result<(), ConfigError> {
+    if config.max_delay_ms < config.base_delay_ms {
+        return Err(ConfigError::Validation(format!(
+            "retry max_delay_ms ({}) must be >= base_delay_ms ({})",
+            config.max_delay_ms, config.base_delay_ms
+        )));
+    }
+
+    if !(0.0..=1.0).contains(&config.jitter) {
+        return Err(ConfigError::Validation(format!(
+            "retry jitter must be between 0 and 1, got {}",
+            config.jitter
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validates the discovered-domain promotion policy
+fn validate_promotion_config(config: &PromotionConfig) -> Result<(), ConfigError> {
+    if config.enabled && config.min_quality_referrers < 1 {
+        return Err(ConfigError::Validation(
+            "promotion min_quality_referrers must be >= 1".to_string(),
+        ));
+    }
+
+    if config.enabled && config.extra_depth < 1 {
+        return Err(ConfigError::Validation(
+            "promotion extra_depth must be >= 1".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates a `content-types` entry ("type/subtype" or "type/*")
+fn validate_content_type(content_type: &str) -> Result<(), ConfigError> {
+    let valid = content_type.split_once('/').is_some_and(|(kind, subtype)| {
+        !kind.is_empty()
+            && kind != "*"
+            && !subtype.is_empty()
+            && !content_type.contains([';', ' '])
+            && !subtype.contains('/')
+    });
+    if !valid {
+        return Err(ConfigError::Validation(format!(
+            "content_types entry '{}' must be a media type like \"text/html\" or \"text/*\"",
+            content_type
+        )));
+    }
     Ok(())
 }
 
@@ -88,14 +199,58 @@ fn validate_output_config(config: &crate::config::types::OutputConfig) -> Result
     Ok(())
 }
 
+/// Validates the crawl calendar
+///
+/// Every window must have well-formed "HH:MM" times and recognized day names.
+fn validate_schedule_config(config: &ScheduleConfig) -> Result<(), ConfigError> {
+    crate::crawler::CrawlCalendar::from_config(config)?;
+    Ok(())
+}
+
+/// Validates the local policy: every deny rule must be a valid regex
+fn validate_policy_config(config: &PolicyConfig) -> Result<(), ConfigError> {
+    crate::crawler::LocalPolicy::from_config(config)?;
+    Ok(())
+}
+
+/// Validates that an aggressive profile only crawls owned domains
+fn validate_profile_config(config: &Config) -> Result<(), ConfigError> {
+    let Some(profile) = config.profile.name else {
+        return Ok(());
+    };
+    if !profile.is_aggressive() || config.profile.allow_unowned {
+        return Ok(());
+    }
+
+    let unowned = unowned_quality_domains(config);
+    if unowned.is_empty() {
+        return Ok(());
+    }
+    let domains: Vec<String> = unowned
+        .iter()
+        .map(|domain| format!("'{}'", domain))
+        .collect();
+    Err(ConfigError::Validation(format!(
+        "The aggressive-internal profile may only crawl domains you operate, but {} {} \
+         listed in profile.owned-domains; list them there or set \
+         profile.allow-unowned = true",
+        domains.join(", "),
+        if domains.len() == 1 {
+            "is neither an internal host nor"
+        } else {
+            "are neither internal hosts nor"
+        }
+    )))
+}
+
 /// Validates quality domain entries
 fn validate_quality_domains(domains: &[QualityEntry]) -> Result<(), ConfigError> {
     for entry in domains {
         validate_domain_pattern(&entry.domain)?;
 
-        if entry.seeds.is_empty() {
+        if entry.seeds.is_empty() && !entry.seeds_from_sitemap {
             return Err(ConfigError::Validation(format!(
-                "Quality domain '{}' must have at least one seed URL",
+                "Quality domain '{}' must have at least one seed URL or seeds-from-sitemap",
                 entry.domain
             )));
         }
@@ -111,12 +266,60 @@ fn validate_quality_domains(domains: &[QualityEntry]) -> Result<(), ConfigError>
                     seed
                 )));
             }
+
+            validate_seed_domain(&entry.domain, seed, &url)?;
         }
     }
 
     Ok(())
 }
 
+/// Validates that a seed URL's host belongs to its quality domain
+///
+/// Seeds outside the pattern would record depth 0 under the wrong origin,
+/// so "example.com" only accepts seeds on example.com and "*.example.com"
+/// accepts example.com and any of its subdomains.
+fn validate_seed_domain(pattern: &str, seed: &str, url: &Url) -> Result<(), ConfigError> {
+    let host = extract_domain(url).unwrap_or_default();
+
+    if !matches_wildcard(&pattern.to_lowercase(), &host) {
+        let hint = if pattern.starts_with("*.") {
+            String::new()
+        } else {
+            format!(" (use \"*.{}\" to include subdomains)", pattern)
+        };
+        return Err(ConfigError::Validation(format!(
+            "Seed URL '{}' is on '{}', which does not match quality domain '{}'{}",
+            seed, host, pattern, hint
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reports duplicate and conflicting domain list entries
+///
+/// Notes and warnings are logged; the first error fails validation.
+fn validate_list_conflicts(config: &Config) -> Result<(), ConfigError> {
+    let diagnostics = find_conflicts(config);
+
+    for diagnostic in &diagnostics {
+        match diagnostic.severity {
+            Severity::Note => tracing::debug!("{}", diagnostic.message),
+            Severity::Warning => tracing::warn!("{}", diagnostic.message),
+            Severity::Error => {}
+        }
+    }
+
+    match diagnostics
+        .into_iter()
+        .find(|d| d.severity == Severity::Error)
+    {
+        Some(error) => Err(ConfigError::Validation(error.message)),
+        None => Ok(()),
+    }
+}
+
 /// Validates blacklist domain entries
 fn validate_blacklist_domains(domains: &[DomainEntry]) -> Result<(), ConfigError> {
     for entry in domains {
@@ -133,8 +336,22 @@ fn validate_stub_domains(domains: &[DomainEntry]) -> Result<(), ConfigError> {
     Ok(())
 }
 
+/// Validates per-domain timeout entries
+fn validate_timeouts(timeouts: &[TimeoutEntry]) -> Result<(), ConfigError> {
+    for entry in timeouts {
+        validate_domain_pattern(&entry.domain)?;
+        if entry.timeout_secs < 1 {
+            return Err(ConfigError::Validation(format!(
+                "timeout_secs for '{}' must be >= 1",
+                entry.domain
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Validates a domain pattern (supports wildcards)
-fn validate_domain_pattern(pattern: &str) -> Result<(), ConfigError> {
+pub(crate) fn validate_domain_pattern(pattern: &str) -> Result<(), ConfigError> {
     if pattern.is_empty() {
         return Err(ConfigError::InvalidPattern(
             "Domain pattern cannot be empty".to_string(),
@@ -161,10 +378,10 @@ fn validate_domain_string(domain: &str) -> Result<(), ConfigError> {
         ));
     }
 
-    // Check for invalid characters
+    // Check for invalid characters (`*` wildcards may appear anywhere)
     if !domain
         .chars()
-        .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+        .all(|c| c.is_alphanumeric() || c == '.' || c == '-' || c == '*')
     {
         return Err(ConfigError::InvalidPattern(format!(
             "Domain '{}' contains invalid characters",
@@ -250,6 +467,8 @@ mod tests {
         assert!(validate_domain_pattern("example.com").is_ok());
         assert!(validate_domain_pattern("*.example.com").is_ok());
         assert!(validate_domain_pattern("sub.example.com").is_ok());
+        assert!(validate_domain_pattern("cdn-*.example.com").is_ok());
+        assert!(validate_domain_pattern("*.example.*").is_ok());
 
         assert!(validate_domain_pattern("").is_err());
         assert!(validate_domain_pattern("*.").is_err());
@@ -258,6 +477,85 @@ mod tests {
         assert!(validate_domain_pattern("example.com.").is_err());
     }
 
+    #[test]
+    fn test_validate_seed_domain() {
+        let check = |pattern: &str, seed: &str| {
+            validate_seed_domain(pattern, seed, &Url::parse(seed).unwrap())
+        };
+
+        assert!(check("example.com", "https://example.com/").is_ok());
+        assert!(check("example.com", "https://EXAMPLE.com/page").is_ok());
+        assert!(check("*.example.com", "https://example.com/").is_ok());
+        assert!(check("*.example.com", "https://blog.example.com/").is_ok());
+        assert!(check("*.example.com", "https://a.b.example.com/").is_ok());
+
+        assert!(check("example.com", "https://blog.example.com/").is_err());
+        assert!(check("*.example.com", "https://example.org/").is_err());
+        assert!(check("*.example.com", "https://notexample.com/").is_err());
+
+        let err = check("example.com", "https://www.example.com/").unwrap_err();
+        assert!(err.to_string().contains("*.example.com"));
+    }
+
+    #[test]
+    fn test_validate_promotion_config() {
+        let mut config = PromotionConfig {
+            extra_depth: 0,
+            ..Default::default()
+        };
+        assert!(validate_promotion_config(&config).is_ok());
+
+        config.enabled = true;
+        assert!(validate_promotion_config(&config).is_err());
+
+        config.extra_depth = 2;
+        config.min_quality_referrers = 0;
+        assert!(validate_promotion_config(&config).is_err());
+
+        config.min_quality_referrers = 5;
+        assert!(validate_promotion_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_retry_config() {
+        assert!(validate_retry_config(&RetryConfig::default()).is_ok());
+
+        let config = RetryConfig {
+            max_delay_ms: 1000,
+            ..Default::default()
+        };
+        assert!(validate_retry_config(&config).is_err());
+
+        let config = RetryConfig {
+            jitter: 1.5,
+            ..Default::default()
+        };
+        assert!(validate_retry_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_validate_timeouts() {
+        let entry = |domain: &str, timeout_secs| TimeoutEntry {
+            domain: domain.to_string(),
+            timeout_secs,
+        };
+        assert!(validate_timeouts(&[entry("*.archive.org", 120)]).is_ok());
+        assert!(validate_timeouts(&[entry("archive.org", 0)]).is_err());
+        assert!(validate_timeouts(&[entry("archive", 60)]).is_err());
+    }
+
+    #[test]
+    fn test_validate_content_type() {
+        assert!(validate_content_type("text/html").is_ok());
+        assert!(validate_content_type("application/xhtml+xml").is_ok());
+        assert!(validate_content_type("text/*").is_ok());
+
+        assert!(validate_content_type("html").is_err());
+        assert!(validate_content_type("*/*").is_err());
+        assert!(validate_content_type("text/").is_err());
+        assert!(validate_content_type("text/html; charset=utf-8").is_err());
+    }
+
     #[test]
     fn test_validate_email() {
         assert!(validate_email("user@example.com").is_ok());
@@ -269,4 +567,30 @@ mod tests {
         assert!(validate_email("user@").is_err());
         assert!(validate_email("user@domain").is_err());
     }
+
+    #[test]
+    fn test_validate_schedule_config() {
+        use crate::config::types::CrawlWindow;
+
+        let window = |start: &str, end: &str, days: &[&str]| CrawlWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.iter().map(|d| d.to_string()).collect(),
+        };
+
+        assert!(validate_schedule_config(&ScheduleConfig::default()).is_ok());
+        assert!(validate_schedule_config(&ScheduleConfig {
+            windows: vec![window("01:00", "06:00", &["sat", "sun"])],
+        })
+        .is_ok());
+
+        assert!(validate_schedule_config(&ScheduleConfig {
+            windows: vec![window("1am", "06:00", &[])],
+        })
+        .is_err());
+        assert!(validate_schedule_config(&ScheduleConfig {
+            windows: vec![window("01:00", "06:00", &["weekend"])],
+        })
+        .is_err());
+    }
 }
@@ -0,0 +1,67 @@
+//! Anomaly detection for fetched pages
+//!
+//! Flags pages whose link count or body size is far from that of ordinary
+//! pages: directory pages with thousands of links, near-empty bodies that are
+//! likely error or placeholder pages, and huge bodies such as data dumps.
+
+use crate::storage::AnomalyKind;
+
+/// Bodies smaller than this are flagged as tiny (bytes)
+pub const TINY_BODY_BYTES: usize = 512;
+
+/// Bodies larger than this are flagged as huge (bytes)
+pub const HUGE_BODY_BYTES: usize = 5 * 1024 * 1024;
+
+/// Detects the anomalies of a fetched page
+///
+/// # Arguments
+///
+/// * `body_bytes` - Size of the page body
+/// * `links` - Number of links extracted from the page
+/// * `max_links` - The `max-links-per-page` setting (0 disables the link check)
+///
+/// # Returns
+///
+/// (kind, detail) pairs, empty for an ordinary page
+pub fn detect_anomalies(
+    body_bytes: usize,
+    links: usize,
+    max_links: usize,
+) -> Vec<(AnomalyKind, String)> {
+    let mut anomalies = Vec::new();
+
+    if max_links > 0 && links > max_links {
+        anomalies.push((AnomalyKind::ExcessiveLinks, format!("{} links", links)));
+    }
+
+    if body_bytes < TINY_BODY_BYTES {
+        anomalies.push((AnomalyKind::TinyBody, format!("{} bytes", body_bytes)));
+    } else if body_bytes > HUGE_BODY_BYTES {
+        anomalies.push((AnomalyKind::HugeBody, format!("{} bytes", body_bytes)));
+    }
+
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_anomalies() {
+        assert!(detect_anomalies(20_000, 50, 1000).is_empty());
+
+        assert_eq!(
+            detect_anomalies(20_000, 4210, 1000),
+            vec![(AnomalyKind::ExcessiveLinks, "4210 links".to_string())]
+        );
+        // Without a link limit any number of links is fine
+        assert!(detect_anomalies(20_000, 4210, 0).is_empty());
+
+        assert_eq!(detect_anomalies(12, 0, 1000)[0].0, AnomalyKind::TinyBody);
+        assert_eq!(
+            detect_anomalies(HUGE_BODY_BYTES + 1, 10, 1000)[0].0,
+            AnomalyKind::HugeBody
+        );
+    }
+}
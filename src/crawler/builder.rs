@@ -0,0 +1,91 @@
+//! Builder for coordinators with custom extensions
+//!
+//! `Coordinator::new` and `Coordinator::with_mode` cover crawls driven by the
+//! configuration alone. A `CrawlerBuilder` also takes the extensions library
+//! users plug in (a custom classifier, page processors, another fetcher) and
+//! sets them up before the coordinator is handed out.
+
+use crate::config::Config;
+use crate::crawler::processor::PageProcessor;
+use crate::crawler::{Coordinator, CrawlMode, Fetcher};
+use crate::url::Classifier;
+use crate::SumiError;
+
+/// Builds a `Coordinator` with custom extensions
+pub struct CrawlerBuilder {
+    config: Config,
+    mode: CrawlMode,
+    classifier: Option<Box<dyn Classifier>>,
+    fetcher: Option<Box<dyn Fetcher>>,
+    processors: Vec<Box<dyn PageProcessor>>,
+}
+
+impl CrawlerBuilder {
+    /// Starts a builder that resumes the crawl of a configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawler configuration
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            mode: CrawlMode::Resume,
+            classifier: None,
+            fetcher: None,
+            processors: Vec::new(),
+        }
+    }
+
+    /// Sets how the coordinator treats the state left by earlier runs
+    pub fn mode(mut self, mode: CrawlMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Replaces the classification by the configured domain lists
+    ///
+    /// See [`Coordinator::set_classifier`].
+    pub fn classifier(mut self, classifier: impl Classifier + 'static) -> Self {
+        self.classifier = Some(Box::new(classifier));
+        self
+    }
+
+    /// Replaces how pages are fetched
+    ///
+    /// By default pages are fetched over HTTP by an [`HttpFetcher`].
+    /// robots.txt is too, unless the fetcher serves it; sitemaps always are.
+    ///
+    /// [`HttpFetcher`]: crate::crawler::HttpFetcher
+    pub fn fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+        self.fetcher = Some(Box::new(fetcher));
+        self
+    }
+
+    /// Adds a processor run on every fetched page
+    ///
+    /// Processors run in the order they are added.
+    pub fn page_processor(mut self, processor: impl PageProcessor + 'static) -> Self {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
+    /// Creates the coordinator
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Coordinator)` - Successfully created coordinator
+    /// * `Err(SumiError)` - Failed to initialize
+    pub fn build(self) -> Result<Coordinator, SumiError> {
+        let mut coordinator = Coordinator::with_mode(self.config, self.mode)?;
+        if let Some(classifier) = self.classifier {
+            coordinator.replace_classifier(classifier)?;
+        }
+        if let Some(fetcher) = self.fetcher {
+            coordinator.replace_fetcher(fetcher);
+        }
+        for processor in self.processors {
+            coordinator.add_page_processor(processor);
+        }
+        Ok(coordinator)
+    }
+}
@@ -0,0 +1,220 @@
+//! Crawl calendar (quiet hours)
+//!
+//! This module decides whether the crawler is allowed to fetch pages at a
+//! given moment, based on the `[schedule]` section of the configuration.
+//! Outside the configured windows the coordinator persists its state and
+//! sleeps until the next window opens.
+
+use crate::config::ScheduleConfig;
+use crate::state::TimeWindow;
+use crate::ConfigError;
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
+
+/// A single calendar entry: a daily window restricted to some weekdays
+#[derive(Debug, Clone)]
+struct CalendarEntry {
+    window: TimeWindow,
+    /// Days the window opens on; empty means every day
+    days: Vec<Weekday>,
+}
+
+impl CalendarEntry {
+    fn opens_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, now: DateTime<Utc>) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+
+        if !self.window.contains(time) {
+            return false;
+        }
+
+        // The portion of a midnight-spanning window after 00:00 belongs to
+        // the day on which the window opened.
+        if self.window.wraps_midnight() && time < self.window.end {
+            self.opens_on(today.pred())
+        } else {
+            self.opens_on(today)
+        }
+    }
+}
+
+/// Determines when the crawler is allowed to fetch
+///
+/// An empty calendar is always open.
+#[derive(Debug, Clone, Default)]
+pub struct CrawlCalendar {
+    entries: Vec<CalendarEntry>,
+}
+
+impl CrawlCalendar {
+    /// Creates a calendar that is always open
+    pub fn always_open() -> Self {
+        Self::default()
+    }
+
+    /// Builds a calendar from the schedule configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The schedule configuration
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CrawlCalendar)` - Successfully built calendar
+    /// * `Err(ConfigError)` - A window has an invalid time or day name
+    pub fn from_config(config: &ScheduleConfig) -> Result<Self, ConfigError> {
+        let mut entries = Vec::with_capacity(config.windows.len());
+
+        for window in &config.windows {
+            let time_window =
+                TimeWindow::parse(&window.start, &window.end).map_err(ConfigError::Validation)?;
+
+            let days = window
+                .days
+                .iter()
+                .map(|day| {
+                    day.trim().parse::<Weekday>().map_err(|_| {
+                        ConfigError::Validation(format!(
+                            "Invalid day '{}' in schedule window, expected e.g. 'mon' or 'saturday'",
+                            day
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            entries.push(CalendarEntry {
+                window: time_window,
+                days,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Returns true if no windows are configured
+    pub fn is_unrestricted(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks if crawling is allowed at the given moment
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.is_unrestricted() || self.entries.iter().any(|entry| entry.contains(now))
+    }
+
+    /// Finds the next moment at which crawling is allowed
+    ///
+    /// Returns `now` if the calendar is currently open, or None if no
+    /// window ever opens (which cannot happen for a validated config).
+    pub fn next_open(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.is_open(now) {
+            return Some(now);
+        }
+
+        // While closed, the next open moment is always the start of some window
+        (0..=7)
+            .map(|offset| now.date_naive() + Duration::days(offset))
+            .flat_map(|date| {
+                self.entries
+                    .iter()
+                    .filter(move |entry| entry.opens_on(date.weekday()))
+                    .map(move |entry| date.and_time(entry.window.start).and_utc())
+            })
+            .filter(|candidate| *candidate > now)
+            .min()
+    }
+
+    /// Calculates how long to wait until crawling is allowed again
+    ///
+    /// Returns None if crawling is allowed now.
+    pub fn time_until_open(&self, now: DateTime<Utc>) -> Option<std::time::Duration> {
+        if self.is_open(now) {
+            return None;
+        }
+        self.next_open(now)
+            .and_then(|next| (next - now).to_std().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CrawlWindow;
+    use chrono::TimeZone;
+
+    fn window(start: &str, end: &str, days: &[&str]) -> CrawlWindow {
+        CrawlWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn calendar(windows: Vec<CrawlWindow>) -> CrawlCalendar {
+        CrawlCalendar::from_config(&ScheduleConfig { windows }).unwrap()
+    }
+
+    #[test]
+    fn test_empty_calendar_always_open() {
+        let cal = CrawlCalendar::always_open();
+        let now = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+        assert!(cal.is_open(now));
+        assert_eq!(cal.time_until_open(now), None);
+    }
+
+    #[test]
+    fn test_calendar_window() {
+        let cal = calendar(vec![window("01:00", "06:00", &[])]);
+        // 2024-01-01 is a Monday
+        let inside = Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+        let outside = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        assert!(cal.is_open(inside));
+        assert!(!cal.is_open(outside));
+        assert_eq!(
+            cal.next_open(outside),
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 1, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_calendar_weekdays_only() {
+        let weekdays = ["mon", "tue", "wed", "thu", "fri"];
+        let cal = calendar(vec![window("00:00", "00:00", &weekdays)]);
+
+        // 2024-01-06 is a Saturday
+        let saturday = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+        assert!(!cal.is_open(saturday));
+        assert_eq!(
+            cal.next_open(saturday),
+            Some(Utc.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap())
+        );
+
+        let friday = Utc.with_ymd_and_hms(2024, 1, 5, 23, 59, 0).unwrap();
+        assert!(cal.is_open(friday));
+    }
+
+    #[test]
+    fn test_calendar_midnight_window_belongs_to_start_day() {
+        let cal = calendar(vec![window("22:00", "04:00", &["fri"])]);
+
+        // Friday 23:00 and early Saturday belong to Friday's window
+        let friday_night = Utc.with_ymd_and_hms(2024, 1, 5, 23, 0, 0).unwrap();
+        let saturday_early = Utc.with_ymd_and_hms(2024, 1, 6, 2, 0, 0).unwrap();
+        let saturday_night = Utc.with_ymd_and_hms(2024, 1, 6, 23, 0, 0).unwrap();
+
+        assert!(cal.is_open(friday_night));
+        assert!(cal.is_open(saturday_early));
+        assert!(!cal.is_open(saturday_night));
+    }
+
+    #[test]
+    fn test_calendar_invalid_day() {
+        let result = CrawlCalendar::from_config(&ScheduleConfig {
+            windows: vec![window("01:00", "06:00", &["someday"])],
+        });
+        assert!(matches!(result, Err(ConfigError::Validation(_))));
+    }
+}
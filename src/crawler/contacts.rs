@@ -0,0 +1,133 @@
+//! Harvesting of email addresses and social profiles
+//!
+//! With `harvest-contacts` enabled, the `mailto:` addresses of quality-domain
+//! pages and their links to known social platforms are recorded, mapping
+//! where the crawled sites present themselves besides their own domains.
+
+use crate::crawler::ParsedPage;
+use url::Url;
+
+/// Social platforms as (host, platform name); subdomains match too
+pub const SOCIAL_PLATFORMS: &[(&str, &str)] = &[
+    ("twitter.com", "twitter"),
+    ("x.com", "twitter"),
+    ("facebook.com", "facebook"),
+    ("instagram.com", "instagram"),
+    ("linkedin.com", "linkedin"),
+    ("github.com", "github"),
+    ("youtube.com", "youtube"),
+    ("mastodon.social", "mastodon"),
+    ("bsky.app", "bluesky"),
+    ("tiktok.com", "tiktok"),
+];
+
+/// First path segments of share and intent links, which are not profiles
+const SHARE_PATHS: &[&str] = &["intent", "share", "sharer", "sharer.php", "sharing", "home"];
+
+/// Finds the contacts on a page
+///
+/// # Arguments
+///
+/// * `page` - The parsed page, with its links and `mailto:` addresses
+///
+/// # Returns
+///
+/// (platform, value) pairs: ("email", address) for each address and
+/// (platform, profile URL) for each social profile link, without duplicates
+pub fn find_contacts(page: &ParsedPage) -> Vec<(&'static str, String)> {
+    let mut contacts: Vec<(&'static str, String)> = page
+        .emails
+        .iter()
+        .map(|email| ("email", email.clone()))
+        .collect();
+
+    for link in &page.links {
+        if let Some(profile) = social_profile(link) {
+            if !contacts.contains(&profile) {
+                contacts.push(profile);
+            }
+        }
+    }
+
+    contacts
+}
+
+/// Recognizes a link to a profile on a social platform
+///
+/// The profile URL is returned without query, fragment or trailing slash.
+fn social_profile(link: &str) -> Option<(&'static str, String)> {
+    let url = Url::parse(link).ok()?;
+    let host = url.host_str()?;
+    let (_, platform) = SOCIAL_PLATFORMS.iter().find(|(domain, _)| {
+        host == *domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|rest| rest.ends_with('.'))
+    })?;
+
+    let path = url.path().trim_end_matches('/');
+    let first_segment = path.trim_start_matches('/').split('/').next()?;
+    if first_segment.is_empty() || SHARE_PATHS.contains(&first_segment) {
+        return None;
+    }
+
+    Some((platform, format!("{}://{}{}", url.scheme(), host, path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(links: &[&str], emails: &[&str]) -> ParsedPage {
+        ParsedPage {
+            title: None,
+            links: links.iter().map(|l| l.to_string()).collect(),
+            embedded_links: vec![],
+            emails: emails.iter().map(|e| e.to_string()).collect(),
+            generator: None,
+            robots_meta: vec![],
+            assets: vec![],
+        }
+    }
+
+    #[test]
+    fn test_find_contacts() {
+        let page = page(
+            &[
+                "https://www.github.com/example/",
+                "https://github.com/example?tab=repositories",
+                "https://mastodon.social/@example",
+                "https://example.com/about",
+            ],
+            &["info@example.com"],
+        );
+
+        assert_eq!(
+            find_contacts(&page),
+            vec![
+                ("email", "info@example.com".to_string()),
+                ("github", "https://www.github.com/example".to_string()),
+                ("github", "https://github.com/example".to_string()),
+                ("mastodon", "https://mastodon.social/@example".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_share_links_are_not_profiles() {
+        assert_eq!(
+            social_profile("https://twitter.com/intent/tweet?text=hi"),
+            None
+        );
+        assert_eq!(
+            social_profile("https://www.facebook.com/sharer.php?u=x"),
+            None
+        );
+        assert_eq!(social_profile("https://github.com/"), None);
+        assert_eq!(social_profile("https://notx.com/example"), None);
+        assert_eq!(
+            social_profile("https://x.com/example"),
+            Some(("twitter", "https://x.com/example".to_string()))
+        );
+    }
+}
@@ -8,27 +8,109 @@
 //! - Handling interrupts and resumption
 //! - Generating final output
 
-use crate::config::Config;
-use crate::crawler::parser::parse_html;
-use crate::crawler::scheduler::{QueuedUrl, Scheduler};
-use crate::crawler::{build_http_client, fetch_url, FetchResult};
-use crate::robots::{fetch_robots, is_allowed, ParsedRobots};
+use crate::config::{serialize_config, Config, ConfigOverrides};
+use crate::crawler::calendar::CrawlCalendar;
+use crate::crawler::eta::{format_eta, EtaEstimator};
+use crate::crawler::events::{CrawlEvent, CrawlEventStream};
+use crate::crawler::parser::{parse_html, sanitize_title};
+use crate::crawler::processor::{FetchedPage, PageProcessor};
+use crate::crawler::reload::{
+    apply_classification_overrides, merge_classification_lists, ConfigWatcher,
+    CONFIG_CHECK_INTERVAL,
+};
+use crate::crawler::scheduler::{inlink_priority, QueuedUrl, Scheduler};
+use crate::crawler::sitemap::fetch_sitemap_seeds;
+use crate::crawler::{
+    build_http_client, detect_anomalies, detect_technologies, detect_trap, find_contacts,
+    media_type, parse_text, template_hash, FetchOptions, FetchResult, Fetcher, HttpFetcher,
+    LocalPolicy, TrapKind,
+};
+use crate::output::WarcWriter;
+use crate::robots::{
+    fetch_robots, is_url_allowed, parse_directives, product_token, robots_path, ParsedRobots,
+    RobotsCache,
+};
 use crate::state::PageState;
-use crate::storage::{SqliteStorage, Storage};
+use crate::storage::{
+    ClassificationOverride, DirectiveSource, DomainPromotion, LinkKind, PageDirective, PageRecord,
+    ProgressSnapshot, RunStatus, SqliteStorage, Storage,
+};
 use crate::url::{
-    classify_domain, extract_domain, extract_domain_with_port, normalize_url, DomainClassification,
+    extract_domain, extract_domain_with_port, normalize_url_keeping_routes, Classifier,
+    DomainClassification, DomainClassifier,
 };
+use crate::ConfigError;
 use crate::SumiError;
+use chrono::{DateTime, Utc};
+use futures_core::Stream;
 use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
+/// How a coordinator treats the state left by earlier runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrawlMode {
+    /// Continue an interrupted or cancelled run, or start a new run without
+    /// seeding
+    Resume,
+
+    /// Clear the frontier and start a new run from the seeds
+    Fresh,
+
+    /// Continue an interrupted run, or start a new run from the seeds that
+    /// only refetches processed pages older than `recrawl-after-days`
+    Incremental,
+}
+
 /// Main crawler coordinator structure
 pub struct Coordinator {
+    /// Effective configuration (file lists plus database overrides)
     config: Arc<Config>,
+    /// Classifies discovered domains; by default the effective domain lists
+    classifier: Box<dyn Classifier>,
+    /// Whether `classifier` was supplied with `set_classifier`, rather than
+    /// compiled from the configuration
+    custom_classifier: bool,
+    /// Configuration as read from the file, without overrides
+    base_config: Arc<Config>,
+    /// Classification overrides currently applied
+    overrides: Vec<ClassificationOverride>,
+    /// When the overrides were last read from storage
+    overrides_checked: Instant,
     storage: Arc<Mutex<SqliteStorage>>,
     scheduler: Scheduler,
+    calendar: CrawlCalendar,
+    policy: LocalPolicy,
+    watcher: Option<ConfigWatcher>,
+    /// Whether quality domains still need seeding from their sitemaps
+    sitemaps_pending: bool,
+    /// When the last progress snapshot was recorded
+    progress_recorded: Option<Instant>,
+    /// Throughput and discovery tracking for the completion forecast
+    eta: EtaEstimator,
+    /// Incremental mode: how old a processed page may be before it is refetched
+    recrawl_after: Option<chrono::Duration>,
+    /// Incremental mode: pages fetched or reused during this run
+    handled: HashSet<i64>,
+    /// Incremental mode: number of pages whose stored links were reused
+    pages_reused: u64,
+    /// Discovered domains promoted to a deeper crawl, in this or earlier runs
+    promoted: HashSet<String>,
+    /// Receives crawl events when the crawl runs with `run_stream`
+    events: Option<UnboundedSender<CrawlEvent>>,
+    /// Run on every fetched page, in order
+    processors: Vec<Box<dyn PageProcessor>>,
+    /// Fetches pages; by default over HTTP with `client`
+    fetcher: Box<dyn Fetcher>,
+    /// Parsed robots.txt of the domains visited
+    robots: RobotsCache,
+    /// HTTP client for sitemaps
     client: Client,
     run_id: i64,
     user_agent: String,
@@ -47,42 +129,92 @@ impl Coordinator {
     /// * `Ok(Coordinator)` - Successfully created coordinator
     /// * `Err(SumiError)` - Failed to initialize
     pub fn new(config: Config, fresh: bool) -> Result<Self, SumiError> {
+        let mode = if fresh {
+            CrawlMode::Fresh
+        } else {
+            CrawlMode::Resume
+        };
+        Self::with_mode(config, mode)
+    }
+
+    /// Creates a new coordinator instance for the given crawl mode
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawler configuration
+    /// * `mode` - How to treat the state left by earlier runs
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Coordinator)` - Successfully created coordinator
+    /// * `Err(SumiError)` - Failed to initialize, or incremental mode without
+    ///   `recrawl-after-days`
+    pub fn with_mode(config: Config, mode: CrawlMode) -> Result<Self, SumiError> {
+        let recrawl_after = match mode {
+            CrawlMode::Incremental => {
+                let days = config.crawler.recrawl_after_days.ok_or_else(|| {
+                    ConfigError::Validation(
+                        "incremental crawls require crawler.recrawl-after-days".to_string(),
+                    )
+                })?;
+                Some(chrono::Duration::days(i64::from(days)))
+            }
+            CrawlMode::Resume | CrawlMode::Fresh => None,
+        };
+
         // Initialize storage
         let storage_path = Path::new(&config.output.database_path);
-        let mut storage = SqliteStorage::new(storage_path)?;
+        let mut storage = SqliteStorage::with_config(storage_path, &config.database)?;
 
-        // Create or resume run
-        let run_id = if fresh {
+        // Create or resume run; new runs record the configuration they use
+        let snapshot = serialize_config(&config)?;
+        let run_id = if mode == CrawlMode::Fresh {
             // Clear frontier and create new run
             storage.clear_frontier()?;
-            storage.create_run("config_hash_placeholder")?
+            start_run(&mut storage, &snapshot)?
         } else {
             // Check for interrupted run
             if let Some(latest_run) = storage.get_latest_run()? {
-                use crate::storage::RunStatus;
-                if matches!(latest_run.status, RunStatus::Running) {
+                if matches!(
+                    latest_run.status,
+                    RunStatus::Running | RunStatus::Interrupted
+                ) {
                     tracing::info!("Resuming interrupted run {}", latest_run.id);
+                    storage.update_run_status(latest_run.id, RunStatus::Running)?;
                     latest_run.id
                 } else {
                     tracing::info!("Starting new run");
-                    storage.create_run("config_hash_placeholder")?
+                    start_run(&mut storage, &snapshot)?
                 }
             } else {
                 tracing::info!("No previous runs found, starting new run");
-                storage.create_run("config_hash_placeholder")?
+                start_run(&mut storage, &snapshot)?
             }
         };
 
+        // Enforce the retention policy now that the current run is the newest
+        if config.retention.is_enabled() {
+            let report = storage.apply_retention(&config.retention)?;
+            tracing::info!(
+                "Retention: pruned {} runs, {} page observations and {} events",
+                report.runs_pruned,
+                report.observations_pruned,
+                report.events_pruned
+            );
+        }
+
         // Load frontier from storage or seed it
         let frontier_data = storage.load_frontier()?;
         let mut frontier = Vec::new();
+        let seeding = frontier_data.is_empty() && mode != CrawlMode::Resume;
 
-        if frontier_data.is_empty() && fresh {
+        if seeding {
             // Seed frontier with quality domain seeds
             tracing::info!("Seeding frontier with quality domain seeds");
             for quality_entry in &config.quality {
                 for seed_url in &quality_entry.seeds {
-                    let normalized = normalize_url(seed_url)?;
+                    let normalized =
+                        normalize_url_keeping_routes(seed_url, &config.crawler.hash_route_domains)?;
                     let domain = extract_domain(&normalized).ok_or_else(|| {
                         SumiError::Storage(format!("Failed to extract domain from {}", normalized))
                     })?;
@@ -121,6 +253,21 @@ impl Coordinator {
         // Load domain states
         let domain_states = storage.load_domain_states()?;
 
+        // Promotions outlast the run that granted them
+        let promoted = storage
+            .get_domain_promotions()?
+            .into_iter()
+            .map(|promotion| promotion.domain)
+            .collect();
+
+        // Classification overrides stored in the database take effect alongside the config
+        let overrides = storage.get_classification_overrides()?;
+        if !overrides.is_empty() {
+            tracing::info!("Applying {} classification overrides", overrides.len());
+        }
+        let base_config = Arc::new(config);
+        let config = apply_classification_overrides(&base_config, &overrides);
+
         // Build HTTP client
         let client = build_http_client(&config.user_agent)?;
 
@@ -136,14 +283,171 @@ impl Coordinator {
         // Create scheduler
         let scheduler = Scheduler::new(config.crawler.clone(), frontier, domain_states);
 
-        Ok(Self {
+        // Build crawl calendar (quiet hours)
+        let calendar = CrawlCalendar::from_config(&config.schedule)?;
+        let policy = LocalPolicy::from_config(&config.policy)?;
+
+        let mut coordinator = Self {
+            classifier: Box::new(DomainClassifier::new(&config)),
+            custom_classifier: false,
             config: Arc::new(config),
+            base_config,
+            overrides,
+            overrides_checked: Instant::now(),
             storage: Arc::new(Mutex::new(storage)),
             scheduler,
+            calendar,
+            policy,
+            watcher: None,
+            sitemaps_pending: seeding,
+            progress_recorded: None,
+            eta: EtaEstimator::new(),
+            recrawl_after,
+            handled: HashSet::new(),
+            pages_reused: 0,
+            promoted,
+            events: None,
+            processors: Vec::new(),
+            fetcher: Box::new(HttpFetcher::new(client.clone())),
+            robots: RobotsCache::default(),
             client,
             run_id,
             user_agent,
-        })
+        };
+
+        // Archive fetched pages before any user processor sees them
+        if let Some(path) = &coordinator.config.output.warc_path {
+            let writer = WarcWriter::create(Path::new(path), &coordinator.user_agent)?;
+            tracing::info!("Archiving fetched pages to {}", path);
+            coordinator.add_page_processor(Box::new(writer));
+        }
+
+        // A resumed frontier may hold URLs that are no longer crawlable
+        coordinator.reclassify_frontier()?;
+
+        Ok(coordinator)
+    }
+
+    /// The ID of the run this coordinator is crawling
+    pub fn run_id(&self) -> i64 {
+        self.run_id
+    }
+
+    /// Replaces the classification by the configured domain lists
+    ///
+    /// URLs already queued are classified again; those the classifier rejects
+    /// are removed from the frontier. The configured lists and their reloads
+    /// no longer affect classification.
+    ///
+    /// # Arguments
+    ///
+    /// * `classifier` - Decides how discovered domains are treated
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The classifier is in place
+    /// * `Err(SumiError)` - Failed to update rejected frontier URLs
+    pub fn set_classifier(
+        &mut self,
+        classifier: impl Classifier + 'static,
+    ) -> Result<(), SumiError> {
+        self.replace_classifier(Box::new(classifier))
+    }
+
+    /// Replaces the classifier, see `set_classifier`
+    pub(crate) fn replace_classifier(
+        &mut self,
+        classifier: Box<dyn Classifier>,
+    ) -> Result<(), SumiError> {
+        self.classifier = classifier;
+        self.custom_classifier = true;
+        self.reclassify_frontier()?;
+        Ok(())
+    }
+
+    /// Replaces how pages are fetched, see `CrawlerBuilder`
+    pub(crate) fn replace_fetcher(&mut self, fetcher: Box<dyn Fetcher>) {
+        self.fetcher = fetcher;
+    }
+
+    /// Adds a processor run on every fetched page, see `CrawlerBuilder`
+    pub(crate) fn add_page_processor(&mut self, processor: Box<dyn PageProcessor>) {
+        self.processors.push(processor);
+    }
+
+    /// Enables reloading of the classification lists while the crawl runs
+    ///
+    /// The configuration file is re-read when its modification time changes
+    /// (checked every `CONFIG_CHECK_INTERVAL`) or when the process receives
+    /// SIGHUP. Must be called from within a Tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_path` - Path to the configuration file the crawl was started with
+    /// * `overrides` - Environment and command-line overrides to re-apply on reload
+    pub fn watch_config(&mut self, config_path: &Path, overrides: ConfigOverrides) {
+        let watcher =
+            ConfigWatcher::new(config_path, CONFIG_CHECK_INTERVAL).with_overrides(overrides);
+        watcher.listen_for_sighup();
+        self.watcher = Some(watcher);
+    }
+
+    /// Runs the crawl in the background, reporting what happens as it happens
+    ///
+    /// Fetched pages, failed pages and discovered links are sent as
+    /// [`CrawlEvent`]s; the stream ends with a `Finished` event once the crawl
+    /// completes or fails. Dropping the stream does not stop the crawl. Must
+    /// be called from within a Tokio runtime.
+    ///
+    /// # Returns
+    ///
+    /// The stream of crawl events
+    pub fn run_stream(self) -> impl Stream<Item = CrawlEvent> {
+        self.run_stream_until_cancelled(CancellationToken::new())
+    }
+
+    /// Runs the crawl in the background until it completes or `cancel` is
+    /// cancelled, reporting what happens as it happens
+    ///
+    /// Like [`Coordinator::run_stream`]; a cancelled crawl is marked
+    /// interrupted as with [`Coordinator::run_until_cancelled`] and its
+    /// stream ends with a `Finished` event without an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - Stops the crawl when cancelled
+    ///
+    /// # Returns
+    ///
+    /// The stream of crawl events
+    pub fn run_stream_until_cancelled(
+        mut self,
+        cancel: CancellationToken,
+    ) -> impl Stream<Item = CrawlEvent> {
+        let (sender, stream) = CrawlEventStream::channel();
+        self.events = Some(sender.clone());
+
+        tokio::spawn(async move {
+            let error = self
+                .run_until_cancelled(cancel)
+                .await
+                .err()
+                .map(|e| e.to_string());
+            let _ = sender.send(CrawlEvent::Finished {
+                run_id: self.run_id,
+                error,
+            });
+        });
+
+        stream
+    }
+
+    /// Sends an event to the `run_stream` consumer, if there is one
+    fn emit(&self, event: CrawlEvent) {
+        if let Some(events) = &self.events {
+            // A dropped stream only means nobody is listening any more
+            let _ = events.send(event);
+        }
     }
 
     /// Runs the main crawl loop
@@ -155,14 +459,58 @@ impl Coordinator {
     /// 4. Classifies discovered URLs
     /// 5. Updates storage and frontier
     pub async fn run(&mut self) -> Result<(), SumiError> {
+        self.run_until_cancelled(CancellationToken::new()).await
+    }
+
+    /// Runs the main crawl loop until it completes or `cancel` is cancelled
+    ///
+    /// Cancellation is checked between pages and while waiting for the next
+    /// URL or crawl window, so the page being processed is finished first.
+    /// A cancelled run persists its state and is marked interrupted; the next
+    /// resumed crawl continues it.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - Stops the crawl when cancelled, e.g. from another task
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The crawl completed or was cancelled
+    /// * `Err(SumiError)` - The crawl failed
+    pub async fn run_until_cancelled(
+        &mut self,
+        cancel: CancellationToken,
+    ) -> Result<(), SumiError> {
         tracing::info!("Starting crawl run {}", self.run_id);
 
         let mut pages_crawled = 0;
         let start_time = std::time::Instant::now();
 
+        if std::mem::take(&mut self.sitemaps_pending) {
+            self.seed_from_sitemaps().await?;
+        }
+
+        self.record_progress()?;
+
         loop {
+            if cancel.is_cancelled() {
+                return self.interrupt(pages_crawled);
+            }
+
+            // Sleep through quiet hours before fetching anything else
+            if !self.wait_for_crawl_window(&cancel).await? {
+                return self.interrupt(pages_crawled);
+            }
+
+            // Pick up edits to the blacklist/stub/quality lists and overrides
+            self.refresh_classification()?;
+
             // Get next URL from scheduler
-            let scheduled = match self.scheduler.next_url().await {
+            let next = tokio::select! {
+                next = self.scheduler.next_url() => next,
+                _ = cancel.cancelled() => return self.interrupt(pages_crawled),
+            };
+            let scheduled = match next {
                 Some(s) => s,
                 None => {
                     tracing::info!("Frontier is empty, crawl complete");
@@ -174,21 +522,39 @@ impl Coordinator {
             tracing::debug!("Processing URL: {}", url.url);
 
             // Process this URL
+            let queued_before = self.scheduler.frontier_size();
             if let Err(e) = self.process_url(&url).await {
                 tracing::error!("Error processing {}: {}", url.url, e);
             }
+            let queued_after = self.scheduler.frontier_size();
+            self.eta
+                .record_page(queued_after.saturating_sub(queued_before) as u64);
 
             pages_crawled += 1;
+            self.record_progress_if_due()?;
+
+            let inlink_interval = self.config.crawler.inlink_priority_interval;
+            if inlink_interval > 0 && pages_crawled % inlink_interval == 0 {
+                self.reprioritize_frontier()?;
+            }
 
             // Progress reporting and periodic persistence every 10 pages
             if pages_crawled % 10 == 0 {
                 let elapsed = start_time.elapsed();
                 let rate = pages_crawled as f64 / elapsed.as_secs_f64();
+                let forecast = self.eta.forecast(&self.scheduler, &self.config.crawler);
+                let eta = match forecast.eta {
+                    Some(eta) if forecast.converging => format!("ETA {}", format_eta(eta)),
+                    Some(eta) => format!("ETA at least {} (frontier growing)", format_eta(eta)),
+                    None => "ETA pending".to_string(),
+                };
                 tracing::info!(
-                    "Progress: {} pages crawled, {} in frontier, {:.2} pages/sec",
+                    "Progress: {} pages crawled, {} in frontier ({} to fetch), {:.2} pages/sec, {}",
                     pages_crawled,
                     self.scheduler.frontier_size(),
-                    rate
+                    forecast.remaining,
+                    rate,
+                    eta
                 );
 
                 // Periodic domain state persistence every 50 pages
@@ -200,11 +566,13 @@ impl Coordinator {
 
         // Final domain state persistence
         self.save_domain_states()?;
+        self.record_progress()?;
 
         // Mark run as completed
         {
             let mut storage = self.storage.lock().unwrap();
             storage.complete_run(self.run_id)?;
+            storage.checkpoint()?;
         }
 
         tracing::info!(
@@ -212,14 +580,244 @@ impl Coordinator {
             pages_crawled,
             start_time.elapsed()
         );
+        if self.recrawl_after.is_some() {
+            tracing::info!(
+                "Incremental crawl: {} pages reused from earlier runs without refetching",
+                self.pages_reused
+            );
+        }
 
         Ok(())
     }
 
+    /// Adds the sitemap URLs of `seeds-from-sitemap` quality entries to the frontier
+    ///
+    /// Sitemap URLs are treated like configured seeds: depth 0 for their
+    /// quality domain and priority 0. URLs already seeded are skipped.
+    async fn seed_from_sitemaps(&mut self) -> Result<(), SumiError> {
+        let entries: Vec<_> = self
+            .base_config
+            .quality
+            .iter()
+            .filter(|entry| entry.seeds_from_sitemap)
+            .cloned()
+            .collect();
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let route_domains = &self.config.crawler.hash_route_domains;
+        let mut seeded: HashSet<String> = self
+            .base_config
+            .quality
+            .iter()
+            .flat_map(|entry| &entry.seeds)
+            .filter_map(|seed| normalize_url_keeping_routes(seed, route_domains).ok())
+            .map(|url| url.to_string())
+            .collect();
+
+        for entry in entries {
+            let urls = fetch_sitemap_seeds(&self.client, &entry).await;
+            let mut added = 0;
+
+            for url in urls {
+                let Ok(normalized) = normalize_url_keeping_routes(
+                    url.as_str(),
+                    &self.config.crawler.hash_route_domains,
+                ) else {
+                    continue;
+                };
+                let Some(domain) = extract_domain(&normalized) else {
+                    continue;
+                };
+
+                let page_id = {
+                    let mut storage = self.storage.lock().unwrap();
+                    let page_id =
+                        storage.insert_or_get_page(normalized.as_str(), &domain, self.run_id)?;
+                    storage.upsert_depth(page_id, &entry.domain, 0)?;
+                    page_id
+                };
+
+                if seeded.insert(normalized.to_string()) {
+                    self.storage.lock().unwrap().add_to_frontier(page_id, 0)?;
+                    self.scheduler.add_to_frontier(QueuedUrl {
+                        url: normalized,
+                        domain,
+                        priority: 0,
+                        page_id,
+                    });
+                    added += 1;
+                }
+            }
+
+            tracing::info!("Seeded {} URLs for {} from sitemaps", added, entry.domain);
+        }
+
+        Ok(())
+    }
+
+    /// Persists the state of a cancelled crawl and marks its run interrupted
+    fn interrupt(&mut self, pages_crawled: u64) -> Result<(), SumiError> {
+        self.save_domain_states()?;
+        self.record_progress()?;
+        {
+            let mut storage = self.storage.lock().unwrap();
+            storage.update_run_status(self.run_id, RunStatus::Interrupted)?;
+            storage.checkpoint()?;
+        }
+
+        tracing::info!(
+            "Crawl run {} cancelled after {} pages; resume to continue it",
+            self.run_id,
+            pages_crawled
+        );
+        Ok(())
+    }
+
+    /// Waits until the crawl calendar allows fetching
+    ///
+    /// Outside the configured windows, domain states are persisted and the
+    /// coordinator sleeps until the next window opens. Returns immediately
+    /// if no windows are configured or a window is currently open.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Fetching is allowed
+    /// * `Ok(false)` - The crawl was cancelled while waiting
+    /// * `Err(SumiError)` - Failed to persist domain states
+    async fn wait_for_crawl_window(
+        &mut self,
+        cancel: &CancellationToken,
+    ) -> Result<bool, SumiError> {
+        let now = chrono::Utc::now();
+        let (wait, next_open) = match (
+            self.calendar.time_until_open(now),
+            self.calendar.next_open(now),
+        ) {
+            (Some(wait), Some(next_open)) => (wait, next_open),
+            _ => return Ok(true),
+        };
+
+        // Persist state so an interrupted sleep loses nothing
+        self.save_domain_states()?;
+
+        tracing::info!(
+            "Outside crawl window, sleeping until {} ({}s)",
+            next_open.format("%Y-%m-%d %H:%M UTC"),
+            wait.as_secs()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => Ok(true),
+            _ = cancel.cancelled() => Ok(false),
+        }
+    }
+
+    /// Applies classification changes made while the crawl is running
+    ///
+    /// Picks up edits to the configuration file's domain lists (if watched)
+    /// and classification overrides added to the database, then drops queued
+    /// URLs that may no longer be crawled. Invalid configuration files are
+    /// logged and ignored, leaving the current lists in place.
+    fn refresh_classification(&mut self) -> Result<(), SumiError> {
+        let mut changed = false;
+
+        match self.watcher.as_mut().and_then(|w| w.poll()) {
+            Some(Ok(reloaded)) => {
+                self.base_config =
+                    Arc::new(merge_classification_lists(&self.base_config, reloaded));
+                tracing::info!(
+                    "Reloaded classification lists (quality: {}, blacklist: {}, stub: {})",
+                    self.base_config.quality.len(),
+                    self.base_config.blacklist.len(),
+                    self.base_config.stub.len()
+                );
+                changed = true;
+            }
+            Some(Err(e)) => tracing::warn!("Ignoring configuration reload: {}", e),
+            None => {}
+        }
+
+        if self.overrides_checked.elapsed() >= CONFIG_CHECK_INTERVAL {
+            self.overrides_checked = Instant::now();
+            let overrides = self
+                .storage
+                .lock()
+                .unwrap()
+                .get_classification_overrides()?;
+            if overrides != self.overrides {
+                tracing::info!("Applying {} classification overrides", overrides.len());
+                self.overrides = overrides;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return Ok(());
+        }
+
+        self.config = Arc::new(apply_classification_overrides(
+            &self.base_config,
+            &self.overrides,
+        ));
+        if !self.custom_classifier {
+            self.classifier = Box::new(DomainClassifier::new(&self.config));
+        }
+
+        let dropped = self.reclassify_frontier()?;
+        if dropped > 0 {
+            tracing::info!(
+                "Dropped {} queued URLs on newly blacklisted or stubbed domains",
+                dropped
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Re-classifies queued URLs, removing those that may no longer be crawled
+    ///
+    /// # Returns
+    ///
+    /// The number of URLs removed from the frontier
+    fn reclassify_frontier(&mut self) -> Result<usize, SumiError> {
+        let classifier = &self.classifier;
+        let mut discovered = HashMap::new();
+        let removed = self.scheduler.retain_frontier(|queued| {
+            let classification = classifier.classify(&queued.domain);
+            discovered.insert(
+                queued.domain.clone(),
+                classification == DomainClassification::Discovered,
+            );
+            classification.should_crawl()
+        });
+
+        // Queued domains keep the request budget of their classification
+        for (domain, discovered) in discovered {
+            let discovered = discovered && !self.is_promoted(&domain);
+            self.scheduler.set_discovered(&domain, discovered);
+        }
+
+        let mut storage = self.storage.lock().unwrap();
+        for queued in &removed {
+            let (state, reason) = match classifier.classify(&queued.domain) {
+                DomainClassification::Blacklisted => {
+                    (PageState::Blacklisted, "Domain blacklisted during crawl")
+                }
+                _ => (PageState::Stubbed, "Domain stubbed during crawl"),
+            };
+            storage.remove_from_frontier(queued.page_id)?;
+            storage.update_page_state(queued.page_id, state, None, None, None, Some(reason))?;
+        }
+
+        Ok(removed.len())
+    }
+
     /// Processes a single URL
     ///
     /// This method:
-    /// 1. Checks robots.txt
+    /// 1. Checks the local policy and robots.txt (including the Visit-time
+    ///    window, if honored)
     /// 2. Fetches the page
     /// 3. Parses HTML and extracts links
     /// 4. Classifies discovered URLs
@@ -228,32 +826,136 @@ impl Coordinator {
         let url_str = queued.url.as_str();
         let page_id = queued.page_id;
 
-        // Record that we're starting to request this domain
-        self.scheduler.record_request(&queued.domain);
+        // Incremental crawls handle each page once
+        if self.recrawl_after.is_some() && !self.handled.insert(page_id) {
+            return Ok(());
+        }
 
-        // Update page state to Fetching
-        {
+        let page = {
+            let storage = self.storage.lock().unwrap();
+            storage.get_page(page_id)?
+        };
+
+        // Pages that keep failing are given up on once their retries, counted
+        // over all runs, are used up
+        if page.state.is_error() && page.retry_count >= self.config.crawler.max_page_retries {
+            tracing::debug!(
+                "Not retrying {} after {} retries",
+                url_str,
+                page.retry_count
+            );
             let mut storage = self.storage.lock().unwrap();
-            storage.update_page_state(page_id, PageState::Fetching, None, None, None, None)?;
+            storage.remove_from_frontier(page_id)?;
+            return Ok(());
         }
 
-        // Check robots.txt - use domain with port for proper fetching
+        // Incremental crawls reuse the links of recently processed pages
+        // instead of fetching them again
+        if let Some(max_age) = self.recrawl_after {
+            if page.state == PageState::Processed
+                && !needs_refetch(page.visited_at.as_deref(), max_age, Utc::now())
+            {
+                tracing::debug!("Reusing stored links of {}", url_str);
+                self.pages_reused += 1;
+                self.storage.lock().unwrap().remove_from_frontier(page_id)?;
+                return self.reuse_stored_links(page_id);
+            }
+        }
+
+        // The operator's own rules apply before anything is requested
+        if let Some(rule) = self.policy.denying_rule(url_str) {
+            tracing::info!("URL {} denied by local policy rule {}", url_str, rule);
+            let reason = format!("Denied by local policy: {}", rule);
+            let mut storage = self.storage.lock().unwrap();
+            storage.update_page_state(
+                page_id,
+                PageState::PolicyDenied,
+                None,
+                None,
+                None,
+                Some(&reason),
+            )?;
+            storage.record_page_observation(page_id, self.run_id, None)?;
+            storage.remove_from_frontier(page_id)?;
+            self.emit_page_failed(queued, PageState::PolicyDenied, &reason);
+            return Ok(());
+        }
+
+        // Check robots.txt - use domain with port for proper fetching. This
+        // comes before the request is counted, so a URL re-queued or refused
+        // here leaves the domain's budget and the page untouched
         let domain_with_port =
             extract_domain_with_port(&queued.url).unwrap_or_else(|| queued.domain.clone());
-        let robots = self.get_or_fetch_robots(&domain_with_port).await?;
+        let robots = match self.get_or_fetch_robots(&domain_with_port).await {
+            Ok(robots) => {
+                self.scheduler.record_robots_fetched(&queued.domain);
+                robots
+            }
+            Err(SumiError::RobotsUnavailable {
+                status,
+                retry_after,
+                ..
+            }) => {
+                let error = format!("robots.txt unavailable (HTTP {})", status);
+                match self
+                    .scheduler
+                    .record_robots_unavailable(&queued.domain, &error, retry_after)
+                {
+                    // Wait for the server, then fetch robots.txt again
+                    Some(wait) => {
+                        tracing::warn!(
+                            "{} for {}, retrying in {}s",
+                            error,
+                            domain_with_port,
+                            wait.as_secs()
+                        );
+                        {
+                            let mut storage = self.storage.lock().unwrap();
+                            storage.update_page_state(
+                                page_id,
+                                PageState::Queued,
+                                None,
+                                None,
+                                None,
+                                None,
+                            )?;
+                        }
+                        self.handled.remove(&page_id);
+                        self.scheduler.add_to_frontier(queued.clone());
+                        return Ok(());
+                    }
+                    // Give up, disallowing the domain until the file expires
+                    None => {
+                        tracing::warn!(
+                            "{} for {}, treating the domain as disallowed",
+                            error,
+                            domain_with_port
+                        );
+                        let now = self.scheduler.clock().utc_now();
+                        self.robots.insert(
+                            &domain_with_port,
+                            ParsedRobots::unavailable(&error),
+                            now,
+                        )
+                    }
+                }
+            }
+            Err(e) => return Err(e),
+        };
 
         // Check if URL is allowed by robots.txt
-        // Extract just the path from the URL for robots.txt checking
-        let url_path = queued.url.path();
         tracing::debug!(
             "Checking robots.txt for path '{}' with user agent '{}'",
-            url_path,
+            robots_path(&queued.url),
             self.user_agent
         );
-        let allowed = is_allowed(&robots, url_path, &self.user_agent);
+        let allowed = is_url_allowed(&robots, &queued.url, &self.user_agent);
         tracing::debug!("Robots.txt check result: allowed={}", allowed);
         if !allowed {
             tracing::info!("URL {} disallowed by robots.txt", url_str);
+            let reason = robots
+                .unavailable_reason()
+                .unwrap_or("Disallowed by robots.txt");
             let mut storage = self.storage.lock().unwrap();
             storage.update_page_state(
                 page_id,
@@ -261,28 +963,99 @@ impl Coordinator {
                 None,
                 None,
                 None,
-                Some("Disallowed by robots.txt"),
+                Some(reason),
             )?;
+            storage.record_page_observation(page_id, self.run_id, None)?;
+            storage.remove_from_frontier(page_id)?;
+            self.emit_page_failed(queued, PageState::Failed, reason);
             return Ok(());
         }
 
-        // Fetch the page
-        let fetch_result = fetch_url(&self.client, url_str).await;
+        // Defer the URL if the domain asks to be visited at another time of
+        // day, before anything is counted against the domain or the page
+        if self.config.crawler.honor_visit_time {
+            let window = robots.visit_time(&self.user_agent);
+            self.scheduler.set_visit_window(&queued.domain, window);
+
+            let now = self.scheduler.clock().utc_now().time();
+            if let Some(wait) = window.and_then(|w| w.time_until_open(now)) {
+                tracing::info!(
+                    "Deferring {} for {}s until robots.txt Visit-time window opens",
+                    url_str,
+                    wait.as_secs()
+                );
+                self.handled.remove(&page_id);
+                self.scheduler.add_to_frontier(queued.clone());
+                return Ok(());
+            }
+        }
+
+        // Record that we're starting to request this domain
+        self.scheduler.record_request(&queued.domain);
+
+        // Update page state to Fetching
+        {
+            let mut storage = self.storage.lock().unwrap();
+            storage.update_page_state(page_id, PageState::Fetching, None, None, None, None)?;
+            storage.mark_page_visited(page_id, self.run_id)?;
+        }
+
+        // Fetch the page, with a HEAD request first only if enabled and the domain handles it
+        let mut options = FetchOptions::for_domain(&self.config, &queued.domain);
+        options.send_head &= !self.scheduler.skip_head(&queued.domain);
+        let outcome = self.fetcher.fetch(url_str, &options).await;
+        self.scheduler
+            .record_head_outcome(&queued.domain, outcome.head);
+
+        // Hold back the domain's URLs for a while if it keeps failing
+        if self
+            .scheduler
+            .record_fetch_result(&queued.domain, outcome.result.is_server_failure())
+        {
+            tracing::warn!(
+                "Pausing {} for {}s after {} failures in a row",
+                queued.domain,
+                self.config.crawler.circuit_breaker_cooldown_secs,
+                self.config.crawler.circuit_breaker_threshold
+            );
+        }
+
+        // Fetching a page that failed before counts as a retry too
+        let retries = outcome.retries + u32::from(page.state.is_error());
+        if retries > 0 {
+            let mut storage = self.storage.lock().unwrap();
+            storage.increment_retry_count(page_id, retries)?;
+        }
+
+        // Where the page was served from, kept with what this run saw of it
+        let served_from = match &outcome.result {
+            FetchResult::Success { final_url, .. } => Some(final_url.clone()),
+            FetchResult::RedirectToTerminal { terminal_url, .. } => Some(terminal_url.clone()),
+            _ => None,
+        };
 
         // Handle fetch result
-        match fetch_result {
+        match outcome.result {
             FetchResult::Success {
                 final_url,
                 status_code,
                 content_type,
                 body,
                 title: _,
+                headers,
             } => {
-                // Parse HTML and extract links
-                let parsed = match parse_html(&body, &queued.url) {
+                // Parse HTML and extract links (plain text, if accepted, is
+                // scanned for URLs)
+                let parsed = if media_type(&content_type) == "text/plain" {
+                    Ok(parse_text(&body, &queued.url))
+                } else {
+                    parse_html(&body, &queued.url)
+                };
+                let parsed = match parsed {
                     Ok(p) => p,
                     Err(e) => {
                         tracing::warn!("Failed to parse HTML for {}: {}", url_str, e);
+                        let error = format!("Parse error: {}", e);
                         let mut storage = self.storage.lock().unwrap();
                         storage.update_page_state(
                             page_id,
@@ -290,31 +1063,174 @@ impl Coordinator {
                             None,
                             Some(status_code),
                             Some(&content_type),
-                            Some(&format!("Parse error: {}", e)),
+                            Some(&error),
+                        )?;
+                        storage.record_page_observation(
+                            page_id,
+                            self.run_id,
+                            served_from.as_deref(),
                         )?;
+                        storage.remove_from_frontier(page_id)?;
+                        self.emit_page_failed(queued, PageState::Failed, &error);
                         return Ok(());
                     }
                 };
 
-                // Update page state to Processed
+                // Update page state to Processed and fingerprint the body
+                let max_links = self.config.crawler.max_links_per_page;
+                let trap = detect_trap(&queued.url, parsed.title.as_deref());
+                let mut follow_links = true;
                 {
+                    let content_hash = hex::encode(Sha256::digest(body.as_bytes()));
+                    let title = parsed.title.as_deref().and_then(|title| {
+                        sanitize_title(title, self.config.crawler.max_title_length)
+                    });
                     let mut storage = self.storage.lock().unwrap();
                     storage.update_page_state(
                         page_id,
                         PageState::Processed,
-                        parsed.title.as_deref(),
+                        title.as_deref(),
                         Some(status_code),
                         Some(&content_type),
                         None,
                     )?;
+                    storage.set_content_hash(page_id, &content_hash)?;
+
+                    // Soft 404s and interstitials share a body once the
+                    // requested URL is blanked out
+                    let template = template_hash(&body, &[url_str, &final_url]);
+                    storage.set_template_hash(page_id, &template)?;
+                    let min_pages = self.config.crawler.error_template_min_pages;
+                    if min_pages > 0
+                        && storage.flag_error_template(&queued.domain, &template, min_pages)?
+                    {
+                        tracing::debug!(
+                            "{} shares an error page template, its links are left out of the graph",
+                            url_str
+                        );
+                    }
+                    storage.set_page_headers(page_id, &headers)?;
+
+                    let technologies = detect_technologies(&parsed, &headers);
+                    if !technologies.is_empty() {
+                        storage.record_technologies(&queued.domain, &technologies)?;
+                    }
+
+                    let anomalies = detect_anomalies(body.len(), parsed.links.len(), max_links);
+                    storage.set_page_anomalies(page_id, &anomalies)?;
+
+                    // Directory listings and calendars can go on forever, so
+                    // only the first ones of a domain are followed
+                    if let Some(trap) = trap {
+                        storage.add_page_tag(page_id, trap.tag())?;
+                        let limit = self.config.crawler.trap_page_limit;
+                        follow_links = limit == 0
+                            || storage.count_domain_pages_with_tag(&queued.domain, trap.tag())?
+                                <= u64::from(limit);
+                    }
+
+                    // Directives a page states for itself are recorded, not acted on
+                    let token = product_token(&self.user_agent);
+                    let mut directives: Vec<PageDirective> = parsed
+                        .robots_meta
+                        .iter()
+                        .flat_map(|content| parse_directives(content, DirectiveSource::Meta, token))
+                        .collect();
+                    for (_, value) in headers.iter().filter(|(name, _)| name == "x-robots-tag") {
+                        directives.extend(parse_directives(value, DirectiveSource::Header, token));
+                    }
+                    storage.set_page_directives(page_id, &directives)?;
+
+                    if self.config.crawler.harvest_contacts
+                        && self.classifier.classify(&queued.domain) == DomainClassification::Quality
+                    {
+                        storage.set_page_contacts(page_id, &find_contacts(&parsed))?;
+                    }
+
+                    if self.events.is_some() {
+                        self.emit(CrawlEvent::PageFetched {
+                            page_id,
+                            url: url_str.to_string(),
+                            final_url: final_url.clone(),
+                            status_code,
+                            content_type: content_type.clone(),
+                            title,
+                            body: body.clone(),
+                        });
+                    }
+                }
+
+                // Hand the page to the plugins before the body is dropped
+                if !self.processors.is_empty() {
+                    let fetched = FetchedPage {
+                        page_id,
+                        url: &queued.url,
+                        final_url: &final_url,
+                        domain: &queued.domain,
+                        classification: self.classifier.classify(&queued.domain),
+                        status_code,
+                        content_type: &content_type,
+                        headers: &headers,
+                        body: &body,
+                        parsed: &parsed,
+                    };
+                    for processor in &self.processors {
+                        if let Err(e) = processor.process(&fetched) {
+                            tracing::warn!("Page processor failed for {}: {}", url_str, e);
+                        }
+                    }
                 }
 
+                // Follow only the first links of pages with very many, so a
+                // single directory page cannot flood the frontier
+                let links = if !follow_links {
+                    tracing::info!(
+                        "Not following the links of {}: {} has more than {} {} pages",
+                        url_str,
+                        queued.domain,
+                        self.config.crawler.trap_page_limit,
+                        trap.map_or("", TrapKind::tag)
+                    );
+                    &[]
+                } else if max_links > 0 && parsed.links.len() > max_links {
+                    tracing::info!(
+                        "Following {} of {} links on {}",
+                        max_links,
+                        parsed.links.len(),
+                        url_str
+                    );
+                    &parsed.links[..max_links]
+                } else {
+                    &parsed.links[..]
+                };
+
+                // Iframes, image maps and meta refreshes are followed
+                // along with the anchors when enabled
+                let embedded_links = &self.config.crawler.embedded_links;
+                let links: Vec<(LinkKind, &str)> = links
+                    .iter()
+                    .map(|link| (LinkKind::Anchor, link.as_str()))
+                    .chain(
+                        parsed
+                            .embedded_links
+                            .iter()
+                            .filter(|(kind, _)| {
+                                follow_links
+                                    && embedded_links
+                                        .iter()
+                                        .any(|source| LinkKind::from(*source) == *kind)
+                            })
+                            .map(|(kind, link)| (*kind, link.as_str())),
+                    )
+                    .collect();
+
                 // Handle discovered links
-                self.handle_discovered_links(page_id, &parsed.links, &final_url)
+                self.handle_discovered_links(page_id, &links, &final_url)
                     .await?;
             }
 
             FetchResult::ContentMismatch { content_type } => {
+                let error = format!("Expected HTML, got {}", content_type);
                 let mut storage = self.storage.lock().unwrap();
                 storage.update_page_state(
                     page_id,
@@ -322,14 +1238,16 @@ impl Coordinator {
                     None,
                     None,
                     Some(&content_type),
-                    Some(&format!("Expected HTML, got {}", content_type)),
+                    Some(&error),
                 )?;
+                self.emit_page_failed(queued, PageState::ContentMismatch, &error);
             }
 
             FetchResult::RedirectToTerminal {
                 terminal_url,
                 reason,
             } => {
+                let error = format!("Redirect to {}: {}", terminal_url, reason);
                 let mut storage = self.storage.lock().unwrap();
                 storage.update_page_state(
                     page_id,
@@ -337,33 +1255,73 @@ impl Coordinator {
                     None,
                     None,
                     None,
-                    Some(&format!("Redirect to {}: {}", terminal_url, reason)),
+                    Some(&error),
                 )?;
+                self.emit_page_failed(queued, PageState::Failed, &error);
             }
 
             FetchResult::HttpError { status_code, state } => {
-                let mut storage = self.storage.lock().unwrap();
-                storage.update_page_state(
-                    page_id,
-                    state,
-                    None,
-                    Some(status_code),
-                    None,
-                    Some(&format!("HTTP {}", status_code)),
-                )?;
+                let error = format!("HTTP {}", status_code);
 
-                // If rate limited, mark the domain
+                // If rate limited, slow the domain down and try the page again
+                // once the longer delay has passed, while retries remain
+                let requeue = status_code == 429
+                    && page.retry_count + retries < self.config.crawler.max_page_retries;
                 if status_code == 429 {
-                    self.scheduler.mark_rate_limited(&queued.domain);
+                    let multiplier = self.scheduler.mark_rate_limited(&queued.domain);
+                    tracing::warn!(
+                        "{} answered HTTP 429; its delay is now {}x per-domain-delay-ms",
+                        queued.domain,
+                        multiplier
+                    );
+                }
+
+                if requeue {
+                    tracing::debug!("Re-queueing rate limited {}", url_str);
+                    {
+                        let mut storage = self.storage.lock().unwrap();
+                        // The page is queued rather than in an error state, so
+                        // the next fetch is counted as a retry here
+                        storage.increment_retry_count(page_id, 1)?;
+                        storage.update_page_state(
+                            page_id,
+                            PageState::Queued,
+                            None,
+                            Some(status_code),
+                            None,
+                            Some(&error),
+                        )?;
+                    }
+                    self.handled.remove(&page_id);
+                    self.scheduler.add_to_frontier(queued.clone());
+                    return Ok(());
+                } else {
+                    let mut storage = self.storage.lock().unwrap();
+                    storage.update_page_state(
+                        page_id,
+                        state,
+                        None,
+                        Some(status_code),
+                        None,
+                        Some(&error),
+                    )?;
+                    self.emit_page_failed(queued, state, &error);
                 }
             }
 
             FetchResult::NetworkError { error, state } => {
                 let mut storage = self.storage.lock().unwrap();
                 storage.update_page_state(page_id, state, None, None, None, Some(&error))?;
+                self.emit_page_failed(queued, state, &error);
             }
 
-            FetchResult::RedirectError { error } => {
+            FetchResult::RedirectError { error, chain } => {
+                // Keep the URLs followed so reports show the loop itself
+                let error = if chain.len() > 1 {
+                    format!("{}: {}", error, chain.join(" -> "))
+                } else {
+                    error
+                };
                 let mut storage = self.storage.lock().unwrap();
                 storage.update_page_state(
                     page_id,
@@ -373,28 +1331,64 @@ impl Coordinator {
                     None,
                     Some(&error),
                 )?;
+                self.emit_page_failed(queued, PageState::Failed, &error);
             }
         }
 
+        // The page is done with, so it leaves the persisted frontier too
+        let mut storage = self.storage.lock().unwrap();
+        storage.record_page_observation(page_id, self.run_id, served_from.as_deref())?;
+        storage.remove_from_frontier(page_id)?;
+
         Ok(())
     }
 
+    /// Reports a page that could not be fetched or parsed to the event stream
+    fn emit_page_failed(&self, queued: &QueuedUrl, state: PageState, error: &str) {
+        self.emit(CrawlEvent::PageFailed {
+            page_id: queued.page_id,
+            url: queued.url.to_string(),
+            state,
+            error: error.to_string(),
+        });
+    }
+
     /// Handles discovered links from a page
     ///
     /// This method:
     /// 1. Normalizes URLs
     /// 2. Classifies domains
-    /// 3. Records links in storage
+    /// 3. Records links in storage, with the element each was found in
     /// 4. Adds crawlable URLs to frontier
     async fn handle_discovered_links(
         &mut self,
         from_page_id: i64,
-        links: &[String],
+        links: &[(LinkKind, &str)],
         base_url: &str,
     ) -> Result<(), SumiError> {
-        for link in links {
+        let route_domains = self.config.crawler.hash_route_domains.clone();
+        let page_url = normalize_url_keeping_routes(base_url, &route_domains).ok();
+        let skip_self_links = self.config.crawler.skip_self_links;
+        let skip_same_path_links = self.config.crawler.skip_same_path_links;
+
+        // Discovered domains linked from quality pages may earn a promotion
+        let from_quality = self.config.promotion.enabled
+            && page_url
+                .as_ref()
+                .and_then(extract_domain)
+                .is_some_and(|domain| {
+                    self.classifier.classify(&domain) == DomainClassification::Quality
+                });
+        let mut promotion_candidates = BTreeSet::new();
+
+        let from_domain = {
+            let storage = self.storage.lock().unwrap();
+            storage.get_page(from_page_id)?.domain
+        };
+
+        for &(kind, link) in links {
             // Normalize URL
-            let normalized = match normalize_url(link) {
+            let normalized = match normalize_url_keeping_routes(link, &route_domains) {
                 Ok(n) => n,
                 Err(e) => {
                     tracing::debug!("Failed to normalize URL {}: {}", link, e);
@@ -402,6 +1396,15 @@ impl Coordinator {
                 }
             };
 
+            // Links back to the page itself would only add self-edges
+            if let Some(page_url) = &page_url {
+                if (skip_self_links && normalized == *page_url)
+                    || (skip_same_path_links && same_path(&normalized, page_url))
+                {
+                    continue;
+                }
+            }
+
             // Extract domain
             let domain = match extract_domain(&normalized) {
                 Some(d) => d,
@@ -412,7 +1415,7 @@ impl Coordinator {
             };
 
             // Classify domain
-            let classification = classify_domain(&domain, &self.config);
+            let classification = self.classifier.classify(&domain);
 
             // Convert Url to string for storage operations
             let normalized_str = normalized.as_str();
@@ -440,90 +1443,367 @@ impl Coordinator {
                         storage.insert_or_get_page(normalized_str, &domain, self.run_id)?
                     };
 
+                    // A redirected page may link to the URL it was requested as
+                    if skip_self_links && to_page_id == from_page_id {
+                        continue;
+                    }
+
                     // Record link
                     {
                         let mut storage = self.storage.lock().unwrap();
-                        storage.insert_link(from_page_id, to_page_id, self.run_id)?;
+                        storage.insert_link_of_kind(from_page_id, to_page_id, self.run_id, kind)?;
                     }
 
-                    // Calculate depth and check if we should crawl
-                    let should_add_to_frontier = {
-                        let mut storage = self.storage.lock().unwrap();
+                    if from_quality
+                        && classification == DomainClassification::Discovered
+                        && !self.promoted.contains(&domain)
+                    {
+                        promotion_candidates.insert(domain.clone());
+                    }
 
-                        // Get depths of source page
-                        let source_depths = storage.get_depths(from_page_id)?;
+                    self.emit(CrawlEvent::LinkDiscovered {
+                        from_page_id,
+                        to_page_id,
+                        url: normalized_str.to_string(),
+                        domain: domain.clone(),
+                        classification,
+                        kind,
+                    });
 
-                        // Calculate new depths for target page
-                        for depth_record in source_depths {
-                            let new_depth = depth_record.depth + 1;
-                            storage.upsert_depth(
-                                to_page_id,
-                                &depth_record.quality_origin,
-                                new_depth,
-                            )?;
-                        }
+                    self.follow_link(
+                        from_page_id,
+                        &from_domain,
+                        to_page_id,
+                        &normalized,
+                        &domain,
+                        classification,
+                    )?;
+                }
+            }
+        }
 
-                        // Check if we should crawl this page
-                        storage.should_crawl(to_page_id, self.config.crawler.max_depth)?
-                    };
+        for domain in promotion_candidates {
+            self.consider_promotion(&domain)?;
+        }
 
-                    // Add to frontier if within depth limits and not already visited
-                    if should_add_to_frontier {
-                        let page = {
-                            let storage = self.storage.lock().unwrap();
-                            storage.get_page(to_page_id)?
-                        };
-
-                        // Only add if page is in Discovered state
-                        if page.state == PageState::Discovered {
-                            // Calculate priority based on classification
-                            let priority = match classification {
-                                DomainClassification::Quality => 0,
-                                DomainClassification::Discovered => 10,
-                                _ => 100,
-                            };
-
-                            // Add to storage frontier
-                            {
-                                let mut storage = self.storage.lock().unwrap();
-                                storage.add_to_frontier(to_page_id, priority)?;
-                            }
-
-                            // Add to scheduler frontier
-                            self.scheduler.add_to_frontier(QueuedUrl {
-                                url: normalized.clone(),
-                                domain: domain.clone(),
-                                priority,
-                                page_id: to_page_id,
-                            });
-                        }
-                    } else {
-                        // Page is beyond max_depth, mark as DepthExceeded
-                        let page = {
-                            let storage = self.storage.lock().unwrap();
-                            storage.get_page(to_page_id)?
-                        };
-
-                        // Only mark as DepthExceeded if it's still in Discovered state
-                        if page.state == PageState::Discovered {
-                            let mut storage = self.storage.lock().unwrap();
-                            storage.update_page_state(
-                                to_page_id,
-                                PageState::DepthExceeded,
-                                None,
-                                None,
-                                None,
-                                Some("Exceeds max depth limit"),
-                            )?;
-                        }
-                    }
+        Ok(())
+    }
+
+    /// Promotes a discovered domain once enough quality pages link to it
+    ///
+    /// The promotion is recorded in the database. Pages of the domain that
+    /// exceeded `max-depth` but are within the deeper limit are queued again.
+    fn consider_promotion(&mut self, domain: &str) -> Result<(), SumiError> {
+        let policy = self.config.promotion.clone();
+        if self.promoted.len() >= policy.max_domains as usize {
+            return Ok(());
+        }
+
+        let referring_domains = {
+            let storage = self.storage.lock().unwrap();
+            storage.get_referring_domains(domain)?
+        };
+        let quality_referrers: u64 = referring_domains
+            .iter()
+            .filter(|(source, _)| self.classifier.classify(source) == DomainClassification::Quality)
+            .map(|(_, pages)| pages)
+            .sum();
+        if quality_referrers < policy.min_quality_referrers {
+            return Ok(());
+        }
+
+        {
+            let mut storage = self.storage.lock().unwrap();
+            storage.record_domain_promotion(&DomainPromotion {
+                domain: domain.to_string(),
+                run_id: self.run_id,
+                quality_referrers,
+                extra_depth: policy.extra_depth,
+                promoted_at: Utc::now().to_rfc3339(),
+            })?;
+        }
+        self.promoted.insert(domain.to_string());
+        self.scheduler.set_discovered(domain, false);
+        tracing::info!(
+            "Promoted {} to a deeper crawl: linked from {} quality pages",
+            domain,
+            quality_referrers
+        );
+
+        // Pages cut off by max-depth may be within reach now
+        let max_depth = self.max_depth(domain);
+        let max_hops = self.config.crawler.max_cross_domain_hops;
+        let pages = {
+            let storage = self.storage.lock().unwrap();
+            storage.get_domain_pages_by_state(domain, PageState::DepthExceeded)?
+        };
+        for page in pages {
+            let priority = self.scheduler.effective_priority(domain, 10);
+            {
+                let mut storage = self.storage.lock().unwrap();
+                if !storage.should_crawl(page.id, max_depth, max_hops)? {
+                    continue;
                 }
+                storage.update_page_state(
+                    page.id,
+                    PageState::Discovered,
+                    None,
+                    None,
+                    None,
+                    None,
+                )?;
+                storage.add_to_frontier(page.id, priority)?;
+            }
+            self.scheduler.add_to_frontier(QueuedUrl {
+                url: Url::parse(&page.url)?,
+                domain: page.domain,
+                priority,
+                page_id: page.id,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether a discovered domain was promoted to the crawl budget of quality domains
+    fn is_promoted(&self, domain: &str) -> bool {
+        self.config.promotion.enabled && self.promoted.contains(domain)
+    }
+
+    /// The depth limit for the pages of a domain, deeper for promoted domains
+    fn max_depth(&self, domain: &str) -> u32 {
+        let max_depth = self.config.crawler.max_depth;
+        if self.is_promoted(domain) {
+            max_depth + self.config.promotion.extra_depth
+        } else {
+            max_depth
+        }
+    }
+
+    /// Propagates depths along a link and queues its target if it should be crawled
+    ///
+    /// # Arguments
+    ///
+    /// * `from_page_id` - The page containing the link
+    /// * `from_domain` - The domain of the page containing the link
+    /// * `to_page_id` - The linked page
+    /// * `url` - The linked page's normalized URL
+    /// * `domain` - The linked page's domain
+    /// * `classification` - Classification of the linked domain
+    fn follow_link(
+        &mut self,
+        from_page_id: i64,
+        from_domain: &str,
+        to_page_id: i64,
+        url: &Url,
+        domain: &str,
+        classification: DomainClassification,
+    ) -> Result<(), SumiError> {
+        // Calculate depth and check if we should crawl
+        let should_add_to_frontier = {
+            let mut storage = self.storage.lock().unwrap();
+
+            // Get depths of source page
+            let source_depths = storage.get_depths(from_page_id)?;
+
+            // Calculate new depths for target page; a link to another domain
+            // also crosses a domain boundary
+            let crossing = u32::from(from_domain != domain);
+            for depth_record in source_depths {
+                let new_depth = depth_record.depth + 1;
+                storage.upsert_depth_with_hops(
+                    to_page_id,
+                    &depth_record.quality_origin,
+                    new_depth,
+                    depth_record.hops + crossing,
+                )?;
+            }
+
+            // Check if we should crawl this page
+            storage.should_crawl(
+                to_page_id,
+                self.max_depth(domain),
+                self.config.crawler.max_cross_domain_hops,
+            )?
+        };
+
+        // Add to frontier if within depth limits and not already visited
+        if should_add_to_frontier {
+            let page = {
+                let storage = self.storage.lock().unwrap();
+                storage.get_page(to_page_id)?
+            };
+
+            // Only add if page is in Discovered state; incremental crawls also
+            // revisit pages handled by earlier runs
+            if page.state == PageState::Discovered || self.revisit_in_incremental_run(&page) {
+                // Calculate priority based on classification
+                let priority = match classification {
+                    DomainClassification::Quality => 0,
+                    DomainClassification::Discovered => 10,
+                    _ => 100,
+                };
+                let mut priority = self.scheduler.effective_priority(domain, priority);
+                if self.config.crawler.inlink_priority_interval > 0 {
+                    let storage = self.storage.lock().unwrap();
+                    let referrers = storage.get_incoming_links(to_page_id)?.len() as u64;
+                    priority = inlink_priority(priority, referrers);
+                }
+
+                // Add to storage frontier
+                {
+                    let mut storage = self.storage.lock().unwrap();
+                    storage.add_to_frontier(to_page_id, priority)?;
+                }
+
+                // Discovered domains are only sampled, unless promoted
+                let discovered =
+                    classification == DomainClassification::Discovered && !self.is_promoted(domain);
+                self.scheduler.set_discovered(domain, discovered);
+
+                // Add to scheduler frontier
+                self.scheduler.add_to_frontier(QueuedUrl {
+                    url: url.clone(),
+                    domain: domain.to_string(),
+                    priority,
+                    page_id: to_page_id,
+                });
+            }
+        } else {
+            // Page is beyond max_depth, mark as DepthExceeded
+            let page = {
+                let storage = self.storage.lock().unwrap();
+                storage.get_page(to_page_id)?
+            };
+
+            // Only mark as DepthExceeded if it's still in Discovered state
+            if page.state == PageState::Discovered {
+                let mut storage = self.storage.lock().unwrap();
+                storage.update_page_state(
+                    to_page_id,
+                    PageState::DepthExceeded,
+                    None,
+                    None,
+                    None,
+                    Some("Exceeds max depth or cross-domain hop limit"),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Follows the links recorded for a page by earlier runs
+    ///
+    /// Links to domains that are now blacklisted or stubbed are skipped.
+    fn reuse_stored_links(&mut self, page_id: i64) -> Result<(), SumiError> {
+        let (domain, targets) = {
+            let storage = self.storage.lock().unwrap();
+            let domain = storage.get_page(page_id)?.domain;
+            let targets = storage
+                .get_outgoing_links(page_id)?
+                .into_iter()
+                .map(|link| storage.get_page(link.to_page_id))
+                .collect::<Result<Vec<_>, _>>()?;
+            (domain, targets)
+        };
+
+        for target in targets {
+            let classification = self.classifier.classify(&target.domain);
+            if matches!(
+                classification,
+                DomainClassification::Blacklisted | DomainClassification::Stubbed
+            ) {
+                continue;
             }
+            let url = Url::parse(&target.url)?;
+            self.follow_link(
+                page_id,
+                &domain,
+                target.id,
+                &url,
+                &target.domain,
+                classification,
+            )?;
         }
 
         Ok(())
     }
 
+    /// Whether an incremental run should queue a page an earlier run already handled
+    ///
+    /// Processed pages are queued to be refetched or have their links reused;
+    /// pages left too deep are queued in case they are now within reach.
+    fn revisit_in_incremental_run(&self, page: &PageRecord) -> bool {
+        self.recrawl_after.is_some()
+            && matches!(page.state, PageState::Processed | PageState::DepthExceeded)
+            && !self.handled.contains(&page.id)
+    }
+
+    /// Reorders the frontier by the number of pages linking to each queued URL
+    ///
+    /// Updates the in-memory queue and the stored frontier so the order
+    /// survives a resume.
+    fn reprioritize_frontier(&mut self) -> Result<(), SumiError> {
+        let mut storage = self.storage.lock().unwrap();
+        let referrers = storage.get_frontier_referrer_counts()?;
+        let changed = self.scheduler.reprioritize(|queued| {
+            let count = referrers.get(&queued.page_id).copied().unwrap_or(0);
+            inlink_priority(queued.priority, count)
+        });
+        storage.update_frontier_priorities(&changed)?;
+        tracing::debug!(
+            "Reprioritized {} queued URLs by in-link count",
+            changed.len()
+        );
+        Ok(())
+    }
+
+    /// Records a progress snapshot once the snapshot interval has elapsed
+    fn record_progress_if_due(&mut self) -> Result<(), SumiError> {
+        let interval = Duration::from_secs(
+            self.config
+                .crawler
+                .progress_snapshot_minutes
+                .saturating_mul(60),
+        );
+        match self.progress_recorded {
+            Some(recorded) if recorded.elapsed() < interval => Ok(()),
+            _ => self.record_progress(),
+        }
+    }
+
+    /// Records the run's current progress (pages processed, frontier size,
+    /// errors and the completion forecast)
+    ///
+    /// Does nothing when progress snapshots are disabled.
+    fn record_progress(&mut self) -> Result<(), SumiError> {
+        if self.config.crawler.progress_snapshot_minutes == 0 {
+            return Ok(());
+        }
+
+        let forecast = self.eta.forecast(&self.scheduler, &self.config.crawler);
+        let mut storage = self.storage.lock().unwrap();
+        let snapshot = ProgressSnapshot {
+            run_id: self.run_id,
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            pages_processed: storage.count_pages_by_state(PageState::Processed)?,
+            frontier_size: self.scheduler.frontier_size() as u64,
+            error_count: storage.get_error_summary()?.values().sum(),
+            eta_seconds: forecast.eta.map(|eta| eta.as_secs()),
+        };
+        storage.record_run_progress(&snapshot)?;
+        self.progress_recorded = Some(Instant::now());
+
+        tracing::debug!(
+            "Recorded progress snapshot: {} processed, {} in frontier, {} errors",
+            snapshot.pages_processed,
+            snapshot.frontier_size,
+            snapshot.error_count
+        );
+        Ok(())
+    }
+
     /// Saves all domain states to the database
     ///
     /// This method persists the current state of all domains being crawled,
@@ -540,39 +1820,49 @@ impl Coordinator {
     ///
     /// This method checks if we have cached robots.txt for the domain,
     /// and fetches it if needed or if the cache is stale.
-    async fn get_or_fetch_robots(&mut self, domain: &str) -> Result<ParsedRobots, SumiError> {
-        // Check if scheduler has cached robots.txt for this domain
-        let cached_content = if let Some(domain_state) = self.scheduler.get_domain_state(domain) {
-            if domain_state.is_robots_stale() {
-                None
-            } else {
-                domain_state.robots_txt.as_ref().map(|r| r.content.clone())
-            }
-        } else {
-            None
-        };
-
-        if let Some(content) = cached_content {
-            // Use cached robots.txt and re-parse it
+    async fn get_or_fetch_robots(&mut self, domain: &str) -> Result<Arc<ParsedRobots>, SumiError> {
+        let now = self.scheduler.clock().utc_now();
+        if let Some(robots) = self.robots.get(domain, now) {
             tracing::debug!("Using cached robots.txt for domain: {}", domain);
-            Ok(ParsedRobots::from_content(&content))
-        } else {
-            // Fetch robots.txt
-            tracing::debug!("Fetching robots.txt for domain: {}", domain);
-            let robots = fetch_robots(domain, &self.user_agent).await?;
-
-            // Cache it in the domain state
-            if let Some(domain_state) = self.scheduler.get_domain_state_mut(domain) {
-                // Get the robots.txt content for caching
-                let content = robots.content();
-                domain_state.update_robots(content);
+            return Ok(robots);
+        }
+
+        // Fetch robots.txt, unless the fetcher serves it
+        let robots = match self.fetcher.robots_txt(domain) {
+            Some(content) => ParsedRobots::from_content(&content),
+            None => {
+                tracing::debug!("Fetching robots.txt for domain: {}", domain);
+                fetch_robots(domain, &self.user_agent).await?
             }
+        };
 
-            Ok(robots)
-        }
+        Ok(self.robots.insert(domain, robots, now))
     }
 }
 
+/// Whether a page last fetched at `visited_at` is older than `max_age`
+///
+/// Pages without a valid fetch time always need refetching.
+fn needs_refetch(visited_at: Option<&str>, max_age: chrono::Duration, now: DateTime<Utc>) -> bool {
+    visited_at
+        .and_then(|visited_at| DateTime::parse_from_rfc3339(visited_at).ok())
+        .map(|visited_at| now.signed_duration_since(visited_at) >= max_age)
+        .unwrap_or(true)
+}
+
+/// Whether two URLs name the same path on the same host, ignoring the query
+fn same_path(a: &Url, b: &Url) -> bool {
+    a.host_str() == b.host_str() && a.port() == b.port() && a.path() == b.path()
+}
+
+/// Creates a run identified by the hash of its configuration snapshot
+fn start_run(storage: &mut SqliteStorage, snapshot: &str) -> Result<i64, SumiError> {
+    let config_hash = hex::encode(Sha256::digest(snapshot.as_bytes()));
+    let run_id = storage.create_run(&config_hash)?;
+    storage.save_run_config(run_id, &config_hash, snapshot)?;
+    Ok(run_id)
+}
+
 /// Runs the main crawl operation
 ///
 /// This function orchestrates the entire crawl process:
@@ -585,7 +1875,7 @@ impl Coordinator {
 /// 6. Main crawl loop:
 ///    a. Get next URL from scheduler
 ///    b. Check robots.txt
-///    c. Fetch page (HEAD then GET)
+///    c. Fetch page (GET, preceded by HEAD if `head-requests` is enabled)
 ///    d. Parse HTML and extract links
 ///    e. Classify discovered URLs
 ///    f. Update state and record links
@@ -630,8 +1920,31 @@ mod tests {
             crawler: CrawlerConfig {
                 max_depth: 2,
                 max_concurrent_pages_open: 5,
-                minimum_time_on_page: 1000,
+                per_domain_delay_ms: 1000,
+                per_request_jitter_ms: 0,
+                max_cross_domain_hops: None,
                 max_domain_requests: 100,
+                max_discovered_domain_requests: 20,
+                honor_visit_time: false,
+                progress_snapshot_minutes: 5,
+                max_title_length: 512,
+                recrawl_after_days: None,
+                priority_policy: Default::default(),
+                inlink_priority_interval: 0,
+                lower_tier_fetch_interval: 10,
+                head_requests: false,
+                content_types: vec!["text/html".to_string()],
+                max_page_retries: 10,
+                circuit_breaker_threshold: 5,
+                circuit_breaker_cooldown_secs: 300,
+                max_links_per_page: 1000,
+                error_template_min_pages: 5,
+                trap_page_limit: 50,
+                embedded_links: vec![],
+                harvest_contacts: false,
+                skip_self_links: true,
+                skip_same_path_links: false,
+                hash_route_domains: vec![],
             },
             user_agent: UserAgentConfig {
                 crawler_name: "TestCrawler".to_string(),
@@ -642,16 +1955,82 @@ mod tests {
             output: OutputConfig {
                 database_path: "./test.db".to_string(),
                 summary_path: "./summary.md".to_string(),
+                summary_template: None,
+                html_template: None,
+                warc_path: None,
             },
             quality: vec![QualityEntry {
                 domain: "example.com".to_string(),
                 seeds: vec!["https://example.com/".to_string()],
+                seeds_file: None,
+                seeds_from_sitemap: false,
             }],
             blacklist: vec![],
             stub: vec![],
+            database: Default::default(),
+            retry: Default::default(),
+            promotion: Default::default(),
+            policy: Default::default(),
+            retention: Default::default(),
+            profile: Default::default(),
+            schedule: Default::default(),
+            blacklist_files: vec![],
+            timeouts: vec![],
         }
     }
 
+    #[test]
+    fn test_same_path() {
+        let page = Url::parse("https://a.com/list?page=2").unwrap();
+        assert!(same_path(&Url::parse("https://a.com/list").unwrap(), &page));
+        assert!(same_path(
+            &Url::parse("https://a.com/list?sort=asc").unwrap(),
+            &page
+        ));
+        assert!(!same_path(
+            &Url::parse("https://a.com/list/2").unwrap(),
+            &page
+        ));
+        assert!(!same_path(
+            &Url::parse("https://b.com/list").unwrap(),
+            &page
+        ));
+    }
+
+    #[test]
+    fn test_needs_refetch() {
+        let now = DateTime::parse_from_rfc3339("2024-03-10T12:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let max_age = chrono::Duration::days(7);
+
+        assert!(!needs_refetch(
+            Some("2024-03-05T12:00:00+00:00"),
+            max_age,
+            now
+        ));
+        assert!(needs_refetch(
+            Some("2024-03-03T12:00:00+00:00"),
+            max_age,
+            now
+        ));
+        assert!(needs_refetch(None, max_age, now));
+        assert!(needs_refetch(Some("not a date"), max_age, now));
+    }
+
+    #[test]
+    fn test_incremental_mode_requires_recrawl_after_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = create_test_config();
+        config.output.database_path = dir.path().join("crawl.db").display().to_string();
+
+        let result = Coordinator::with_mode(config.clone(), CrawlMode::Incremental);
+        assert!(matches!(result, Err(SumiError::Config(_))));
+
+        config.crawler.recrawl_after_days = Some(30);
+        assert!(Coordinator::with_mode(config, CrawlMode::Incremental).is_ok());
+    }
+
     #[tokio::test]
     async fn test_coordinator_creation() {
         let config = create_test_config();
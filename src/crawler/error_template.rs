@@ -0,0 +1,110 @@
+//! Error and interstitial page templates
+//!
+//! Many sites answer unknown URLs with a "not found" page served as 200 OK,
+//! and put cookie walls or login prompts in front of whole sections. Every
+//! such page carries the site's navigation, so each one crawled adds the same
+//! links to the graph again. These pages are recognized by their body: once
+//! the requested URL, which such pages often echo, is blanked out, they are
+//! identical. Pages of one domain sharing this template hash with enough
+//! others are flagged in storage and their links left out of the link graph.
+
+use sha2::{Digest, Sha256};
+
+/// Shortest URL path blanked out of a body
+///
+/// Shorter paths such as "/a" also occur in ordinary markup ("</a>").
+pub const MIN_ECHOED_PATH_LEN: usize = 6;
+
+/// Hashes a page body with the URLs it was requested under blanked out
+///
+/// Each URL is removed as written and HTML-escaped, along with its path and
+/// query when these are at least `MIN_ECHOED_PATH_LEN` bytes long. Runs of
+/// whitespace are collapsed, so reflowed markup hashes the same.
+///
+/// # Arguments
+///
+/// * `body` - The page body
+/// * `urls` - The requested URL and the URL it was finally served from
+///
+/// # Returns
+///
+/// The SHA-256 of the remaining body, hex-encoded
+pub fn template_hash(body: &str, urls: &[&str]) -> String {
+    let mut echoes: Vec<String> = Vec::new();
+    for url in urls {
+        echoes.push(url.to_string());
+        if let Ok(parsed) = url::Url::parse(url) {
+            let path = match parsed.query() {
+                Some(query) => format!("{}?{}", parsed.path(), query),
+                None => parsed.path().to_string(),
+            };
+            if path.len() >= MIN_ECHOED_PATH_LEN {
+                echoes.push(path);
+            }
+        }
+    }
+    let escaped: Vec<String> = echoes
+        .iter()
+        .filter(|echo| echo.contains('&'))
+        .map(|echo| echo.replace('&', "&amp;"))
+        .collect();
+    echoes.extend(escaped);
+    // Longest first, so a URL is removed before its path
+    echoes.sort_by_key(|echo| std::cmp::Reverse(echo.len()));
+
+    let mut remaining = body.to_string();
+    for echo in &echoes {
+        remaining = remaining.replace(echo.as_str(), "");
+    }
+
+    let collapsed = remaining.split_whitespace().collect::<Vec<_>>().join(" ");
+    hex::encode(Sha256::digest(collapsed.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_found(path: &str) -> String {
+        format!(
+            "<html><body><nav><a href=\"/\">Home</a></nav>\n<p>Sorry, {} was not found.</p></body></html>",
+            path
+        )
+    }
+
+    #[test]
+    fn test_template_hash_ignores_echoed_url() {
+        let first = template_hash(&not_found("/missing-page"), &["https://a.com/missing-page"]);
+        let second = template_hash(
+            &not_found("/another/lost?x=1&y=2"),
+            &["https://a.com/another/lost?x=1&y=2"],
+        );
+        assert_eq!(first, second);
+
+        // Escaped and full URLs are blanked out too
+        let escaped = template_hash(
+            &not_found("https://a.com/another/lost?x=1&amp;y=2"),
+            &["https://a.com/another/lost?x=1&y=2"],
+        );
+        assert_eq!(first, escaped);
+
+        // Whitespace does not matter, content does
+        let reflowed = template_hash(
+            &not_found("/missing-page").replace('\n', "\n\n  "),
+            &["https://a.com/missing-page"],
+        );
+        assert_eq!(first, reflowed);
+        let other = template_hash("<html><body>Welcome</body></html>", &["https://a.com/"]);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_short_paths_are_kept() {
+        // Blanking "/a" would also change "</a>"
+        let body = "<a href=\"/x\">x</a>";
+        assert_eq!(
+            template_hash(body, &["https://a.com/a"]),
+            template_hash(body, &[])
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Completion forecasting
+//!
+//! The time a crawl still needs depends on three things: how many queued URLs
+//! will actually be fetched (a domain's URLs beyond its remaining request
+//! budget never are), how quickly pages are processed, and how many new URLs
+//! each processed page adds to the frontier. When every page adds less than
+//! one new URL on average the frontier drains, and the expected total work is
+//! the geometric series `remaining / (1 - discovery_ratio)`. Otherwise the
+//! frontier is still growing and the forecast is only a lower bound.
+//!
+//! Per-domain politeness also bounds the ETA from below: a domain with `n`
+//! fetches left needs at least `n` times the minimum delay, however fast the
+//! rest of the crawl goes.
+
+use crate::config::CrawlerConfig;
+use crate::crawler::Scheduler;
+use std::time::{Duration, Instant};
+
+/// Pages that must be processed before an ETA is estimated
+pub const MIN_PAGES_FOR_ETA: u64 = 20;
+
+/// Discovery ratios above this are treated as a growing frontier
+const MAX_CONVERGING_RATIO: f64 = 0.95;
+
+/// Forecast of the remaining crawl
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlForecast {
+    /// Queued URLs that will be fetched within their domains' request budgets
+    pub remaining: u64,
+
+    /// Pages processed per second so far
+    pub pages_per_second: f64,
+
+    /// New URLs queued per processed page so far
+    pub discovery_ratio: f64,
+
+    /// Whether the frontier is expected to drain
+    pub converging: bool,
+
+    /// Estimated time until the crawl completes; `None` until enough pages
+    /// have been processed. A lower bound when not `converging`.
+    pub eta: Option<Duration>,
+}
+
+/// Tracks crawl throughput and link discovery to forecast completion
+#[derive(Debug, Clone)]
+pub struct EtaEstimator {
+    started: Instant,
+    pages: u64,
+    queued: u64,
+}
+
+impl EtaEstimator {
+    /// Creates an estimator starting now
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            pages: 0,
+            queued: 0,
+        }
+    }
+
+    /// Records a processed page and the number of URLs it added to the frontier
+    pub fn record_page(&mut self, newly_queued: u64) {
+        self.pages += 1;
+        self.queued += newly_queued;
+    }
+
+    /// Forecasts the remaining crawl from the scheduler's current backlog
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - The scheduler holding the frontier and domain states
+    /// * `config` - The crawler configuration (request budgets and delays)
+    pub fn forecast(&self, scheduler: &Scheduler, config: &CrawlerConfig) -> CrawlForecast {
+        forecast(
+            &scheduler.domain_backlog(),
+            // Jitter adds half its maximum on average
+            Duration::from_millis(config.per_domain_delay_ms + config.per_request_jitter_ms / 2),
+            self.pages,
+            self.queued,
+            self.started.elapsed(),
+        )
+    }
+}
+
+impl Default for EtaEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Forecasts the remaining crawl
+///
+/// # Arguments
+///
+/// * `backlog` - Per-domain fetches still to make (queued URLs within budget)
+/// * `min_delay` - Minimum delay between requests to one domain
+/// * `pages` - Pages processed so far
+/// * `queued` - URLs added to the frontier by those pages
+/// * `elapsed` - Time spent processing them
+pub fn forecast(
+    backlog: &[u64],
+    min_delay: Duration,
+    pages: u64,
+    queued: u64,
+    elapsed: Duration,
+) -> CrawlForecast {
+    let remaining: u64 = backlog.iter().sum();
+    let seconds = elapsed.as_secs_f64();
+    let pages_per_second = if seconds > 0.0 {
+        pages as f64 / seconds
+    } else {
+        0.0
+    };
+    let discovery_ratio = if pages > 0 {
+        queued as f64 / pages as f64
+    } else {
+        0.0
+    };
+    let converging = discovery_ratio <= MAX_CONVERGING_RATIO;
+
+    let eta = (pages >= MIN_PAGES_FOR_ETA && pages_per_second > 0.0).then(|| {
+        let expected = if converging {
+            remaining as f64 / (1.0 - discovery_ratio)
+        } else {
+            remaining as f64
+        };
+        let throughput = Duration::from_secs_f64(expected / pages_per_second);
+        let politeness = min_delay * backlog.iter().copied().max().unwrap_or(0) as u32;
+        throughput.max(politeness)
+    });
+
+    CrawlForecast {
+        remaining,
+        pages_per_second,
+        discovery_ratio,
+        converging,
+        eta,
+    }
+}
+
+/// Formats a duration as a short human-readable string ("2d 4h", "3h 12m", "45s")
+pub fn format_eta(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_eta_before_enough_pages() {
+        let result = forecast(&[10], Duration::ZERO, 5, 2, Duration::from_secs(5));
+        assert_eq!(result.remaining, 10);
+        assert!(result.eta.is_none());
+    }
+
+    #[test]
+    fn test_converging_frontier() {
+        // 100 pages in 100s adding 50 URLs: 1 page/s, half a new URL per page
+        let result = forecast(&[30, 10], Duration::ZERO, 100, 50, Duration::from_secs(100));
+        assert_eq!(result.remaining, 40);
+        assert_eq!(result.discovery_ratio, 0.5);
+        assert!(result.converging);
+        assert_eq!(result.eta, Some(Duration::from_secs(80)));
+    }
+
+    #[test]
+    fn test_growing_frontier_is_lower_bound() {
+        let result = forecast(&[40], Duration::ZERO, 100, 300, Duration::from_secs(100));
+        assert!(!result.converging);
+        assert_eq!(result.eta, Some(Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn test_politeness_floor() {
+        // Fast overall, but one domain still needs 30 fetches 10s apart
+        let result = forecast(
+            &[30, 1, 1],
+            Duration::from_secs(10),
+            100,
+            0,
+            Duration::from_secs(10),
+        );
+        assert_eq!(result.eta, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(Duration::from_secs(45)), "45s");
+        assert_eq!(format_eta(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(
+            format_eta(Duration::from_secs(3 * 3600 + 12 * 60)),
+            "3h 12m"
+        );
+        assert_eq!(
+            format_eta(Duration::from_secs(2 * 86_400 + 4 * 3600)),
+            "2d 4h"
+        );
+    }
+}
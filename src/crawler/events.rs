@@ -0,0 +1,97 @@
+//! Crawl events for library users
+//!
+//! `Coordinator::run_stream` runs a crawl in the background and reports what
+//! happens as a stream of `CrawlEvent`s, so embedders can index fetched pages
+//! or follow the link graph as it grows instead of reading the database once
+//! the crawl is over. The crawl still records everything in storage.
+
+use crate::state::PageState;
+use crate::storage::LinkKind;
+use crate::url::DomainClassification;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Something that happened during a crawl
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrawlEvent {
+    /// A page was fetched and parsed
+    PageFetched {
+        /// The page ID in storage
+        page_id: i64,
+        /// The URL the page was requested as
+        url: String,
+        /// The URL after redirects
+        final_url: String,
+        /// HTTP status code
+        status_code: u16,
+        /// The Content-Type of the response
+        content_type: String,
+        /// The page title, if any
+        title: Option<String>,
+        /// The response body
+        body: String,
+    },
+
+    /// A page could not be fetched or parsed
+    PageFailed {
+        /// The page ID in storage
+        page_id: i64,
+        /// The URL the page was requested as
+        url: String,
+        /// The state the page was left in
+        state: PageState,
+        /// Why the page failed
+        error: String,
+    },
+
+    /// A fetched page links to a crawlable URL
+    LinkDiscovered {
+        /// The page containing the link
+        from_page_id: i64,
+        /// The linked page
+        to_page_id: i64,
+        /// The linked page's normalized URL
+        url: String,
+        /// The linked page's domain
+        domain: String,
+        /// Classification of the linked domain
+        classification: DomainClassification,
+        /// The element the link was found in
+        kind: LinkKind,
+    },
+
+    /// The crawl ended; always the last event
+    Finished {
+        /// The ID of the run
+        run_id: i64,
+        /// Why the crawl stopped early, if it did
+        error: Option<String>,
+    },
+}
+
+/// The events of a crawl running in the background
+///
+/// Ends after the `Finished` event. Events are buffered until they are read,
+/// so a slow consumer does not slow the crawl down.
+#[derive(Debug)]
+pub struct CrawlEventStream {
+    receiver: mpsc::UnboundedReceiver<CrawlEvent>,
+}
+
+impl CrawlEventStream {
+    /// Creates a stream and the sender its events are emitted with
+    pub(crate) fn channel() -> (mpsc::UnboundedSender<CrawlEvent>, Self) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (sender, Self { receiver })
+    }
+}
+
+impl Stream for CrawlEventStream {
+    type Item = CrawlEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<CrawlEvent>> {
+        self.receiver.poll_recv(cx)
+    }
+}
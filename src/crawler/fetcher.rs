@@ -2,17 +2,42 @@
 //!
 //! This module handles all HTTP requests for the crawler, including:
 //! - Building HTTP clients with proper user agent strings
-//! - HEAD requests to check Content-Type
-//! - GET requests to fetch page content
+//! - GET requests whose Content-Type is checked before the body is downloaded
+//! - Optional HEAD requests to check Content-Type (skipped for servers that mishandle them)
 //! - Retry logic for transient failures
 //! - Redirect handling
 //! - Error classification
-
-use crate::config::UserAgentConfig;
+//!
+//! The coordinator fetches pages through the `Fetcher` trait; `HttpFetcher`
+//! is the reqwest-based default. Other implementations can serve canned
+//! responses in tests or use another transport (a headless browser, archive
+//! replay, an HTTP cache).
+
+use crate::config::{
+    Config, RetryConfig, UserAgentConfig, DEFAULT_CONTENT_TYPES, DEFAULT_REQUEST_TIMEOUT_SECS,
+};
 use crate::state::PageState;
+use crate::url::matches_wildcard;
 use reqwest::{redirect::Policy, Client, StatusCode};
 use std::collections::HashSet;
-use std::time::Duration;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// HEAD requests taking longer than this count as mishandled
+pub const SLOW_HEAD_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Response headers recorded for every fetched page (lowercase)
+pub const RECORDED_HEADERS: &[&str] = &[
+    "server",
+    "x-powered-by",
+    "content-language",
+    "cache-control",
+    "x-robots-tag",
+];
+
+/// Longest recorded header value (characters); longer values are truncated
+const MAX_HEADER_VALUE_LENGTH: usize = 256;
 
 /// Result of a fetch operation
 #[derive(Debug)]
@@ -29,6 +54,8 @@ pub enum FetchResult {
         body: String,
         /// Page title (if extracted)
         title: Option<String>,
+        /// The `RECORDED_HEADERS` present in the response, as (name, value)
+        headers: Vec<(String, String)>,
     },
 
     /// Page is not HTML (Content-Type mismatch)
@@ -65,9 +92,154 @@ pub enum FetchResult {
     RedirectError {
         /// Error description
         error: String,
+        /// The URLs requested, in order; a loop ends with the repeated URL
+        chain: Vec<String>,
     },
 }
 
+impl FetchResult {
+    /// Whether the result suggests the server is struggling: a network
+    /// failure or a 5xx response
+    pub fn is_server_failure(&self) -> bool {
+        match self {
+            FetchResult::NetworkError { .. } => true,
+            FetchResult::HttpError { status_code, .. } => *status_code >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// A fetch result with details about how it was obtained
+#[derive(Debug)]
+pub struct FetchOutcome {
+    /// The final result
+    pub result: FetchResult,
+
+    /// How the HEAD requests went
+    pub head: HeadOutcome,
+
+    /// Number of times the request was retried after a transient failure
+    pub retries: u32,
+}
+
+/// How the preliminary HEAD requests of a fetch went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadOutcome {
+    /// No HEAD request was sent
+    Skipped,
+
+    /// The server answered HEAD promptly
+    Supported,
+
+    /// HEAD failed, was rejected (405/501) or was slow while the server
+    /// may still serve GET
+    Mishandled,
+}
+
+impl HeadOutcome {
+    /// Combines the outcomes of the HEAD requests of several redirect hops
+    ///
+    /// A single mishandled HEAD makes the whole fetch count as mishandled.
+    fn merge(self, other: HeadOutcome) -> HeadOutcome {
+        match (self, other) {
+            (HeadOutcome::Mishandled, _) | (_, HeadOutcome::Mishandled) => HeadOutcome::Mishandled,
+            (HeadOutcome::Supported, _) | (_, HeadOutcome::Supported) => HeadOutcome::Supported,
+            _ => HeadOutcome::Skipped,
+        }
+    }
+}
+
+/// Settings of a fetch
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// Whether to check the Content-Type with a HEAD request before the GET
+    pub send_head: bool,
+
+    /// Media types that count as crawlable ("type/subtype", or "type/*" for
+    /// every subtype)
+    pub content_types: Vec<String>,
+
+    /// How transient failures are retried
+    pub retry: RetryPolicy,
+
+    /// How long each request may take, including reading the body
+    pub timeout: Duration,
+}
+
+impl FetchOptions {
+    /// Creates the options configured for a crawl
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawl configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            send_head: config.crawler.head_requests,
+            content_types: config.crawler.content_types.clone(),
+            retry: RetryPolicy::from_config(&config.retry),
+            timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+        }
+    }
+
+    /// Creates the options configured for fetching from a domain
+    ///
+    /// Like [`FetchOptions::from_config`], with the timeout of the first
+    /// `[[timeout]]` entry whose pattern matches the domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawl configuration
+    /// * `domain` - The domain to fetch from
+    pub fn for_domain(config: &Config, domain: &str) -> Self {
+        let mut options = Self::from_config(config);
+        if let Some(entry) = config
+            .timeouts
+            .iter()
+            .find(|entry| matches_wildcard(&entry.domain.to_lowercase(), domain))
+        {
+            options.timeout = Duration::from_secs(entry.timeout_secs);
+        }
+        options
+    }
+
+    /// Checks whether a response with this Content-Type should be downloaded
+    ///
+    /// Responses without a Content-Type are accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The Content-Type header value (may be empty)
+    pub fn accepts(&self, content_type: &str) -> bool {
+        let media_type = media_type(content_type);
+        if media_type.is_empty() {
+            return true;
+        }
+        self.content_types.iter().any(|allowed| {
+            let allowed = allowed.to_lowercase();
+            match allowed.strip_suffix("/*") {
+                Some(prefix) => media_type
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/')),
+                None => media_type == allowed,
+            }
+        })
+    }
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            send_head: false,
+            content_types: DEFAULT_CONTENT_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            retry: RetryPolicy::default(),
+            timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+        }
+    }
+}
+
 /// Retry policy configuration
 #[derive(Debug, Clone)]
 pub struct RetryPolicy {
@@ -75,6 +247,10 @@ pub struct RetryPolicy {
     pub max_retries: u32,
     /// Base delay between retries (exponential backoff)
     pub base_delay: Duration,
+    /// Upper bound of the delay between retries
+    pub max_delay: Duration,
+    /// Fraction of each delay that is randomized (0.0 to 1.0)
+    pub jitter: f64,
 }
 
 /// Redirect chain tracker for handling HTTP redirects
@@ -84,6 +260,8 @@ pub struct RedirectChain {
     pub max_redirects: u32,
     /// Set of visited URLs to detect loops
     pub visited: HashSet<String>,
+    /// The URLs requested, in order
+    pub urls: Vec<String>,
 }
 
 impl RedirectChain {
@@ -92,19 +270,29 @@ impl RedirectChain {
         Self {
             max_redirects: 10,
             visited: HashSet::new(),
+            urls: Vec::new(),
         }
     }
 
-    /// Adds a URL to the visited set
+    /// Adds a URL to the chain
     ///
     /// # Returns
     ///
     /// * `true` - If this is a new URL
     /// * `false` - If we've already visited this URL (loop detected)
     pub fn add_url(&mut self, url: &str) -> bool {
+        self.urls.push(url.to_string());
         self.visited.insert(url.to_string())
     }
 
+    /// Creates a redirect error carrying the chain followed so far
+    pub fn error(&self, error: String) -> FetchResult {
+        FetchResult::RedirectError {
+            error,
+            chain: self.urls.clone(),
+        }
+    }
+
     /// Checks if we've exceeded the maximum redirect count
     pub fn is_too_long(&self) -> bool {
         self.visited.len() > self.max_redirects as usize
@@ -125,17 +313,29 @@ impl Default for RedirectChain {
 
 impl Default for RetryPolicy {
     fn default() -> Self {
-        Self {
-            max_retries: 3,
-            base_delay: Duration::from_secs(5),
-        }
+        Self::from_config(&RetryConfig::default())
     }
 }
 
 impl RetryPolicy {
+    /// Creates the retry policy configured in the `[retry]` section
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The retry configuration
+    pub fn from_config(config: &RetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+            jitter: config.jitter.clamp(0.0, 1.0),
+        }
+    }
+
     /// Calculates the delay for a given retry attempt
     ///
-    /// Uses exponential backoff: delay = base_delay * 2^attempt
+    /// Uses exponential backoff: delay = base_delay * 2^attempt, capped at
+    /// `max_delay`
     ///
     /// # Arguments
     ///
@@ -145,8 +345,26 @@ impl RetryPolicy {
     ///
     /// The delay duration for this attempt
     fn delay_for_attempt(&self, attempt: u32) -> Duration {
-        let multiplier = 2u32.pow(attempt);
-        self.base_delay * multiplier
+        let multiplier = 2u32.saturating_pow(attempt);
+        self.base_delay
+            .checked_mul(multiplier)
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay)
+    }
+
+    /// Calculates the randomized delay for a given retry attempt
+    ///
+    /// Picks a delay between `(1 - jitter)` times and the full
+    /// [`delay_for_attempt`](Self::delay_for_attempt), so clients that
+    /// failed together do not all retry at the same moment.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The retry attempt number (0-indexed)
+    /// * `random` - A random number in `[0, 1)`
+    fn jittered_delay(&self, attempt: u32, random: f64) -> Duration {
+        let delay = self.delay_for_attempt(attempt);
+        delay.mul_f64(1.0 - self.jitter * random)
     }
 
     /// Checks if an error is retryable
@@ -220,7 +438,7 @@ pub fn build_http_client(config: &UserAgentConfig) -> Result<Client, reqwest::Er
 
     Client::builder()
         .user_agent(user_agent)
-        .timeout(Duration::from_secs(30))
+        .timeout(Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS))
         .connect_timeout(Duration::from_secs(10))
         .redirect(Policy::none()) // Handle redirects manually
         .https_only(false) // Allow HTTP for testing
@@ -233,9 +451,13 @@ pub fn build_http_client(config: &UserAgentConfig) -> Result<Client, reqwest::Er
 ///
 /// # Request Flow
 ///
-/// 1. Send HEAD request to check Content-Type
-///    - If not HTML → return ContentMismatch
+/// 1. Send HEAD request to check Content-Type (only with
+///    [`FetchOptions::send_head`], see [`fetch_url_with_options`])
+///    - If not an accepted content type → return ContentMismatch
+///    - If HEAD is rejected (405/501) or fails → continue with GET
 /// 2. Send GET request
+///    - If not an accepted content type → return ContentMismatch without
+///      downloading the body
 /// 3. Handle redirects manually (max 10 hops)
 ///    - Track visited URLs to detect loops
 ///    - Stop if redirect hits blacklist/stub domain
@@ -247,13 +469,16 @@ pub fn build_http_client(config: &UserAgentConfig) -> Result<Client, reqwest::Er
 /// |-----------|--------|
 /// | HTTP 404 | Immediate → DeadLink |
 /// | HTTP 429 | Immediate → RateLimited |
-/// | HTTP 5xx | Retry up to 3 times, 5s delay |
-/// | Timeout | Retry up to 3 times, 5s delay |
+/// | HTTP 5xx | Retry up to 3 times, backoff from 5s |
+/// | Timeout | Retry up to 3 times, backoff from 5s |
 /// | Connection refused | Retry up to 2 times |
 /// | TLS/SSL error | Immediate → Unreachable |
 /// | Redirect loop | Immediate → Failed |
 /// | Redirect chain > 10 | Immediate → Failed |
 ///
+/// The retry counts and delays are the `[retry]` defaults: delays double per
+/// retry up to 60s, with up to half of each delay randomized.
+///
 /// # Arguments
 ///
 /// * `client` - The HTTP client to use
@@ -266,6 +491,99 @@ pub async fn fetch_url(client: &Client, url: &str) -> FetchResult {
     fetch_url_with_retry(client, url, &RetryPolicy::default()).await
 }
 
+/// A future returned by [`Fetcher::fetch`]
+pub type FetchFuture<'a> = Pin<Box<dyn Future<Output = FetchOutcome> + Send + 'a>>;
+
+/// Fetches the pages of a crawl
+///
+/// Implementations report every outcome, failures included, as a
+/// `FetchOutcome`; the coordinator records it like any HTTP response.
+pub trait Fetcher: Send + Sync {
+    /// Fetches a URL
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to fetch
+    /// * `options` - HEAD request, accepted content types, retries and timeout
+    fn fetch<'a>(&'a self, url: &'a str, options: &'a FetchOptions) -> FetchFuture<'a>;
+
+    /// Serves the robots.txt of a domain instead of fetching it over HTTP
+    ///
+    /// Returns None, the default, to have it fetched over HTTP.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain, with its port if any
+    fn robots_txt(&self, domain: &str) -> Option<String> {
+        let _ = domain;
+        None
+    }
+}
+
+/// A shared fetcher, so callers can keep a handle on the one they hand out
+impl<F: Fetcher + ?Sized> Fetcher for std::sync::Arc<F> {
+    fn fetch<'a>(&'a self, url: &'a str, options: &'a FetchOptions) -> FetchFuture<'a> {
+        (**self).fetch(url, options)
+    }
+
+    fn robots_txt(&self, domain: &str) -> Option<String> {
+        (**self).robots_txt(domain)
+    }
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for Box<F> {
+    fn fetch<'a>(&'a self, url: &'a str, options: &'a FetchOptions) -> FetchFuture<'a> {
+        (**self).fetch(url, options)
+    }
+
+    fn robots_txt(&self, domain: &str) -> Option<String> {
+        (**self).robots_txt(domain)
+    }
+}
+
+/// Fetches pages over HTTP with reqwest, the default `Fetcher`
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    client: Client,
+}
+
+impl HttpFetcher {
+    /// Creates a fetcher using an HTTP client
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client, usually from `build_http_client`
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, options: &'a FetchOptions) -> FetchFuture<'a> {
+        Box::pin(fetch_url_with_options(&self.client, url, options))
+    }
+}
+
+/// Fetches a URL with the given options
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client to use
+/// * `url` - The URL to fetch
+/// * `options` - Whether to send HEAD and which content types to accept
+///
+/// # Returns
+///
+/// The FetchResult, the number of retries it took, and how the HEAD requests
+/// went so the caller can stop sending them to servers that mishandle them
+pub async fn fetch_url_with_options(
+    client: &Client,
+    url: &str,
+    options: &FetchOptions,
+) -> FetchOutcome {
+    fetch_with_policy(client, url, &options.retry, options).await
+}
+
 /// Fetches a URL with custom retry policy
 ///
 /// # Arguments
@@ -278,11 +596,25 @@ pub async fn fetch_url(client: &Client, url: &str) -> FetchResult {
 ///
 /// A FetchResult indicating success or the type of failure
 pub async fn fetch_url_with_retry(client: &Client, url: &str, policy: &RetryPolicy) -> FetchResult {
+    fetch_with_policy(client, url, policy, &FetchOptions::default())
+        .await
+        .result
+}
+
+/// Fetches a URL with a retry policy, reporting retries and HEAD requests
+async fn fetch_with_policy(
+    client: &Client,
+    url: &str,
+    policy: &RetryPolicy,
+    options: &FetchOptions,
+) -> FetchOutcome {
     let mut attempt = 0;
+    let mut head = HeadOutcome::Skipped;
 
     loop {
         // Try to fetch
-        let result = fetch_url_once(client, url).await;
+        let (result, attempt_head) = fetch_url_once(client, url, options).await;
+        head = head.merge(attempt_head);
 
         // Check if we should retry
         let should_retry = match &result {
@@ -299,11 +631,15 @@ pub async fn fetch_url_with_retry(client: &Client, url: &str, policy: &RetryPoli
 
         // Return if successful or non-retryable error
         if !should_retry || attempt >= policy.max_retries {
-            return result;
+            return FetchOutcome {
+                result,
+                head,
+                retries: attempt,
+            };
         }
 
         // Wait before retrying
-        let delay = policy.delay_for_attempt(attempt);
+        let delay = policy.jittered_delay(attempt, rand::random::<f64>());
         tracing::debug!(
             "Retry attempt {} for {}, waiting {:?}",
             attempt + 1,
@@ -317,8 +653,15 @@ pub async fn fetch_url_with_retry(client: &Client, url: &str, policy: &RetryPoli
 }
 
 /// Performs a single fetch attempt without retry logic
-async fn fetch_url_once(client: &Client, url: &str) -> FetchResult {
-    fetch_url_with_redirects(client, url, &mut RedirectChain::new()).await
+async fn fetch_url_once(
+    client: &Client,
+    url: &str,
+    options: &FetchOptions,
+) -> (FetchResult, HeadOutcome) {
+    let mut head = HeadOutcome::Skipped;
+    let result =
+        fetch_url_with_redirects(client, url, &mut RedirectChain::new(), options, &mut head).await;
+    (result, head)
 }
 
 /// Performs a single fetch with manual redirect following
@@ -326,26 +669,46 @@ async fn fetch_url_with_redirects(
     client: &Client,
     url: &str,
     redirect_chain: &mut RedirectChain,
+    options: &FetchOptions,
+    head: &mut HeadOutcome,
 ) -> FetchResult {
     // Add current URL to redirect chain
     if !redirect_chain.add_url(url) {
-        return FetchResult::RedirectError {
-            error: format!("Redirect loop detected at {}", url),
-        };
+        return redirect_chain.error(format!("Redirect loop detected at {}", url));
     }
 
     // Check if redirect chain is too long
     if redirect_chain.is_too_long() {
-        return FetchResult::RedirectError {
-            error: format!("Too many redirects (max {})", redirect_chain.max_redirects),
-        };
+        return redirect_chain.error(format!(
+            "Too many redirects (max {})",
+            redirect_chain.max_redirects
+        ));
     }
 
     // First, send HEAD request to check Content-Type
-    match client.head(url).send().await {
-        Ok(response) => {
+    let head_started = Instant::now();
+    let head_response = if options.send_head {
+        Some(client.head(url).timeout(options.timeout).send().await)
+    } else {
+        None
+    };
+    match head_response {
+        None => {}
+        Some(Ok(response)) => {
             let status = response.status();
 
+            // Servers rejecting the method or taking long to answer are better
+            // served by GET alone
+            let rejected =
+                status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED;
+            *head = head.merge(
+                if rejected || head_started.elapsed() > SLOW_HEAD_THRESHOLD {
+                    HeadOutcome::Mishandled
+                } else {
+                    HeadOutcome::Supported
+                },
+            );
+
             // Check for redirect (we disabled automatic redirects)
             if status.is_redirection() {
                 // Extract redirect location
@@ -362,18 +725,15 @@ async fn fetch_url_with_redirects(
                                 Ok(base) => match base.join(location_str) {
                                     Ok(resolved) => resolved.to_string(),
                                     Err(_) => {
-                                        return FetchResult::RedirectError {
-                                            error: format!(
-                                                "Invalid redirect URL: {}",
-                                                location_str
-                                            ),
-                                        };
+                                        return redirect_chain.error(format!(
+                                            "Invalid redirect URL: {}",
+                                            location_str
+                                        ));
                                     }
                                 },
                                 Err(_) => {
-                                    return FetchResult::RedirectError {
-                                        error: format!("Invalid base URL: {}", url),
-                                    };
+                                    return redirect_chain
+                                        .error(format!("Invalid base URL: {}", url));
                                 }
                             }
                         };
@@ -385,16 +745,17 @@ async fn fetch_url_with_redirects(
                             client,
                             &redirect_url,
                             redirect_chain,
+                            options,
+                            head,
                         ))
                         .await;
                     }
                 }
 
                 // Redirect without Location header - treat as error
-                return FetchResult::RedirectError {
-                    error: format!("Redirect response without Location header"),
-                };
-            } else if !status.is_success() {
+                return redirect_chain
+                    .error("Redirect response without Location header".to_string());
+            } else if !status.is_success() && !rejected {
                 // If HEAD fails with a client error, return early
                 if status == StatusCode::NOT_FOUND {
                     return FetchResult::HttpError {
@@ -429,30 +790,21 @@ async fn fetch_url_with_redirects(
             // If HEAD succeeded and content-type is definitely not HTML, return mismatch
             // We only reject if we're certain it's not HTML (e.g., images, PDFs)
             // text/plain might just be a server misconfiguration, so we'll try GET anyway
-            if status.is_success() && !content_type.is_empty() {
-                let lowercase_type = content_type.to_lowercase();
-                // Only reject obviously non-HTML content types
-                if lowercase_type.starts_with("image/")
-                    || lowercase_type.starts_with("video/")
-                    || lowercase_type.starts_with("audio/")
-                    || lowercase_type.starts_with("application/pdf")
-                    || lowercase_type.starts_with("application/zip")
-                    || lowercase_type.starts_with("application/octet-stream")
-                {
-                    return FetchResult::ContentMismatch { content_type };
-                }
-                // For text/plain, application/json, etc., we'll try GET to be sure
+            if status.is_success() && !options.accepts(&content_type) {
+                return FetchResult::ContentMismatch { content_type };
             }
+            // For text/plain, application/json, etc., we'll try GET to be sure
         }
-        Err(e) => {
+        Some(Err(e)) => {
             // HEAD request failed, we'll try GET anyway
             // Some servers don't support HEAD
             tracing::debug!("HEAD request failed for {}: {}, trying GET", url, e);
+            *head = head.merge(HeadOutcome::Mishandled);
         }
     }
 
     // Now send GET request
-    match client.get(url).send().await {
+    match client.get(url).timeout(options.timeout).send().await {
         Ok(response) => {
             let status = response.status();
             let final_url = response.url().to_string();
@@ -473,18 +825,15 @@ async fn fetch_url_with_redirects(
                                 Ok(base) => match base.join(location_str) {
                                     Ok(resolved) => resolved.to_string(),
                                     Err(_) => {
-                                        return FetchResult::RedirectError {
-                                            error: format!(
-                                                "Invalid redirect URL: {}",
-                                                location_str
-                                            ),
-                                        };
+                                        return redirect_chain.error(format!(
+                                            "Invalid redirect URL: {}",
+                                            location_str
+                                        ));
                                     }
                                 },
                                 Err(_) => {
-                                    return FetchResult::RedirectError {
-                                        error: format!("Invalid base URL: {}", url),
-                                    };
+                                    return redirect_chain
+                                        .error(format!("Invalid base URL: {}", url));
                                 }
                             }
                         };
@@ -496,15 +845,16 @@ async fn fetch_url_with_redirects(
                             client,
                             &redirect_url,
                             redirect_chain,
+                            options,
+                            head,
                         ))
                         .await;
                     }
                 }
 
                 // Redirect without Location header - treat as error
-                return FetchResult::RedirectError {
-                    error: format!("GET redirect response without Location header"),
-                };
+                return redirect_chain
+                    .error("GET redirect response without Location header".to_string());
             }
 
             // Handle specific HTTP status codes
@@ -543,7 +893,8 @@ async fn fetch_url_with_redirects(
                 };
             }
 
-            // Check Content-Type
+            // Check Content-Type from the response headers, before any of
+            // the body has been read
             let content_type = response
                 .headers()
                 .get("content-type")
@@ -551,21 +902,15 @@ async fn fetch_url_with_redirects(
                 .unwrap_or("")
                 .to_string();
 
-            // Only reject obviously non-HTML content types
-            // Similar logic to HEAD check above
-            if !content_type.is_empty() {
-                let lowercase_type = content_type.to_lowercase();
-                if lowercase_type.starts_with("image/")
-                    || lowercase_type.starts_with("video/")
-                    || lowercase_type.starts_with("audio/")
-                    || lowercase_type.starts_with("application/pdf")
-                    || lowercase_type.starts_with("application/zip")
-                    || lowercase_type.starts_with("application/octet-stream")
-                {
-                    return FetchResult::ContentMismatch { content_type };
-                }
+            // Dropping the response here closes the connection, so the body
+            // of a non-HTML file is never downloaded
+            if !options.accepts(&content_type) {
+                drop(response);
+                return FetchResult::ContentMismatch { content_type };
             }
 
+            let headers = recorded_headers(response.headers());
+
             // Get body
             match response.text().await {
                 Ok(body) => FetchResult::Success {
@@ -574,6 +919,7 @@ async fn fetch_url_with_redirects(
                     content_type,
                     body,
                     title: None, // Will be extracted during parsing
+                    headers,
                 },
                 Err(e) => FetchResult::NetworkError {
                     error: e.to_string(),
@@ -628,6 +974,55 @@ async fn fetch_url_with_redirects(
     }
 }
 
+/// Picks the `RECORDED_HEADERS` out of a response
+///
+/// Repeated headers are joined with ", ". Values that are not valid text are
+/// skipped and long values are truncated.
+///
+/// # Arguments
+///
+/// * `headers` - The response headers
+///
+/// # Returns
+///
+/// (name, value) pairs in the order of `RECORDED_HEADERS`
+pub fn recorded_headers(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    RECORDED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let values: Vec<&str> = headers
+                .get_all(*name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .collect();
+            if values.is_empty() {
+                return None;
+            }
+            let value: String = values
+                .join(", ")
+                .chars()
+                .take(MAX_HEADER_VALUE_LENGTH)
+                .collect();
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Extracts the media type of a Content-Type header value
+///
+/// Parameters such as the charset are dropped and the result is lowercase,
+/// e.g. "text/html" for "Text/HTML; charset=utf-8".
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+}
+
 /// Sends a HEAD request to check Content-Type before fetching
 ///
 /// # Arguments
@@ -702,6 +1097,86 @@ mod tests {
         assert!(format!("{:?}", client).contains("Client"));
     }
 
+    #[test]
+    fn test_media_type() {
+        assert_eq!(media_type("Text/HTML; charset=utf-8"), "text/html");
+        assert_eq!(media_type(" application/pdf "), "application/pdf");
+        assert_eq!(media_type(""), "");
+    }
+
+    #[test]
+    fn test_fetch_options_accepts() {
+        let options = FetchOptions::default();
+        assert!(options.accepts("text/html; charset=utf-8"));
+        assert!(options.accepts("application/xhtml+xml"));
+        assert!(!options.accepts("application/pdf"));
+        assert!(!options.accepts("text/plain"));
+        // Servers omitting the header usually serve HTML
+        assert!(options.accepts(""));
+
+        let options = FetchOptions {
+            content_types: vec!["text/*".to_string()],
+            ..Default::default()
+        };
+        assert!(options.accepts("text/plain"));
+        assert!(options.accepts("TEXT/HTML"));
+        assert!(!options.accepts("image/png"));
+    }
+
+    #[test]
+    fn test_recorded_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Server", HeaderValue::from_static("nginx/1.24"));
+        headers.append("cache-control", HeaderValue::from_static("no-cache"));
+        headers.append("cache-control", HeaderValue::from_static("no-store"));
+        headers.insert("x-powered-by", HeaderValue::from_static(" "));
+        headers.insert("etag", HeaderValue::from_static("\"abc\""));
+
+        assert_eq!(
+            recorded_headers(&headers),
+            vec![
+                ("server".to_string(), "nginx/1.24".to_string()),
+                (
+                    "cache-control".to_string(),
+                    "no-cache, no-store".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fetch_options_for_domain() {
+        use crate::config::TimeoutEntry;
+
+        let mut config: Config = toml::from_str(crate::config::EXAMPLE_CONFIG).unwrap();
+        config.timeouts = vec![
+            TimeoutEntry {
+                domain: "*.Archive.org".to_string(),
+                timeout_secs: 120,
+            },
+            TimeoutEntry {
+                domain: "flaky.net".to_string(),
+                timeout_secs: 5,
+            },
+        ];
+
+        let timeout = |domain| FetchOptions::for_domain(&config, domain).timeout;
+        assert_eq!(timeout("web.archive.org"), Duration::from_secs(120));
+        assert_eq!(timeout("flaky.net"), Duration::from_secs(5));
+        assert_eq!(timeout("other.com"), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_head_outcome_merge() {
+        use HeadOutcome::*;
+        assert_eq!(Skipped.merge(Skipped), Skipped);
+        assert_eq!(Skipped.merge(Supported), Supported);
+        assert_eq!(Supported.merge(Mishandled), Mishandled);
+        assert_eq!(Mishandled.merge(Supported), Mishandled);
+    }
+
     #[test]
     fn test_retry_policy_default() {
         let policy = RetryPolicy::default();
@@ -719,6 +1194,27 @@ mod tests {
         assert_eq!(policy.delay_for_attempt(3), Duration::from_secs(40));
     }
 
+    #[test]
+    fn test_retry_policy_delay_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay_for_attempt(4), Duration::from_secs(60));
+        assert_eq!(policy.delay_for_attempt(40), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retry_policy_jitter() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.jittered_delay(1, 0.0), Duration::from_secs(10));
+        assert_eq!(policy.jittered_delay(1, 0.5), Duration::from_millis(7500));
+        assert!(policy.jittered_delay(1, 0.999) > Duration::from_secs(5));
+
+        let fixed = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(fixed.jittered_delay(1, 0.9), Duration::from_secs(10));
+    }
+
     #[test]
     fn test_is_retryable_5xx() {
         assert!(RetryPolicy::is_retryable(
@@ -767,6 +1263,47 @@ mod tests {
         assert!(RetryPolicy::is_retryable(None, false, true));
     }
 
+    #[test]
+    fn test_redirect_chain_error() {
+        let mut chain = RedirectChain::new();
+        assert!(chain.add_url("https://example.com/a"));
+        assert!(chain.add_url("https://example.com/b"));
+        assert!(!chain.add_url("https://example.com/a"));
+        match chain.error("Redirect loop detected".to_string()) {
+            FetchResult::RedirectError { error, chain } => {
+                assert_eq!(error, "Redirect loop detected");
+                assert_eq!(
+                    chain,
+                    vec![
+                        "https://example.com/a",
+                        "https://example.com/b",
+                        "https://example.com/a"
+                    ]
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_is_server_failure() {
+        let http = |status_code| FetchResult::HttpError {
+            status_code,
+            state: PageState::Failed,
+        };
+        assert!(http(503).is_server_failure());
+        assert!(!http(404).is_server_failure());
+        assert!(FetchResult::NetworkError {
+            error: "connection refused".to_string(),
+            state: PageState::Unreachable,
+        }
+        .is_server_failure());
+        assert!(!FetchResult::ContentMismatch {
+            content_type: "image/png".to_string(),
+        }
+        .is_server_failure());
+    }
+
     // Additional tests would require mocking HTTP responses
     // These would be implemented with wiremock in integration tests
 }
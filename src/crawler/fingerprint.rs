@@ -0,0 +1,206 @@
+//! Technology fingerprinting of crawled pages
+//!
+//! Recognizes the platform behind a page from three kinds of hints: the
+//! generator meta tag, the URLs of its scripts and stylesheets, and the
+//! recorded response headers. The detection is deliberately lightweight: a
+//! fixed list of substrings per technology, no scripts are run.
+
+use crate::crawler::ParsedPage;
+
+/// Hints that identify one technology
+struct Signature {
+    /// Name reported for the technology
+    technology: &'static str,
+
+    /// Lowercase substrings of the generator meta tag
+    generator: &'static [&'static str],
+
+    /// Lowercase substrings of script or stylesheet URLs
+    assets: &'static [&'static str],
+
+    /// (header name, lowercase substring of its value)
+    headers: &'static [(&'static str, &'static str)],
+}
+
+/// Known technologies and their hints
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        technology: "WordPress",
+        generator: &["wordpress"],
+        assets: &["/wp-content/", "/wp-includes/"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Drupal",
+        generator: &["drupal"],
+        assets: &["/sites/default/files/", "/core/misc/drupal"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Joomla",
+        generator: &["joomla"],
+        assets: &["/media/jui/", "/media/system/js/"],
+        headers: &[],
+    },
+    Signature {
+        technology: "MediaWiki",
+        generator: &["mediawiki"],
+        assets: &["/load.php?"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Shopify",
+        generator: &[],
+        assets: &["cdn.shopify.com"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Squarespace",
+        generator: &["squarespace"],
+        assets: &["static1.squarespace.com"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Wix",
+        generator: &["wix.com"],
+        assets: &["static.parastorage.com"],
+        headers: &[],
+    },
+    Signature {
+        technology: "Ghost",
+        generator: &["ghost"],
+        assets: &[],
+        headers: &[],
+    },
+    Signature {
+        technology: "Hugo",
+        generator: &["hugo"],
+        assets: &[],
+        headers: &[],
+    },
+    Signature {
+        technology: "Jekyll",
+        generator: &["jekyll"],
+        assets: &[],
+        headers: &[],
+    },
+    Signature {
+        technology: "Next.js",
+        generator: &[],
+        assets: &["/_next/static/"],
+        headers: &[("x-powered-by", "next.js")],
+    },
+    Signature {
+        technology: "PHP",
+        generator: &[],
+        assets: &[],
+        headers: &[("x-powered-by", "php")],
+    },
+    Signature {
+        technology: "ASP.NET",
+        generator: &[],
+        assets: &[],
+        headers: &[("x-powered-by", "asp.net")],
+    },
+    Signature {
+        technology: "Express",
+        generator: &[],
+        assets: &[],
+        headers: &[("x-powered-by", "express")],
+    },
+    Signature {
+        technology: "Cloudflare",
+        generator: &[],
+        assets: &[],
+        headers: &[("server", "cloudflare")],
+    },
+];
+
+/// Detects the technologies a page was built with
+///
+/// # Arguments
+///
+/// * `page` - The parsed page, with its generator tag and asset URLs
+/// * `headers` - The recorded response headers, as (lowercase name, value)
+///
+/// # Returns
+///
+/// The names of the detected technologies, in a fixed order
+pub fn detect_technologies(page: &ParsedPage, headers: &[(String, String)]) -> Vec<&'static str> {
+    let generator = page.generator.as_deref().unwrap_or("").to_lowercase();
+    let assets: Vec<String> = page
+        .assets
+        .iter()
+        .map(|asset| asset.to_lowercase())
+        .collect();
+
+    SIGNATURES
+        .iter()
+        .filter(|signature| {
+            signature
+                .generator
+                .iter()
+                .any(|hint| generator.contains(hint))
+                || signature
+                    .assets
+                    .iter()
+                    .any(|hint| assets.iter().any(|asset| asset.contains(hint)))
+                || signature.headers.iter().any(|(name, hint)| {
+                    headers.iter().any(|(header, value)| {
+                        header == name && value.to_lowercase().contains(hint)
+                    })
+                })
+        })
+        .map(|signature| signature.technology)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(generator: Option<&str>, assets: &[&str]) -> ParsedPage {
+        ParsedPage {
+            title: None,
+            links: vec![],
+            embedded_links: vec![],
+            emails: vec![],
+            generator: generator.map(str::to_string),
+            robots_meta: vec![],
+            assets: assets.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_detect_from_generator_and_assets() {
+        let wordpress = page(
+            Some("WordPress 6.4.2"),
+            &["https://blog.example.com/wp-content/themes/t/style.css"],
+        );
+        assert_eq!(detect_technologies(&wordpress, &[]), vec!["WordPress"]);
+
+        let shop = page(None, &["https://cdn.shopify.com/s/files/app.js"]);
+        assert_eq!(detect_technologies(&shop, &[]), vec!["Shopify"]);
+
+        let wiki = page(Some("MediaWiki 1.41.0"), &[]);
+        assert_eq!(detect_technologies(&wiki, &[]), vec!["MediaWiki"]);
+    }
+
+    #[test]
+    fn test_detect_from_headers() {
+        let headers = vec![
+            ("server".to_string(), "cloudflare".to_string()),
+            ("x-powered-by".to_string(), "PHP/8.2.1".to_string()),
+        ];
+        assert_eq!(
+            detect_technologies(&page(None, &[]), &headers),
+            vec!["PHP", "Cloudflare"]
+        );
+    }
+
+    #[test]
+    fn test_detect_nothing() {
+        let plain = page(None, &["https://example.com/static/app.js"]);
+        assert!(detect_technologies(&plain, &[]).is_empty());
+    }
+}
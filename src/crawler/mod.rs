@@ -3,21 +3,74 @@
 //! This module contains the core crawling logic, including:
 //! - HTTP fetching with retry logic
 //! - HTML parsing and link extraction
+//! - Technology fingerprinting
+//! - Harvesting email addresses and social profiles (opt-in)
+//! - Link count and page size anomaly detection
+//! - Recognizing error and interstitial page templates
+//! - Recognizing crawl traps (directory listings, calendars)
 //! - Request scheduling and rate limiting
+//! - The operator's local fetch policy
+//! - Crawl calendar (quiet hours)
+//! - Reloading classification lists mid-crawl
+//! - Seeding quality domains from sitemaps
+//! - Completion forecasting (ETA)
+//! - Streaming crawl events to library users
+//! - Per-page processing plugins and the builder registering them
+//! - Replaying responses archived in WARC files
 //! - Overall crawl coordination
 
+mod anomaly;
+mod builder;
+mod calendar;
+mod contacts;
 mod coordinator;
+mod error_template;
+mod eta;
+mod events;
 mod fetcher;
+mod fingerprint;
 mod parser;
+mod policy;
+mod processor;
+mod reload;
+mod replay;
 mod scheduler;
+mod sitemap;
+mod trap;
 
-pub use coordinator::{run_crawl, Coordinator};
-pub use fetcher::{build_http_client, fetch_url, FetchResult};
-pub use parser::{extract_links_simple, parse_html};
-pub use scheduler::Scheduler;
+pub use anomaly::{detect_anomalies, HUGE_BODY_BYTES, TINY_BODY_BYTES};
+pub use builder::CrawlerBuilder;
+pub use calendar::CrawlCalendar;
+pub use contacts::{find_contacts, SOCIAL_PLATFORMS};
+pub use coordinator::{run_crawl, Coordinator, CrawlMode};
+pub use error_template::{template_hash, MIN_ECHOED_PATH_LEN};
+pub use eta::{forecast, format_eta, CrawlForecast, EtaEstimator, MIN_PAGES_FOR_ETA};
+pub use events::{CrawlEvent, CrawlEventStream};
+pub use fetcher::{
+    build_http_client, fetch_url, fetch_url_with_options, media_type, recorded_headers,
+    FetchFuture, FetchOptions, FetchOutcome, FetchResult, Fetcher, HeadOutcome, HttpFetcher,
+    RECORDED_HEADERS, SLOW_HEAD_THRESHOLD,
+};
+pub use fingerprint::detect_technologies;
+pub use parser::{extract_links_simple, parse_html, parse_text, sanitize_title, ParsedPage};
+pub use policy::LocalPolicy;
+pub use processor::{FetchedPage, PageProcessor, ProcessorError};
+pub use reload::{
+    apply_classification_overrides, merge_classification_lists, ConfigWatcher,
+    CONFIG_CHECK_INTERVAL,
+};
+pub use replay::{ArchivedResponse, ReplayFetcher};
+pub use scheduler::{inlink_priority, Scheduler, INLINK_PRIORITY_LEVELS, NEW_DOMAIN_BOOST};
+pub use sitemap::{fetch_sitemap_seeds, parse_sitemap, sitemap_locations, SitemapDocument};
+pub use tokio_util::sync::CancellationToken;
+pub use trap::{detect_trap, TrapKind};
 
-use crate::config::Config;
+// Shared with robots.txt parsing, so it lives in state
+pub use crate::state::TimeWindow;
+
+use crate::config::{Config, ConfigOverrides};
 use crate::SumiError;
+use std::path::Path;
 
 /// Runs a complete crawl operation
 ///
@@ -40,3 +93,44 @@ use crate::SumiError;
 pub async fn crawl(config: Config) -> Result<(), SumiError> {
     run_crawl(config).await
 }
+
+/// Runs a complete crawl operation, reloading classification lists on change
+///
+/// Behaves like [`crawl`], but re-reads `config_path` when the file changes or
+/// the process receives SIGHUP, applying the new quality, blacklist and stub
+/// lists and dropping queued URLs on newly blacklisted or stubbed domains.
+/// A configuration loaded from the environment has no file to watch and is
+/// not reloaded.
+///
+/// # Arguments
+///
+/// * `config` - The crawler configuration
+/// * `config_path` - Path the configuration was loaded from, `None` if it
+///   came from the environment
+/// * `overrides` - Environment and command-line overrides applied when loading
+/// * `mode` - How to treat the state left by earlier runs
+/// * `cancel` - Stops the crawl, leaving the run to be resumed, when cancelled
+/// * `fetcher` - Fetches pages instead of HTTP, e.g. a [`ReplayFetcher`]
+///
+/// # Returns
+///
+/// * `Ok(i64)` - Crawl completed or was cancelled; the ID of the run
+/// * `Err(SumiError)` - Crawl failed
+pub async fn crawl_with_reload(
+    config: Config,
+    config_path: Option<&Path>,
+    overrides: ConfigOverrides,
+    mode: CrawlMode,
+    cancel: CancellationToken,
+    fetcher: Option<Box<dyn Fetcher>>,
+) -> Result<i64, SumiError> {
+    let mut coordinator = Coordinator::with_mode(config, mode)?;
+    if let Some(fetcher) = fetcher {
+        coordinator.replace_fetcher(fetcher);
+    }
+    if let Some(config_path) = config_path {
+        coordinator.watch_config(config_path, overrides);
+    }
+    coordinator.run_until_cancelled(cancel).await?;
+    Ok(coordinator.run_id())
+}
@@ -1,11 +1,16 @@
 //! HTML parser for extracting links and metadata
 //!
-//! This module handles parsing HTML content to extract:
+//! This module handles parsing HTML content (and URLs in plain text) to extract:
 //! - Links to follow (from <a> tags and canonical links)
+//! - Links from iframes, image maps and meta refresh, tagged with their kind
 //! - Page title
-//! - Other metadata as needed
+//! - The generator meta tag and script/stylesheet URLs (for fingerprinting)
 
+use crate::storage::LinkKind;
+use crate::url::is_route_fragment;
 use scraper::{Html, Selector};
+use std::collections::HashSet;
+use std::hash::Hash;
 use url::Url;
 
 /// Extracted information from an HTML page
@@ -14,8 +19,26 @@ pub struct ParsedPage {
     /// The page title (from <title> tag)
     pub title: Option<String>,
 
-    /// All links found on the page (absolute URLs)
+    /// All links found on the page (absolute URLs), each listed once in
+    /// order of first occurrence
     pub links: Vec<String>,
+
+    /// Links from `<iframe src>`, `<area href>` and `<meta http-equiv="refresh">`
+    /// (absolute URLs), followed only when enabled in the configuration
+    pub embedded_links: Vec<(LinkKind, String)>,
+
+    /// Addresses of `mailto:` links (lowercase, without query)
+    pub emails: Vec<String>,
+
+    /// Content of the `<meta name="generator">` tag
+    pub generator: Option<String>,
+
+    /// Contents of the `<meta name="robots">` tags
+    pub robots_meta: Vec<String>,
+
+    /// Script and stylesheet URLs (absolute), not followed but used to
+    /// recognize the software behind the page
+    pub assets: Vec<String>,
 }
 
 /// Parses HTML content and extracts links and metadata
@@ -26,6 +49,11 @@ pub struct ParsedPage {
 /// - `<a href="...">` tags in body, nav, header, footer
 /// - `<link rel="canonical" href="...">`
 ///
+/// **Embedded links** (kept apart in `embedded_links`):
+/// - `<iframe src="...">`
+/// - `<area href="...">`
+/// - `<meta http-equiv="refresh" content="5; url=...">`
+///
 /// **Exclude:**
 /// - `<link rel="stylesheet" ...>`
 /// - `<script src="...">`
@@ -66,18 +94,98 @@ pub fn parse_html(html: &str, base_url: &Url) -> Result<ParsedPage, String> {
     // Extract links
     let links = extract_links(&document, base_url)?;
 
-    Ok(ParsedPage { title, links })
+    Ok(ParsedPage {
+        title,
+        links,
+        embedded_links: extract_embedded_links(&document, base_url),
+        emails: extract_emails(&document),
+        generator: extract_generator(&document),
+        robots_meta: extract_robots_meta(&document),
+        assets: extract_assets(&document, base_url),
+    })
+}
+
+/// Extracts the URLs written out in a plain text document
+///
+/// Every whitespace-separated word starting with `http://` or `https://` is
+/// a link; trailing punctuation (as in "see https://example.com/.") and
+/// enclosing brackets are removed. Plain text has no title.
+///
+/// # Arguments
+///
+/// * `text` - The text content
+/// * `base_url` - The URL of the document
+///
+/// # Returns
+///
+/// The links found, as a page without title
+pub fn parse_text(text: &str, base_url: &Url) -> ParsedPage {
+    let mut links: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.trim_start_matches(['(', '<', '[', '"', '\''])
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '>', ']', '"', '\''])
+        })
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .filter_map(|word| resolve_link(word, base_url))
+        .collect();
+    dedupe(&mut links);
+
+    ParsedPage {
+        title: None,
+        links,
+        embedded_links: Vec::new(),
+        emails: Vec::new(),
+        generator: None,
+        robots_meta: Vec::new(),
+        assets: Vec::new(),
+    }
 }
 
 /// Extracts the page title from the HTML document
+///
+/// Whitespace and control characters are normalized; the title is not
+/// truncated.
 fn extract_title(document: &Html) -> Option<String> {
     let title_selector = Selector::parse("title").ok()?;
 
     document
         .select(&title_selector)
         .next()
-        .map(|element| element.text().collect::<String>().trim().to_string())
-        .filter(|s| !s.is_empty())
+        .and_then(|element| sanitize_title(&element.text().collect::<String>(), usize::MAX))
+}
+
+/// Cleans up a page title for storage and reports
+///
+/// Control characters become spaces, runs of whitespace collapse to a single
+/// space, and titles longer than `max_chars` characters are cut at a
+/// character boundary and end with "…".
+///
+/// # Arguments
+///
+/// * `title` - The raw title text
+/// * `max_chars` - Maximum length of the result in characters
+///
+/// # Returns
+///
+/// The cleaned title, or `None` if nothing but whitespace remains
+pub fn sanitize_title(title: &str, max_chars: usize) -> Option<String> {
+    let words: Vec<&str> = title
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let title = words.join(" ");
+    if title.chars().count() <= max_chars {
+        return Some(title);
+    }
+    let mut truncated: String = title.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push('…');
+    Some(truncated)
 }
 
 /// Extracts all valid links from the HTML document
@@ -111,9 +219,171 @@ fn extract_links(document: &Html, base_url: &Url) -> Result<Vec<String>, String>
         }
     }
 
+    // Navigation, footers and body often link the same pages
+    dedupe(&mut links);
+
     Ok(links)
 }
 
+/// Removes repeated items, keeping the first occurrence of each
+fn dedupe<T: Eq + Hash + Clone>(items: &mut Vec<T>) {
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.clone()));
+}
+
+/// Extracts the targets of iframes, image map areas and meta refreshes
+fn extract_embedded_links(document: &Html, base_url: &Url) -> Vec<(LinkKind, String)> {
+    let mut links = Vec::new();
+
+    if let Ok(selector) = Selector::parse("iframe[src]") {
+        for element in document.select(&selector) {
+            if let Some(absolute_url) = element
+                .value()
+                .attr("src")
+                .and_then(|src| resolve_link(src, base_url))
+            {
+                links.push((LinkKind::Iframe, absolute_url));
+            }
+        }
+    }
+
+    if let Ok(selector) = Selector::parse("area[href]") {
+        for element in document.select(&selector) {
+            if let Some(absolute_url) = element
+                .value()
+                .attr("href")
+                .and_then(|href| resolve_link(href, base_url))
+            {
+                links.push((LinkKind::Area, absolute_url));
+            }
+        }
+    }
+
+    if let Ok(selector) = Selector::parse("meta[http-equiv][content]") {
+        for element in document.select(&selector) {
+            let is_refresh = element
+                .value()
+                .attr("http-equiv")
+                .is_some_and(|name| name.eq_ignore_ascii_case("refresh"));
+            if !is_refresh {
+                continue;
+            }
+            if let Some(absolute_url) = element
+                .value()
+                .attr("content")
+                .and_then(refresh_target)
+                .and_then(|target| resolve_link(target, base_url))
+            {
+                links.push((LinkKind::MetaRefresh, absolute_url));
+            }
+        }
+    }
+
+    dedupe(&mut links);
+    links
+}
+
+/// Extracts the URL from the content of a meta refresh, e.g. `5; url=/next`
+///
+/// Returns None when the refresh only reloads the page.
+fn refresh_target(content: &str) -> Option<&str> {
+    let (_, rest) = content.split_once([';', ','])?;
+    let rest = rest.trim_start();
+    if !rest.get(..3)?.eq_ignore_ascii_case("url") {
+        return None;
+    }
+    let target = rest[3..].trim_start().strip_prefix('=')?.trim();
+    let target = target.trim_matches(['\'', '"']);
+    (!target.is_empty()).then_some(target)
+}
+
+/// Extracts the addresses of `mailto:` links
+///
+/// A link may name several comma-separated addresses; anything after `?`
+/// (subject, body) is dropped.
+fn extract_emails(document: &Html) -> Vec<String> {
+    let mut emails: Vec<String> = Vec::new();
+
+    if let Ok(selector) = Selector::parse("a[href]") {
+        for element in document.select(&selector) {
+            let Some(href) = element.value().attr("href").map(str::trim) else {
+                continue;
+            };
+            if !href
+                .get(..7)
+                .is_some_and(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+            {
+                continue;
+            }
+            let addresses = href[7..].split('?').next().unwrap_or("");
+            for address in addresses.split(',') {
+                let address = address.trim().to_lowercase();
+                if address.contains('@') && !emails.contains(&address) {
+                    emails.push(address);
+                }
+            }
+        }
+    }
+
+    emails
+}
+
+/// Extracts the content of the generator meta tag
+fn extract_generator(document: &Html) -> Option<String> {
+    let selector = Selector::parse("meta[name][content]").ok()?;
+
+    document
+        .select(&selector)
+        .find(|element| {
+            element
+                .value()
+                .attr("name")
+                .is_some_and(|name| name.eq_ignore_ascii_case("generator"))
+        })
+        .and_then(|element| element.value().attr("content"))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// Extracts the contents of the `<meta name="robots">` tags
+fn extract_robots_meta(document: &Html) -> Vec<String> {
+    let Ok(selector) = Selector::parse("meta[name][content]") else {
+        return Vec::new();
+    };
+
+    document
+        .select(&selector)
+        .filter(|element| {
+            element
+                .value()
+                .attr("name")
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+        })
+        .filter_map(|element| element.value().attr("content"))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+        .collect()
+}
+
+/// Extracts the URLs of scripts and stylesheets
+fn extract_assets(document: &Html, base_url: &Url) -> Vec<String> {
+    let mut assets = Vec::new();
+
+    if let Ok(selector) = Selector::parse("script[src], link[rel='stylesheet'][href]") {
+        for element in document.select(&selector) {
+            let src = element
+                .value()
+                .attr("src")
+                .or_else(|| element.value().attr("href"));
+            if let Some(absolute_url) = src.and_then(|src| resolve_link(src, base_url)) {
+                assets.push(absolute_url);
+            }
+        }
+    }
+
+    assets
+}
+
 /// Resolves a link href to an absolute URL and validates it
 ///
 /// Returns None if the link should be excluded:
@@ -138,8 +408,12 @@ fn resolve_link(href: &str, base_url: &Url) -> Option<String> {
         return None;
     }
 
-    // Skip fragment-only links (same page anchors)
-    if href.starts_with('#') {
+    // Skip fragment-only links (same page anchors), but keep client-side
+    // routes such as "#!/about"; normalization decides whether they count
+    if href
+        .strip_prefix('#')
+        .is_some_and(|fragment| !is_route_fragment(fragment))
+    {
         return None;
     }
 
@@ -181,6 +455,53 @@ mod tests {
         Url::parse("https://example.com/page").unwrap()
     }
 
+    #[test]
+    fn test_parse_text() {
+        let text = "Mirrors:\nhttps://a.com/x, (https://b.com/y).\nnot/a/link ftp://c.com/ <https://d.com/>";
+        let parsed = parse_text(text, &base_url());
+        assert_eq!(parsed.title, None);
+        assert_eq!(
+            parsed.links,
+            vec!["https://a.com/x", "https://b.com/y", "https://d.com/"]
+        );
+    }
+
+    #[test]
+    fn test_extract_generator_and_assets() {
+        let html = r#"<html><head>
+            <meta name="Generator" content=" WordPress 6.4 ">
+            <link rel="stylesheet" href="/wp-content/themes/x/style.css">
+            <script src="https://cdn.example.net/app.js"></script>
+            <script>inline()</script>
+            </head><body><a href="/about">About</a></body></html>"#;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(parsed.generator, Some("WordPress 6.4".to_string()));
+        assert_eq!(
+            parsed.assets,
+            vec![
+                "https://example.com/wp-content/themes/x/style.css",
+                "https://cdn.example.net/app.js"
+            ]
+        );
+        // Assets are not followed
+        assert_eq!(parsed.links, vec!["https://example.com/about"]);
+    }
+
+    #[test]
+    fn test_extract_robots_meta() {
+        let html = r#"<html><head>
+            <meta name="ROBOTS" content=" noarchive, nosnippet ">
+            <meta name="robots" content="">
+            <meta name="otherbot" content="noindex">
+            <meta name="robots" content="unavailable_after: 2027-11-03">
+            </head><body></body></html>"#;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(
+            parsed.robots_meta,
+            vec!["noarchive, nosnippet", "unavailable_after: 2027-11-03"]
+        );
+    }
+
     #[test]
     fn test_extract_title() {
         let html = r#"<html><head><title>Test Page</title></head><body></body></html>"#;
@@ -195,6 +516,25 @@ mod tests {
         assert_eq!(parsed.title, Some("Test Page".to_string()));
     }
 
+    #[test]
+    fn test_extract_title_normalizes_whitespace() {
+        let html = "<html><head><title>\n  Test\t\tPage\u{0007}  Home \r\n</title></head></html>";
+        let parsed = parse_html(html, &Url::parse("https://example.com/").unwrap()).unwrap();
+        assert_eq!(parsed.title, Some("Test Page Home".to_string()));
+    }
+
+    #[test]
+    fn test_sanitize_title_truncates() {
+        assert_eq!(
+            sanitize_title("Über lange Seite", 8),
+            Some("Über la…".to_string())
+        );
+        // No dangling space before the ellipsis
+        assert_eq!(sanitize_title("abc def", 5), Some("abc…".to_string()));
+        assert_eq!(sanitize_title("short", 5), Some("short".to_string()));
+        assert_eq!(sanitize_title(" \u{0000}\t ", 10), None);
+    }
+
     #[test]
     fn test_no_title() {
         let html = r#"<html><head></head><body></body></html>"#;
@@ -268,6 +608,19 @@ mod tests {
         assert_eq!(parsed.links.len(), 0);
     }
 
+    #[test]
+    fn test_keep_route_fragments() {
+        let html = r##"<a href="#!/users">Users</a><a href="#/settings">Settings</a><a href="#top">Top</a>"##;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(
+            parsed.links,
+            vec![
+                "https://example.com/page#!/users",
+                "https://example.com/page#/settings"
+            ]
+        );
+    }
+
     #[test]
     fn test_follow_nofollow_links() {
         let html = r#"<html><body><a href="/page" rel="nofollow">Link</a></body></html>"#;
@@ -315,4 +668,89 @@ mod tests {
         let parsed = parse_html(html, &base_url()).unwrap();
         assert_eq!(parsed.links.len(), 2);
     }
+
+    #[test]
+    fn test_extract_embedded_links() {
+        let html = r#"
+            <html>
+            <head><meta http-equiv="Refresh" content="5; URL='/moved'"></head>
+            <body>
+                <a href="/page1">Link</a>
+                <iframe src="/frame"></iframe>
+                <iframe src="javascript:void(0)"></iframe>
+                <map name="nav"><area shape="rect" coords="0,0,10,10" href="/area"></map>
+            </body>
+            </html>
+        "#;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(parsed.links, vec!["https://example.com/page1"]);
+        assert_eq!(
+            parsed.embedded_links,
+            vec![
+                (LinkKind::Iframe, "https://example.com/frame".to_string()),
+                (LinkKind::Area, "https://example.com/area".to_string()),
+                (
+                    LinkKind::MetaRefresh,
+                    "https://example.com/moved".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_refresh_target() {
+        assert_eq!(
+            refresh_target("0; url=https://a.com/"),
+            Some("https://a.com/")
+        );
+        assert_eq!(refresh_target("0;URL = \"/next\""), Some("/next"));
+        assert_eq!(refresh_target("3, url=/next"), Some("/next"));
+        assert_eq!(refresh_target("30"), None);
+        assert_eq!(refresh_target("0; url="), None);
+        assert_eq!(refresh_target("0; u"), None);
+    }
+
+    #[test]
+    fn test_extract_emails() {
+        let html = r#"
+            <a href="mailto:Info@Example.com?subject=Hello">Mail</a>
+            <a href="MAILTO:a@example.com,b@example.com">Both</a>
+            <a href="mailto:info@example.com">Again</a>
+            <a href="mailto:">Empty</a>
+        "#;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(
+            parsed.emails,
+            vec!["info@example.com", "a@example.com", "b@example.com"]
+        );
+        assert!(parsed.links.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_links_are_listed_once() {
+        let html = r#"
+            <html>
+            <head><link rel="canonical" href="https://example.com/page"></head>
+            <body>
+                <nav><a href="/about">About</a><a href="/page">Home</a></nav>
+                <a href="/contact">Contact</a>
+                <footer><a href="/about">About</a><a href="https://example.com/contact">Contact</a></footer>
+                <iframe src="/frame"></iframe><iframe src="/frame"></iframe>
+            </body>
+            </html>
+        "#;
+        let parsed = parse_html(html, &base_url()).unwrap();
+        assert_eq!(
+            parsed.links,
+            vec![
+                "https://example.com/about",
+                "https://example.com/page",
+                "https://example.com/contact"
+            ]
+        );
+        assert_eq!(parsed.embedded_links.len(), 1);
+
+        let parsed = parse_text("https://a.com/ and again https://a.com/", &base_url());
+        assert_eq!(parsed.links, vec!["https://a.com/"]);
+    }
 }
@@ -0,0 +1,96 @@
+//! Local fetch policy
+//!
+//! The `[policy]` section lets operators impose their own rules on top of
+//! robots.txt and the domain lists: regular expressions matched against every
+//! URL before it is fetched. A matching URL is recorded with the
+//! `policy_denied` state and never requested, whatever its domain.
+
+use crate::config::PolicyConfig;
+use crate::ConfigError;
+use regex::Regex;
+
+/// Compiled deny rules of the local policy
+#[derive(Debug, Clone, Default)]
+pub struct LocalPolicy {
+    deny: Vec<Regex>,
+}
+
+impl LocalPolicy {
+    /// Compiles the deny rules of the policy configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The policy configuration
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LocalPolicy)` - The compiled policy
+    /// * `Err(ConfigError)` - A rule is not a valid regular expression
+    pub fn from_config(config: &PolicyConfig) -> Result<Self, ConfigError> {
+        let deny = config
+            .deny
+            .iter()
+            .map(|rule| {
+                Regex::new(rule).map_err(|e| {
+                    ConfigError::Validation(format!("Invalid policy deny rule '{}': {}", rule, e))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { deny })
+    }
+
+    /// Finds the first deny rule matching a URL
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL about to be fetched
+    ///
+    /// # Returns
+    ///
+    /// The rule as written in the configuration, or `None` if the URL may be
+    /// fetched
+    pub fn denying_rule(&self, url: &str) -> Option<&str> {
+        self.deny
+            .iter()
+            .find(|rule| rule.is_match(url))
+            .map(Regex::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(rules: &[&str]) -> Result<LocalPolicy, ConfigError> {
+        LocalPolicy::from_config(&PolicyConfig {
+            deny: rules.iter().map(|rule| rule.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_denying_rule() {
+        let policy = policy(&["(?i)/wp-admin/", "[?&]sessionid="]).unwrap();
+        assert_eq!(
+            policy.denying_rule("https://a.com/WP-Admin/edit.php"),
+            Some("(?i)/wp-admin/")
+        );
+        assert_eq!(
+            policy.denying_rule("https://b.org/page?x=1&sessionid=42"),
+            Some("[?&]sessionid=")
+        );
+        assert_eq!(policy.denying_rule("https://a.com/blog/"), None);
+
+        // Without rules everything may be fetched
+        assert_eq!(
+            LocalPolicy::default().denying_rule("https://a.com/wp-admin/"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule() {
+        let result = policy(&["/ok/", "(unclosed"]);
+        assert!(matches!(result, Err(ConfigError::Validation(_))));
+    }
+}
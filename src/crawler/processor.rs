@@ -0,0 +1,121 @@
+//! Per-page processing plugins
+//!
+//! The coordinator keeps only what it needs of a fetched page (state, title,
+//! headers, links) and drops the body. A `PageProcessor` sees every fetched
+//! page while the body is still around, so library users can add keyword
+//! extraction, screenshot triggers or their own storage without changing the
+//! coordinator. Processors are registered on the `CrawlerBuilder`.
+
+use crate::crawler::ParsedPage;
+use crate::url::DomainClassification;
+use url::Url;
+
+/// Error returned by a page processor
+pub type ProcessorError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A fetched and parsed page, as handed to page processors
+#[derive(Debug, Clone, Copy)]
+pub struct FetchedPage<'a> {
+    /// The page ID in storage
+    pub page_id: i64,
+
+    /// The URL the page was requested as
+    pub url: &'a Url,
+
+    /// The URL after redirects
+    pub final_url: &'a str,
+
+    /// The page's domain
+    pub domain: &'a str,
+
+    /// Classification of the page's domain
+    pub classification: DomainClassification,
+
+    /// HTTP status code
+    pub status_code: u16,
+
+    /// The Content-Type of the response
+    pub content_type: &'a str,
+
+    /// The recorded response headers, as (lowercase name, value)
+    pub headers: &'a [(String, String)],
+
+    /// The response body
+    pub body: &'a str,
+
+    /// Title, links and other metadata parsed from the body
+    pub parsed: &'a ParsedPage,
+}
+
+/// Runs custom logic on every page the crawler fetches
+///
+/// Processors run in registration order, after the page is stored and before
+/// its links are followed. An error is logged and does not stop the crawl.
+/// Any `Fn(&FetchedPage) -> Result<(), ProcessorError>` closure is a
+/// processor too.
+pub trait PageProcessor: Send + Sync {
+    /// Processes a fetched page
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The fetched page with its body and parsed metadata
+    fn process(&self, page: &FetchedPage<'_>) -> Result<(), ProcessorError>;
+}
+
+impl<F> PageProcessor for F
+where
+    F: Fn(&FetchedPage<'_>) -> Result<(), ProcessorError> + Send + Sync,
+{
+    fn process(&self, page: &FetchedPage<'_>) -> Result<(), ProcessorError> {
+        self(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_closure_processor() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let parsed = ParsedPage {
+            title: Some("Home".to_string()),
+            links: vec![],
+            embedded_links: vec![],
+            emails: vec![],
+            generator: None,
+            robots_meta: vec![],
+            assets: vec![],
+        };
+        let page = FetchedPage {
+            page_id: 1,
+            url: &url,
+            final_url: "https://example.com/",
+            domain: "example.com",
+            classification: DomainClassification::Quality,
+            status_code: 200,
+            content_type: "text/html",
+            headers: &[],
+            body: "<html>rust and tokio</html>",
+            parsed: &parsed,
+        };
+
+        let words = Mutex::new(vec![]);
+        let processor = |page: &FetchedPage<'_>| -> Result<(), ProcessorError> {
+            if page.body.contains("rust") {
+                words.lock().unwrap().push(page.page_id);
+                Ok(())
+            } else {
+                Err("no keywords".into())
+            }
+        };
+        let processor: &dyn PageProcessor = &processor;
+
+        processor.process(&page).unwrap();
+        assert_eq!(*words.lock().unwrap(), vec![1]);
+        assert!(processor
+            .process(&FetchedPage { body: "", ..page })
+            .is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Configuration reloading for long-running crawls
+//!
+//! The watcher notices when the configuration file changes, either because
+//! the modification time of the file or of one of its includes moved or
+//! because the process received SIGHUP (on Unix). The coordinator then re-reads the file and applies the updated
+//! quality, blacklist and stub lists without restarting the run.
+
+use crate::config::{
+    load_layered_config, read_config_source, Config, ConfigOverrides, DomainEntry, QualityEntry,
+};
+use crate::storage::ClassificationOverride;
+use crate::url::DomainClassification;
+use crate::ConfigError;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the configuration files' modification times are checked
+pub const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Watches a configuration file for changes
+pub struct ConfigWatcher {
+    /// Path to the configuration file
+    path: PathBuf,
+
+    /// The configuration file and the files it includes, with their
+    /// modification times when the configuration was last loaded
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+
+    /// When the modification time was last checked
+    last_check: Instant,
+
+    /// Minimum time between modification time checks
+    interval: Duration,
+
+    /// Set when a reload was explicitly requested (SIGHUP)
+    reload_requested: Arc<AtomicBool>,
+
+    /// Environment and command-line overrides applied on every reload
+    overrides: ConfigOverrides,
+}
+
+impl ConfigWatcher {
+    /// Creates a watcher for the given configuration file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the TOML configuration file
+    /// * `interval` - Minimum time between modification time checks
+    pub fn new(path: &Path, interval: Duration) -> Self {
+        let mut watcher = Self {
+            path: path.to_path_buf(),
+            files: vec![(path.to_path_buf(), None)],
+            last_check: Instant::now(),
+            interval,
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            overrides: ConfigOverrides::new(),
+        };
+        watcher.record_files();
+        watcher
+    }
+
+    /// Sets the overrides applied on top of the file when it is reloaded
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - The overrides the crawl was started with
+    pub fn with_overrides(mut self, overrides: ConfigOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Returns the flag that requests a reload on the next check
+    pub fn reload_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.reload_requested)
+    }
+
+    /// Requests a reload on the next check
+    pub fn request_reload(&self) {
+        self.reload_requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Treats SIGHUP as a reload request (Unix only)
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn listen_for_sighup(&self) {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let flag = self.reload_flag();
+            match signal(SignalKind::hangup()) {
+                Ok(mut hangups) => {
+                    tokio::spawn(async move {
+                        while hangups.recv().await.is_some() {
+                            tracing::info!("Received SIGHUP, configuration will be reloaded");
+                            flag.store(true, Ordering::SeqCst);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to install SIGHUP handler: {}", e),
+            }
+        }
+    }
+
+    /// Checks whether the configuration should be reloaded and loads it if so
+    ///
+    /// # Returns
+    ///
+    /// * `None` - No reload was requested and the file has not changed
+    /// * `Some(Ok(Config))` - The freshly loaded and validated configuration
+    /// * `Some(Err(ConfigError))` - The file changed but could not be loaded
+    pub fn poll(&mut self) -> Option<Result<Config, ConfigError>> {
+        let requested = self.reload_requested.swap(false, Ordering::SeqCst);
+
+        if !requested {
+            if self.last_check.elapsed() < self.interval {
+                return None;
+            }
+            self.last_check = Instant::now();
+
+            if self
+                .files
+                .iter()
+                .all(|(path, modified)| modified_time(path) == *modified)
+            {
+                return None;
+            }
+        }
+
+        // Record the new modification times even if loading fails, so a broken
+        // file is reported once rather than on every check
+        self.record_files();
+        Some(load_layered_config(&self.path, &self.overrides).map(|(config, _)| config))
+    }
+
+    /// Records the files the configuration is read from and their modification times
+    ///
+    /// The includes are resolved again, since an edit may add or remove some.
+    /// If they cannot be resolved, e.g. while a file is broken, the files
+    /// found last time are kept so fixing it is noticed.
+    fn record_files(&mut self) {
+        let paths: Vec<PathBuf> = match read_config_source(&self.path) {
+            Ok(source) => source.files.into_iter().map(|(path, _)| path).collect(),
+            Err(_) => self.files.drain(..).map(|(path, _)| path).collect(),
+        };
+        self.files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+    }
+}
+
+/// Reads a file's modification time, if available
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Copies the domain classification lists from a reloaded configuration
+///
+/// Only the quality, blacklist and stub lists are applied mid-run; other
+/// settings (rate limits, output paths, user agent) keep their original values.
+/// New quality seeds are not queued until the next fresh run.
+///
+/// # Arguments
+///
+/// * `current` - The configuration the crawl is running with
+/// * `reloaded` - The configuration read from disk
+///
+/// # Returns
+///
+/// The current configuration with the reloaded classification lists
+pub fn merge_classification_lists(current: &Config, reloaded: Config) -> Config {
+    Config {
+        quality: reloaded.quality,
+        blacklist: reloaded.blacklist,
+        stub: reloaded.stub,
+        ..current.clone()
+    }
+}
+
+/// Adds classification overrides to a configuration's domain lists
+///
+/// Overrides are appended to the matching list, so the usual priority order
+/// (blacklist, stub, quality) still applies. Quality overrides carry no seeds;
+/// `Discovered` overrides have no effect.
+///
+/// # Arguments
+///
+/// * `config` - The configuration as read from the file
+/// * `overrides` - Overrides stored in the database
+///
+/// # Returns
+///
+/// The configuration with the overrides applied
+pub fn apply_classification_overrides(
+    config: &Config,
+    overrides: &[ClassificationOverride],
+) -> Config {
+    let mut config = config.clone();
+
+    for entry in overrides {
+        let domain = entry.domain.clone();
+        match entry.classification {
+            DomainClassification::Blacklisted => config.blacklist.push(DomainEntry { domain }),
+            DomainClassification::Stubbed => config.stub.push(DomainEntry { domain }),
+            DomainClassification::Quality => config.quality.push(QualityEntry {
+                domain,
+                seeds: Vec::new(),
+                seeds_file: None,
+                seeds_from_sitemap: false,
+            }),
+            DomainClassification::Discovered => {}
+        }
+    }
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{load_config, test_config_toml};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const QUALITY: &str = r#"
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+"#;
+
+    fn write_config(extra: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}{}", test_config_toml(QUALITY), extra).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_poll_without_changes() {
+        let file = write_config("");
+        let mut watcher = ConfigWatcher::new(file.path(), Duration::ZERO);
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_poll_after_request() {
+        let file = write_config("\n[[blacklist]]\ndomain = \"spam.com\"\n");
+        let mut watcher = ConfigWatcher::new(file.path(), Duration::from_secs(3600));
+
+        watcher.request_reload();
+        let config = watcher.poll().unwrap().unwrap();
+        assert_eq!(config.blacklist.len(), 1);
+
+        // The request is consumed
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_poll_detects_modification() {
+        let file = write_config("");
+        let mut watcher = ConfigWatcher::new(file.path(), Duration::ZERO);
+        watcher.files[0].1 = Some(SystemTime::UNIX_EPOCH);
+
+        assert!(watcher.poll().unwrap().is_ok());
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_poll_detects_modified_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("blacklist-common.toml");
+        std::fs::write(&shared, "[[blacklist]]\ndomain = \"spam.com\"\n").unwrap();
+        let main = dir.path().join("config.toml");
+        std::fs::write(
+            &main,
+            format!(
+                "include = [\"blacklist-common.toml\"]\n{}",
+                test_config_toml(QUALITY)
+            ),
+        )
+        .unwrap();
+
+        let mut watcher = ConfigWatcher::new(&main, Duration::ZERO);
+        assert_eq!(watcher.files.len(), 2);
+        assert!(watcher.poll().is_none());
+
+        // Touch only the included file
+        std::fs::write(
+            &shared,
+            "[[blacklist]]\ndomain = \"spam.com\"\n\n[[blacklist]]\ndomain = \"ads.net\"\n",
+        )
+        .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&shared)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        let config = watcher.poll().unwrap().unwrap();
+        assert_eq!(config.blacklist.len(), 2);
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_poll_reports_invalid_config() {
+        let file = write_config("");
+        let mut watcher = ConfigWatcher::new(file.path(), Duration::ZERO);
+        std::fs::write(file.path(), "not valid toml [").unwrap();
+
+        watcher.request_reload();
+        assert!(watcher.poll().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_merge_classification_lists() {
+        let file = write_config("");
+        let current = load_config(file.path()).unwrap();
+
+        let mut reloaded = current.clone();
+        reloaded.crawler.max_depth = 99;
+        reloaded.stub.push(DomainEntry {
+            domain: "stub.org".to_string(),
+        });
+
+        let merged = merge_classification_lists(&current, reloaded);
+        assert_eq!(merged.stub.len(), 1);
+        assert_eq!(merged.crawler.max_depth, 3);
+    }
+
+    #[test]
+    fn test_apply_classification_overrides() {
+        use crate::url::classify_domain;
+
+        let file = write_config("");
+        let config = load_config(file.path()).unwrap();
+        let overrides = vec![
+            ClassificationOverride {
+                domain: "example.com".to_string(),
+                classification: DomainClassification::Blacklisted,
+                created_at: String::new(),
+            },
+            ClassificationOverride {
+                domain: "*.ads.net".to_string(),
+                classification: DomainClassification::Stubbed,
+                created_at: String::new(),
+            },
+        ];
+
+        let applied = apply_classification_overrides(&config, &overrides);
+        assert_eq!(
+            classify_domain("example.com", &applied),
+            DomainClassification::Blacklisted
+        );
+        assert_eq!(
+            classify_domain("cdn.ads.net", &applied),
+            DomainClassification::Stubbed
+        );
+        // The original configuration is untouched
+        assert_eq!(
+            classify_domain("example.com", &config),
+            DomainClassification::Quality
+        );
+    }
+}
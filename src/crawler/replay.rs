@@ -0,0 +1,540 @@
+//! Replaying archived responses
+//!
+//! A `ReplayFetcher` serves the responses captured in a WARC file instead of
+//! fetching them, so a captured crawl can be parsed, classified and analyzed
+//! again with a new configuration without touching the network. robots.txt
+//! is served from the archive too; a domain without an archived robots.txt
+//! is treated as allowing everything.
+//!
+//! WARC files may be gzip-compressed (`.warc.gz`). Only `response` records
+//! with an HTTP payload are used; chunked and gzip-encoded bodies are
+//! decoded. A URL captured more than once is replayed from its last capture.
+
+use crate::crawler::fetcher::{
+    FetchFuture, FetchOptions, FetchOutcome, FetchResult, Fetcher, RedirectChain,
+};
+use crate::crawler::{HeadOutcome, RECORDED_HEADERS};
+use crate::state::PageState;
+use crate::url::normalize_url;
+use crate::SumiError;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use url::Url;
+
+/// An HTTP response captured in an archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedResponse {
+    /// HTTP status code
+    pub status_code: u16,
+
+    /// Response headers, as (lowercase name, value)
+    pub headers: Vec<(String, String)>,
+
+    /// The decoded response body
+    pub body: String,
+}
+
+impl ArchivedResponse {
+    /// The first value of a header
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serves archived responses as a `Fetcher`
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFetcher {
+    /// Responses by captured URL, and by its normalized form
+    responses: HashMap<String, ArchivedResponse>,
+}
+
+impl ReplayFetcher {
+    /// Loads the responses captured in a WARC file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to a `.warc` or `.warc.gz` file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReplayFetcher)` - The archived responses, ready to replay
+    /// * `Err(SumiError)` - The file could not be read or is not a WARC file
+    pub fn from_warc(path: &Path) -> Result<Self, SumiError> {
+        let data = std::fs::read(path)
+            .map_err(|e| SumiError::Replay(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::from_warc_bytes(&data)
+            .map_err(|e| SumiError::Replay(format!("Invalid WARC file {}: {}", path.display(), e)))
+    }
+
+    /// Loads the responses captured in WARC data
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The WARC records, optionally gzip-compressed
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReplayFetcher)` - The archived responses
+    /// * `Err(String)` - The data is not valid WARC
+    pub fn from_warc_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut fetcher = Self::default();
+        let data = if data.starts_with(&[0x1f, 0x8b]) {
+            let mut decoded = Vec::new();
+            MultiGzDecoder::new(data)
+                .read_to_end(&mut decoded)
+                .map_err(|e| format!("gzip: {}", e))?;
+            decoded
+        } else {
+            data.to_vec()
+        };
+
+        for record in parse_warc_records(&data)? {
+            let is_http_response = record.header("warc-type") == Some("response")
+                && record
+                    .header("content-type")
+                    .is_some_and(|content_type| content_type.starts_with("application/http"));
+            let Some(uri) = record.header("warc-target-uri") else {
+                continue;
+            };
+            if !is_http_response {
+                continue;
+            }
+            if let Some(response) = parse_http_response(record.content) {
+                // Some writers wrap the URI in angle brackets
+                let uri = uri.trim_start_matches('<').trim_end_matches('>');
+                fetcher.insert(uri, response);
+            }
+        }
+
+        Ok(fetcher)
+    }
+
+    /// Adds a response for a URL, replacing an earlier capture
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL the response was captured for
+    /// * `response` - The captured response
+    pub fn insert(&mut self, url: &str, response: ArchivedResponse) {
+        if let Ok(normalized) = normalize_url(url) {
+            self.responses
+                .insert(normalized.to_string(), response.clone());
+        }
+        self.responses.insert(url.to_string(), response);
+    }
+
+    /// Number of URLs with an archived response (captured and normalized forms)
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Whether no responses are archived
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Looks up the archived response of a URL
+    pub fn get(&self, url: &str) -> Option<&ArchivedResponse> {
+        self.responses.get(url).or_else(|| {
+            normalize_url(url)
+                .ok()
+                .and_then(|normalized| self.responses.get(normalized.as_str()))
+        })
+    }
+
+    /// Replays a URL, following archived redirects
+    fn replay(&self, url: &str, options: &FetchOptions) -> FetchResult {
+        let mut chain = RedirectChain::new();
+        let mut current = url.to_string();
+        loop {
+            if !chain.add_url(&current) {
+                return chain.error(format!("Redirect loop detected at {}", current));
+            }
+            if chain.is_too_long() {
+                return chain.error(format!("Too many redirects (max {})", chain.max_redirects));
+            }
+
+            let Some(response) = self.get(&current) else {
+                return FetchResult::NetworkError {
+                    error: format!("Not in the archive: {}", current),
+                    state: PageState::Unreachable,
+                };
+            };
+
+            let status = response.status_code;
+            if (300..400).contains(&status) {
+                let target = response
+                    .header("location")
+                    .and_then(|location| Url::parse(&current).ok()?.join(location).ok());
+                match target {
+                    Some(target) => {
+                        current = target.to_string();
+                        continue;
+                    }
+                    None => {
+                        return chain.error("Redirect response without Location header".to_string())
+                    }
+                }
+            }
+
+            if !(200..300).contains(&status) {
+                let state = match status {
+                    404 => PageState::DeadLink,
+                    429 => PageState::RateLimited,
+                    _ => PageState::Failed,
+                };
+                return FetchResult::HttpError {
+                    status_code: status,
+                    state,
+                };
+            }
+
+            let content_type = response.header("content-type").unwrap_or("").to_string();
+            if !options.accepts(&content_type) {
+                return FetchResult::ContentMismatch { content_type };
+            }
+
+            let headers = RECORDED_HEADERS
+                .iter()
+                .filter_map(|name| {
+                    response
+                        .header(name)
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect();
+            return FetchResult::Success {
+                final_url: current,
+                status_code: status,
+                content_type,
+                body: response.body.clone(),
+                title: None,
+                headers,
+            };
+        }
+    }
+}
+
+impl Fetcher for ReplayFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, options: &'a FetchOptions) -> FetchFuture<'a> {
+        let result = self.replay(url, options);
+        Box::pin(async move {
+            FetchOutcome {
+                result,
+                head: HeadOutcome::Skipped,
+                retries: 0,
+            }
+        })
+    }
+
+    fn robots_txt(&self, domain: &str) -> Option<String> {
+        let archived = ["https", "http"].iter().find_map(|scheme| {
+            self.get(&format!("{}://{}/robots.txt", scheme, domain))
+                .filter(|response| (200..300).contains(&response.status_code))
+        });
+        Some(
+            archived
+                .map(|response| response.body.clone())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// A WARC record: its headers and content block
+struct WarcRecord<'a> {
+    /// Headers, as (lowercase name, value)
+    headers: Vec<(String, String)>,
+    content: &'a [u8],
+}
+
+impl WarcRecord<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Splits uncompressed WARC data into records
+fn parse_warc_records(data: &[u8]) -> Result<Vec<WarcRecord<'_>>, String> {
+    let mut records = Vec::new();
+    let mut rest = data;
+
+    loop {
+        // Records are separated by blank lines
+        while let Some(stripped) = rest
+            .strip_prefix(b"\r\n")
+            .or_else(|| rest.strip_prefix(b"\n"))
+        {
+            rest = stripped;
+        }
+        if rest.is_empty() {
+            break;
+        }
+        if !rest.starts_with(b"WARC/") {
+            return Err("expected a WARC record header".to_string());
+        }
+
+        let (head, body) = split_head(rest).ok_or("unterminated WARC record header")?;
+        let headers = parse_headers(head);
+        let length: usize = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .and_then(|(_, value)| value.parse().ok())
+            .ok_or("WARC record without a valid Content-Length")?;
+        if body.len() < length {
+            return Err("truncated WARC record".to_string());
+        }
+
+        records.push(WarcRecord {
+            headers,
+            content: &body[..length],
+        });
+        rest = &body[length..];
+    }
+
+    Ok(records)
+}
+
+/// Parses an HTTP response captured in a WARC record
+fn parse_http_response(data: &[u8]) -> Option<ArchivedResponse> {
+    let (head, body) = split_head(data)?;
+    let head = String::from_utf8_lossy(head);
+    let status_line = head.lines().next()?;
+    let status_code = status_line.split_whitespace().nth(1)?.parse().ok()?;
+    let headers = parse_headers(head.as_bytes());
+
+    let has_value = |name: &str, value: &str| {
+        headers
+            .iter()
+            .any(|(header, v)| header == name && v.to_lowercase().contains(value))
+    };
+    let mut body = body.to_vec();
+    if has_value("transfer-encoding", "chunked") {
+        body = decode_chunked(&body)?;
+    }
+    if has_value("content-encoding", "gzip") {
+        let mut decoded = Vec::new();
+        GzDecoder::new(body.as_slice())
+            .read_to_end(&mut decoded)
+            .ok()?;
+        body = decoded;
+    }
+
+    Some(ArchivedResponse {
+        status_code,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+/// Splits a message at the blank line ending its header block
+fn split_head(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+        return Some((&data[..end], &data[end + 4..]));
+    }
+    data.windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|end| (&data[..end], &data[end + 2..]))
+}
+
+/// Parses "Name: value" header lines, skipping the first (version or status) line
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(head)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_lowercase(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Decodes a chunked transfer-encoded body
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size_hex = size_line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size_hex, 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn record(uri: &str, http: &str) -> String {
+        format!(
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-Target-URI: {}\r\n\
+             Content-Type: application/http; msgtype=response\r\n\
+             Content-Length: {}\r\n\r\n{}\r\n\r\n",
+            uri,
+            http.len(),
+            http
+        )
+    }
+
+    fn archive() -> String {
+        let request = "WARC/1.0\r\nWARC-Type: request\r\nWARC-Target-URI: https://example.com/\r\n\
+                       Content-Length: 4\r\n\r\nGET \r\n\r\n";
+        [
+            request.to_string(),
+            record(
+                "https://example.com/",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nServer: nginx\r\n\r\n<html>Home</html>",
+            ),
+            record(
+                "https://example.com/old",
+                "HTTP/1.1 301 Moved\r\nLocation: /chunked\r\n\r\n",
+            ),
+            record(
+                "https://example.com/chunked",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 6\r\n<html>\r\n7\r\n</html>\r\n0\r\n\r\n",
+            ),
+            record(
+                "https://example.com/logo.png",
+                "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\nPNG",
+            ),
+            record(
+                "https://example.com/robots.txt",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nUser-agent: *\nDisallow: /private",
+            ),
+        ]
+        .concat()
+    }
+
+    fn replay(fetcher: &ReplayFetcher, url: &str) -> FetchResult {
+        fetcher.replay(url, &FetchOptions::default())
+    }
+
+    #[test]
+    fn test_replay_warc() {
+        let fetcher = ReplayFetcher::from_warc_bytes(archive().as_bytes()).unwrap();
+
+        match replay(&fetcher, "https://example.com/") {
+            FetchResult::Success {
+                body,
+                headers,
+                status_code,
+                ..
+            } => {
+                assert_eq!(status_code, 200);
+                assert_eq!(body, "<html>Home</html>");
+                assert_eq!(headers, vec![("server".to_string(), "nginx".to_string())]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Redirects are followed within the archive; chunked bodies are decoded
+        match replay(&fetcher, "https://example.com/old") {
+            FetchResult::Success {
+                final_url, body, ..
+            } => {
+                assert_eq!(final_url, "https://example.com/chunked");
+                assert_eq!(body, "<html></html>");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Redirect loops report the URLs followed
+        let mut looping = fetcher.clone();
+        for (from, to) in [("/a", "/b"), ("/b", "/a")] {
+            looping.insert(
+                &format!("https://example.com{}", from),
+                ArchivedResponse {
+                    status_code: 302,
+                    headers: vec![("location".to_string(), to.to_string())],
+                    body: String::new(),
+                },
+            );
+        }
+        match replay(&looping, "https://example.com/a") {
+            FetchResult::RedirectError { chain, .. } => assert_eq!(
+                chain,
+                vec![
+                    "https://example.com/a",
+                    "https://example.com/b",
+                    "https://example.com/a"
+                ]
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(matches!(
+            replay(&fetcher, "https://example.com/logo.png"),
+            FetchResult::ContentMismatch { .. }
+        ));
+        assert!(matches!(
+            replay(&fetcher, "https://example.com/missing"),
+            FetchResult::NetworkError {
+                state: PageState::Unreachable,
+                ..
+            }
+        ));
+
+        assert_eq!(
+            fetcher.robots_txt("example.com").as_deref(),
+            Some("User-agent: *\nDisallow: /private")
+        );
+        assert_eq!(fetcher.robots_txt("other.org").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_replay_gzipped_warc() {
+        // Each record compressed as its own gzip member, as WARC writers do
+        let mut data = Vec::new();
+        for record in [
+            record(
+                "https://example.com/",
+                "HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\n\r\n",
+            ),
+            record(
+                "https://example.com/a",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\nA",
+            ),
+        ] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(record.as_bytes()).unwrap();
+            data.extend(encoder.finish().unwrap());
+        }
+
+        let fetcher = ReplayFetcher::from_warc_bytes(&data).unwrap();
+        assert!(matches!(
+            replay(&fetcher, "https://example.com/"),
+            FetchResult::HttpError {
+                status_code: 404,
+                state: PageState::DeadLink
+            }
+        ));
+        assert!(matches!(
+            replay(&fetcher, "https://example.com/a"),
+            FetchResult::Success { .. }
+        ));
+    }
+
+    #[test]
+    fn test_invalid_warc() {
+        assert!(ReplayFetcher::from_warc_bytes(b"not a warc").is_err());
+        assert!(
+            ReplayFetcher::from_warc_bytes(b"WARC/1.0\r\nContent-Length: 99\r\n\r\nshort").is_err()
+        );
+        assert!(ReplayFetcher::from_warc_bytes(b"").unwrap().is_empty());
+    }
+}
@@ -6,16 +6,31 @@
 //! - Per-domain rate limiting and request counting
 //! - Respecting minimum delays between requests
 //! - Integrating robots.txt crawl delays
+//!
+//! Time is read and waited on through a [`Clock`], so tests can drive the
+//! scheduler with a simulated clock.
 
-use crate::config::CrawlerConfig;
-use crate::state::DomainState;
+use crate::config::{CrawlerConfig, PriorityPolicy};
+use crate::crawler::{HeadOutcome, TimeWindow};
+use crate::state::{Clock, DomainState, SystemClock};
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tokio::sync::Semaphore;
 use url::Url;
 
+/// Priority levels the first URL of a new domain is queued ahead of further
+/// URLs from known domains under [`PriorityPolicy::NewDomainsFirst`]
+pub const NEW_DOMAIN_BOOST: u32 = 20;
+
+/// Width of a priority class; in-link ordering only moves URLs within their class
+pub const INLINK_PRIORITY_LEVELS: u32 = 10;
+
+/// How long `next_url` waits without a ready URL before logging a stall, and
+/// between further stall warnings
+const STALL_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
 /// A URL queued for fetching with priority information
 #[derive(Debug, Clone)]
 pub struct QueuedUrl {
@@ -84,8 +99,17 @@ pub struct Scheduler {
     /// Frontier priority queue of URLs to fetch (lower priority values are fetched first)
     frontier: BinaryHeap<QueuedUrl>,
 
+    /// Domains with URLs queued or requested so far
+    seen_domains: HashSet<String>,
+
+    /// Fetches in a row of URLs from the first priority class
+    first_class_streak: u32,
+
     /// Crawler configuration
     config: CrawlerConfig,
+
+    /// Source of the current time, and of waiting
+    clock: Arc<dyn Clock>,
 }
 
 impl Scheduler {
@@ -104,69 +128,105 @@ impl Scheduler {
         config: CrawlerConfig,
         initial_frontier: Vec<QueuedUrl>,
         initial_domain_states: HashMap<String, DomainState>,
+    ) -> Self {
+        Self::with_clock(
+            config,
+            initial_frontier,
+            initial_domain_states,
+            Arc::new(SystemClock),
+        )
+    }
+
+    /// Creates a new scheduler that reads the time from a given clock
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawler configuration
+    /// * `initial_frontier` - Initial URLs to crawl
+    /// * `initial_domain_states` - Existing domain states (for resume)
+    /// * `clock` - Source of the current time, e.g. a `SimulatedClock` in tests
+    ///
+    /// # Returns
+    ///
+    /// A new Scheduler instance
+    pub fn with_clock(
+        config: CrawlerConfig,
+        initial_frontier: Vec<QueuedUrl>,
+        initial_domain_states: HashMap<String, DomainState>,
+        clock: Arc<dyn Clock>,
     ) -> Self {
         let global_semaphore = Arc::new(Semaphore::new(config.max_concurrent_pages_open as usize));
+        let seen_domains = initial_frontier
+            .iter()
+            .map(|queued| queued.domain.clone())
+            .chain(initial_domain_states.keys().cloned())
+            .collect();
 
         Self {
             global_semaphore,
             domain_states: initial_domain_states,
             frontier: BinaryHeap::from(initial_frontier),
+            seen_domains,
+            first_class_streak: 0,
             config,
+            clock,
         }
     }
 
+    /// The clock the scheduler reads the time from
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
     /// Gets the next URL to fetch
     ///
     /// This method:
     /// 1. Returns None if the frontier is truly empty
-    /// 2. Acquires a global semaphore permit
-    /// 3. Searches the frontier for a URL whose domain can accept a request
-    /// 4. If no domain is ready, waits for the minimum required time and retries
+    /// 2. Searches the frontier for a URL whose domain can accept a request;
+    ///    every `lower-tier-fetch-interval`th fetch prefers one beyond the
+    ///    first priority class
+    /// 3. If no domain is ready, waits until the earliest one is and retries,
+    ///    logging a warning every `STALL_WARNING_INTERVAL` spent waiting
+    /// 4. Acquires a global semaphore permit for the selected URL
     /// 5. Returns the URL with its permit
     ///
+    /// The permit is only taken once a URL is ready, so waiting on domains
+    /// never holds a permit. While waiting for a permit the URL stays in the
+    /// frontier, so dropping the returned future loses nothing.
+    ///
     /// # Returns
     ///
     /// * `Some(ScheduledFetch)` - A URL that's ready to fetch
-    /// * `None` - The frontier is empty
+    /// * `None` - The frontier is empty, or none of its URLs can ever be
+    ///   fetched (their domains are out of requests)
     pub async fn next_url(&mut self) -> Option<ScheduledFetch> {
         // Return None only if frontier is truly empty
         if self.frontier.is_empty() {
             return None;
         }
 
-        // Acquire global semaphore permit
-        let permit = self.global_semaphore.clone().acquire_owned().await.ok()?;
-
         // Active wait loop: keep trying until we find a ready domain
-        let start_waiting = Instant::now();
-        let max_wait_time = Duration::from_secs(30); // Maximum 30 seconds wait
+        let mut start_waiting = self.clock.now();
 
-        loop {
-            // Check if we've been waiting too long
-            if start_waiting.elapsed() > max_wait_time {
-                tracing::warn!(
-                    "Exceeded maximum wait time of {:?} while waiting for domains. Frontier size: {}",
-                    max_wait_time,
-                    self.frontier.len()
-                );
-                // This might indicate a bug, but let's not hang forever
-                return None;
-            }
-            let now = Instant::now();
+        // Permit taken while waiting for a fetch to finish
+        let mut permit = None;
 
+        loop {
             // Collect URLs that are not ready yet (need to put them back)
             let mut not_ready = Vec::new();
             let mut found = None;
 
+            // When a lower tier is due, the best ready first-class URL is only
+            // taken if no lower-tier URL is ready
+            let lower_tier_due = self.lower_tier_due();
+            let mut first_class_fallback = None;
+
             // Pop URLs from the heap until we find one that's ready
             // URLs are popped in priority order (lower priority values first)
             while let Some(queued) = self.frontier.pop() {
-                let state = self
-                    .domain_states
-                    .entry(queued.domain.clone())
-                    .or_insert_with(DomainState::new);
+                let state = self.domain_states.entry(queued.domain.clone()).or_default();
 
-                let can_req = state.can_request(&self.config, now);
+                let can_req = state.can_request(&self.config, self.clock.as_ref());
                 tracing::trace!(
                     "Checking domain {} for URL {}: can_request={}",
                     queued.domain,
@@ -174,7 +234,13 @@ impl Scheduler {
                     can_req
                 );
 
-                if can_req {
+                if can_req && lower_tier_due && queued.priority < INLINK_PRIORITY_LEVELS {
+                    if first_class_fallback.is_none() {
+                        first_class_fallback = Some(queued);
+                    } else {
+                        not_ready.push(queued);
+                    }
+                } else if can_req {
                     // Found a ready URL
                     found = Some(queued);
                     break;
@@ -188,8 +254,32 @@ impl Scheduler {
             for queued in not_ready {
                 self.frontier.push(queued);
             }
+            match (&found, first_class_fallback) {
+                (None, fallback) => found = fallback,
+                (Some(_), Some(unused)) => self.frontier.push(unused),
+                (Some(_), None) => {}
+            }
 
             if let Some(url) = found {
+                // Acquire global semaphore permit
+                let Some(permit) = permit
+                    .take()
+                    .or_else(|| self.global_semaphore.clone().try_acquire_owned().ok())
+                else {
+                    // Wait for a fetch to finish with the URL back in the
+                    // frontier, so a cancelled call loses nothing, then select again
+                    self.frontier.push(url);
+                    permit = Some(self.global_semaphore.clone().acquire_owned().await.ok()?);
+                    start_waiting = self.clock.now();
+                    continue;
+                };
+
+                if url.priority < INLINK_PRIORITY_LEVELS {
+                    self.first_class_streak += 1;
+                } else {
+                    self.first_class_streak = 0;
+                }
+
                 tracing::debug!("Returning URL: {}", url.url);
                 return Some(ScheduledFetch {
                     url,
@@ -197,8 +287,17 @@ impl Scheduler {
                 });
             }
 
+            // Fetches under way may finish while we wait on domains
+            permit = None;
+
             // No domains ready, calculate minimum wait time
-            let min_wait = self.calculate_minimum_wait_time(now);
+            let Some(min_wait) = self.calculate_minimum_wait_time() else {
+                tracing::info!(
+                    "None of the {} queued URLs can be fetched: their domains are out of requests",
+                    self.frontier.len()
+                );
+                return None;
+            };
 
             tracing::debug!(
                 "No domains ready, waiting {:?}. Frontier size: {}",
@@ -206,8 +305,23 @@ impl Scheduler {
                 self.frontier.len()
             );
 
+            // Domains held back by their Visit-time window, an open circuit or
+            // a robots.txt retry are expected to wait, so waiting on them is not a stall
+            let waited = self.clock.now().duration_since(start_waiting);
+            if self.all_deferred() {
+                start_waiting = self.clock.now() + min_wait;
+            } else if waited >= STALL_WARNING_INTERVAL {
+                tracing::warn!(
+                    "No queued URL has been ready for {:?}; waiting another {:?}. Frontier size: {}",
+                    waited,
+                    min_wait,
+                    self.frontier.len()
+                );
+                start_waiting = self.clock.now();
+            }
+
             // Sleep for the minimum time needed
-            tokio::time::sleep(min_wait).await;
+            self.clock.sleep(min_wait).await;
 
             // Check again if frontier is still not empty after sleep
             if self.frontier.is_empty() {
@@ -216,39 +330,60 @@ impl Scheduler {
         }
     }
 
+    /// Whether the next fetch should go to a URL beyond the first priority class
+    ///
+    /// True once `lower-tier-fetch-interval` - 1 first-class URLs were fetched
+    /// in a row.
+    fn lower_tier_due(&self) -> bool {
+        let interval = self.config.lower_tier_fetch_interval;
+        interval > 0 && self.first_class_streak + 1 >= interval
+    }
+
     /// Calculates the minimum time to wait before any domain is ready
     ///
     /// This method iterates through the frontier and finds the domain that will
     /// be ready soonest, returning the time until that domain is ready.
-    ///
-    /// # Arguments
-    ///
-    /// * `now` - The current time instant
+    /// Domains out of requests never become ready and are skipped.
     ///
     /// # Returns
     ///
-    /// The minimum duration to wait before checking again
-    fn calculate_minimum_wait_time(&self, now: Instant) -> Duration {
-        let mut min_wait = Duration::from_millis(100); // Default 100ms
+    /// The minimum duration to wait before checking again, or `None` if no
+    /// queued URL will ever be ready
+    fn calculate_minimum_wait_time(&self) -> Option<Duration> {
+        let mut min_wait: Option<Duration> = None;
 
         for queued in self.frontier.iter() {
-            if let Some(state) = self.domain_states.get(&queued.domain) {
-                if let Some(wait) = state.time_until_next_request(&self.config, now) {
-                    if wait < min_wait {
-                        min_wait = wait;
-                    }
-                } else {
-                    // Domain state exists but can request now - return minimal wait
-                    return Duration::from_millis(10);
-                }
-            } else {
+            let Some(state) = self.domain_states.get(&queued.domain) else {
                 // Domain has no state yet, so it's ready immediately
-                return Duration::from_millis(10);
+                return Some(Duration::from_millis(10));
+            };
+            if state.has_exceeded_limit(&self.config) {
+                continue;
+            }
+
+            match state.time_until_next_request(&self.config, self.clock.as_ref()) {
+                Some(wait) => min_wait = Some(min_wait.map_or(wait, |min| min.min(wait))),
+                // Domain state exists but can request now - return minimal wait
+                None => return Some(Duration::from_millis(10)),
             }
         }
 
         // Add small buffer to ensure the domain is definitely ready
-        min_wait + Duration::from_millis(10)
+        min_wait.map(|wait| wait + Duration::from_millis(10))
+    }
+
+    /// Returns true if every URL in the frontier is waiting on a Visit-time
+    /// window, an open circuit or a robots.txt retry
+    fn all_deferred(&self) -> bool {
+        let wall_clock = self.clock.utc_now();
+        !self.frontier.is_empty()
+            && self.frontier.iter().all(|queued| {
+                self.domain_states.get(&queued.domain).is_some_and(|state| {
+                    state.visit_window_wait(&self.config, wall_clock).is_some()
+                        || state.circuit_wait(self.clock.as_ref()).is_some()
+                        || state.robots_wait(self.clock.as_ref()).is_some()
+                })
+            })
     }
 
     /// Adds a URL to the frontier
@@ -260,36 +395,220 @@ impl Scheduler {
     ///
     /// * `url` - The queued URL to add
     pub fn add_to_frontier(&mut self, url: QueuedUrl) {
+        self.seen_domains.insert(url.domain.clone());
         self.frontier.push(url);
     }
 
+    /// Recomputes the priority of every queued URL
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - Computes a queued URL's new priority
+    ///
+    /// # Returns
+    ///
+    /// (page ID, new priority) for every URL whose priority changed
+    pub fn reprioritize<F>(&mut self, mut priority: F) -> Vec<(i64, u32)>
+    where
+        F: FnMut(&QueuedUrl) -> u32,
+    {
+        let mut changed = Vec::new();
+        let frontier: Vec<QueuedUrl> = self
+            .frontier
+            .drain()
+            .map(|mut queued| {
+                let new_priority = priority(&queued);
+                if new_priority != queued.priority {
+                    queued.priority = new_priority;
+                    changed.push((queued.page_id, new_priority));
+                }
+                queued
+            })
+            .collect();
+        self.frontier = BinaryHeap::from(frontier);
+        changed
+    }
+
+    /// Applies the configured priority policy to a URL's base priority
+    ///
+    /// Under [`PriorityPolicy::NewDomainsFirst`], URLs from domains that
+    /// already have URLs queued or requested are pushed back by
+    /// [`NEW_DOMAIN_BOOST`], so the first URL of a new domain goes ahead.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the URL about to be queued
+    /// * `base_priority` - Priority from the URL's classification
+    ///
+    /// # Returns
+    ///
+    /// The priority to queue the URL with
+    pub fn effective_priority(&self, domain: &str, base_priority: u32) -> u32 {
+        match self.config.priority_policy {
+            PriorityPolicy::Standard => base_priority,
+            PriorityPolicy::NewDomainsFirst if self.seen_domains.contains(domain) => {
+                base_priority.saturating_add(NEW_DOMAIN_BOOST)
+            }
+            PriorityPolicy::NewDomainsFirst => base_priority,
+        }
+    }
+
+    /// Removes every frontier URL for which `keep` returns false
+    ///
+    /// # Arguments
+    ///
+    /// * `keep` - Predicate deciding whether a queued URL stays in the frontier
+    ///
+    /// # Returns
+    ///
+    /// The removed URLs
+    pub fn retain_frontier<F>(&mut self, mut keep: F) -> Vec<QueuedUrl>
+    where
+        F: FnMut(&QueuedUrl) -> bool,
+    {
+        let (kept, removed): (Vec<QueuedUrl>, Vec<QueuedUrl>) =
+            self.frontier.drain().partition(|queued| keep(queued));
+        self.frontier = BinaryHeap::from(kept);
+        removed
+    }
+
     /// Records that a request was made to a domain
     ///
+    /// Draws the jitter for the domain's next request and logs, at debug
+    /// level, the delay actually left since the previous request and the one
+    /// required before the next.
+    ///
     /// # Arguments
     ///
     /// * `domain` - The domain that received the request
     pub fn record_request(&mut self, domain: &str) {
-        let now = Instant::now();
-        let state = self
-            .domain_states
-            .entry(domain.to_string())
-            .or_insert_with(DomainState::new);
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        let now = self.clock.now();
+        let since_last = state.last_request_time.map(|last| now.duration_since(last));
+        state.record_request(self.clock.as_ref());
+        state.request_jitter = request_jitter(&self.config, rand::random::<f64>());
+
+        tracing::debug!(
+            "Request to {} {}; next one in {:?} ({}ms delay x{} + {:?} jitter)",
+            domain,
+            since_last.map_or_else(
+                || "is the first".to_string(),
+                |gap| format!("came {:?} after the previous one", gap)
+            ),
+            state.request_delay(&self.config),
+            self.config.per_domain_delay_ms,
+            state.delay_multiplier,
+            state.request_jitter
+        );
+    }
+
+    /// Sets the robots.txt Visit-time window for a domain
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain the window applies to
+    /// * `window` - The preferred visit window, or None if unrestricted
+    pub fn set_visit_window(&mut self, domain: &str, window: Option<TimeWindow>) {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        state.visit_window = window;
+    }
+
+    /// Sets whether a domain gets the smaller budget of discovered domains
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of a queued URL
+    /// * `discovered` - Whether the domain is classified as discovered
+    pub fn set_discovered(&mut self, domain: &str, discovered: bool) {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        state.discovered = discovered;
+    }
+
+    /// Records how the HEAD requests of a fetch from a domain went
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain that was fetched from
+    /// * `outcome` - How its HEAD requests went
+    pub fn record_head_outcome(&mut self, domain: &str, outcome: HeadOutcome) {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        state.record_head_outcome(outcome);
+    }
+
+    /// Records whether a fetch from a domain failed, for its circuit breaker
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain that was fetched from
+    /// * `failed` - Whether the fetch ended in a network failure or 5xx response
+    ///
+    /// # Returns
+    ///
+    /// `true` if the domain's circuit opened, holding back its queued URLs
+    pub fn record_fetch_result(&mut self, domain: &str, failed: bool) -> bool {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        state.record_fetch_result(failed, &self.config, self.clock.as_ref())
+    }
+
+    /// Records that a domain's robots.txt failed with a server error
+    ///
+    /// See [`DomainState::record_robots_unavailable`].
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain whose robots.txt failed
+    /// * `error` - Why the fetch failed
+    /// * `retry_after` - The delay the server asked for, if any
+    ///
+    /// # Returns
+    ///
+    /// How long the domain's URLs are held back, or `None` if the domain is
+    /// not retried
+    pub fn record_robots_unavailable(
+        &mut self,
+        domain: &str,
+        error: &str,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
+
+        state.record_robots_unavailable(error, retry_after, self.clock.as_ref())
+    }
 
-        state.record_request(now);
+    /// Records that a domain's robots.txt was fetched
+    pub fn record_robots_fetched(&mut self, domain: &str) {
+        if let Some(state) = self.domain_states.get_mut(domain) {
+            state.record_robots_fetched();
+        }
+    }
+
+    /// Whether fetches from a domain should skip the preliminary HEAD request
+    pub fn skip_head(&self, domain: &str) -> bool {
+        self.domain_states
+            .get(domain)
+            .is_some_and(|state| state.skip_head())
     }
 
-    /// Marks a domain as rate limited
+    /// Marks a domain as rate limited, multiplying its delay
+    ///
+    /// See [`DomainState::mark_rate_limited`].
     ///
     /// # Arguments
     ///
     /// * `domain` - The domain to mark as rate limited
-    pub fn mark_rate_limited(&mut self, domain: &str) {
-        let state = self
-            .domain_states
-            .entry(domain.to_string())
-            .or_insert_with(DomainState::new);
+    ///
+    /// # Returns
+    ///
+    /// The factor the domain's delay is now multiplied by
+    pub fn mark_rate_limited(&mut self, domain: &str) -> u32 {
+        let state = self.domain_states.entry(domain.to_string()).or_default();
 
-        state.mark_rate_limited();
+        state.mark_rate_limited()
     }
 
     /// Returns the number of URLs in the frontier
@@ -297,6 +616,25 @@ impl Scheduler {
         self.frontier.len()
     }
 
+    /// Returns the number of fetches left per domain in the frontier
+    ///
+    /// Queued URLs beyond a domain's remaining request budget are never
+    /// fetched and are not counted.
+    pub fn domain_backlog(&self) -> Vec<u64> {
+        let mut queued: HashMap<&str, u64> = HashMap::new();
+        for url in &self.frontier {
+            *queued.entry(url.domain.as_str()).or_insert(0) += 1;
+        }
+
+        queued
+            .into_iter()
+            .map(|(domain, count)| match self.domain_states.get(domain) {
+                Some(state) => count.min(state.requests_remaining(&self.config) as u64),
+                None => count.min(self.config.max_domain_requests as u64),
+            })
+            .collect()
+    }
+
     /// Returns whether the frontier is empty
     pub fn is_empty(&self) -> bool {
         self.frontier.is_empty()
@@ -318,16 +656,52 @@ impl Scheduler {
     }
 }
 
+/// Orders a URL within its priority class by the number of pages linking to it
+///
+/// Priorities form classes of [`INLINK_PRIORITY_LEVELS`] (quality domains
+/// 0-9, discovered domains 10-19, ...). A URL with one referring page is
+/// placed last in its class; each further referrer moves it one level
+/// forward, up to the front of the class.
+///
+/// # Arguments
+///
+/// * `priority` - The URL's current priority
+/// * `referrers` - Number of distinct pages linking to the URL
+///
+/// # Returns
+///
+/// The new priority, in the same class
+pub fn inlink_priority(priority: u32, referrers: u64) -> u32 {
+    let class = priority - priority % INLINK_PRIORITY_LEVELS;
+    let max_offset = INLINK_PRIORITY_LEVELS - 1;
+    let boost = referrers.saturating_sub(1).min(u64::from(max_offset)) as u32;
+    class + max_offset - boost
+}
+
+/// Draws the random wait added to a domain's delay after a request
+///
+/// # Arguments
+///
+/// * `config` - The crawler configuration
+/// * `random` - A random number in `[0, 1)`
+///
+/// # Returns
+///
+/// Up to `per_request_jitter_ms` milliseconds
+pub fn request_jitter(config: &CrawlerConfig, random: f64) -> Duration {
+    Duration::from_millis((config.per_request_jitter_ms as f64 * random) as u64)
+}
+
 /// Calculates the effective delay for a domain
 ///
 /// This takes the maximum of:
-/// - The configured minimum time on page
+/// - The configured per-domain delay
 /// - The robots.txt crawl delay (if specified)
 ///
 /// # Arguments
 ///
 /// * `config` - The crawler configuration
-/// * `domain_state` - The domain state (which may contain robots.txt data)
+/// * `robots` - The domain's robots.txt, if fetched
 /// * `user_agent` - The user agent string to check for crawl delay
 ///
 /// # Returns
@@ -336,21 +710,14 @@ impl Scheduler {
 #[cfg(test)]
 pub fn effective_delay(
     config: &CrawlerConfig,
-    domain_state: &DomainState,
+    robots: Option<&crate::robots::ParsedRobots>,
     user_agent: &str,
 ) -> Duration {
-    let config_delay = Duration::from_millis(config.minimum_time_on_page);
+    let config_delay = Duration::from_millis(config.per_domain_delay_ms);
 
     // Check for robots.txt crawl delay
-    let robots_delay = domain_state
-        .robots_txt
-        .as_ref()
-        .and_then(|cached_robots| {
-            // Parse robots.txt and extract crawl delay
-            use crate::robots::ParsedRobots;
-            let parsed = ParsedRobots::from_content(&cached_robots.content);
-            parsed.crawl_delay(user_agent)
-        })
+    let robots_delay = robots
+        .and_then(|robots| robots.crawl_delay(user_agent))
         .map(|seconds| Duration::from_secs_f64(seconds))
         .unwrap_or(Duration::ZERO);
 
@@ -360,13 +727,38 @@ pub fn effective_delay(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::robots::ParsedRobots;
+    use crate::state::SimulatedClock;
 
     fn create_test_config() -> CrawlerConfig {
         CrawlerConfig {
             max_depth: 3,
             max_concurrent_pages_open: 10,
-            minimum_time_on_page: 1000,
+            per_domain_delay_ms: 1000,
+            per_request_jitter_ms: 0,
+            max_cross_domain_hops: None,
             max_domain_requests: 500,
+            max_discovered_domain_requests: 20,
+            honor_visit_time: false,
+            progress_snapshot_minutes: 5,
+            max_title_length: 512,
+            recrawl_after_days: None,
+            priority_policy: Default::default(),
+            inlink_priority_interval: 0,
+            lower_tier_fetch_interval: 10,
+            head_requests: false,
+            content_types: vec!["text/html".to_string()],
+            max_page_retries: 10,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 300,
+            max_links_per_page: 1000,
+            error_template_min_pages: 5,
+            trap_page_limit: 50,
+            embedded_links: vec![],
+            harvest_contacts: false,
+            skip_self_links: true,
+            skip_same_path_links: false,
+            hash_route_domains: vec![],
         }
     }
 
@@ -401,6 +793,89 @@ mod tests {
         assert!(!scheduler.is_empty());
     }
 
+    #[test]
+    fn test_retain_frontier() {
+        let config = create_test_config();
+        let mut scheduler = Scheduler::new(
+            config,
+            vec![
+                create_test_url("example.com", "/a", 1),
+                create_test_url("spam.com", "/b", 2),
+                create_test_url("example.com", "/c", 3),
+            ],
+            HashMap::new(),
+        );
+
+        let removed = scheduler.retain_frontier(|queued| queued.domain != "spam.com");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].page_id, 2);
+        assert_eq!(scheduler.frontier_size(), 2);
+    }
+
+    #[test]
+    fn test_effective_priority_new_domains_first() {
+        let mut config = create_test_config();
+        let standard = Scheduler::new(
+            config.clone(),
+            vec![create_test_url("known.com", "/", 1)],
+            HashMap::new(),
+        );
+        assert_eq!(standard.effective_priority("known.com", 10), 10);
+
+        config.priority_policy = PriorityPolicy::NewDomainsFirst;
+        let mut domain_states = HashMap::new();
+        domain_states.insert("crawled.com".to_string(), DomainState::new());
+        let mut scheduler = Scheduler::new(
+            config,
+            vec![create_test_url("known.com", "/", 1)],
+            domain_states,
+        );
+
+        assert_eq!(scheduler.effective_priority("new.com", 10), 10);
+        assert_eq!(
+            scheduler.effective_priority("known.com", 0),
+            NEW_DOMAIN_BOOST
+        );
+        assert_eq!(
+            scheduler.effective_priority("crawled.com", 10),
+            10 + NEW_DOMAIN_BOOST
+        );
+
+        // Once queued, a domain is no longer new
+        scheduler.add_to_frontier(create_test_url("new.com", "/", 2));
+        assert_eq!(
+            scheduler.effective_priority("new.com", 10),
+            10 + NEW_DOMAIN_BOOST
+        );
+    }
+
+    #[test]
+    fn test_inlink_priority() {
+        assert_eq!(inlink_priority(0, 0), 9);
+        assert_eq!(inlink_priority(0, 1), 9);
+        assert_eq!(inlink_priority(0, 4), 6);
+        assert_eq!(inlink_priority(10, 50), 10);
+        assert_eq!(inlink_priority(17, 2), 18);
+        assert_eq!(inlink_priority(25, 3), 27);
+    }
+
+    #[test]
+    fn test_reprioritize() {
+        let config = create_test_config();
+        let mut scheduler = Scheduler::new(
+            config,
+            vec![
+                create_test_url("example.com", "/a", 1),
+                create_test_url("example.com", "/b", 2),
+            ],
+            HashMap::new(),
+        );
+
+        let changed = scheduler.reprioritize(|queued| if queued.page_id == 2 { 0 } else { 5 });
+        assert_eq!(changed, vec![(1, 5)]);
+        assert_eq!(scheduler.frontier.peek().unwrap().page_id, 2);
+    }
+
     #[tokio::test]
     async fn test_next_url_from_frontier() {
         let config = create_test_config();
@@ -415,6 +890,35 @@ mod tests {
         assert_eq!(scheduler.frontier_size(), 0);
     }
 
+    #[tokio::test]
+    async fn test_next_url_lower_tier_interval() {
+        let mut config = create_test_config();
+        config.lower_tier_fetch_interval = 3;
+        let mut frontier: Vec<QueuedUrl> = (1..=6)
+            .map(|id| create_test_url(&format!("q{}.com", id), "/", id))
+            .collect();
+        let mut discovered = create_test_url("found.net", "/", 7);
+        discovered.priority = 10;
+        frontier.push(discovered);
+        let mut scheduler = Scheduler::new(config.clone(), frontier.clone(), HashMap::new());
+
+        // Every third fetch goes to the discovered URL while it is queued
+        let mut order = vec![];
+        while let Some(scheduled) = scheduler.next_url().await {
+            order.push(scheduled.url.page_id);
+        }
+        assert_eq!(order, vec![6, 5, 7, 4, 3, 2, 1]);
+
+        // 0 keeps strict priority order
+        config.lower_tier_fetch_interval = 0;
+        let mut scheduler = Scheduler::new(config, frontier, HashMap::new());
+        let mut order = vec![];
+        while let Some(scheduled) = scheduler.next_url().await {
+            order.push(scheduled.url.page_id);
+        }
+        assert_eq!(order, vec![6, 5, 4, 3, 2, 1, 7]);
+    }
+
     #[tokio::test]
     async fn test_next_url_empty_frontier() {
         let config = create_test_config();
@@ -446,26 +950,228 @@ mod tests {
         let state = scheduler.get_domain_state("example.com");
         assert!(state.is_some());
         assert!(state.unwrap().rate_limited);
+        assert_eq!(state.unwrap().delay_multiplier, 2);
     }
 
     #[test]
-    fn test_effective_delay_uses_config() {
+    fn test_domain_backlog() {
+        let mut config = create_test_config();
+        config.max_domain_requests = 3;
+        let frontier = (0..5)
+            .map(|i| create_test_url("big.com", &format!("/{}", i), i))
+            .chain([
+                create_test_url("small.com", "/", 10),
+                create_test_url("spent.com", "/", 11),
+            ])
+            .collect();
+        let mut scheduler = Scheduler::new(config, frontier, HashMap::new());
+        scheduler.record_request("big.com");
+        for _ in 0..3 {
+            scheduler.record_request("spent.com");
+        }
+
+        let mut backlog = scheduler.domain_backlog();
+        backlog.sort();
+        assert_eq!(backlog, vec![0, 1, 2]);
+
+        // Discovered domains get the smaller budget
+        scheduler.set_discovered("big.com", true);
+        scheduler.config.max_discovered_domain_requests = 2;
+        let mut backlog = scheduler.domain_backlog();
+        backlog.sort();
+        assert_eq!(backlog, vec![0, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_next_url_skips_closed_visit_window() {
+        let mut config = create_test_config();
+        config.honor_visit_time = true;
+
+        let deferred = create_test_url("deferred.com", "/page", 1);
+        let ready = create_test_url("ready.com", "/page", 2);
+        let mut scheduler = Scheduler::new(config, vec![deferred, ready], HashMap::new());
+
+        // A window that opens an hour from now is closed right now
+        let now = chrono::Utc::now().time();
+        let start = now + chrono::Duration::hours(1);
+        let end = now + chrono::Duration::hours(2);
+        scheduler.set_visit_window("deferred.com", Some(TimeWindow::new(start, end)));
+
+        let scheduled = scheduler.next_url().await.unwrap();
+        assert_eq!(scheduled.url.domain, "ready.com");
+        assert!(scheduler.all_deferred());
+    }
+
+    #[tokio::test]
+    async fn test_next_url_skips_open_circuit() {
+        let config = create_test_config();
+        let failing = create_test_url("failing.com", "/page", 1);
+        let healthy = create_test_url("healthy.com", "/page", 2);
+        let mut scheduler = Scheduler::new(config.clone(), vec![failing, healthy], HashMap::new());
+
+        for _ in 1..config.circuit_breaker_threshold {
+            assert!(!scheduler.record_fetch_result("failing.com", true));
+        }
+        assert!(scheduler.record_fetch_result("failing.com", true));
+
+        let scheduled = scheduler.next_url().await.unwrap();
+        assert_eq!(scheduled.url.domain, "healthy.com");
+        assert!(scheduler.all_deferred());
+    }
+
+    #[tokio::test]
+    async fn test_next_url_waits_with_clock() {
         let config = create_test_config();
-        let domain_state = DomainState::new();
+        let clock = SimulatedClock::new();
+        let mut scheduler = Scheduler::with_clock(
+            config.clone(),
+            vec![
+                create_test_url("example.com", "/a", 1),
+                create_test_url("example.com", "/b", 2),
+            ],
+            HashMap::new(),
+            Arc::new(clock.clone()),
+        );
+
+        let first = scheduler.next_url().await.unwrap();
+        scheduler.record_request(&first.url.domain);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        // The second URL waits out per-domain-delay-ms on the simulated clock
+        let second = scheduler.next_url().await.unwrap();
+        assert_ne!(second.url.page_id, first.url.page_id);
+        assert!(clock.elapsed() >= Duration::from_millis(config.per_domain_delay_ms));
+        assert!(clock.elapsed() < Duration::from_secs(2));
+
+        // An open circuit is waited out, however long the cooldown
+        scheduler.add_to_frontier(create_test_url("failing.com", "/", 3));
+        for _ in 0..config.circuit_breaker_threshold {
+            scheduler.record_fetch_result("failing.com", true);
+        }
+        let before = clock.elapsed();
+        let third = scheduler.next_url().await.unwrap();
+        assert_eq!(third.url.page_id, 3);
+        assert!(
+            clock.elapsed() - before >= Duration::from_secs(config.circuit_breaker_cooldown_secs)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_url_waits_without_permit() {
+        let mut config = create_test_config();
+        config.max_concurrent_pages_open = 1;
+        let clock = SimulatedClock::new();
+        let mut scheduler = Scheduler::with_clock(
+            config.clone(),
+            vec![
+                create_test_url("example.com", "/a", 1),
+                create_test_url("example.com", "/b", 2),
+            ],
+            HashMap::new(),
+            Arc::new(clock.clone()),
+        );
 
-        let delay = effective_delay(&config, &domain_state, "TestBot");
+        let first = scheduler.next_url().await.unwrap();
+        let first_id = first.url.page_id;
+        scheduler.record_request(&first.url.domain);
+
+        // While the only permit is taken, the domain wait still runs; the
+        // scheduler then blocks on the permit, leaving the URL queued
+        let blocked = tokio::time::timeout(Duration::from_millis(50), scheduler.next_url()).await;
+        assert!(blocked.is_err());
+        assert!(clock.elapsed() >= Duration::from_millis(config.per_domain_delay_ms));
+        assert_eq!(scheduler.frontier_size(), 1);
+
+        drop(first);
+        let second = scheduler.next_url().await.unwrap();
+        assert_ne!(second.url.page_id, first_id);
+    }
+
+    #[tokio::test]
+    async fn test_next_url_waits_out_long_delays() {
+        let mut config = create_test_config();
+        config.per_domain_delay_ms = 90_000;
+        let clock = SimulatedClock::new();
+        let mut scheduler = Scheduler::with_clock(
+            config,
+            vec![
+                create_test_url("example.com", "/a", 1),
+                create_test_url("example.com", "/b", 2),
+            ],
+            HashMap::new(),
+            Arc::new(clock.clone()),
+        );
+
+        let first = scheduler.next_url().await.unwrap();
+        scheduler.record_request(&first.url.domain);
+
+        // A single domain with a long delay is waited on, not given up
+        let second = scheduler.next_url().await.unwrap();
+        assert_ne!(second.url.page_id, first.url.page_id);
+        assert!(clock.elapsed() >= Duration::from_secs(90));
+    }
+
+    #[tokio::test]
+    async fn test_next_url_stops_when_nothing_can_be_fetched() {
+        let config = create_test_config();
+        let clock = SimulatedClock::new();
+        let mut scheduler = Scheduler::with_clock(
+            config,
+            vec![
+                create_test_url("spent.com", "/", 1),
+                create_test_url("full.com", "/", 2),
+            ],
+            HashMap::new(),
+            Arc::new(clock.clone()),
+        );
+        scheduler.set_discovered("full.com", true);
+        for _ in 0..20 {
+            scheduler.record_request("full.com");
+        }
+        for _ in 0..500 {
+            scheduler.record_request("spent.com");
+        }
+
+        assert!(scheduler.next_url().await.is_none());
+        assert_eq!(scheduler.frontier_size(), 2);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_request_jitter() {
+        let mut config = create_test_config();
+        assert_eq!(request_jitter(&config, 0.9), Duration::ZERO);
+
+        config.per_request_jitter_ms = 500;
+        assert_eq!(request_jitter(&config, 0.0), Duration::ZERO);
+        assert_eq!(request_jitter(&config, 0.5), Duration::from_millis(250));
+
+        // The jitter drawn for a request extends the wait before the next one
+        let mut scheduler = Scheduler::new(config.clone(), vec![], HashMap::new());
+        scheduler.record_request("example.com");
+        let state = scheduler.get_domain_state("example.com").unwrap();
+        assert!(state.request_jitter < Duration::from_millis(500));
+        assert_eq!(
+            state.request_delay(&config),
+            Duration::from_millis(config.per_domain_delay_ms) + state.request_jitter
+        );
+    }
+
+    #[test]
+    fn test_effective_delay_uses_config() {
+        let config = create_test_config();
+        let delay = effective_delay(&config, None, "TestBot");
         assert_eq!(delay, Duration::from_millis(1000));
     }
 
     #[test]
     fn test_effective_delay_with_robots_delay() {
         let config = create_test_config();
-        let mut domain_state = DomainState::new();
 
-        // Add robots.txt with crawl delay of 5 seconds
-        domain_state.update_robots("User-agent: *\nCrawl-delay: 5\nDisallow: /admin".to_string());
+        // robots.txt with crawl delay of 5 seconds
+        let robots = ParsedRobots::from_content("User-agent: *\nCrawl-delay: 5\nDisallow: /admin");
 
-        let delay = effective_delay(&config, &domain_state, "TestBot");
+        let delay = effective_delay(&config, Some(&robots), "TestBot");
         // Should use the maximum of config (1 second) and robots (5 seconds)
         assert_eq!(delay, Duration::from_secs(5));
     }
@@ -473,12 +1179,11 @@ mod tests {
     #[test]
     fn test_effective_delay_robots_smaller_than_config() {
         let config = create_test_config();
-        let mut domain_state = DomainState::new();
 
-        // Add robots.txt with crawl delay of 0.5 seconds (500ms)
-        domain_state.update_robots("User-agent: *\nCrawl-delay: 0.5".to_string());
+        // robots.txt with crawl delay of 0.5 seconds (500ms)
+        let robots = ParsedRobots::from_content("User-agent: *\nCrawl-delay: 0.5");
 
-        let delay = effective_delay(&config, &domain_state, "TestBot");
+        let delay = effective_delay(&config, Some(&robots), "TestBot");
         // Should use the maximum of config (1000ms) and robots (500ms)
         assert_eq!(delay, Duration::from_millis(1000));
     }
@@ -486,19 +1191,18 @@ mod tests {
     #[test]
     fn test_effective_delay_specific_user_agent() {
         let config = create_test_config();
-        let mut domain_state = DomainState::new();
 
-        // Add robots.txt with different delays for different user agents
-        domain_state.update_robots(
-            "User-agent: TestBot\nCrawl-delay: 10\n\nUser-agent: *\nCrawl-delay: 2".to_string(),
+        // robots.txt with different delays for different user agents
+        let robots = ParsedRobots::from_content(
+            "User-agent: TestBot\nCrawl-delay: 10\n\nUser-agent: *\nCrawl-delay: 2",
         );
 
         // TestBot should get 10 seconds
-        let delay_testbot = effective_delay(&config, &domain_state, "TestBot");
+        let delay_testbot = effective_delay(&config, Some(&robots), "TestBot");
         assert_eq!(delay_testbot, Duration::from_secs(10));
 
         // Other bots should get 2 seconds
-        let delay_other = effective_delay(&config, &domain_state, "OtherBot");
+        let delay_other = effective_delay(&config, Some(&robots), "OtherBot");
         assert_eq!(delay_other, Duration::from_secs(2));
     }
 }
@@ -0,0 +1,283 @@
+//! Seeding quality domains from their sitemaps
+//!
+//! Quality entries with `seeds-from-sitemap = true` are seeded with the URLs
+//! listed in `/sitemap.xml` on each seed origin (and on the domain itself for
+//! non-wildcard patterns). Sitemap index files are followed, within limits,
+//! and only URLs on the quality domain are kept.
+
+use crate::config::QualityEntry;
+use crate::url::{extract_domain, matches_wildcard};
+use reqwest::Client;
+use std::collections::{HashSet, VecDeque};
+use url::Url;
+
+/// Path of the sitemap fetched from each origin
+pub const SITEMAP_PATH: &str = "/sitemap.xml";
+
+/// Maximum number of sitemap documents fetched per quality entry
+pub const MAX_SITEMAP_DOCUMENTS: usize = 50;
+
+/// Maximum number of seed URLs taken from sitemaps per quality entry
+pub const MAX_SITEMAP_URLS: usize = 50_000;
+
+/// The `<loc>` entries of a sitemap document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SitemapDocument {
+    /// Page URLs (from a `<urlset>`)
+    pub urls: Vec<String>,
+
+    /// Nested sitemap URLs (from a `<sitemapindex>`)
+    pub sitemaps: Vec<String>,
+}
+
+/// Parses a sitemap or sitemap index
+///
+/// # Arguments
+///
+/// * `xml` - The sitemap document
+///
+/// # Returns
+///
+/// The page or nested sitemap URLs listed in the document
+pub fn parse_sitemap(xml: &str) -> SitemapDocument {
+    let locs = extract_locs(xml);
+
+    if xml.contains("<sitemapindex") {
+        SitemapDocument {
+            urls: Vec::new(),
+            sitemaps: locs,
+        }
+    } else {
+        SitemapDocument {
+            urls: locs,
+            sitemaps: Vec::new(),
+        }
+    }
+}
+
+/// Returns the sitemap URLs to fetch for a quality entry
+///
+/// # Arguments
+///
+/// * `entry` - The quality entry
+///
+/// # Returns
+///
+/// `/sitemap.xml` on each seed origin, plus the domain itself (or the base
+/// domain of a `*.` pattern; skipped if the pattern has other wildcards) over
+/// HTTPS, without duplicates
+pub fn sitemap_locations(entry: &QualityEntry) -> Vec<String> {
+    let mut origins: Vec<String> = entry
+        .seeds
+        .iter()
+        .filter_map(|seed| Url::parse(seed).ok())
+        .map(|url| url.origin().ascii_serialization())
+        .collect();
+
+    let host = entry.domain.strip_prefix("*.").unwrap_or(&entry.domain);
+    if !host.contains('*') {
+        origins.push(format!("https://{}", host));
+    }
+
+    let mut seen = HashSet::new();
+    origins
+        .into_iter()
+        .filter(|origin| seen.insert(origin.clone()))
+        .map(|origin| format!("{}{}", origin, SITEMAP_PATH))
+        .collect()
+}
+
+/// Collects seed URLs for a quality entry from its sitemaps
+///
+/// Fetch failures are logged and skipped, so an unreachable or missing
+/// sitemap simply contributes no seeds.
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client to use
+/// * `entry` - The quality entry to seed
+///
+/// # Returns
+///
+/// URLs on the quality domain listed in the entry's sitemaps
+pub async fn fetch_sitemap_seeds(client: &Client, entry: &QualityEntry) -> Vec<Url> {
+    let mut queue: VecDeque<String> = sitemap_locations(entry).into();
+    let mut fetched = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut seeds = Vec::new();
+
+    while let Some(location) = queue.pop_front() {
+        if fetched.len() >= MAX_SITEMAP_DOCUMENTS || seeds.len() >= MAX_SITEMAP_URLS {
+            break;
+        }
+        if !fetched.insert(location.clone()) {
+            continue;
+        }
+
+        let Some(xml) = fetch_document(client, &location).await else {
+            continue;
+        };
+        let document = parse_sitemap(&xml);
+        queue.extend(document.sitemaps);
+
+        for loc in document.urls {
+            let Ok(url) = Url::parse(&loc) else {
+                continue;
+            };
+            let on_domain =
+                extract_domain(&url).is_some_and(|domain| matches_wildcard(&entry.domain, &domain));
+
+            if matches!(url.scheme(), "http" | "https") && on_domain && seen.insert(loc) {
+                seeds.push(url);
+                if seeds.len() >= MAX_SITEMAP_URLS {
+                    tracing::warn!(
+                        "Sitemap seeds for {} truncated at {} URLs",
+                        entry.domain,
+                        MAX_SITEMAP_URLS
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    tracing::info!(
+        "Found {} sitemap seeds for {} in {} sitemap documents",
+        seeds.len(),
+        entry.domain,
+        fetched.len()
+    );
+    seeds
+}
+
+/// Fetches a sitemap document, returning `None` on any failure
+async fn fetch_document(client: &Client, location: &str) -> Option<String> {
+    tracing::debug!("Fetching sitemap {}", location);
+
+    match client.get(location).send().await {
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(body) => Some(body),
+            Err(e) => {
+                tracing::debug!("Failed to read sitemap {}: {}", location, e);
+                None
+            }
+        },
+        Ok(response) => {
+            tracing::debug!("Sitemap {} returned status {}", location, response.status());
+            None
+        }
+        Err(e) => {
+            tracing::debug!("Failed to fetch sitemap {}: {}", location, e);
+            None
+        }
+    }
+}
+
+/// Extracts the text of every `<loc>` element
+fn extract_locs(xml: &str) -> Vec<String> {
+    let mut locs = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<loc>") {
+        rest = &rest[start + "<loc>".len()..];
+        let Some(end) = rest.find("</loc>") else {
+            break;
+        };
+
+        let raw = rest[..end].trim();
+        let raw = raw
+            .strip_prefix("<![CDATA[")
+            .and_then(|inner| inner.strip_suffix("]]>"))
+            .unwrap_or(raw)
+            .trim();
+        if !raw.is_empty() {
+            locs.push(decode_entities(raw));
+        }
+
+        rest = &rest[end + "</loc>".len()..];
+    }
+
+    locs
+}
+
+/// Decodes the XML entities allowed in sitemap URLs
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(domain: &str, seeds: &[&str]) -> QualityEntry {
+        QualityEntry {
+            domain: domain.to_string(),
+            seeds: seeds.iter().map(|s| s.to_string()).collect(),
+            seeds_file: None,
+            seeds_from_sitemap: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_urlset() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc></url>
+  <url>
+    <loc>
+      https://example.com/search?q=a&amp;page=2
+    </loc>
+  </url>
+  <url><loc><![CDATA[https://example.com/cdata]]></loc></url>
+</urlset>"#;
+
+        let document = parse_sitemap(xml);
+        assert_eq!(
+            document.urls,
+            vec![
+                "https://example.com/",
+                "https://example.com/search?q=a&page=2",
+                "https://example.com/cdata",
+            ]
+        );
+        assert!(document.sitemaps.is_empty());
+    }
+
+    #[test]
+    fn test_parse_sitemap_index() {
+        let xml = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/sitemap-posts.xml</loc></sitemap>
+</sitemapindex>"#;
+
+        let document = parse_sitemap(xml);
+        assert!(document.urls.is_empty());
+        assert_eq!(
+            document.sitemaps,
+            vec!["https://example.com/sitemap-posts.xml"]
+        );
+    }
+
+    #[test]
+    fn test_sitemap_locations() {
+        let locations = sitemap_locations(&entry(
+            "*.example.com",
+            &[
+                "https://blog.example.com/",
+                "https://blog.example.com/about",
+                "http://127.0.0.1:8080/",
+            ],
+        ));
+        assert_eq!(
+            locations,
+            vec![
+                "https://blog.example.com/sitemap.xml",
+                "http://127.0.0.1:8080/sitemap.xml",
+                "https://example.com/sitemap.xml",
+            ]
+        );
+    }
+}
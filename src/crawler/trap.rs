@@ -0,0 +1,146 @@
+//! Crawl trap detection
+//!
+//! Some pages generate an endless supply of new URLs: auto-index directory
+//! listings of large file trees, and calendars whose "previous month" link
+//! goes back decades. Such pages are tagged when fetched, and once a domain
+//! has more of them than `trap-page-limit` their links are no longer followed.
+
+use url::Url;
+
+/// Title prefixes of auto-index directory listings (Apache, nginx, Python)
+const DIRECTORY_LISTING_TITLES: &[&str] = &["index of /", "directory listing for /"];
+
+/// Query parameters that select a calendar period
+const CALENDAR_PARAMS: &[&str] = &[
+    "month", "year", "date", "day", "week", "ym", "cal", "calendar",
+];
+
+/// Path segments introducing a calendar period, as in "/calendar/2019/03"
+const CALENDAR_SEGMENTS: &[&str] = &["calendar", "events", "agenda"];
+
+/// A kind of page that generates endless URLs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapKind {
+    /// An auto-index listing of a directory
+    DirectoryListing,
+
+    /// A calendar page for a day, week, month or year
+    Calendar,
+}
+
+impl TrapKind {
+    /// The page tag recording the kind
+    pub fn tag(self) -> &'static str {
+        match self {
+            TrapKind::DirectoryListing => "directory-listing",
+            TrapKind::Calendar => "calendar",
+        }
+    }
+}
+
+/// Recognizes a directory listing or calendar page
+///
+/// Directory listings are recognized by their title, calendars by a query
+/// parameter such as `month=2019-03` or a path such as "/calendar/2019/03".
+///
+/// # Arguments
+///
+/// * `url` - The page URL
+/// * `title` - The page title, if any
+///
+/// # Returns
+///
+/// The kind of trap, or `None` for an ordinary page
+pub fn detect_trap(url: &Url, title: Option<&str>) -> Option<TrapKind> {
+    if let Some(title) = title {
+        let title = title.trim().to_lowercase();
+        if DIRECTORY_LISTING_TITLES
+            .iter()
+            .any(|prefix| title.starts_with(prefix))
+        {
+            return Some(TrapKind::DirectoryListing);
+        }
+    }
+
+    let calendar_query = url.query_pairs().any(|(key, value)| {
+        CALENDAR_PARAMS.contains(&key.to_lowercase().as_str()) && is_date_like(&value)
+    });
+    if calendar_query {
+        return Some(TrapKind::Calendar);
+    }
+
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let calendar_path = segments.windows(2).any(|pair| {
+        CALENDAR_SEGMENTS.contains(&pair[0].to_lowercase().as_str()) && is_year(pair[1])
+    });
+    calendar_path.then_some(TrapKind::Calendar)
+}
+
+/// Whether a value looks like a date or part of one ("2019-03", "201903", "7")
+fn is_date_like(value: &str) -> bool {
+    value.len() <= 10
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '/' | '.'))
+}
+
+/// Whether a path segment is a year, optionally followed by more of a date
+fn is_year(segment: &str) -> bool {
+    segment
+        .get(..4)
+        .and_then(|year| year.parse::<u32>().ok())
+        .is_some_and(|year| (1900..=2100).contains(&year))
+        && is_date_like(segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(url: &str, title: Option<&str>) -> Option<TrapKind> {
+        detect_trap(&Url::parse(url).unwrap(), title)
+    }
+
+    #[test]
+    fn test_detect_directory_listing() {
+        assert_eq!(
+            detect("https://a.com/files/", Some("Index of /files")),
+            Some(TrapKind::DirectoryListing)
+        );
+        assert_eq!(
+            detect("https://a.com/pub/", Some("Directory listing for /pub/")),
+            Some(TrapKind::DirectoryListing)
+        );
+        assert_eq!(detect("https://a.com/", Some("Index of my recipes")), None);
+    }
+
+    #[test]
+    fn test_detect_calendar() {
+        assert_eq!(
+            detect("https://a.com/events?month=1987-04", None),
+            Some(TrapKind::Calendar)
+        );
+        assert_eq!(
+            detect("https://a.com/?view=cal&Year=1999", None),
+            Some(TrapKind::Calendar)
+        );
+        assert_eq!(
+            detect("https://a.com/calendar/2019/03/", None),
+            Some(TrapKind::Calendar)
+        );
+        // Blog archives and unrelated parameters are not calendars
+        assert_eq!(detect("https://a.com/2019/03/my-post", None), None);
+        assert_eq!(detect("https://a.com/?date=latest", None), None);
+        assert_eq!(detect("https://a.com/events/summer-fair", None), None);
+    }
+
+    #[test]
+    fn test_trap_tags() {
+        assert_eq!(TrapKind::DirectoryListing.tag(), "directory-listing");
+        assert_eq!(TrapKind::Calendar.tag(), "calendar");
+    }
+}
@@ -0,0 +1,518 @@
+//! In-memory link graph for analyzing crawl results
+//!
+//! This module provides a typed query layer over the pages and links in
+//! storage, so crawl data can be analyzed from Rust without writing SQL:
+//! - Page-level neighbors and degrees
+//! - Domain-level degrees and page lists
+//! - Subgraphs restricted to a set of domains
+//! - Stubbed domains as terminal nodes, with the pages referencing them
+//! - Breadth-first traversal along outgoing links
+//!
+//! ```no_run
+//! use sumi_ripple::graph::LinkGraph;
+//! use sumi_ripple::storage::SqliteStorage;
+//! use std::path::Path;
+//!
+//! let storage = SqliteStorage::new(Path::new("sumi-ripple.db"))?;
+//! let graph = LinkGraph::load(&storage)?;
+//! for domain in graph.domains() {
+//!     println!("{}: {} inbound links", domain, graph.in_degree(domain));
+//! }
+//! # Ok::<(), sumi_ripple::SumiError>(())
+//! ```
+
+mod traversal;
+
+pub use traversal::Bfs;
+
+use crate::state::PageState;
+use crate::storage::{LinkRecord, PageRecord, Storage};
+use crate::url::{extract_domain, normalize_url};
+use crate::SumiError;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Pages and the links between them, indexed for traversal
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    /// Page data; a page's position is its node index
+    pages: Vec<PageRecord>,
+
+    /// Page ID -> node index
+    by_id: HashMap<i64, usize>,
+
+    /// Page URL -> node index
+    by_url: HashMap<String, usize>,
+
+    /// Outgoing link targets per node
+    out_edges: Vec<Vec<usize>>,
+
+    /// Incoming link sources per node
+    in_edges: Vec<Vec<usize>>,
+
+    /// Domain -> nodes on that domain
+    domains: BTreeMap<String, Vec<usize>>,
+
+    /// Stubbed domain -> nodes referencing it, sorted
+    ///
+    /// Stubbed URLs are never visited, so their domains are terminal nodes
+    /// without pages or outgoing links.
+    stubs: BTreeMap<String, Vec<usize>>,
+}
+
+impl LinkGraph {
+    /// Loads every page, link and stubbed URL reference from storage
+    ///
+    /// The links of pages flagged as copies of an error or interstitial page
+    /// template are left out, so a site's "not found" page does not repeat
+    /// its navigation for every missing URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The storage backend containing crawl data
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LinkGraph)` - The complete link graph
+    /// * `Err(SumiError)` - Failed to query storage
+    pub fn load(storage: &dyn Storage) -> Result<Self, SumiError> {
+        let mut pages = Vec::new();
+        for state in PageState::all_states() {
+            pages.extend(storage.get_pages_by_state(state)?);
+        }
+        let links = storage.get_graph_links()?;
+        let mut graph = Self::from_parts(pages, &links);
+        graph.add_stub_references(&storage.get_stub_references(None)?);
+        Ok(graph)
+    }
+
+    /// Builds a graph from pages and links
+    ///
+    /// Links whose source or target is not among `pages` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `pages` - The graph's pages
+    /// * `links` - Links between them
+    pub fn from_parts(mut pages: Vec<PageRecord>, links: &[LinkRecord]) -> Self {
+        pages.sort_by_key(|page| page.id);
+
+        let by_id: HashMap<i64, usize> = pages
+            .iter()
+            .enumerate()
+            .map(|(node, page)| (page.id, node))
+            .collect();
+        let by_url = pages
+            .iter()
+            .enumerate()
+            .map(|(node, page)| (page.url.clone(), node))
+            .collect();
+        let mut domains: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (node, page) in pages.iter().enumerate() {
+            domains.entry(page.domain.clone()).or_default().push(node);
+        }
+
+        let mut out_edges = vec![Vec::new(); pages.len()];
+        let mut in_edges = vec![Vec::new(); pages.len()];
+        for link in links {
+            if let (Some(&from), Some(&to)) =
+                (by_id.get(&link.from_page_id), by_id.get(&link.to_page_id))
+            {
+                out_edges[from].push(to);
+                in_edges[to].push(from);
+            }
+        }
+
+        Self {
+            pages,
+            by_id,
+            by_url,
+            out_edges,
+            in_edges,
+            domains,
+            stubs: BTreeMap::new(),
+        }
+    }
+
+    /// Adds references from pages to stubbed URLs
+    ///
+    /// Referrers are matched to pages by URL, also after normalization;
+    /// references from URLs that are not pages of the graph are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `references` - (stubbed URL, referrer URL) pairs
+    pub fn add_stub_references(&mut self, references: &[(String, String)]) {
+        for (url, referrer) in references {
+            let Some(domain) = url::Url::parse(url).ok().as_ref().and_then(extract_domain) else {
+                continue;
+            };
+            let node = self.by_url.get(referrer.as_str()).copied().or_else(|| {
+                normalize_url(referrer)
+                    .ok()
+                    .and_then(|normalized| self.by_url.get(normalized.as_str()).copied())
+            });
+            if let Some(node) = node {
+                let nodes = self.stubs.entry(domain).or_default();
+                if let Err(position) = nodes.binary_search(&node) {
+                    nodes.insert(position, node);
+                }
+            }
+        }
+    }
+
+    /// Number of pages in the graph
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Number of links in the graph
+    pub fn link_count(&self) -> usize {
+        self.out_edges.iter().map(Vec::len).sum()
+    }
+
+    /// All pages, ordered by page ID
+    pub fn pages(&self) -> impl Iterator<Item = &PageRecord> {
+        self.pages.iter()
+    }
+
+    /// Looks up a page by ID
+    pub fn page(&self, page_id: i64) -> Option<&PageRecord> {
+        self.by_id.get(&page_id).map(|&node| &self.pages[node])
+    }
+
+    /// Looks up a page by its normalized URL
+    pub fn page_by_url(&self, url: &str) -> Option<&PageRecord> {
+        self.by_url.get(url).map(|&node| &self.pages[node])
+    }
+
+    /// Pages the given page links to; empty for unknown pages
+    pub fn out_neighbors(&self, page_id: i64) -> impl Iterator<Item = &PageRecord> {
+        self.neighbors(&self.out_edges, page_id)
+    }
+
+    /// Pages linking to the given page; empty for unknown pages
+    pub fn in_neighbors(&self, page_id: i64) -> impl Iterator<Item = &PageRecord> {
+        self.neighbors(&self.in_edges, page_id)
+    }
+
+    /// Number of links on the given page
+    pub fn page_out_degree(&self, page_id: i64) -> usize {
+        self.out_neighbors(page_id).count()
+    }
+
+    /// Number of links to the given page
+    pub fn page_in_degree(&self, page_id: i64) -> usize {
+        self.in_neighbors(page_id).count()
+    }
+
+    /// All domains, sorted
+    pub fn domains(&self) -> impl Iterator<Item = &str> {
+        self.domains.keys().map(String::as_str)
+    }
+
+    /// Pages on the given domain, ordered by page ID
+    pub fn domain_pages<'a>(&'a self, domain: &str) -> impl Iterator<Item = &'a PageRecord> {
+        self.domain_nodes(domain)
+            .iter()
+            .map(move |&node| &self.pages[node])
+    }
+
+    /// Number of links from pages on other domains to pages on `domain`
+    pub fn in_degree(&self, domain: &str) -> usize {
+        self.domain_nodes(domain)
+            .iter()
+            .flat_map(|&node| &self.in_edges[node])
+            .filter(|&&source| self.pages[source].domain != domain)
+            .count()
+    }
+
+    /// Number of links from pages on `domain` to pages on other domains
+    pub fn out_degree(&self, domain: &str) -> usize {
+        self.domain_nodes(domain)
+            .iter()
+            .flat_map(|&node| &self.out_edges[node])
+            .filter(|&&target| self.pages[target].domain != domain)
+            .count()
+    }
+
+    /// Other domains that pages on `domain` link to, sorted
+    pub fn linked_domains(&self, domain: &str) -> Vec<&str> {
+        let linked: HashSet<&str> = self
+            .domain_nodes(domain)
+            .iter()
+            .flat_map(|&node| &self.out_edges[node])
+            .map(|&target| self.pages[target].domain.as_str())
+            .filter(|&target| target != domain)
+            .collect();
+        let mut linked: Vec<&str> = linked.into_iter().collect();
+        linked.sort_unstable();
+        linked
+    }
+
+    /// All stubbed domains referenced by a page of the graph, sorted
+    pub fn stub_domains(&self) -> impl Iterator<Item = &str> {
+        self.stubs.keys().map(String::as_str)
+    }
+
+    /// Pages referencing URLs on a stubbed domain, ordered by page ID
+    pub fn stub_referrers<'a>(&'a self, domain: &str) -> impl Iterator<Item = &'a PageRecord> {
+        self.stubs
+            .get(domain)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+            .iter()
+            .map(move |&node| &self.pages[node])
+    }
+
+    /// Number of distinct pages referencing URLs on a stubbed domain
+    pub fn stub_in_degree(&self, domain: &str) -> usize {
+        self.stubs.get(domain).map_or(0, Vec::len)
+    }
+
+    /// The graph restricted to pages on the given domains
+    ///
+    /// Only links with both ends on one of the domains are kept, and stubbed
+    /// domains referenced from one of them.
+    pub fn subgraph<I, S>(&self, domains: I) -> LinkGraph
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let domains: HashSet<String> = domains
+            .into_iter()
+            .map(|domain| domain.as_ref().to_string())
+            .collect();
+        let pages: Vec<PageRecord> = self
+            .pages
+            .iter()
+            .filter(|page| domains.contains(&page.domain))
+            .cloned()
+            .collect();
+        let links: Vec<LinkRecord> = self
+            .out_edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, targets)| targets.iter().map(move |&to| (from, to)))
+            .filter(|&(from, to)| {
+                domains.contains(&self.pages[from].domain)
+                    && domains.contains(&self.pages[to].domain)
+            })
+            .map(|(from, to)| LinkRecord {
+                from_page_id: self.pages[from].id,
+                to_page_id: self.pages[to].id,
+                discovered_run: self.pages[to].discovered_run,
+                last_seen_run: self.pages[to].discovered_run,
+            })
+            .collect();
+        let mut graph = LinkGraph::from_parts(pages, &links);
+        for (domain, nodes) in &self.stubs {
+            let referrers: Vec<usize> = nodes
+                .iter()
+                .filter_map(|&node| graph.by_id.get(&self.pages[node].id).copied())
+                .collect();
+            if !referrers.is_empty() {
+                graph.stubs.insert(domain.clone(), referrers);
+            }
+        }
+        graph
+    }
+
+    /// Breadth-first traversal along outgoing links
+    ///
+    /// Yields each page reachable from `start` once, with its distance in
+    /// links, starting with `start` itself at distance 0. Yields nothing if
+    /// `start` is not in the graph.
+    pub fn bfs(&self, start: i64) -> Bfs<'_> {
+        Bfs::new(self, self.by_id.get(&start).copied())
+    }
+
+    /// Node indexes of a domain's pages
+    fn domain_nodes(&self, domain: &str) -> &[usize] {
+        self.domains.get(domain).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Neighbors of a page along the given edge lists
+    fn neighbors<'a>(
+        &'a self,
+        edges: &'a [Vec<usize>],
+        page_id: i64,
+    ) -> impl Iterator<Item = &'a PageRecord> {
+        self.by_id
+            .get(&page_id)
+            .map(|&node| edges[node].as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .map(move |&neighbor| &self.pages[neighbor])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(id: i64, url: &str) -> PageRecord {
+        let domain = url::Url::parse(url)
+            .unwrap()
+            .host_str()
+            .unwrap()
+            .to_string();
+        PageRecord {
+            id,
+            url: url.to_string(),
+            domain,
+            state: PageState::Processed,
+            title: None,
+            status_code: Some(200),
+            content_type: None,
+            last_modified: None,
+            visited_at: None,
+            discovered_at: "2024-01-01T00:00:00Z".to_string(),
+            discovered_run: 1,
+            error_message: None,
+            retry_count: 0,
+        }
+    }
+
+    fn link(from_page_id: i64, to_page_id: i64) -> LinkRecord {
+        LinkRecord {
+            from_page_id,
+            to_page_id,
+            discovered_run: 1,
+            last_seen_run: 1,
+        }
+    }
+
+    /// a.com/1 -> a.com/2 -> b.com/1 -> c.com/1, a.com/1 -> b.com/1, c.com/1 -> a.com/1
+    fn sample_graph() -> LinkGraph {
+        LinkGraph::from_parts(
+            vec![
+                page(1, "https://a.com/1"),
+                page(2, "https://a.com/2"),
+                page(3, "https://b.com/1"),
+                page(4, "https://c.com/1"),
+            ],
+            &[
+                link(1, 2),
+                link(2, 3),
+                link(3, 4),
+                link(1, 3),
+                link(4, 1),
+                link(4, 99),
+            ],
+        )
+    }
+
+    fn ids<'a>(pages: impl Iterator<Item = &'a PageRecord>) -> Vec<i64> {
+        pages.map(|page| page.id).collect()
+    }
+
+    #[test]
+    fn test_neighbors_and_degrees() {
+        let graph = sample_graph();
+        assert_eq!(graph.page_count(), 4);
+        assert_eq!(graph.link_count(), 5);
+
+        assert_eq!(ids(graph.out_neighbors(1)), vec![2, 3]);
+        assert_eq!(ids(graph.in_neighbors(3)), vec![2, 1]);
+        assert_eq!(graph.page_in_degree(1), 1);
+        assert_eq!(graph.page_out_degree(42), 0);
+        assert_eq!(graph.page_by_url("https://b.com/1").map(|p| p.id), Some(3));
+    }
+
+    #[test]
+    fn test_domain_queries() {
+        let graph = sample_graph();
+        assert_eq!(
+            graph.domains().collect::<Vec<_>>(),
+            vec!["a.com", "b.com", "c.com"]
+        );
+        assert_eq!(ids(graph.domain_pages("a.com")), vec![1, 2]);
+
+        // Links within a.com do not count
+        assert_eq!(graph.out_degree("a.com"), 2);
+        assert_eq!(graph.in_degree("a.com"), 1);
+        assert_eq!(graph.in_degree("b.com"), 2);
+        assert_eq!(graph.linked_domains("a.com"), vec!["b.com"]);
+        assert_eq!(graph.in_degree("unknown.org"), 0);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let sub = sample_graph().subgraph(["a.com", "b.com"]);
+        assert_eq!(sub.page_count(), 3);
+        assert_eq!(sub.link_count(), 3);
+        assert!(sub.page(4).is_none());
+        assert_eq!(sub.out_degree("b.com"), 0);
+    }
+
+    #[test]
+    fn test_load_from_storage() {
+        use crate::storage::SqliteStorage;
+
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let a = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let b = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+        storage.insert_link(a, b, run_id).unwrap();
+
+        let graph = LinkGraph::load(&storage).unwrap();
+        assert_eq!(graph.page_count(), 2);
+        assert_eq!(ids(graph.out_neighbors(a)), vec![b]);
+        assert_eq!(graph.in_degree("b.com"), 1);
+    }
+
+    #[test]
+    fn test_load_skips_error_template_links() {
+        use crate::storage::SqliteStorage;
+
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let target = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+        let mut missing = Vec::new();
+        for path in ["x", "y", "z"] {
+            let page = storage
+                .insert_or_get_page(&format!("https://a.com/{}", path), "a.com", run_id)
+                .unwrap();
+            storage.insert_link(page, target, run_id).unwrap();
+            storage.set_template_hash(page, "404").unwrap();
+            missing.push(page);
+        }
+        assert!(storage.flag_error_template("a.com", "404", 3).unwrap());
+
+        // Only the first copy of the template keeps its link
+        let graph = LinkGraph::load(&storage).unwrap();
+        assert_eq!(graph.page_count(), 4);
+        assert_eq!(ids(graph.in_neighbors(target)), vec![missing[0]]);
+    }
+
+    #[test]
+    fn test_stub_domains() {
+        let reference = |url: &str, referrer: &str| (url.to_string(), referrer.to_string());
+        let mut graph = sample_graph();
+        graph.add_stub_references(&[
+            reference("https://cdn.net/a.js", "https://a.com/1"),
+            reference("https://cdn.net/b.js", "https://a.com/1"),
+            reference("https://cdn.net/a.js", "https://c.com/1"),
+            reference("https://social.net/x", "https://B.com/1"),
+            reference("https://ads.net/", "https://unknown.org/"),
+        ]);
+
+        assert_eq!(
+            graph.stub_domains().collect::<Vec<_>>(),
+            vec!["cdn.net", "social.net"]
+        );
+        assert_eq!(ids(graph.stub_referrers("cdn.net")), vec![1, 4]);
+        assert_eq!(graph.stub_in_degree("social.net"), 1);
+        assert_eq!(graph.stub_in_degree("ads.net"), 0);
+
+        let sub = graph.subgraph(["a.com"]);
+        assert_eq!(sub.stub_domains().collect::<Vec<_>>(), vec!["cdn.net"]);
+        assert_eq!(ids(sub.stub_referrers("cdn.net")), vec![1]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Iterator-based traversal of the link graph
+
+use crate::graph::LinkGraph;
+use crate::storage::PageRecord;
+use std::collections::VecDeque;
+
+/// Breadth-first traversal along outgoing links, created by [`LinkGraph::bfs`]
+///
+/// Yields `(page, distance)` pairs in order of increasing distance.
+#[derive(Debug, Clone)]
+pub struct Bfs<'a> {
+    graph: &'a LinkGraph,
+    queue: VecDeque<(usize, u32)>,
+    visited: Vec<bool>,
+}
+
+impl<'a> Bfs<'a> {
+    pub(crate) fn new(graph: &'a LinkGraph, start: Option<usize>) -> Self {
+        let mut visited = vec![false; graph.pages.len()];
+        let mut queue = VecDeque::new();
+        if let Some(start) = start {
+            visited[start] = true;
+            queue.push_back((start, 0));
+        }
+        Self {
+            graph,
+            queue,
+            visited,
+        }
+    }
+}
+
+impl<'a> Iterator for Bfs<'a> {
+    type Item = (&'a PageRecord, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, distance) = self.queue.pop_front()?;
+        for &target in &self.graph.out_edges[node] {
+            if !self.visited[target] {
+                self.visited[target] = true;
+                self.queue.push_back((target, distance + 1));
+            }
+        }
+        Some((&self.graph.pages[node], distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::LinkGraph;
+    use crate::state::PageState;
+    use crate::storage::{LinkRecord, PageRecord};
+
+    fn page(id: i64) -> PageRecord {
+        PageRecord {
+            id,
+            url: format!("https://a.com/{}", id),
+            domain: "a.com".to_string(),
+            state: PageState::Processed,
+            title: None,
+            status_code: None,
+            content_type: None,
+            last_modified: None,
+            visited_at: None,
+            discovered_at: String::new(),
+            discovered_run: 1,
+            error_message: None,
+            retry_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_bfs_distances() {
+        let links: Vec<LinkRecord> = [(1, 2), (1, 3), (2, 4), (3, 4), (4, 1), (5, 1)]
+            .into_iter()
+            .map(|(from_page_id, to_page_id)| LinkRecord {
+                from_page_id,
+                to_page_id,
+                discovered_run: 1,
+                last_seen_run: 1,
+            })
+            .collect();
+        let graph = LinkGraph::from_parts((1..=5).map(page).collect(), &links);
+
+        let visited: Vec<(i64, u32)> = graph.bfs(1).map(|(page, d)| (page.id, d)).collect();
+        assert_eq!(visited, vec![(1, 0), (2, 1), (3, 1), (4, 2)]);
+
+        // Stops early when only the nearest pages are needed
+        let near: Vec<i64> = graph
+            .bfs(5)
+            .take_while(|(_, d)| *d <= 1)
+            .map(|(page, _)| page.id)
+            .collect();
+        assert_eq!(near, vec![5, 1]);
+
+        assert_eq!(graph.bfs(42).count(), 0);
+    }
+}
@@ -0,0 +1,540 @@
+//! gRPC control API
+//!
+//! Runs the crawler as a long-lived service that other systems start, stop
+//! and watch over gRPC, instead of as a one-shot command. The service is
+//! defined in `proto/sumi_control.proto`; one crawl runs at a time, and
+//! stopping it marks the run interrupted so a later `StartCrawl` in resume
+//! mode continues where it left off. Only available when built with the
+//! `grpc` feature.
+
+use crate::config::{load_config, Config};
+use crate::crawler::{CancellationToken, Coordinator, CrawlEvent, CrawlMode};
+use crate::SumiError;
+use proto::crawl_control_server::{CrawlControl, CrawlControlServer};
+use proto::{CrawlState, CrawlStatus};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+/// Code generated from `proto/sumi_control.proto`
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("sumi.control.v1");
+}
+
+/// Events kept for watchers: the latest events of a crawl are replayed to a
+/// watcher joining late, and a watcher falling further behind misses events
+const WATCH_BUFFER: usize = 1024;
+
+/// The crawl controlled by the service
+#[derive(Debug, Default)]
+struct CrawlSlot {
+    status: CrawlStatus,
+    /// Stops the running crawl
+    cancel: Option<CancellationToken>,
+    /// Sends the events of the running crawl to its watchers
+    events: Option<broadcast::Sender<proto::CrawlEvent>>,
+    /// The latest events of the current or last crawl, replayed to new watchers
+    history: VecDeque<proto::CrawlEvent>,
+    /// Forwards the events of the running crawl
+    task: Option<JoinHandle<()>>,
+}
+
+impl CrawlSlot {
+    fn is_active(&self) -> bool {
+        matches!(
+            self.status.state(),
+            CrawlState::Running | CrawlState::Stopping
+        )
+    }
+}
+
+/// The `CrawlControl` service
+#[derive(Debug, Clone)]
+pub struct ControlService {
+    /// Configuration used when `StartCrawl` names no configuration file
+    config: Config,
+    slot: Arc<Mutex<CrawlSlot>>,
+}
+
+impl ControlService {
+    /// Creates the service
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration used when `StartCrawl` names no file
+    ///
+    /// # Returns
+    ///
+    /// An idle service
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            slot: Arc::default(),
+        }
+    }
+
+    fn slot(&self) -> MutexGuard<'_, CrawlSlot> {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stops the running crawl, if any, and waits until its run is marked
+    /// interrupted
+    async fn shutdown(&self) {
+        let task = {
+            let mut slot = self.slot();
+            if let Some(cancel) = &slot.cancel {
+                cancel.cancel();
+            }
+            slot.task.take()
+        };
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+
+    /// Forwards the events of a crawl to its watchers, keeping the status
+    /// up to date
+    async fn forward(
+        slot: Arc<Mutex<CrawlSlot>>,
+        mut events: impl Stream<Item = CrawlEvent> + Unpin,
+        sender: broadcast::Sender<proto::CrawlEvent>,
+    ) {
+        while let Some(event) = events.next().await {
+            {
+                let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+                match &event {
+                    CrawlEvent::PageFetched { .. } => slot.status.pages_fetched += 1,
+                    CrawlEvent::PageFailed { .. } => slot.status.pages_failed += 1,
+                    CrawlEvent::LinkDiscovered { .. } => slot.status.links_discovered += 1,
+                    CrawlEvent::Finished { error, .. } => {
+                        slot.status.set_state(if error.is_some() {
+                            CrawlState::Failed
+                        } else {
+                            CrawlState::Finished
+                        });
+                        slot.status.error = error.clone().unwrap_or_default();
+                        slot.cancel = None;
+                        slot.events = None;
+                    }
+                }
+
+                // Kept and sent under the lock, so a watcher subscribing in
+                // between neither misses nor repeats the event
+                let event = event_to_proto(event);
+                if slot.history.len() == WATCH_BUFFER {
+                    slot.history.pop_front();
+                }
+                slot.history.push_back(event.clone());
+                // Nobody watching is not an error
+                let _ = sender.send(event);
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CrawlControl for ControlService {
+    async fn start_crawl(
+        &self,
+        request: Request<proto::StartCrawlRequest>,
+    ) -> Result<Response<CrawlStatus>, Status> {
+        let request = request.into_inner();
+        let mode = match request.mode() {
+            proto::CrawlMode::Resume => CrawlMode::Resume,
+            proto::CrawlMode::Fresh => CrawlMode::Fresh,
+            proto::CrawlMode::Incremental => CrawlMode::Incremental,
+        };
+
+        {
+            let mut slot = self.slot();
+            if slot.is_active() {
+                return Err(Status::failed_precondition(format!(
+                    "run {} is still crawling",
+                    slot.status.run_id
+                )));
+            }
+            // Claim the slot before the coordinator is built
+            slot.status = CrawlStatus {
+                state: CrawlState::Running.into(),
+                ..Default::default()
+            };
+            slot.history.clear();
+        }
+
+        let coordinator = if request.config_path.is_empty() {
+            Ok(self.config.clone())
+        } else {
+            load_config(Path::new(&request.config_path)).map_err(SumiError::from)
+        }
+        .and_then(|config| Coordinator::with_mode(config, mode));
+        let coordinator = match coordinator {
+            Ok(coordinator) => coordinator,
+            Err(e) => {
+                let mut slot = self.slot();
+                slot.status.set_state(CrawlState::Failed);
+                slot.status.error = e.to_string();
+                return Err(Status::failed_precondition(e.to_string()));
+            }
+        };
+
+        let run_id = coordinator.run_id();
+        let cancel = CancellationToken::new();
+        let (sender, _) = broadcast::channel(WATCH_BUFFER);
+        let events = coordinator.run_stream_until_cancelled(cancel.clone());
+        tracing::info!("Started run {} over gRPC", run_id);
+
+        let mut slot = self.slot();
+        slot.status.run_id = run_id;
+        slot.cancel = Some(cancel);
+        slot.events = Some(sender.clone());
+        slot.task = Some(tokio::spawn(Self::forward(
+            Arc::clone(&self.slot),
+            Box::pin(events),
+            sender,
+        )));
+        Ok(Response::new(slot.status.clone()))
+    }
+
+    async fn stop_crawl(
+        &self,
+        _request: Request<proto::StopCrawlRequest>,
+    ) -> Result<Response<CrawlStatus>, Status> {
+        let mut slot = self.slot();
+        let Some(cancel) = &slot.cancel else {
+            return Err(Status::failed_precondition("no crawl is running"));
+        };
+        cancel.cancel();
+        slot.status.set_state(CrawlState::Stopping);
+        tracing::info!("Stopping run {} over gRPC", slot.status.run_id);
+        Ok(Response::new(slot.status.clone()))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<CrawlStatus>, Status> {
+        Ok(Response::new(self.slot().status.clone()))
+    }
+
+    type WatchCrawlStream = Pin<Box<dyn Stream<Item = Result<proto::CrawlEvent, Status>> + Send>>;
+
+    async fn watch_crawl(
+        &self,
+        _request: Request<proto::WatchCrawlRequest>,
+    ) -> Result<Response<Self::WatchCrawlStream>, Status> {
+        let slot = self.slot();
+        if slot.events.is_none() && slot.history.is_empty() {
+            return Err(Status::failed_precondition("no crawl has been started"));
+        }
+
+        // The events sent so far are replayed first; a finished crawl's end
+        // with `finished`, so the stream ends there
+        let replay = tokio_stream::iter(slot.history.clone().into_iter().map(Ok));
+        let stream: Self::WatchCrawlStream = match &slot.events {
+            Some(sender) => {
+                // Events a lagging watcher missed are skipped
+                let live =
+                    BroadcastStream::new(sender.subscribe()).filter_map(|event| event.ok().map(Ok));
+                Box::pin(replay.chain(live))
+            }
+            None => Box::pin(replay),
+        };
+        Ok(Response::new(stream))
+    }
+}
+
+/// Converts a crawl event into its gRPC message
+///
+/// The body of fetched pages is not sent.
+///
+/// # Arguments
+///
+/// * `event` - The crawl event
+///
+/// # Returns
+///
+/// The `CrawlEvent` message; missing values are empty strings
+pub fn event_to_proto(event: CrawlEvent) -> proto::CrawlEvent {
+    use proto::crawl_event::Event;
+
+    let event = match event {
+        CrawlEvent::PageFetched {
+            page_id,
+            url,
+            final_url,
+            status_code,
+            content_type,
+            title,
+            body: _,
+        } => Event::PageFetched(proto::PageFetched {
+            page_id,
+            url,
+            final_url,
+            status_code: u32::from(status_code),
+            content_type,
+            title: title.unwrap_or_default(),
+        }),
+        CrawlEvent::PageFailed {
+            page_id,
+            url,
+            state,
+            error,
+        } => Event::PageFailed(proto::PageFailed {
+            page_id,
+            url,
+            state: state.to_db_string().to_string(),
+            error,
+        }),
+        CrawlEvent::LinkDiscovered {
+            from_page_id,
+            to_page_id,
+            url,
+            domain,
+            classification,
+            kind,
+        } => Event::LinkDiscovered(proto::LinkDiscovered {
+            from_page_id,
+            to_page_id,
+            url,
+            domain,
+            classification: classification.to_db_string().to_string(),
+            kind: kind.as_str().to_string(),
+        }),
+        CrawlEvent::Finished { run_id, error } => Event::Finished(proto::Finished {
+            run_id,
+            error: error.unwrap_or_default(),
+        }),
+    };
+    proto::CrawlEvent { event: Some(event) }
+}
+
+/// Serves the control API until Ctrl-C is pressed
+///
+/// A crawl still running at shutdown is stopped and its run marked
+/// interrupted before this returns.
+///
+/// # Arguments
+///
+/// * `addr` - Address to listen on, e.g. 127.0.0.1:50051
+/// * `config` - Configuration used when `StartCrawl` names no file
+///
+/// # Returns
+///
+/// * `Ok(())` - The server shut down
+/// * `Err(SumiError)` - The address could not be bound or the server failed
+pub async fn serve_grpc(addr: SocketAddr, config: Config) -> Result<(), SumiError> {
+    let service = ControlService::new(config);
+    tracing::info!("Serving the crawl control API on {}", addr);
+
+    let result = tonic::transport::Server::builder()
+        .add_service(CrawlControlServer::new(service.clone()))
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    service.shutdown().await;
+
+    result.map_err(|e| SumiError::Io(std::io::Error::other(e.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use crate::state::PageState;
+    use crate::storage::LinkKind;
+    use crate::url::DomainClassification;
+    use proto::crawl_event::Event;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(quality_domain: &str, seed: &str, db_path: &str) -> Config {
+        let mut config: Config = toml::from_str(&test_config_toml(&format!(
+            "\n[[quality]]\ndomain = \"{quality_domain}\"\nseeds = [\"{seed}\"]\n"
+        )))
+        .unwrap();
+        config.crawler.max_depth = 1;
+        config.crawler.max_concurrent_pages_open = 2;
+        config.crawler.per_domain_delay_ms = 10;
+        config.crawler.max_domain_requests = 50;
+        config.output.database_path = db_path.to_string();
+        config
+    }
+
+    #[test]
+    fn test_event_to_proto() {
+        let fetched = event_to_proto(CrawlEvent::PageFetched {
+            page_id: 1,
+            url: "https://a.com/".to_string(),
+            final_url: "https://a.com/home".to_string(),
+            status_code: 200,
+            content_type: "text/html".to_string(),
+            title: None,
+            body: "<html></html>".to_string(),
+        });
+        let Some(Event::PageFetched(fetched)) = fetched.event else {
+            panic!("expected page_fetched");
+        };
+        assert_eq!(fetched.final_url, "https://a.com/home");
+        assert_eq!(fetched.status_code, 200);
+        assert_eq!(fetched.title, "");
+
+        let failed = event_to_proto(CrawlEvent::PageFailed {
+            page_id: 2,
+            url: "https://a.com/gone".to_string(),
+            state: PageState::DeadLink,
+            error: "HTTP 404".to_string(),
+        });
+        assert!(matches!(failed.event, Some(Event::PageFailed(ref e)) if e.state == "dead_link"));
+
+        let link = event_to_proto(CrawlEvent::LinkDiscovered {
+            from_page_id: 1,
+            to_page_id: 3,
+            url: "https://b.org/".to_string(),
+            domain: "b.org".to_string(),
+            classification: DomainClassification::Discovered,
+            kind: LinkKind::Anchor,
+        });
+        let Some(Event::LinkDiscovered(link)) = link.event else {
+            panic!("expected link_discovered");
+        };
+        assert_eq!(link.classification, "discovered");
+        assert_eq!(link.kind, LinkKind::Anchor.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_idle_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("crawl.db");
+        let service =
+            ControlService::new(config("a.com", "https://a.com/", db_path.to_str().unwrap()));
+
+        let status = service
+            .get_status(Request::new(proto::GetStatusRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(status.state(), CrawlState::Idle);
+
+        let stopped = service
+            .stop_crawl(Request::new(proto::StopCrawlRequest {}))
+            .await;
+        assert_eq!(stopped.unwrap_err().code(), tonic::Code::FailedPrecondition);
+        let watched = service
+            .watch_crawl(Request::new(proto::WatchCrawlRequest {}))
+            .await;
+        assert!(matches!(watched, Err(status) if status.code() == tonic::Code::FailedPrecondition));
+
+        // Only one crawl runs at a time
+        service.slot().status.set_state(CrawlState::Running);
+        let busy = service
+            .start_crawl(Request::new(proto::StartCrawlRequest::default()))
+            .await;
+        assert_eq!(busy.unwrap_err().code(), tonic::Code::FailedPrecondition);
+        service.slot().status.set_state(CrawlState::Idle);
+
+        let missing = service
+            .start_crawl(Request::new(proto::StartCrawlRequest {
+                mode: proto::CrawlMode::Fresh.into(),
+                config_path: dir.path().join("missing.toml").display().to_string(),
+            }))
+            .await;
+        assert!(missing.is_err());
+        let status = service.slot().status.clone();
+        assert_eq!(status.state(), CrawlState::Failed);
+        assert!(!status.error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_start_and_watch_crawl() {
+        let mock_server = MockServer::start().await;
+        let base_url = mock_server.uri();
+        let domain = url::Url::parse(&base_url)
+            .unwrap()
+            .host_str()
+            .unwrap()
+            .to_string();
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-type", "text/html"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(r#"<html><body><a href="{base_url}/about">About</a></body></html>"#),
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/about"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw("<html><body></body></html>", "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("crawl.db");
+        let service = ControlService::new(config(
+            &domain,
+            &format!("{base_url}/"),
+            db_path.to_str().unwrap(),
+        ));
+
+        let started = service
+            .start_crawl(Request::new(proto::StartCrawlRequest {
+                mode: proto::CrawlMode::Fresh.into(),
+                config_path: String::new(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(started.state(), CrawlState::Running);
+        assert!(started.run_id > 0);
+
+        // However far the crawl got, a watcher sees every event, ending
+        // with finished
+        let watch = || async {
+            let mut events = service
+                .watch_crawl(Request::new(proto::WatchCrawlRequest {}))
+                .await
+                .unwrap()
+                .into_inner();
+            let mut received = Vec::new();
+            while let Some(event) = events.next().await {
+                received.push(event.unwrap().event.unwrap());
+            }
+            received
+        };
+        let received = watch().await;
+        assert!(received
+            .iter()
+            .any(|event| matches!(event, Event::PageFetched(_))));
+        let Some(Event::Finished(finished)) = received.last() else {
+            panic!("the stream ends with finished");
+        };
+        assert_eq!(finished.run_id, started.run_id);
+        assert_eq!(finished.error, "");
+
+        // The finished crawl can still be watched
+        assert_eq!(watch().await, received);
+
+        let status = service
+            .get_status(Request::new(proto::GetStatusRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(status.state(), CrawlState::Finished);
+        assert!(status.pages_fetched >= 1);
+        assert!(status.links_discovered >= 1);
+    }
+}
@@ -3,8 +3,13 @@
 //! This crate implements a web crawler that maps link relationships between websites,
 //! respecting robots.txt, rate limits, and domain classifications.
 
+#[cfg(feature = "api")]
+pub mod api;
 pub mod config;
 pub mod crawler;
+pub mod graph;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod output;
 pub mod robots;
 pub mod state;
@@ -52,6 +57,14 @@ pub enum SumiError {
     #[error("URL disallowed by robots.txt: {url}")]
     RobotsDenied { url: String },
 
+    #[error("robots.txt for {domain} unavailable (HTTP {status})")]
+    RobotsUnavailable {
+        domain: String,
+        status: u16,
+        /// How long the server asked to wait, from its Retry-After header
+        retry_after: Option<std::time::Duration>,
+    },
+
     #[error("Invalid state transition: {from:?} -> {to:?}")]
     InvalidTransition {
         from: state::PageState,
@@ -66,6 +79,9 @@ pub enum SumiError {
 
     #[error("Robots.txt error: {0}")]
     Robots(String),
+
+    #[error("Replay error: {0}")]
+    Replay(String),
 }
 
 /// Configuration-specific errors
@@ -85,6 +101,18 @@ pub enum ConfigError {
 
     #[error("Invalid domain pattern: {0}")]
     InvalidPattern(String),
+
+    #[error("Config include error: {0}")]
+    Include(String),
+
+    #[error("Failed to import blocklist: {0}")]
+    Blocklist(String),
+
+    #[error("Failed to read seeds file: {0}")]
+    SeedsFile(String),
+
+    #[error("Failed to serialize config: {0}")]
+    Serialize(#[from] toml::ser::Error),
 }
 
 /// URL-specific errors
@@ -115,4 +143,7 @@ pub type UrlResult<T> = std::result::Result<T, UrlError>;
 // Re-export commonly used types
 pub use config::Config;
 pub use state::{DomainState, PageState};
-pub use url::{classify_domain, extract_domain, normalize_url, DomainClassification};
+pub use url::{
+    classify_domain, extract_domain, normalize_url, Classifier, DomainClassification,
+    DomainClassifier,
+};
@@ -2,10 +2,16 @@
 //!
 //! This is the command-line interface for the Sumi-Ripple web terrain mapper.
 
-use clap::Parser;
+use clap::{ArgGroup, Parser, ValueEnum};
 use std::path::PathBuf;
-use sumi_ripple::config::load_config_with_hash;
-use sumi_ripple::crawler::crawl;
+use sumi_ripple::config::{
+    load_env_config, load_layered_config, serialize_config, validate_config_file,
+    validate_env_config, write_example_config, ConfigOverrides, CrawlProfile, CONFIG_JSON_ENV,
+};
+use sumi_ripple::crawler::{crawl_with_reload, CrawlMode, Fetcher, ReplayFetcher};
+use sumi_ripple::url::DomainClassification;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::EnvFilter;
 
 /// Sumi-Ripple: A polite web terrain mapper
@@ -17,10 +23,25 @@ use tracing_subscriber::EnvFilter;
 #[command(name = "sumi-ripple")]
 #[command(version = "1.0.0")]
 #[command(about = "A polite web terrain mapper", long_about = None)]
+#[command(group(ArgGroup::new("mode").multiple(false)))]
+#[command(group(ArgGroup::new("run_scoped").args(["stats", "export_summary", "export_hits"])))]
 struct Cli {
     /// Path to TOML configuration file
-    #[arg(value_name = "CONFIG")]
-    config: PathBuf,
+    #[arg(
+        value_name = "CONFIG",
+        required_unless_present_any = ["init_config", "env_config"]
+    )]
+    config: Option<PathBuf>,
+
+    /// Read the configuration from the environment instead of a file: the
+    /// JSON in SUMI_CONFIG_JSON, if set, with SUMI_SECTION__KEY variables
+    /// applied on top
+    #[arg(long, conflicts_with = "config")]
+    env_config: bool,
+
+    /// Format of log lines: human-readable text or one JSON object per line
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
 
     /// Increase logging verbosity (-v, -vv, -vvv)
     #[arg(short, long, action = clap::ArgAction::Count)]
@@ -31,36 +52,306 @@ struct Cli {
     quiet: bool,
 
     /// Resume an interrupted crawl (default behavior)
-    #[arg(long, conflicts_with = "fresh")]
+    #[arg(long, conflicts_with_all = ["fresh", "incremental"])]
     resume: bool,
 
     /// Start a fresh crawl, ignoring previous state
-    #[arg(long, conflicts_with = "resume")]
+    #[arg(long, conflicts_with_all = ["resume", "incremental"])]
     fresh: bool,
 
+    /// Start a new crawl from the seeds that only refetches pages older than
+    /// crawler.recrawl-after-days, reusing the stored links of the others
+    #[arg(long, conflicts_with_all = ["resume", "fresh"])]
+    incremental: bool,
+
+    /// When the crawl finishes, print a JSON report of the run to stdout
+    /// (logs go to stderr)
+    #[arg(long, conflicts_with = "mode")]
+    report_json: bool,
+
+    /// Serve pages and robots.txt from a WARC file (.warc or .warc.gz)
+    /// instead of the network, re-crawling a captured crawl
+    #[arg(long, value_name = "WARC", conflicts_with = "mode")]
+    replay: Option<PathBuf>,
+
+    /// Override crawler.max-depth
+    #[arg(long, value_name = "N")]
+    max_depth: Option<u32>,
+
+    /// Override crawler.max-concurrent-pages-open
+    #[arg(long, value_name = "N")]
+    max_concurrent_pages: Option<u32>,
+
+    /// Override crawler.max-domain-requests
+    #[arg(long, value_name = "N")]
+    max_domain_requests: Option<u32>,
+
+    /// Override output.database-path
+    #[arg(long, value_name = "FILE")]
+    database: Option<PathBuf>,
+
+    /// Preset the politeness settings the configuration leaves out
+    /// (overrides profile.name)
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        value_parser = clap::builder::PossibleValuesParser::new(CrawlProfile::NAMES)
+    )]
+    profile: Option<String>,
+
+    /// Allow an aggressive profile on quality domains that are not owned
+    /// (sets profile.allow-unowned)
+    #[arg(long)]
+    allow_unowned_profile: bool,
+
+    /// Override any configuration value by its dotted key (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_set_spec)]
+    set: Vec<(String, String)>,
+
     /// Validate config and show what would be crawled without actually crawling
-    #[arg(long, conflicts_with_all = ["stats", "export_summary"])]
+    #[arg(long, group = "mode")]
     dry_run: bool,
 
+    /// Write a commented example configuration (default: config.toml) and exit
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = "config.toml",
+        group = "mode"
+    )]
+    init_config: Option<PathBuf>,
+
+    /// Print the fully merged configuration (with defaults) as TOML and exit
+    #[arg(long, group = "mode")]
+    print_effective_config: bool,
+
+    /// Run all config checks and lints, print JSON diagnostics, and exit
+    /// (exit status 1 if there are errors)
+    #[arg(long, group = "mode")]
+    validate_config: bool,
+
     /// Show statistics from the database and exit
-    #[arg(long, conflicts_with_all = ["dry_run", "export_summary"])]
+    #[arg(long, group = "mode")]
     stats: bool,
 
+    /// List the crawl runs stored in the database and exit
+    #[arg(long, group = "mode")]
+    list_runs: bool,
+
+    /// Show the details and statistics of one run and exit
+    #[arg(long, value_name = "ID", group = "mode")]
+    show_run: Option<i64>,
+
+    /// Delete a run and the pages, links and records it discovered, then exit
+    #[arg(long, value_name = "ID", group = "mode")]
+    delete_run: Option<i64>,
+
+    /// Prune stale rows, reindex and vacuum the database, then exit
+    #[arg(long, group = "mode")]
+    db_maintenance: bool,
+
+    /// Delete the runs and history the [retention] policy no longer keeps,
+    /// then exit
+    #[arg(long, group = "mode")]
+    prune: bool,
+
+    /// Check the database for violated crawl invariants and exit
+    /// (exit status 1 if any remain)
+    #[arg(long, group = "mode")]
+    check_db: bool,
+
+    /// With --check-db, delete the rows violating repairable invariants
+    #[arg(long, requires = "check_db")]
+    repair: bool,
+
+    /// Show how the configuration changed between two runs and exit
+    #[arg(long, value_names = ["RUN_A", "RUN_B"], num_args = 2, group = "mode")]
+    diff_runs: Option<Vec<i64>>,
+
+    /// Show the pages whose state, status, title, redirect or content changed
+    /// between two runs and exit
+    #[arg(long, value_names = ["RUN_A", "RUN_B"], num_args = 2, group = "mode")]
+    diff_pages: Option<Vec<i64>>,
+
     /// Generate markdown summary from existing data and exit
-    #[arg(long, conflicts_with_all = ["dry_run", "stats"])]
+    #[arg(long, group = "mode")]
     export_summary: bool,
+
+    /// Limit --stats, --export-summary or --export-hits to the pages and links of one run
+    #[arg(long, value_name = "ID", requires = "run_scoped")]
+    run_id: Option<i64>,
+
+    /// Generate an interactive HTML report from existing data and exit
+    #[arg(long, value_name = "FILE", group = "mode")]
+    export_html: Option<PathBuf>,
+
+    /// Export blacklisted and stubbed URL encounters with referrers as CSV and exit
+    #[arg(long, value_name = "FILE", group = "mode")]
+    export_hits: Option<PathBuf>,
+
+    /// Export pages, links and domains as Parquet files into a directory and
+    /// exit (requires the `parquet` feature)
+    #[arg(long, value_name = "DIR", group = "mode")]
+    export_parquet: Option<PathBuf>,
+
+    /// Export pages and links as a Cypher script for Neo4j and exit
+    #[arg(long, value_name = "FILE", group = "mode")]
+    export_cypher: Option<PathBuf>,
+
+    /// Serve the crawl database as a read-only JSON API on an address such as
+    /// 127.0.0.1:8080 until interrupted (requires the `api` feature)
+    #[arg(long, value_name = "ADDR", group = "mode")]
+    serve_api: Option<std::net::SocketAddr>,
+
+    /// Serve the gRPC control API on an address such as 127.0.0.1:50051,
+    /// starting, stopping and streaming crawls on request until interrupted
+    /// (requires the `grpc` feature)
+    #[arg(long, value_name = "ADDR", group = "mode")]
+    serve_grpc: Option<std::net::SocketAddr>,
+
+    /// Fetch the robots.txt governing a URL, report whether the configured
+    /// user agent may crawl it and which rule decided, and exit
+    #[arg(long, value_name = "URL", group = "mode")]
+    check_robots: Option<String>,
+
+    /// Print everything known about a URL (classification, state, depths,
+    /// inbound links, frontier) and exit
+    #[arg(long, value_name = "URL", group = "mode")]
+    explain: Option<String>,
+
+    /// Search crawled page titles and URLs and exit
+    #[arg(long, value_name = "QUERY", group = "mode")]
+    search: Option<String>,
+
+    /// Tag a domain (DOMAIN:TAG, repeatable) and exit
+    #[arg(long, value_name = "DOMAIN:TAG", value_parser = parse_tag_spec, group = "mode")]
+    tag_domain: Vec<(String, String)>,
+
+    /// Tag a crawled page (URL:TAG, repeatable) and exit
+    #[arg(long, value_name = "URL:TAG", value_parser = parse_tag_spec, group = "mode")]
+    tag_page: Vec<(String, String)>,
+
+    /// Blacklist a domain via a database override (repeatable) and exit
+    #[arg(long, value_name = "DOMAIN", group = "mode")]
+    blacklist_domain: Vec<String>,
+
+    /// Stub a domain via a database override (repeatable) and exit
+    #[arg(long, value_name = "DOMAIN", group = "mode")]
+    stub_domain: Vec<String>,
+
+    /// Remove a domain's database override (repeatable) and exit
+    #[arg(long, value_name = "DOMAIN", group = "mode")]
+    clear_domain_override: Vec<String>,
+}
+
+/// Format of log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Parses a `KEY=VALUE` configuration override
+fn parse_set_spec(spec: &str) -> Result<(String, String), String> {
+    let (key, value) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", spec))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("expected KEY=VALUE, got '{}'", spec));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Builds the override layers: environment variables, then command-line flags
+fn collect_overrides(cli: &Cli) -> ConfigOverrides {
+    let mut overrides = ConfigOverrides::from_env();
+
+    if let Some(n) = cli.max_depth {
+        overrides.push_cli("crawler.max-depth", &n.to_string());
+    }
+    if let Some(n) = cli.max_concurrent_pages {
+        overrides.push_cli("crawler.max-concurrent-pages-open", &n.to_string());
+    }
+    if let Some(n) = cli.max_domain_requests {
+        overrides.push_cli("crawler.max-domain-requests", &n.to_string());
+    }
+    if let Some(path) = &cli.database {
+        overrides.push_cli("output.database-path", &path.to_string_lossy());
+    }
+    if let Some(profile) = &cli.profile {
+        overrides.push_cli("profile.name", profile);
+    }
+    if cli.allow_unowned_profile {
+        overrides.push_cli("profile.allow-unowned", "true");
+    }
+    // Generic --set overrides come last so they win over the dedicated flags
+    for (key, value) in &cli.set {
+        overrides.push_cli(key, value);
+    }
+
+    overrides
+}
+
+/// Parses a `TARGET:TAG` argument, splitting on the last colon so URLs work
+fn parse_tag_spec(spec: &str) -> Result<(String, String), String> {
+    let (target, tag) = spec
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected TARGET:TAG, got '{}'", spec))?;
+    let (target, tag) = (target.trim(), tag.trim());
+    if target.is_empty() || tag.is_empty() {
+        return Err(format!("expected TARGET:TAG, got '{}'", spec));
+    }
+    Ok((target.to_string(), tag.to_string()))
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
-    // Setup logging based on verbosity
-    setup_logging(cli.verbose, cli.quiet);
+    // Setup logging based on verbosity; keep stdout clean for machine-readable output
+    setup_logging(
+        cli.verbose,
+        cli.quiet || cli.validate_config || cli.print_effective_config,
+        cli.report_json,
+        cli.log_format,
+    );
+
+    // Needs no existing configuration
+    if let Some(path) = &cli.init_config {
+        return handle_init_config(path);
+    }
+    // Without a file the configuration comes from the environment
+    let config_path = cli.config.clone();
+
+    let overrides = collect_overrides(&cli);
+
+    // Reports problems itself instead of failing on the first one
+    if cli.validate_config {
+        return handle_validate_config(config_path.as_deref(), &overrides);
+    }
 
     // Load and validate configuration
-    tracing::info!("Loading configuration from: {}", cli.config.display());
-    let (config, _config_hash) = match load_config_with_hash(&cli.config) {
+    for entry in overrides.entries() {
+        tracing::debug!("Config override: {} = {}", entry.key, entry.value);
+    }
+    let loaded = match &config_path {
+        Some(path) => {
+            tracing::info!("Loading configuration from: {}", path.display());
+            load_layered_config(path, &overrides)
+        }
+        None => {
+            tracing::info!(
+                "Loading configuration from {} and SUMI_* variables",
+                CONFIG_JSON_ENV
+            );
+            load_env_config(&overrides)
+        }
+    };
+    let (config, _config_hash) = match loaded {
         Ok((cfg, hash)) => {
             tracing::info!("Configuration loaded successfully (hash: {})", hash);
             (cfg, hash)
@@ -74,19 +365,87 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Handle different modes
     if cli.dry_run {
         handle_dry_run(&config)?;
+    } else if cli.print_effective_config {
+        print!("{}", serialize_config(&config)?);
     } else if cli.stats {
-        handle_stats(&config)?;
+        handle_stats(&config, cli.run_id)?;
+    } else if cli.list_runs {
+        handle_list_runs(&config)?;
+    } else if let Some(run_id) = cli.show_run {
+        handle_show_run(&config, run_id)?;
+    } else if let Some(run_id) = cli.delete_run {
+        handle_delete_run(&config, run_id)?;
+    } else if cli.db_maintenance {
+        handle_db_maintenance(&config)?;
+    } else if cli.prune {
+        handle_prune(&config)?;
+    } else if cli.check_db {
+        handle_check_db(&config, cli.repair)?;
+    } else if let Some(runs) = &cli.diff_runs {
+        handle_diff_runs(&config, runs[0], runs[1])?;
+    } else if let Some(runs) = &cli.diff_pages {
+        handle_diff_pages(&config, runs[0], runs[1])?;
     } else if cli.export_summary {
-        handle_export_summary(&config)?;
+        handle_export_summary(&config, cli.run_id)?;
+    } else if let Some(path) = &cli.export_html {
+        handle_export_html(&config, path)?;
+    } else if let Some(path) = &cli.export_hits {
+        handle_export_hits(&config, path, cli.run_id)?;
+    } else if let Some(dir) = &cli.export_parquet {
+        handle_export_parquet(&config, dir)?;
+    } else if let Some(path) = &cli.export_cypher {
+        handle_export_cypher(&config, path)?;
+    } else if let Some(addr) = cli.serve_api {
+        handle_serve_api(&config, addr).await?;
+    } else if let Some(addr) = cli.serve_grpc {
+        handle_serve_grpc(&config, addr).await?;
+    } else if let Some(url) = &cli.check_robots {
+        handle_check_robots(&config, url).await?;
+    } else if let Some(url) = &cli.explain {
+        handle_explain(&config, url)?;
+    } else if let Some(query) = &cli.search {
+        handle_search(&config, query)?;
+    } else if !cli.tag_domain.is_empty() {
+        handle_tag_domains(&config, &cli.tag_domain)?;
+    } else if !cli.tag_page.is_empty() {
+        handle_tag_pages(&config, &cli.tag_page)?;
+    } else if !cli.blacklist_domain.is_empty() {
+        handle_set_overrides(
+            &config,
+            &cli.blacklist_domain,
+            DomainClassification::Blacklisted,
+        )?;
+    } else if !cli.stub_domain.is_empty() {
+        handle_set_overrides(&config, &cli.stub_domain, DomainClassification::Stubbed)?;
+    } else if !cli.clear_domain_override.is_empty() {
+        handle_clear_overrides(&config, &cli.clear_domain_override)?;
     } else {
-        handle_crawl(config, cli.fresh).await?;
+        let mode = if cli.incremental {
+            CrawlMode::Incremental
+        } else if cli.fresh {
+            CrawlMode::Fresh
+        } else {
+            CrawlMode::Resume
+        };
+        handle_crawl(
+            config,
+            config_path.as_deref(),
+            overrides,
+            mode,
+            cli.report_json,
+            cli.replay.as_deref(),
+        )
+        .await?;
     }
 
     Ok(())
 }
 
 /// Sets up the logging/tracing subscriber based on verbosity level
-fn setup_logging(verbose: u8, quiet: bool) {
+///
+/// Logs go to stdout unless `to_stderr` is set, which keeps stdout free for
+/// machine-readable output.
+fn setup_logging(verbose: u8, quiet: bool, to_stderr: bool, format: LogFormat) {
     let filter = if quiet {
         // Only show errors
         EnvFilter::new("error")
@@ -99,12 +458,56 @@ fn setup_logging(verbose: u8, quiet: bool) {
         }
     };
 
-    tracing_subscriber::fmt()
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(writer)
         .with_env_filter(filter)
         .with_target(false)
         .with_thread_ids(false)
-        .with_file(false)
-        .init();
+        .with_file(false);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        // Fields at the top level so collectors need no nested lookups
+        LogFormat::Json => subscriber.json().flatten_event(true).init(),
+    }
+}
+
+/// Handles the --init-config mode: writes the example configuration
+fn handle_init_config(path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    if path.exists() {
+        return Err(format!("{} already exists, not overwriting it", path.display()).into());
+    }
+
+    write_example_config(path)?;
+    println!("✓ Wrote example configuration to {}", path.display());
+    println!("  Edit the [user-agent] contact details and [[quality]] seeds, then check it with:");
+    println!("  sumi-ripple {} --validate-config", path.display());
+    Ok(())
+}
+
+/// Handles the --validate-config mode: prints every diagnostic as JSON
+///
+/// Exits with status 1 if the configuration has errors, so CI jobs can gate
+/// on it; warnings and notes alone leave the status at 0.
+fn handle_validate_config(
+    config_path: Option<&std::path::Path>,
+    overrides: &ConfigOverrides,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = match config_path {
+        Some(path) => validate_config_file(path, overrides),
+        None => validate_env_config(overrides),
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.valid {
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
 /// Handles the --dry-run mode: validates config and shows what would be crawled
@@ -112,19 +515,26 @@ fn handle_dry_run(config: &sumi_ripple::config::Config) -> Result<(), Box<dyn st
     println!("=== Sumi-Ripple Dry Run ===\n");
 
     println!("Crawler Configuration:");
+    if let Some(profile) = config.profile.name {
+        println!("  Profile: {}", profile.as_str());
+    }
     println!("  Max depth: {}", config.crawler.max_depth);
     println!(
         "  Max concurrent pages: {}",
         config.crawler.max_concurrent_pages_open
     );
     println!(
-        "  Minimum time on page: {}ms",
-        config.crawler.minimum_time_on_page
+        "  Per-domain delay: {}ms (+ up to {}ms jitter)",
+        config.crawler.per_domain_delay_ms, config.crawler.per_request_jitter_ms
     );
     println!(
         "  Max domain requests: {}",
         config.crawler.max_domain_requests
     );
+    println!(
+        "  Max discovered domain requests: {}",
+        config.crawler.max_discovered_domain_requests
+    );
 
     println!("\nUser Agent:");
     println!("  Name: {}", config.user_agent.crawler_name);
@@ -163,19 +573,27 @@ fn handle_dry_run(config: &sumi_ripple::config::Config) -> Result<(), Box<dyn st
     Ok(())
 }
 
-/// Handles the --stats mode: shows statistics from the database
-fn handle_stats(config: &sumi_ripple::config::Config) -> Result<(), Box<dyn std::error::Error>> {
+/// Handles the --stats mode: shows statistics from the database, optionally
+/// limited to a single run
+fn handle_stats(
+    config: &sumi_ripple::config::Config,
+    run_id: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
     use std::path::Path;
-    use sumi_ripple::output::{load_statistics, print_statistics};
+    use sumi_ripple::output::{load_run_statistics, load_statistics, print_statistics};
     use sumi_ripple::storage::SqliteStorage;
 
     println!("Database: {}\n", config.output.database_path);
 
     // Open the database
-    let storage = SqliteStorage::new(Path::new(&config.output.database_path))?;
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
 
     // Load statistics
-    let stats = load_statistics(&storage)?;
+    let stats = match run_id {
+        Some(run_id) => load_run_statistics(&storage, run_id)?,
+        None => load_statistics(&storage)?,
+    };
 
     // Print statistics
     print_statistics(&stats);
@@ -183,12 +601,316 @@ fn handle_stats(config: &sumi_ripple::config::Config) -> Result<(), Box<dyn std:
     Ok(())
 }
 
-/// Handles the --export-summary mode: generates markdown summary
+/// Handles the --list-runs mode: lists every run with its page counts
+fn handle_list_runs(
+    config: &sumi_ripple::config::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    let runs = storage.list_runs()?;
+    if runs.is_empty() {
+        println!("No crawl runs found in database");
+        return Ok(());
+    }
+
+    println!(
+        "{:>5}  {:<10}  {:<25}  {:<25}  {:>10}  {:>8}",
+        "ID", "Status", "Started", "Finished", "Discovered", "Visited"
+    );
+    for run in &runs {
+        println!(
+            "{:>5}  {:<10}  {:<25}  {:<25}  {:>10}  {:>8}",
+            run.id,
+            run.status.to_db_string(),
+            run.started_at,
+            run.finished_at.as_deref().unwrap_or("-"),
+            storage.count_pages_discovered_in_run(run.id)?,
+            storage.count_pages_visited_in_run(run.id)?
+        );
+    }
+    println!("\n✓ {} runs", runs.len());
+
+    Ok(())
+}
+
+/// Handles the --show-run mode: shows a run's metadata and run-scoped statistics
+fn handle_show_run(
+    config: &sumi_ripple::config::Config,
+    run_id: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::{load_run_statistics, print_statistics};
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    let run = storage.get_run(run_id)?;
+    println!("=== Run {} ===\n", run.id);
+    println!("  Status: {}", run.status.to_db_string());
+    println!("  Started: {}", run.started_at);
+    println!("  Finished: {}", run.finished_at.as_deref().unwrap_or("-"));
+    println!("  Config hash: {}", run.config_hash);
+    println!(
+        "  Progress snapshots: {}",
+        storage.get_run_progress(run.id)?.len()
+    );
+    println!();
+
+    print_statistics(&load_run_statistics(&storage, run_id)?);
+
+    Ok(())
+}
+
+/// Handles the --delete-run mode: deletes a run and the rows scoped to it
+fn handle_delete_run(
+    config: &sumi_ripple::config::Config,
+    run_id: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    let deletion = storage.delete_run(run_id)?;
+    println!("✓ Deleted run {}", run_id);
+    println!("  Pages: {}", deletion.pages);
+    println!("  Links: {}", deletion.links);
+    println!(
+        "  Blacklisted/stubbed URL records: {}",
+        deletion.url_records
+    );
+    println!("  Progress snapshots: {}", deletion.progress_snapshots);
+
+    Ok(())
+}
+
+/// Handles the --db-maintenance mode: prunes, reindexes and vacuums the database
+fn handle_db_maintenance(
+    config: &sumi_ripple::config::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("Database: {}\n", config.output.database_path);
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    let report = storage.run_maintenance()?;
+    println!(
+        "  Orphaned frontier entries removed: {}",
+        report.frontier_pruned
+    );
+    println!(
+        "  Duplicate referrer records removed: {}",
+        report.referrers_pruned
+    );
+    println!(
+        "  Old page state transitions removed: {}",
+        report.history_pruned
+    );
+    println!(
+        "  Size: {:.1} KiB -> {:.1} KiB",
+        report.size_before as f64 / 1024.0,
+        report.size_after as f64 / 1024.0
+    );
+    println!(
+        "\n✓ Maintenance complete, {:.1} KiB reclaimed",
+        report.reclaimed() as f64 / 1024.0
+    );
+
+    Ok(())
+}
+
+/// Handles the --prune mode: applies the retention policy to the database
+fn handle_prune(config: &sumi_ripple::config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("Database: {}\n", config.output.database_path);
+
+    if !config.retention.is_enabled() {
+        println!("No [retention] limits configured, nothing to prune");
+        return Ok(());
+    }
+
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    let report = storage.apply_retention(&config.retention)?;
+    println!("  Runs removed: {}", report.runs_pruned);
+    println!(
+        "  Page observations removed: {}",
+        report.observations_pruned
+    );
+    println!(
+        "  State transitions and progress snapshots removed: {}",
+        report.events_pruned
+    );
+    println!("\n✓ Pruning complete; run --db-maintenance to shrink the file");
+
+    Ok(())
+}
+
+/// Handles the --check-db mode: reports (and optionally repairs) violated
+/// crawl invariants
+///
+/// Exits with status 1 if violations remain after any repair.
+fn handle_check_db(
+    config: &sumi_ripple::config::Config,
+    repair: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("Database: {}\n", config.output.database_path);
+
+    // Only repairs need write access
+    let path = Path::new(&config.output.database_path);
+    let storage = if repair {
+        let mut storage = SqliteStorage::with_config(path, &config.database)?;
+        for (check, removed) in storage.repair_integrity()? {
+            if removed > 0 {
+                println!("Repaired: {} ({} rows removed)", check, removed);
+            }
+        }
+        println!();
+        storage
+    } else {
+        SqliteStorage::open_read_only(path, &config.database)?
+    };
+
+    let issues = storage.check_integrity()?;
+    for issue in &issues {
+        if issue.is_ok() {
+            println!("✓ {}", issue.check);
+            continue;
+        }
+        let hint = if issue.check.is_repairable() && !repair {
+            " (repairable with --repair)"
+        } else {
+            ""
+        };
+        println!("✗ {}: {} violations{}", issue.check, issue.count, hint);
+        for example in &issue.examples {
+            println!("    {}", example);
+        }
+    }
+
+    let violated = issues.iter().filter(|issue| !issue.is_ok()).count();
+    if violated > 0 {
+        println!("\n{} of {} checks failed", violated, issues.len());
+        std::process::exit(1);
+    }
+    println!("\n✓ All {} checks passed", issues.len());
+
+    Ok(())
+}
+
+/// Handles the --diff-runs mode: compares the configurations of two runs
+fn handle_diff_runs(
+    config: &sumi_ripple::config::Config,
+    run_a: i64,
+    run_b: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::config::diff_configs;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    let load = |run_id: i64| -> Result<String, Box<dyn std::error::Error>> {
+        storage.get_run(run_id)?;
+        let record = storage
+            .get_run_config(run_id)?
+            .ok_or_else(|| format!("No configuration stored for run {}", run_id))?;
+        Ok(record.config_toml)
+    };
+    let changes = diff_configs(&load(run_a)?, &load(run_b)?)?;
+
+    if changes.is_empty() {
+        println!(
+            "✓ Runs {} and {} used identical configurations",
+            run_a, run_b
+        );
+        return Ok(());
+    }
+
+    println!(
+        "=== Configuration changes: run {} -> run {} ===\n",
+        run_a, run_b
+    );
+    for change in &changes {
+        println!("{}", change);
+    }
+    println!("\n✓ {} changes", changes.len());
+
+    Ok(())
+}
+
+/// Handles the --diff-pages mode: lists the pages that changed between two runs
+fn handle_diff_pages(
+    config: &sumi_ripple::config::Config,
+    run_a: i64,
+    run_b: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::load_page_changes;
+    use sumi_ripple::storage::SqliteStorage;
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    let pages = load_page_changes(&storage, run_a, run_b)?;
+    if pages.is_empty() {
+        println!(
+            "✓ No page seen by both runs {} and {} changed",
+            run_a, run_b
+        );
+        return Ok(());
+    }
+
+    println!("=== Page changes: run {} -> run {} ===", run_a, run_b);
+    for page in &pages {
+        println!("\n{}", page.url);
+        for change in &page.changes {
+            println!(
+                "  {}: {} -> {}",
+                change.field,
+                change.before.as_deref().unwrap_or("(none)"),
+                change.after.as_deref().unwrap_or("(none)")
+            );
+        }
+    }
+    println!("\n✓ {} pages changed", pages.len());
+
+    Ok(())
+}
+
+/// Handles the --export-summary mode: generates markdown summary, optionally
+/// limited to a single run
 fn handle_export_summary(
     config: &sumi_ripple::config::Config,
+    run_id: Option<i64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use std::path::Path;
-    use sumi_ripple::output::{generate_markdown_summary, generate_summary};
+    use sumi_ripple::output::{
+        generate_markdown_summary, generate_run_manifest, generate_run_summary, generate_summary,
+        generate_templated_report, load_run_manifest, run_manifest_path, TemplateFormat,
+    };
     use sumi_ripple::storage::SqliteStorage;
 
     println!("=== Exporting Crawl Summary ===\n");
@@ -197,30 +919,672 @@ fn handle_export_summary(
     println!();
 
     // Open the database
-    let storage = SqliteStorage::new(Path::new(&config.output.database_path))?;
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
 
     // Generate summary from storage
     tracing::info!("Loading crawl data from database...");
-    let summary = generate_summary(&storage)?;
+    let summary = match run_id {
+        Some(run_id) => generate_run_summary(&storage, run_id)?,
+        None => generate_summary(&storage)?,
+    };
+    let manifest = load_run_manifest(&storage, summary.run_id)?;
 
     // Write markdown summary to file
     tracing::info!("Generating markdown summary...");
-    generate_markdown_summary(&summary, Path::new(&config.output.summary_path))?;
+    let output_path = Path::new(&config.output.summary_path);
+    match &config.output.summary_template {
+        Some(template) => generate_templated_report(
+            Path::new(template),
+            &summary,
+            TemplateFormat::Markdown,
+            output_path,
+        )?,
+        None => generate_markdown_summary(&summary, output_path)?,
+    }
 
     println!("✓ Summary exported to: {}", config.output.summary_path);
 
+    let manifest_path = run_manifest_path(output_path, manifest.run_id);
+    generate_run_manifest(&manifest, &manifest_path)?;
+    println!("✓ Run manifest written to: {}", manifest_path.display());
+
+    Ok(())
+}
+
+/// Handles the --export-html mode: generates an interactive HTML report
+fn handle_export_html(
+    config: &sumi_ripple::config::Config,
+    output_path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::{
+        generate_html_report, generate_summary, generate_templated_report, load_html_report_data,
+        TemplateFormat,
+    };
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("=== Exporting HTML Report ===\n");
+    println!("Database: {}", config.output.database_path);
+    println!("Output: {}", output_path.display());
+    println!();
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    // Gather summary data; pages and links are only needed by the built-in layout
+    tracing::info!("Loading crawl data from database...");
+    let summary = generate_summary(&storage)?;
+
+    tracing::info!("Generating HTML report...");
+    match &config.output.html_template {
+        Some(template) => generate_templated_report(
+            Path::new(template),
+            &summary,
+            TemplateFormat::Html,
+            output_path,
+        )?,
+        None => {
+            let data = load_html_report_data(&storage)?;
+            generate_html_report(&summary, &data, output_path)?;
+        }
+    }
+
+    println!("✓ Report exported to: {}", output_path.display());
+
+    Ok(())
+}
+
+/// Handles the --export-hits mode: writes the blacklist/stub hit log as CSV,
+/// optionally limited to a single run
+fn handle_export_hits(
+    config: &sumi_ripple::config::Config,
+    output_path: &std::path::Path,
+    run_id: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::generate_hits_csv;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    println!("=== Exporting Blacklist/Stub Hits ===\n");
+    println!("Database: {}", config.output.database_path);
+    println!("Output: {}", output_path.display());
+    println!();
+
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+    if let Some(run_id) = run_id {
+        // Fail on unknown runs rather than writing an empty file
+        storage.get_run(run_id)?;
+    }
+
+    let hits = storage.get_filter_hits(run_id)?;
+    generate_hits_csv(&hits, output_path)?;
+
+    println!(
+        "✓ {} hits exported to: {}",
+        hits.len(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Handles the --export-parquet mode: writes pages, links and domains as
+/// Parquet files
+#[cfg(feature = "parquet")]
+fn handle_export_parquet(
+    config: &sumi_ripple::config::Config,
+    output_dir: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::export_parquet;
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("=== Exporting Parquet ===\n");
+    println!("Database: {}", config.output.database_path);
+    println!("Output: {}", output_dir.display());
+    println!();
+
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+    let export = export_parquet(&storage, output_dir)?;
+
+    println!("  pages.parquet: {} rows", export.pages);
+    println!("  links.parquet: {} rows", export.links);
+    println!("  domains.parquet: {} rows", export.domains);
+    println!("\n✓ Parquet files exported to: {}", output_dir.display());
+
+    Ok(())
+}
+
+/// Handles the --export-parquet mode in builds without Parquet support
+#[cfg(not(feature = "parquet"))]
+fn handle_export_parquet(
+    _config: &sumi_ripple::config::Config,
+    _output_dir: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err(
+        "--export-parquet requires a build with the parquet feature \
+         (cargo build --release --features parquet)"
+            .into(),
+    )
+}
+
+/// Handles the --export-cypher mode: writes the page graph as Cypher statements
+fn handle_export_cypher(
+    config: &sumi_ripple::config::Config,
+    output_path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::generate_cypher_export;
+    use sumi_ripple::storage::SqliteStorage;
+
+    println!("=== Exporting Cypher ===\n");
+    println!("Database: {}", config.output.database_path);
+    println!("Output: {}", output_path.display());
+    println!();
+
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+    let export = generate_cypher_export(&storage, config, output_path)?;
+
+    println!(
+        "✓ {} pages and {} links exported to: {}",
+        export.pages,
+        export.links,
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Handles the --serve-api mode: serves the database read-only over HTTP
+#[cfg(feature = "api")]
+async fn handle_serve_api(
+    config: &sumi_ripple::config::Config,
+    addr: std::net::SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::SqliteStorage;
+
+    // Fail early on a missing or outdated database
+    let path = Path::new(&config.output.database_path);
+    SqliteStorage::open_read_only(path, &config.database)?;
+
+    println!("Database: {}", config.output.database_path);
+    println!("Serving on http://{} (Ctrl-C to stop)", addr);
+    sumi_ripple::api::serve_api(addr, path.to_path_buf(), config.database.clone()).await?;
+
+    Ok(())
+}
+
+/// Handles the --serve-api mode in builds without the API
+#[cfg(not(feature = "api"))]
+async fn handle_serve_api(
+    _config: &sumi_ripple::config::Config,
+    _addr: std::net::SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--serve-api requires a build with the api feature \
+         (cargo build --release --features api)"
+        .into())
+}
+
+/// Handles the --serve-grpc mode: runs crawls on request over gRPC
+#[cfg(feature = "grpc")]
+async fn handle_serve_grpc(
+    config: &sumi_ripple::config::Config,
+    addr: std::net::SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Database: {}", config.output.database_path);
+    println!("Serving the control API on {} (Ctrl-C to stop)", addr);
+    sumi_ripple::grpc::serve_grpc(addr, config.clone()).await?;
+
+    Ok(())
+}
+
+/// Handles the --serve-grpc mode in builds without the control API
+#[cfg(not(feature = "grpc"))]
+async fn handle_serve_grpc(
+    _config: &sumi_ripple::config::Config,
+    _addr: std::net::SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--serve-grpc requires a build with the grpc feature \
+         (cargo build --release --features grpc)"
+        .into())
+}
+
+/// Maximum number of results shown by --search
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+/// Handles the --search mode: full-text search over page titles and URLs
+fn handle_search(
+    config: &sumi_ripple::config::Config,
+    query: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    println!("=== Search: {} ===\n", query);
+
+    // Open the database
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+
+    let hits = storage.search_pages(query, SEARCH_RESULT_LIMIT)?;
+
+    for hit in &hits {
+        println!("{}", hit.title.as_deref().unwrap_or("(untitled)"));
+        println!("  URL: {}", hit.url);
+        println!("  Domain: {}", hit.domain);
+        if !hit.depths.is_empty() {
+            let depths: Vec<String> = hit
+                .depths
+                .iter()
+                .map(|d| format!("{} @ {}", d.quality_origin, d.depth))
+                .collect();
+            println!("  Depths: {}", depths.join(", "));
+        }
+        println!();
+    }
+
+    if hits.len() == SEARCH_RESULT_LIMIT {
+        println!("✓ Showing first {} matches", hits.len());
+    } else {
+        println!("✓ {} matching pages", hits.len());
+    }
+
+    Ok(())
+}
+
+/// Handles the --check-robots mode: explains the robots.txt decision for a URL
+///
+/// Checks the URL's path the way the crawler does, with the configured user
+/// agent string.
+async fn handle_check_robots(
+    config: &sumi_ripple::config::Config,
+    url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use sumi_ripple::robots::{fetch_robots, is_url_allowed, robots_path};
+    use sumi_ripple::url::extract_domain_with_port;
+
+    let url = url::Url::parse(url)?;
+    let domain = extract_domain_with_port(&url).ok_or("URL has no host")?;
+    let user_agent = format!(
+        "{}/{} (+{}; {})",
+        config.user_agent.crawler_name,
+        config.user_agent.crawler_version,
+        config.user_agent.contact_url,
+        config.user_agent.contact_email
+    );
+
+    println!("=== robots.txt check: {} ===\n", url);
+    println!("User agent: {}", user_agent);
+
+    let robots = match fetch_robots(&domain, &user_agent).await {
+        Ok(robots) => robots,
+        Err(sumi_ripple::SumiError::RobotsUnavailable {
+            status,
+            retry_after,
+            ..
+        }) => {
+            let error = format!("robots.txt unavailable (HTTP {})", status);
+            match retry_after {
+                Some(wait) => println!("Retry-After: {}s", wait.as_secs()),
+                None => println!("Retry-After: none"),
+            }
+            sumi_ripple::robots::ParsedRobots::unavailable(&error)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(reason) = robots.unavailable_reason() {
+        println!(
+            "robots.txt: {} from {} (a crawl retries it, then treats everything as disallowed)",
+            reason, domain
+        );
+    } else if robots.content().is_empty() {
+        println!(
+            "robots.txt: none found for {} (everything is allowed)",
+            domain
+        );
+    } else {
+        println!(
+            "robots.txt: {} lines from {}",
+            robots.content().lines().count(),
+            domain
+        );
+    }
+
+    let allowed = is_url_allowed(&robots, &url, &user_agent);
+    println!("Allowed: {}", if allowed { "yes" } else { "no" });
+    match robots.matching_rule(&robots_path(&url), &user_agent) {
+        Some(rule) => println!("Matched rule: {}", rule),
+        None => println!("Matched rule: none (allowed by default)"),
+    }
+
+    match robots.crawl_delay(&user_agent) {
+        Some(delay) => println!("Crawl-delay: {}s", delay),
+        None => println!("Crawl-delay: none"),
+    }
+    println!(
+        "Per-domain delay: {}ms (+ up to {}ms jitter)",
+        config.crawler.per_domain_delay_ms, config.crawler.per_request_jitter_ms
+    );
+    if config.crawler.honor_visit_time {
+        if let Some(window) = robots.visit_time(&user_agent) {
+            println!(
+                "Visit-time: {}-{} UTC",
+                window.start.format("%H:%M"),
+                window.end.format("%H:%M")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the --explain mode: prints everything known about a URL
+fn handle_explain(
+    config: &sumi_ripple::config::Config,
+    url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::output::explain_url;
+    use sumi_ripple::storage::SqliteStorage;
+
+    let storage =
+        SqliteStorage::open_read_only(Path::new(&config.output.database_path), &config.database)?;
+    let explanation = explain_url(&storage, config, url)?;
+
+    println!("=== Explain: {} ===\n", explanation.url);
+
+    println!("Domain: {}", explanation.domain);
+    match &explanation.matched_pattern {
+        Some(pattern) => println!(
+            "Classification: {} (matches '{}')",
+            explanation.classification.to_db_string(),
+            pattern
+        ),
+        None => println!(
+            "Classification: {} (no domain list matches)",
+            explanation.classification.to_db_string()
+        ),
+    }
+    if let Some(entry) = &explanation.classification_override {
+        println!(
+            "  Database override: {} -> {} (since {})",
+            entry.domain,
+            entry.classification.to_db_string(),
+            entry.created_at
+        );
+    }
+    if let Some(promotion) = &explanation.promotion {
+        println!(
+            "  Promoted in run {} ({} quality referrers, +{} depth)",
+            promotion.run_id, promotion.quality_referrers, promotion.extra_depth
+        );
+    }
+
+    let Some(page) = &explanation.page else {
+        println!("\n✓ URL has not been discovered");
+        return Ok(());
+    };
+
+    println!("\nPage {}:", page.id);
+    println!("  State: {}", page.state.to_db_string());
+    if let Some(status) = page.status_code {
+        println!("  Status code: {}", status);
+    }
+    if let Some(content_type) = &page.content_type {
+        println!("  Content type: {}", content_type);
+    }
+    if let Some(title) = &page.title {
+        println!("  Title: {}", title);
+    }
+    println!(
+        "  Discovered: {} (run {})",
+        page.discovered_at, page.discovered_run
+    );
+    if let Some(visited_at) = &page.visited_at {
+        println!("  Last visited: {}", visited_at);
+    }
+    if let Some(error) = &page.error_message {
+        println!("  Error: {}", error);
+    }
+    println!("  Retries: {}", page.retry_count);
+
+    println!("\nState history:");
+    if explanation.history.is_empty() {
+        println!("  (none recorded)");
+    }
+    for transition in &explanation.history {
+        let run = transition
+            .run_id
+            .map(|run_id| format!("run {}", run_id))
+            .unwrap_or_else(|| "deleted run".to_string());
+        println!(
+            "  {}  {} ({})",
+            transition.changed_at,
+            transition.to_state.to_db_string(),
+            run
+        );
+    }
+
+    println!("\nDepths:");
+    if explanation.depths.is_empty() {
+        println!("  (none recorded)");
+    }
+    for depth in &explanation.depths {
+        println!(
+            "  {} @ {} ({} cross-domain hops)",
+            depth.quality_origin, depth.depth, depth.hops
+        );
+    }
+
+    if !explanation.observations.is_empty() {
+        println!("\nObservations by run:");
+        for observation in &explanation.observations {
+            let status = observation
+                .status_code
+                .map(|code| format!(" {}", code))
+                .unwrap_or_default();
+            println!(
+                "  run {}: {}{}{}{}",
+                observation.run_id,
+                observation.state.to_db_string(),
+                status,
+                observation
+                    .title
+                    .as_deref()
+                    .map(|title| format!(" \"{}\"", title))
+                    .unwrap_or_default(),
+                observation
+                    .final_url
+                    .as_deref()
+                    .map(|url| format!(" -> {}", url))
+                    .unwrap_or_default()
+            );
+        }
+    }
+
+    if !explanation.directives.is_empty() {
+        println!("\nRobots directives:");
+        for directive in &explanation.directives {
+            match &directive.value {
+                Some(value) => println!(
+                    "  {}: {} ({})",
+                    directive.name,
+                    value,
+                    directive.source.as_str()
+                ),
+                None => println!("  {} ({})", directive.name, directive.source.as_str()),
+            }
+        }
+    }
+
+    println!("\nInbound links ({}):", explanation.inbound_total);
+    for link in &explanation.inbound {
+        println!("  {} (run {})", link.url, link.discovered_run);
+    }
+    if explanation.inbound_total > explanation.inbound.len() {
+        println!(
+            "  ... and {} more",
+            explanation.inbound_total - explanation.inbound.len()
+        );
+    }
+
+    match explanation.frontier_priority {
+        Some(priority) => println!("\nFrontier: queued (priority {})", priority),
+        None => println!("\nFrontier: not queued"),
+    }
+
+    Ok(())
+}
+
+/// Handles the --tag-domain mode: attaches tags to domains
+fn handle_tag_domains(
+    config: &sumi_ripple::config::Config,
+    tags: &[(String, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    for (domain, tag) in tags {
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        storage.add_domain_tag(&domain, tag)?;
+        println!("✓ Tagged {} as '{}'", domain, tag);
+    }
+
+    Ok(())
+}
+
+/// Handles the --tag-page mode: attaches tags to crawled pages
+fn handle_tag_pages(
+    config: &sumi_ripple::config::Config,
+    tags: &[(String, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage, StorageError};
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    for (url, tag) in tags {
+        let page = storage
+            .get_page_by_url(url)?
+            .ok_or_else(|| StorageError::PageNotFound(url.clone()))?;
+        storage.add_page_tag(page.id, tag)?;
+        println!("✓ Tagged {} as '{}'", page.url, tag);
+    }
+
+    Ok(())
+}
+
+/// Handles --blacklist-domain / --stub-domain: stores classification overrides
+///
+/// A running crawl picks up the overrides within `CONFIG_CHECK_INTERVAL`.
+fn handle_set_overrides(
+    config: &sumi_ripple::config::Config,
+    domains: &[String],
+    classification: DomainClassification,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    for domain in domains {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        storage.set_classification_override(&domain, classification)?;
+        println!("✓ {} is now {}", domain, classification.to_db_string());
+    }
+
+    Ok(())
+}
+
+/// Handles --clear-domain-override: removes classification overrides
+fn handle_clear_overrides(
+    config: &sumi_ripple::config::Config,
+    domains: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::path::Path;
+    use sumi_ripple::storage::{SqliteStorage, Storage};
+
+    // Open the database
+    let mut storage =
+        SqliteStorage::with_config(Path::new(&config.output.database_path), &config.database)?;
+
+    for domain in domains {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        if storage.remove_classification_override(&domain)? {
+            println!("✓ Removed override for {}", domain);
+        } else {
+            println!("No override found for {}", domain);
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the completion report of a run as a single line of JSON
+fn print_completion_report(
+    database_path: &std::path::Path,
+    database_config: &sumi_ripple::config::DatabaseConfig,
+    run_id: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use sumi_ripple::output::load_completion_report;
+    use sumi_ripple::storage::SqliteStorage;
+
+    let storage = SqliteStorage::open_read_only(database_path, database_config)?;
+    let report = load_completion_report(&storage, run_id)?;
+    println!("{}", serde_json::to_string(&report)?);
+    Ok(())
+}
+
+/// Writes the reproducibility manifest of a run next to the summary
+fn write_manifest(
+    database_path: &std::path::Path,
+    database_config: &sumi_ripple::config::DatabaseConfig,
+    summary_path: &std::path::Path,
+    run_id: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use sumi_ripple::output::{generate_run_manifest, load_run_manifest, run_manifest_path};
+    use sumi_ripple::storage::SqliteStorage;
+
+    let storage = SqliteStorage::open_read_only(database_path, database_config)?;
+    let manifest = load_run_manifest(&storage, run_id)?;
+    let path = run_manifest_path(summary_path, run_id);
+    generate_run_manifest(&manifest, &path)?;
+    tracing::info!("Run manifest written to {}", path.display());
     Ok(())
 }
 
 /// Handles the main crawl operation
 async fn handle_crawl(
     config: sumi_ripple::config::Config,
-    fresh: bool,
+    config_path: Option<&std::path::Path>,
+    overrides: ConfigOverrides,
+    mode: CrawlMode,
+    report_json: bool,
+    replay: Option<&std::path::Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if fresh {
-        tracing::info!("Starting fresh crawl (ignoring previous state)");
-    } else {
-        tracing::info!("Starting crawl (will resume if interrupted run exists)");
+    match mode {
+        CrawlMode::Fresh => tracing::info!("Starting fresh crawl (ignoring previous state)"),
+        CrawlMode::Incremental => tracing::info!(
+            "Starting incremental crawl (refetching pages older than {} days)",
+            config.crawler.recrawl_after_days.unwrap_or_default()
+        ),
+        CrawlMode::Resume => {
+            tracing::info!("Starting crawl (will resume if interrupted run exists)")
+        }
     }
 
     tracing::info!(
@@ -234,10 +1598,67 @@ async fn handle_crawl(
     let seed_count: usize = config.quality.iter().map(|q| q.seeds.len()).sum();
     tracing::info!("Total seed URLs: {}", seed_count);
 
+    let fetcher: Option<Box<dyn Fetcher>> = match replay {
+        Some(path) => {
+            let fetcher = ReplayFetcher::from_warc(path)?;
+            tracing::info!(
+                "Replaying {} archived URLs from {}",
+                fetcher.len(),
+                path.display()
+            );
+            Some(Box::new(fetcher))
+        }
+        None => None,
+    };
+
     // Run the crawler
-    match crawl(config).await {
-        Ok(()) => {
+    let database_path = config.output.database_path.clone();
+    let database_config = config.database.clone();
+    let summary_path = config.output.summary_path.clone();
+    // Ctrl-C stops the crawl cleanly so it can be resumed later
+    let cancel = CancellationToken::new();
+    {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                tracing::info!("Interrupt received, stopping after the current page");
+                cancel.cancel();
+            }
+        });
+    }
+
+    match crawl_with_reload(
+        config,
+        config_path,
+        overrides,
+        mode,
+        cancel.clone(),
+        fetcher,
+    )
+    .await
+    {
+        Ok(_) if cancel.is_cancelled() => {
+            tracing::info!("Crawl interrupted; run again without --fresh to resume");
+            Ok(())
+        }
+        Ok(run_id) => {
             tracing::info!("Crawl completed successfully");
+            // The crawl itself succeeded even if the manifest cannot be written
+            if let Err(e) = write_manifest(
+                std::path::Path::new(&database_path),
+                &database_config,
+                std::path::Path::new(&summary_path),
+                run_id,
+            ) {
+                tracing::warn!("Failed to write the run manifest: {}", e);
+            }
+            if report_json {
+                print_completion_report(
+                    std::path::Path::new(&database_path),
+                    &database_config,
+                    run_id,
+                )?;
+            }
             Ok(())
         }
         Err(e) => {
@@ -0,0 +1,184 @@
+//! Page changes between runs
+//!
+//! Every run keeps what it saw of each page it fetched (its state, status,
+//! title, redirect target and content hash). Comparing the observations of
+//! two runs shows which pages changed in between. Backs the `--diff-pages`
+//! command.
+
+use crate::storage::{PageObservation, Storage};
+use crate::SumiError;
+use std::collections::HashMap;
+
+/// A value of a page that differs between two runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// What changed: "state", "status", "title", "redirect" or "content"
+    pub field: &'static str,
+
+    /// The value in the earlier run
+    pub before: Option<String>,
+
+    /// The value in the later run
+    pub after: Option<String>,
+}
+
+/// A page that changed between two runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageChange {
+    /// URL of the page
+    pub url: String,
+
+    /// The values that differ, in the order of the fields above
+    pub changes: Vec<FieldChange>,
+}
+
+/// Compares what two runs saw of their pages
+///
+/// Only pages observed in both runs are compared.
+///
+/// # Arguments
+///
+/// * `before` - Observations of the earlier run
+/// * `after` - Observations of the later run
+///
+/// # Returns
+///
+/// The pages with at least one difference, in the order of `after`
+pub fn diff_observations(before: &[PageObservation], after: &[PageObservation]) -> Vec<PageChange> {
+    let before: HashMap<i64, &PageObservation> = before
+        .iter()
+        .map(|observation| (observation.page_id, observation))
+        .collect();
+
+    after
+        .iter()
+        .filter_map(|new| {
+            let old = before.get(&new.page_id)?;
+            let fields = [
+                (
+                    "state",
+                    Some(old.state.to_db_string().to_string()),
+                    Some(new.state.to_db_string().to_string()),
+                ),
+                (
+                    "status",
+                    old.status_code.map(|code| code.to_string()),
+                    new.status_code.map(|code| code.to_string()),
+                ),
+                ("title", old.title.clone(), new.title.clone()),
+                ("redirect", old.final_url.clone(), new.final_url.clone()),
+                (
+                    "content",
+                    old.content_hash.clone(),
+                    new.content_hash.clone(),
+                ),
+            ];
+            let changes: Vec<FieldChange> = fields
+                .into_iter()
+                .filter(|(_, before, after)| before != after)
+                .map(|(field, before, after)| FieldChange {
+                    field,
+                    before,
+                    after,
+                })
+                .collect();
+
+            (!changes.is_empty()).then(|| PageChange {
+                url: new.url.clone(),
+                changes,
+            })
+        })
+        .collect()
+}
+
+/// Loads the pages that changed between two runs
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `run_a` - The earlier run
+/// * `run_b` - The later run
+///
+/// # Returns
+///
+/// * `Ok(Vec<PageChange>)` - The changed pages, by URL
+/// * `Err(SumiError)` - A run does not exist or storage could not be queried
+pub fn load_page_changes(
+    storage: &dyn Storage,
+    run_a: i64,
+    run_b: i64,
+) -> Result<Vec<PageChange>, SumiError> {
+    storage.get_run(run_a)?;
+    storage.get_run(run_b)?;
+
+    Ok(diff_observations(
+        &storage.get_run_observations(run_a)?,
+        &storage.get_run_observations(run_b)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PageState;
+    use crate::storage::SqliteStorage;
+
+    #[test]
+    fn test_load_page_changes() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", first)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://a.com/about", "a.com", first)
+            .unwrap();
+        let new = storage
+            .insert_or_get_page("https://a.com/new", "a.com", first)
+            .unwrap();
+        for page in [home, about] {
+            storage
+                .update_page_state(page, PageState::Processed, Some("A"), Some(200), None, None)
+                .unwrap();
+            storage.record_page_observation(page, first, None).unwrap();
+        }
+
+        let second = storage.create_run("hash").unwrap();
+        storage
+            .update_page_state(home, PageState::Processed, Some("B"), Some(200), None, None)
+            .unwrap();
+        storage
+            .record_page_observation(home, second, Some("https://a.com/index"))
+            .unwrap();
+        storage
+            .record_page_observation(about, second, None)
+            .unwrap();
+        // Pages only one run saw are not compared
+        storage
+            .update_page_state(new, PageState::Processed, Some("C"), Some(200), None, None)
+            .unwrap();
+        storage.record_page_observation(new, second, None).unwrap();
+
+        let changes = load_page_changes(&storage, first, second).unwrap();
+        assert_eq!(
+            changes,
+            vec![PageChange {
+                url: "https://a.com/".to_string(),
+                changes: vec![
+                    FieldChange {
+                        field: "title",
+                        before: Some("A".to_string()),
+                        after: Some("B".to_string()),
+                    },
+                    FieldChange {
+                        field: "redirect",
+                        before: None,
+                        after: Some("https://a.com/index".to_string()),
+                    },
+                ],
+            }]
+        );
+
+        assert!(load_page_changes(&storage, first, 99).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Link farm / reciprocal link cluster detection
+//!
+//! This module looks for groups of domains that link to each other far more
+//! densely than ordinary sites do. Two domains are "reciprocal" when each links
+//! to the other. Domains with few reciprocal partners are peeled away, and the
+//! remaining tightly-knit groups are reported if their reciprocal link density
+//! is abnormally high.
+
+use crate::storage::Storage;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Smallest group of domains reported as a cluster
+pub const MIN_CLUSTER_SIZE: usize = 3;
+
+/// Minimum fraction of domain pairs in a cluster that must link reciprocally
+pub const MIN_RECIPROCAL_DENSITY: f64 = 0.5;
+
+/// A group of densely interlinked domains
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuspiciousCluster {
+    /// Domains in the cluster, sorted alphabetically
+    pub domains: Vec<String>,
+
+    /// Number of domain pairs in the cluster that link to each other
+    pub reciprocal_pairs: usize,
+
+    /// Total links between domains of the cluster
+    pub internal_links: u64,
+
+    /// Fraction of possible domain pairs that link reciprocally (0.0 - 1.0)
+    pub density: f64,
+}
+
+/// Detects suspicious link clusters using the data in storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// * `Ok(Vec<SuspiciousCluster>)` - Suspicious clusters, largest first
+/// * `Err(SumiError)` - Failed to query storage
+pub fn detect_link_clusters(storage: &dyn Storage) -> Result<Vec<SuspiciousCluster>, SumiError> {
+    let links = storage.get_domain_links()?;
+    Ok(find_link_clusters(&links))
+}
+
+/// Finds suspicious link clusters in a domain-level link graph
+///
+/// # Arguments
+///
+/// * `links` - (source domain, target domain, link count) edges
+///
+/// # Returns
+///
+/// Clusters of at least `MIN_CLUSTER_SIZE` domains whose reciprocal density is
+/// at least `MIN_RECIPROCAL_DENSITY`, largest first
+pub fn find_link_clusters(links: &[(String, String, u64)]) -> Vec<SuspiciousCluster> {
+    let directed: HashSet<(&str, &str)> = links
+        .iter()
+        .map(|(from, to, _)| (from.as_str(), to.as_str()))
+        .collect();
+
+    // Undirected graph of reciprocal relationships
+    let mut reciprocal: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (from, to) in &directed {
+        if from != to && directed.contains(&(*to, *from)) {
+            reciprocal.entry(*from).or_default().insert(*to);
+            reciprocal.entry(*to).or_default().insert(*from);
+        }
+    }
+
+    peel_to_core(&mut reciprocal, MIN_CLUSTER_SIZE - 1);
+
+    let mut clusters: Vec<SuspiciousCluster> = connected_components(&reciprocal)
+        .into_iter()
+        .filter(|component| component.len() >= MIN_CLUSTER_SIZE)
+        .map(|component| describe_cluster(&component, &reciprocal, links))
+        .filter(|cluster| cluster.density >= MIN_RECIPROCAL_DENSITY)
+        .collect();
+
+    clusters.sort_by(|a, b| {
+        b.domains
+            .len()
+            .cmp(&a.domains.len())
+            .then_with(|| {
+                b.density
+                    .partial_cmp(&a.density)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| a.domains.cmp(&b.domains))
+    });
+
+    clusters
+}
+
+/// Repeatedly removes domains with fewer than `min_degree` reciprocal partners
+fn peel_to_core(graph: &mut BTreeMap<&str, BTreeSet<&str>>, min_degree: usize) {
+    loop {
+        let weak: Vec<&str> = graph
+            .iter()
+            .filter(|(_, neighbours)| neighbours.len() < min_degree)
+            .map(|(domain, _)| *domain)
+            .collect();
+
+        if weak.is_empty() {
+            return;
+        }
+
+        for domain in weak {
+            if let Some(neighbours) = graph.remove(domain) {
+                for neighbour in neighbours {
+                    if let Some(set) = graph.get_mut(neighbour) {
+                        set.remove(domain);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits the reciprocal graph into connected components
+fn connected_components<'a>(graph: &BTreeMap<&'a str, BTreeSet<&'a str>>) -> Vec<Vec<&'a str>> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut components = Vec::new();
+
+    for start in graph.keys() {
+        if !seen.insert(*start) {
+            continue;
+        }
+
+        let mut component = vec![*start];
+        let mut stack = vec![*start];
+        while let Some(domain) = stack.pop() {
+            for neighbour in graph.get(domain).into_iter().flatten() {
+                if seen.insert(*neighbour) {
+                    component.push(*neighbour);
+                    stack.push(*neighbour);
+                }
+            }
+        }
+
+        component.sort_unstable();
+        components.push(component);
+    }
+
+    components
+}
+
+/// Computes the statistics for one cluster
+fn describe_cluster(
+    component: &[&str],
+    reciprocal: &BTreeMap<&str, BTreeSet<&str>>,
+    links: &[(String, String, u64)],
+) -> SuspiciousCluster {
+    let members: HashSet<&str> = component.iter().copied().collect();
+
+    let reciprocal_pairs = component
+        .iter()
+        .map(|domain| reciprocal.get(domain).map_or(0, |n| n.len()))
+        .sum::<usize>()
+        / 2;
+
+    let internal_links = links
+        .iter()
+        .filter(|(from, to, _)| members.contains(from.as_str()) && members.contains(to.as_str()))
+        .map(|(_, _, count)| *count)
+        .sum();
+
+    let n = component.len();
+    let possible_pairs = n * (n - 1) / 2;
+    let density = reciprocal_pairs as f64 / possible_pairs as f64;
+
+    SuspiciousCluster {
+        domains: component.iter().map(|d| d.to_string()).collect(),
+        reciprocal_pairs,
+        internal_links,
+        density,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: &str, to: &str) -> (String, String, u64) {
+        (from.to_string(), to.to_string(), 1)
+    }
+
+    fn both_ways(a: &str, b: &str) -> Vec<(String, String, u64)> {
+        vec![edge(a, b), edge(b, a)]
+    }
+
+    #[test]
+    fn test_detects_fully_reciprocal_cluster() {
+        let mut links = Vec::new();
+        links.extend(both_ways("a.com", "b.com"));
+        links.extend(both_ways("b.com", "c.com"));
+        links.extend(both_ways("a.com", "c.com"));
+        // One-way links are not part of the cluster
+        links.push(edge("a.com", "news.org"));
+        links.push(edge("blog.net", "a.com"));
+
+        let clusters = find_link_clusters(&links);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].domains, vec!["a.com", "b.com", "c.com"]);
+        assert_eq!(clusters[0].reciprocal_pairs, 3);
+        assert_eq!(clusters[0].internal_links, 6);
+        assert!((clusters[0].density - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_ignores_single_reciprocal_pair() {
+        let links = both_ways("a.com", "b.com");
+        assert!(find_link_clusters(&links).is_empty());
+    }
+
+    #[test]
+    fn test_ignores_sparse_reciprocal_ring() {
+        // A ring survives peeling but is sparse: 7 reciprocal pairs out of 21
+        let domains = ["a", "b", "c", "d", "e", "f", "g"];
+        let mut links = Vec::new();
+        for i in 0..domains.len() {
+            links.extend(both_ways(domains[i], domains[(i + 1) % domains.len()]));
+        }
+
+        assert!(find_link_clusters(&links).is_empty());
+    }
+
+    #[test]
+    fn test_peels_loosely_attached_domains() {
+        let mut links = Vec::new();
+        links.extend(both_ways("a.com", "b.com"));
+        links.extend(both_ways("b.com", "c.com"));
+        links.extend(both_ways("a.com", "c.com"));
+        // d.com only has one reciprocal partner and is peeled away
+        links.extend(both_ways("c.com", "d.com"));
+
+        let clusters = find_link_clusters(&links);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].domains, vec!["a.com", "b.com", "c.com"]);
+    }
+
+    #[test]
+    fn test_no_links() {
+        assert!(find_link_clusters(&[]).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Machine-readable completion report
+//!
+//! With `--report-json` the crawler prints one JSON object describing the run
+//! to stdout when the crawl finishes, so scripts and orchestration systems can
+//! capture the result without opening the database.
+
+use crate::output::run_duration_seconds;
+use crate::storage::Storage;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Outcome of one crawl run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompletionReport {
+    /// The run the report describes
+    pub run_id: i64,
+
+    /// Final run status ("completed", "interrupted", ...)
+    pub status: String,
+
+    /// When the run started (RFC 3339)
+    pub started_at: String,
+
+    /// When the run finished (RFC 3339)
+    pub finished_at: Option<String>,
+
+    /// How long the run took
+    pub duration_seconds: Option<u64>,
+
+    /// Pages first discovered during the run
+    pub pages_discovered: u64,
+
+    /// Pages fetched during the run
+    pub pages_visited: u64,
+
+    /// Links first discovered during the run
+    pub links_discovered: u64,
+
+    /// Domains of the pages discovered or fetched during the run
+    pub domains: u64,
+
+    /// Pages of the run in an error state
+    pub errors: u64,
+
+    /// Pages of the run by current state
+    pub pages_by_state: BTreeMap<String, u64>,
+}
+
+/// Loads the completion report of a run
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `run_id` - The run to report on
+///
+/// # Returns
+///
+/// * `Ok(CompletionReport)` - The report
+/// * `Err(SumiError)` - The run does not exist or storage could not be queried
+pub fn load_completion_report(
+    storage: &dyn Storage,
+    run_id: i64,
+) -> Result<CompletionReport, SumiError> {
+    let run = storage.get_run(run_id)?;
+    let state_counts = storage.get_run_state_counts(run_id)?;
+
+    Ok(CompletionReport {
+        run_id,
+        status: run.status.to_db_string().to_string(),
+        duration_seconds: run_duration_seconds(&run),
+        started_at: run.started_at,
+        finished_at: run.finished_at,
+        pages_discovered: storage.count_pages_discovered_in_run(run_id)?,
+        pages_visited: storage.count_pages_visited_in_run(run_id)?,
+        links_discovered: storage.count_links_discovered_in_run(run_id)?,
+        domains: storage.get_run_domains(run_id)?.len() as u64,
+        errors: state_counts
+            .iter()
+            .filter(|(state, _)| state.is_error())
+            .map(|(_, count)| count)
+            .sum(),
+        pages_by_state: state_counts
+            .iter()
+            .map(|(state, count)| (state.to_db_string().to_string(), *count))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PageState;
+    use crate::storage::SqliteStorage;
+
+    #[test]
+    fn test_completion_report() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let gone = storage
+            .insert_or_get_page("https://b.com/gone", "b.com", run_id)
+            .unwrap();
+        storage.insert_link(home, gone, run_id).unwrap();
+        storage.mark_page_visited(home, run_id).unwrap();
+        storage
+            .update_page_state(home, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+        storage
+            .update_page_state(gone, PageState::DeadLink, None, Some(404), None, None)
+            .unwrap();
+        storage.complete_run(run_id).unwrap();
+
+        let report = load_completion_report(&storage, run_id).unwrap();
+        assert_eq!(report.status, "completed");
+        assert!(report.finished_at.is_some());
+        assert_eq!(report.pages_discovered, 2);
+        assert_eq!(report.pages_visited, 1);
+        assert_eq!(report.links_discovered, 1);
+        assert_eq!(report.domains, 2);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.pages_by_state.get("processed"), Some(&1));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["run_id"], run_id);
+        assert_eq!(json["pages_by_state"]["dead_link"], 1);
+    }
+
+    #[test]
+    fn test_completion_report_unknown_run() {
+        let storage = SqliteStorage::new_in_memory().unwrap();
+        assert!(load_completion_report(&storage, 7).is_err());
+    }
+}
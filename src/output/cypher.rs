@@ -0,0 +1,290 @@
+//! Cypher export of the page graph
+//!
+//! Writes pages and the links between them as a Cypher script that can be
+//! piped into `cypher-shell`, so the crawled terrain can be explored in Neo4j
+//! or another graph database speaking Cypher. Every page becomes a `:Page`
+//! node with two further labels, its domain's classification (`:Quality`,
+//! `:Blacklisted`, `:Stubbed`, `:Discovered`) and its state (`:Processed`,
+//! `:DeadLink`, ...). Links become `:LINKS_TO` relationships; as in the link
+//! graph, those of pages flagged as copies of an error template are left out.
+
+use crate::config::Config;
+use crate::crawler::apply_classification_overrides;
+use crate::state::PageState;
+use crate::storage::{LinkRecord, PageRecord, Storage};
+use crate::url::{classify_domain, DomainClassification};
+use crate::SumiError;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Rows created per `UNWIND` statement
+const CYPHER_BATCH_SIZE: usize = 1000;
+
+/// Nodes and relationships written by a Cypher export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CypherExport {
+    /// `:Page` nodes
+    pub pages: u64,
+    /// `:LINKS_TO` relationships
+    pub links: u64,
+}
+
+/// Exports the page graph as a Cypher script
+///
+/// Domains are classified as a crawl would, with the database overrides
+/// applied.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `config` - The crawler configuration, for classifying domains
+/// * `output_path` - Path where the script should be written
+///
+/// # Returns
+///
+/// * `Ok(CypherExport)` - What the script creates
+/// * `Err(SumiError)` - The database could not be read or the file written
+pub fn generate_cypher_export(
+    storage: &dyn Storage,
+    config: &Config,
+    output_path: &Path,
+) -> Result<CypherExport, SumiError> {
+    let mut pages = Vec::new();
+    for state in PageState::all_states() {
+        pages.extend(storage.get_pages_by_state(state)?);
+    }
+    pages.sort_by_key(|page| page.id);
+    let links = storage.get_graph_links()?;
+
+    let effective =
+        apply_classification_overrides(config, &storage.get_classification_overrides()?);
+    let mut classifications = HashMap::new();
+    for page in &pages {
+        classifications
+            .entry(page.domain.clone())
+            .or_insert_with(|| classify_domain(&page.domain, &effective));
+    }
+
+    std::fs::write(output_path, format_cypher(&pages, &classifications, &links))?;
+
+    Ok(CypherExport {
+        pages: pages.len() as u64,
+        links: links.len() as u64,
+    })
+}
+
+/// Formats pages and links as Cypher statements
+///
+/// The script creates a uniqueness constraint on `Page.id`, then the nodes
+/// and relationships in `UNWIND` batches of `CYPHER_BATCH_SIZE` rows. Pages
+/// of domains missing from `classifications` are labelled `:Discovered`.
+///
+/// # Arguments
+///
+/// * `pages` - The pages to create
+/// * `classifications` - Classification of each page domain
+/// * `links` - Links between the pages
+///
+/// # Returns
+///
+/// The script, one statement per `;`-terminated paragraph
+pub fn format_cypher(
+    pages: &[PageRecord],
+    classifications: &HashMap<String, DomainClassification>,
+    links: &[LinkRecord],
+) -> String {
+    let mut script = String::from(
+        "// Page graph exported by sumi-ripple\n\
+         CREATE CONSTRAINT page_id IF NOT EXISTS FOR (p:Page) REQUIRE p.id IS UNIQUE;\n",
+    );
+
+    // Labels cannot be parameters, so pages are created per label pair
+    let mut groups: BTreeMap<(String, String), Vec<&PageRecord>> = BTreeMap::new();
+    for page in pages {
+        let classification = classifications
+            .get(&page.domain)
+            .copied()
+            .unwrap_or(DomainClassification::Discovered);
+        groups
+            .entry((
+                label(classification.to_db_string()),
+                label(page.state.to_db_string()),
+            ))
+            .or_default()
+            .push(page);
+    }
+
+    for ((classification, state), pages) in &groups {
+        for batch in pages.chunks(CYPHER_BATCH_SIZE) {
+            let rows: Vec<String> = batch
+                .iter()
+                .map(|page| page_row(page, &classification.to_lowercase()))
+                .collect();
+            script.push_str(&format!(
+                "\nUNWIND [\n  {}\n] AS row\nCREATE (p:Page:{}:{}) SET p = row;\n",
+                rows.join(",\n  "),
+                classification,
+                state
+            ));
+        }
+    }
+
+    for batch in links.chunks(CYPHER_BATCH_SIZE) {
+        let rows: Vec<String> = batch
+            .iter()
+            .map(|link| {
+                format!(
+                    "{{from: {}, to: {}, discovered_run: {}, last_seen_run: {}}}",
+                    link.from_page_id, link.to_page_id, link.discovered_run, link.last_seen_run
+                )
+            })
+            .collect();
+        script.push_str(&format!(
+            "\nUNWIND [\n  {}\n] AS row\n\
+             MATCH (a:Page {{id: row.from}}), (b:Page {{id: row.to}})\n\
+             CREATE (a)-[:LINKS_TO {{discovered_run: row.discovered_run, last_seen_run: row.last_seen_run}}]->(b);\n",
+            rows.join(",\n  ")
+        ));
+    }
+
+    script
+}
+
+/// Formats the properties of a page node as a Cypher map
+fn page_row(page: &PageRecord, classification: &str) -> String {
+    format!(
+        "{{id: {}, url: {}, domain: {}, classification: {}, state: {}, \
+         title: {}, status_code: {}, discovered_run: {}}}",
+        page.id,
+        string(&page.url),
+        string(&page.domain),
+        string(classification),
+        string(page.state.to_db_string()),
+        page.title.as_deref().map_or("null".to_string(), string),
+        page.status_code
+            .map_or("null".to_string(), |code| code.to_string()),
+        page.discovered_run
+    )
+}
+
+/// Turns a database string such as "dead_link" into a label ("DeadLink")
+fn label(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Quotes a string as a Cypher literal
+///
+/// JSON string escapes (`\"`, `\\`, `\n`, `\uXXXX`) are valid in Cypher.
+fn string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use crate::storage::SqliteStorage;
+
+    fn page(id: i64, url: &str, domain: &str, state: PageState) -> PageRecord {
+        PageRecord {
+            id,
+            url: url.to_string(),
+            domain: domain.to_string(),
+            state,
+            title: None,
+            status_code: None,
+            content_type: None,
+            last_modified: None,
+            visited_at: None,
+            discovered_at: "2024-01-01T00:00:00+00:00".to_string(),
+            discovered_run: 1,
+            error_message: None,
+            retry_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_format_cypher() {
+        let mut home = page(1, "https://a.com/", "a.com", PageState::Processed);
+        home.title = Some("Say \"hi\"\n".to_string());
+        home.status_code = Some(200);
+        let pages = vec![
+            home,
+            page(2, "https://spam.net/", "spam.net", PageState::Blacklisted),
+            page(3, "https://a.com/gone", "a.com", PageState::DeadLink),
+        ];
+        let classifications = HashMap::from([
+            ("a.com".to_string(), DomainClassification::Quality),
+            ("spam.net".to_string(), DomainClassification::Blacklisted),
+        ]);
+        let links = vec![LinkRecord {
+            from_page_id: 1,
+            to_page_id: 2,
+            discovered_run: 1,
+            last_seen_run: 2,
+        }];
+
+        let script = format_cypher(&pages, &classifications, &links);
+        assert!(script.contains("REQUIRE p.id IS UNIQUE;"));
+        assert!(script.contains("CREATE (p:Page:Quality:Processed) SET p = row;"));
+        assert!(script.contains("CREATE (p:Page:Quality:DeadLink) SET p = row;"));
+        assert!(script.contains("CREATE (p:Page:Blacklisted:Blacklisted) SET p = row;"));
+        assert!(script.contains(
+            "{id: 1, url: \"https://a.com/\", domain: \"a.com\", classification: \"quality\", \
+             state: \"processed\", title: \"Say \\\"hi\\\"\\n\", status_code: 200, discovered_run: 1}"
+        ));
+        assert!(script.contains("title: null, status_code: null"));
+        assert!(script.contains("{from: 1, to: 2, discovered_run: 1, last_seen_run: 2}"));
+        assert!(script.contains("-[:LINKS_TO"));
+        // Every statement is terminated
+        assert_eq!(script.matches(';').count(), 5);
+    }
+
+    #[test]
+    fn test_error_template_links_are_left_out() {
+        let config: Config = toml::from_str(&test_config_toml(
+            "\n[[quality]]\ndomain = \"a.com\"\nseeds = [\"https://a.com/\"]\n",
+        ))
+        .unwrap();
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let target = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+        let mut missing = Vec::new();
+        for n in 1..=3 {
+            let page = storage
+                .insert_or_get_page(&format!("https://a.com/gone{}", n), "a.com", run_id)
+                .unwrap();
+            storage.insert_link(page, target, run_id).unwrap();
+            storage.set_template_hash(page, "404").unwrap();
+            missing.push(page);
+        }
+        assert!(storage.flag_error_template("a.com", "404", 3).unwrap());
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("graph.cypher");
+        let export = generate_cypher_export(&storage, &config, &output_path).unwrap();
+        // The first page of the template keeps its link, the copies do not
+        assert_eq!(export, CypherExport { pages: 4, links: 1 });
+        let script = std::fs::read_to_string(&output_path).unwrap();
+        assert!(script.contains(&format!("{{from: {}, to: {}", missing[0], target)));
+        assert!(!script.contains(&format!("{{from: {}, to: {}", missing[1], target)));
+        assert!(!script.contains(&format!("{{from: {}, to: {}", missing[2], target)));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label("dead_link"), "DeadLink");
+        assert_eq!(label("policy_denied"), "PolicyDenied");
+        assert_eq!(label("quality"), "Quality");
+    }
+}
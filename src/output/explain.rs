@@ -0,0 +1,240 @@
+//! Explaining a single URL
+//!
+//! Gathers everything the database and configuration say about one URL: how
+//! its domain is classified and why, its stored state and the states it went
+//! through, the depths it was
+//! reached at, what each run saw of it, the robots directives it stated, the
+//! pages linking to it and whether it is waiting in the frontier. Backs the `--explain` command.
+
+use crate::config::Config;
+use crate::crawler::apply_classification_overrides;
+use crate::storage::{
+    ClassificationOverride, DepthRecord, DomainPromotion, PageDirective, PageObservation,
+    PageRecord, StateTransition, Storage,
+};
+use crate::url::{
+    classify_domain_with_pattern, extract_domain, matches_wildcard, normalize_url,
+    DomainClassification,
+};
+use crate::SumiError;
+
+/// Most inbound links listed by an explanation
+pub const EXPLAIN_INBOUND_LINKS: usize = 20;
+
+/// A page linking to the explained URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundLink {
+    /// URL of the linking page
+    pub url: String,
+
+    /// The run the link was first discovered in
+    pub discovered_run: i64,
+}
+
+/// Everything known about a URL
+#[derive(Debug, Clone)]
+pub struct UrlExplanation {
+    /// The URL as stored (normalized)
+    pub url: String,
+
+    /// The URL's domain
+    pub domain: String,
+
+    /// How the domain is classified, overrides included
+    pub classification: DomainClassification,
+
+    /// The domain list pattern that decided the classification
+    pub matched_pattern: Option<String>,
+
+    /// The database override matching the domain, if any
+    pub classification_override: Option<ClassificationOverride>,
+
+    /// The domain's promotion to a deeper crawl, if it was promoted
+    pub promotion: Option<DomainPromotion>,
+
+    /// The stored page, if the URL was ever discovered
+    pub page: Option<PageRecord>,
+
+    /// The states the page went through, oldest first
+    pub history: Vec<StateTransition>,
+
+    /// Depths the page was reached at, per quality origin
+    pub depths: Vec<DepthRecord>,
+
+    /// What each run saw of the page, oldest run first
+    pub observations: Vec<PageObservation>,
+
+    /// Robots directives the page stated on its last fetch
+    pub directives: Vec<PageDirective>,
+
+    /// Number of pages linking to the URL
+    pub inbound_total: usize,
+
+    /// Up to `EXPLAIN_INBOUND_LINKS` of the pages linking to the URL
+    pub inbound: Vec<InboundLink>,
+
+    /// Priority in the frontier, if the page is queued
+    pub frontier_priority: Option<u32>,
+}
+
+/// Explains what the crawler knows and decided about a URL
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `config` - The crawler configuration
+/// * `url` - The URL to explain; normalized as the crawler would
+///
+/// # Returns
+///
+/// * `Ok(UrlExplanation)` - The explanation; `page` is `None` if the URL was
+///   never discovered
+/// * `Err(SumiError)` - The URL is invalid or the database could not be read
+pub fn explain_url(
+    storage: &dyn Storage,
+    config: &Config,
+    url: &str,
+) -> Result<UrlExplanation, SumiError> {
+    let normalized = normalize_url(url)?;
+    let domain = extract_domain(&normalized).unwrap_or_default();
+
+    // Classify as a crawl would, with the database overrides applied
+    let overrides = storage.get_classification_overrides()?;
+    let effective = apply_classification_overrides(config, &overrides);
+    let (classification, matched_pattern) = classify_domain_with_pattern(&domain, &effective);
+    let classification_override = overrides
+        .into_iter()
+        .find(|entry| matches_wildcard(&entry.domain, &domain));
+    let promotion = storage
+        .get_domain_promotions()?
+        .into_iter()
+        .find(|promotion| promotion.domain == domain);
+
+    // Pages stored before normalization changed may be under the raw URL
+    let page = match storage.get_page_by_url(normalized.as_str())? {
+        Some(page) => Some(page),
+        None => storage.get_page_by_url(url)?,
+    };
+
+    let mut explanation = UrlExplanation {
+        url: page
+            .as_ref()
+            .map(|page| page.url.clone())
+            .unwrap_or_else(|| normalized.to_string()),
+        domain,
+        classification,
+        matched_pattern: matched_pattern.map(str::to_string),
+        classification_override,
+        promotion,
+        page: None,
+        history: vec![],
+        depths: vec![],
+        observations: vec![],
+        directives: vec![],
+        inbound_total: 0,
+        inbound: vec![],
+        frontier_priority: None,
+    };
+
+    let Some(page) = page else {
+        return Ok(explanation);
+    };
+
+    explanation.history = storage.get_state_history(page.id)?;
+    explanation.depths = storage.get_depths(page.id)?;
+    explanation.observations = storage.get_page_observations(page.id)?;
+    explanation.directives = storage.get_page_directives(page.id)?;
+
+    let incoming = storage.get_incoming_links(page.id)?;
+    explanation.inbound_total = incoming.len();
+    for link in incoming.iter().take(EXPLAIN_INBOUND_LINKS) {
+        explanation.inbound.push(InboundLink {
+            url: storage.get_page(link.from_page_id)?.url,
+            discovered_run: link.discovered_run,
+        });
+    }
+
+    explanation.frontier_priority = storage.get_frontier_priority(page.id)?;
+    explanation.page = Some(page);
+
+    Ok(explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use crate::state::PageState;
+    use crate::storage::SqliteStorage;
+
+    const QUALITY: &str = r#"
+[[quality]]
+domain = "*.example.com"
+seeds = ["https://blog.example.com/"]
+"#;
+
+    #[test]
+    fn test_explain_url() {
+        let config: Config = toml::from_str(&test_config_toml(QUALITY)).unwrap();
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://blog.example.com/", "blog.example.com", run_id)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://blog.example.com/about", "blog.example.com", run_id)
+            .unwrap();
+        storage.insert_link(home, about, run_id).unwrap();
+        storage.upsert_depth(about, "blog.example.com", 1).unwrap();
+        storage
+            .update_page_state(
+                about,
+                PageState::Failed,
+                None,
+                Some(500),
+                None,
+                Some("HTTP 500"),
+            )
+            .unwrap();
+        storage.add_to_frontier(about, 7).unwrap();
+
+        let explanation = explain_url(&storage, &config, "https://BLOG.example.com/about").unwrap();
+        assert_eq!(explanation.url, "https://blog.example.com/about");
+        assert_eq!(explanation.classification, DomainClassification::Quality);
+        assert_eq!(
+            explanation.matched_pattern.as_deref(),
+            Some("*.example.com")
+        );
+        assert!(explanation.classification_override.is_none());
+        let page = explanation.page.unwrap();
+        assert_eq!(page.state, PageState::Failed);
+        assert_eq!(page.error_message.as_deref(), Some("HTTP 500"));
+        let states: Vec<_> = explanation.history.iter().map(|t| t.to_state).collect();
+        assert_eq!(states, vec![PageState::Discovered, PageState::Failed]);
+        assert_eq!(explanation.depths.len(), 1);
+        assert_eq!(explanation.depths[0].depth, 1);
+        assert!(explanation.observations.is_empty());
+        assert!(explanation.directives.is_empty());
+        assert_eq!(
+            explanation.inbound,
+            vec![InboundLink {
+                url: "https://blog.example.com/".to_string(),
+                discovered_run: run_id,
+            }]
+        );
+        assert_eq!(explanation.frontier_priority, Some(7));
+
+        // Database overrides take part in the classification
+        storage
+            .set_classification_override("spam.net", DomainClassification::Blacklisted)
+            .unwrap();
+        let explanation = explain_url(&storage, &config, "https://spam.net/x").unwrap();
+        assert_eq!(
+            explanation.classification,
+            DomainClassification::Blacklisted
+        );
+        assert_eq!(explanation.matched_pattern.as_deref(), Some("spam.net"));
+        assert!(explanation.classification_override.is_some());
+        assert!(explanation.page.is_none());
+    }
+}
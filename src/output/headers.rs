@@ -0,0 +1,106 @@
+//! Response header summary
+//!
+//! The crawler records a few response headers of every fetched page
+//! (`Server`, `X-Powered-By`, `Content-Language`, `Cache-Control`). Counting
+//! the domains behind each value shows what the crawled sites run on, e.g.
+//! that 40% of them are served by nginx.
+
+use crate::storage::{HeaderValueCount, Storage};
+use crate::SumiError;
+use serde::Serialize;
+
+/// Number of values listed per header
+pub const TOP_HEADER_VALUES: usize = 10;
+
+/// Domains sending one value of a response header
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeaderShare {
+    /// Header name (lowercase)
+    pub header: String,
+
+    /// The header value
+    pub value: String,
+
+    /// Domains with at least one page served with the value
+    pub domains: u64,
+
+    /// Fraction of the domains with processed pages (0.0 to 1.0)
+    pub share: f64,
+}
+
+/// Loads the most common values of the recorded response headers
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// * `Ok(Vec<HeaderShare>)` - Up to `TOP_HEADER_VALUES` values per header
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_header_summary(storage: &dyn Storage) -> Result<Vec<HeaderShare>, SumiError> {
+    let counts = storage.get_header_value_counts(TOP_HEADER_VALUES)?;
+    Ok(build_header_summary(
+        &counts,
+        count_crawled_domains(storage)?,
+    ))
+}
+
+/// Counts the domains with at least one processed page
+pub(crate) fn count_crawled_domains(storage: &dyn Storage) -> Result<u64, SumiError> {
+    Ok(storage
+        .get_domain_summaries()?
+        .iter()
+        .filter(|summary| summary.pages_processed > 0)
+        .count() as u64)
+}
+
+/// Turns header value counts into shares of the crawled domains
+///
+/// # Arguments
+///
+/// * `counts` - Domains per header value
+/// * `crawled_domains` - Domains with at least one processed page
+pub fn build_header_summary(counts: &[HeaderValueCount], crawled_domains: u64) -> Vec<HeaderShare> {
+    counts
+        .iter()
+        .map(|count| HeaderShare {
+            header: count.name.clone(),
+            value: count.value.clone(),
+            domains: count.domains,
+            share: if crawled_domains == 0 {
+                0.0
+            } else {
+                count.domains as f64 / crawled_domains as f64
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_header_summary() {
+        let counts = vec![
+            HeaderValueCount {
+                name: "server".to_string(),
+                value: "nginx".to_string(),
+                domains: 2,
+            },
+            HeaderValueCount {
+                name: "x-powered-by".to_string(),
+                value: "PHP/8.2".to_string(),
+                domains: 1,
+            },
+        ];
+
+        let summary = build_header_summary(&counts, 5);
+        assert_eq!(summary[0].header, "server");
+        assert_eq!(summary[0].share, 0.4);
+        assert_eq!(summary[1].share, 0.2);
+
+        assert_eq!(build_header_summary(&counts, 0)[0].share, 0.0);
+    }
+}
@@ -0,0 +1,131 @@
+//! CSV export of the blacklist/stub hit log
+//!
+//! Every time the crawler finds a link to a blacklisted or stubbed domain it
+//! records the URL, the page linking to it and the run. Exporting these
+//! encounters as CSV allows reviewing in a spreadsheet what is being filtered
+//! and which pages keep linking to it.
+
+use crate::output::traits::OutputResult;
+use crate::storage::FilterHit;
+use crate::url::extract_domain;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use url::Url;
+
+/// Header row of the exported CSV
+const CSV_HEADER: &str = "kind,url,domain,referrer,run_id,discovered_at";
+
+/// Writes the hit log as a CSV file
+///
+/// # Arguments
+///
+/// * `hits` - The blacklisted and stubbed URL encounters
+/// * `output_path` - Path where the CSV file should be written
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully wrote the file
+/// * `Err(OutputError)` - Failed to write the file
+pub fn generate_hits_csv(hits: &[FilterHit], output_path: &Path) -> OutputResult<()> {
+    let csv = format_hits_csv(hits);
+
+    let mut file = File::create(output_path)?;
+    file.write_all(csv.as_bytes())?;
+
+    Ok(())
+}
+
+/// Formats the hit log as CSV (RFC 4180, with a header row)
+///
+/// # Arguments
+///
+/// * `hits` - The blacklisted and stubbed URL encounters
+///
+/// # Returns
+///
+/// The CSV text, one line per encounter
+pub fn format_hits_csv(hits: &[FilterHit]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push_str("\r\n");
+
+    for hit in hits {
+        let domain = Url::parse(&hit.url)
+            .ok()
+            .and_then(|url| extract_domain(&url))
+            .unwrap_or_default();
+        let fields = [
+            hit.kind.as_str(),
+            &hit.url,
+            &domain,
+            &hit.referrer,
+            &hit.run_id.to_string(),
+            &hit.discovered_at,
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilterKind;
+
+    fn hit(kind: FilterKind, url: &str, referrer: &str) -> FilterHit {
+        FilterHit {
+            kind,
+            url: url.to_string(),
+            referrer: referrer.to_string(),
+            run_id: 2,
+            discovered_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_hits_csv() {
+        let csv = format_hits_csv(&[
+            hit(
+                FilterKind::Blacklisted,
+                "https://Ads.Example.net/x",
+                "https://a.com/",
+            ),
+            hit(
+                FilterKind::Stubbed,
+                "https://social.net/p",
+                "https://a.com/b",
+            ),
+        ]);
+
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "blacklisted,https://Ads.Example.net/x,ads.example.net,https://a.com/,2,2024-01-01T00:00:00+00:00"
+        );
+        assert!(lines[2].starts_with("stubbed,https://social.net/p,social.net,"));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(
+            csv_field("https://a.com/?a=1,2"),
+            "\"https://a.com/?a=1,2\""
+        );
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
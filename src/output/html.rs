@@ -0,0 +1,682 @@
+//! Interactive HTML report generation
+//!
+//! This module generates a single self-contained HTML file with summary
+//! statistics, sortable domain and page tables, and a force-directed graph of
+//! the domain-level link graph. All data is inlined as JSON and the page has no
+//! external dependencies, so the report can be opened offline or shared as-is.
+
+use crate::output::progress::{crawl_velocity, VelocityPoint};
+use crate::output::traits::{CrawlSummary, OutputError, OutputResult};
+use crate::state::PageState;
+use crate::storage::{PageRecord, Storage};
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Maximum number of rows rendered in the page table
+pub const MAX_PAGE_ROWS: usize = 5000;
+
+/// Maximum number of domains drawn in the link graph
+pub const MAX_GRAPH_NODES: usize = 300;
+
+/// Size of the crawl velocity chart (pixels)
+const VELOCITY_CHART_WIDTH: f64 = 1100.0;
+const VELOCITY_CHART_HEIGHT: f64 = 240.0;
+
+/// Crawl data needed for the HTML report beyond the summary
+#[derive(Debug, Clone, Default)]
+pub struct HtmlReportData {
+    /// All known pages
+    pub pages: Vec<PageRecord>,
+
+    /// Domain-level link graph as (source, target, link count)
+    pub domain_links: Vec<(String, String, u64)>,
+}
+
+/// Per-domain row of the domain table
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DomainRow {
+    domain: String,
+    pages: u64,
+    processed: u64,
+    errors: u64,
+    outbound_domains: u64,
+    inbound_domains: u64,
+}
+
+/// Node of the embedded link graph
+#[derive(Debug, Serialize)]
+struct GraphNode<'a> {
+    id: &'a str,
+    pages: u64,
+}
+
+/// Edge of the embedded link graph
+#[derive(Debug, Serialize)]
+struct GraphLink<'a> {
+    source: &'a str,
+    target: &'a str,
+    count: u64,
+}
+
+/// Graph data inlined into the report
+#[derive(Debug, Serialize)]
+struct GraphData<'a> {
+    nodes: Vec<GraphNode<'a>>,
+    links: Vec<GraphLink<'a>>,
+}
+
+/// Loads the data needed for the HTML report from storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// * `Ok(HtmlReportData)` - Successfully loaded report data
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_html_report_data(storage: &dyn Storage) -> Result<HtmlReportData, SumiError> {
+    let mut pages = Vec::new();
+    for state in PageState::all_states() {
+        pages.extend(storage.get_pages_by_state(state)?);
+    }
+    pages.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let domain_links = storage.get_domain_links()?;
+
+    Ok(HtmlReportData {
+        pages,
+        domain_links,
+    })
+}
+
+/// Generates an interactive HTML report
+///
+/// # Arguments
+///
+/// * `summary` - The crawl summary data
+/// * `data` - Page and link data for the tables and graph
+/// * `output_path` - Path where the HTML file should be written
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully wrote the report
+/// * `Err(OutputError)` - Failed to write the report
+pub fn generate_html_report(
+    summary: &CrawlSummary,
+    data: &HtmlReportData,
+    output_path: &Path,
+) -> OutputResult<()> {
+    let html = format_html_report(summary, data)?;
+
+    let mut file = File::create(output_path)?;
+    file.write_all(html.as_bytes())?;
+
+    Ok(())
+}
+
+/// Formats the HTML report
+///
+/// # Arguments
+///
+/// * `summary` - The crawl summary data
+/// * `data` - Page and link data for the tables and graph
+///
+/// # Returns
+///
+/// * `Ok(String)` - The complete HTML document
+/// * `Err(OutputError)` - Failed to serialize the graph data
+pub fn format_html_report(summary: &CrawlSummary, data: &HtmlReportData) -> OutputResult<String> {
+    let domains = build_domain_rows(data);
+    let graph_json = script_safe_json(&build_graph(&domains, &data.domain_links))?;
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Sumi-Ripple Crawl Report (run {})</title>\n",
+        summary.run_id
+    ));
+    html.push_str(STYLE);
+    html.push_str("</head>\n<body>\n");
+    html.push_str("<h1>Sumi-Ripple Crawl Report</h1>\n");
+
+    // Summary statistics
+    html.push_str("<section>\n<h2>Summary</h2>\n<dl class=\"stats\">\n");
+    let mut stat = |label: &str, value: String| {
+        html.push_str(&format!(
+            "<div><dt>{}</dt><dd>{}</dd></div>\n",
+            escape_html(label),
+            escape_html(&value)
+        ));
+    };
+    stat("Run ID", summary.run_id.to_string());
+    stat("Status", summary.status.clone());
+    stat("Started", summary.started_at.clone());
+    if let Some(finished) = &summary.finished_at {
+        stat("Finished", finished.clone());
+    }
+    stat("Total Pages", summary.total_pages.to_string());
+    stat("Processed", summary.pages_processed.to_string());
+    stat("Unique Domains", summary.unique_domains.to_string());
+    stat("Total Links", summary.total_links.to_string());
+    stat("Total Errors", summary.total_errors.to_string());
+    stat("Success Rate", format!("{:.2}%", summary.success_rate()));
+    html.push_str("</dl>\n</section>\n");
+
+    // Crawl velocity
+    let velocity = crawl_velocity(&summary.progress);
+    if velocity.len() > 1 {
+        html.push_str("<section>\n<h2>Crawl Velocity</h2>\n");
+        html.push_str(
+            "<p class=\"note\">Pages processed per minute between progress snapshots. Red markers are stalls: intervals with URLs queued but nothing processed.</p>\n",
+        );
+        html.push_str(&velocity_chart_svg(&velocity));
+        html.push_str("</section>\n");
+    }
+
+    // Link graph
+    html.push_str("<section>\n<h2>Domain Link Graph</h2>\n");
+    html.push_str(&format!(
+        "<p class=\"note\">Top {} domains by page count. Drag to pan, scroll to zoom, hover for details.</p>\n",
+        MAX_GRAPH_NODES
+    ));
+    html.push_str("<canvas id=\"graph\" width=\"1100\" height=\"700\"></canvas>\n</section>\n");
+
+    // Domain table
+    html.push_str("<section>\n<h2>Domains</h2>\n<table class=\"sortable\">\n<thead><tr>");
+    for header in [
+        "Domain",
+        "Pages",
+        "Processed",
+        "Errors",
+        "Links To (domains)",
+        "Linked From (domains)",
+        "Tags",
+    ] {
+        html.push_str(&format!("<th>{}</th>", header));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for row in &domains {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&row.domain),
+            row.pages,
+            row.processed,
+            row.errors,
+            row.outbound_domains,
+            row.inbound_domains,
+            escape_html(&format_tags(summary.domain_tags.get(&row.domain)))
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</section>\n");
+
+    // Page table
+    html.push_str("<section>\n<h2>Pages</h2>\n");
+    if data.pages.len() > MAX_PAGE_ROWS {
+        html.push_str(&format!(
+            "<p class=\"note\">Showing the first {} of {} pages.</p>\n",
+            MAX_PAGE_ROWS,
+            data.pages.len()
+        ));
+    }
+    html.push_str("<table class=\"sortable\">\n<thead><tr>");
+    for header in ["URL", "Domain", "State", "Status", "Title", "Tags"] {
+        html.push_str(&format!("<th>{}</th>", header));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for page in data.pages.iter().take(MAX_PAGE_ROWS) {
+        html.push_str(&format!(
+            "<tr><td><a href=\"{url}\">{url}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&page.domain),
+            page.state,
+            page.status_code.map(|c| c.to_string()).unwrap_or_default(),
+            escape_html(page.title.as_deref().unwrap_or("")),
+            escape_html(&format_tags(summary.page_tags.get(&page.url))),
+            url = escape_html(&page.url),
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</section>\n");
+
+    html.push_str(&format!(
+        "<script id=\"graph-data\" type=\"application/json\">{}</script>\n",
+        graph_json
+    ));
+    html.push_str(SCRIPT);
+    html.push_str("</body>\n</html>\n");
+
+    Ok(html)
+}
+
+/// Aggregates pages and links into per-domain rows, largest domains first
+fn build_domain_rows(data: &HtmlReportData) -> Vec<DomainRow> {
+    let mut rows: BTreeMap<&str, DomainRow> = BTreeMap::new();
+
+    for page in &data.pages {
+        let row = rows
+            .entry(page.domain.as_str())
+            .or_insert_with(|| DomainRow {
+                domain: page.domain.clone(),
+                ..Default::default()
+            });
+        row.pages += 1;
+        if page.state.is_success() {
+            row.processed += 1;
+        }
+        if page.state.is_error() {
+            row.errors += 1;
+        }
+    }
+
+    for (from, to, _) in &data.domain_links {
+        if let Some(row) = rows.get_mut(from.as_str()) {
+            row.outbound_domains += 1;
+        }
+        if let Some(row) = rows.get_mut(to.as_str()) {
+            row.inbound_domains += 1;
+        }
+    }
+
+    let mut rows: Vec<DomainRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| b.pages.cmp(&a.pages).then_with(|| a.domain.cmp(&b.domain)));
+    rows
+}
+
+/// Builds graph data for the largest domains and the links between them
+fn build_graph<'a>(
+    domains: &'a [DomainRow],
+    domain_links: &'a [(String, String, u64)],
+) -> GraphData<'a> {
+    let nodes: Vec<GraphNode> = domains
+        .iter()
+        .take(MAX_GRAPH_NODES)
+        .map(|row| GraphNode {
+            id: &row.domain,
+            pages: row.pages,
+        })
+        .collect();
+
+    let included: HashSet<&str> = nodes.iter().map(|n| n.id).collect();
+    let links = domain_links
+        .iter()
+        .filter(|(from, to, _)| included.contains(from.as_str()) && included.contains(to.as_str()))
+        .map(|(from, to, count)| GraphLink {
+            source: from,
+            target: to,
+            count: *count,
+        })
+        .collect();
+
+    GraphData { nodes, links }
+}
+
+/// Draws pages processed per minute over the course of the run as an inline SVG
+fn velocity_chart_svg(points: &[VelocityPoint]) -> String {
+    let (width, height) = (VELOCITY_CHART_WIDTH, VELOCITY_CHART_HEIGHT);
+    let pad = 40.0;
+    let max_minutes = points
+        .iter()
+        .map(|p| p.elapsed_minutes)
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let max_rate = points
+        .iter()
+        .map(|p| p.pages_per_minute)
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let x = |minutes: f64| pad + minutes / max_minutes * (width - 2.0 * pad);
+    let y = |rate: f64| height - pad - rate / max_rate * (height - 2.0 * pad);
+
+    let mut svg = format!(
+        "<svg class=\"velocity\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\" role=\"img\">\n",
+        w = width,
+        h = height
+    );
+    svg.push_str(&format!(
+        "<line x1=\"{p}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#999\"/>\n<line x1=\"{p}\" y1=\"{p}\" x2=\"{p}\" y2=\"{b}\" stroke=\"#999\"/>\n",
+        p = pad,
+        b = height - pad,
+        r = width - pad
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" class=\"axis\">{:.1} pages/min</text>\n",
+        pad + 4.0,
+        pad - 8.0,
+        max_rate
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" class=\"axis\" text-anchor=\"end\">{:.0} min</text>\n",
+        width - pad,
+        height - pad + 18.0,
+        max_minutes
+    ));
+
+    let line: Vec<String> = points
+        .iter()
+        .skip(1)
+        .map(|p| format!("{:.1},{:.1}", x(p.elapsed_minutes), y(p.pages_per_minute)))
+        .collect();
+    svg.push_str(&format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"#2255aa\" stroke-width=\"2\"/>\n",
+        line.join(" ")
+    ));
+
+    for point in points.iter().skip(1) {
+        svg.push_str(&format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"{}\"><title>{}: {:.1} pages/min, {} processed, {} queued, {} errors</title></circle>\n",
+            x(point.elapsed_minutes),
+            y(point.pages_per_minute),
+            if point.stalled { 4 } else { 2 },
+            if point.stalled { "#cc3333" } else { "#2255aa" },
+            escape_html(&point.recorded_at),
+            point.pages_per_minute,
+            point.pages_processed,
+            point.frontier_size,
+            point.error_count
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Formats a tag list for a table cell
+fn format_tags(tags: Option<&Vec<String>>) -> String {
+    tags.map(|tags| tags.join(", ")).unwrap_or_default()
+}
+
+/// Serializes a value as JSON that is safe to embed in a `<script>` element
+fn script_safe_json<T: Serialize>(value: &T) -> OutputResult<String> {
+    let json = serde_json::to_string(value).map_err(|e| OutputError::Format(e.to_string()))?;
+    Ok(json.replace("</", "<\\/"))
+}
+
+/// Escapes text for inclusion in HTML content or attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = r##"<style>
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-weight: 600; }
+section { margin-bottom: 2.5em; }
+.stats { display: flex; flex-wrap: wrap; gap: 1em; }
+.stats div { background: #f4f4f6; padding: 0.6em 1em; border-radius: 6px; }
+.stats dt { font-size: 0.8em; color: #666; }
+.stats dd { margin: 0; font-size: 1.3em; font-weight: 600; }
+.note { color: #666; font-size: 0.9em; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.35em 0.6em; text-align: left; }
+th { cursor: pointer; background: #f4f4f6; position: sticky; top: 0; user-select: none; }
+th.asc::after { content: " \25B2"; }
+th.desc::after { content: " \25BC"; }
+td a { color: #2255aa; word-break: break-all; }
+canvas { border: 1px solid #ddd; border-radius: 6px; max-width: 100%; cursor: grab; }
+svg.velocity { border: 1px solid #ddd; border-radius: 6px; max-width: 100%; height: auto; }
+svg.velocity .axis { font-size: 12px; fill: #666; }
+</style>
+"##;
+
+const SCRIPT: &str = r##"<script>
+(function () {
+  // Sortable tables: click a header to sort, click again to reverse
+  document.querySelectorAll("table.sortable").forEach(function (table) {
+    var headers = table.querySelectorAll("th");
+    headers.forEach(function (th, col) {
+      th.addEventListener("click", function () {
+        var asc = !th.classList.contains("asc");
+        headers.forEach(function (h) { h.classList.remove("asc", "desc"); });
+        th.classList.add(asc ? "asc" : "desc");
+        var body = table.tBodies[0];
+        var rows = Array.prototype.slice.call(body.rows);
+        rows.sort(function (a, b) {
+          var x = a.cells[col].textContent, y = b.cells[col].textContent;
+          var nx = parseFloat(x), ny = parseFloat(y);
+          var cmp = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+          return asc ? cmp : -cmp;
+        });
+        rows.forEach(function (r) { body.appendChild(r); });
+      });
+    });
+  });
+
+  // Force-directed domain graph
+  var data = JSON.parse(document.getElementById("graph-data").textContent);
+  var canvas = document.getElementById("graph");
+  var ctx = canvas.getContext("2d");
+  var W = canvas.width, H = canvas.height;
+  var index = {};
+  var nodes = data.nodes.map(function (n, i) {
+    index[n.id] = i;
+    var angle = 2 * Math.PI * i / Math.max(data.nodes.length, 1);
+    return { id: n.id, pages: n.pages, x: Math.cos(angle) * 200, y: Math.sin(angle) * 200, vx: 0, vy: 0,
+             r: 3 + Math.sqrt(n.pages) };
+  });
+  var links = data.links.map(function (l) {
+    return { s: nodes[index[l.source]], t: nodes[index[l.target]], count: l.count };
+  });
+  var view = { x: W / 2, y: H / 2, k: 1 }, hover = null;
+
+  function step() {
+    for (var i = 0; i < nodes.length; i++) {
+      for (var j = i + 1; j < nodes.length; j++) {
+        var a = nodes[i], b = nodes[j];
+        var dx = b.x - a.x, dy = b.y - a.y, d2 = dx * dx + dy * dy + 0.01;
+        var f = 800 / d2, d = Math.sqrt(d2);
+        a.vx -= f * dx / d; a.vy -= f * dy / d; b.vx += f * dx / d; b.vy += f * dy / d;
+      }
+    }
+    links.forEach(function (l) {
+      var dx = l.t.x - l.s.x, dy = l.t.y - l.s.y;
+      var d = Math.sqrt(dx * dx + dy * dy) + 0.01, f = (d - 80) * 0.01;
+      l.s.vx += f * dx / d; l.s.vy += f * dy / d; l.t.vx -= f * dx / d; l.t.vy -= f * dy / d;
+    });
+    nodes.forEach(function (n) {
+      n.vx -= n.x * 0.002; n.vy -= n.y * 0.002;
+      n.x += n.vx; n.y += n.vy; n.vx *= 0.8; n.vy *= 0.8;
+    });
+  }
+
+  function draw() {
+    ctx.clearRect(0, 0, W, H);
+    ctx.save();
+    ctx.translate(view.x, view.y);
+    ctx.scale(view.k, view.k);
+    ctx.strokeStyle = "rgba(80, 80, 120, 0.25)";
+    links.forEach(function (l) {
+      ctx.lineWidth = Math.min(1 + Math.log(l.count), 5) / view.k;
+      ctx.beginPath(); ctx.moveTo(l.s.x, l.s.y); ctx.lineTo(l.t.x, l.t.y); ctx.stroke();
+    });
+    nodes.forEach(function (n) {
+      ctx.fillStyle = n === hover ? "#dd5533" : "#3366aa";
+      ctx.beginPath(); ctx.arc(n.x, n.y, n.r, 0, 2 * Math.PI); ctx.fill();
+    });
+    if (hover) {
+      ctx.fillStyle = "#222";
+      ctx.font = (12 / view.k) + "px sans-serif";
+      ctx.fillText(hover.id + " (" + hover.pages + " pages)", hover.x + hover.r + 4, hover.y);
+    }
+    ctx.restore();
+  }
+
+  var ticks = 0;
+  (function animate() {
+    if (ticks++ < 300) { step(); }
+    draw();
+    requestAnimationFrame(animate);
+  })();
+
+  function toGraph(e) {
+    var rect = canvas.getBoundingClientRect();
+    var sx = (e.clientX - rect.left) * W / rect.width, sy = (e.clientY - rect.top) * H / rect.height;
+    return { x: (sx - view.x) / view.k, y: (sy - view.y) / view.k, sx: sx, sy: sy };
+  }
+  var drag = null;
+  canvas.addEventListener("mousedown", function (e) { var p = toGraph(e); drag = { x: p.sx, y: p.sy }; });
+  window.addEventListener("mouseup", function () { drag = null; });
+  canvas.addEventListener("mousemove", function (e) {
+    var p = toGraph(e);
+    if (drag) { view.x += p.sx - drag.x; view.y += p.sy - drag.y; drag = { x: p.sx, y: p.sy }; return; }
+    hover = null;
+    nodes.forEach(function (n) {
+      var dx = n.x - p.x, dy = n.y - p.y;
+      if (dx * dx + dy * dy <= (n.r + 2) * (n.r + 2)) { hover = n; }
+    });
+  });
+  canvas.addEventListener("wheel", function (e) {
+    e.preventDefault();
+    var p = toGraph(e), k = e.deltaY < 0 ? 1.1 : 1 / 1.1;
+    view.k *= k; view.x = p.sx - p.x * view.k; view.y = p.sy - p.y * view.k;
+  }, { passive: false });
+})();
+</script>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, domain: &str, state: PageState, title: Option<&str>) -> PageRecord {
+        PageRecord {
+            id: 0,
+            url: url.to_string(),
+            domain: domain.to_string(),
+            state,
+            title: title.map(|t| t.to_string()),
+            status_code: Some(200),
+            content_type: None,
+            last_modified: None,
+            visited_at: None,
+            discovered_at: "2024-01-01T00:00:00Z".to_string(),
+            discovered_run: 1,
+            error_message: None,
+            retry_count: 0,
+        }
+    }
+
+    fn test_data() -> HtmlReportData {
+        HtmlReportData {
+            pages: vec![
+                page(
+                    "https://a.com/",
+                    "a.com",
+                    PageState::Processed,
+                    Some("A <home>"),
+                ),
+                page("https://a.com/x", "a.com", PageState::DeadLink, None),
+                page("https://b.com/", "b.com", PageState::Processed, Some("B")),
+            ],
+            domain_links: vec![("a.com".to_string(), "b.com".to_string(), 3)],
+        }
+    }
+
+    #[test]
+    fn test_build_domain_rows() {
+        let rows = build_domain_rows(&test_data());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0],
+            DomainRow {
+                domain: "a.com".to_string(),
+                pages: 2,
+                processed: 1,
+                errors: 1,
+                outbound_domains: 1,
+                inbound_domains: 0,
+            }
+        );
+        assert_eq!(rows[1].inbound_domains, 1);
+    }
+
+    #[test]
+    fn test_format_html_report() {
+        let mut summary = CrawlSummary::new();
+        summary.run_id = 7;
+        summary.total_pages = 3;
+
+        let html = format_html_report(&summary, &test_data()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Sumi-Ripple Crawl Report (run 7)"));
+        assert!(html.contains("<td>a.com</td><td>2</td><td>1</td><td>1</td>"));
+        // Titles are escaped
+        assert!(html.contains("A &lt;home&gt;"));
+        // Graph data is inlined
+        assert!(html.contains(r#"{"source":"a.com","target":"b.com","count":3}"#));
+        // No external resources
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("<link rel"));
+    }
+
+    #[test]
+    fn test_html_report_includes_tags() {
+        let mut summary = CrawlSummary::new();
+        summary
+            .domain_tags
+            .insert("b.com".to_string(), vec!["partner".to_string()]);
+        summary
+            .page_tags
+            .insert("https://a.com/".to_string(), vec!["<reviewed>".to_string()]);
+
+        let html = format_html_report(&summary, &test_data()).unwrap();
+        assert!(html.contains("<td>partner</td>"));
+        assert!(html.contains("<td>&lt;reviewed&gt;</td>"));
+    }
+
+    #[test]
+    fn test_html_report_velocity_chart() {
+        use crate::storage::ProgressSnapshot;
+
+        let mut summary = CrawlSummary::new();
+        assert!(!format_html_report(&summary, &test_data())
+            .unwrap()
+            .contains("Crawl Velocity"));
+
+        for (recorded_at, pages_processed) in [
+            ("2024-01-01T00:00:00+00:00", 0),
+            ("2024-01-01T00:05:00+00:00", 50),
+            ("2024-01-01T00:10:00+00:00", 50),
+        ] {
+            summary.progress.push(ProgressSnapshot {
+                run_id: 1,
+                recorded_at: recorded_at.to_string(),
+                pages_processed,
+                frontier_size: 20,
+                error_count: 0,
+                eta_seconds: None,
+            });
+        }
+
+        let html = format_html_report(&summary, &test_data()).unwrap();
+        assert!(html.contains("<h2>Crawl Velocity</h2>"));
+        assert!(html.contains("<polyline points="));
+        assert_eq!(html.matches("fill=\"#cc3333\"").count(), 1);
+    }
+
+    #[test]
+    fn test_script_safe_json() {
+        let json = script_safe_json(&vec!["</script><script>alert(1)"]).unwrap();
+        assert!(!json.contains("</script>"));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+}
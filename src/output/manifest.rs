@@ -0,0 +1,242 @@
+//! Reproducibility manifest of a run
+//!
+//! When a crawl finishes, and whenever its summary is exported, a JSON
+//! manifest is written next to the summary. It records how the run was
+//! produced: the configuration snapshot and seeds, the crawler version, when
+//! it ran, how many pages it fetched from each domain, and a hash of the
+//! domain link graph, so published maps can cite exactly what they were made
+//! from and readers can check a copy of the database against them.
+
+use crate::config::Config;
+use crate::output::traits::{OutputError, OutputResult};
+use crate::storage::Storage;
+use crate::SumiError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How a run was produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunManifest {
+    /// The run the manifest describes
+    pub run_id: i64,
+
+    /// Run status when the manifest was written ("completed", ...)
+    pub status: String,
+
+    /// When the run started (RFC 3339)
+    pub started_at: String,
+
+    /// When the run finished (RFC 3339)
+    pub finished_at: Option<String>,
+
+    /// When the manifest was written (RFC 3339)
+    pub generated_at: String,
+
+    /// Version of the crawler
+    pub crawler_version: String,
+
+    /// Hash of the configuration the run was started with
+    pub config_hash: String,
+
+    /// The configuration the run was started with (TOML), if it was stored
+    pub config: Option<String>,
+
+    /// Seed URLs of the quality entries in the configuration
+    pub seeds: Vec<String>,
+
+    /// Pages fetched from each domain during the run
+    pub domain_requests: BTreeMap<String, u64>,
+
+    /// Number of edges in the domain link graph
+    pub graph_edges: u64,
+
+    /// SHA-256 of the domain link graph, see [`domain_graph_hash`]
+    pub graph_sha256: String,
+}
+
+/// Loads the manifest of a run
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `run_id` - The run to describe
+///
+/// # Returns
+///
+/// * `Ok(RunManifest)` - The manifest
+/// * `Err(SumiError)` - The run does not exist or storage could not be queried
+pub fn load_run_manifest(storage: &dyn Storage, run_id: i64) -> Result<RunManifest, SumiError> {
+    let run = storage.get_run(run_id)?;
+    let config = storage
+        .get_run_config(run_id)?
+        .map(|record| record.config_toml);
+    let seeds = config
+        .as_deref()
+        .and_then(|toml| toml::from_str::<Config>(toml).ok())
+        .map(|config| {
+            config
+                .quality
+                .into_iter()
+                .flat_map(|entry| entry.seeds)
+                .collect()
+        })
+        .unwrap_or_default();
+    let graph = storage.get_domain_links()?;
+
+    Ok(RunManifest {
+        run_id,
+        status: run.status.to_db_string().to_string(),
+        started_at: run.started_at,
+        finished_at: run.finished_at,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        crawler_version: env!("CARGO_PKG_VERSION").to_string(),
+        config_hash: run.config_hash,
+        config,
+        seeds,
+        domain_requests: storage.count_pages_visited_in_run_by_domain(run_id)?,
+        graph_edges: graph.len() as u64,
+        graph_sha256: domain_graph_hash(&graph),
+    })
+}
+
+/// Hashes a domain link graph
+///
+/// The edges are sorted and hashed one per line as "source\ttarget\tcount",
+/// so the hash depends only on the graph, not on the order it was read in.
+///
+/// # Arguments
+///
+/// * `edges` - (source domain, target domain, link count) triples
+///
+/// # Returns
+///
+/// The SHA-256 of the graph, hex-encoded
+pub fn domain_graph_hash(edges: &[(String, String, u64)]) -> String {
+    let mut edges: Vec<_> = edges.iter().collect();
+    edges.sort();
+
+    let mut hasher = Sha256::new();
+    for (source, target, count) in edges {
+        hasher.update(format!("{}\t{}\t{}\n", source, target, count).as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Where the manifest of a run is written
+///
+/// Next to the summary, named after it: `out/summary.md` gives
+/// `out/summary.run-3.manifest.json` for run 3.
+///
+/// # Arguments
+///
+/// * `summary_path` - The configured `summary-path`
+/// * `run_id` - The run the manifest describes
+pub fn run_manifest_path(summary_path: &Path, run_id: i64) -> PathBuf {
+    let stem = summary_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "summary".to_string());
+    summary_path.with_file_name(format!("{}.run-{}.manifest.json", stem, run_id))
+}
+
+/// Writes a manifest as pretty-printed JSON
+///
+/// # Arguments
+///
+/// * `manifest` - The manifest to write
+/// * `output_path` - Path where the JSON file should be written
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully wrote the manifest
+/// * `Err(OutputError)` - Failed to write the manifest
+pub fn generate_run_manifest(manifest: &RunManifest, output_path: &Path) -> OutputResult<()> {
+    let json =
+        serde_json::to_string_pretty(manifest).map_err(|e| OutputError::Format(e.to_string()))?;
+
+    let mut file = File::create(output_path)?;
+    file.write_all(json.as_bytes())?;
+    file.write_all(b"\n")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config_toml;
+    use crate::storage::SqliteStorage;
+
+    const QUALITY: &str = r#"
+[[quality]]
+domain = "blog.example.com"
+seeds = ["https://blog.example.com/", "https://blog.example.com/archive"]
+"#;
+
+    #[test]
+    fn test_run_manifest() {
+        let config = test_config_toml(QUALITY);
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        storage.save_run_config(run_id, "hash", &config).unwrap();
+        let home = storage
+            .insert_or_get_page("https://blog.example.com/", "blog.example.com", run_id)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://other.org/", "other.org", run_id)
+            .unwrap();
+        storage.insert_link(home, other, run_id).unwrap();
+        storage.mark_page_visited(home, run_id).unwrap();
+        storage.complete_run(run_id).unwrap();
+
+        let manifest = load_run_manifest(&storage, run_id).unwrap();
+        assert_eq!(manifest.status, "completed");
+        assert_eq!(manifest.config_hash, "hash");
+        assert_eq!(manifest.config.as_deref(), Some(config.as_str()));
+        assert_eq!(
+            manifest.seeds,
+            vec![
+                "https://blog.example.com/",
+                "https://blog.example.com/archive"
+            ]
+        );
+        assert_eq!(manifest.domain_requests.get("blog.example.com"), Some(&1));
+        assert_eq!(manifest.domain_requests.len(), 1);
+        assert_eq!(manifest.graph_edges, 1);
+        assert_eq!(
+            manifest.graph_sha256,
+            domain_graph_hash(&[("blog.example.com".to_string(), "other.org".to_string(), 1)])
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = run_manifest_path(&dir.path().join("summary.md"), run_id);
+        assert_eq!(
+            path.file_name().unwrap().to_str().unwrap(),
+            format!("summary.run-{}.manifest.json", run_id)
+        );
+        generate_run_manifest(&manifest, &path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["run_id"], run_id);
+        assert_eq!(json["crawler_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_domain_graph_hash_ignores_order() {
+        let a = ("a.com".to_string(), "b.com".to_string(), 2);
+        let b = ("b.com".to_string(), "c.com".to_string(), 1);
+        assert_eq!(
+            domain_graph_hash(&[a.clone(), b.clone()]),
+            domain_graph_hash(&[b.clone(), a.clone()])
+        );
+        assert_ne!(
+            domain_graph_hash(std::slice::from_ref(&a)),
+            domain_graph_hash(&[a, b])
+        );
+        assert_eq!(domain_graph_hash(&[]).len(), 64);
+    }
+}
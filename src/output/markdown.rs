@@ -3,10 +3,19 @@
 //! This module generates human-readable markdown summaries of crawl results,
 //! including statistics, error reports, and discovered domains.
 
-use crate::output::traits::{CrawlSummary, OutputResult};
+use crate::crawler::format_eta;
+use crate::output::progress::{crawl_velocity, sample_velocity};
+use crate::output::traits::{CrawlSummary, OutputResult, TOP_FILTERED_URLS};
+use crate::output::TOP_STUB_DOMAINS;
+use crate::storage::LinkKind;
+use crate::url::matches_wildcard;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
+use std::time::Duration;
+
+/// Maximum number of rows in the crawl velocity table
+const MAX_VELOCITY_ROWS: usize = 48;
 
 /// Generates a markdown summary from crawl statistics
 ///
@@ -58,7 +67,14 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         ));
     }
     md.push_str(&format!("- **Status**: {}\n", summary.status));
-    md.push_str(&format!("- **Config Hash**: {}\n\n", summary.config_hash));
+    md.push_str(&format!("- **Config Hash**: {}\n", summary.config_hash));
+    if summary.run_scoped {
+        md.push_str(&format!(
+            "- **Scope**: pages and links discovered or visited in run {}\n",
+            summary.run_id
+        ));
+    }
+    md.push('\n');
 
     // Overall statistics
     md.push_str("## Overall Statistics\n\n");
@@ -68,6 +84,15 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         summary.unique_domains
     ));
     md.push_str(&format!("- **Total Links**: {}\n", summary.total_links));
+    if summary
+        .link_kinds
+        .keys()
+        .any(|kind| *kind != LinkKind::Anchor)
+    {
+        for (kind, count) in &summary.link_kinds {
+            md.push_str(&format!("  - {}: {}\n", kind.as_str(), count));
+        }
+    }
     md.push_str(&format!("- **Total Errors**: {}\n", summary.total_errors));
     md.push_str(&format!(
         "- **Success Rate**: {:.2}%\n",
@@ -90,6 +115,10 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         summary.pages_blacklisted
     ));
     md.push_str(&format!("| Stubbed | {} |\n", summary.pages_stubbed));
+    md.push_str(&format!(
+        "| Policy Denied | {} |\n",
+        summary.pages_policy_denied
+    ));
     md.push_str(&format!(
         "| Dead Link (404) | {} |\n",
         summary.pages_dead_link
@@ -116,6 +145,41 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         summary.pages_content_mismatch
     ));
 
+    // Crawl velocity
+    let velocity = crawl_velocity(&summary.progress);
+    if velocity.len() > 1 {
+        md.push_str("## Crawl Velocity\n\n");
+        md.push_str("| Time | Elapsed (min) | Processed | Frontier | Errors | Pages/min | ETA |\n");
+        md.push_str("|------|---------------|-----------|----------|--------|-----------|-----|\n");
+
+        for point in sample_velocity(&velocity, MAX_VELOCITY_ROWS) {
+            md.push_str(&format!(
+                "| {} | {:.0} | {} | {} | {} | {:.1}{} | {} |\n",
+                point.recorded_at,
+                point.elapsed_minutes,
+                point.pages_processed,
+                point.frontier_size,
+                point.error_count,
+                point.pages_per_minute,
+                if point.stalled { " (stalled)" } else { "" },
+                point
+                    .eta_seconds
+                    .map(|eta| format_eta(Duration::from_secs(eta)))
+                    .unwrap_or_else(|| "-".to_string())
+            ));
+        }
+        md.push('\n');
+
+        let stalls: Vec<_> = velocity.iter().filter(|p| p.stalled).collect();
+        if let Some(first) = stalls.first() {
+            md.push_str(&format!(
+                "**Stalls**: {} snapshot intervals processed no pages while URLs were queued (first ending {})\n\n",
+                stalls.len(),
+                first.recorded_at
+            ));
+        }
+    }
+
     // Depth breakdown
     if !summary.depth_breakdown.is_empty() {
         md.push_str("## Depth Breakdown\n\n");
@@ -128,35 +192,192 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         for (depth, count) in depths {
             md.push_str(&format!("| {} | {} |\n", depth, count));
         }
-        md.push_str("\n");
+        md.push('\n');
     }
 
     // Quality domains
     if !summary.quality_domains.is_empty() {
         md.push_str("## Quality Domains Crawled\n\n");
-        for domain in &summary.quality_domains {
-            md.push_str(&format!("- {}\n", domain));
+        md.push_str("| Domain | Hosts Discovered |\n");
+        md.push_str("|--------|------------------|\n");
+        for pattern in &summary.quality_domains {
+            let hosts = summary
+                .configured_domains
+                .iter()
+                .filter(|domain| matches_wildcard(pattern, domain))
+                .count();
+            md.push_str(&format!("| {} | {} |\n", pattern, hosts));
         }
-        md.push_str("\n");
+        md.push('\n');
     }
 
-    // Discovered domains
+    // Results per quality origin
+    if !summary.origins.is_empty() {
+        md.push_str("## Results by Quality Origin\n\n");
+        for origin in &summary.origins {
+            md.push_str(&format!("### {}\n\n", origin.origin));
+            md.push_str(&format!(
+                "- **Pages Reached**: {} ({} processed)\n",
+                origin.pages_reached, origin.pages_processed
+            ));
+            md.push_str(&format!("- **Max Depth**: {}\n", origin.max_depth));
+            md.push_str(&format!("- **Dead Links**: {}\n\n", origin.dead_links));
+
+            if !origin.top_external_domains.is_empty() {
+                md.push_str("| Top External Domain | Links |\n");
+                md.push_str("|---------------------|-------|\n");
+                for (domain, count) in &origin.top_external_domains {
+                    md.push_str(&format!("| {} | {} |\n", escape_markdown(domain), count));
+                }
+                md.push('\n');
+            }
+
+            if !origin.dead_link_examples.is_empty() {
+                md.push_str("Dead links:\n\n");
+                for url in &origin.dead_link_examples {
+                    md.push_str(&format!("- {}\n", escape_markdown(url)));
+                }
+                if origin.dead_links > origin.dead_link_examples.len() as u64 {
+                    md.push_str(&format!(
+                        "\n*... and {} more*\n",
+                        origin.dead_links - origin.dead_link_examples.len() as u64
+                    ));
+                }
+                md.push('\n');
+            }
+        }
+    }
+
+    // External domains linked from each quality domain
+    if !summary.outbound.is_empty() {
+        md.push_str("## Outbound Links by Quality Domain\n\n");
+        for report in &summary.outbound {
+            md.push_str(&format!("### {}\n\n", report.quality_domain));
+            if report.targets.is_empty() {
+                md.push_str("*No external links.*\n\n");
+                continue;
+            }
+            md.push_str("| External Domain | Links | Example Sources |\n");
+            md.push_str("|-----------------|-------|-----------------|\n");
+            for target in &report.targets {
+                let sources: Vec<String> = target
+                    .example_sources
+                    .iter()
+                    .map(|url| escape_markdown(url))
+                    .collect();
+                md.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    escape_markdown(&target.domain),
+                    target.links,
+                    sources.join("<br>")
+                ));
+            }
+            md.push('\n');
+        }
+    }
+
+    // Discovered domains, split into configured quality domains and the rest
     if !summary.discovered_domains.is_empty() {
+        let external: Vec<&String> = summary
+            .discovered_domains
+            .iter()
+            .filter(|domain| !summary.configured_domains.contains(domain))
+            .collect();
+
         md.push_str("## Discovered Domains\n\n");
         md.push_str(&format!(
-            "Total discovered: {}\n\n",
-            summary.discovered_domains.len()
+            "Total discovered: {} ({} within configured quality domains, {} found through links)\n\n",
+            summary.discovered_domains.len(),
+            summary.configured_domains.len(),
+            external.len()
         ));
-        for domain in summary.discovered_domains.iter().take(50) {
-            md.push_str(&format!("- {}\n", domain));
+        for domain in external.iter().take(50) {
+            md.push_str(&format!("- {}\n", escape_markdown(domain)));
         }
-        if summary.discovered_domains.len() > 50 {
+        if external.len() > 50 {
+            md.push_str(&format!("\n... and {} more\n\n", external.len() - 50));
+        } else {
+            md.push('\n');
+        }
+    }
+
+    // Suspected mirrors
+    if !summary.suspected_mirrors.is_empty() {
+        md.push_str("## Suspected Mirrors\n\n");
+        md.push_str(
+            "Domain pairs that appear to serve the same site under different hostnames.\n\n",
+        );
+        md.push_str(
+            "| Domain | Domain | Identical Pages | Shared Titles | Link Overlap | Score |\n",
+        );
+        md.push_str(
+            "|--------|--------|-----------------|---------------|--------------|-------|\n",
+        );
+
+        for mirror in &summary.suspected_mirrors {
             md.push_str(&format!(
-                "\n... and {} more\n\n",
-                summary.discovered_domains.len() - 50
+                "| {} | {} | {} | {} | {:.0}% | {:.2} |\n",
+                mirror.domain_a,
+                mirror.domain_b,
+                mirror.shared_content,
+                mirror.shared_titles,
+                mirror.link_similarity * 100.0,
+                mirror.score
             ));
-        } else {
-            md.push_str("\n");
+        }
+        md.push('\n');
+    }
+
+    // Suspicious clusters
+    if !summary.suspicious_clusters.is_empty() {
+        md.push_str("## Suspicious Clusters\n\n");
+        md.push_str(
+            "Groups of domains with abnormally dense reciprocal linking (possible link farms).\n\n",
+        );
+        md.push_str("| Domains | Size | Reciprocal Pairs | Internal Links | Density |\n");
+        md.push_str("|---------|------|------------------|----------------|---------|\n");
+
+        for cluster in &summary.suspicious_clusters {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {:.0}% |\n",
+                cluster.domains.join(", "),
+                cluster.domains.len(),
+                cluster.reciprocal_pairs,
+                cluster.internal_links,
+                cluster.density * 100.0
+            ));
+        }
+        md.push('\n');
+    }
+
+    // User-defined tags
+    if !summary.domain_tags.is_empty() || !summary.page_tags.is_empty() {
+        md.push_str("## Tags\n\n");
+
+        if !summary.domain_tags.is_empty() {
+            md.push_str("| Domain | Tags |\n");
+            md.push_str("|--------|------|\n");
+            for (domain, tags) in &summary.domain_tags {
+                md.push_str(&format!(
+                    "| {} | {} |\n",
+                    escape_markdown(domain),
+                    escape_markdown(&tags.join(", "))
+                ));
+            }
+            md.push('\n');
+        }
+
+        if !summary.page_tags.is_empty() {
+            md.push_str("| Page | Tags |\n");
+            md.push_str("|------|------|\n");
+            for (url, tags) in &summary.page_tags {
+                md.push_str(&format!(
+                    "| {} | {} |\n",
+                    escape_markdown(url),
+                    escape_markdown(&tags.join(", "))
+                ));
+            }
+            md.push('\n');
         }
     }
 
@@ -166,10 +387,10 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         md.push_str("| URL | References |\n");
         md.push_str("|-----|------------|\n");
 
-        for (url, count) in summary.top_blacklisted.iter().take(20) {
-            md.push_str(&format!("| {} | {} |\n", url, count));
+        for (url, count) in summary.top_blacklisted.iter().take(TOP_FILTERED_URLS) {
+            md.push_str(&format!("| {} | {} |\n", escape_markdown(url), count));
         }
-        md.push_str("\n");
+        md.push('\n');
     }
 
     // Top stubbed URLs
@@ -178,10 +399,46 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         md.push_str("| URL | References |\n");
         md.push_str("|-----|------------|\n");
 
-        for (url, count) in summary.top_stubbed.iter().take(20) {
-            md.push_str(&format!("| {} | {} |\n", url, count));
+        for (url, count) in summary.top_stubbed.iter().take(TOP_FILTERED_URLS) {
+            md.push_str(&format!("| {} | {} |\n", escape_markdown(url), count));
+        }
+        md.push('\n');
+    }
+
+    // Discovered domains promoted to a deeper crawl
+    if !summary.promotions.is_empty() {
+        md.push_str("## Promoted Domains\n\n");
+        md.push_str("| Domain | Quality Referrers | Extra Depth | Run | Promoted At |\n");
+        md.push_str("|--------|-------------------|-------------|-----|-------------|\n");
+        for promotion in &summary.promotions {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                escape_markdown(&promotion.domain),
+                promotion.quality_referrers,
+                promotion.extra_depth,
+                promotion.run_id,
+                promotion.promoted_at
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Stub domains ranked by referring quality pages
+    if !summary.stub_domains.is_empty() {
+        md.push_str("## Stub Domains\n\n");
+        md.push_str("Stubbed domains are noted but never visited.\n\n");
+        md.push_str("| Domain | URLs | Referring Pages | Quality Referrers |\n");
+        md.push_str("|--------|------|-----------------|-------------------|\n");
+        for stub in summary.stub_domains.iter().take(TOP_STUB_DOMAINS) {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                escape_markdown(&stub.domain),
+                stub.urls,
+                stub.referring_pages,
+                stub.quality_referrers
+            ));
         }
-        md.push_str("\n");
+        md.push('\n');
     }
 
     // Error summary
@@ -193,7 +450,25 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
         for (state, count) in &summary.error_summary {
             md.push_str(&format!("| {:?} | {} |\n", state, count));
         }
-        md.push_str("\n");
+        md.push('\n');
+    }
+
+    // Failing pages retried the most
+    if !summary.most_retried.is_empty() {
+        md.push_str("## Most Retried Pages\n\n");
+        md.push_str("| URL | State | Retries | Error |\n");
+        md.push_str("|-----|-------|---------|-------|\n");
+
+        for error in &summary.most_retried {
+            md.push_str(&format!(
+                "| {} | {:?} | {} | {} |\n",
+                escape_markdown(&error.url),
+                error.state,
+                error.retry_count,
+                escape_markdown(&error.message)
+            ));
+        }
+        md.push('\n');
     }
 
     // Rate-limited domains
@@ -204,17 +479,211 @@ pub fn format_markdown_summary(summary: &CrawlSummary) -> String {
             summary.rate_limited_domains.len()
         ));
         for domain in &summary.rate_limited_domains {
-            md.push_str(&format!("- {}\n", domain));
+            md.push_str(&format!("- {}\n", escape_markdown(domain)));
         }
-        md.push_str("\n");
+        md.push('\n');
+    }
+
+    // Domains paused by the circuit breaker
+    if !summary.degraded_domains.is_empty() {
+        md.push_str("## Degraded Domains\n\n");
+        md.push_str(
+            "Domains whose requests were paused after repeated network failures or 5xx responses.\n\n",
+        );
+        md.push_str("| Domain | Times Paused |\n");
+        md.push_str("|--------|--------------|\n");
+        for (domain, trips) in &summary.degraded_domains {
+            md.push_str(&format!("| {} | {} |\n", escape_markdown(domain), trips));
+        }
+        md.push('\n');
+    }
+
+    // Domains slowed down after HTTP 429 responses
+    if !summary.slowed_domains.is_empty() {
+        md.push_str("## Domains Requiring Slower Crawling\n\n");
+        md.push_str(
+            "Domains that answered HTTP 429 (Too Many Requests). Their per-domain delay was \
+             multiplied, doubling with every 429.\n\n",
+        );
+        md.push_str("| Domain | Delay Multiplier |\n");
+        md.push_str("|--------|------------------|\n");
+        for (domain, multiplier) in &summary.slowed_domains {
+            md.push_str(&format!(
+                "| {} | {}x |\n",
+                escape_markdown(domain),
+                multiplier
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Domains whose politeness rules could not be retrieved
+    if !summary.robots_failures.is_empty() {
+        md.push_str("## Unavailable robots.txt\n\n");
+        md.push_str(
+            "Domains whose robots.txt failed with a server error. Their pages wait for it; \
+             after repeated failures they are treated as disallowed.\n\n",
+        );
+        md.push_str("| Domain | Failed Fetches | Last Error |\n");
+        md.push_str("|--------|----------------|------------|\n");
+        for failure in &summary.robots_failures {
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_markdown(&failure.domain),
+                failure.failures,
+                escape_markdown(failure.last_error.as_deref().unwrap_or(""))
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Pages with anomalies
+    if !summary.anomaly_counts.is_empty() {
+        md.push_str("## Page Anomalies\n\n");
+        for (kind, count) in &summary.anomaly_counts {
+            md.push_str(&format!("- {}: {} pages\n", kind.as_str(), count));
+        }
+        md.push_str("\n| URL | Anomaly | Detail |\n");
+        md.push_str("|-----|---------|--------|\n");
+        for anomaly in &summary.anomalies {
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_markdown(&anomaly.url),
+                anomaly.kind.as_str(),
+                anomaly.detail
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Links gone from pages fetched again
+    if summary.disappeared_link_count > 0 {
+        md.push_str("## Disappeared Links\n\n");
+        md.push_str(&format!(
+            "{} links were no longer on their page when this run fetched it again.\n\n",
+            summary.disappeared_link_count
+        ));
+        md.push_str("| Page | Link Target | First Seen | Last Seen |\n");
+        md.push_str("|------|-------------|------------|-----------|\n");
+        for link in &summary.disappeared_links {
+            md.push_str(&format!(
+                "| {} | {} | run {} | run {} |\n",
+                escape_markdown(&link.from_url),
+                escape_markdown(&link.to_url),
+                link.discovered_run,
+                link.last_seen_run
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Error page templates left out of the graph
+    if !summary.error_templates.is_empty() {
+        md.push_str("## Error Page Templates\n\n");
+        md.push_str(
+            "Pages of a domain sharing one body, such as a \"not found\" page served with \
+             200 OK. Only the first of each keeps its links in the link graph.\n\n",
+        );
+        md.push_str("| Domain | Pages | Example |\n");
+        md.push_str("|--------|-------|---------|\n");
+        for template in &summary.error_templates {
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_markdown(&template.domain),
+                template.pages,
+                escape_markdown(&template.example_url)
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Detected technologies
+    if !summary.technologies.is_empty() {
+        md.push_str("## Technologies\n\n");
+        md.push_str("Share of the crawled domains each technology was detected on.\n\n");
+        md.push_str("| Technology | Domains | Share |\n");
+        md.push_str("|------------|---------|-------|\n");
+        for technology in &summary.technologies {
+            md.push_str(&format!(
+                "| {} | {} | {:.0}% |\n",
+                technology.technology,
+                technology.domains,
+                technology.share * 100.0
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Harvested contacts
+    if !summary.contacts.is_empty() {
+        md.push_str("## Contacts\n\n");
+        md.push_str("| Domain | Platform | Contact | Pages |\n");
+        md.push_str("|--------|----------|---------|-------|\n");
+        for contact in &summary.contacts {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                escape_markdown(&contact.domain),
+                contact.platform,
+                escape_markdown(&contact.value),
+                contact.pages
+            ));
+        }
+        md.push('\n');
+    }
+
+    // Response header values
+    if !summary.response_headers.is_empty() {
+        md.push_str("## Response Headers\n\n");
+        md.push_str("Share of the crawled domains sending each value.\n\n");
+        md.push_str("| Header | Value | Domains | Share |\n");
+        md.push_str("|--------|-------|---------|-------|\n");
+        for share in &summary.response_headers {
+            md.push_str(&format!(
+                "| {} | {} | {} | {:.0}% |\n",
+                share.header,
+                escape_markdown(&share.value),
+                share.domains,
+                share.share * 100.0
+            ));
+        }
+        md.push('\n');
     }
 
     md
 }
 
+/// Escapes crawled text for use in markdown
+///
+/// Characters with markdown meaning are backslash-escaped, so URLs and tags
+/// cannot break table layout or introduce formatting, and line breaks and
+/// other control characters become spaces.
+///
+/// # Arguments
+///
+/// * `text` - The text to escape
+///
+/// # Returns
+///
+/// The escaped text, safe to place in a table cell or list item
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::output::{OriginReport, OutboundDomain, OutboundReport};
     use std::collections::HashMap;
 
     fn create_test_summary() -> CrawlSummary {
@@ -281,4 +750,433 @@ mod tests {
         assert!(markdown.contains("example.com"));
         assert!(markdown.contains("test.org"));
     }
+
+    #[test]
+    fn test_markdown_configured_domain_split() {
+        let mut summary = create_test_summary();
+        summary.quality_domains = vec!["*.example.com".to_string(), "wiki.org".to_string()];
+        summary.discovered_domains = vec![
+            "blog.example.com".to_string(),
+            "example.com".to_string(),
+            "other.net".to_string(),
+        ];
+        summary.configured_domains =
+            vec!["blog.example.com".to_string(), "example.com".to_string()];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("| *.example.com | 2 |"));
+        assert!(markdown.contains("| wiki.org | 0 |"));
+        assert!(markdown.contains(
+            "Total discovered: 3 (2 within configured quality domains, 1 found through links)"
+        ));
+        assert!(markdown.contains("- other.net\n"));
+        assert!(!markdown.contains("- blog.example.com\n"));
+    }
+
+    #[test]
+    fn test_markdown_origin_sections() {
+        let mut summary = create_test_summary();
+        summary.origins = vec![OriginReport {
+            origin: "example.com".to_string(),
+            pages_reached: 40,
+            pages_processed: 35,
+            max_depth: 3,
+            dead_links: 12,
+            dead_link_examples: vec!["https://example.com/gone".to_string()],
+            top_external_domains: vec![("other.net".to_string(), 9)],
+        }];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Results by Quality Origin"));
+        assert!(markdown.contains("### example.com"));
+        assert!(markdown.contains("- **Pages Reached**: 40 (35 processed)"));
+        assert!(markdown.contains("| other.net | 9 |"));
+        assert!(markdown.contains("- https://example.com/gone"));
+        assert!(markdown.contains("*... and 11 more*"));
+    }
+
+    #[test]
+    fn test_markdown_outbound_links() {
+        let mut summary = create_test_summary();
+        summary.outbound = vec![
+            OutboundReport {
+                quality_domain: "example.com".to_string(),
+                targets: vec![OutboundDomain {
+                    domain: "other.net".to_string(),
+                    links: 7,
+                    example_sources: vec![
+                        "https://example.com/a".to_string(),
+                        "https://example.com/b".to_string(),
+                    ],
+                }],
+            },
+            OutboundReport {
+                quality_domain: "quiet.org".to_string(),
+                targets: vec![],
+            },
+        ];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Outbound Links by Quality Domain"));
+        assert!(
+            markdown.contains("| other.net | 7 | https://example.com/a<br>https://example.com/b |")
+        );
+        assert!(markdown.contains("### quiet.org\n\n*No external links.*"));
+    }
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(
+            escape_markdown("https://a.com/x_y|z*[1]"),
+            "https://a.com/x\\_y\\|z\\*\\[1\\]"
+        );
+        assert_eq!(escape_markdown("line\nbreak"), "line break");
+        assert_eq!(escape_markdown("plain"), "plain");
+    }
+
+    #[test]
+    fn test_markdown_escapes_table_cells() {
+        let mut summary = create_test_summary();
+        summary.top_blacklisted = vec![("https://ads.example.net/a|b".to_string(), 3)];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("| https://ads.example.net/a\\|b | 3 |"));
+    }
+
+    #[test]
+    fn test_markdown_run_scope() {
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("**Scope**"));
+
+        summary.run_scoped = true;
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains(&format!(
+            "- **Scope**: pages and links discovered or visited in run {}\n",
+            summary.run_id
+        )));
+    }
+
+    #[test]
+    fn test_markdown_suspected_mirrors() {
+        use crate::output::MirrorCandidate;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Suspected Mirrors"));
+
+        summary.suspected_mirrors.push(MirrorCandidate {
+            domain_a: "example.com".to_string(),
+            domain_b: "example-mirror.net".to_string(),
+            shared_content: 12,
+            shared_titles: 14,
+            link_similarity: 0.75,
+            score: 0.9,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Suspected Mirrors"));
+        assert!(markdown.contains("| example.com | example-mirror.net | 12 | 14 | 75% | 0.90 |"));
+    }
+
+    #[test]
+    fn test_markdown_crawl_velocity() {
+        use crate::storage::ProgressSnapshot;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Crawl Velocity"));
+
+        for (recorded_at, pages_processed) in [
+            ("2024-01-01T00:00:00+00:00", 0),
+            ("2024-01-01T00:05:00+00:00", 50),
+            ("2024-01-01T00:10:00+00:00", 50),
+        ] {
+            summary.progress.push(ProgressSnapshot {
+                run_id: 1,
+                recorded_at: recorded_at.to_string(),
+                pages_processed,
+                frontier_size: 20,
+                error_count: 2,
+                eta_seconds: (pages_processed > 0).then_some(7200),
+            });
+        }
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Crawl Velocity"));
+        assert!(markdown.contains("| 2024-01-01T00:05:00+00:00 | 5 | 50 | 20 | 2 | 10.0 | 2h 0m |"));
+        assert!(markdown
+            .contains("| 2024-01-01T00:10:00+00:00 | 10 | 50 | 20 | 2 | 0.0 (stalled) | 2h 0m |"));
+        assert!(markdown.contains("**Stalls**: 1 snapshot intervals"));
+    }
+
+    #[test]
+    fn test_markdown_suspicious_clusters() {
+        use crate::output::SuspiciousCluster;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Suspicious Clusters"));
+
+        summary.suspicious_clusters.push(SuspiciousCluster {
+            domains: vec![
+                "a.com".to_string(),
+                "b.com".to_string(),
+                "c.com".to_string(),
+            ],
+            reciprocal_pairs: 3,
+            internal_links: 42,
+            density: 1.0,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Suspicious Clusters"));
+        assert!(markdown.contains("| a.com, b.com, c.com | 3 | 3 | 42 | 100% |"));
+    }
+
+    #[test]
+    fn test_markdown_most_retried() {
+        use crate::output::CrawlError;
+        use crate::state::PageState;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Most Retried"));
+
+        summary.most_retried.push(CrawlError {
+            url: "https://flaky.example.com/".to_string(),
+            state: PageState::Unreachable,
+            message: "Request timeout".to_string(),
+            retry_count: 7,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Most Retried Pages"));
+        assert!(markdown.contains("| Unreachable | 7 | Request timeout |"));
+    }
+
+    #[test]
+    fn test_markdown_degraded_domains() {
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Degraded Domains"));
+
+        summary.degraded_domains = vec![("down.example.com".to_string(), 2)];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Degraded Domains"));
+        assert!(markdown.contains("| down.example.com | 2 |"));
+    }
+
+    #[test]
+    fn test_markdown_slowed_domains() {
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Slower Crawling"));
+
+        summary.slowed_domains = vec![("busy.example.com".to_string(), 8)];
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Domains Requiring Slower Crawling"));
+        assert!(markdown.contains("| busy.example.com | 8x |"));
+    }
+
+    #[test]
+    fn test_markdown_robots_failures() {
+        use crate::storage::RobotsFailure;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Unavailable robots.txt"));
+
+        summary.robots_failures.push(RobotsFailure {
+            domain: "busy.example.com".to_string(),
+            failures: 3,
+            last_error: Some("robots.txt unavailable (HTTP 503)".to_string()),
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Unavailable robots.txt"));
+        assert!(markdown.contains("| busy.example.com | 3 | robots.txt unavailable (HTTP 503) |"));
+    }
+
+    #[test]
+    fn test_markdown_response_headers() {
+        use crate::output::HeaderShare;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Response Headers"));
+
+        summary.response_headers.push(HeaderShare {
+            header: "x-powered-by".to_string(),
+            value: "WordPress".to_string(),
+            domains: 4,
+            share: 0.4,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Response Headers"));
+        assert!(markdown.contains("| x-powered-by | WordPress | 4 | 40% |"));
+    }
+
+    #[test]
+    fn test_markdown_link_kinds() {
+        let mut summary = create_test_summary();
+        summary.link_kinds.insert(LinkKind::Anchor, 40);
+        assert!(!format_markdown_summary(&summary).contains("  - anchor"));
+
+        summary.link_kinds.insert(LinkKind::Iframe, 2);
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("  - anchor: 40\n  - iframe: 2\n"));
+    }
+
+    #[test]
+    fn test_markdown_anomalies() {
+        use crate::storage::{AnomalyKind, PageAnomaly};
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("Page Anomalies"));
+
+        summary
+            .anomaly_counts
+            .insert(AnomalyKind::ExcessiveLinks, 1);
+        summary.anomalies.push(PageAnomaly {
+            url: "https://example.com/directory".to_string(),
+            kind: AnomalyKind::ExcessiveLinks,
+            detail: "4210 links".to_string(),
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Page Anomalies"));
+        assert!(markdown.contains("- excessive_links: 1 pages"));
+        assert!(
+            markdown.contains("| https://example.com/directory | excessive_links | 4210 links |")
+        );
+    }
+
+    #[test]
+    fn test_markdown_disappeared_links() {
+        use crate::storage::DisappearedLink;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Disappeared Links"));
+
+        summary.disappeared_link_count = 1;
+        summary.disappeared_links.push(DisappearedLink {
+            from_url: "https://example.com/".to_string(),
+            to_url: "https://gone.net/".to_string(),
+            discovered_run: 1,
+            last_seen_run: 2,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Disappeared Links"));
+        assert!(markdown.contains("1 links were no longer on their page"));
+        assert!(markdown.contains("| https://example.com/ | https://gone.net/ | run 1 | run 2 |"));
+    }
+
+    #[test]
+    fn test_markdown_error_templates() {
+        use crate::storage::ErrorTemplate;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Error Page Templates"));
+
+        summary.error_templates.push(ErrorTemplate {
+            domain: "example.com".to_string(),
+            template_hash: "abc".to_string(),
+            pages: 140,
+            example_url: "https://example.com/missing".to_string(),
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Error Page Templates"));
+        assert!(markdown.contains("| example.com | 140 | https://example.com/missing |"));
+    }
+
+    #[test]
+    fn test_markdown_promotions() {
+        use crate::storage::DomainPromotion;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Promoted Domains"));
+
+        summary.promotions.push(DomainPromotion {
+            domain: "found.net".to_string(),
+            run_id: 1,
+            quality_referrers: 12,
+            extra_depth: 2,
+            promoted_at: "2024-01-01T00:30:00Z".to_string(),
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Promoted Domains"));
+        assert!(markdown.contains("| found.net | 12 | 2 | 1 | 2024-01-01T00:30:00Z |"));
+    }
+
+    #[test]
+    fn test_markdown_stub_domains() {
+        use crate::output::StubDomain;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Stub Domains"));
+
+        summary.stub_domains.push(StubDomain {
+            domain: "cdn.net".to_string(),
+            urls: 4,
+            referring_pages: 7,
+            quality_referrers: 5,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Stub Domains"));
+        assert!(markdown.contains("| cdn.net | 4 | 7 | 5 |"));
+    }
+
+    #[test]
+    fn test_markdown_contacts() {
+        use crate::storage::ContactRecord;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Contacts"));
+
+        summary.contacts.push(ContactRecord {
+            domain: "example.com".to_string(),
+            platform: "email".to_string(),
+            value: "info@example.com".to_string(),
+            pages: 3,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Contacts"));
+        assert!(markdown.contains("| example.com | email | info@example.com | 3 |"));
+    }
+
+    #[test]
+    fn test_markdown_technologies() {
+        use crate::output::TechnologyShare;
+
+        let mut summary = create_test_summary();
+        assert!(!format_markdown_summary(&summary).contains("## Technologies"));
+
+        summary.technologies.push(TechnologyShare {
+            technology: "WordPress".to_string(),
+            domains: 4,
+            share: 0.4,
+        });
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Technologies"));
+        assert!(markdown.contains("| WordPress | 4 | 40% |"));
+    }
+
+    #[test]
+    fn test_markdown_tags() {
+        let mut summary = create_test_summary();
+        summary.domain_tags.insert(
+            "example.com".to_string(),
+            vec!["news".to_string(), "partner".to_string()],
+        );
+        summary.page_tags.insert(
+            "https://example.com/about".to_string(),
+            vec!["reviewed".to_string()],
+        );
+
+        let markdown = format_markdown_summary(&summary);
+        assert!(markdown.contains("## Tags"));
+        assert!(markdown.contains("| example.com | news, partner |"));
+        assert!(markdown.contains("| https://example.com/about | reviewed |"));
+    }
 }
@@ -0,0 +1,319 @@
+//! Mirror detection analysis
+//!
+//! This module flags pairs of domains that are likely mirrors of each other,
+//! i.e. the same site reached via multiple hostnames. Two domains are compared
+//! on identical page bodies (content hashes), shared page titles, and how much
+//! their outbound link sets overlap.
+
+use crate::storage::{PageFingerprint, Storage};
+use crate::url::extract_domain;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use url::Url;
+
+/// Minimum score for a domain pair to be reported as a suspected mirror
+pub const MIRROR_SCORE_THRESHOLD: f64 = 0.6;
+
+/// Fingerprint values shared by more domains than this are too common
+/// (e.g. "Home", "404 Not Found") to suggest a mirror relationship
+const MAX_DOMAINS_PER_KEY: usize = 50;
+
+/// A pair of domains suspected to be mirrors
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MirrorCandidate {
+    /// First domain (alphabetically)
+    pub domain_a: String,
+
+    /// Second domain (alphabetically)
+    pub domain_b: String,
+
+    /// Number of identical page bodies found on both domains
+    pub shared_content: usize,
+
+    /// Number of page titles found on both domains
+    pub shared_titles: usize,
+
+    /// Jaccard similarity of the two domains' outbound link sets (0.0 - 1.0)
+    pub link_similarity: f64,
+
+    /// Overall mirror score (0.0 - 1.0)
+    pub score: f64,
+}
+
+/// Per-domain data used for comparison
+#[derive(Debug, Default)]
+struct DomainProfile {
+    content_hashes: HashSet<String>,
+    titles: HashSet<String>,
+    /// Outbound link target URL -> target domain
+    link_targets: HashMap<String, String>,
+}
+
+/// Detects suspected mirrors using the data in storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// * `Ok(Vec<MirrorCandidate>)` - Suspected mirrors, highest score first
+/// * `Err(SumiError)` - Failed to query storage
+pub fn detect_mirrors(storage: &dyn Storage) -> Result<Vec<MirrorCandidate>, SumiError> {
+    let fingerprints = storage.get_page_fingerprints()?;
+    let links = storage.get_cross_domain_links()?;
+    Ok(find_mirrors(&fingerprints, &links))
+}
+
+/// Finds suspected mirrors from page fingerprints and cross-domain links
+///
+/// # Arguments
+///
+/// * `fingerprints` - Title and content hash of each processed page
+/// * `links` - (source domain, target URL) pairs for cross-domain links
+///
+/// # Returns
+///
+/// Domain pairs scoring at least `MIRROR_SCORE_THRESHOLD`, highest score first
+pub fn find_mirrors(
+    fingerprints: &[PageFingerprint],
+    links: &[(String, String)],
+) -> Vec<MirrorCandidate> {
+    let profiles = build_profiles(fingerprints, links);
+
+    let mut candidates: Vec<MirrorCandidate> = candidate_pairs(&profiles)
+        .into_iter()
+        .filter_map(|(a, b)| score_pair(a, &profiles[a], b, &profiles[b]))
+        .filter(|candidate| candidate.score >= MIRROR_SCORE_THRESHOLD)
+        .collect();
+
+    candidates.sort_by(|x, y| {
+        y.score
+            .partial_cmp(&x.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| x.domain_a.cmp(&y.domain_a))
+            .then_with(|| x.domain_b.cmp(&y.domain_b))
+    });
+
+    candidates
+}
+
+/// Groups fingerprints and links by domain
+fn build_profiles(
+    fingerprints: &[PageFingerprint],
+    links: &[(String, String)],
+) -> HashMap<String, DomainProfile> {
+    let mut profiles: HashMap<String, DomainProfile> = HashMap::new();
+
+    for fingerprint in fingerprints {
+        let profile = profiles.entry(fingerprint.domain.clone()).or_default();
+        if let Some(hash) = &fingerprint.content_hash {
+            profile.content_hashes.insert(hash.clone());
+        }
+        if let Some(title) = fingerprint.title.as_deref().map(normalize_title) {
+            if !title.is_empty() {
+                profile.titles.insert(title);
+            }
+        }
+    }
+
+    for (from_domain, to_url) in links {
+        // Only domains we actually fetched pages from can be compared
+        let Some(profile) = profiles.get_mut(from_domain) else {
+            continue;
+        };
+        let target_domain = Url::parse(to_url)
+            .ok()
+            .and_then(|url| extract_domain(&url))
+            .unwrap_or_default();
+        profile.link_targets.insert(to_url.clone(), target_domain);
+    }
+
+    profiles
+}
+
+/// Normalizes a title for comparison
+fn normalize_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Finds domain pairs that share at least one content hash or title
+///
+/// Pairs are returned with the alphabetically smaller domain first.
+fn candidate_pairs(profiles: &HashMap<String, DomainProfile>) -> BTreeSet<(&str, &str)> {
+    let mut by_key: HashMap<(bool, &str), Vec<&str>> = HashMap::new();
+
+    for (domain, profile) in profiles {
+        for hash in &profile.content_hashes {
+            by_key.entry((true, hash)).or_default().push(domain);
+        }
+        for title in &profile.titles {
+            by_key.entry((false, title)).or_default().push(domain);
+        }
+    }
+
+    let mut pairs = BTreeSet::new();
+    for domains in by_key.values() {
+        if domains.len() < 2 || domains.len() > MAX_DOMAINS_PER_KEY {
+            continue;
+        }
+        for (i, a) in domains.iter().enumerate() {
+            for b in &domains[i + 1..] {
+                pairs.insert(if a < b { (*a, *b) } else { (*b, *a) });
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Scores a single domain pair
+fn score_pair(
+    domain_a: &str,
+    a: &DomainProfile,
+    domain_b: &str,
+    b: &DomainProfile,
+) -> Option<MirrorCandidate> {
+    let shared_content = a.content_hashes.intersection(&b.content_hashes).count();
+    let shared_titles = a.titles.intersection(&b.titles).count();
+
+    let content_ratio = overlap_ratio(
+        shared_content,
+        a.content_hashes.len(),
+        b.content_hashes.len(),
+    );
+    let title_ratio = overlap_ratio(shared_titles, a.titles.len(), b.titles.len());
+
+    // Mirrors commonly link to each other, so links between the pair are ignored
+    let outbound = |profile: &DomainProfile| -> HashSet<String> {
+        profile
+            .link_targets
+            .iter()
+            .filter(|(_, target)| target.as_str() != domain_a && target.as_str() != domain_b)
+            .map(|(url, _)| url.clone())
+            .collect()
+    };
+    let link_similarity = jaccard(&outbound(a), &outbound(b));
+
+    let score = content_ratio.max((title_ratio + link_similarity) / 2.0);
+    if score <= 0.0 {
+        return None;
+    }
+
+    Some(MirrorCandidate {
+        domain_a: domain_a.to_string(),
+        domain_b: domain_b.to_string(),
+        shared_content,
+        shared_titles,
+        link_similarity,
+        score,
+    })
+}
+
+/// Shared items relative to the smaller of the two sets
+fn overlap_ratio(shared: usize, len_a: usize, len_b: usize) -> f64 {
+    let smaller = len_a.min(len_b);
+    if smaller == 0 {
+        0.0
+    } else {
+        shared as f64 / smaller as f64
+    }
+}
+
+/// Jaccard similarity of two sets (0.0 when both are empty)
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        0.0
+    } else {
+        a.intersection(b).count() as f64 / union as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(domain: &str, title: &str, hash: &str) -> PageFingerprint {
+        PageFingerprint {
+            domain: domain.to_string(),
+            title: Some(title.to_string()),
+            content_hash: Some(hash.to_string()),
+        }
+    }
+
+    fn link(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn test_identical_content_is_mirror() {
+        let fingerprints = vec![
+            page("example.com", "Home", "h1"),
+            page("example.com", "About", "h2"),
+            page("www-mirror.net", "Home", "h1"),
+            page("www-mirror.net", "About", "h2"),
+            page("other.org", "Something else", "h3"),
+        ];
+
+        let mirrors = find_mirrors(&fingerprints, &[]);
+        assert_eq!(mirrors.len(), 1);
+        assert_eq!(mirrors[0].domain_a, "example.com");
+        assert_eq!(mirrors[0].domain_b, "www-mirror.net");
+        assert_eq!(mirrors[0].shared_content, 2);
+        assert!((mirrors[0].score - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_titles_and_links_indicate_mirror() {
+        // Different bodies (e.g. hostname embedded in the page) but the same
+        // titles and outbound links
+        let fingerprints = vec![
+            page("a.com", "Welcome to Foo", "a1"),
+            page("b.com", "Welcome  to foo", "b1"),
+        ];
+        let links = vec![
+            link("a.com", "https://x.org/"),
+            link("a.com", "https://y.org/"),
+            link("a.com", "https://b.com/"),
+            link("b.com", "https://x.org/"),
+            link("b.com", "https://y.org/"),
+            link("b.com", "https://a.com/"),
+        ];
+
+        let mirrors = find_mirrors(&fingerprints, &links);
+        assert_eq!(mirrors.len(), 1);
+        assert_eq!(mirrors[0].shared_titles, 1);
+        assert_eq!(mirrors[0].shared_content, 0);
+        assert!((mirrors[0].link_similarity - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_shared_title_alone_is_not_mirror() {
+        let fingerprints = vec![page("a.com", "Home", "a1"), page("b.com", "Home", "b1")];
+        let links = vec![
+            link("a.com", "https://x.org/"),
+            link("b.com", "https://y.org/"),
+        ];
+
+        assert!(find_mirrors(&fingerprints, &links).is_empty());
+    }
+
+    #[test]
+    fn test_no_fingerprints() {
+        assert!(find_mirrors(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let a: HashSet<String> = ["1", "2"].iter().map(|s| s.to_string()).collect();
+        let b: HashSet<String> = ["2", "3"].iter().map(|s| s.to_string()).collect();
+        assert!((jaccard(&a, &b) - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(jaccard(&HashSet::new(), &HashSet::new()), 0.0);
+    }
+}
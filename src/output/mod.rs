@@ -2,20 +2,79 @@
 //!
 //! This module handles:
 //! - Generating markdown summaries of crawl results
-//! - Exporting data in various formats
+//! - Exporting data in various formats (including an interactive HTML report
+//!   and a CSV log of blacklisted/stubbed URLs)
+//! - Rendering reports from user-supplied templates
 //! - Recording crawl statistics and metrics
+//! - Analyses such as mirror and link farm detection
+//! - Per-quality-origin results and outbound external links
+//! - Stub domains ranked by the quality pages referencing them
+//! - Archiving fetched pages as WARC
+//! - Explaining what is known about a single URL
+//! - Reproducibility manifests of runs
+//! - Page changes between runs
+//! - Parquet files for analytics tools (with the `parquet` feature)
+//! - Cypher scripts loading the page graph into a graph database
 
+mod changes;
+mod clusters;
+mod completion;
+mod cypher;
+mod explain;
+mod headers;
+mod hits;
+mod html;
+mod manifest;
 mod markdown;
+mod mirrors;
+mod origins;
+mod outbound;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod progress;
 mod sqlite_output;
 pub mod stats;
+mod stubs;
+mod technologies;
+mod template;
 mod traits;
+mod warc;
 
+pub use changes::{diff_observations, load_page_changes, FieldChange, PageChange};
+pub use clusters::{detect_link_clusters, find_link_clusters, SuspiciousCluster};
+pub use completion::{load_completion_report, CompletionReport};
+pub use cypher::{format_cypher, generate_cypher_export, CypherExport};
+pub use explain::{explain_url, InboundLink, UrlExplanation, EXPLAIN_INBOUND_LINKS};
+pub use headers::{build_header_summary, load_header_summary, HeaderShare, TOP_HEADER_VALUES};
+pub use hits::{format_hits_csv, generate_hits_csv};
+pub use html::{generate_html_report, load_html_report_data, HtmlReportData};
+pub use manifest::{
+    domain_graph_hash, generate_run_manifest, load_run_manifest, run_manifest_path, RunManifest,
+};
 pub use markdown::generate_markdown_summary;
+pub use mirrors::{detect_mirrors, find_mirrors, MirrorCandidate};
+pub use origins::{build_origin_reports, load_origin_reports, OriginReport};
+pub use outbound::{build_outbound_reports, load_outbound_reports, OutboundDomain, OutboundReport};
+#[cfg(feature = "parquet")]
+pub use parquet::{export_parquet, ParquetExport};
+pub use progress::{crawl_velocity, sample_velocity, VelocityPoint};
 pub use sqlite_output::SqliteOutputHandler;
-pub use stats::{load_statistics, print_statistics, CrawlStatistics};
-pub use traits::{CrawlSummary, OutputHandler};
+pub use stats::{
+    load_run_statistics, load_statistics, print_statistics, CrawlStatistics, RunConfigSummary,
+    RunScope, TOP_DOMAINS,
+};
+pub use stubs::{build_stub_domains, load_stub_domains, StubDomain, TOP_STUB_DOMAINS};
+pub use technologies::{build_technology_summary, load_technology_summary, TechnologyShare};
+pub use template::{generate_templated_report, render_template, TemplateFormat};
+pub use traits::{
+    CrawlError, CrawlSummary, OutputHandler, TOP_ANOMALIES, TOP_DISAPPEARED_LINKS,
+    TOP_FILTERED_URLS, TOP_RETRIED_PAGES,
+};
+pub use warc::WarcWriter;
 
-use crate::storage::Storage;
+use crate::config::Config;
+use crate::storage::{RunRecord, Storage};
+use crate::url::matches_wildcard;
 use crate::SumiError;
 
 /// Generates a crawl summary from storage
@@ -29,30 +88,49 @@ use crate::SumiError;
 /// * `Ok(CrawlSummary)` - Successfully generated summary
 /// * `Err(SumiError)` - Failed to generate summary
 pub fn generate_summary(storage: &dyn Storage) -> Result<CrawlSummary, SumiError> {
-    use crate::state::PageState;
-
     // Get the latest run
     let run = storage
         .get_latest_run()?
         .ok_or_else(|| SumiError::Storage("No crawl runs found in database".to_string()))?;
 
-    // Calculate duration if finished
-    let duration_seconds = if let (Ok(started), Some(finished_str)) = (
-        run.started_at.parse::<chrono::DateTime<chrono::Utc>>(),
-        &run.finished_at,
-    ) {
-        if let Ok(finished) = finished_str.parse::<chrono::DateTime<chrono::Utc>>() {
-            Some((finished - started).num_seconds() as u64)
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
     // Load statistics
     let stats = stats::load_statistics(storage)?;
 
+    build_summary(storage, run, stats, false)
+}
+
+/// Generates a crawl summary limited to a single run
+///
+/// Only pages first discovered or fetched during the run, and links first
+/// discovered during it, are counted.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `run_id` - The run to summarize
+///
+/// # Returns
+///
+/// * `Ok(CrawlSummary)` - Successfully generated summary
+/// * `Err(SumiError)` - The run does not exist or the summary failed
+pub fn generate_run_summary(storage: &dyn Storage, run_id: i64) -> Result<CrawlSummary, SumiError> {
+    let run = storage.get_run(run_id)?;
+    let stats = stats::load_run_statistics(storage, run_id)?;
+
+    build_summary(storage, run, stats, true)
+}
+
+/// Assembles a summary of a run from previously loaded statistics
+fn build_summary(
+    storage: &dyn Storage,
+    run: RunRecord,
+    stats: CrawlStatistics,
+    run_scoped: bool,
+) -> Result<CrawlSummary, SumiError> {
+    use crate::state::PageState;
+
+    let duration_seconds = run_duration_seconds(&run);
+
     // Get page counts by state
     let pages_discovered = stats
         .pages_by_state
@@ -79,6 +157,11 @@ pub fn generate_summary(storage: &dyn Storage) -> Result<CrawlSummary, SumiError
         .get(&PageState::Stubbed)
         .copied()
         .unwrap_or(0);
+    let pages_policy_denied = stats
+        .pages_by_state
+        .get(&PageState::PolicyDenied)
+        .copied()
+        .unwrap_or(0);
     let pages_dead_link = stats
         .pages_by_state
         .get(&PageState::DeadLink)
@@ -116,20 +199,26 @@ pub fn generate_summary(storage: &dyn Storage) -> Result<CrawlSummary, SumiError
         .unwrap_or(0);
 
     // Get blacklisted and stubbed URLs
-    let top_blacklisted = storage.get_blacklisted_urls()?;
-    let top_stubbed = storage.get_stubbed_urls()?;
+    let top_blacklisted = storage.get_blacklisted_urls(TOP_FILTERED_URLS, 0)?;
+    let top_stubbed = storage.get_stubbed_urls(TOP_FILTERED_URLS, 0)?;
 
-    // Get depth breakdown
-    let depth_breakdown = storage
-        .get_depth_breakdown()?
-        .into_iter()
-        .map(|(k, v)| (k, v as u64))
-        .collect();
+    // Flag domains that look like mirrors of each other
+    let suspected_mirrors = mirrors::detect_mirrors(storage)?;
+
+    // Flag densely interlinked domain groups (possible link farms)
+    let suspicious_clusters = clusters::detect_link_clusters(storage)?;
+
+    // Results per quality origin
+    let origins = origins::load_origin_reports(storage, run_scoped.then_some(run.id))?;
 
-    // Get discovered domains
-    let discovered_domains = storage.get_discovered_domains()?;
+    // User annotations
+    let domain_tags = storage.get_domain_tags()?;
+    let page_tags = storage.get_page_tags()?;
 
-    Ok(CrawlSummary {
+    // Progress snapshots for the crawl-velocity chart
+    let progress = storage.get_run_progress(run.id)?;
+
+    let mut summary = CrawlSummary {
         run_id: run.id,
         started_at: run.started_at,
         finished_at: run.finished_at,
@@ -140,11 +229,13 @@ pub fn generate_summary(storage: &dyn Storage) -> Result<CrawlSummary, SumiError
         unique_domains: stats.unique_domains,
         total_links: stats.total_links,
         total_errors: stats.error_summary.values().sum(),
+        link_kinds: storage.count_links_by_kind()?,
         pages_discovered,
         pages_queued,
         pages_processed,
         pages_blacklisted,
         pages_stubbed,
+        pages_policy_denied,
         pages_dead_link,
         pages_unreachable,
         pages_rate_limited,
@@ -152,12 +243,148 @@ pub fn generate_summary(storage: &dyn Storage) -> Result<CrawlSummary, SumiError
         pages_depth_exceeded,
         pages_request_limit_hit,
         pages_content_mismatch,
-        depth_breakdown,
-        discovered_domains,
+        // Filled in by load_domain_breakdown
+        depth_breakdown: Default::default(),
+        discovered_domains: vec![],
         top_blacklisted,
         top_stubbed,
         error_summary: stats.error_summary.clone(),
         rate_limited_domains: stats.rate_limited_domains.clone(),
-        quality_domains: vec![], // Note: Quality domains would need to be stored in DB or passed from config
-    })
+        degraded_domains: storage.get_degraded_domains()?,
+        slowed_domains: storage.get_slowed_domains()?,
+        robots_failures: storage.get_robots_failures()?,
+        response_headers: headers::load_header_summary(storage)?,
+        anomaly_counts: storage.count_page_anomalies()?,
+        anomalies: storage.get_page_anomalies(TOP_ANOMALIES)?,
+        disappeared_link_count: storage.count_disappeared_links(run.id)?,
+        disappeared_links: storage.get_disappeared_links(run.id, TOP_DISAPPEARED_LINKS)?,
+        error_templates: storage.get_error_templates()?,
+        technologies: technologies::load_technology_summary(storage)?,
+        domain_technologies: storage.get_domain_technologies()?,
+        contacts: storage.get_contacts()?,
+        most_retried: load_most_retried(storage)?,
+        quality_domains: vec![],
+        configured_domains: vec![],
+        origins,
+        // Filled in once the quality domains are known
+        outbound: vec![],
+        stub_domains: vec![],
+        promotions: storage.get_domain_promotions()?,
+        suspected_mirrors,
+        suspicious_clusters,
+        domain_tags,
+        page_tags,
+        progress,
+        run_scoped,
+    };
+
+    load_domain_breakdown(storage, &mut summary)?;
+    summary.outbound = outbound::load_outbound_reports(
+        storage,
+        &summary.quality_domains,
+        run_scoped.then_some(summary.run_id),
+    )?;
+    summary.stub_domains = stubs::load_stub_domains(
+        storage,
+        &summary.quality_domains,
+        run_scoped.then_some(summary.run_id),
+    )?;
+
+    Ok(summary)
+}
+
+/// Calculates how long a finished run took
+///
+/// # Returns
+///
+/// The duration in seconds, or `None` if the run has not finished or its
+/// timestamps cannot be parsed
+pub(crate) fn run_duration_seconds(run: &RunRecord) -> Option<u64> {
+    let started = run
+        .started_at
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .ok()?;
+    let finished = run
+        .finished_at
+        .as_ref()?
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .ok()?;
+    Some((finished - started).num_seconds() as u64)
+}
+
+/// Loads the failing pages that were retried the most
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// Up to `TOP_RETRIED_PAGES` pages in an error state, most retries first
+pub(crate) fn load_most_retried(storage: &dyn Storage) -> Result<Vec<CrawlError>, SumiError> {
+    Ok(storage
+        .get_most_retried_pages(TOP_RETRIED_PAGES)?
+        .into_iter()
+        .map(|page| CrawlError {
+            url: page.url,
+            state: page.state,
+            message: page.error_message.unwrap_or_default(),
+            retry_count: page.retry_count,
+        })
+        .collect())
+}
+
+/// Fills in the depth breakdown and the quality, configured and discovered
+/// domain lists of a summary
+///
+/// Quality domains are taken from the configuration the run was started with.
+/// Runs without a stored configuration fall back to the quality origins that
+/// page depths were recorded for. Run-scoped summaries only count the pages
+/// and domains the run discovered or visited.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `summary` - The summary to fill in; `run_id` must be set
+pub(crate) fn load_domain_breakdown(
+    storage: &dyn Storage,
+    summary: &mut CrawlSummary,
+) -> Result<(), SumiError> {
+    let depth_breakdown = if summary.run_scoped {
+        storage.get_run_depth_breakdown(summary.run_id)?
+    } else {
+        storage.get_depth_breakdown()?
+    };
+    summary.depth_breakdown = depth_breakdown
+        .into_iter()
+        .map(|(k, v)| (k, v as u64))
+        .collect();
+
+    let configured = storage
+        .get_run_config(summary.run_id)?
+        .and_then(|record| toml::from_str::<Config>(&record.config_toml).ok())
+        .map(|config| config.quality.into_iter().map(|q| q.domain).collect());
+    summary.quality_domains = match configured {
+        Some(domains) => domains,
+        None => storage.get_quality_origins()?,
+    };
+
+    summary.discovered_domains = if summary.run_scoped {
+        storage.get_run_domains(summary.run_id)?
+    } else {
+        storage.get_discovered_domains()?
+    };
+    summary.configured_domains = summary
+        .discovered_domains
+        .iter()
+        .filter(|domain| {
+            summary
+                .quality_domains
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, domain))
+        })
+        .cloned()
+        .collect();
+
+    Ok(())
 }
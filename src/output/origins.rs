@@ -0,0 +1,173 @@
+//! Per-origin crawl reports
+//!
+//! A crawl with several quality domains is really several crawls sharing a
+//! frontier. Page depths are recorded relative to each quality origin, so the
+//! pages reached from one origin, how deep the crawl got, which outside
+//! domains it links to and which of its links are dead can be reported per
+//! origin.
+
+use crate::storage::{OriginStats, Storage};
+use crate::url::matches_wildcard;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Number of external domains listed per origin
+pub const TOP_EXTERNAL_DOMAINS: usize = 10;
+
+/// Number of dead link URLs listed per origin
+pub const DEAD_LINK_EXAMPLES: usize = 10;
+
+/// Crawl results for one quality origin
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OriginReport {
+    /// The quality domain pattern
+    pub origin: String,
+
+    /// Pages with a depth recorded from this origin
+    pub pages_reached: u64,
+
+    /// Reached pages that were fetched and processed
+    pub pages_processed: u64,
+
+    /// Greatest depth reached from this origin
+    pub max_depth: u32,
+
+    /// Reached pages that turned out to be dead links
+    pub dead_links: u64,
+
+    /// Up to `DEAD_LINK_EXAMPLES` dead link URLs
+    pub dead_link_examples: Vec<String>,
+
+    /// Domains outside the origin pattern linked most often, with link counts
+    pub top_external_domains: Vec<(String, u64)>,
+}
+
+/// Loads a report for every quality origin using the data in storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `run_id` - Only include pages and links of this run
+///
+/// # Returns
+///
+/// * `Ok(Vec<OriginReport>)` - One report per origin, sorted by origin
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_origin_reports(
+    storage: &dyn Storage,
+    run_id: Option<i64>,
+) -> Result<Vec<OriginReport>, SumiError> {
+    let stats = storage.get_origin_stats(run_id)?;
+    let link_domains = storage.get_origin_link_domains(run_id)?;
+    let dead_links = storage.get_origin_dead_links(run_id, DEAD_LINK_EXAMPLES)?;
+    Ok(build_origin_reports(stats, &link_domains, dead_links))
+}
+
+/// Combines per-origin statistics, linked domains and dead links into reports
+///
+/// # Arguments
+///
+/// * `stats` - Page counts per origin
+/// * `link_domains` - (origin, target domain, link count) triples
+/// * `dead_links` - (origin, URL) pairs
+///
+/// # Returns
+///
+/// One report per entry of `stats`, in the same order
+pub fn build_origin_reports(
+    stats: Vec<OriginStats>,
+    link_domains: &[(String, String, u64)],
+    dead_links: Vec<(String, String)>,
+) -> Vec<OriginReport> {
+    let mut examples: HashMap<String, Vec<String>> = HashMap::new();
+    for (origin, url) in dead_links {
+        examples.entry(origin).or_default().push(url);
+    }
+
+    stats
+        .into_iter()
+        .map(|stats| {
+            let mut external: Vec<(String, u64)> = link_domains
+                .iter()
+                .filter(|(origin, domain, _)| {
+                    *origin == stats.origin && !matches_wildcard(origin, domain)
+                })
+                .map(|(_, domain, count)| (domain.clone(), *count))
+                .collect();
+            external.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            external.truncate(TOP_EXTERNAL_DOMAINS);
+
+            OriginReport {
+                dead_link_examples: examples.remove(&stats.origin).unwrap_or_default(),
+                top_external_domains: external,
+                origin: stats.origin,
+                pages_reached: stats.pages_reached,
+                pages_processed: stats.pages_processed,
+                max_depth: stats.max_depth,
+                dead_links: stats.dead_links,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(origin: &str, pages_reached: u64) -> OriginStats {
+        OriginStats {
+            origin: origin.to_string(),
+            pages_reached,
+            pages_processed: pages_reached,
+            max_depth: 2,
+            dead_links: 0,
+        }
+    }
+
+    fn link(origin: &str, domain: &str, count: u64) -> (String, String, u64) {
+        (origin.to_string(), domain.to_string(), count)
+    }
+
+    #[test]
+    fn test_external_domains_exclude_origin() {
+        let reports = build_origin_reports(
+            vec![stats("*.example.com", 10), stats("wiki.org", 3)],
+            &[
+                link("*.example.com", "blog.example.com", 50),
+                link("*.example.com", "a.net", 2),
+                link("*.example.com", "b.net", 7),
+                link("wiki.org", "a.net", 1),
+            ],
+            vec![],
+        );
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(
+            reports[0].top_external_domains,
+            vec![("b.net".to_string(), 7), ("a.net".to_string(), 2)]
+        );
+        assert_eq!(
+            reports[1].top_external_domains,
+            vec![("a.net".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_dead_links_grouped_by_origin() {
+        let reports = build_origin_reports(
+            vec![stats("a.com", 5), stats("b.com", 5)],
+            &[],
+            vec![
+                ("a.com".to_string(), "https://a.com/x".to_string()),
+                ("a.com".to_string(), "https://a.com/y".to_string()),
+            ],
+        );
+
+        assert_eq!(
+            reports[0].dead_link_examples,
+            vec!["https://a.com/x", "https://a.com/y"]
+        );
+        assert!(reports[1].dead_link_examples.is_empty());
+    }
+}
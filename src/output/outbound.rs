@@ -0,0 +1,177 @@
+//! Outbound external links per quality domain
+//!
+//! For each quality domain pattern, lists every domain outside the pattern
+//! that its pages link to, with the number of links and a few of the pages
+//! the links are on. This shows where a site sends its visitors.
+
+use crate::storage::{DomainLinkSummary, Storage};
+use crate::url::matches_wildcard;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Number of example source pages listed per external domain
+pub const EXAMPLE_SOURCES: usize = 3;
+
+/// An external domain linked from a quality domain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutboundDomain {
+    /// The linked domain
+    pub domain: String,
+
+    /// Number of links to it
+    pub links: u64,
+
+    /// Up to `EXAMPLE_SOURCES` pages containing such a link, sorted
+    pub example_sources: Vec<String>,
+}
+
+/// External domains linked from one quality domain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutboundReport {
+    /// The quality domain pattern
+    pub quality_domain: String,
+
+    /// Every external domain linked, most links first
+    pub targets: Vec<OutboundDomain>,
+}
+
+/// Loads the outbound link report using the data in storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `quality_domains` - The quality domain patterns to report on
+/// * `run_id` - Only include links first discovered during this run
+///
+/// # Returns
+///
+/// * `Ok(Vec<OutboundReport>)` - One report per pattern, in the given order
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_outbound_reports(
+    storage: &dyn Storage,
+    quality_domains: &[String],
+    run_id: Option<i64>,
+) -> Result<Vec<OutboundReport>, SumiError> {
+    let links = storage.get_domain_link_summaries(run_id, EXAMPLE_SOURCES)?;
+    Ok(build_outbound_reports(quality_domains, &links))
+}
+
+/// Groups domain-level links by the quality domain pattern of their source
+///
+/// # Arguments
+///
+/// * `quality_domains` - The quality domain patterns to report on
+/// * `links` - Links between distinct domains
+///
+/// # Returns
+///
+/// One report per pattern. Links to domains matching the same pattern (e.g.
+/// between subdomains of `*.example.com`) are not external and are left out.
+pub fn build_outbound_reports(
+    quality_domains: &[String],
+    links: &[DomainLinkSummary],
+) -> Vec<OutboundReport> {
+    quality_domains
+        .iter()
+        .map(|pattern| {
+            let mut targets: BTreeMap<&str, OutboundDomain> = BTreeMap::new();
+            for link in links.iter().filter(|link| {
+                matches_wildcard(pattern, &link.source_domain)
+                    && !matches_wildcard(pattern, &link.target_domain)
+            }) {
+                let target = targets
+                    .entry(&link.target_domain)
+                    .or_insert_with(|| OutboundDomain {
+                        domain: link.target_domain.clone(),
+                        links: 0,
+                        example_sources: Vec::new(),
+                    });
+                target.links += link.links;
+                target
+                    .example_sources
+                    .extend(link.example_sources.iter().cloned());
+            }
+
+            let mut targets: Vec<OutboundDomain> = targets
+                .into_values()
+                .map(|mut target| {
+                    target.example_sources.sort();
+                    target.example_sources.truncate(EXAMPLE_SOURCES);
+                    target
+                })
+                .collect();
+            targets.sort_by(|a, b| b.links.cmp(&a.links).then_with(|| a.domain.cmp(&b.domain)));
+
+            OutboundReport {
+                quality_domain: pattern.clone(),
+                targets,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(source: &str, target: &str, links: u64, sources: &[&str]) -> DomainLinkSummary {
+        DomainLinkSummary {
+            source_domain: source.to_string(),
+            target_domain: target.to_string(),
+            links,
+            example_sources: sources.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_outbound_reports() {
+        let links = vec![
+            link(
+                "blog.example.com",
+                "other.net",
+                2,
+                &["https://blog.example.com/a"],
+            ),
+            link(
+                "www.example.com",
+                "other.net",
+                3,
+                &["https://www.example.com/"],
+            ),
+            link(
+                "www.example.com",
+                "blog.example.com",
+                9,
+                &["https://www.example.com/"],
+            ),
+            link(
+                "www.example.com",
+                "ads.net",
+                4,
+                &["https://www.example.com/x"],
+            ),
+            link("other.net", "ads.net", 1, &["https://other.net/"]),
+        ];
+
+        let reports = build_outbound_reports(&["*.example.com".to_string()], &links);
+        assert_eq!(reports.len(), 1);
+
+        let targets = &reports[0].targets;
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].domain, "other.net");
+        assert_eq!(targets[0].links, 5);
+        assert_eq!(
+            targets[0].example_sources,
+            vec!["https://blog.example.com/a", "https://www.example.com/"]
+        );
+        assert_eq!(targets[1].domain, "ads.net");
+        assert_eq!(targets[1].links, 4);
+    }
+
+    #[test]
+    fn test_quality_domain_without_links() {
+        let reports = build_outbound_reports(&["quiet.org".to_string()], &[]);
+        assert!(reports[0].targets.is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! Parquet export of the crawl data
+//!
+//! Writes pages, page-level links and per-domain summaries as three Parquet
+//! files, so crawl results can be loaded directly into DuckDB, Spark or
+//! pandas. Pages are keyed by `id`, which the `from_page_id`/`to_page_id`
+//! columns of the links refer to. Like the link graph, the links leave out
+//! those of pages flagged as copies of an error template. Timestamps are kept
+//! as the RFC 3339 strings stored in the database.
+//!
+//! Only available when built with the `parquet` feature.
+
+use crate::output::traits::{OutputError, OutputResult};
+use crate::state::PageState;
+use crate::storage::{DomainSummary, LinkRecord, PageRecord, Storage};
+use arrow_array::{
+    ArrayRef, Int64Array, RecordBatch, StringArray, UInt16Array, UInt32Array, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fmt::Display;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows per row group of the written files
+const ROW_GROUP_ROWS: usize = 65_536;
+
+/// Rows written to each file by a Parquet export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParquetExport {
+    /// Rows of pages.parquet
+    pub pages: u64,
+    /// Rows of links.parquet
+    pub links: u64,
+    /// Rows of domains.parquet
+    pub domains: u64,
+}
+
+/// Exports pages, links and domains as Parquet files
+///
+/// Writes `pages.parquet`, `links.parquet` and `domains.parquet` into the
+/// directory, creating it if needed and replacing existing files.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `output_dir` - Directory the files are written to
+///
+/// # Returns
+///
+/// * `Ok(ParquetExport)` - The number of rows written to each file
+/// * `Err(OutputError)` - Storage could not be queried or a file not written
+pub fn export_parquet(storage: &dyn Storage, output_dir: &Path) -> OutputResult<ParquetExport> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut pages = Vec::new();
+    for state in PageState::all_states() {
+        pages.extend(storage.get_pages_by_state(state).map_err(storage_error)?);
+    }
+    pages.sort_by_key(|page| page.id);
+    let links = storage.get_graph_links().map_err(storage_error)?;
+    let domains = storage.get_domain_summaries().map_err(storage_error)?;
+
+    write_parquet(
+        &output_dir.join("pages.parquet"),
+        pages_schema(),
+        &pages,
+        page_columns,
+    )?;
+    write_parquet(
+        &output_dir.join("links.parquet"),
+        links_schema(),
+        &links,
+        link_columns,
+    )?;
+    write_parquet(
+        &output_dir.join("domains.parquet"),
+        domains_schema(),
+        &domains,
+        domain_columns,
+    )?;
+
+    Ok(ParquetExport {
+        pages: pages.len() as u64,
+        links: links.len() as u64,
+        domains: domains.len() as u64,
+    })
+}
+
+fn pages_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("url", DataType::Utf8, false),
+        Field::new("domain", DataType::Utf8, false),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, true),
+        Field::new("status_code", DataType::UInt16, true),
+        Field::new("content_type", DataType::Utf8, true),
+        Field::new("last_modified", DataType::Utf8, true),
+        Field::new("visited_at", DataType::Utf8, true),
+        Field::new("discovered_at", DataType::Utf8, false),
+        Field::new("discovered_run", DataType::Int64, false),
+        Field::new("error_message", DataType::Utf8, true),
+        Field::new("retry_count", DataType::UInt32, false),
+    ]))
+}
+
+fn page_columns(pages: &[PageRecord]) -> Vec<ArrayRef> {
+    vec![
+        Arc::new(Int64Array::from_iter_values(pages.iter().map(|p| p.id))),
+        Arc::new(StringArray::from_iter_values(pages.iter().map(|p| &p.url))),
+        Arc::new(StringArray::from_iter_values(
+            pages.iter().map(|p| &p.domain),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            pages.iter().map(|p| p.state.to_db_string()),
+        )),
+        Arc::new(StringArray::from_iter(
+            pages.iter().map(|p| p.title.as_deref()),
+        )),
+        Arc::new(UInt16Array::from_iter(pages.iter().map(|p| p.status_code))),
+        Arc::new(StringArray::from_iter(
+            pages.iter().map(|p| p.content_type.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter(
+            pages.iter().map(|p| p.last_modified.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter(
+            pages.iter().map(|p| p.visited_at.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            pages.iter().map(|p| &p.discovered_at),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            pages.iter().map(|p| p.discovered_run),
+        )),
+        Arc::new(StringArray::from_iter(
+            pages.iter().map(|p| p.error_message.as_deref()),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            pages.iter().map(|p| p.retry_count),
+        )),
+    ]
+}
+
+fn links_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("from_page_id", DataType::Int64, false),
+        Field::new("to_page_id", DataType::Int64, false),
+        Field::new("discovered_run", DataType::Int64, false),
+        Field::new("last_seen_run", DataType::Int64, false),
+    ]))
+}
+
+fn link_columns(links: &[LinkRecord]) -> Vec<ArrayRef> {
+    vec![
+        Arc::new(Int64Array::from_iter_values(
+            links.iter().map(|l| l.from_page_id),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            links.iter().map(|l| l.to_page_id),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            links.iter().map(|l| l.discovered_run),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            links.iter().map(|l| l.last_seen_run),
+        )),
+    ]
+}
+
+fn domains_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("domain", DataType::Utf8, false),
+        Field::new("pages", DataType::UInt64, false),
+        Field::new("pages_processed", DataType::UInt64, false),
+        Field::new("errors", DataType::UInt64, false),
+        Field::new("first_seen", DataType::Utf8, false),
+        Field::new("last_seen", DataType::Utf8, false),
+    ]))
+}
+
+fn domain_columns(domains: &[DomainSummary]) -> Vec<ArrayRef> {
+    vec![
+        Arc::new(StringArray::from_iter_values(
+            domains.iter().map(|d| &d.domain),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            domains.iter().map(|d| d.pages),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            domains.iter().map(|d| d.pages_processed),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            domains.iter().map(|d| d.errors),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            domains.iter().map(|d| &d.first_seen),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            domains.iter().map(|d| &d.last_seen),
+        )),
+    ]
+}
+
+/// Writes rows as a Snappy-compressed Parquet file, one row group per
+/// `ROW_GROUP_ROWS` rows
+fn write_parquet<T>(
+    path: &Path,
+    schema: SchemaRef,
+    rows: &[T],
+    columns: fn(&[T]) -> Vec<ArrayRef>,
+) -> OutputResult<()> {
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = File::create(path)?;
+    let mut writer =
+        ArrowWriter::try_new(file, schema.clone(), Some(properties)).map_err(format_error)?;
+
+    for chunk in rows.chunks(ROW_GROUP_ROWS) {
+        let batch = RecordBatch::try_new(schema.clone(), columns(chunk)).map_err(format_error)?;
+        writer.write(&batch).map_err(format_error)?;
+    }
+    writer.close().map_err(format_error)?;
+
+    Ok(())
+}
+
+fn storage_error(e: impl Display) -> OutputError {
+    OutputError::Storage(e.to_string())
+}
+
+fn format_error(e: impl Display) -> OutputError {
+    OutputError::Format(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SqliteStorage;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn read(path: &Path) -> Vec<RecordBatch> {
+        ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_export_parquet() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+        storage
+            .update_page_state(
+                home,
+                PageState::Processed,
+                Some("Home"),
+                Some(200),
+                Some("text/html"),
+                None,
+            )
+            .unwrap();
+        storage.insert_link(home, other, run_id).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("parquet");
+        let export = export_parquet(&storage, &output_dir).unwrap();
+        assert_eq!(
+            export,
+            ParquetExport {
+                pages: 2,
+                links: 1,
+                domains: 2
+            }
+        );
+
+        let pages = read(&output_dir.join("pages.parquet"));
+        assert_eq!(pages[0].num_rows(), 2);
+        assert_eq!(pages[0].schema(), pages_schema());
+        let urls = pages[0]
+            .column_by_name("url")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(urls.value(0), "https://a.com/");
+        let titles = pages[0]
+            .column_by_name("title")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(titles.value(0), "Home");
+        assert!(titles.is_null(1));
+
+        let links = read(&output_dir.join("links.parquet"));
+        let from = links[0]
+            .column_by_name("from_page_id")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(from.values(), &[home]);
+
+        let domains = read(&output_dir.join("domains.parquet"));
+        assert_eq!(domains[0].num_rows(), 2);
+    }
+}
@@ -0,0 +1,174 @@
+//! Crawl velocity from run progress snapshots
+//!
+//! The coordinator records a progress snapshot every few minutes while a run
+//! is crawling. The difference between consecutive snapshots gives the crawl
+//! rate over that interval; intervals in which no page was processed although
+//! URLs were still queued are flagged as stalls.
+
+use crate::storage::ProgressSnapshot;
+use chrono::{DateTime, FixedOffset};
+
+/// Crawl rate at one progress snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct VelocityPoint {
+    /// When the snapshot was recorded (RFC 3339)
+    pub recorded_at: String,
+
+    /// Minutes since the first snapshot of the run
+    pub elapsed_minutes: f64,
+
+    /// Total pages processed at this point
+    pub pages_processed: u64,
+
+    /// URLs waiting in the frontier at this point
+    pub frontier_size: u64,
+
+    /// Total pages in an error state at this point
+    pub error_count: u64,
+
+    /// Pages processed per minute since the previous snapshot
+    pub pages_per_minute: f64,
+
+    /// Whether nothing was processed since the previous snapshot although
+    /// URLs were queued
+    pub stalled: bool,
+
+    /// Completion forecast at this point, if one was available (seconds)
+    pub eta_seconds: Option<u64>,
+}
+
+/// Computes the crawl velocity between consecutive progress snapshots
+///
+/// # Arguments
+///
+/// * `snapshots` - Progress snapshots of a run, oldest first
+///
+/// # Returns
+///
+/// One point per snapshot. Snapshots with unparseable timestamps are skipped.
+pub fn crawl_velocity(snapshots: &[ProgressSnapshot]) -> Vec<VelocityPoint> {
+    let timed: Vec<(DateTime<FixedOffset>, &ProgressSnapshot)> = snapshots
+        .iter()
+        .filter_map(|s| Some((DateTime::parse_from_rfc3339(&s.recorded_at).ok()?, s)))
+        .collect();
+    let Some((start, _)) = timed.first().copied() else {
+        return Vec::new();
+    };
+
+    let mut points = Vec::with_capacity(timed.len());
+    let mut previous: Option<(DateTime<FixedOffset>, &ProgressSnapshot)> = None;
+
+    for (time, snapshot) in timed {
+        let (pages_per_minute, stalled) = match previous {
+            Some((prev_time, prev)) => {
+                let minutes = minutes_between(prev_time, time);
+                let processed = snapshot
+                    .pages_processed
+                    .saturating_sub(prev.pages_processed);
+                let rate = if minutes > 0.0 {
+                    processed as f64 / minutes
+                } else {
+                    0.0
+                };
+                (rate, processed == 0 && prev.frontier_size > 0)
+            }
+            None => (0.0, false),
+        };
+
+        points.push(VelocityPoint {
+            recorded_at: snapshot.recorded_at.clone(),
+            elapsed_minutes: minutes_between(start, time),
+            pages_processed: snapshot.pages_processed,
+            frontier_size: snapshot.frontier_size,
+            error_count: snapshot.error_count,
+            pages_per_minute,
+            stalled,
+            eta_seconds: snapshot.eta_seconds,
+        });
+        previous = Some((time, snapshot));
+    }
+
+    points
+}
+
+/// Picks at most `max` evenly spaced points, always keeping the last one
+///
+/// # Arguments
+///
+/// * `points` - The velocity points of a run
+/// * `max` - Maximum number of points to keep
+pub fn sample_velocity(points: &[VelocityPoint], max: usize) -> Vec<&VelocityPoint> {
+    if points.len() <= max {
+        return points.iter().collect();
+    }
+    if max < 2 {
+        return points.last().into_iter().take(max).collect();
+    }
+    let last = points.len() - 1;
+    (0..max).map(|i| &points[i * last / (max - 1)]).collect()
+}
+
+/// Fractional minutes between two timestamps
+fn minutes_between(from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> f64 {
+    (to - from).num_milliseconds() as f64 / 60_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(recorded_at: &str, pages_processed: u64, frontier_size: u64) -> ProgressSnapshot {
+        ProgressSnapshot {
+            run_id: 1,
+            recorded_at: recorded_at.to_string(),
+            pages_processed,
+            frontier_size,
+            error_count: 0,
+            eta_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_crawl_velocity() {
+        let points = crawl_velocity(&[
+            snapshot("2024-01-01T00:00:00+00:00", 0, 5),
+            snapshot("2024-01-01T00:05:00+00:00", 50, 40),
+            snapshot("2024-01-01T00:10:00+00:00", 50, 40),
+            snapshot("2024-01-01T00:15:00+00:00", 80, 0),
+        ]);
+
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0].pages_per_minute, 0.0);
+        assert_eq!(points[1].elapsed_minutes, 5.0);
+        assert_eq!(points[1].pages_per_minute, 10.0);
+        assert!(!points[1].stalled);
+        assert!(points[2].stalled);
+        assert_eq!(points[3].pages_per_minute, 6.0);
+        assert!(!points[3].stalled);
+    }
+
+    #[test]
+    fn test_crawl_velocity_skips_bad_timestamps() {
+        let points = crawl_velocity(&[
+            snapshot("not a time", 0, 5),
+            snapshot("2024-01-01T00:00:00+00:00", 10, 5),
+        ]);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].elapsed_minutes, 0.0);
+        assert!(crawl_velocity(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_sample_velocity() {
+        let points = crawl_velocity(
+            &(0..10)
+                .map(|i| snapshot(&format!("2024-01-01T00:{:02}:00+00:00", i), i * 10, 1))
+                .collect::<Vec<_>>(),
+        );
+
+        let sampled = sample_velocity(&points, 4);
+        let processed: Vec<u64> = sampled.iter().map(|p| p.pages_processed).collect();
+        assert_eq!(processed, vec![0, 30, 60, 90]);
+        assert_eq!(sample_velocity(&points, 20).len(), 10);
+    }
+}
@@ -5,6 +5,11 @@
 
 use crate::output::traits::{
     CrawlError, CrawlSummary, OutputError, OutputHandler, OutputResult, ProcessedPage,
+    TOP_ANOMALIES, TOP_DISAPPEARED_LINKS, TOP_FILTERED_URLS,
+};
+use crate::output::{
+    load_domain_breakdown, load_header_summary, load_most_retried, load_stub_domains,
+    load_technology_summary,
 };
 use crate::state::PageState;
 use crate::storage::{RunStatus, Storage};
@@ -58,6 +63,9 @@ impl OutputHandler for SqliteOutputHandler {
                 None,
             )
             .map_err(|e| OutputError::Storage(e.to_string()))?;
+        storage
+            .mark_page_visited(page_id, self.run_id)
+            .map_err(|e| OutputError::Storage(e.to_string()))?;
 
         // Record depths
         for (quality_origin, depth) in &page.depths {
@@ -179,62 +187,123 @@ impl OutputHandler for SqliteOutputHandler {
             .map_err(|e| OutputError::Storage(e.to_string()))?;
 
         // State breakdown
-        summary.pages_discovered = storage
-            .count_pages_by_state(PageState::Discovered)
+        let state_counts = storage
+            .get_state_counts()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_queued = storage
-            .count_pages_by_state(PageState::Queued)
+        let count = |state: PageState| state_counts.get(&state).copied().unwrap_or(0);
+        summary.pages_discovered = count(PageState::Discovered);
+        summary.pages_queued = count(PageState::Queued);
+        summary.pages_processed = count(PageState::Processed);
+        summary.pages_blacklisted = count(PageState::Blacklisted);
+        summary.pages_stubbed = count(PageState::Stubbed);
+        summary.pages_policy_denied = count(PageState::PolicyDenied);
+        summary.pages_dead_link = count(PageState::DeadLink);
+        summary.pages_unreachable = count(PageState::Unreachable);
+        summary.pages_rate_limited = count(PageState::RateLimited);
+        summary.pages_failed = count(PageState::Failed);
+        summary.pages_depth_exceeded = count(PageState::DepthExceeded);
+        summary.pages_request_limit_hit = count(PageState::RequestLimitHit);
+        summary.pages_content_mismatch = count(PageState::ContentMismatch);
+
+        // Error summary
+        summary.error_summary = state_counts
+            .iter()
+            .filter(|(state, _)| state.is_error())
+            .map(|(state, count)| (*state, *count))
+            .collect();
+        summary.total_errors = summary.error_summary.values().sum();
+
+        // Blacklisted and stubbed URLs
+        summary.top_blacklisted = storage
+            .get_blacklisted_urls(TOP_FILTERED_URLS, 0)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_processed = storage
-            .count_pages_by_state(PageState::Processed)
+        summary.top_stubbed = storage
+            .get_stubbed_urls(TOP_FILTERED_URLS, 0)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_blacklisted = storage
-            .count_pages_by_state(PageState::Blacklisted)
+
+        // Rate-limited domains
+        summary.rate_limited_domains = storage
+            .get_rate_limited_domains()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_stubbed = storage
-            .count_pages_by_state(PageState::Stubbed)
+
+        // Domains paused by the circuit breaker
+        summary.degraded_domains = storage
+            .get_degraded_domains()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_dead_link = storage
-            .count_pages_by_state(PageState::DeadLink)
+
+        // Domains slowed down after HTTP 429 responses
+        summary.slowed_domains = storage
+            .get_slowed_domains()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_unreachable = storage
-            .count_pages_by_state(PageState::Unreachable)
+
+        // Domains whose robots.txt could not be fetched
+        summary.robots_failures = storage
+            .get_robots_failures()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_rate_limited = storage
-            .count_pages_by_state(PageState::RateLimited)
+
+        // Response header values
+        summary.response_headers =
+            load_header_summary(&*storage).map_err(|e| OutputError::Storage(e.to_string()))?;
+
+        // Links by kind
+        summary.link_kinds = storage
+            .count_links_by_kind()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_failed = storage
-            .count_pages_by_state(PageState::Failed)
+
+        // Pages with anomalies
+        summary.anomaly_counts = storage
+            .count_page_anomalies()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_depth_exceeded = storage
-            .count_pages_by_state(PageState::DepthExceeded)
+        summary.anomalies = storage
+            .get_page_anomalies(TOP_ANOMALIES)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_request_limit_hit = storage
-            .count_pages_by_state(PageState::RequestLimitHit)
+
+        // Links gone from pages fetched again
+        summary.disappeared_link_count = storage
+            .count_disappeared_links(self.run_id)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.pages_content_mismatch = storage
-            .count_pages_by_state(PageState::ContentMismatch)
+        summary.disappeared_links = storage
+            .get_disappeared_links(self.run_id, TOP_DISAPPEARED_LINKS)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
 
-        // Error summary
-        summary.error_summary = storage
-            .get_error_summary()
+        // Error page templates left out of the graph
+        summary.error_templates = storage
+            .get_error_templates()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.total_errors = summary.error_summary.values().sum();
 
-        // Blacklisted and stubbed URLs
-        summary.top_blacklisted = storage
-            .get_blacklisted_urls()
+        // Detected technologies
+        summary.technologies =
+            load_technology_summary(&*storage).map_err(|e| OutputError::Storage(e.to_string()))?;
+        summary.domain_technologies = storage
+            .get_domain_technologies()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
-        summary.top_stubbed = storage
-            .get_stubbed_urls()
+
+        // Harvested contacts
+        summary.contacts = storage
+            .get_contacts()
             .map_err(|e| OutputError::Storage(e.to_string()))?;
 
-        // Rate-limited domains
-        summary.rate_limited_domains = storage
-            .get_rate_limited_domains()
+        // Discovered domains promoted to a deeper crawl
+        summary.promotions = storage
+            .get_domain_promotions()
+            .map_err(|e| OutputError::Storage(e.to_string()))?;
+
+        // Failing pages retried the most
+        summary.most_retried =
+            load_most_retried(&*storage).map_err(|e| OutputError::Storage(e.to_string()))?;
+
+        // Depth breakdown and domain lists
+        load_domain_breakdown(&*storage, &mut summary)
             .map_err(|e| OutputError::Storage(e.to_string()))?;
 
+        // Stub domains, ranked by the quality pages referencing them
+        summary.stub_domains = load_stub_domains(
+            &*storage,
+            &summary.quality_domains,
+            summary.run_scoped.then_some(summary.run_id),
+        )
+        .map_err(|e| OutputError::Storage(e.to_string()))?;
+
         Ok(summary)
     }
 
@@ -298,6 +367,36 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_generate_summary_domain_breakdown() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+
+        for (url, domain, depth) in [
+            ("https://example.com/", "example.com", 0),
+            ("https://blog.example.com/", "blog.example.com", 1),
+            ("https://other.net/", "other.net", 2),
+        ] {
+            let page_id = storage.insert_or_get_page(url, domain, run_id).unwrap();
+            storage
+                .upsert_depth(page_id, "*.example.com", depth)
+                .unwrap();
+        }
+
+        let storage_arc: Arc<Mutex<dyn Storage>> = Arc::new(Mutex::new(storage));
+        let handler = SqliteOutputHandler::new(storage_arc, run_id);
+        let summary = handler.generate_summary().unwrap();
+
+        // Without a stored run configuration the depth origins are used
+        assert_eq!(summary.quality_domains, vec!["*.example.com"]);
+        assert_eq!(
+            summary.configured_domains,
+            vec!["blog.example.com", "example.com"]
+        );
+        assert_eq!(summary.discovered_domains.len(), 3);
+        assert_eq!(summary.depth_breakdown.get(&2), Some(&1));
+    }
+
     #[test]
     fn test_finalize() {
         let storage = SqliteStorage::new_in_memory().unwrap();
@@ -3,10 +3,15 @@
 //! This module provides functionality for extracting and displaying
 //! crawl statistics from the storage layer.
 
+use crate::config::Config;
+use crate::crawler::format_eta;
 use crate::state::PageState;
-use crate::storage::Storage;
+use crate::storage::{
+    DomainSummary, ProgressSnapshot, RunConfigRecord, RunRecord, RunStatus, Storage,
+};
 use crate::SumiError;
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Crawl statistics summary
 #[derive(Debug, Clone)]
@@ -28,6 +33,94 @@ pub struct CrawlStatistics {
 
     /// Domains that were rate limited
     pub rate_limited_domains: Vec<String>,
+
+    /// Configuration of the latest run, if one was recorded
+    pub run_config: Option<RunConfigSummary>,
+
+    /// Most recent progress snapshot of the latest run, if it is still running
+    pub latest_progress: Option<ProgressSnapshot>,
+
+    /// The run the statistics are limited to; `None` when they cover all runs
+    pub scope: Option<RunScope>,
+
+    /// Domains with the most pages (up to `TOP_DOMAINS`); empty for run-scoped statistics
+    pub top_domains: Vec<DomainSummary>,
+}
+
+/// Number of domains listed in the top domains section
+pub const TOP_DOMAINS: usize = 10;
+
+/// Per-run counts for statistics scoped to a single run
+#[derive(Debug, Clone)]
+pub struct RunScope {
+    /// The run the statistics are limited to
+    pub run_id: i64,
+
+    /// Pages first discovered during the run
+    pub pages_discovered: u64,
+
+    /// Pages fetched during the run
+    pub pages_visited: u64,
+
+    /// Links first discovered during the run
+    pub links_discovered: u64,
+}
+
+/// The limits and domain lists a run was started with
+#[derive(Debug, Clone)]
+pub struct RunConfigSummary {
+    /// The run the configuration belongs to
+    pub run_id: i64,
+
+    /// Hash of the serialized configuration
+    pub config_hash: String,
+
+    /// Maximum crawl depth
+    pub max_depth: u32,
+
+    /// Maximum number of requests per domain
+    pub max_domain_requests: u32,
+
+    /// Maximum number of requests per discovered domain
+    pub max_discovered_domain_requests: u32,
+
+    /// Maximum number of concurrent page fetches
+    pub max_concurrent_pages_open: u32,
+
+    /// Minimum time between requests to the same domain (milliseconds)
+    pub per_domain_delay_ms: u64,
+
+    /// Number of quality domain entries
+    pub quality_domains: usize,
+
+    /// Number of blacklist entries
+    pub blacklisted_domains: usize,
+
+    /// Number of stub entries
+    pub stubbed_domains: usize,
+}
+
+impl RunConfigSummary {
+    /// Summarizes a stored run configuration
+    ///
+    /// # Returns
+    ///
+    /// `None` if the stored configuration can no longer be parsed
+    pub fn from_record(record: &RunConfigRecord) -> Option<Self> {
+        let config: Config = toml::from_str(&record.config_toml).ok()?;
+        Some(Self {
+            run_id: record.run_id,
+            config_hash: record.config_hash.clone(),
+            max_depth: config.crawler.max_depth,
+            max_domain_requests: config.crawler.max_domain_requests,
+            max_discovered_domain_requests: config.crawler.max_discovered_domain_requests,
+            max_concurrent_pages_open: config.crawler.max_concurrent_pages_open,
+            per_domain_delay_ms: config.crawler.per_domain_delay_ms,
+            quality_domains: config.quality.len(),
+            blacklisted_domains: config.blacklist.len(),
+            stubbed_domains: config.stub.len(),
+        })
+    }
 }
 
 /// Loads statistics from storage
@@ -50,37 +143,29 @@ pub fn load_statistics(storage: &dyn Storage) -> Result<CrawlStatistics, SumiErr
     // Get total links
     let total_links = storage.count_links()?;
 
-    // Get error summary (includes all states with errors)
-    let error_summary = storage.get_error_summary()?;
-
-    // Calculate pages by state
-    let mut pages_by_state = HashMap::new();
-
-    // Count each state
-    for state in [
-        PageState::Discovered,
-        PageState::Queued,
-        PageState::Fetching,
-        PageState::Processed,
-        PageState::Blacklisted,
-        PageState::Stubbed,
-        PageState::DeadLink,
-        PageState::Unreachable,
-        PageState::RateLimited,
-        PageState::Failed,
-        PageState::DepthExceeded,
-        PageState::RequestLimitHit,
-        PageState::ContentMismatch,
-    ] {
-        let count = storage.count_pages_by_state(state)?;
-        if count > 0 {
-            pages_by_state.insert(state, count);
-        }
-    }
+    // Page counts of every state, read from the aggregates the storage layer maintains
+    let pages_by_state = storage.get_state_counts()?;
+
+    // Error summary (includes all states with errors)
+    let error_summary = pages_by_state
+        .iter()
+        .filter(|(state, _)| state.is_error())
+        .map(|(state, count)| (*state, *count))
+        .collect();
 
     // Get rate limited domains
     let rate_limited_domains = storage.get_rate_limited_domains()?;
 
+    // Domain summaries are kept up to date by the storage layer
+    let mut top_domains = storage.get_domain_summaries()?;
+    top_domains.truncate(TOP_DOMAINS);
+
+    // Get the configuration and forecast of the latest run
+    let (run_config, latest_progress) = match storage.get_latest_run()? {
+        Some(run) => load_run_details(storage, &run)?,
+        None => (None, None),
+    };
+
     Ok(CrawlStatistics {
         total_pages,
         pages_by_state,
@@ -88,21 +173,114 @@ pub fn load_statistics(storage: &dyn Storage) -> Result<CrawlStatistics, SumiErr
         total_links,
         error_summary,
         rate_limited_domains,
+        run_config,
+        latest_progress,
+        scope: None,
+        top_domains,
+    })
+}
+
+/// Loads statistics limited to the pages and links of a single run
+///
+/// Pages count towards the run if they were first discovered or fetched
+/// during it; their states are the ones they are in now.
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend to query
+/// * `run_id` - The run to report on
+///
+/// # Returns
+///
+/// * `Ok(CrawlStatistics)` - Successfully loaded statistics
+/// * `Err(SumiError)` - The run does not exist or the query failed
+pub fn load_run_statistics(
+    storage: &dyn Storage,
+    run_id: i64,
+) -> Result<CrawlStatistics, SumiError> {
+    let run = storage.get_run(run_id)?;
+
+    let pages_by_state = storage.get_run_state_counts(run_id)?;
+    let total_pages = pages_by_state.values().sum();
+    let error_summary = pages_by_state
+        .iter()
+        .filter(|(state, _)| state.is_error())
+        .map(|(state, count)| (*state, *count))
+        .collect();
+
+    let domains = storage.get_run_domains(run_id)?;
+    let rate_limited_domains = storage
+        .get_rate_limited_domains()?
+        .into_iter()
+        .filter(|domain| domains.binary_search(domain).is_ok())
+        .collect();
+
+    let links_discovered = storage.count_links_discovered_in_run(run_id)?;
+    let (run_config, latest_progress) = load_run_details(storage, &run)?;
+
+    Ok(CrawlStatistics {
+        total_pages,
+        pages_by_state,
+        unique_domains: domains.len() as u64,
+        total_links: links_discovered,
+        error_summary,
+        rate_limited_domains,
+        run_config,
+        latest_progress,
+        scope: Some(RunScope {
+            run_id,
+            pages_discovered: storage.count_pages_discovered_in_run(run_id)?,
+            pages_visited: storage.count_pages_visited_in_run(run_id)?,
+            links_discovered,
+        }),
+        top_domains: Vec::new(),
     })
 }
 
+/// Loads the stored configuration of a run and, while it is still running,
+/// its most recent progress snapshot
+fn load_run_details(
+    storage: &dyn Storage,
+    run: &RunRecord,
+) -> Result<(Option<RunConfigSummary>, Option<ProgressSnapshot>), SumiError> {
+    let run_config = storage
+        .get_run_config(run.id)?
+        .as_ref()
+        .and_then(RunConfigSummary::from_record);
+    let latest_progress = if run.status == RunStatus::Running {
+        storage.get_run_progress(run.id)?.pop()
+    } else {
+        None
+    };
+    Ok((run_config, latest_progress))
+}
+
 /// Prints statistics to stdout in a formatted manner
 ///
 /// # Arguments
 ///
 /// * `stats` - The statistics to display
 pub fn print_statistics(stats: &CrawlStatistics) {
-    println!("=== Crawl Statistics ===\n");
+    match &stats.scope {
+        Some(scope) => {
+            println!("=== Crawl Statistics (run {}) ===\n", scope.run_id);
+
+            println!("Overview:");
+            println!("  Pages discovered in run: {}", scope.pages_discovered);
+            println!("  Pages visited in run: {}", scope.pages_visited);
+            println!("  Pages touched by run: {}", stats.total_pages);
+            println!("  Unique domains: {}", stats.unique_domains);
+            println!("  Links discovered in run: {}", scope.links_discovered);
+        }
+        None => {
+            println!("=== Crawl Statistics ===\n");
 
-    println!("Overview:");
-    println!("  Total pages discovered: {}", stats.total_pages);
-    println!("  Unique domains: {}", stats.unique_domains);
-    println!("  Total links found: {}", stats.total_links);
+            println!("Overview:");
+            println!("  Total pages discovered: {}", stats.total_pages);
+            println!("  Unique domains: {}", stats.unique_domains);
+            println!("  Total links found: {}", stats.total_links);
+        }
+    }
     println!();
 
     println!("Pages by State:");
@@ -131,6 +309,21 @@ pub fn print_statistics(stats: &CrawlStatistics) {
         println!();
     }
 
+    if !stats.top_domains.is_empty() {
+        println!("Top Domains:");
+        for summary in &stats.top_domains {
+            println!(
+                "  {}: {} pages, {} processed, {} errors (last seen {})",
+                summary.domain,
+                summary.pages,
+                summary.pages_processed,
+                summary.errors,
+                summary.last_seen
+            );
+        }
+        println!();
+    }
+
     if !stats.rate_limited_domains.is_empty() {
         println!(
             "Rate Limited Domains ({}):",
@@ -142,6 +335,39 @@ pub fn print_statistics(stats: &CrawlStatistics) {
         println!();
     }
 
+    if let Some(config) = &stats.run_config {
+        println!("Run Configuration (run {}):", config.run_id);
+        println!("  Config hash: {}", config.config_hash);
+        println!("  Max depth: {}", config.max_depth);
+        println!("  Max domain requests: {}", config.max_domain_requests);
+        println!(
+            "  Max discovered domain requests: {}",
+            config.max_discovered_domain_requests
+        );
+        println!(
+            "  Max concurrent pages: {}",
+            config.max_concurrent_pages_open
+        );
+        println!("  Per-domain delay: {}ms", config.per_domain_delay_ms);
+        println!(
+            "  Domain lists: {} quality, {} blacklisted, {} stubbed",
+            config.quality_domains, config.blacklisted_domains, config.stubbed_domains
+        );
+        println!();
+    }
+
+    if let Some(progress) = &stats.latest_progress {
+        println!("Run In Progress (as of {}):", progress.recorded_at);
+        println!("  Pages processed: {}", progress.pages_processed);
+        println!("  URLs in frontier: {}", progress.frontier_size);
+        println!("  Errors: {}", progress.error_count);
+        match progress.eta_seconds {
+            Some(eta) => println!("  ETA: {}", format_eta(Duration::from_secs(eta))),
+            None => println!("  ETA: not yet available"),
+        }
+        println!();
+    }
+
     // Calculate success rate
     let processed = stats
         .pages_by_state
@@ -176,10 +402,98 @@ mod tests {
             total_links: 500,
             error_summary: HashMap::new(),
             rate_limited_domains: vec![],
+            run_config: None,
+            latest_progress: None,
+            scope: None,
+            top_domains: vec![],
         };
 
         assert_eq!(stats.total_pages, 150);
         assert_eq!(stats.unique_domains, 10);
         assert_eq!(stats.total_links, 500);
     }
+
+    #[test]
+    fn test_statistics_include_run_config() {
+        use crate::config::test_config_toml;
+        use crate::storage::SqliteStorage;
+
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let config = test_config_toml(
+            r#"
+[[quality]]
+domain = "example.com"
+seeds = ["https://example.com/"]
+
+[[blacklist]]
+domain = "ads.example.net"
+
+[[blacklist]]
+domain = "tracker.example.org"
+"#,
+        );
+        storage.save_run_config(run_id, "hash", &config).unwrap();
+
+        let stats = load_statistics(&storage).unwrap();
+        let summary = stats.run_config.unwrap();
+        assert_eq!(summary.run_id, run_id);
+        assert_eq!(summary.max_depth, 3);
+        assert_eq!(summary.max_domain_requests, 500);
+        assert_eq!(summary.quality_domains, 1);
+        assert_eq!(summary.blacklisted_domains, 2);
+        assert_eq!(summary.stubbed_domains, 0);
+        assert!(stats.latest_progress.is_none());
+
+        storage
+            .record_run_progress(&ProgressSnapshot {
+                run_id,
+                recorded_at: "2024-01-01T00:05:00+00:00".to_string(),
+                pages_processed: 40,
+                frontier_size: 12,
+                error_count: 1,
+                eta_seconds: Some(900),
+            })
+            .unwrap();
+        let stats = load_statistics(&storage).unwrap();
+        assert_eq!(stats.latest_progress.unwrap().eta_seconds, Some(900));
+    }
+
+    #[test]
+    fn test_run_statistics() {
+        use crate::storage::SqliteStorage;
+
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://example.com/", "example.com", first)
+            .unwrap();
+        let second = storage.create_run("hash").unwrap();
+        let dead = storage
+            .insert_or_get_page("https://other.org/gone", "other.org", second)
+            .unwrap();
+        storage.insert_link(home, dead, second).unwrap();
+        storage
+            .update_page_state(dead, PageState::DeadLink, None, Some(404), None, None)
+            .unwrap();
+
+        let stats = load_run_statistics(&storage, second).unwrap();
+        let scope = stats.scope.unwrap();
+        assert_eq!(scope.run_id, second);
+        assert_eq!(scope.pages_discovered, 1);
+        assert_eq!(scope.pages_visited, 0);
+        assert_eq!(scope.links_discovered, 1);
+        assert_eq!(stats.total_pages, 1);
+        assert_eq!(stats.unique_domains, 1);
+        assert_eq!(stats.error_summary.get(&PageState::DeadLink), Some(&1));
+
+        assert!(stats.top_domains.is_empty());
+
+        let all = load_statistics(&storage).unwrap();
+        assert_eq!(all.total_pages, 2);
+        assert_eq!(all.top_domains.len(), 2);
+        assert_eq!(all.top_domains[1].domain, "other.org");
+        assert_eq!(all.top_domains[1].errors, 1);
+        assert!(load_run_statistics(&storage, 99).is_err());
+    }
 }
@@ -0,0 +1,153 @@
+//! Stub domains ranked by the pages referencing them
+//!
+//! Stubbed URLs are noted but never visited, so they never become pages.
+//! Grouping them by domain, and counting the distinct pages (and quality
+//! pages in particular) that link to each domain, shows which external
+//! services the crawled sites depend on most.
+
+use crate::storage::Storage;
+use crate::url::{extract_domain, matches_wildcard};
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+/// Number of stub domains listed in a summary
+pub const TOP_STUB_DOMAINS: usize = 20;
+
+/// A stubbed domain and the pages referencing it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StubDomain {
+    /// The stubbed domain
+    pub domain: String,
+
+    /// Distinct stubbed URLs on the domain
+    pub urls: u64,
+
+    /// Distinct pages linking to the domain
+    pub referring_pages: u64,
+
+    /// Referring pages on a quality domain
+    pub quality_referrers: u64,
+}
+
+/// Loads the stub domains using the data in storage
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+/// * `quality_domains` - The quality domain patterns of the run
+/// * `run_id` - Only include references recorded during this run
+///
+/// # Returns
+///
+/// * `Ok(Vec<StubDomain>)` - Every stub domain, most quality referrers first
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_stub_domains(
+    storage: &dyn Storage,
+    quality_domains: &[String],
+    run_id: Option<i64>,
+) -> Result<Vec<StubDomain>, SumiError> {
+    let references = storage.get_stub_references(run_id)?;
+    Ok(build_stub_domains(&references, quality_domains))
+}
+
+/// Groups stubbed URL references by the stubbed URL's domain
+///
+/// Domains are ranked by quality referrers, then by all referring pages.
+/// References with an unparseable URL are skipped.
+///
+/// # Arguments
+///
+/// * `references` - Distinct (stubbed URL, referrer URL) pairs
+/// * `quality_domains` - The quality domain patterns of the run
+pub fn build_stub_domains(
+    references: &[(String, String)],
+    quality_domains: &[String],
+) -> Vec<StubDomain> {
+    #[derive(Default)]
+    struct References<'a> {
+        urls: BTreeSet<&'a str>,
+        referrers: BTreeSet<&'a str>,
+        quality_referrers: BTreeSet<&'a str>,
+    }
+
+    let domain_of = |url: &str| Url::parse(url).ok().as_ref().and_then(extract_domain);
+
+    let mut by_domain: BTreeMap<String, References> = BTreeMap::new();
+    for (url, referrer) in references {
+        let Some(domain) = domain_of(url) else {
+            continue;
+        };
+        let entry = by_domain.entry(domain).or_default();
+        entry.urls.insert(url);
+        entry.referrers.insert(referrer);
+
+        let is_quality = domain_of(referrer).is_some_and(|referrer_domain| {
+            quality_domains
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, &referrer_domain))
+        });
+        if is_quality {
+            entry.quality_referrers.insert(referrer);
+        }
+    }
+
+    let mut domains: Vec<StubDomain> = by_domain
+        .into_iter()
+        .map(|(domain, references)| StubDomain {
+            domain,
+            urls: references.urls.len() as u64,
+            referring_pages: references.referrers.len() as u64,
+            quality_referrers: references.quality_referrers.len() as u64,
+        })
+        .collect();
+    domains.sort_by(|a, b| {
+        b.quality_referrers
+            .cmp(&a.quality_referrers)
+            .then(b.referring_pages.cmp(&a.referring_pages))
+            .then_with(|| a.domain.cmp(&b.domain))
+    });
+    domains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(url: &str, referrer: &str) -> (String, String) {
+        (url.to_string(), referrer.to_string())
+    }
+
+    #[test]
+    fn test_build_stub_domains() {
+        let references = vec![
+            reference("https://cdn.net/a.js", "https://other.org/"),
+            reference("https://cdn.net/a.js", "https://other.org/x"),
+            reference("https://cdn.net/b.js", "https://other.org/"),
+            reference("https://social.net/share", "https://blog.example.com/"),
+            reference("https://social.net/share", "https://blog.example.com/2"),
+            reference("https://social.net/me", "https://other.org/"),
+            reference("not a url", "https://blog.example.com/"),
+        ];
+
+        let domains = build_stub_domains(&references, &["*.example.com".to_string()]);
+        assert_eq!(
+            domains,
+            vec![
+                StubDomain {
+                    domain: "social.net".to_string(),
+                    urls: 2,
+                    referring_pages: 3,
+                    quality_referrers: 2,
+                },
+                StubDomain {
+                    domain: "cdn.net".to_string(),
+                    urls: 2,
+                    referring_pages: 2,
+                    quality_referrers: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Technology summary
+//!
+//! While crawling, the platforms behind each page (WordPress, Shopify,
+//! MediaWiki, ...) are recognized from its generator tag, script URLs and
+//! response headers and recorded per domain. The summary counts the domains
+//! running each technology.
+
+use crate::output::headers::count_crawled_domains;
+use crate::storage::Storage;
+use crate::SumiError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Domains running one technology
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TechnologyShare {
+    /// The technology name
+    pub technology: String,
+
+    /// Domains it was detected on
+    pub domains: u64,
+
+    /// Fraction of the domains with processed pages (0.0 to 1.0)
+    pub share: f64,
+}
+
+/// Loads the number of domains running each detected technology
+///
+/// # Arguments
+///
+/// * `storage` - The storage backend containing crawl data
+///
+/// # Returns
+///
+/// * `Ok(Vec<TechnologyShare>)` - Every detected technology, most domains first
+/// * `Err(SumiError)` - Failed to query storage
+pub fn load_technology_summary(storage: &dyn Storage) -> Result<Vec<TechnologyShare>, SumiError> {
+    let technologies = storage.get_domain_technologies()?;
+    Ok(build_technology_summary(
+        &technologies,
+        count_crawled_domains(storage)?,
+    ))
+}
+
+/// Counts the domains per technology
+///
+/// # Arguments
+///
+/// * `technologies` - Domain -> detected technologies
+/// * `crawled_domains` - Domains with at least one processed page
+pub fn build_technology_summary(
+    technologies: &BTreeMap<String, Vec<String>>,
+    crawled_domains: u64,
+) -> Vec<TechnologyShare> {
+    let mut domains: HashMap<&str, u64> = HashMap::new();
+    for technology in technologies.values().flatten() {
+        *domains.entry(technology).or_default() += 1;
+    }
+
+    let mut summary: Vec<TechnologyShare> = domains
+        .into_iter()
+        .map(|(technology, domains)| TechnologyShare {
+            technology: technology.to_string(),
+            domains,
+            share: if crawled_domains == 0 {
+                0.0
+            } else {
+                domains as f64 / crawled_domains as f64
+            },
+        })
+        .collect();
+    summary.sort_by(|a, b| {
+        b.domains
+            .cmp(&a.domains)
+            .then_with(|| a.technology.cmp(&b.technology))
+    });
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_technology_summary() {
+        let technologies: BTreeMap<String, Vec<String>> = [
+            ("a.com", vec!["PHP", "WordPress"]),
+            ("b.com", vec!["WordPress"]),
+            ("c.com", vec!["Shopify"]),
+        ]
+        .into_iter()
+        .map(|(domain, found)| {
+            (
+                domain.to_string(),
+                found.into_iter().map(str::to_string).collect(),
+            )
+        })
+        .collect();
+
+        let summary = build_technology_summary(&technologies, 5);
+        let names: Vec<&str> = summary.iter().map(|t| t.technology.as_str()).collect();
+        assert_eq!(names, vec!["WordPress", "PHP", "Shopify"]);
+        assert_eq!(summary[0].domains, 2);
+        assert_eq!(summary[0].share, 0.4);
+    }
+}
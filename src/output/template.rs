@@ -0,0 +1,187 @@
+//! User-supplied report templates
+//!
+//! Instead of the built-in layouts, the markdown summary and the HTML report
+//! can be rendered from a Handlebars template (`summary-template` and
+//! `html-template` in `[output]`). The template context is the serialized
+//! `CrawlSummary`, plus a few derived values:
+//!
+//! - `success_rate`, `error_rate` - percentages of terminal pages
+//! - `total_terminal_pages` - pages that reached a terminal state
+//! - `depths` - `[depth, pages]` pairs sorted by depth
+//! - `generated_at` - when the report was rendered (RFC 3339)
+//!
+//! Values are escaped for the output format: markdown characters in markdown
+//! templates, HTML entities in HTML templates. Two helpers are registered:
+//! `{{fixed value digits}}` formats a number with a fixed number of decimals
+//! and `{{duration seconds}}` formats a duration like "3h 12m".
+
+use crate::crawler::format_eta;
+use crate::output::markdown::escape_markdown;
+use crate::output::traits::{CrawlSummary, OutputError, OutputResult};
+use handlebars::{handlebars_helper, Handlebars};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Output format a template renders to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateFormat {
+    /// Markdown; values are markdown-escaped
+    Markdown,
+
+    /// HTML; values are HTML-escaped
+    Html,
+}
+
+/// Data exposed to report templates
+#[derive(Debug, Serialize)]
+struct TemplateContext<'a> {
+    #[serde(flatten)]
+    summary: &'a CrawlSummary,
+    success_rate: f64,
+    error_rate: f64,
+    total_terminal_pages: u64,
+    depths: Vec<(u32, u64)>,
+    generated_at: String,
+}
+
+handlebars_helper!(fixed: |value: f64, digits: u64| format!("{:.*}", digits as usize, value));
+handlebars_helper!(duration: |seconds: u64| format_eta(Duration::from_secs(seconds)));
+
+/// Renders a crawl summary with a template
+///
+/// # Arguments
+///
+/// * `template` - The Handlebars template source
+/// * `summary` - The crawl summary data
+/// * `format` - The format the template produces, which selects the escaping
+///
+/// # Returns
+///
+/// * `Ok(String)` - The rendered report
+/// * `Err(OutputError)` - The template is invalid or failed to render
+pub fn render_template(
+    template: &str,
+    summary: &CrawlSummary,
+    format: TemplateFormat,
+) -> OutputResult<String> {
+    let mut handlebars = Handlebars::new();
+    if format == TemplateFormat::Markdown {
+        handlebars.register_escape_fn(escape_markdown);
+    }
+    handlebars.register_helper("fixed", Box::new(fixed));
+    handlebars.register_helper("duration", Box::new(duration));
+
+    let mut depths: Vec<(u32, u64)> = summary
+        .depth_breakdown
+        .iter()
+        .map(|(depth, count)| (*depth, *count))
+        .collect();
+    depths.sort_unstable();
+
+    let context = TemplateContext {
+        summary,
+        success_rate: summary.success_rate(),
+        error_rate: summary.error_rate(),
+        total_terminal_pages: summary.total_terminal_pages(),
+        depths,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    handlebars
+        .render_template(template, &context)
+        .map_err(|e| OutputError::Format(format!("template: {}", e)))
+}
+
+/// Renders a crawl summary with a template file and writes the result
+///
+/// # Arguments
+///
+/// * `template_path` - Path to the Handlebars template
+/// * `summary` - The crawl summary data
+/// * `format` - The format the template produces
+/// * `output_path` - Path where the report should be written
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully wrote the report
+/// * `Err(OutputError)` - The template could not be read or rendered, or the
+///   report could not be written
+pub fn generate_templated_report(
+    template_path: &Path,
+    summary: &CrawlSummary,
+    format: TemplateFormat,
+    output_path: &Path,
+) -> OutputResult<()> {
+    let template = fs::read_to_string(template_path)
+        .map_err(|e| OutputError::Format(format!("template {}: {}", template_path.display(), e)))?;
+    let report = render_template(&template, summary, format)?;
+    fs::write(output_path, report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// The example template shipped with the repository
+    const EXAMPLE_TEMPLATE: &str = include_str!("../../examples/summary_template.md.hbs");
+
+    fn test_summary() -> CrawlSummary {
+        CrawlSummary {
+            run_id: 3,
+            status: "completed".to_string(),
+            duration_seconds: Some(3 * 3600 + 12 * 60),
+            pages_processed: 5,
+            pages_failed: 1,
+            depth_breakdown: HashMap::from([(2, 4), (0, 1), (1, 3)]),
+            top_blacklisted: vec![("https://ads.example.net/a|b".to_string(), 7)],
+            ..CrawlSummary::default()
+        }
+    }
+
+    #[test]
+    fn test_render_summary_fields() {
+        let rendered = render_template(
+            "Run {{run_id}} ({{status}}) took {{duration duration_seconds}}, \
+             {{fixed success_rate 1}}% ok{{#each depths}} d{{this.[0]}}={{this.[1]}}{{/each}}",
+            &test_summary(),
+            TemplateFormat::Markdown,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "Run 3 (completed) took 3h 12m, 83.3% ok d0=1 d1=3 d2=4"
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_for_format() {
+        let template = "{{#each top_blacklisted}}{{this.[0]}}{{/each}}";
+        let summary = CrawlSummary {
+            top_blacklisted: vec![("https://a.com/<x>|y".to_string(), 1)],
+            ..CrawlSummary::default()
+        };
+
+        let markdown = render_template(template, &summary, TemplateFormat::Markdown).unwrap();
+        assert_eq!(markdown, "https://a.com/\\<x\\>\\|y");
+        let html = render_template(template, &summary, TemplateFormat::Html).unwrap();
+        assert_eq!(html, "https://a.com/&lt;x&gt;|y");
+    }
+
+    #[test]
+    fn test_invalid_template_is_reported() {
+        let result = render_template("{{#each pages}", &test_summary(), TemplateFormat::Html);
+        assert!(matches!(result, Err(OutputError::Format(_))));
+    }
+
+    #[test]
+    fn test_example_template_renders() {
+        let rendered =
+            render_template(EXAMPLE_TEMPLATE, &test_summary(), TemplateFormat::Markdown).unwrap();
+        assert!(rendered.contains("| https://ads.example.net/a\\|b | 7 |"));
+        assert!(rendered.contains("| 1 | 3 |"));
+    }
+}
@@ -3,11 +3,31 @@
 //! This module defines the trait interface for output handlers and
 //! associated data structures for crawl summaries.
 
+use crate::output::{
+    HeaderShare, MirrorCandidate, OriginReport, OutboundReport, StubDomain, SuspiciousCluster,
+    TechnologyShare,
+};
 use crate::state::PageState;
-use crate::storage::RunStatus;
-use std::collections::HashMap;
+use crate::storage::{
+    AnomalyKind, ContactRecord, DisappearedLink, DomainPromotion, ErrorTemplate, LinkKind,
+    PageAnomaly, ProgressSnapshot, RobotsFailure, RunStatus,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 
+/// Number of pages with anomalies listed in a summary
+pub const TOP_ANOMALIES: usize = 20;
+
+/// Number of disappeared links listed in a summary
+pub const TOP_DISAPPEARED_LINKS: usize = 20;
+
+/// Number of blacklisted and stubbed URLs listed in a summary
+pub const TOP_FILTERED_URLS: usize = 20;
+
+/// Number of most retried failing pages listed in a summary
+pub const TOP_RETRIED_PAGES: usize = 20;
+
 /// Errors that can occur during output operations
 #[derive(Debug, Error)]
 pub enum OutputError {
@@ -53,7 +73,7 @@ pub struct ProcessedPage {
 }
 
 /// Error information for failed pages
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CrawlError {
     /// The URL that failed
     pub url: String,
@@ -69,7 +89,7 @@ pub struct CrawlError {
 }
 
 /// Summary statistics for a crawl
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CrawlSummary {
     // Run metadata
     pub run_id: i64,
@@ -85,12 +105,16 @@ pub struct CrawlSummary {
     pub total_links: u64,
     pub total_errors: u64,
 
+    // Links by the element they were found in
+    pub link_kinds: BTreeMap<LinkKind, u64>,
+
     // State breakdown
     pub pages_discovered: u64,
     pub pages_queued: u64,
     pub pages_processed: u64,
     pub pages_blacklisted: u64,
     pub pages_stubbed: u64,
+    pub pages_policy_denied: u64,
     pub pages_dead_link: u64,
     pub pages_unreachable: u64,
     pub pages_rate_limited: u64,
@@ -105,10 +129,10 @@ pub struct CrawlSummary {
     // Discovered domains list
     pub discovered_domains: Vec<String>,
 
-    // Top blacklisted URLs with reference counts
+    // Most referenced blacklisted URLs (up to `TOP_FILTERED_URLS`)
     pub top_blacklisted: Vec<(String, u32)>,
 
-    // Top stubbed URLs with reference counts
+    // Most referenced stubbed URLs (up to `TOP_FILTERED_URLS`)
     pub top_stubbed: Vec<(String, u32)>,
 
     // Error summary (state -> count)
@@ -117,8 +141,81 @@ pub struct CrawlSummary {
     // Rate-limited domains
     pub rate_limited_domains: Vec<String>,
 
-    // Quality domains crawled
+    // Domains paused by the circuit breaker (domain, times paused)
+    pub degraded_domains: Vec<(String, u32)>,
+
+    // Domains slowed down after HTTP 429 responses (domain, delay multiplier)
+    pub slowed_domains: Vec<(String, u32)>,
+
+    // Domains whose robots.txt failed with a server error
+    pub robots_failures: Vec<RobotsFailure>,
+
+    // Most common values of the recorded response headers
+    pub response_headers: Vec<HeaderShare>,
+
+    // Pages per kind of anomaly
+    pub anomaly_counts: BTreeMap<AnomalyKind, u64>,
+
+    // Pages with anomalies (up to `TOP_ANOMALIES`)
+    pub anomalies: Vec<PageAnomaly>,
+
+    // Links missing from pages this run fetched again
+    pub disappeared_link_count: u64,
+
+    // Disappeared links (up to `TOP_DISAPPEARED_LINKS`)
+    pub disappeared_links: Vec<DisappearedLink>,
+
+    // Error and interstitial page templates whose copies' links are left out
+    // of the link graph
+    pub error_templates: Vec<ErrorTemplate>,
+
+    // Domains per detected technology, most domains first
+    pub technologies: Vec<TechnologyShare>,
+
+    // Detected technologies (domain -> technologies)
+    pub domain_technologies: BTreeMap<String, Vec<String>>,
+
+    // Harvested email addresses and social profiles (with harvest-contacts)
+    pub contacts: Vec<ContactRecord>,
+
+    // Failing pages retried the most (up to `TOP_RETRIED_PAGES`)
+    pub most_retried: Vec<CrawlError>,
+
+    // Quality domain patterns the run was configured with
     pub quality_domains: Vec<String>,
+
+    // Discovered domains covered by a quality domain pattern
+    pub configured_domains: Vec<String>,
+
+    // Pages, depth, external links and dead links per quality origin
+    pub origins: Vec<OriginReport>,
+
+    // External domains linked from each quality domain
+    pub outbound: Vec<OutboundReport>,
+
+    // Stubbed domains, most referencing quality pages first
+    pub stub_domains: Vec<StubDomain>,
+
+    // Discovered domains promoted to a deeper crawl, oldest first
+    pub promotions: Vec<DomainPromotion>,
+
+    // Domain pairs suspected to be mirrors of each other
+    pub suspected_mirrors: Vec<MirrorCandidate>,
+
+    // Densely interlinked domain groups (possible link farms)
+    pub suspicious_clusters: Vec<SuspiciousCluster>,
+
+    // User-defined tags (domain -> tags)
+    pub domain_tags: BTreeMap<String, Vec<String>>,
+
+    // User-defined tags (page URL -> tags)
+    pub page_tags: BTreeMap<String, Vec<String>>,
+
+    // Progress snapshots recorded during the run, oldest first
+    pub progress: Vec<ProgressSnapshot>,
+
+    // Whether the counts are limited to pages and links of this run
+    pub run_scoped: bool,
 }
 
 impl CrawlSummary {
@@ -132,6 +229,7 @@ impl CrawlSummary {
         self.pages_processed
             + self.pages_blacklisted
             + self.pages_stubbed
+            + self.pages_policy_denied
             + self.pages_dead_link
             + self.pages_unreachable
             + self.pages_rate_limited
@@ -0,0 +1,312 @@
+//! WARC output
+//!
+//! A `WarcWriter` records a request/response pair for every fetched page in
+//! WARC 1.1 format, so a crawl can be opened with existing web-archiving
+//! tools (or replayed with `--replay`). It runs as a page processor and is
+//! enabled with `warc-path` in `[output]`.
+//!
+//! The response record is rebuilt from what the crawler keeps of a page: the
+//! status line, the Content-Type, the recorded headers and the decoded body.
+//! Files ending in `.gz` are written with every record compressed as its own
+//! gzip member, as WARC tools expect. An existing file is appended to, so
+//! resumed runs add to the same archive.
+
+use crate::crawler::{FetchedPage, PageProcessor, ProcessorError};
+use crate::SumiError;
+use chrono::Utc;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Writes fetched pages to a WARC file
+#[derive(Debug)]
+pub struct WarcWriter {
+    file: Mutex<File>,
+
+    /// Whether each record is gzip-compressed
+    gzip: bool,
+
+    /// The User-Agent recorded in request records
+    user_agent: String,
+}
+
+impl WarcWriter {
+    /// Opens a WARC file for appending, starting it with a warcinfo record
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The WARC file; compressed if it ends in `.gz`
+    /// * `user_agent` - The User-Agent pages are fetched with
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(WarcWriter)` - The writer
+    /// * `Err(SumiError)` - The file could not be opened or written
+    pub fn create(path: &Path, user_agent: &str) -> Result<Self, SumiError> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                SumiError::Storage(format!(
+                    "Failed to open WARC file {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        let writer = Self {
+            file: Mutex::new(file),
+            gzip: path.extension().is_some_and(|ext| ext == "gz"),
+            user_agent: user_agent.to_string(),
+        };
+
+        let info = format!(
+            "software: sumi-ripple/{}\r\nformat: WARC File Format 1.1\r\n\
+             conformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n\
+             http-header-user-agent: {}\r\n",
+            env!("CARGO_PKG_VERSION"),
+            user_agent
+        );
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let record = warc_record(
+            "warcinfo",
+            &[("WARC-Filename", filename)],
+            "application/warc-fields",
+            info.as_bytes(),
+        );
+        writer.write_records(&[record])?;
+
+        Ok(writer)
+    }
+
+    /// Writes the request and response records of a fetched page
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The fetched page
+    pub fn write_page(&self, page: &FetchedPage<'_>) -> Result<(), SumiError> {
+        let target = url::Url::parse(page.final_url).unwrap_or_else(|_| page.url.clone());
+        let date = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let response_id = record_id();
+
+        let mut path = target.path().to_string();
+        if let Some(query) = target.query() {
+            path = format!("{}?{}", path, query);
+        }
+        let host = match target.port() {
+            Some(port) => format!("{}:{}", target.host_str().unwrap_or_default(), port),
+            None => target.host_str().unwrap_or_default().to_string(),
+        };
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\n\r\n",
+            path, host, self.user_agent
+        );
+
+        let reason = reqwest::StatusCode::from_u16(page.status_code)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or("");
+        let mut response = format!("HTTP/1.1 {} {}\r\n", page.status_code, reason);
+        if !page.content_type.is_empty() {
+            response.push_str(&format!("Content-Type: {}\r\n", page.content_type));
+        }
+        for (name, value) in page.headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{}",
+            page.body.len(),
+            page.body
+        ));
+
+        let response_record = warc_record(
+            "response",
+            &[
+                ("WARC-Record-ID", response_id.clone()),
+                ("WARC-Date", date.clone()),
+                ("WARC-Target-URI", target.to_string()),
+            ],
+            "application/http;msgtype=response",
+            response.as_bytes(),
+        );
+        let request_record = warc_record(
+            "request",
+            &[
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Date", date),
+                ("WARC-Target-URI", target.to_string()),
+                ("WARC-Concurrent-To", response_id),
+            ],
+            "application/http;msgtype=request",
+            request.as_bytes(),
+        );
+
+        self.write_records(&[response_record, request_record])
+    }
+
+    /// Appends records to the file, each as its own gzip member if compressed
+    fn write_records(&self, records: &[Vec<u8>]) -> Result<(), SumiError> {
+        let mut data = Vec::new();
+        for record in records {
+            if self.gzip {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(record)?;
+                data.extend(encoder.finish()?);
+            } else {
+                data.extend_from_slice(record);
+            }
+        }
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&data)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl PageProcessor for WarcWriter {
+    fn process(&self, page: &FetchedPage<'_>) -> Result<(), ProcessorError> {
+        Ok(self.write_page(page)?)
+    }
+}
+
+/// Formats a WARC record
+///
+/// A `WARC-Record-ID` and `WARC-Date` are added unless given in `fields`.
+fn warc_record(
+    warc_type: &str,
+    fields: &[(&str, String)],
+    content_type: &str,
+    block: &[u8],
+) -> Vec<u8> {
+    let mut head = format!("WARC/1.1\r\nWARC-Type: {}\r\n", warc_type);
+    if !fields.iter().any(|(name, _)| *name == "WARC-Record-ID") {
+        head.push_str(&format!("WARC-Record-ID: {}\r\n", record_id()));
+    }
+    if !fields.iter().any(|(name, _)| *name == "WARC-Date") {
+        head.push_str(&format!(
+            "WARC-Date: {}\r\n",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        ));
+    }
+    for (name, value) in fields {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        content_type,
+        block.len()
+    ));
+
+    let mut record = head.into_bytes();
+    record.extend_from_slice(block);
+    record.extend_from_slice(b"\r\n\r\n");
+    record
+}
+
+/// A random (version 4) UUID record ID
+fn record_id() -> String {
+    let mut bits = rand::random::<u128>();
+    // Version 4, variant 1
+    bits = (bits & !(0xf << 76)) | (0x4 << 76);
+    bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+    let hex = format!("{:032x}", bits);
+    format!(
+        "<urn:uuid:{}-{}-{}-{}-{}>",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::{FetchOptions, FetchResult, Fetcher, ParsedPage, ReplayFetcher};
+    use crate::url::DomainClassification;
+
+    fn write_pages(path: &Path) {
+        let writer = WarcWriter::create(path, "TestBot/1.0").unwrap();
+        let parsed = ParsedPage {
+            title: None,
+            links: vec![],
+            embedded_links: vec![],
+            emails: vec![],
+            generator: None,
+            robots_meta: vec![],
+            assets: vec![],
+        };
+        let url = url::Url::parse("https://example.com/old").unwrap();
+        writer
+            .write_page(&FetchedPage {
+                page_id: 1,
+                url: &url,
+                final_url: "https://example.com/new?page=2",
+                domain: "example.com",
+                classification: DomainClassification::Quality,
+                status_code: 200,
+                content_type: "text/html; charset=utf-8",
+                headers: &[("server".to_string(), "nginx".to_string())],
+                body: "<html>Bonjour, \u{e9}t\u{e9}</html>",
+                parsed: &parsed,
+            })
+            .unwrap();
+    }
+
+    async fn assert_replayed(path: &Path) {
+        let replay = ReplayFetcher::from_warc(path).unwrap();
+        let outcome = replay
+            .fetch("https://example.com/new?page=2", &FetchOptions::default())
+            .await;
+        match outcome.result {
+            FetchResult::Success {
+                body,
+                headers,
+                content_type,
+                ..
+            } => {
+                assert_eq!(body, "<html>Bonjour, \u{e9}t\u{e9}</html>");
+                assert_eq!(content_type, "text/html; charset=utf-8");
+                assert_eq!(headers, vec![("server".to_string(), "nginx".to_string())]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warc_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let plain = dir.path().join("crawl.warc");
+        write_pages(&plain);
+        let content = std::fs::read_to_string(&plain).unwrap();
+        assert!(content.starts_with("WARC/1.1\r\nWARC-Type: warcinfo\r\n"));
+        assert_eq!(content.matches("WARC-Type: request").count(), 1);
+        assert!(content.contains("GET /new?page=2 HTTP/1.1\r\nHost: example.com\r\n"));
+        assert_replayed(&plain).await;
+
+        let gzipped = dir.path().join("crawl.warc.gz");
+        write_pages(&gzipped);
+        // Reopening appends to the archive
+        write_pages(&gzipped);
+        assert_eq!(&std::fs::read(&gzipped).unwrap()[..2], &[0x1f, 0x8b]);
+        assert_replayed(&gzipped).await;
+    }
+
+    #[test]
+    fn test_record_id() {
+        let id = record_id();
+        assert_eq!(id.len(), "<urn:uuid:>".len() + 36);
+        assert_eq!(&id[24..25], "4");
+        assert!(matches!(&id[29..30], "8" | "9" | "a" | "b"));
+        assert_ne!(id, record_id());
+    }
+}
@@ -1,10 +1,20 @@
 //! Robots.txt caching implementation
 //!
 //! This module provides caching functionality for robots.txt files, including
-//! automatic expiration after 24 hours.
+//! automatic expiration after 24 hours. A `RobotsCache` holds the parsed files
+//! of every domain a crawl visits, bounded in size, so each is fetched and
+//! parsed once per day rather than for every page.
 
 use crate::robots::ParsedRobots;
 use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How long a fetched robots.txt is trusted, in hours
+pub const ROBOTS_TTL_HOURS: i64 = 24;
+
+/// Default number of domains whose robots.txt a `RobotsCache` keeps
+pub const ROBOTS_CACHE_CAPACITY: usize = 10_000;
 
 /// Cached robots.txt data for a domain
 ///
@@ -12,8 +22,8 @@ use chrono::{DateTime, Duration, Utc};
 /// when it was fetched, allowing for cache expiration checks.
 #[derive(Debug, Clone)]
 pub struct CachedRobots {
-    /// The parsed robots.txt content
-    pub content: ParsedRobots,
+    /// The parsed robots.txt content, shared with the pages checked against it
+    pub content: Arc<ParsedRobots>,
 
     /// When the robots.txt was fetched
     pub fetched_at: DateTime<Utc>,
@@ -31,7 +41,7 @@ impl CachedRobots {
     /// A new CachedRobots instance with the current timestamp
     pub fn new(content: ParsedRobots) -> Self {
         Self {
-            content,
+            content: Arc::new(content),
             fetched_at: Utc::now(),
         }
     }
@@ -47,7 +57,7 @@ impl CachedRobots {
     /// * `false` - If the cache is still fresh
     pub fn is_stale(&self) -> bool {
         let age = Utc::now() - self.fetched_at;
-        age > Duration::hours(24)
+        age > Duration::hours(ROBOTS_TTL_HOURS)
     }
 
     /// Returns the age of the cached robots.txt
@@ -88,6 +98,132 @@ impl CachedRobots {
     }
 }
 
+/// Parsed robots.txt files keyed by authority
+///
+/// Keys are the host, plus the port when it is not the default one, as
+/// robots.txt applies per authority. Entries expire after a time to live;
+/// once the cache is full, adding a domain evicts the least recently used.
+#[derive(Debug)]
+pub struct RobotsCache {
+    /// Cached files and when each was last used
+    entries: HashMap<String, (CachedRobots, u64)>,
+
+    /// Most authorities kept
+    capacity: usize,
+
+    /// How long a fetched file stays valid
+    ttl: Duration,
+
+    /// Increases on every use, ordering entries by recency
+    uses: u64,
+}
+
+impl RobotsCache {
+    /// Creates an empty cache
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Most authorities kept (at least one)
+    /// * `ttl` - How long a fetched robots.txt stays valid
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            ttl,
+            uses: 0,
+        }
+    }
+
+    /// Looks up the robots.txt of an authority
+    ///
+    /// An expired entry is dropped, so the caller fetches the file again.
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - The host, with its port if not the default
+    /// * `now` - The current time
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Arc<ParsedRobots>)` - The cached file
+    /// * `None` - If it was never cached or has expired
+    pub fn get(&mut self, authority: &str, now: DateTime<Utc>) -> Option<Arc<ParsedRobots>> {
+        let key = authority.to_lowercase();
+        let (cached, _) = self.entries.get(&key)?;
+        if now - cached.fetched_at > self.ttl {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        self.uses += 1;
+        let (cached, last_used) = self.entries.get_mut(&key)?;
+        *last_used = self.uses;
+        Some(cached.content.clone())
+    }
+
+    /// Caches the robots.txt of an authority, replacing any earlier one
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - The host, with its port if not the default
+    /// * `robots` - The parsed file
+    /// * `now` - When the file was fetched
+    ///
+    /// # Returns
+    ///
+    /// The cached file, to check the authority's pages against
+    pub fn insert(
+        &mut self,
+        authority: &str,
+        robots: ParsedRobots,
+        now: DateTime<Utc>,
+    ) -> Arc<ParsedRobots> {
+        let key = authority.to_lowercase();
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict(now);
+        }
+
+        let content = Arc::new(robots);
+        self.uses += 1;
+        let cached = CachedRobots {
+            content: content.clone(),
+            fetched_at: now,
+        };
+        self.entries.insert(key, (cached, self.uses));
+        content
+    }
+
+    /// Number of authorities cached
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no authority is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes the least recently used entry, preferring expired ones
+    fn evict(&mut self, now: DateTime<Utc>) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (cached, last_used))| {
+                (now - cached.fetched_at <= self.ttl, *last_used)
+            })
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+impl Default for RobotsCache {
+    fn default() -> Self {
+        Self::new(ROBOTS_CACHE_CAPACITY, Duration::hours(ROBOTS_TTL_HOURS))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +286,54 @@ mod tests {
         // Currently returns None as we haven't implemented crawl delay parsing
         assert_eq!(cache.crawl_delay("TestBot"), None);
     }
+
+    #[test]
+    fn test_robots_cache_expiry() {
+        let mut cache = RobotsCache::default();
+        let start = Utc::now();
+        assert!(cache.get("example.com", start).is_none());
+
+        let robots = ParsedRobots::from_content("User-agent: *\nDisallow: /admin");
+        let inserted = cache.insert("Example.com", robots, start);
+        let cached = cache
+            .get("example.com", start + Duration::hours(23))
+            .unwrap();
+        assert!(Arc::ptr_eq(&inserted, &cached));
+        assert!(!cached.is_allowed("/admin", "TestBot"));
+
+        // Other ports are other authorities
+        assert!(cache.get("example.com:8080", start).is_none());
+
+        assert!(cache
+            .get("example.com", start + Duration::hours(25))
+            .is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_robots_cache_evicts_least_recently_used() {
+        let mut cache = RobotsCache::new(2, Duration::hours(ROBOTS_TTL_HOURS));
+        let now = Utc::now();
+        cache.insert("a.com", ParsedRobots::allow_all(), now);
+        cache.insert("b.com", ParsedRobots::allow_all(), now);
+        assert!(cache.get("a.com", now).is_some());
+
+        // b.com was used least recently
+        cache.insert("c.com", ParsedRobots::allow_all(), now);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b.com", now).is_none());
+        assert!(cache.get("a.com", now).is_some());
+
+        // Replacing an entry evicts nothing
+        cache.insert("a.com", ParsedRobots::allow_all(), now);
+        assert_eq!(cache.len(), 2);
+
+        // Expired entries go first, however recently used
+        let later = now + Duration::hours(ROBOTS_TTL_HOURS + 1);
+        cache.insert("c.com", ParsedRobots::allow_all(), later);
+        assert!(cache.get("a.com", now).is_some());
+        cache.insert("d.com", ParsedRobots::allow_all(), later);
+        assert!(cache.get("a.com", later).is_none());
+        assert!(cache.get("c.com", later).is_some());
+    }
 }
@@ -0,0 +1,140 @@
+//! Page-level robots directives
+//!
+//! Besides robots.txt, a page can state directives for itself in a
+//! `<meta name="robots">` tag or an `X-Robots-Tag` header: `noarchive`,
+//! `nosnippet`, `unavailable_after: <date>` and the like. The crawler does not
+//! act on them, but records them so users of the archive can filter pages out.
+
+use crate::storage::{DirectiveSource, PageDirective};
+
+/// Directives that take a value after a colon
+///
+/// Any other name followed by a colon is a user agent the directives after it
+/// are meant for, as in "otherbot: noarchive".
+const VALUED_DIRECTIVES: &[&str] = &[
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+/// Parses the directives of a meta robots tag or an X-Robots-Tag header
+///
+/// Directives are separated by commas. Those scoped to another user agent
+/// are skipped; a scope lasts until the next one. The date of
+/// `unavailable_after` may contain commas itself, as in "Wednesday,
+/// 03-Nov-2027 15:00:00 GMT", and is kept whole.
+///
+/// # Arguments
+///
+/// * `content` - The content of the tag or the value of the header
+/// * `source` - Where the directives come from
+/// * `product_token` - The crawler's product token, as in robots.txt
+///
+/// # Returns
+///
+/// The directives meant for the crawler, in order
+pub fn parse_directives(
+    content: &str,
+    source: DirectiveSource,
+    product_token: &str,
+) -> Vec<PageDirective> {
+    let mut directives: Vec<PageDirective> = Vec::new();
+    let mut applies = true;
+    let mut in_date = false;
+
+    for part in content.split(',').map(str::trim) {
+        // A part starting with a digit continues a date, as in "Wed, 03 Nov"
+        if in_date && part.starts_with(|c: char| c.is_ascii_digit()) {
+            if let Some(value) = directives
+                .last_mut()
+                .filter(|_| applies)
+                .and_then(|directive| directive.value.as_mut())
+            {
+                value.push_str(", ");
+                value.push_str(part);
+            }
+            continue;
+        }
+        in_date = false;
+
+        let (mut name, mut value) = split_directive(part);
+        if let Some(rest) = value.as_deref() {
+            if !VALUED_DIRECTIVES.contains(&name.as_str()) {
+                applies = name.eq_ignore_ascii_case(product_token);
+                (name, value) = split_directive(rest);
+            }
+        }
+        if name.is_empty() {
+            continue;
+        }
+
+        in_date = name == "unavailable_after";
+        if applies {
+            directives.push(PageDirective {
+                source,
+                name,
+                value,
+            });
+        }
+    }
+
+    directives
+}
+
+/// Splits "name: value" into the lowercase name and the value, if any
+fn split_directive(part: &str) -> (String, Option<String>) {
+    match part.split_once(':') {
+        Some((name, value)) => (
+            name.trim().to_ascii_lowercase(),
+            Some(value.trim().to_string()).filter(|value| !value.is_empty()),
+        ),
+        None => (part.trim().to_ascii_lowercase(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(directives: &[PageDirective]) -> Vec<&str> {
+        directives
+            .iter()
+            .map(|directive| directive.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_directives() {
+        let directives = parse_directives("NOARCHIVE, nosnippet", DirectiveSource::Meta, "sumi");
+        assert_eq!(names(&directives), vec!["noarchive", "nosnippet"]);
+        assert!(directives.iter().all(
+            |directive| directive.source == DirectiveSource::Meta && directive.value.is_none()
+        ));
+
+        let directives = parse_directives(
+            "unavailable_after: Wednesday, 03-Nov-2027 15:00:00 GMT, max-snippet: 20",
+            DirectiveSource::Header,
+            "sumi",
+        );
+        assert_eq!(names(&directives), vec!["unavailable_after", "max-snippet"]);
+        assert_eq!(
+            directives[0].value.as_deref(),
+            Some("Wednesday, 03-Nov-2027 15:00:00 GMT")
+        );
+        assert_eq!(directives[1].value.as_deref(), Some("20"));
+
+        assert!(parse_directives(" , ", DirectiveSource::Meta, "sumi").is_empty());
+    }
+
+    #[test]
+    fn test_directives_for_other_crawlers_are_skipped() {
+        let directives = parse_directives(
+            "nosnippet, otherbot: noarchive, noindex, Sumi: unavailable_after: 2027-11-03",
+            DirectiveSource::Header,
+            "sumi",
+        );
+        assert_eq!(names(&directives), vec!["nosnippet", "unavailable_after"]);
+        assert_eq!(directives[1].value.as_deref(), Some("2027-11-03"));
+    }
+}
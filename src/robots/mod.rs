@@ -1,15 +1,19 @@
 //! Robots.txt handling module
 //!
 //! This module provides functionality for fetching, parsing, and caching robots.txt files.
-//! It respects robots.txt directives when crawling websites.
+//! It respects robots.txt directives when crawling websites, and reads the
+//! directives pages state for themselves in meta tags and headers.
 
 mod cache;
+mod directives;
 mod parser;
 
-pub use cache::CachedRobots;
-pub use parser::ParsedRobots;
+pub use cache::{CachedRobots, RobotsCache, ROBOTS_CACHE_CAPACITY, ROBOTS_TTL_HOURS};
+pub use directives::parse_directives;
+pub use parser::{product_token, robots_path, ParsedRobots, RobotsRule};
 
 use crate::SumiError;
+use std::time::Duration;
 
 /// Fetches robots.txt for a domain
 ///
@@ -18,9 +22,15 @@ use crate::SumiError;
 /// * `domain` - The domain to fetch robots.txt from
 /// * `user_agent` - The user agent string to use
 ///
+/// A missing robots.txt, or one that cannot be reached, allows everything.
+/// A server error does not: the crawler should wait and try again, as
+/// RFC 9309 has it assume a complete disallow in the meantime.
+///
 /// # Returns
 ///
 /// * `Ok(ParsedRobots)` - Successfully fetched and parsed robots.txt
+/// * `Err(SumiError::RobotsUnavailable)` - The server answered with a 5xx
+///   status, with the delay its Retry-After header asked for
 /// * `Err(SumiError)` - Failed to fetch or parse
 pub async fn fetch_robots(domain: &str, user_agent: &str) -> Result<ParsedRobots, SumiError> {
     // Domain might include port (e.g., "localhost:8080"), so we need to handle both http and https
@@ -36,7 +46,7 @@ pub async fn fetch_robots(domain: &str, user_agent: &str) -> Result<ParsedRobots
     // Build a simple HTTP client for robots.txt fetching
     let client = reqwest::Client::builder()
         .user_agent(user_agent)
-        .timeout(std::time::Duration::from_secs(10))
+        .timeout(Duration::from_secs(10))
         .build()?;
 
     // Fetch robots.txt
@@ -53,6 +63,22 @@ pub async fn fetch_robots(domain: &str, user_agent: &str) -> Result<ParsedRobots
                         Ok(ParsedRobots::allow_all())
                     }
                 }
+            } else if response.status().is_server_error() {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+                tracing::warn!(
+                    "robots.txt for {} unavailable (status {})",
+                    domain,
+                    response.status()
+                );
+                Err(SumiError::RobotsUnavailable {
+                    domain: domain.to_string(),
+                    status: response.status().as_u16(),
+                    retry_after,
+                })
             } else {
                 tracing::debug!(
                     "robots.txt not found for {} (status {}), allowing all",
@@ -73,12 +99,37 @@ pub async fn fetch_robots(domain: &str, user_agent: &str) -> Result<ParsedRobots
     }
 }
 
+/// Parses a Retry-After header value
+///
+/// # Arguments
+///
+/// * `value` - Either a number of seconds or an HTTP date
+/// * `now` - The current time, for HTTP dates
+///
+/// # Returns
+///
+/// * `Some(Duration)` - How long to wait; zero for dates in the past
+/// * `None` - If the value is neither
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
 /// Checks if a URL is allowed by robots.txt
 ///
 /// # Arguments
 ///
 /// * `robots` - The parsed robots.txt data
-/// * `url` - The URL to check
+/// * `url` - A full URL, or its path and query
 /// * `user_agent` - The user agent string
 ///
 /// # Returns
@@ -88,3 +139,47 @@ pub async fn fetch_robots(domain: &str, user_agent: &str) -> Result<ParsedRobots
 pub fn is_allowed(robots: &ParsedRobots, url: &str, user_agent: &str) -> bool {
     robots.is_allowed(url, user_agent)
 }
+
+/// Checks if a parsed URL is allowed by robots.txt
+///
+/// Rules are matched against the URL's path and query.
+///
+/// # Arguments
+///
+/// * `robots` - The parsed robots.txt data
+/// * `url` - The URL to check
+/// * `user_agent` - The user agent string
+///
+/// # Returns
+///
+/// * `true` - If the URL is allowed
+/// * `false` - If the URL is disallowed
+pub fn is_url_allowed(robots: &ParsedRobots, url: &url::Url, user_agent: &str) -> bool {
+    robots.is_url_allowed(url, user_agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::Utc
+            .with_ymd_and_hms(2015, 10, 21, 7, 28, 0)
+            .unwrap();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
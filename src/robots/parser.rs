@@ -2,7 +2,10 @@
 //!
 //! This module provides functionality for parsing robots.txt content using the robotstxt crate.
 
+use crate::state::TimeWindow;
+use chrono::NaiveTime;
 use robotstxt::DefaultMatcher;
+use url::Url;
 
 /// Parsed robots.txt data
 ///
@@ -14,6 +17,8 @@ pub struct ParsedRobots {
     content: String,
     /// Whether to allow all (true = allow all, false = parse content)
     allow_all: bool,
+    /// Why the server's robots.txt could not be used, if it could not
+    unavailable: Option<String>,
 }
 
 impl ParsedRobots {
@@ -30,6 +35,7 @@ impl ParsedRobots {
         Self {
             content: content.to_string(),
             allow_all: false,
+            unavailable: None,
         }
     }
 
@@ -40,9 +46,31 @@ impl ParsedRobots {
         Self {
             content: String::new(),
             allow_all: true,
+            unavailable: None,
         }
     }
 
+    /// Creates a ParsedRobots that disallows everything
+    ///
+    /// Stands in for a robots.txt the server keeps failing to provide, as
+    /// RFC 9309 has crawlers assume a complete disallow then.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the robots.txt is unavailable
+    pub fn unavailable(reason: &str) -> Self {
+        Self {
+            content: "User-agent: *\nDisallow: /".to_string(),
+            allow_all: false,
+            unavailable: Some(reason.to_string()),
+        }
+    }
+
+    /// Why the robots.txt is unavailable, if it stands in for one
+    pub fn unavailable_reason(&self) -> Option<&str> {
+        self.unavailable.as_deref()
+    }
+
     /// Returns the raw robots.txt content
     ///
     /// # Returns
@@ -54,9 +82,12 @@ impl ParsedRobots {
 
     /// Checks if a URL is allowed for the given user agent
     ///
+    /// Rules are matched against the path and query; a full URL is reduced
+    /// to them first (see [`robots_path`]), so both forms give the same answer.
+    ///
     /// # Arguments
     ///
-    /// * `url` - The URL path to check (e.g., "/page.html")
+    /// * `url` - A full URL, or its path and query (e.g., "/page.html?id=2")
     /// * `user_agent` - The user agent string
     ///
     /// # Returns
@@ -71,7 +102,26 @@ impl ParsedRobots {
 
         // Parse and check on-demand
         let mut matcher = DefaultMatcher::default();
-        matcher.one_agent_allowed_by_robots(&self.content, user_agent, url)
+        matcher.one_agent_allowed_by_robots(
+            &self.content,
+            product_token(user_agent),
+            &request_path(url),
+        )
+    }
+
+    /// Checks if a parsed URL is allowed for the given user agent
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to check
+    /// * `user_agent` - The user agent string
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If the URL is allowed
+    /// * `false` - If the URL is disallowed
+    pub fn is_url_allowed(&self, url: &Url, user_agent: &str) -> bool {
+        self.is_allowed(&robots_path(url), user_agent)
     }
 
     /// Gets the crawl delay for a specific user agent
@@ -89,67 +139,276 @@ impl ParsedRobots {
             return None;
         }
 
-        // Parse robots.txt manually to find Crawl-delay directive
-        // Format: Crawl-delay: <seconds>
-        // This directive applies to the most recent User-agent group
+        self.group_lines(user_agent)
+            .into_iter()
+            .filter(|line| line.key == "crawl-delay")
+            .find_map(|line| line.value.parse::<f64>().ok())
+    }
 
-        let mut current_user_agents: Vec<String> = Vec::new();
-        let mut crawl_delay_for_wildcard: Option<f64> = None;
-        let mut crawl_delay_for_agent: Option<f64> = None;
+    /// Gets the Visit-time window for a specific user agent
+    ///
+    /// `Visit-time` is a non-standard directive of the form
+    /// `Visit-time: 0100-0645`, giving the UTC hours during which the site
+    /// would like to be crawled.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The user agent string
+    ///
+    /// # Returns
+    ///
+    /// * `Some(TimeWindow)` - The preferred visit window
+    /// * `None` - If no valid Visit-time is specified for this agent
+    pub fn visit_time(&self, user_agent: &str) -> Option<TimeWindow> {
+        if self.allow_all || self.content.is_empty() {
+            return None;
+        }
+
+        self.group_lines(user_agent)
+            .into_iter()
+            .filter(|line| line.key == "visit-time")
+            .find_map(|line| parse_visit_time(&line.value))
+    }
+
+    /// Finds the Allow or Disallow rule deciding whether a path may be fetched
+    ///
+    /// Rules come from the groups naming the user agent, or from the `*`
+    /// groups if none does. The longest matching pattern wins, Allow winning
+    /// ties; `*` matches any characters and a trailing `$` anchors the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A full URL, or its path and query (e.g., "/page.html?id=2")
+    /// * `user_agent` - The user agent string
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RobotsRule)` - The rule that applies
+    /// * `None` - If no rule matches, so the path is allowed
+    pub fn matching_rule(&self, path: &str, user_agent: &str) -> Option<RobotsRule> {
+        if self.allow_all || self.content.is_empty() {
+            return None;
+        }
+        let path = request_path(path);
 
-        let normalized_agent = user_agent.to_lowercase();
+        self.group_lines(user_agent)
+            .into_iter()
+            .filter_map(|line| {
+                let allow = match line.key.as_str() {
+                    "allow" => true,
+                    // An empty Disallow allows everything
+                    "disallow" if !line.value.is_empty() => false,
+                    _ => return None,
+                };
+                Some(RobotsRule {
+                    line: line.number,
+                    allow,
+                    pattern: line.value,
+                })
+            })
+            .filter(|rule| pattern_matches(&rule.pattern, &path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+    }
 
-        for line in self.content.lines() {
-            let trimmed = line.trim();
+    /// The lines of the groups that apply to a user agent
+    ///
+    /// Following RFC 9309, these are the lines of every group naming the
+    /// agent's product token (compared case-insensitively), or of the `*`
+    /// groups if none does. A group naming the agent applies even if empty.
+    fn group_lines(&self, user_agent: &str) -> Vec<GroupLine> {
+        let token = product_token(user_agent).to_lowercase();
+        let mut current_user_agents: Vec<String> = Vec::new();
+        let mut in_user_agent_lines = false;
+        let mut agent_lines = Vec::new();
+        let mut wildcard_lines = Vec::new();
+        let mut agent_named = false;
 
-            // Skip comments and empty lines
-            if trimmed.is_empty() || trimmed.starts_with('#') {
+        for (index, line) in self.content.lines().enumerate() {
+            // Strip trailing comments
+            let trimmed = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = trimmed.split_once(':') else {
                 continue;
-            }
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
 
-            // Parse directive
-            if let Some((key, value)) = trimmed.split_once(':') {
-                let key = key.trim().to_lowercase();
-                let value = value.trim();
-
-                match key.as_str() {
-                    "user-agent" => {
-                        // Add to current user-agent group
-                        // Multiple User-agent lines belong to the same group
-                        current_user_agents.push(value.to_lowercase());
-                    }
-                    "crawl-delay" => {
-                        // Parse the crawl delay value
-                        if let Ok(delay) = value.parse::<f64>() {
-                            // Check if this applies to our user agent or wildcard
-                            if current_user_agents
-                                .iter()
-                                .any(|ua| ua == "*" || normalized_agent.contains(ua))
-                            {
-                                if current_user_agents.contains(&"*".to_string()) {
-                                    crawl_delay_for_wildcard = Some(delay);
-                                } else {
-                                    crawl_delay_for_agent = Some(delay);
-                                }
-                            }
-                        }
-                        // After processing crawl-delay, clear current group
-                        // The next User-agent directive will start a new group
-                        current_user_agents.clear();
-                    }
-                    _ => {
-                        // Other directives (Allow, Disallow, etc.)
-                        // These don't affect crawl delay parsing
-                    }
+            if key == "user-agent" {
+                // A User-agent line following other directives starts a new group
+                if !in_user_agent_lines {
+                    current_user_agents.clear();
+                }
+                let agent = if value.starts_with('*') {
+                    "*".to_string()
+                } else {
+                    product_token(value).to_lowercase()
+                };
+                if !agent.is_empty() && agent == token {
+                    agent_named = true;
                 }
+                current_user_agents.push(agent);
+                in_user_agent_lines = true;
+                continue;
+            }
+            in_user_agent_lines = false;
+
+            let line = GroupLine {
+                number: index + 1,
+                key,
+                value: value.to_string(),
+            };
+            if !token.is_empty() && current_user_agents.contains(&token) {
+                agent_lines.push(line);
+            } else if current_user_agents.iter().any(|ua| ua == "*") {
+                wildcard_lines.push(line);
             }
         }
 
-        // Prefer specific user-agent delay over wildcard delay
-        crawl_delay_for_agent.or(crawl_delay_for_wildcard)
+        if agent_named {
+            agent_lines
+        } else {
+            wildcard_lines
+        }
+    }
+}
+
+/// A directive line within a robots.txt group
+struct GroupLine {
+    /// Line number in the file, starting at 1
+    number: usize,
+
+    /// The directive name, lowercased
+    key: String,
+
+    /// The directive value
+    value: String,
+}
+
+/// An Allow or Disallow rule of a robots.txt file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RobotsRule {
+    /// Line number in the file, starting at 1
+    pub line: usize,
+
+    /// Whether this is an Allow rule
+    pub allow: bool,
+
+    /// The path pattern
+    pub pattern: String,
+}
+
+impl std::fmt::Display for RobotsRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let directive = if self.allow { "Allow" } else { "Disallow" };
+        write!(f, "line {}: {}: {}", self.line, directive, self.pattern)
     }
 }
 
+/// The product token of a user agent, which robots.txt groups are matched by
+///
+/// This is the leading run of letters, `_` and `-`, so `TestBot/1.0 (+url)`
+/// gives `TestBot`. RFC 9309 requires a crawler to match groups by this
+/// token rather than by any substring of its user agent.
+///
+/// # Arguments
+///
+/// * `user_agent` - The full user agent string
+///
+/// # Returns
+///
+/// The product token; empty if the user agent does not start with one
+pub fn product_token(user_agent: &str) -> &str {
+    let user_agent = user_agent.trim_start();
+    let end = user_agent
+        .find(|c: char| !(c.is_ascii_alphabetic() || c == '_' || c == '-'))
+        .unwrap_or(user_agent.len());
+    &user_agent[..end]
+}
+
+/// The part of a URL robots.txt rules are matched against
+///
+/// Rules apply to the path and the query, so `Disallow: /*?sort=` can match.
+/// The fragment is never sent to the server and is left out.
+///
+/// # Arguments
+///
+/// * `url` - The URL to check
+///
+/// # Returns
+///
+/// The path, followed by `?` and the query if there is one
+pub fn robots_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Reduces a full URL or a path to what rules are matched against
+///
+/// Anything that does not parse as an absolute URL is taken as a path: its
+/// fragment is dropped and a missing leading `/` added.
+fn request_path(url: &str) -> String {
+    if let Ok(parsed) = Url::parse(url) {
+        if parsed.has_host() {
+            return robots_path(&parsed);
+        }
+    }
+
+    let path = url.split('#').next().unwrap_or("");
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// Whether a robots.txt path pattern matches a path
+///
+/// `*` matches any run of characters and a trailing `$` anchors the pattern
+/// at the end of the path; otherwise the pattern is a prefix.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern must end the path
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Parses a Visit-time value such as "0100-0645" or "01:00-06:45 UTC"
+fn parse_visit_time(value: &str) -> Option<TimeWindow> {
+    let range = value.split_whitespace().next()?;
+    let (start, end) = range.split_once('-')?;
+    Some(TimeWindow::new(parse_hhmm(start)?, parse_hhmm(end)?))
+}
+
+/// Parses an "HHMM" or "HH:MM" time of day
+fn parse_hhmm(value: &str) -> Option<NaiveTime> {
+    let digits: String = value.chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hour = digits[..2].parse().ok()?;
+    let minute = digits[2..].parse().ok()?;
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,6 +455,18 @@ mod tests {
         assert!(!robots.is_allowed("/page", "BadBot"));
     }
 
+    #[test]
+    fn test_unavailable() {
+        let robots = ParsedRobots::unavailable("robots.txt unavailable (HTTP 503)");
+        assert!(!robots.is_allowed("/", "TestBot"));
+        assert!(!robots.is_allowed("/page", "TestBot"));
+        assert_eq!(
+            robots.unavailable_reason(),
+            Some("robots.txt unavailable (HTTP 503)")
+        );
+        assert_eq!(ParsedRobots::allow_all().unavailable_reason(), None);
+    }
+
     #[test]
     fn test_invalid_robots_txt() {
         let content = "This is not valid robots.txt {{{";
@@ -263,4 +534,180 @@ mod tests {
         assert_eq!(robots.crawl_delay("BotB"), Some(3.0));
         assert_eq!(robots.crawl_delay("BotC"), None);
     }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_visit_time_wildcard() {
+        let content = "User-agent: *\nDisallow: /admin\nVisit-time: 0100-0645";
+        let robots = ParsedRobots::from_content(content);
+        assert_eq!(
+            robots.visit_time("TestBot"),
+            Some(TimeWindow::new(time(1, 0), time(6, 45)))
+        );
+    }
+
+    #[test]
+    fn test_visit_time_specific_agent() {
+        let content =
+            "User-agent: TestBot\nVisit-time: 2200-0400\n\nUser-agent: *\nVisit-time: 0100-0500";
+        let robots = ParsedRobots::from_content(content);
+        assert_eq!(
+            robots.visit_time("TestBot/1.0"),
+            Some(TimeWindow::new(time(22, 0), time(4, 0)))
+        );
+        assert_eq!(
+            robots.visit_time("OtherBot"),
+            Some(TimeWindow::new(time(1, 0), time(5, 0)))
+        );
+    }
+
+    #[test]
+    fn test_visit_time_with_colons_and_suffix() {
+        let content = "User-agent: *\nvisit-time: 01:30-05:00 UTC # nightly";
+        let robots = ParsedRobots::from_content(content);
+        assert_eq!(
+            robots.visit_time("TestBot"),
+            Some(TimeWindow::new(time(1, 30), time(5, 0)))
+        );
+    }
+
+    #[test]
+    fn test_visit_time_missing_or_invalid() {
+        let robots = ParsedRobots::from_content("User-agent: *\nDisallow: /admin");
+        assert_eq!(robots.visit_time("TestBot"), None);
+
+        let robots = ParsedRobots::from_content("User-agent: *\nVisit-time: 2500-0100");
+        assert_eq!(robots.visit_time("TestBot"), None);
+
+        let robots = ParsedRobots::from_content("User-agent: OtherBot\nVisit-time: 0100-0200");
+        assert_eq!(robots.visit_time("TestBot"), None);
+
+        assert_eq!(ParsedRobots::allow_all().visit_time("TestBot"), None);
+    }
+
+    #[test]
+    fn test_matching_rule() {
+        let content = "# Example\nUser-agent: *\nDisallow: /private\nAllow: /private/public\n\n\
+                       User-agent: TestBot\nDisallow: /tmp\nDisallow: /*.pdf$\nAllow: /tmp";
+        let robots = ParsedRobots::from_content(content);
+
+        // Other agents follow the * group; the longest pattern wins
+        let rule = robots.matching_rule("/private/x", "OtherBot").unwrap();
+        assert_eq!(rule.to_string(), "line 3: Disallow: /private");
+        let rule = robots
+            .matching_rule("/private/public/x", "OtherBot")
+            .unwrap();
+        assert!(rule.allow);
+        assert_eq!(rule.line, 4);
+        assert_eq!(robots.matching_rule("/", "OtherBot"), None);
+
+        // A named agent only follows its own group; Allow wins ties
+        assert_eq!(robots.matching_rule("/private/x", "TestBot/1.0"), None);
+        assert!(robots.matching_rule("/tmp/a", "TestBot/1.0").unwrap().allow);
+        assert_eq!(
+            robots
+                .matching_rule("/docs/a.pdf", "TestBot/1.0")
+                .unwrap()
+                .line,
+            8
+        );
+        assert_eq!(robots.matching_rule("/docs/a.pdf?x", "TestBot/1.0"), None);
+
+        assert_eq!(
+            ParsedRobots::allow_all().matching_rule("/", "TestBot"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_product_token() {
+        assert_eq!(
+            product_token("TestBot/1.0 (+https://example.com; a@example.com)"),
+            "TestBot"
+        );
+        assert_eq!(product_token("my_crawler-news"), "my_crawler-news");
+        assert_eq!(product_token("  Bot"), "Bot");
+        assert_eq!(product_token("*"), "");
+    }
+
+    #[test]
+    fn test_groups_match_whole_product_tokens() {
+        let content = "User-agent: bot\nCrawl-delay: 30\nDisallow: /\n\n\
+                       User-agent: TestBot-News\nCrawl-delay: 20\n\n\
+                       User-agent: *\nCrawl-delay: 5\nVisit-time: 0100-0200\nDisallow: /tmp\n\n\
+                       User-agent: testbot/2.0\nVisit-time: 0300-0400\n\n\
+                       User-agent: TESTBOT\nDisallow: /private";
+        let robots = ParsedRobots::from_content(content);
+        let agent = "TestBot/1.0 (+https://example.com/about)";
+
+        // "bot" and "TestBot-News" name other crawlers, so the TestBot groups
+        // apply, merged; their missing Crawl-delay is not taken from `*`
+        assert_eq!(robots.crawl_delay(agent), None);
+        assert_eq!(
+            robots.visit_time(agent),
+            Some(TimeWindow::new(time(3, 0), time(4, 0)))
+        );
+        assert_eq!(robots.matching_rule("/tmp/x", agent), None);
+        assert_eq!(robots.matching_rule("/private/x", agent).unwrap().line, 17);
+
+        assert_eq!(robots.crawl_delay("TestBot-News/3"), Some(20.0));
+        assert_eq!(robots.crawl_delay("Bot"), Some(30.0));
+        assert_eq!(robots.crawl_delay("RoboBot"), Some(5.0));
+        assert!(!robots.matching_rule("/tmp/x", "RoboBot").unwrap().allow);
+    }
+
+    #[test]
+    fn test_robots_path() {
+        let url = Url::parse("https://example.com/search?q=a&sort=asc#results").unwrap();
+        assert_eq!(robots_path(&url), "/search?q=a&sort=asc");
+        let url = Url::parse("https://example.com").unwrap();
+        assert_eq!(robots_path(&url), "/");
+
+        assert_eq!(request_path("https://example.com:8080/a/b?c#d"), "/a/b?c");
+        assert_eq!(request_path("/a/b?c#d"), "/a/b?c");
+        assert_eq!(request_path("a/b"), "/a/b");
+        assert_eq!(request_path(""), "/");
+    }
+
+    #[test]
+    fn test_full_urls_match_like_paths() {
+        let content = "User-agent: *\nDisallow: /search?\nDisallow: /cgi-bin\n\n\
+                       User-agent: TestBot\nDisallow: /*?sort=\nDisallow: /*.php$\nAllow: /";
+        let robots = ParsedRobots::from_content(content);
+
+        // The query takes part in matching
+        let url = Url::parse("https://example.com/search?q=rust").unwrap();
+        assert!(!robots.is_url_allowed(&url, "OtherBot"));
+        assert!(!robots.is_allowed("https://example.com/search?q=rust", "OtherBot"));
+        assert!(!robots.is_allowed("/search?q=rust", "OtherBot"));
+        assert!(robots.is_allowed("https://example.com/search", "OtherBot"));
+
+        // The host, port and fragment do not
+        let url = Url::parse("http://example.com:8080/cgi-bin/x#top").unwrap();
+        assert!(!robots.is_url_allowed(&url, "OtherBot"));
+        assert!(robots.is_allowed("https://cgi-bin.example.com/", "OtherBot"));
+
+        // Wildcards and anchors see the query too
+        let rule = |url| robots.matching_rule(url, "TestBot/1.0").unwrap();
+        assert!(!rule("https://example.com/list?sort=asc&page=2").allow);
+        assert!(rule("https://example.com/list?page=2").allow);
+        assert!(!rule("https://example.com/index.php").allow);
+        assert!(rule("https://example.com/index.php?id=1").allow);
+        assert_eq!(rule("https://example.com/index.php#top").line, 7);
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/a", "/abc"));
+        assert!(!pattern_matches("/b", "/abc"));
+        assert!(pattern_matches("/a*c", "/abbc"));
+        assert!(pattern_matches("/*.php$", "/x/index.php"));
+        assert!(!pattern_matches("/*.php$", "/index.php5"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exactly"));
+        assert!(pattern_matches("*", "/anything"));
+    }
 }
@@ -0,0 +1,137 @@
+//! Time source for politeness and backoff decisions
+//!
+//! Domain states and the scheduler read the time through a `Clock` rather
+//! than `Instant::now()`/`Utc::now()` and wait through it rather than
+//! `tokio::time::sleep`. A crawl uses the `SystemClock`; tests use a
+//! `SimulatedClock`, which only moves when advanced (or slept on), so delays,
+//! cooldowns and visit windows can be checked deterministically without real
+//! sleeps.
+
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A future returned by [`Clock::sleep`]
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Source of the current time and of waiting
+pub trait Clock: Send + Sync {
+    /// The current monotonic time, for delays and cooldowns
+    fn now(&self) -> Instant;
+
+    /// The current wall-clock time, for visit windows and cache ages
+    fn utc_now(&self) -> DateTime<Utc>;
+
+    /// Waits for a duration
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// The real time, as used by crawls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A clock that only moves when told to
+///
+/// Sleeping advances the clock by the requested duration and returns at
+/// once. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    /// Monotonic time the clock started at
+    start: Instant,
+
+    /// Wall-clock time the clock started at
+    start_utc: DateTime<Utc>,
+
+    /// Time passed since the start
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl SimulatedClock {
+    /// Creates a clock starting at the current wall-clock time
+    pub fn new() -> Self {
+        Self::starting_at(Utc::now())
+    }
+
+    /// Creates a clock starting at a given wall-clock time
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The wall-clock time the clock reports before advancing
+    pub fn starting_at(start: DateTime<Utc>) -> Self {
+        Self {
+            start: Instant::now(),
+            start_utc: start,
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Time passed since the clock started
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        // Durations a test advances by always fit
+        self.start_utc + chrono::Duration::from_std(self.elapsed()).unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        self.advance(duration);
+        Box::pin(std::future::ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_simulated_clock() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let clock = SimulatedClock::starting_at(start);
+        let before = clock.now();
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(clock.now() - before, Duration::from_secs(60));
+        assert_eq!(clock.utc_now(), start + chrono::Duration::minutes(1));
+
+        // Sleeping returns at once, having moved the shared time
+        let shared = clock.clone();
+        shared.sleep(Duration::from_secs(3600)).await;
+        assert_eq!(clock.elapsed(), Duration::from_secs(3660));
+    }
+}
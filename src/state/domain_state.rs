@@ -1,21 +1,30 @@
 use crate::config::CrawlerConfig;
+use crate::crawler::HeadOutcome;
+use crate::state::{Clock, TimeWindow};
 use chrono::{DateTime, Utc};
 use std::time::{Duration, Instant};
 
-/// Represents the robots.txt content for a domain
-///
-/// This is a placeholder type that will be properly implemented in the robots module.
-/// For now, we just need to track whether we have robots.txt data.
-#[derive(Debug, Clone)]
-pub struct CachedRobots {
-    pub content: String,
-    pub fetched_at: DateTime<Utc>,
-}
+/// Consecutive mishandled HEAD requests after which a domain is fetched with GET only
+pub const HEAD_FAILURE_LIMIT: u32 = 3;
+
+/// Largest factor a domain's delay is multiplied by after HTTP 429 responses
+pub const MAX_DELAY_MULTIPLIER: u32 = 32;
+
+/// Failed robots.txt fetches in a row after which a domain is given up on
+pub const ROBOTS_RETRY_LIMIT: u32 = 3;
+
+/// Wait before fetching an unavailable robots.txt again, doubled per failure
+/// unless the server sends Retry-After
+pub const ROBOTS_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Longest wait before fetching an unavailable robots.txt again
+pub const ROBOTS_RETRY_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
 
 /// Tracks the state of a domain during crawling
 ///
-/// This structure maintains per-domain information needed for rate limiting,
-/// request counting, and robots.txt caching.
+/// This structure maintains per-domain information needed for rate limiting
+/// and request counting. robots.txt files are cached by the coordinator's
+/// `RobotsCache`.
 #[derive(Debug, Clone)]
 pub struct DomainState {
     /// Number of requests made to this domain in the current crawl
@@ -24,14 +33,46 @@ pub struct DomainState {
     /// Timestamp of the last request to this domain
     pub last_request_time: Option<Instant>,
 
-    /// Whether this domain has been rate limited (HTTP 429)
+    /// Random wait added to the per-domain delay after the last request
+    pub request_jitter: Duration,
+
+    /// Whether this domain has answered with HTTP 429 (Too Many Requests)
     pub rate_limited: bool,
 
-    /// Cached robots.txt data for this domain
-    pub robots_txt: Option<CachedRobots>,
+    /// Factor the per-domain delay is multiplied by; doubled with every HTTP
+    /// 429 response, up to `MAX_DELAY_MULTIPLIER`
+    pub delay_multiplier: u32,
+
+    /// Preferred visit window from robots.txt Visit-time (UTC)
+    pub visit_window: Option<TimeWindow>,
+
+    /// Consecutive HEAD requests the domain rejected, failed or answered slowly
+    pub head_failures: u32,
 
-    /// When the robots.txt was fetched (for cache expiration)
-    pub robots_fetched_at: Option<DateTime<Utc>>,
+    /// Consecutive fetches that ended in a network failure or a 5xx response
+    pub consecutive_failures: u32,
+
+    /// Until when the domain's circuit is open and its URLs are held back
+    pub circuit_open_until: Option<Instant>,
+
+    /// How often the domain's circuit has opened
+    pub circuit_trips: u32,
+
+    /// robots.txt fetches that failed with a server error
+    pub robots_failures: u32,
+
+    /// Why the last failed robots.txt fetch failed
+    pub robots_error: Option<String>,
+
+    /// robots.txt fetches in a row that failed with a server error
+    pub robots_streak: u32,
+
+    /// Until when the domain's URLs wait for its robots.txt to be fetched again
+    pub robots_retry_until: Option<Instant>,
+
+    /// Whether the domain is classified as discovered (found by the crawl,
+    /// not on a domain list), limiting it to `max_discovered_domain_requests`
+    pub discovered: bool,
 }
 
 impl DomainState {
@@ -40,77 +81,255 @@ impl DomainState {
         Self {
             request_count: 0,
             last_request_time: None,
+            request_jitter: Duration::ZERO,
             rate_limited: false,
-            robots_txt: None,
-            robots_fetched_at: None,
+            delay_multiplier: 1,
+            visit_window: None,
+            head_failures: 0,
+            consecutive_failures: 0,
+            circuit_open_until: None,
+            circuit_trips: 0,
+            robots_failures: 0,
+            robots_error: None,
+            robots_streak: 0,
+            robots_retry_until: None,
+            discovered: false,
         }
     }
 
     /// Checks if a request can be made to this domain
     ///
     /// This method enforces:
-    /// - Rate limiting (if domain returned HTTP 429)
-    /// - Maximum requests per domain
-    /// - Minimum time between requests to the same domain
+    /// - The circuit breaker (after repeated failures)
+    /// - The wait before an unavailable robots.txt is fetched again
+    /// - Maximum requests per domain (fewer for discovered domains)
+    /// - Minimum time between requests to the same domain (longer after HTTP
+    ///   429 responses)
+    /// - The robots.txt Visit-time window (if `honor_visit_time` is enabled)
     ///
     /// # Arguments
     ///
     /// * `config` - The crawler configuration containing limits
-    /// * `now` - The current time instant
+    /// * `clock` - The source of the current time
     ///
     /// # Returns
     ///
     /// * `true` - If a request can be made now
     /// * `false` - If the request should be delayed or blocked
-    pub fn can_request(&self, config: &CrawlerConfig, now: Instant) -> bool {
-        // Check if domain is rate limited
-        if self.rate_limited {
+    pub fn can_request(&self, config: &CrawlerConfig, clock: &dyn Clock) -> bool {
+        let now = clock.now();
+
+        // Check if the domain's circuit is open
+        if self.circuit_wait(clock).is_some() {
+            return false;
+        }
+
+        // Check if the domain waits for its robots.txt
+        if self.robots_wait(clock).is_some() {
             return false;
         }
 
         // Check if we've hit the maximum request limit for this domain
-        if self.request_count >= config.max_domain_requests {
+        if self.request_count >= self.request_limit(config) {
             return false;
         }
 
         // Check minimum time between requests
         if let Some(last) = self.last_request_time {
-            let min_delay = Duration::from_millis(config.minimum_time_on_page);
+            let min_delay = self.request_delay(config);
             if now.duration_since(last) < min_delay {
                 return false;
             }
         }
 
+        // Check the robots.txt Visit-time window
+        if self.visit_window_wait(config, clock.utc_now()).is_some() {
+            return false;
+        }
+
         true
     }
 
+    /// Time that must pass after the last request before the next one
+    ///
+    /// The configured per-domain delay, times the delay multiplier, plus the
+    /// jitter drawn for the last request.
+    pub fn request_delay(&self, config: &CrawlerConfig) -> Duration {
+        Duration::from_millis(config.per_domain_delay_ms) * self.delay_multiplier
+            + self.request_jitter
+    }
+
     /// Records that a request was made to this domain
     ///
     /// Updates the request count and last request time.
-    pub fn record_request(&mut self, now: Instant) {
+    pub fn record_request(&mut self, clock: &dyn Clock) {
         self.request_count += 1;
-        self.last_request_time = Some(now);
+        self.last_request_time = Some(clock.now());
     }
 
     /// Marks this domain as rate limited
-    pub fn mark_rate_limited(&mut self) {
+    ///
+    /// Doubles the factor its delay is multiplied by, up to
+    /// `MAX_DELAY_MULTIPLIER`. The domain is crawled more slowly, not excluded.
+    ///
+    /// # Returns
+    ///
+    /// The new delay multiplier
+    pub fn mark_rate_limited(&mut self) -> u32 {
         self.rate_limited = true;
+        self.delay_multiplier = self
+            .delay_multiplier
+            .saturating_mul(2)
+            .clamp(1, MAX_DELAY_MULTIPLIER);
+        self.delay_multiplier
     }
 
-    /// Clears the rate limited flag (e.g., after cooldown period)
+    /// Clears the rate limited flag and restores the normal delay (e.g., after cooldown period)
     pub fn clear_rate_limit(&mut self) {
         self.rate_limited = false;
+        self.delay_multiplier = 1;
+    }
+
+    /// Records how the HEAD requests of a fetch from this domain went
+    ///
+    /// A supported HEAD resets the failure streak; skipped ones change nothing.
+    pub fn record_head_outcome(&mut self, outcome: HeadOutcome) {
+        match outcome {
+            HeadOutcome::Supported => self.head_failures = 0,
+            HeadOutcome::Mishandled => self.head_failures += 1,
+            HeadOutcome::Skipped => {}
+        }
+    }
+
+    /// Records whether a fetch from this domain failed
+    ///
+    /// Network failures and 5xx responses count as failures. Once
+    /// `circuit_breaker_threshold` of them happen in a row the circuit opens
+    /// for `circuit_breaker_cooldown_secs`; the first fetch after the
+    /// cooldown reopens it if it fails too. A successful fetch closes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `failed` - Whether the fetch failed
+    /// * `config` - The crawler configuration containing the thresholds
+    /// * `clock` - The source of the current time
+    ///
+    /// # Returns
+    ///
+    /// `true` if the circuit opened because of this fetch
+    pub fn record_fetch_result(
+        &mut self,
+        failed: bool,
+        config: &CrawlerConfig,
+        clock: &dyn Clock,
+    ) -> bool {
+        if !failed {
+            self.consecutive_failures = 0;
+            self.circuit_open_until = None;
+            return false;
+        }
+
+        self.consecutive_failures += 1;
+        if config.circuit_breaker_threshold == 0
+            || self.consecutive_failures < config.circuit_breaker_threshold
+            || self.circuit_wait(clock).is_some()
+        {
+            return false;
+        }
+
+        self.circuit_open_until =
+            Some(clock.now() + Duration::from_secs(config.circuit_breaker_cooldown_secs));
+        self.circuit_trips += 1;
+        true
+    }
+
+    /// Calculates the time until the domain's circuit closes
+    ///
+    /// Returns None if the circuit is closed.
+    pub fn circuit_wait(&self, clock: &dyn Clock) -> Option<Duration> {
+        let now = clock.now();
+        self.circuit_open_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    /// Records that the domain's robots.txt failed with a server error
+    ///
+    /// The domain is held back before the file is fetched again: as long as
+    /// the Retry-After header asks, or `ROBOTS_RETRY_DELAY` doubled for every
+    /// earlier failure in a row, at most `ROBOTS_RETRY_MAX_DELAY`.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - Why the fetch failed
+    /// * `retry_after` - The delay the server asked for, if any
+    /// * `clock` - The source of the current time
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Duration)` - How long the domain is held back
+    /// * `None` - `ROBOTS_RETRY_LIMIT` fetches in a row failed; the domain is
+    ///   not retried
+    pub fn record_robots_unavailable(
+        &mut self,
+        error: &str,
+        retry_after: Option<Duration>,
+        clock: &dyn Clock,
+    ) -> Option<Duration> {
+        self.robots_failures += 1;
+        self.robots_streak += 1;
+        self.robots_error = Some(error.to_string());
+        if self.robots_streak >= ROBOTS_RETRY_LIMIT {
+            self.robots_retry_until = None;
+            return None;
+        }
+
+        let backoff = ROBOTS_RETRY_DELAY.saturating_mul(1 << (self.robots_streak - 1).min(16));
+        let wait = retry_after.unwrap_or(backoff).min(ROBOTS_RETRY_MAX_DELAY);
+        self.robots_retry_until = Some(clock.now() + wait);
+        Some(wait)
+    }
+
+    /// Records that the domain's robots.txt was fetched
+    pub fn record_robots_fetched(&mut self) {
+        self.robots_streak = 0;
+        self.robots_retry_until = None;
+    }
+
+    /// Calculates the time until the domain's robots.txt is fetched again
+    ///
+    /// Returns None if the domain is not waiting for its robots.txt.
+    pub fn robots_wait(&self, clock: &dyn Clock) -> Option<Duration> {
+        let now = clock.now();
+        self.robots_retry_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    /// Whether to fetch from this domain without the preliminary HEAD request
+    ///
+    /// True once `HEAD_FAILURE_LIMIT` HEAD requests in a row were mishandled.
+    pub fn skip_head(&self) -> bool {
+        self.head_failures >= HEAD_FAILURE_LIMIT
+    }
+
+    /// Maximum number of requests to this domain, depending on its classification
+    pub fn request_limit(&self, config: &CrawlerConfig) -> u32 {
+        if self.discovered {
+            config.max_discovered_domain_requests
+        } else {
+            config.max_domain_requests
+        }
     }
 
     /// Checks if this domain has exceeded the request limit
     pub fn has_exceeded_limit(&self, config: &CrawlerConfig) -> bool {
-        self.request_count >= config.max_domain_requests
+        self.request_count >= self.request_limit(config)
     }
 
     /// Returns the number of requests remaining for this domain
     pub fn requests_remaining(&self, config: &CrawlerConfig) -> u32 {
-        config
-            .max_domain_requests
+        self.request_limit(config)
             .saturating_sub(self.request_count)
     }
 
@@ -120,36 +339,52 @@ impl DomainState {
     pub fn time_until_next_request(
         &self,
         config: &CrawlerConfig,
-        now: Instant,
+        clock: &dyn Clock,
     ) -> Option<Duration> {
+        let mut wait = None;
+
         if let Some(last) = self.last_request_time {
-            let min_delay = Duration::from_millis(config.minimum_time_on_page);
-            let elapsed = now.duration_since(last);
+            let min_delay = self.request_delay(config);
+            let elapsed = clock.now().duration_since(last);
             if elapsed < min_delay {
-                return Some(min_delay - elapsed);
+                wait = Some(min_delay - elapsed);
             }
         }
-        None
-    }
 
-    /// Checks if the robots.txt cache is stale (older than 24 hours)
-    pub fn is_robots_stale(&self) -> bool {
-        if let Some(fetched_at) = self.robots_fetched_at {
-            let age = Utc::now() - fetched_at;
-            age > chrono::Duration::hours(24)
-        } else {
-            true // No robots.txt fetched yet
+        if let Some(window_wait) = self.visit_window_wait(config, clock.utc_now()) {
+            wait = Some(wait.map_or(window_wait, |w: Duration| w.max(window_wait)));
         }
+
+        if let Some(circuit_wait) = self.circuit_wait(clock) {
+            wait = Some(wait.map_or(circuit_wait, |w: Duration| w.max(circuit_wait)));
+        }
+
+        if let Some(robots_wait) = self.robots_wait(clock) {
+            wait = Some(wait.map_or(robots_wait, |w: Duration| w.max(robots_wait)));
+        }
+
+        wait
     }
 
-    /// Updates the robots.txt cache
-    pub fn update_robots(&mut self, content: String) {
-        let now = Utc::now();
-        self.robots_txt = Some(CachedRobots {
-            content,
-            fetched_at: now,
-        });
-        self.robots_fetched_at = Some(now);
+    /// Calculates the time until the robots.txt Visit-time window opens
+    ///
+    /// Returns None if Visit-time is not honored, not specified, or the
+    /// window is currently open.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The crawler configuration
+    /// * `now` - The current wall-clock time
+    pub fn visit_window_wait(
+        &self,
+        config: &CrawlerConfig,
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        if !config.honor_visit_time {
+            return None;
+        }
+        self.visit_window
+            .and_then(|window| window.time_until_open(now.time()))
     }
 }
 
@@ -162,13 +397,37 @@ impl Default for DomainState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::SimulatedClock;
 
     fn create_test_config() -> CrawlerConfig {
         CrawlerConfig {
             max_depth: 3,
             max_concurrent_pages_open: 10,
-            minimum_time_on_page: 1000, // 1 second
+            per_domain_delay_ms: 1000, // 1 second
+            per_request_jitter_ms: 0,
+            max_cross_domain_hops: None,
             max_domain_requests: 100,
+            max_discovered_domain_requests: 20,
+            honor_visit_time: false,
+            progress_snapshot_minutes: 5,
+            max_title_length: 512,
+            recrawl_after_days: None,
+            priority_policy: Default::default(),
+            inlink_priority_interval: 0,
+            lower_tier_fetch_interval: 10,
+            head_requests: false,
+            content_types: vec!["text/html".to_string()],
+            max_page_retries: 10,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 300,
+            max_links_per_page: 1000,
+            error_template_min_pages: 5,
+            trap_page_limit: 50,
+            embedded_links: vec![],
+            harvest_contacts: false,
+            skip_self_links: true,
+            skip_same_path_links: false,
+            hash_route_domains: vec![],
         }
     }
 
@@ -178,28 +437,98 @@ mod tests {
         assert_eq!(state.request_count, 0);
         assert!(state.last_request_time.is_none());
         assert!(!state.rate_limited);
-        assert!(state.robots_txt.is_none());
-        assert!(state.robots_fetched_at.is_none());
+    }
+
+    #[test]
+    fn test_head_skip_after_repeated_failures() {
+        let mut state = DomainState::new();
+        state.record_head_outcome(HeadOutcome::Mishandled);
+        state.record_head_outcome(HeadOutcome::Mishandled);
+        state.record_head_outcome(HeadOutcome::Supported);
+        assert!(!state.skip_head());
+
+        for _ in 0..HEAD_FAILURE_LIMIT {
+            state.record_head_outcome(HeadOutcome::Mishandled);
+        }
+        assert!(state.skip_head());
+
+        // Fetches without HEAD keep the domain skipped
+        state.record_head_outcome(HeadOutcome::Skipped);
+        assert!(state.skip_head());
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let mut state = DomainState::new();
+        let config = create_test_config();
+        let clock = SimulatedClock::new();
+
+        for _ in 1..config.circuit_breaker_threshold {
+            assert!(!state.record_fetch_result(true, &config, &clock));
+        }
+        assert!(state.can_request(&config, &clock));
+
+        // The threshold-th failure in a row opens the circuit
+        assert!(state.record_fetch_result(true, &config, &clock));
+        assert_eq!(state.circuit_trips, 1);
+        assert!(!state.can_request(&config, &clock));
+        assert_eq!(
+            state.time_until_next_request(&config, &clock),
+            Some(Duration::from_secs(300))
+        );
+
+        // Failures of requests already in flight do not trip it again
+        assert!(!state.record_fetch_result(true, &config, &clock));
+
+        // The first failure after the cooldown reopens it
+        clock.advance(Duration::from_secs(301));
+        assert!(state.can_request(&config, &clock));
+        assert!(state.record_fetch_result(true, &config, &clock));
+        assert_eq!(state.circuit_trips, 2);
+
+        // A success closes it
+        state.record_fetch_result(false, &config, &clock);
+        assert_eq!(state.consecutive_failures, 0);
+        assert!(state.circuit_wait(&clock).is_none());
+    }
+
+    #[test]
+    fn test_circuit_breaker_disabled() {
+        let mut state = DomainState::new();
+        let mut config = create_test_config();
+        config.circuit_breaker_threshold = 0;
+        let clock = SimulatedClock::new();
+
+        for _ in 0..20 {
+            assert!(!state.record_fetch_result(true, &config, &clock));
+        }
+        assert!(state.can_request(&config, &clock));
     }
 
     #[test]
     fn test_can_request_initially() {
         let state = DomainState::new();
         let config = create_test_config();
-        let now = Instant::now();
+        let clock = SimulatedClock::new();
 
-        assert!(state.can_request(&config, now));
+        assert!(state.can_request(&config, &clock));
     }
 
     #[test]
-    fn test_cannot_request_when_rate_limited() {
+    fn test_rate_limited_domain_is_slowed_down() {
         let mut state = DomainState::new();
-        state.rate_limited = true;
+        state.mark_rate_limited();
 
         let config = create_test_config();
-        let now = Instant::now();
-
-        assert!(!state.can_request(&config, now));
+        let clock = SimulatedClock::new();
+        assert!(state.can_request(&config, &clock));
+
+        // After a request, the domain waits twice the configured delay
+        state.record_request(&clock);
+        clock.advance(Duration::from_millis(config.per_domain_delay_ms));
+        assert!(!state.can_request(&config, &clock));
+        clock.advance(Duration::from_millis(config.per_domain_delay_ms));
+        assert!(state.can_request(&config, &clock));
     }
 
     #[test]
@@ -208,54 +537,70 @@ mod tests {
         state.request_count = 100;
 
         let config = create_test_config();
-        let now = Instant::now();
+        let clock = SimulatedClock::new();
 
-        assert!(!state.can_request(&config, now));
+        assert!(!state.can_request(&config, &clock));
+    }
+
+    #[test]
+    fn test_discovered_domain_budget() {
+        let mut state = DomainState::new();
+        state.request_count = 20;
+
+        let config = create_test_config();
+        let clock = SimulatedClock::new();
+        assert!(state.can_request(&config, &clock));
+        assert_eq!(state.requests_remaining(&config), 80);
+
+        state.discovered = true;
+        assert!(!state.can_request(&config, &clock));
+        assert!(state.has_exceeded_limit(&config));
+        assert_eq!(state.requests_remaining(&config), 0);
     }
 
     #[test]
     fn test_cannot_request_too_soon() {
         let mut state = DomainState::new();
-        let now = Instant::now();
-        state.last_request_time = Some(now);
+        let clock = SimulatedClock::new();
+        state.last_request_time = Some(clock.now());
 
         let config = create_test_config();
 
         // Try immediately - should fail
-        assert!(!state.can_request(&config, now));
+        assert!(!state.can_request(&config, &clock));
 
         // Try 500ms later - should still fail (min is 1000ms)
-        let soon = now + Duration::from_millis(500);
-        assert!(!state.can_request(&config, soon));
+        clock.advance(Duration::from_millis(500));
+        assert!(!state.can_request(&config, &clock));
     }
 
     #[test]
     fn test_can_request_after_delay() {
         let mut state = DomainState::new();
-        let now = Instant::now();
-        state.last_request_time = Some(now);
+        let clock = SimulatedClock::new();
+        state.last_request_time = Some(clock.now());
 
         let config = create_test_config();
 
         // Try 1100ms later - should succeed
-        let later = now + Duration::from_millis(1100);
-        assert!(state.can_request(&config, later));
+        clock.advance(Duration::from_millis(1100));
+        assert!(state.can_request(&config, &clock));
     }
 
     #[test]
     fn test_record_request() {
         let mut state = DomainState::new();
-        let now = Instant::now();
+        let clock = SimulatedClock::new();
 
         assert_eq!(state.request_count, 0);
         assert!(state.last_request_time.is_none());
 
-        state.record_request(now);
+        state.record_request(&clock);
 
         assert_eq!(state.request_count, 1);
-        assert_eq!(state.last_request_time, Some(now));
+        assert_eq!(state.last_request_time, Some(clock.now()));
 
-        state.record_request(now);
+        state.record_request(&clock);
         assert_eq!(state.request_count, 2);
     }
 
@@ -264,17 +609,25 @@ mod tests {
         let mut state = DomainState::new();
         assert!(!state.rate_limited);
 
-        state.mark_rate_limited();
+        assert_eq!(state.mark_rate_limited(), 2);
         assert!(state.rate_limited);
+        assert_eq!(state.mark_rate_limited(), 4);
+
+        // The multiplier stops at its cap
+        for _ in 0..10 {
+            state.mark_rate_limited();
+        }
+        assert_eq!(state.delay_multiplier, MAX_DELAY_MULTIPLIER);
     }
 
     #[test]
     fn test_clear_rate_limit() {
         let mut state = DomainState::new();
-        state.rate_limited = true;
+        state.mark_rate_limited();
 
         state.clear_rate_limit();
         assert!(!state.rate_limited);
+        assert_eq!(state.delay_multiplier, 1);
     }
 
     #[test]
@@ -315,68 +668,117 @@ mod tests {
     fn test_time_until_next_request() {
         let mut state = DomainState::new();
         let config = create_test_config();
-        let now = Instant::now();
+        let clock = SimulatedClock::new();
 
         // No previous request
-        assert!(state.time_until_next_request(&config, now).is_none());
+        assert!(state.time_until_next_request(&config, &clock).is_none());
 
         // Just made a request
-        state.last_request_time = Some(now);
-        let wait = state.time_until_next_request(&config, now);
+        state.last_request_time = Some(clock.now());
+        let wait = state.time_until_next_request(&config, &clock);
         assert!(wait.is_some());
         assert_eq!(wait.unwrap(), Duration::from_millis(1000));
 
         // 500ms later
-        let soon = now + Duration::from_millis(500);
-        let wait = state.time_until_next_request(&config, soon);
+        clock.advance(Duration::from_millis(500));
+        let wait = state.time_until_next_request(&config, &clock);
         assert!(wait.is_some());
         assert_eq!(wait.unwrap(), Duration::from_millis(500));
 
         // 1100ms later
-        let later = now + Duration::from_millis(1100);
-        let wait = state.time_until_next_request(&config, later);
+        clock.advance(Duration::from_millis(600));
+        let wait = state.time_until_next_request(&config, &clock);
         assert!(wait.is_none());
     }
 
     #[test]
-    fn test_is_robots_stale_no_fetch() {
-        let state = DomainState::new();
-        assert!(state.is_robots_stale());
-    }
+    fn test_time_until_next_request_visit_window() {
+        use chrono::{NaiveTime, TimeZone};
 
-    #[test]
-    fn test_is_robots_stale_recent() {
         let mut state = DomainState::new();
-        state.robots_fetched_at = Some(Utc::now());
-        assert!(!state.is_robots_stale());
+        state.visit_window = Some(TimeWindow::new(
+            NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+        ));
+        let mut config = create_test_config();
+        config.honor_visit_time = true;
+        let clock =
+            SimulatedClock::starting_at(Utc.with_ymd_and_hms(2024, 1, 1, 0, 30, 0).unwrap());
+
+        assert!(!state.can_request(&config, &clock));
+        assert_eq!(
+            state.time_until_next_request(&config, &clock),
+            Some(Duration::from_secs(30 * 60))
+        );
+
+        clock.advance(Duration::from_secs(30 * 60));
+        assert!(state.can_request(&config, &clock));
     }
 
     #[test]
-    fn test_is_robots_stale_old() {
-        let mut state = DomainState::new();
-        let old_time = Utc::now() - chrono::Duration::hours(25);
-        state.robots_fetched_at = Some(old_time);
-        assert!(state.is_robots_stale());
+    fn test_default() {
+        let state = DomainState::default();
+        assert_eq!(state.request_count, 0);
+        assert!(!state.rate_limited);
     }
 
     #[test]
-    fn test_update_robots() {
-        let mut state = DomainState::new();
-        assert!(state.robots_txt.is_none());
-
-        state.update_robots("User-agent: *\nDisallow: /admin".to_string());
+    fn test_visit_window_wait() {
+        use chrono::{NaiveTime, TimeZone};
 
-        assert!(state.robots_txt.is_some());
-        assert!(state.robots_fetched_at.is_some());
-
-        let robots = state.robots_txt.unwrap();
-        assert_eq!(robots.content, "User-agent: *\nDisallow: /admin");
+        let mut state = DomainState::new();
+        state.visit_window = Some(TimeWindow::new(
+            NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+        ));
+        let inside = Utc.with_ymd_and_hms(2024, 1, 1, 2, 0, 0).unwrap();
+        let outside = Utc.with_ymd_and_hms(2024, 1, 1, 0, 30, 0).unwrap();
+
+        // Ignored unless enabled
+        let mut config = create_test_config();
+        assert!(state.visit_window_wait(&config, outside).is_none());
+
+        config.honor_visit_time = true;
+        assert!(state.visit_window_wait(&config, inside).is_none());
+        assert_eq!(
+            state.visit_window_wait(&config, outside),
+            Some(Duration::from_secs(30 * 60))
+        );
     }
 
     #[test]
-    fn test_default() {
-        let state = DomainState::default();
-        assert_eq!(state.request_count, 0);
-        assert!(!state.rate_limited);
+    fn test_robots_unavailable_backoff() {
+        let config = create_test_config();
+        let clock = SimulatedClock::new();
+        let mut state = DomainState::new();
+
+        let wait = state.record_robots_unavailable("HTTP 503", None, &clock);
+        assert_eq!(wait, Some(ROBOTS_RETRY_DELAY));
+        assert!(!state.can_request(&config, &clock));
+        assert_eq!(
+            state.time_until_next_request(&config, &clock),
+            Some(ROBOTS_RETRY_DELAY)
+        );
+
+        // Retry-After wins over the backoff, within the maximum
+        clock.advance(ROBOTS_RETRY_DELAY);
+        assert!(state.can_request(&config, &clock));
+        let wait =
+            state.record_robots_unavailable("HTTP 503", Some(Duration::from_secs(5)), &clock);
+        assert_eq!(wait, Some(Duration::from_secs(5)));
+
+        // Given up on after the limit; a fetch resets the streak, not the total
+        assert_eq!(
+            state.record_robots_unavailable("HTTP 500", None, &clock),
+            None
+        );
+        assert!(state.robots_wait(&clock).is_none());
+        state.record_robots_fetched();
+        assert_eq!(state.robots_streak, 0);
+        assert_eq!(state.robots_failures, 3);
+        assert_eq!(state.robots_error.as_deref(), Some("HTTP 500"));
+        let wait =
+            state.record_robots_unavailable("HTTP 503", Some(Duration::from_secs(86400)), &clock);
+        assert_eq!(wait, Some(ROBOTS_RETRY_MAX_DELAY));
     }
 }
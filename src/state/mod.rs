@@ -6,11 +6,18 @@
 //!
 //! - `PageState`: Tracks the state of individual pages (discovered, queued, fetching, processed, etc.)
 //! - `DomainState`: Tracks per-domain state for rate limiting and request counting
-//! - `CachedRobots`: Stores cached robots.txt data for domains
+//! - `Clock`: Time source for rate limiting, with a simulated clock for tests
+//! - `TimeWindow`: A daily time-of-day window, for crawl schedules and robots.txt Visit-time
 
+mod clock;
 mod domain_state;
 mod page_state;
+mod time_window;
 
 // Re-export main types
-pub use domain_state::{CachedRobots, DomainState};
+pub use clock::{Clock, SimulatedClock, Sleep, SystemClock};
+pub use domain_state::{
+    DomainState, HEAD_FAILURE_LIMIT, ROBOTS_RETRY_DELAY, ROBOTS_RETRY_LIMIT, ROBOTS_RETRY_MAX_DELAY,
+};
 pub use page_state::PageState;
+pub use time_window::TimeWindow;
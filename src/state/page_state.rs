@@ -1,10 +1,14 @@
 /// Page state definitions for tracking crawl progress
 ///
 /// This module defines all possible states a page can be in during the crawl process.
+use serde::Serialize;
 use std::fmt;
 
 /// Represents the current state of a page in the crawl process
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Serializes to the same names as `to_db_string`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PageState {
     // ===== Active States =====
     /// Page has been discovered but not yet queued for fetching
@@ -27,6 +31,9 @@ pub enum PageState {
     /// Page is on a stubbed domain - noted but never visited
     Stubbed,
 
+    /// Page matches a deny rule of the local policy - recorded but never fetched
+    PolicyDenied,
+
     // ===== Terminal Error States =====
     /// Page returned HTTP 404 or similar (permanent failure)
     DeadLink,
@@ -70,9 +77,9 @@ impl PageState {
         matches!(self, Self::Processed)
     }
 
-    /// Returns true if this represents a skip state (blacklist/stub)
+    /// Returns true if this represents a skip state (blacklist/stub/policy)
     pub fn is_skipped(&self) -> bool {
-        matches!(self, Self::Blacklisted | Self::Stubbed)
+        matches!(self, Self::Blacklisted | Self::Stubbed | Self::PolicyDenied)
     }
 
     /// Returns true if this represents an error state
@@ -100,6 +107,7 @@ impl PageState {
             Self::Processed => "processed",
             Self::Blacklisted => "blacklisted",
             Self::Stubbed => "stubbed",
+            Self::PolicyDenied => "policy_denied",
             Self::DeadLink => "dead_link",
             Self::Unreachable => "unreachable",
             Self::RateLimited => "rate_limited",
@@ -121,6 +129,7 @@ impl PageState {
             "processed" => Some(Self::Processed),
             "blacklisted" => Some(Self::Blacklisted),
             "stubbed" => Some(Self::Stubbed),
+            "policy_denied" => Some(Self::PolicyDenied),
             "dead_link" => Some(Self::DeadLink),
             "unreachable" => Some(Self::Unreachable),
             "rate_limited" => Some(Self::RateLimited),
@@ -141,6 +150,7 @@ impl PageState {
             Self::Processed,
             Self::Blacklisted,
             Self::Stubbed,
+            Self::PolicyDenied,
             Self::DeadLink,
             Self::Unreachable,
             Self::RateLimited,
@@ -173,6 +183,7 @@ mod tests {
         assert!(PageState::Processed.is_terminal());
         assert!(PageState::Blacklisted.is_terminal());
         assert!(PageState::Stubbed.is_terminal());
+        assert!(PageState::PolicyDenied.is_terminal());
         assert!(PageState::DeadLink.is_terminal());
         assert!(PageState::Unreachable.is_terminal());
         assert!(PageState::RateLimited.is_terminal());
@@ -205,6 +216,7 @@ mod tests {
     fn test_is_skipped() {
         assert!(PageState::Blacklisted.is_skipped());
         assert!(PageState::Stubbed.is_skipped());
+        assert!(PageState::PolicyDenied.is_skipped());
 
         assert!(!PageState::Processed.is_skipped());
         assert!(!PageState::Failed.is_skipped());
@@ -233,6 +245,7 @@ mod tests {
         assert_eq!(PageState::Processed.to_db_string(), "processed");
         assert_eq!(PageState::Blacklisted.to_db_string(), "blacklisted");
         assert_eq!(PageState::Stubbed.to_db_string(), "stubbed");
+        assert_eq!(PageState::PolicyDenied.to_db_string(), "policy_denied");
         assert_eq!(PageState::DeadLink.to_db_string(), "dead_link");
         assert_eq!(PageState::Unreachable.to_db_string(), "unreachable");
         assert_eq!(PageState::RateLimited.to_db_string(), "rate_limited");
@@ -271,6 +284,10 @@ mod tests {
             PageState::from_db_string("stubbed"),
             Some(PageState::Stubbed)
         );
+        assert_eq!(
+            PageState::from_db_string("policy_denied"),
+            Some(PageState::PolicyDenied)
+        );
         assert_eq!(
             PageState::from_db_string("dead_link"),
             Some(PageState::DeadLink)
@@ -318,7 +335,7 @@ mod tests {
     #[test]
     fn test_all_states_complete() {
         let all = PageState::all_states();
-        assert_eq!(all.len(), 13);
+        assert_eq!(all.len(), 14);
 
         // Verify no duplicates
         for i in 0..all.len() {
@@ -0,0 +1,122 @@
+//! Daily time-of-day windows
+//!
+//! A `TimeWindow` is a stretch of the day, such as the crawl windows of the
+//! `[schedule]` section or the Visit-time a robots.txt asks for.
+
+use chrono::{Duration, NaiveTime};
+
+/// A daily time-of-day window
+///
+/// If `end` is earlier than `start` the window spans midnight
+/// (e.g. 22:00-04:00). If `start` equals `end` the window covers the whole day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// Time of day the window opens
+    pub start: NaiveTime,
+    /// Time of day the window closes
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Creates a new time window
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    /// Parses a window from two "HH:MM" strings
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TimeWindow)` - Successfully parsed window
+    /// * `Err(String)` - One of the times is malformed
+    pub fn parse(start: &str, end: &str) -> Result<Self, String> {
+        Ok(Self::new(
+            parse_time_of_day(start)?,
+            parse_time_of_day(end)?,
+        ))
+    }
+
+    /// Returns true if the window spans midnight
+    pub fn wraps_midnight(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Checks if a time of day falls inside the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start == self.end {
+            true
+        } else if self.wraps_midnight() {
+            time >= self.start || time < self.end
+        } else {
+            time >= self.start && time < self.end
+        }
+    }
+
+    /// Calculates how long until the window next opens
+    ///
+    /// Returns None if the window is already open at `time`.
+    pub fn time_until_open(&self, time: NaiveTime) -> Option<std::time::Duration> {
+        if self.contains(time) {
+            return None;
+        }
+
+        let mut wait = self.start.signed_duration_since(time);
+        if wait < Duration::zero() {
+            wait += Duration::days(1);
+        }
+        wait.to_std().ok()
+    }
+}
+
+/// Parses a "HH:MM" time of day
+fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time of day '{}', expected HH:MM", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_time_window_contains() {
+        let w = TimeWindow::parse("01:00", "06:00").unwrap();
+        assert!(w.contains(time(1, 0)));
+        assert!(w.contains(time(5, 59)));
+        assert!(!w.contains(time(6, 0)));
+        assert!(!w.contains(time(0, 59)));
+    }
+
+    #[test]
+    fn test_time_window_wraps_midnight() {
+        let w = TimeWindow::parse("22:00", "04:00").unwrap();
+        assert!(w.wraps_midnight());
+        assert!(w.contains(time(23, 0)));
+        assert!(w.contains(time(3, 0)));
+        assert!(!w.contains(time(12, 0)));
+    }
+
+    #[test]
+    fn test_time_window_time_until_open() {
+        let w = TimeWindow::parse("01:00", "06:00").unwrap();
+        assert_eq!(w.time_until_open(time(2, 0)), None);
+        assert_eq!(
+            w.time_until_open(time(0, 30)),
+            Some(std::time::Duration::from_secs(30 * 60))
+        );
+        assert_eq!(
+            w.time_until_open(time(23, 0)),
+            Some(std::time::Duration::from_secs(2 * 3600))
+        );
+    }
+
+    #[test]
+    fn test_time_window_invalid() {
+        assert!(TimeWindow::parse("25:00", "06:00").is_err());
+        assert!(TimeWindow::parse("01:00", "six").is_err());
+    }
+}
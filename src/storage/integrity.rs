@@ -0,0 +1,271 @@
+//! Crawl invariant checks
+//!
+//! SQLite enforces the schema, but not the invariants the crawler relies on
+//! when resuming: the frontier must only hold pages that are still waiting to
+//! be fetched, links and depth records must point at existing pages, and every
+//! processed page must have been reached from some quality origin. Databases
+//! written by crashed runs, older versions or manual edits can break them.
+
+use crate::state::PageState;
+use rusqlite::{params_from_iter, Connection};
+use std::fmt;
+
+/// Maximum number of example rows reported per violated invariant
+const MAX_EXAMPLES: usize = 5;
+
+/// An invariant of the crawl database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntegrityCheck {
+    /// Frontier entries whose page does not exist
+    FrontierMissingPage,
+
+    /// Frontier entries whose page is already in a terminal state
+    FrontierTerminalPage,
+
+    /// Links from or to a page that does not exist
+    DanglingLink,
+
+    /// Depth records of a page that does not exist
+    DanglingDepth,
+
+    /// Processed pages without any depth record
+    ProcessedWithoutDepth,
+}
+
+impl IntegrityCheck {
+    /// All checks, in the order they are run
+    pub const ALL: [IntegrityCheck; 5] = [
+        Self::FrontierMissingPage,
+        Self::FrontierTerminalPage,
+        Self::DanglingLink,
+        Self::DanglingDepth,
+        Self::ProcessedWithoutDepth,
+    ];
+
+    /// Whether violations can be repaired automatically (by deleting the rows)
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::ProcessedWithoutDepth)
+    }
+
+    /// Query selecting a description of each violating row
+    fn violations_sql(&self) -> &'static str {
+        match self {
+            Self::FrontierMissingPage => {
+                "SELECT 'frontier page_id ' || f.page_id FROM frontier f
+                 WHERE NOT EXISTS (SELECT 1 FROM pages p WHERE p.id = f.page_id)"
+            }
+            Self::FrontierTerminalPage => {
+                "SELECT p.url || ' (' || p.state || ')' FROM frontier f
+                 JOIN pages p ON p.id = f.page_id
+                 WHERE p.state NOT IN (?1, ?2, ?3)"
+            }
+            Self::DanglingLink => {
+                "SELECT 'link ' || l.from_page_id || ' -> ' || l.to_page_id FROM links l
+                 WHERE NOT EXISTS (SELECT 1 FROM pages p WHERE p.id = l.from_page_id)
+                 OR NOT EXISTS (SELECT 1 FROM pages p WHERE p.id = l.to_page_id)"
+            }
+            Self::DanglingDepth => {
+                "SELECT 'depth page_id ' || d.page_id || ' from ' || d.quality_origin
+                 FROM page_depths d
+                 WHERE NOT EXISTS (SELECT 1 FROM pages p WHERE p.id = d.page_id)"
+            }
+            Self::ProcessedWithoutDepth => {
+                "SELECT p.url FROM pages p
+                 WHERE p.state = ?1
+                 AND NOT EXISTS (SELECT 1 FROM page_depths d WHERE d.page_id = p.id)"
+            }
+        }
+    }
+
+    /// States bound to the `?N` placeholders of the check's statements
+    fn state_params(&self) -> Vec<&'static str> {
+        match self {
+            Self::FrontierTerminalPage => vec![
+                PageState::Discovered.to_db_string(),
+                PageState::Queued.to_db_string(),
+                PageState::Fetching.to_db_string(),
+            ],
+            Self::ProcessedWithoutDepth => vec![PageState::Processed.to_db_string()],
+            _ => vec![],
+        }
+    }
+
+    /// Statement deleting the violating rows, if the check is repairable
+    fn repair_sql(&self) -> Option<&'static str> {
+        match self {
+            Self::FrontierMissingPage => Some(
+                "DELETE FROM frontier
+                 WHERE page_id NOT IN (SELECT id FROM pages)",
+            ),
+            Self::FrontierTerminalPage => Some(
+                "DELETE FROM frontier WHERE page_id IN
+                 (SELECT id FROM pages WHERE state NOT IN (?1, ?2, ?3))",
+            ),
+            Self::DanglingLink => Some(
+                "DELETE FROM links
+                 WHERE from_page_id NOT IN (SELECT id FROM pages)
+                 OR to_page_id NOT IN (SELECT id FROM pages)",
+            ),
+            Self::DanglingDepth => Some(
+                "DELETE FROM page_depths
+                 WHERE page_id NOT IN (SELECT id FROM pages)",
+            ),
+            Self::ProcessedWithoutDepth => None,
+        }
+    }
+}
+
+impl fmt::Display for IntegrityCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Self::FrontierMissingPage => "frontier entries reference existing pages",
+            Self::FrontierTerminalPage => "frontier holds only pages in an active state",
+            Self::DanglingLink => "links reference existing pages",
+            Self::DanglingDepth => "depth records reference existing pages",
+            Self::ProcessedWithoutDepth => "processed pages have a depth record",
+        };
+        f.write_str(description)
+    }
+}
+
+/// Result of one invariant check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityIssue {
+    /// The invariant that was checked
+    pub check: IntegrityCheck,
+
+    /// Number of rows violating it
+    pub count: u64,
+
+    /// Descriptions of up to five violating rows
+    pub examples: Vec<String>,
+}
+
+impl IntegrityIssue {
+    /// Whether the invariant holds
+    pub fn is_ok(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Runs every invariant check
+///
+/// # Arguments
+///
+/// * `conn` - The database connection
+///
+/// # Returns
+///
+/// One result per check in `IntegrityCheck::ALL`, including the ones that pass
+pub fn check_integrity(conn: &Connection) -> Result<Vec<IntegrityIssue>, rusqlite::Error> {
+    IntegrityCheck::ALL
+        .iter()
+        .map(|check| {
+            let mut stmt = conn.prepare(check.violations_sql())?;
+            let rows = stmt
+                .query_map(params_from_iter(check.state_params()), |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(IntegrityIssue {
+                check: *check,
+                count: rows.len() as u64,
+                examples: rows.into_iter().take(MAX_EXAMPLES).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Deletes the rows violating repairable invariants
+///
+/// # Arguments
+///
+/// * `conn` - The database connection
+///
+/// # Returns
+///
+/// The number of rows removed for each repairable check
+pub fn repair_integrity(
+    conn: &mut Connection,
+) -> Result<Vec<(IntegrityCheck, u64)>, rusqlite::Error> {
+    let tx = conn.transaction()?;
+    let mut repaired = Vec::new();
+    for check in IntegrityCheck::ALL {
+        if let Some(sql) = check.repair_sql() {
+            let removed = tx.execute(sql, params_from_iter(check.state_params()))?;
+            repaired.push((check, removed as u64));
+        }
+    }
+    tx.commit()?;
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::schema::initialize_schema;
+
+    fn broken_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        // Without foreign key enforcement, as in databases edited by hand
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             INSERT INTO runs (started_at, config_hash, status) VALUES ('now', 'h', 'running');
+             INSERT INTO pages (url, domain, state, discovered_at, discovered_run)
+             VALUES ('https://a.com/', 'a.com', 'processed', 'now', 1),
+                    ('https://a.com/next', 'a.com', 'queued', 'now', 1);
+             INSERT INTO page_depths (page_id, quality_origin, depth) VALUES (2, 'a.com', 1);
+             INSERT INTO page_depths (page_id, quality_origin, depth) VALUES (9, 'a.com', 1);
+             INSERT INTO frontier (page_id, priority) VALUES (1, 0), (2, 1), (7, 2);
+             INSERT INTO links (from_page_id, to_page_id, discovered_run) VALUES (1, 2, 1), (1, 8, 1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn counts(issues: &[IntegrityIssue]) -> Vec<(IntegrityCheck, u64)> {
+        issues.iter().map(|i| (i.check, i.count)).collect()
+    }
+
+    #[test]
+    fn test_clean_database_passes() {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        assert!(check_integrity(&conn).unwrap().iter().all(|i| i.is_ok()));
+    }
+
+    #[test]
+    fn test_detects_violations() {
+        let conn = broken_database();
+        let issues = check_integrity(&conn).unwrap();
+
+        assert_eq!(
+            counts(&issues),
+            vec![
+                (IntegrityCheck::FrontierMissingPage, 1),
+                (IntegrityCheck::FrontierTerminalPage, 1),
+                (IntegrityCheck::DanglingLink, 1),
+                (IntegrityCheck::DanglingDepth, 1),
+                (IntegrityCheck::ProcessedWithoutDepth, 1),
+            ]
+        );
+        assert_eq!(issues[1].examples, vec!["https://a.com/ (processed)"]);
+        assert_eq!(issues[2].examples, vec!["link 1 -> 8"]);
+    }
+
+    #[test]
+    fn test_repair_removes_violating_rows() {
+        let mut conn = broken_database();
+        let repaired = repair_integrity(&mut conn).unwrap();
+        assert!(repaired.iter().all(|(_, removed)| *removed == 1));
+
+        let issues = check_integrity(&conn).unwrap();
+        let remaining: Vec<IntegrityCheck> = issues
+            .iter()
+            .filter(|i| !i.is_ok())
+            .map(|i| i.check)
+            .collect();
+        assert_eq!(remaining, vec![IntegrityCheck::ProcessedWithoutDepth]);
+    }
+}
@@ -7,15 +7,21 @@
 //! - Frontier queue management
 //! - Run tracking and resumption support
 
+mod integrity;
+mod privacy;
 mod schema;
 mod sqlite;
 mod traits;
 
+pub use integrity::{IntegrityCheck, IntegrityIssue};
 pub use sqlite::{init_database, SqliteStorage};
 pub use traits::{Storage, StorageError};
 
+use crate::config::EmbeddedLinkSource;
 use crate::state::PageState;
+use crate::url::DomainClassification;
 use crate::SumiError;
+use serde::Serialize;
 
 use std::path::Path;
 
@@ -34,7 +40,7 @@ pub fn open_storage(path: &Path) -> Result<SqliteStorage, SumiError> {
 }
 
 /// Represents a page in the database
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PageRecord {
     pub id: i64,
     pub url: String,
@@ -51,12 +57,43 @@ pub struct PageRecord {
     pub retry_count: u32,
 }
 
+/// A change of a page's state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateTransition {
+    pub page_id: i64,
+    /// The state before the change; `None` when the page was first stored
+    pub from_state: Option<PageState>,
+    pub to_state: PageState,
+    /// The run the change happened in, unless it has been deleted
+    pub run_id: Option<i64>,
+    pub changed_at: String,
+}
+
+/// What a run saw of a page: the outcome of its last fetch in that run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageObservation {
+    pub page_id: i64,
+    pub url: String,
+    pub run_id: i64,
+    pub observed_at: String,
+    pub state: PageState,
+    pub status_code: Option<u16>,
+    pub title: Option<String>,
+    /// Where the page redirected to; `None` if it was served from its own URL
+    pub final_url: Option<String>,
+    /// Hash of the body, for pages that were processed
+    pub content_hash: Option<String>,
+    pub error_message: Option<String>,
+}
+
 /// Represents a depth record for a page from a quality origin
 #[derive(Debug, Clone)]
 pub struct DepthRecord {
     pub page_id: i64,
     pub quality_origin: String,
     pub depth: u32,
+    /// Domain boundaries crossed on the way from the origin
+    pub hops: u32,
 }
 
 /// Represents a link relationship between pages
@@ -65,6 +102,291 @@ pub struct LinkRecord {
     pub from_page_id: i64,
     pub to_page_id: i64,
     pub discovered_run: i64,
+    /// The last run that found the link on its page
+    pub last_seen_run: i64,
+}
+
+/// A link missing from its page when a later run fetched the page again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DisappearedLink {
+    /// URL of the linking page
+    pub from_url: String,
+    /// URL the link pointed to
+    pub to_url: String,
+    /// The run that first found the link
+    pub discovered_run: i64,
+    /// The last run that found the link
+    pub last_seen_run: i64,
+}
+
+/// Pages of a domain sharing an error or interstitial page template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorTemplate {
+    /// Domain serving the pages
+    pub domain: String,
+    /// Hash of the body the pages share, see `crawler::template_hash`
+    pub template_hash: String,
+    /// Number of pages sharing the template
+    pub pages: u64,
+    /// One of the pages, the first by URL
+    pub example_url: String,
+}
+
+/// How a page refers to the page it links to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// An `<a href>` or `<link rel="canonical">`
+    Anchor,
+    /// An `<iframe src>`
+    Iframe,
+    /// An `<area href>` of an image map
+    Area,
+    /// A `<meta http-equiv="refresh">` target
+    MetaRefresh,
+}
+
+impl LinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Anchor => "anchor",
+            Self::Iframe => "iframe",
+            Self::Area => "area",
+            Self::MetaRefresh => "meta_refresh",
+        }
+    }
+
+    pub fn from_db_string(s: &str) -> Option<Self> {
+        match s {
+            "anchor" => Some(Self::Anchor),
+            "iframe" => Some(Self::Iframe),
+            "area" => Some(Self::Area),
+            "meta_refresh" => Some(Self::MetaRefresh),
+            _ => None,
+        }
+    }
+}
+
+impl From<EmbeddedLinkSource> for LinkKind {
+    fn from(source: EmbeddedLinkSource) -> Self {
+        match source {
+            EmbeddedLinkSource::Iframe => Self::Iframe,
+            EmbeddedLinkSource::Area => Self::Area,
+            EmbeddedLinkSource::MetaRefresh => Self::MetaRefresh,
+        }
+    }
+}
+
+/// Content fingerprint of a processed page, used for mirror detection
+#[derive(Debug, Clone)]
+pub struct PageFingerprint {
+    pub domain: String,
+    pub title: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// Pages reached from one quality origin (a quality domain pattern)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginStats {
+    pub origin: String,
+    pub pages_reached: u64,
+    pub pages_processed: u64,
+    pub max_depth: u32,
+    pub dead_links: u64,
+}
+
+/// Page counts of one domain, kept up to date as pages change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DomainSummary {
+    pub domain: String,
+    pub pages: u64,
+    pub pages_processed: u64,
+    /// Pages in an error state
+    pub errors: u64,
+    /// Earliest discovery time of the domain's pages
+    pub first_seen: String,
+    /// Latest discovery or fetch time of the domain's pages
+    pub last_seen: String,
+}
+
+/// Links from pages of one domain to pages of another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainLinkSummary {
+    pub source_domain: String,
+    pub target_domain: String,
+    pub links: u64,
+    /// Source page URLs, sorted, at most the requested number
+    pub example_sources: Vec<String>,
+}
+
+/// Number of domains sending a response header value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderValueCount {
+    /// Header name (lowercase)
+    pub name: String,
+    pub value: String,
+    /// Distinct domains with at least one page served with this value
+    pub domains: u64,
+}
+
+/// Which filter list a URL was recorded for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    Blacklisted,
+    Stubbed,
+}
+
+impl FilterKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Blacklisted => "blacklisted",
+            Self::Stubbed => "stubbed",
+        }
+    }
+}
+
+/// One encounter of a blacklisted or stubbed URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterHit {
+    pub kind: FilterKind,
+    pub url: String,
+    pub referrer: String,
+    pub run_id: i64,
+    pub discovered_at: String,
+}
+
+/// Why a fetched page stands out from ordinary pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// More links than `max-links-per-page`
+    ExcessiveLinks,
+    /// A body so small it is likely an error or placeholder page
+    TinyBody,
+    /// A body far larger than a typical page
+    HugeBody,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ExcessiveLinks => "excessive_links",
+            Self::TinyBody => "tiny_body",
+            Self::HugeBody => "huge_body",
+        }
+    }
+
+    pub fn from_db_string(s: &str) -> Option<Self> {
+        match s {
+            "excessive_links" => Some(Self::ExcessiveLinks),
+            "tiny_body" => Some(Self::TinyBody),
+            "huge_body" => Some(Self::HugeBody),
+            _ => None,
+        }
+    }
+}
+
+/// An anomaly detected on a page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageAnomaly {
+    pub url: String,
+    pub kind: AnomalyKind,
+    /// What was measured, e.g. "4210 links"
+    pub detail: String,
+}
+
+/// Where a page stated a robots directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectiveSource {
+    /// A `<meta name="robots">` tag
+    Meta,
+    /// An `X-Robots-Tag` response header
+    Header,
+}
+
+impl DirectiveSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Meta => "meta",
+            Self::Header => "header",
+        }
+    }
+
+    pub fn from_db_string(s: &str) -> Option<Self> {
+        match s {
+            "meta" => Some(Self::Meta),
+            "header" => Some(Self::Header),
+            _ => None,
+        }
+    }
+}
+
+/// A robots directive stated by a page, such as `noarchive`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageDirective {
+    pub source: DirectiveSource,
+    /// The directive, lowercase, e.g. "nosnippet"
+    pub name: String,
+    /// The value of directives that take one, e.g. the date of
+    /// "unavailable_after"
+    pub value: Option<String>,
+}
+
+/// An email address or social profile found on the pages of a domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContactRecord {
+    pub domain: String,
+    /// "email" or the social platform, e.g. "github"
+    pub platform: String,
+    /// The address, or the profile URL
+    pub value: String,
+    /// Pages of the domain it was found on
+    pub pages: u64,
+}
+
+/// A page matched by a full-text search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub page_id: i64,
+    pub url: String,
+    pub domain: String,
+    pub title: Option<String>,
+    pub depths: Vec<DepthRecord>,
+}
+
+/// A domain whose robots.txt failed with a server error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RobotsFailure {
+    pub domain: String,
+    /// Fetches that failed
+    pub failures: u32,
+    /// Why the last failed fetch failed
+    pub last_error: Option<String>,
+}
+
+/// A discovered domain promoted to a deeper crawl
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DomainPromotion {
+    pub domain: String,
+    /// The run during which the domain was promoted
+    pub run_id: i64,
+    /// Distinct quality-domain pages linking to the domain when promoted
+    pub quality_referrers: u64,
+    /// Levels added to max-depth for the domain's pages
+    pub extra_depth: u32,
+    pub promoted_at: String,
+}
+
+/// A domain classification stored in the database
+///
+/// Overrides are applied on top of the configuration's domain lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationOverride {
+    /// Domain pattern (may use a leading wildcard, as in the config)
+    pub domain: String,
+    pub classification: DomainClassification,
+    pub created_at: String,
 }
 
 /// Represents a crawl run
@@ -77,6 +399,75 @@ pub struct RunRecord {
     pub status: RunStatus,
 }
 
+/// The effective configuration a crawl run was started with
+#[derive(Debug, Clone)]
+pub struct RunConfigRecord {
+    pub run_id: i64,
+    pub config_hash: String,
+    pub config_toml: String,
+    pub created_at: String,
+}
+
+/// Rows removed when a run was deleted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunDeletion {
+    /// Pages first discovered during the run
+    pub pages: u64,
+    /// Links first discovered during the run or touching one of its pages
+    pub links: u64,
+    /// Blacklisted and stubbed URL records of the run
+    pub url_records: u64,
+    /// Progress snapshots of the run
+    pub progress_snapshots: u64,
+}
+
+/// State transitions kept per page by database maintenance
+pub const STATE_HISTORY_KEPT: usize = 50;
+
+/// Outcome of a database maintenance pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Database size before maintenance (bytes)
+    pub size_before: u64,
+    /// Database size after maintenance (bytes)
+    pub size_after: u64,
+    /// Frontier entries removed because their page is gone or finished
+    pub frontier_pruned: u64,
+    /// Duplicate blacklisted/stubbed referrer records removed
+    pub referrers_pruned: u64,
+    /// State transitions removed beyond the latest `STATE_HISTORY_KEPT` per page
+    pub history_pruned: u64,
+}
+
+impl MaintenanceReport {
+    /// Bytes reclaimed by the maintenance pass
+    pub fn reclaimed(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+/// Rows deleted by the retention policy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// Runs deleted beyond `keep-runs`
+    pub runs_pruned: u64,
+    /// Page observations older than `observation-max-age-days`
+    pub observations_pruned: u64,
+    /// State transitions and progress snapshots older than `event-max-age-days`
+    pub events_pruned: u64,
+}
+
+/// Crawl progress at a point in time during a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProgressSnapshot {
+    pub run_id: i64,
+    pub recorded_at: String,
+    pub pages_processed: u64,
+    pub frontier_size: u64,
+    pub error_count: u64,
+    pub eta_seconds: Option<u64>,
+}
+
 /// Status of a crawl run
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RunStatus {
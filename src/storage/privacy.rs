@@ -0,0 +1,192 @@
+//! Personal data scrubbing
+//!
+//! With `scrub-pii` enabled in `[database]`, storage keeps personal data out
+//! of the database so it can be shared publicly: query parameters that look
+//! like credentials, session identifiers or email addresses are dropped from
+//! every stored URL, referrer URLs are replaced by their hash, and harvested
+//! email addresses are not stored at all.
+
+use sha2::{Digest, Sha256};
+use url::Url;
+
+/// Query parameter names whose values are dropped, matched case-insensitively
+const SENSITIVE_PARAMS: &[&str] = &[
+    "access_token",
+    "api_key",
+    "apikey",
+    "auth",
+    "code",
+    "email",
+    "jsessionid",
+    "jwt",
+    "key",
+    "mail",
+    "password",
+    "phpsessid",
+    "pwd",
+    "secret",
+    "session",
+    "sessionid",
+    "sid",
+    "sig",
+    "signature",
+    "token",
+];
+
+/// Shortest value treated as an opaque token when it mixes letters and digits
+const MIN_TOKEN_LENGTH: usize = 20;
+
+/// Removes query parameters that may carry personal data from a URL
+///
+/// A parameter is removed when its name is sensitive ("token", "sid",
+/// "email", ...), when its value contains an email address, or when its value
+/// looks like an opaque token: at least `MIN_TOKEN_LENGTH` characters mixing
+/// letters and digits. The query is dropped entirely if nothing remains.
+///
+/// # Arguments
+///
+/// * `url` - The URL to scrub
+///
+/// # Returns
+///
+/// The URL without sensitive parameters; a URL that does not parse is
+/// returned unchanged
+pub fn scrub_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+
+    let pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = pairs
+        .iter()
+        .filter(|(name, value)| !is_sensitive(name, value))
+        .collect();
+    if kept.len() == pairs.len() {
+        return url.to_string();
+    }
+
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+/// Scrubs every URL in a free-text message
+///
+/// Error messages quote URLs, e.g. a redirect chain joined with " -> " or
+/// "Redirect loop detected at <url>"; each space-separated word starting with
+/// `http://` or `https://` is passed through [`scrub_url`], keeping trailing
+/// punctuation such as the colon of "Redirect loop detected at <url>: ...".
+///
+/// # Arguments
+///
+/// * `text` - The message to scrub
+///
+/// # Returns
+///
+/// The message with sensitive query parameters removed from its URLs
+pub fn scrub_text(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            if word.starts_with("http://") || word.starts_with("https://") {
+                let url = word.trim_end_matches([':', ',', ';']);
+                format!("{}{}", scrub_url(url), &word[url.len()..])
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replaces a URL by its hash
+///
+/// # Arguments
+///
+/// * `url` - The URL to hide
+///
+/// # Returns
+///
+/// "sha256:" followed by the hex-encoded SHA-256 of the URL, so equal URLs
+/// still compare equal
+pub fn hash_url(url: &str) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(url.as_bytes())))
+}
+
+/// Whether a query parameter may carry personal data
+fn is_sensitive(name: &str, value: &str) -> bool {
+    let name = name.to_lowercase();
+    if SENSITIVE_PARAMS.contains(&name.as_str()) {
+        return true;
+    }
+    if value.contains('@') {
+        return true;
+    }
+    value.len() >= MIN_TOKEN_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '=' | '+' | '/'))
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_url() {
+        assert_eq!(
+            scrub_url("https://a.com/page?id=7&token=abc&Email=x"),
+            "https://a.com/page?id=7"
+        );
+        assert_eq!(
+            scrub_url("https://a.com/unsubscribe?u=jane%40example.com"),
+            "https://a.com/unsubscribe"
+        );
+        assert_eq!(
+            scrub_url("https://a.com/reset?r=9f8e7d6c5b4a39281706f5e4d3c2b1a0&lang=en"),
+            "https://a.com/reset?lang=en"
+        );
+
+        // Ordinary parameters and URLs without a query are left as written
+        assert_eq!(
+            scrub_url("https://a.com/search?q=rust+crawler&page=2"),
+            "https://a.com/search?q=rust+crawler&page=2"
+        );
+        assert_eq!(scrub_url("https://a.com/about"), "https://a.com/about");
+        assert_eq!(
+            scrub_url("https://a.com/2019/03/a-very-long-slug-with-2-numbers"),
+            "https://a.com/2019/03/a-very-long-slug-with-2-numbers"
+        );
+    }
+
+    #[test]
+    fn test_scrub_text() {
+        assert_eq!(
+            scrub_text(
+                "Redirect chain: https://a.com/login -> https://sso.a.com/?token=abc&email=x -> https://a.com/home"
+            ),
+            "Redirect chain: https://a.com/login -> https://sso.a.com/ -> https://a.com/home"
+        );
+        assert_eq!(
+            scrub_text("Redirect loop detected at https://a.com/?sid=1: https://a.com/?sid=1"),
+            "Redirect loop detected at https://a.com/: https://a.com/"
+        );
+        assert_eq!(scrub_text("HTTP 404"), "HTTP 404");
+    }
+
+    #[test]
+    fn test_hash_url() {
+        let hashed = hash_url("https://a.com/?sid=1");
+        assert!(hashed.starts_with("sha256:"));
+        assert_eq!(hashed.len(), "sha256:".len() + 64);
+        assert_eq!(hashed, hash_url("https://a.com/?sid=1"));
+        assert_ne!(hashed, hash_url("https://a.com/"));
+    }
+}
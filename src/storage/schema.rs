@@ -13,6 +13,24 @@ CREATE TABLE IF NOT EXISTS runs (
     status TEXT NOT NULL
 );
 
+-- Effective configuration (serialized TOML) each run was started with
+CREATE TABLE IF NOT EXISTS run_configs (
+    run_id INTEGER PRIMARY KEY REFERENCES runs(id) ON DELETE CASCADE,
+    config_hash TEXT NOT NULL,
+    config_toml TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Periodic progress snapshots taken while a run is crawling
+CREATE TABLE IF NOT EXISTS run_progress (
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    recorded_at TEXT NOT NULL,
+    pages_processed INTEGER NOT NULL,
+    frontier_size INTEGER NOT NULL,
+    error_count INTEGER NOT NULL,
+    PRIMARY KEY (run_id, recorded_at)
+);
+
 -- Track all discovered URLs
 CREATE TABLE IF NOT EXISTS pages (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -25,9 +43,10 @@ CREATE TABLE IF NOT EXISTS pages (
     last_modified TEXT,
     visited_at TEXT,
     discovered_at TEXT NOT NULL,
-    discovered_run INTEGER NOT NULL REFERENCES runs(id),
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
     error_message TEXT,
-    retry_count INTEGER DEFAULT 0
+    retry_count INTEGER DEFAULT 0,
+    content_hash TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_pages_domain ON pages(domain);
@@ -37,9 +56,10 @@ CREATE INDEX IF NOT EXISTS idx_pages_url ON pages(url);
 -- Track depth from multiple quality origins
 CREATE TABLE IF NOT EXISTS page_depths (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    page_id INTEGER NOT NULL REFERENCES pages(id),
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
     quality_origin TEXT NOT NULL,
     depth INTEGER NOT NULL,
+    hops INTEGER NOT NULL DEFAULT 0,
     UNIQUE(page_id, quality_origin)
 );
 
@@ -48,21 +68,23 @@ CREATE INDEX IF NOT EXISTS idx_page_depths_page ON page_depths(page_id);
 -- Track link relationships
 CREATE TABLE IF NOT EXISTS links (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    from_page_id INTEGER NOT NULL REFERENCES pages(id),
-    to_page_id INTEGER NOT NULL REFERENCES pages(id),
-    discovered_run INTEGER NOT NULL REFERENCES runs(id),
+    from_page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    to_page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL DEFAULT 'anchor',
     UNIQUE(from_page_id, to_page_id)
 );
 
 CREATE INDEX IF NOT EXISTS idx_links_from ON links(from_page_id);
 CREATE INDEX IF NOT EXISTS idx_links_to ON links(to_page_id);
+CREATE INDEX IF NOT EXISTS idx_links_discovered_run ON links(discovered_run);
 
 -- Track blacklisted URLs
 CREATE TABLE IF NOT EXISTS blacklisted_urls (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     url TEXT NOT NULL,
     referrer TEXT NOT NULL,
-    discovered_run INTEGER NOT NULL REFERENCES runs(id),
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
     discovered_at TEXT NOT NULL
 );
 
@@ -73,7 +95,7 @@ CREATE TABLE IF NOT EXISTS blacklisted_referrers (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     blacklisted_url TEXT NOT NULL,
     referrer_url TEXT NOT NULL,
-    discovered_run INTEGER NOT NULL REFERENCES runs(id)
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE
 );
 
 -- Track stubbed URLs
@@ -81,7 +103,7 @@ CREATE TABLE IF NOT EXISTS stubbed_urls (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     url TEXT NOT NULL,
     referrer TEXT NOT NULL,
-    discovered_run INTEGER NOT NULL REFERENCES runs(id),
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
     discovered_at TEXT NOT NULL
 );
 
@@ -92,7 +114,7 @@ CREATE TABLE IF NOT EXISTS stubbed_referrers (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     stubbed_url TEXT NOT NULL,
     referrer_url TEXT NOT NULL,
-    discovered_run INTEGER NOT NULL REFERENCES runs(id)
+    discovered_run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE
 );
 
 -- Persist domain states for resumption
@@ -100,18 +122,120 @@ CREATE TABLE IF NOT EXISTS domain_states (
     domain TEXT PRIMARY KEY,
     request_count INTEGER NOT NULL DEFAULT 0,
     rate_limited INTEGER NOT NULL DEFAULT 0,
-    robots_txt TEXT,
-    robots_fetched_at TEXT,
-    last_request_time TEXT
+    robots_txt TEXT,            -- unused; robots.txt is fetched again each run
+    robots_fetched_at TEXT,     -- unused
+    last_request_time TEXT,
+    head_failures INTEGER NOT NULL DEFAULT 0,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    circuit_trips INTEGER NOT NULL DEFAULT 0,
+    robots_failures INTEGER NOT NULL DEFAULT 0,
+    robots_error TEXT,
+    delay_multiplier INTEGER NOT NULL DEFAULT 1
 );
 
 -- Crawl frontier queue
 CREATE TABLE IF NOT EXISTS frontier (
-    page_id INTEGER PRIMARY KEY REFERENCES pages(id),
+    page_id INTEGER PRIMARY KEY REFERENCES pages(id) ON DELETE CASCADE,
     priority INTEGER NOT NULL DEFAULT 0
 );
 
 CREATE INDEX IF NOT EXISTS idx_frontier_priority ON frontier(priority);
+
+-- User-defined labels attached to domains
+CREATE TABLE IF NOT EXISTS domain_tags (
+    domain TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (domain, tag)
+);
+
+-- Domain classifications set at runtime, applied on top of the config
+CREATE TABLE IF NOT EXISTS classification_overrides (
+    domain TEXT PRIMARY KEY,
+    classification TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Labels attached to pages by users, or by the crawler for crawl traps
+CREATE TABLE IF NOT EXISTS page_tags (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (page_id, tag)
+);
+
+-- Selected response headers of the last fetch of each page
+CREATE TABLE IF NOT EXISTS page_headers (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (page_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_headers_name ON page_headers(name, value);
+
+-- Anomalies detected on the last fetch of each page
+CREATE TABLE IF NOT EXISTS page_anomalies (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    PRIMARY KEY (page_id, kind)
+);
+
+-- What each run saw of a page: the outcome of its last fetch in that run,
+-- kept so titles, statuses and redirects can be compared between runs
+CREATE TABLE IF NOT EXISTS page_observations (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    observed_at TEXT NOT NULL,
+    state TEXT NOT NULL,
+    status_code INTEGER,
+    title TEXT,
+    final_url TEXT,
+    content_hash TEXT,
+    error_message TEXT,
+    PRIMARY KEY (page_id, run_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_observations_run ON page_observations(run_id);
+
+-- Robots directives (meta tag or X-Robots-Tag) stated on the last fetch of
+-- each page, recorded whether or not the crawler acts on them
+CREATE TABLE IF NOT EXISTS page_directives (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT,
+    PRIMARY KEY (page_id, source, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_directives_name ON page_directives(name);
+
+-- Technologies detected on the pages of each domain
+CREATE TABLE IF NOT EXISTS domain_technologies (
+    domain TEXT NOT NULL,
+    technology TEXT NOT NULL,
+    pages INTEGER NOT NULL DEFAULT 0,
+    first_seen TEXT NOT NULL,
+    PRIMARY KEY (domain, technology)
+);
+
+-- Email addresses and social profiles linked from quality-domain pages
+CREATE TABLE IF NOT EXISTS contacts (
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    platform TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (page_id, platform, value)
+);
+
+-- Discovered domains promoted to a deeper crawl, kept as an audit log
+CREATE TABLE IF NOT EXISTS domain_promotions (
+    domain TEXT PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    quality_referrers INTEGER NOT NULL,
+    extra_depth INTEGER NOT NULL,
+    promoted_at TEXT NOT NULL
+);
 "#;
 
 /// Initializes the database schema
@@ -126,9 +250,436 @@ CREATE INDEX IF NOT EXISTS idx_frontier_priority ON frontier(priority);
 /// * `Err(rusqlite::Error)` - Failed to initialize schema
 pub fn initialize_schema(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
     conn.execute_batch(SCHEMA_SQL)?;
+    add_missing_columns(conn)?;
+    migrate_foreign_keys(conn)?;
+    conn.execute_batch(POST_MIGRATION_SQL)?;
+    initialize_search_index(conn)?;
+    initialize_domain_summaries(conn)?;
+    initialize_state_counts(conn)?;
+    conn.execute_batch(STATE_HISTORY_SQL)?;
     Ok(())
 }
 
+/// Checks whether a database already has the current schema
+///
+/// Read-only connections cannot run `initialize_schema`, so they check
+/// that every table, added column and foreign key rule is in place instead.
+///
+/// # Arguments
+///
+/// * `conn` - The database connection
+pub fn is_schema_current(conn: &rusqlite::Connection) -> Result<bool, rusqlite::Error> {
+    let tables = SCHEMA_SQL
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("CREATE TABLE IF NOT EXISTS "))
+        .filter_map(|rest| rest.split_whitespace().next());
+    for table in tables.chain([
+        "pages_fts",
+        "domain_summaries",
+        "state_counts",
+        "page_state_history",
+    ]) {
+        if column_names(conn, table)?.is_empty() {
+            return Ok(false);
+        }
+    }
+    for (table, column, _) in ADDED_COLUMNS {
+        if !has_column(conn, table, column)? {
+            return Ok(false);
+        }
+    }
+    for table in FOREIGN_KEY_TABLES {
+        if has_foreign_key_without_rule(conn, table)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Columns added after the initial schema, as (table, column, definition)
+///
+/// Databases created by older versions are upgraded in place by
+/// `add_missing_columns`.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("pages", "content_hash", "TEXT"),
+    ("run_progress", "eta_seconds", "INTEGER"),
+    (
+        "pages",
+        "visited_run",
+        "INTEGER REFERENCES runs(id) ON DELETE SET NULL",
+    ),
+    (
+        "domain_states",
+        "head_failures",
+        "INTEGER NOT NULL DEFAULT 0",
+    ),
+    (
+        "domain_states",
+        "consecutive_failures",
+        "INTEGER NOT NULL DEFAULT 0",
+    ),
+    (
+        "domain_states",
+        "circuit_trips",
+        "INTEGER NOT NULL DEFAULT 0",
+    ),
+    (
+        "domain_states",
+        "robots_failures",
+        "INTEGER NOT NULL DEFAULT 0",
+    ),
+    ("domain_states", "robots_error", "TEXT"),
+    (
+        "domain_states",
+        "delay_multiplier",
+        "INTEGER NOT NULL DEFAULT 1",
+    ),
+    (
+        "links",
+        "last_seen_run",
+        "INTEGER REFERENCES runs(id) ON DELETE SET NULL",
+    ),
+    ("links", "kind", "TEXT NOT NULL DEFAULT 'anchor'"),
+    ("pages", "template_hash", "TEXT"),
+    ("pages", "error_template", "INTEGER NOT NULL DEFAULT 0"),
+    ("page_depths", "hops", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+/// Tables whose foreign keys carry ON DELETE rules
+///
+/// Older versions created them without; `migrate_foreign_keys` rebuilds them.
+const FOREIGN_KEY_TABLES: &[&str] = &[
+    "run_configs",
+    "run_progress",
+    "pages",
+    "page_depths",
+    "links",
+    "blacklisted_urls",
+    "blacklisted_referrers",
+    "stubbed_urls",
+    "stubbed_referrers",
+    "frontier",
+    "page_tags",
+];
+
+/// Rebuilds tables whose foreign keys predate the ON DELETE rules
+///
+/// SQLite cannot alter a constraint in place, so each outdated table is
+/// renamed, recreated from `SCHEMA_SQL`, refilled with its rows (keeping
+/// their ids) and the old copy dropped. Foreign key enforcement is suspended
+/// meanwhile and references from other tables are left untouched, so they
+/// resolve to the rebuilt tables.
+fn migrate_foreign_keys(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    let mut outdated = Vec::new();
+    for table in FOREIGN_KEY_TABLES {
+        if has_foreign_key_without_rule(conn, table)? {
+            outdated.push(*table);
+        }
+    }
+    if outdated.is_empty() {
+        return Ok(());
+    }
+
+    let enforced: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.execute_batch("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON; BEGIN;")?;
+
+    let result = (|| {
+        for table in &outdated {
+            conn.execute_batch(&format!("ALTER TABLE {0} RENAME TO {0}_old", table))?;
+        }
+        conn.execute_batch(SCHEMA_SQL)?;
+        add_missing_columns(conn)?;
+
+        for table in &outdated {
+            let old = format!("{}_old", table);
+            let new_columns = column_names(conn, table)?;
+            let columns: Vec<String> = column_names(conn, &old)?
+                .into_iter()
+                .filter(|column| new_columns.contains(column))
+                .collect();
+            conn.execute_batch(&format!(
+                "INSERT INTO {0} ({1}) SELECT {1} FROM {2}; DROP TABLE {2};",
+                table,
+                columns.join(", "),
+                old
+            ))?;
+        }
+
+        // Indexes were dropped with the old tables
+        conn.execute_batch(SCHEMA_SQL)
+    })();
+
+    match result {
+        Ok(()) => conn.execute_batch("COMMIT;")?,
+        Err(e) => {
+            conn.execute_batch("ROLLBACK;")?;
+            return Err(e);
+        }
+    }
+    conn.execute_batch(&format!(
+        "PRAGMA legacy_alter_table = OFF; PRAGMA foreign_keys = {};",
+        if enforced { "ON" } else { "OFF" }
+    ))?;
+    Ok(())
+}
+
+/// Checks whether any foreign key of a table lacks an ON DELETE rule
+fn has_foreign_key_without_rule(
+    conn: &rusqlite::Connection,
+    table: &str,
+) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_list({})", table))?;
+    let rules = stmt
+        .query_map([], |row| row.get::<_, String>(6))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rules.iter().any(|rule| rule == "NO ACTION"))
+}
+
+/// Schema statements that depend on added columns
+const POST_MIGRATION_SQL: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_pages_content_hash ON pages(content_hash);
+CREATE INDEX IF NOT EXISTS idx_pages_visited_run ON pages(visited_run);
+CREATE INDEX IF NOT EXISTS idx_pages_template ON pages(domain, template_hash);
+"#;
+
+/// Full-text search index over page titles and URLs
+///
+/// `pages_fts` is an external-content FTS5 table backed by `pages`; the
+/// triggers keep it in sync as pages are inserted, updated and deleted.
+const SEARCH_INDEX_SQL: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS pages_fts USING fts5(
+    title,
+    url,
+    content='pages',
+    content_rowid='id'
+);
+
+CREATE TRIGGER IF NOT EXISTS pages_fts_insert AFTER INSERT ON pages BEGIN
+    INSERT INTO pages_fts(rowid, title, url) VALUES (new.id, new.title, new.url);
+END;
+
+CREATE TRIGGER IF NOT EXISTS pages_fts_delete AFTER DELETE ON pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, url)
+    VALUES ('delete', old.id, old.title, old.url);
+END;
+
+CREATE TRIGGER IF NOT EXISTS pages_fts_update AFTER UPDATE OF title, url ON pages BEGIN
+    INSERT INTO pages_fts(pages_fts, rowid, title, url)
+    VALUES ('delete', old.id, old.title, old.url);
+    INSERT INTO pages_fts(rowid, title, url) VALUES (new.id, new.title, new.url);
+END;
+"#;
+
+/// Creates the full-text search index, backfilling it for existing databases
+fn initialize_search_index(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'pages_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute_batch(SEARCH_INDEX_SQL)?;
+
+    if exists == 0 {
+        conn.execute_batch("INSERT INTO pages_fts(pages_fts) VALUES ('rebuild');")?;
+    }
+    Ok(())
+}
+
+/// Per-domain page counts maintained as pages are inserted, updated and deleted
+///
+/// Lets statistics read one row per domain instead of scanning `pages`.
+/// `errors` counts pages in a state for which `PageState::is_error` holds.
+const DOMAIN_SUMMARIES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS domain_summaries (
+    domain TEXT PRIMARY KEY,
+    pages INTEGER NOT NULL DEFAULT 0,
+    pages_processed INTEGER NOT NULL DEFAULT 0,
+    errors INTEGER NOT NULL DEFAULT 0,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS domain_summaries_insert AFTER INSERT ON pages BEGIN
+    INSERT INTO domain_summaries (domain, pages, pages_processed, errors, first_seen, last_seen)
+    VALUES (
+        new.domain,
+        1,
+        new.state = 'processed',
+        new.state IN (ERROR_STATES),
+        new.discovered_at,
+        COALESCE(new.visited_at, new.discovered_at)
+    )
+    ON CONFLICT(domain) DO UPDATE SET
+        pages = pages + 1,
+        pages_processed = pages_processed + excluded.pages_processed,
+        errors = errors + excluded.errors,
+        first_seen = MIN(first_seen, excluded.first_seen),
+        last_seen = MAX(last_seen, excluded.last_seen);
+END;
+
+CREATE TRIGGER IF NOT EXISTS domain_summaries_update AFTER UPDATE OF state, visited_at ON pages BEGIN
+    UPDATE domain_summaries SET
+        pages_processed = pages_processed - (old.state = 'processed') + (new.state = 'processed'),
+        errors = errors - (old.state IN (ERROR_STATES)) + (new.state IN (ERROR_STATES)),
+        last_seen = MAX(last_seen, COALESCE(new.visited_at, new.discovered_at))
+    WHERE domain = new.domain;
+END;
+
+CREATE TRIGGER IF NOT EXISTS domain_summaries_delete AFTER DELETE ON pages BEGIN
+    UPDATE domain_summaries SET
+        pages = pages - 1,
+        pages_processed = pages_processed - (old.state = 'processed'),
+        errors = errors - (old.state IN (ERROR_STATES))
+    WHERE domain = old.domain;
+    DELETE FROM domain_summaries WHERE domain = old.domain AND pages <= 0;
+END;
+"#;
+
+/// Database names of the error states, as used in `DOMAIN_SUMMARIES_SQL` and
+/// error queries
+pub(crate) const ERROR_STATES: &str = "'dead_link', 'unreachable', 'rate_limited', 'failed', \
+'depth_exceeded', 'request_limit_hit', 'content_mismatch'";
+
+/// Creates the domain summaries, backfilling them for existing databases
+fn initialize_domain_summaries(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    let exists = table_exists(conn, "domain_summaries")?;
+
+    conn.execute_batch(&DOMAIN_SUMMARIES_SQL.replace("ERROR_STATES", ERROR_STATES))?;
+
+    // Pages tables too old to record discovery and visit times start empty
+    let backfill = !exists
+        && has_column(conn, "pages", "discovered_at")?
+        && has_column(conn, "pages", "visited_at")?;
+    if backfill {
+        conn.execute(
+            &format!(
+                "INSERT INTO domain_summaries
+                 SELECT domain, COUNT(*), SUM(state = 'processed'), SUM(state IN ({})),
+                        MIN(discovered_at), MAX(COALESCE(visited_at, discovered_at))
+                 FROM pages GROUP BY domain",
+                ERROR_STATES
+            ),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Number of pages in each state, maintained as pages are inserted, updated and deleted
+///
+/// Statistics read the counts of all states with one query over a handful of
+/// rows instead of counting the pages table once per state.
+const STATE_COUNTS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS state_counts (
+    state TEXT PRIMARY KEY,
+    pages INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TRIGGER IF NOT EXISTS state_counts_insert AFTER INSERT ON pages BEGIN
+    INSERT INTO state_counts (state, pages) VALUES (new.state, 1)
+    ON CONFLICT(state) DO UPDATE SET pages = pages + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS state_counts_update AFTER UPDATE OF state ON pages
+WHEN old.state <> new.state BEGIN
+    UPDATE state_counts SET pages = pages - 1 WHERE state = old.state;
+    INSERT INTO state_counts (state, pages) VALUES (new.state, 1)
+    ON CONFLICT(state) DO UPDATE SET pages = pages + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS state_counts_delete AFTER DELETE ON pages BEGIN
+    UPDATE state_counts SET pages = pages - 1 WHERE state = old.state;
+END;
+"#;
+
+/// Every state a page has been in, recorded as pages are inserted and updated
+///
+/// Transitions are attributed to the latest run, the one a crawl is writing
+/// to. History starts when a database is upgraded; earlier transitions are
+/// unknown.
+const STATE_HISTORY_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS page_state_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    run_id INTEGER REFERENCES runs(id) ON DELETE SET NULL,
+    changed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_state_history_page ON page_state_history(page_id);
+
+CREATE TRIGGER IF NOT EXISTS page_state_history_insert AFTER INSERT ON pages BEGIN
+    INSERT INTO page_state_history (page_id, from_state, to_state, run_id, changed_at)
+    VALUES (new.id, NULL, new.state, (SELECT MAX(id) FROM runs),
+            strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS page_state_history_update AFTER UPDATE OF state ON pages
+WHEN old.state <> new.state BEGIN
+    INSERT INTO page_state_history (page_id, from_state, to_state, run_id, changed_at)
+    VALUES (new.id, old.state, new.state, (SELECT MAX(id) FROM runs),
+            strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+"#;
+
+/// Creates the state counts, backfilling them for existing databases
+fn initialize_state_counts(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    let exists = table_exists(conn, "state_counts")?;
+
+    conn.execute_batch(STATE_COUNTS_SQL)?;
+
+    if !exists {
+        conn.execute(
+            "INSERT INTO state_counts SELECT state, COUNT(*) FROM pages GROUP BY state",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks whether a table exists
+fn table_exists(conn: &rusqlite::Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Adds any columns from `ADDED_COLUMNS` that are missing from the database
+fn add_missing_columns(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    for (table, column, definition) in ADDED_COLUMNS {
+        if !has_column(conn, table, column)? {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a table has a column
+fn has_column(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+) -> Result<bool, rusqlite::Error> {
+    Ok(column_names(conn, table)?.iter().any(|name| name == column))
+}
+
+/// Gets the column names of a table
+fn column_names(conn: &rusqlite::Connection, table: &str) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
 /// Gets the current schema version
 ///
 /// This function is reserved for future schema migration support.
@@ -169,6 +720,8 @@ mod tests {
         // Check that key tables exist
         let tables = vec![
             "runs",
+            "run_configs",
+            "run_progress",
             "pages",
             "page_depths",
             "links",
@@ -176,6 +729,16 @@ mod tests {
             "stubbed_urls",
             "domain_states",
             "frontier",
+            "domain_tags",
+            "page_tags",
+            "page_headers",
+            "domain_technologies",
+            "page_anomalies",
+            "page_directives",
+            "page_observations",
+            "contacts",
+            "classification_overrides",
+            "domain_promotions",
         ];
 
         for table in tables {
@@ -191,4 +754,232 @@ mod tests {
             assert_eq!(count.unwrap(), 1, "Table {} should exist", table);
         }
     }
+
+    #[test]
+    fn test_migration_adds_missing_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Simulate a database created before content hashes were tracked
+        conn.execute_batch(
+            "CREATE TABLE pages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL UNIQUE,
+                domain TEXT NOT NULL,
+                state TEXT NOT NULL,
+                title TEXT
+            );",
+        )
+        .unwrap();
+        assert!(!has_column(&conn, "pages", "content_hash").unwrap());
+
+        initialize_schema(&conn).unwrap();
+        assert!(has_column(&conn, "pages", "content_hash").unwrap());
+    }
+
+    #[test]
+    fn test_migration_adds_delete_rules() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Simulate a database created before the ON DELETE rules
+        conn.execute_batch(
+            "CREATE TABLE runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                config_hash TEXT NOT NULL,
+                status TEXT NOT NULL
+            );
+            CREATE TABLE pages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL UNIQUE,
+                domain TEXT NOT NULL,
+                state TEXT NOT NULL,
+                title TEXT,
+                discovered_at TEXT NOT NULL,
+                discovered_run INTEGER NOT NULL REFERENCES runs(id)
+            );
+            CREATE TABLE links (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_page_id INTEGER NOT NULL REFERENCES pages(id),
+                to_page_id INTEGER NOT NULL REFERENCES pages(id),
+                discovered_run INTEGER NOT NULL REFERENCES runs(id),
+                UNIQUE(from_page_id, to_page_id)
+            );
+            INSERT INTO runs (started_at, config_hash, status) VALUES ('now', 'h', 'running');
+            INSERT INTO pages (id, url, domain, state, title, discovered_at, discovered_run)
+            VALUES (5, 'https://a.com/', 'a.com', 'processed', 'Home', 'now', 1),
+                   (9, 'https://a.com/b', 'a.com', 'queued', NULL, 'now', 1);
+            INSERT INTO links (from_page_id, to_page_id, discovered_run) VALUES (5, 9, 1);",
+        )
+        .unwrap();
+        assert!(has_foreign_key_without_rule(&conn, "links").unwrap());
+
+        initialize_schema(&conn).unwrap();
+        for table in FOREIGN_KEY_TABLES {
+            assert!(!has_foreign_key_without_rule(&conn, table).unwrap());
+        }
+
+        // Rows and ids survive the rebuild, and deleting the run cascades
+        let ids: Vec<i64> = conn
+            .prepare("SELECT id FROM pages ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec![5, 9]);
+        assert!(has_column(&conn, "pages", "visited_run").unwrap());
+
+        conn.execute_batch("PRAGMA foreign_keys = ON; DELETE FROM runs;")
+            .unwrap();
+        let links: i64 = conn
+            .query_row("SELECT COUNT(*) FROM links", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(links, 0);
+
+        // Already migrated databases are left alone
+        initialize_schema(&conn).unwrap();
+    }
+
+    #[test]
+    fn test_is_schema_current() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(!is_schema_current(&conn).unwrap());
+
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        assert!(!is_schema_current(&conn).unwrap());
+
+        initialize_schema(&conn).unwrap();
+        assert!(is_schema_current(&conn).unwrap());
+    }
+
+    #[test]
+    fn test_error_states_match_page_states() {
+        use crate::state::PageState;
+
+        let expected: Vec<String> = PageState::all_states()
+            .into_iter()
+            .filter(PageState::is_error)
+            .map(|state| format!("'{}'", state.to_db_string()))
+            .collect();
+        let listed: Vec<String> = ERROR_STATES
+            .split(',')
+            .map(|state| state.trim().to_string())
+            .collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn test_domain_summaries_follow_pages() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO runs (started_at, config_hash, status) VALUES ('now', 'h', 'running');
+             INSERT INTO pages (url, domain, state, discovered_at, discovered_run)
+             VALUES ('https://a.com/', 'a.com', 'processed', '2024-01-02', 1);",
+        )
+        .unwrap();
+
+        // Existing pages are backfilled, new ones counted by the triggers
+        initialize_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pages (url, domain, state, discovered_at, discovered_run)
+             VALUES ('https://a.com/x', 'a.com', 'discovered', '2024-01-01', 1),
+                    ('https://b.com/', 'b.com', 'discovered', '2024-01-03', 1);
+             UPDATE pages SET state = 'dead_link', visited_at = '2024-01-05'
+             WHERE url = 'https://a.com/x';
+             DELETE FROM pages WHERE domain = 'b.com';",
+        )
+        .unwrap();
+
+        let rows: Vec<(String, i64, i64, i64, String, String)> = conn
+            .prepare("SELECT * FROM domain_summaries ORDER BY domain")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![(
+                "a.com".to_string(),
+                2,
+                1,
+                1,
+                "2024-01-01".to_string(),
+                "2024-01-05".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_state_counts_follow_pages() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO runs (started_at, config_hash, status) VALUES ('now', 'h', 'running');
+             INSERT INTO pages (url, domain, state, discovered_at, discovered_run)
+             VALUES ('https://a.com/', 'a.com', 'processed', 'now', 1);",
+        )
+        .unwrap();
+
+        initialize_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO pages (url, domain, state, discovered_at, discovered_run)
+             VALUES ('https://a.com/x', 'a.com', 'discovered', 'now', 1),
+                    ('https://a.com/y', 'a.com', 'discovered', 'now', 1);
+             UPDATE pages SET state = 'failed' WHERE url = 'https://a.com/x';
+             UPDATE pages SET title = 'Y' WHERE url = 'https://a.com/y';
+             DELETE FROM pages WHERE url = 'https://a.com/';",
+        )
+        .unwrap();
+
+        let counts: Vec<(String, i64)> = conn
+            .prepare("SELECT state, pages FROM state_counts ORDER BY state")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                ("discovered".to_string(), 1),
+                ("failed".to_string(), 1),
+                ("processed".to_string(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_index_backfills_existing_pages() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO runs (started_at, config_hash, status) VALUES ('now', 'h', 'running');
+             INSERT INTO pages (url, domain, state, title, discovered_at, discovered_run)
+             VALUES ('https://a.com/', 'a.com', 'processed', 'Gardening Tips', 'now', 1);",
+        )
+        .unwrap();
+
+        initialize_schema(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pages_fts WHERE pages_fts MATCH 'gardening'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
 }
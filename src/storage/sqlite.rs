@@ -2,23 +2,46 @@
 //!
 //! This module provides a SQLite-based implementation of the Storage trait.
 
-use crate::state::{CachedRobots, DomainState, PageState};
-use crate::storage::schema::initialize_schema;
+use crate::config::{DatabaseConfig, RetentionConfig};
+use crate::state::{DomainState, PageState};
+use crate::storage::integrity::{self, IntegrityCheck, IntegrityIssue};
+use crate::storage::privacy::{hash_url, scrub_text, scrub_url};
+use crate::storage::schema::{initialize_schema, is_schema_current, ERROR_STATES};
 use crate::storage::traits::{Storage, StorageError, StorageResult};
-use crate::storage::{DepthRecord, LinkRecord, PageRecord, RunRecord, RunStatus};
+use crate::storage::{
+    AnomalyKind, ClassificationOverride, ContactRecord, DepthRecord, DirectiveSource,
+    DisappearedLink, DomainLinkSummary, DomainPromotion, DomainSummary, ErrorTemplate, FilterHit,
+    FilterKind, HeaderValueCount, LinkKind, LinkRecord, MaintenanceReport, OriginStats,
+    PageAnomaly, PageDirective, PageFingerprint, PageObservation, PageRecord, ProgressSnapshot,
+    RetentionReport, RobotsFailure, RunConfigRecord, RunDeletion, RunRecord, RunStatus, SearchHit,
+    StateTransition, STATE_HISTORY_KEPT,
+};
+use crate::url::DomainClassification;
 use crate::SumiError;
-use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, OptionalExtension};
-use std::collections::HashMap;
+use chrono::Utc;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::time::Duration;
+
+/// FROM clause of the links missing from their page when run ?1 processed it
+const DISAPPEARED_LINKS_FROM: &str = "FROM links l
+     JOIN page_observations o
+       ON o.page_id = l.from_page_id AND o.run_id = ?1 AND o.state = 'processed'
+     JOIN pages f ON f.id = l.from_page_id
+     JOIN pages t ON t.id = l.to_page_id
+     WHERE COALESCE(l.last_seen_run, l.discovered_run) < ?1";
 
 /// SQLite storage backend
 pub struct SqliteStorage {
     conn: Connection,
+
+    /// Whether URLs, referrers and contacts are scrubbed before being written
+    scrub_pii: bool,
 }
 
 impl SqliteStorage {
-    /// Creates a new SqliteStorage instance
+    /// Creates a new SqliteStorage instance with the default SQLite tuning
     ///
     /// # Arguments
     ///
@@ -29,23 +52,94 @@ impl SqliteStorage {
     /// * `Ok(SqliteStorage)` - Successfully opened/created database
     /// * `Err(SumiError)` - Failed to open database
     pub fn new(path: &Path) -> Result<Self, SumiError> {
+        Self::with_config(path, &DatabaseConfig::default())
+    }
+
+    /// Creates a new SqliteStorage instance
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the SQLite database file
+    /// * `config` - SQLite tuning (busy timeout, checkpointing, sync level, cache)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SqliteStorage)` - Successfully opened/created database
+    /// * `Err(SumiError)` - Failed to open database
+    pub fn with_config(path: &Path, config: &DatabaseConfig) -> Result<Self, SumiError> {
         let conn = Connection::open(path)?;
 
+        // Wait for other processes (e.g. a --stats reader) instead of failing
+        // with SQLITE_BUSY
+        conn.busy_timeout(Duration::from_millis(config.busy_timeout_ms))?;
+
         // Configure SQLite for better performance
-        conn.execute_batch(
+        conn.execute_batch(&format!(
             "
             PRAGMA journal_mode = WAL;
-            PRAGMA synchronous = NORMAL;
+            PRAGMA synchronous = {};
+            PRAGMA wal_autocheckpoint = {};
+            PRAGMA cache_size = -{};
             PRAGMA foreign_keys = ON;
             PRAGMA temp_store = MEMORY;
             PRAGMA mmap_size = 268435456;
         ",
-        )?;
+            config.synchronous.as_pragma(),
+            config.wal_autocheckpoint,
+            config.cache_size_kib
+        ))?;
 
         // Initialize schema
         initialize_schema(&conn)?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            scrub_pii: config.scrub_pii,
+        })
+    }
+
+    /// Opens an existing database without write access
+    ///
+    /// Used by the reporting modes, which must not interfere with a crawl
+    /// writing to the same database: the schema is checked but never
+    /// created or migrated, and every write fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the SQLite database file
+    /// * `config` - SQLite tuning; only the busy timeout and cache size apply
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SqliteStorage)` - Successfully opened database
+    /// * `Err(SumiError)` - The database is missing, unreadable or needs a
+    ///   schema upgrade
+    pub fn open_read_only(path: &Path, config: &DatabaseConfig) -> Result<Self, SumiError> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(Duration::from_millis(config.busy_timeout_ms))?;
+        conn.execute_batch(&format!(
+            "
+            PRAGMA query_only = ON;
+            PRAGMA cache_size = -{};
+            PRAGMA temp_store = MEMORY;
+        ",
+            config.cache_size_kib
+        ))?;
+
+        if !is_schema_current(&conn)? {
+            return Err(SumiError::Storage(format!(
+                "{} was written by an older version; run a crawl or --db-maintenance once to upgrade it",
+                path.display()
+            )));
+        }
+
+        Ok(Self {
+            conn,
+            scrub_pii: config.scrub_pii,
+        })
     }
 
     /// Creates an in-memory database (for testing)
@@ -54,7 +148,242 @@ impl SqliteStorage {
         let conn = Connection::open_in_memory()?;
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
         initialize_schema(&conn)?;
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            scrub_pii: false,
+        })
+    }
+
+    /// Applies `scrub-pii` to a URL about to be written or looked up
+    fn stored_url(&self, url: &str) -> String {
+        if self.scrub_pii {
+            scrub_url(url)
+        } else {
+            url.to_string()
+        }
+    }
+
+    /// Applies `scrub-pii` to a referrer URL about to be written
+    fn stored_referrer(&self, referrer: &str) -> String {
+        if self.scrub_pii {
+            hash_url(referrer)
+        } else {
+            referrer.to_string()
+        }
+    }
+
+    /// Runs a query selecting (from, to, discovered run, last seen run) links
+    fn query_links(&self, query: &str) -> StorageResult<Vec<LinkRecord>> {
+        let mut stmt = self.conn.prepare(query)?;
+        let links = stmt
+            .query_map([], |row| {
+                Ok(LinkRecord {
+                    from_page_id: row.get(0)?,
+                    to_page_id: row.get(1)?,
+                    discovered_run: row.get(2)?,
+                    last_seen_run: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(links)
+    }
+
+    /// Gets domain summaries, most pages first
+    fn query_domain_summaries(&self, limit: i64, offset: i64) -> StorageResult<Vec<DomainSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, pages, pages_processed, errors, first_seen, last_seen
+             FROM domain_summaries
+             ORDER BY pages DESC, domain
+             LIMIT ?1 OFFSET ?2",
+        )?;
+        let summaries = stmt
+            .query_map(params![limit, offset], |row| {
+                Ok(DomainSummary {
+                    domain: row.get(0)?,
+                    pages: row.get::<_, i64>(1)? as u64,
+                    pages_processed: row.get::<_, i64>(2)? as u64,
+                    errors: row.get::<_, i64>(3)? as u64,
+                    first_seen: row.get(4)?,
+                    last_seen: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(summaries)
+    }
+}
+
+impl SqliteStorage {
+    /// Prunes stale rows, rebuilds indexes and compacts the database file
+    ///
+    /// Removes frontier entries whose page no longer exists or has reached a
+    /// terminal state, duplicate blacklisted/stubbed referrer records and all
+    /// but the latest `STATE_HISTORY_KEPT` state transitions of each page,
+    /// then runs `REINDEX`, `VACUUM` and `PRAGMA optimize`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MaintenanceReport)` - What was pruned and the size reclaimed
+    /// * `Err(StorageError)` - A maintenance statement failed
+    pub fn run_maintenance(&mut self) -> StorageResult<MaintenanceReport> {
+        let mut report = MaintenanceReport {
+            size_before: self.database_size()?,
+            ..Default::default()
+        };
+
+        let tx = self.conn.transaction()?;
+        report.frontier_pruned = tx.execute(
+            "DELETE FROM frontier WHERE page_id NOT IN
+             (SELECT id FROM pages WHERE state IN (?1, ?2, ?3))",
+            params![
+                PageState::Discovered.to_db_string(),
+                PageState::Queued.to_db_string(),
+                PageState::Fetching.to_db_string()
+            ],
+        )? as u64;
+        for (table, url_column, referrer_column) in [
+            ("blacklisted_urls", "url", "referrer"),
+            ("stubbed_urls", "url", "referrer"),
+            ("blacklisted_referrers", "blacklisted_url", "referrer_url"),
+            ("stubbed_referrers", "stubbed_url", "referrer_url"),
+        ] {
+            report.referrers_pruned += tx.execute(
+                &format!(
+                    "DELETE FROM {0} WHERE id NOT IN
+                     (SELECT MIN(id) FROM {0} GROUP BY {1}, {2})",
+                    table, url_column, referrer_column
+                ),
+                [],
+            )? as u64;
+        }
+        report.history_pruned = tx.execute(
+            "DELETE FROM page_state_history WHERE id IN (
+                 SELECT id FROM (
+                     SELECT id, ROW_NUMBER() OVER (PARTITION BY page_id ORDER BY id DESC) AS age
+                     FROM page_state_history
+                 ) WHERE age > ?1
+             )",
+            params![STATE_HISTORY_KEPT as i64],
+        )? as u64;
+        tx.commit()?;
+
+        self.conn.execute_batch(
+            "
+            REINDEX;
+            VACUUM;
+            PRAGMA optimize;
+        ",
+        )?;
+
+        report.size_after = self.database_size()?;
+        Ok(report)
+    }
+
+    /// Deletes the runs and history the retention policy no longer keeps
+    ///
+    /// Runs older than the newest `keep-runs` are deleted with their
+    /// observations, configuration, progress snapshots and blacklisted/stubbed
+    /// URL records. Unlike `delete_run`, the pages, links and promotions they
+    /// discovered are kept and attributed to the oldest remaining run, so the
+    /// crawl state stays intact. Page observations and events (state
+    /// transitions, progress snapshots) past their maximum age are deleted
+    /// whatever their run.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The retention policy
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RetentionReport)` - What was deleted
+    /// * `Err(StorageError)` - A statement failed
+    pub fn apply_retention(&mut self, config: &RetentionConfig) -> StorageResult<RetentionReport> {
+        let mut report = RetentionReport::default();
+        let cutoff =
+            |days: u32| (Utc::now() - chrono::Duration::days(i64::from(days))).to_rfc3339();
+
+        let tx = self.conn.transaction()?;
+        if config.keep_runs > 0 {
+            let oldest_kept: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM runs ORDER BY id DESC LIMIT 1 OFFSET ?1",
+                    params![i64::from(config.keep_runs) - 1],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(oldest_kept) = oldest_kept {
+                for (table, column) in [
+                    ("pages", "discovered_run"),
+                    ("links", "discovered_run"),
+                    ("domain_promotions", "run_id"),
+                ] {
+                    tx.execute(
+                        &format!("UPDATE {0} SET {1} = ?1 WHERE {1} < ?1", table, column),
+                        params![oldest_kept],
+                    )?;
+                }
+                report.runs_pruned =
+                    tx.execute("DELETE FROM runs WHERE id < ?1", params![oldest_kept])? as u64;
+            }
+        }
+        if config.observation_max_age_days > 0 {
+            report.observations_pruned = tx.execute(
+                "DELETE FROM page_observations WHERE observed_at < ?1",
+                params![cutoff(config.observation_max_age_days)],
+            )? as u64;
+        }
+        if config.event_max_age_days > 0 {
+            let cutoff = cutoff(config.event_max_age_days);
+            report.events_pruned = tx.execute(
+                "DELETE FROM page_state_history WHERE changed_at < ?1",
+                params![cutoff],
+            )? as u64
+                + tx.execute(
+                    "DELETE FROM run_progress WHERE recorded_at < ?1",
+                    params![cutoff],
+                )? as u64;
+        }
+        tx.commit()?;
+
+        Ok(report)
+    }
+
+    /// Checks the crawl invariants of the database
+    ///
+    /// # Returns
+    ///
+    /// One result per check, including the ones that pass
+    pub fn check_integrity(&self) -> StorageResult<Vec<IntegrityIssue>> {
+        Ok(integrity::check_integrity(&self.conn)?)
+    }
+
+    /// Deletes the rows violating repairable crawl invariants
+    ///
+    /// # Returns
+    ///
+    /// The number of rows removed for each repairable check
+    pub fn repair_integrity(&mut self) -> StorageResult<Vec<(IntegrityCheck, u64)>> {
+        Ok(integrity::repair_integrity(&mut self.conn)?)
+    }
+
+    /// Copies the write-ahead log into the database and truncates it
+    ///
+    /// Called when a run completes so the database file is self-contained
+    /// and readers do not have to replay a large log.
+    pub fn checkpoint(&self) -> StorageResult<()> {
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Current size of the database (bytes), excluding the write-ahead log
+    fn database_size(&self) -> StorageResult<u64> {
+        let pages: i64 = self
+            .conn
+            .query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = self
+            .conn
+            .query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok((pages * page_size) as u64)
     }
 }
 
@@ -112,6 +441,60 @@ impl Storage for SqliteStorage {
         Ok(run)
     }
 
+    fn list_runs(&self) -> StorageResult<Vec<RunRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started_at, finished_at, config_hash, status FROM runs ORDER BY id",
+        )?;
+
+        let runs = stmt
+            .query_map([], |row| {
+                Ok(RunRecord {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    config_hash: row.get(3)?,
+                    status: RunStatus::from_db_string(&row.get::<_, String>(4)?)
+                        .unwrap_or(RunStatus::Running),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(runs)
+    }
+
+    fn delete_run(&mut self, run_id: i64) -> StorageResult<RunDeletion> {
+        self.get_run(run_id)?;
+
+        let tx = self.conn.transaction()?;
+        let count = |sql: &str| -> StorageResult<u64> {
+            Ok(tx.query_row(sql, params![run_id], |row| row.get::<_, i64>(0))? as u64)
+        };
+
+        // Count what the ON DELETE rules are about to remove
+        let deletion = RunDeletion {
+            pages: count("SELECT COUNT(*) FROM pages WHERE discovered_run = ?1")?,
+            links: count(
+                "SELECT COUNT(*) FROM links WHERE discovered_run = ?1
+                 OR from_page_id IN (SELECT id FROM pages WHERE discovered_run = ?1)
+                 OR to_page_id IN (SELECT id FROM pages WHERE discovered_run = ?1)",
+            )?,
+            url_records: count(
+                "SELECT (SELECT COUNT(*) FROM blacklisted_urls WHERE discovered_run = ?1)
+                      + (SELECT COUNT(*) FROM blacklisted_referrers WHERE discovered_run = ?1)
+                      + (SELECT COUNT(*) FROM stubbed_urls WHERE discovered_run = ?1)
+                      + (SELECT COUNT(*) FROM stubbed_referrers WHERE discovered_run = ?1)",
+            )?,
+            progress_snapshots: count("SELECT COUNT(*) FROM run_progress WHERE run_id = ?1")?,
+        };
+
+        // Cascades to everything scoped to the run and its pages; pages it
+        // only fetched keep existing with visited_run cleared
+        tx.execute("DELETE FROM runs WHERE id = ?1", params![run_id])?;
+
+        tx.commit()?;
+        Ok(deletion)
+    }
+
     fn update_run_status(&mut self, run_id: i64, status: RunStatus) -> StorageResult<()> {
         self.conn.execute(
             "UPDATE runs SET status = ?1 WHERE id = ?2",
@@ -129,6 +512,79 @@ impl Storage for SqliteStorage {
         Ok(())
     }
 
+    fn save_run_config(
+        &mut self,
+        run_id: i64,
+        config_hash: &str,
+        config_toml: &str,
+    ) -> StorageResult<()> {
+        self.get_run(run_id)?;
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO run_configs (run_id, config_hash, config_toml, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![run_id, config_hash, config_toml, now],
+        )?;
+        Ok(())
+    }
+
+    fn get_run_config(&self, run_id: i64) -> StorageResult<Option<RunConfigRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                "SELECT run_id, config_hash, config_toml, created_at FROM run_configs
+                 WHERE run_id = ?1",
+                params![run_id],
+                |row| {
+                    Ok(RunConfigRecord {
+                        run_id: row.get(0)?,
+                        config_hash: row.get(1)?,
+                        config_toml: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(record)
+    }
+
+    fn record_run_progress(&mut self, snapshot: &ProgressSnapshot) -> StorageResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO run_progress
+             (run_id, recorded_at, pages_processed, frontier_size, error_count, eta_seconds)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                snapshot.run_id,
+                snapshot.recorded_at,
+                snapshot.pages_processed as i64,
+                snapshot.frontier_size as i64,
+                snapshot.error_count as i64,
+                snapshot.eta_seconds.map(|eta| eta as i64)
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_run_progress(&self, run_id: i64) -> StorageResult<Vec<ProgressSnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT run_id, recorded_at, pages_processed, frontier_size, error_count, eta_seconds
+             FROM run_progress WHERE run_id = ?1 ORDER BY recorded_at",
+        )?;
+        let snapshots = stmt
+            .query_map(params![run_id], |row| {
+                Ok(ProgressSnapshot {
+                    run_id: row.get(0)?,
+                    recorded_at: row.get(1)?,
+                    pages_processed: row.get::<_, i64>(2)? as u64,
+                    frontier_size: row.get::<_, i64>(3)? as u64,
+                    error_count: row.get::<_, i64>(4)? as u64,
+                    eta_seconds: row.get::<_, Option<i64>>(5)?.map(|eta| eta as u64),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(snapshots)
+    }
+
     // ===== Page Management =====
 
     fn insert_or_get_page(
@@ -137,6 +593,8 @@ impl Storage for SqliteStorage {
         domain: &str,
         discovered_run: i64,
     ) -> StorageResult<i64> {
+        let url = &self.stored_url(url);
+
         // Try to get existing page
         let existing: Option<i64> = self
             .conn
@@ -191,6 +649,7 @@ impl Storage for SqliteStorage {
     }
 
     fn get_page_by_url(&self, url: &str) -> StorageResult<Option<PageRecord>> {
+        let url = &self.stored_url(url);
         let mut stmt = self.conn.prepare(
             "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
              visited_at, discovered_at, discovered_run, error_message, retry_count
@@ -230,6 +689,14 @@ impl Storage for SqliteStorage {
         content_type: Option<&str>,
         error_message: Option<&str>,
     ) -> StorageResult<()> {
+        // Redirect chains and fetch errors quote the URLs involved
+        let error_message = error_message.map(|message| {
+            if self.scrub_pii {
+                scrub_text(message)
+            } else {
+                message.to_string()
+            }
+        });
         let now = Utc::now().to_rfc3339();
         self.conn.execute(
             "UPDATE pages SET state = ?1, title = ?2, status_code = ?3, content_type = ?4,
@@ -247,456 +714,1843 @@ impl Storage for SqliteStorage {
         Ok(())
     }
 
-    fn increment_retry_count(&mut self, page_id: i64) -> StorageResult<()> {
+    fn mark_page_visited(&mut self, page_id: i64, run_id: i64) -> StorageResult<()> {
         self.conn.execute(
-            "UPDATE pages SET retry_count = retry_count + 1 WHERE id = ?1",
-            params![page_id],
+            "UPDATE pages SET visited_run = ?1 WHERE id = ?2",
+            params![run_id, page_id],
         )?;
         Ok(())
     }
 
-    fn get_pages_by_state(&self, state: PageState) -> StorageResult<Vec<PageRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
-             visited_at, discovered_at, discovered_run, error_message, retry_count
-             FROM pages WHERE state = ?1",
+    fn set_content_hash(&mut self, page_id: i64, content_hash: &str) -> StorageResult<()> {
+        self.conn.execute(
+            "UPDATE pages SET content_hash = ?1 WHERE id = ?2",
+            params![content_hash, page_id],
         )?;
-
-        let pages = stmt
-            .query_map(params![state.to_db_string()], |row| {
-                Ok(PageRecord {
-                    id: row.get(0)?,
-                    url: row.get(1)?,
-                    domain: row.get(2)?,
-                    state: PageState::from_db_string(&row.get::<_, String>(3)?)
-                        .unwrap_or(PageState::Failed),
-                    title: row.get(4)?,
-                    status_code: row.get(5)?,
-                    content_type: row.get(6)?,
-                    last_modified: row.get(7)?,
-                    visited_at: row.get(8)?,
-                    discovered_at: row.get(9)?,
-                    discovered_run: row.get(10)?,
-                    error_message: row.get(11)?,
-                    retry_count: row.get(12)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(pages)
+        Ok(())
     }
 
-    fn get_interrupted_pages(&self) -> StorageResult<Vec<PageRecord>> {
-        self.get_pages_by_state(PageState::Fetching)
+    fn set_template_hash(&mut self, page_id: i64, template_hash: &str) -> StorageResult<()> {
+        self.conn.execute(
+            "UPDATE pages SET template_hash = ?1, error_template = 0 WHERE id = ?2",
+            params![template_hash, page_id],
+        )?;
+        Ok(())
     }
 
-    // ===== Depth Tracking =====
+    fn flag_error_template(
+        &mut self,
+        domain: &str,
+        template_hash: &str,
+        min_pages: u32,
+    ) -> StorageResult<bool> {
+        let pages: u32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pages WHERE domain = ?1 AND template_hash = ?2",
+            params![domain, template_hash],
+            |row| row.get(0),
+        )?;
+        if pages < min_pages {
+            return Ok(false);
+        }
 
-    fn upsert_depth(
+        self.conn.execute(
+            "UPDATE pages SET error_template = 1
+             WHERE domain = ?1 AND template_hash = ?2
+             AND id > (SELECT MIN(id) FROM pages WHERE domain = ?1 AND template_hash = ?2)",
+            params![domain, template_hash],
+        )?;
+        Ok(true)
+    }
+
+    fn set_page_headers(
         &mut self,
         page_id: i64,
-        quality_origin: &str,
-        depth: u32,
+        headers: &[(String, String)],
     ) -> StorageResult<()> {
-        // Try to insert, on conflict keep the minimum depth
-        self.conn.execute(
-            "INSERT INTO page_depths (page_id, quality_origin, depth) VALUES (?1, ?2, ?3)
-             ON CONFLICT(page_id, quality_origin) DO UPDATE SET depth = MIN(depth, excluded.depth)",
-            params![page_id, quality_origin, depth],
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM page_headers WHERE page_id = ?1",
+            params![page_id],
         )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO page_headers (page_id, name, value) VALUES (?1, ?2, ?3)",
+            )?;
+            for (name, value) in headers {
+                stmt.execute(params![page_id, name, value])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    fn get_depths(&self, page_id: i64) -> StorageResult<Vec<DepthRecord>> {
+    fn get_page_headers(&self, page_id: i64) -> StorageResult<Vec<(String, String)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT page_id, quality_origin, depth FROM page_depths WHERE page_id = ?1")?;
+            .prepare("SELECT name, value FROM page_headers WHERE page_id = ?1 ORDER BY name")?;
+        let headers = stmt
+            .query_map(params![page_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(headers)
+    }
 
-        let depths = stmt
-            .query_map(params![page_id], |row| {
-                Ok(DepthRecord {
-                    page_id: row.get(0)?,
-                    quality_origin: row.get(1)?,
-                    depth: row.get(2)?,
+    fn get_header_value_counts(&self, limit: usize) -> StorageResult<Vec<HeaderValueCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, value, domains FROM (
+                 SELECT h.name, h.value, COUNT(DISTINCT p.domain) AS domains,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY h.name
+                            ORDER BY COUNT(DISTINCT p.domain) DESC, h.value
+                        ) AS rank
+                 FROM page_headers h
+                 JOIN pages p ON p.id = h.page_id
+                 GROUP BY h.name, h.value
+             )
+             WHERE rank <= ?1
+             ORDER BY name, rank",
+        )?;
+        let counts = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(HeaderValueCount {
+                    name: row.get(0)?,
+                    value: row.get(1)?,
+                    domains: row.get::<_, i64>(2)? as u64,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
 
-        Ok(depths)
+    fn set_page_anomalies(
+        &mut self,
+        page_id: i64,
+        anomalies: &[(AnomalyKind, String)],
+    ) -> StorageResult<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM page_anomalies WHERE page_id = ?1",
+            params![page_id],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO page_anomalies (page_id, kind, detail) VALUES (?1, ?2, ?3)",
+            )?;
+            for (kind, detail) in anomalies {
+                stmt.execute(params![page_id, kind.as_str(), detail])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
     }
 
-    fn should_crawl(&self, page_id: i64, max_depth: u32) -> StorageResult<bool> {
-        let min_depth: Option<u32> = self
-            .conn
-            .query_row(
-                "SELECT MIN(depth) FROM page_depths WHERE page_id = ?1",
-                params![page_id],
-                |row| row.get(0),
-            )
-            .optional()?
-            .flatten();
+    fn get_page_anomalies(&self, limit: usize) -> StorageResult<Vec<PageAnomaly>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.url, a.kind, a.detail
+             FROM page_anomalies a
+             JOIN pages p ON p.id = a.page_id
+             ORDER BY a.kind, p.url
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
 
-        Ok(min_depth.map(|d| d <= max_depth).unwrap_or(false))
+        let mut anomalies = Vec::new();
+        for row in rows {
+            let (url, kind, detail) = row?;
+            if let Some(kind) = AnomalyKind::from_db_string(&kind) {
+                anomalies.push(PageAnomaly { url, kind, detail });
+            }
+        }
+        Ok(anomalies)
     }
 
-    // ===== Link Management =====
+    fn count_page_anomalies(&self) -> StorageResult<BTreeMap<AnomalyKind, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT kind, COUNT(*) FROM page_anomalies GROUP BY kind")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
 
-    fn insert_link(
+        let mut counts = BTreeMap::new();
+        for row in rows {
+            let (kind, count) = row?;
+            if let Some(kind) = AnomalyKind::from_db_string(&kind) {
+                counts.insert(kind, count as u64);
+            }
+        }
+        Ok(counts)
+    }
+
+    fn set_page_directives(
         &mut self,
-        from_page_id: i64,
-        to_page_id: i64,
-        run_id: i64,
+        page_id: i64,
+        directives: &[PageDirective],
     ) -> StorageResult<()> {
-        self.conn
-            .execute(
-                "INSERT OR IGNORE INTO links (from_page_id, to_page_id, discovered_run) VALUES (?1, ?2, ?3)",
-                params![from_page_id, to_page_id, run_id],
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM page_directives WHERE page_id = ?1",
+            params![page_id],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO page_directives (page_id, source, name, value)
+                 VALUES (?1, ?2, ?3, ?4)",
             )?;
+            for directive in directives {
+                stmt.execute(params![
+                    page_id,
+                    directive.source.as_str(),
+                    directive.name,
+                    directive.value
+                ])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    fn get_outgoing_links(&self, page_id: i64) -> StorageResult<Vec<LinkRecord>> {
+    fn get_page_directives(&self, page_id: i64) -> StorageResult<Vec<PageDirective>> {
         let mut stmt = self.conn.prepare(
-            "SELECT from_page_id, to_page_id, discovered_run FROM links WHERE from_page_id = ?1",
+            "SELECT source, name, value FROM page_directives
+             WHERE page_id = ?1
+             ORDER BY source DESC, name",
         )?;
+        let rows = stmt.query_map(params![page_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
 
-        let links = stmt
-            .query_map(params![page_id], |row| {
-                Ok(LinkRecord {
-                    from_page_id: row.get(0)?,
-                    to_page_id: row.get(1)?,
-                    discovered_run: row.get(2)?,
+        let mut directives = Vec::new();
+        for row in rows {
+            let (source, name, value) = row?;
+            if let Some(source) = DirectiveSource::from_db_string(&source) {
+                directives.push(PageDirective {
+                    source,
+                    name,
+                    value,
+                });
+            }
+        }
+        Ok(directives)
+    }
+
+    fn get_pages_with_directive(&self, name: &str) -> StorageResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT p.url
+             FROM page_directives d
+             JOIN pages p ON p.id = d.page_id
+             WHERE d.name = ?1
+             ORDER BY p.url",
+        )?;
+        let urls = stmt
+            .query_map(params![name.to_ascii_lowercase()], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(urls)
+    }
+
+    fn increment_retry_count(&mut self, page_id: i64, retries: u32) -> StorageResult<()> {
+        self.conn.execute(
+            "UPDATE pages SET retry_count = retry_count + ?2 WHERE id = ?1",
+            params![page_id, retries],
+        )?;
+        Ok(())
+    }
+
+    fn get_most_retried_pages(&self, limit: usize) -> StorageResult<Vec<PageRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
+             visited_at, discovered_at, discovered_run, error_message, retry_count
+             FROM pages WHERE retry_count > 0 AND state IN ({})
+             ORDER BY retry_count DESC, url LIMIT ?1",
+            ERROR_STATES
+        ))?;
+
+        let pages = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(PageRecord {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    domain: row.get(2)?,
+                    state: PageState::from_db_string(&row.get::<_, String>(3)?)
+                        .unwrap_or(PageState::Failed),
+                    title: row.get(4)?,
+                    status_code: row.get(5)?,
+                    content_type: row.get(6)?,
+                    last_modified: row.get(7)?,
+                    visited_at: row.get(8)?,
+                    discovered_at: row.get(9)?,
+                    discovered_run: row.get(10)?,
+                    error_message: row.get(11)?,
+                    retry_count: row.get(12)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(links)
+        Ok(pages)
     }
 
-    fn get_incoming_links(&self, page_id: i64) -> StorageResult<Vec<LinkRecord>> {
+    fn get_pages_by_state(&self, state: PageState) -> StorageResult<Vec<PageRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT from_page_id, to_page_id, discovered_run FROM links WHERE to_page_id = ?1",
+            "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
+             visited_at, discovered_at, discovered_run, error_message, retry_count
+             FROM pages WHERE state = ?1",
         )?;
 
-        let links = stmt
-            .query_map(params![page_id], |row| {
-                Ok(LinkRecord {
-                    from_page_id: row.get(0)?,
-                    to_page_id: row.get(1)?,
-                    discovered_run: row.get(2)?,
+        let pages = stmt
+            .query_map(params![state.to_db_string()], |row| {
+                Ok(PageRecord {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    domain: row.get(2)?,
+                    state: PageState::from_db_string(&row.get::<_, String>(3)?)
+                        .unwrap_or(PageState::Failed),
+                    title: row.get(4)?,
+                    status_code: row.get(5)?,
+                    content_type: row.get(6)?,
+                    last_modified: row.get(7)?,
+                    visited_at: row.get(8)?,
+                    discovered_at: row.get(9)?,
+                    discovered_run: row.get(10)?,
+                    error_message: row.get(11)?,
+                    retry_count: row.get(12)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(links)
-    }
-
-    fn count_links(&self) -> StorageResult<u64> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM links", [], |row| row.get(0))?;
-        Ok(count as u64)
+        Ok(pages)
     }
 
-    // ===== Frontier Management =====
-
-    fn add_to_frontier(&mut self, page_id: i64, priority: u32) -> StorageResult<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO frontier (page_id, priority) VALUES (?1, ?2)",
-            params![page_id, priority],
+    fn get_domain_pages_by_state(
+        &self,
+        domain: &str,
+        state: PageState,
+    ) -> StorageResult<Vec<PageRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
+             visited_at, discovered_at, discovered_run, error_message, retry_count
+             FROM pages WHERE domain = ?1 AND state = ?2 ORDER BY id",
         )?;
-        Ok(())
-    }
-
-    fn pop_from_frontier(&mut self) -> StorageResult<Option<i64>> {
-        let page_id: Option<i64> = self
-            .conn
-            .query_row(
-                "SELECT page_id FROM frontier ORDER BY priority ASC LIMIT 1",
-                [],
-                |row| row.get(0),
-            )
-            .optional()?;
 
-        if let Some(id) = page_id {
-            self.conn
-                .execute("DELETE FROM frontier WHERE page_id = ?1", params![id])?;
-        }
+        let pages = stmt
+            .query_map(params![domain, state.to_db_string()], |row| {
+                Ok(PageRecord {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    domain: row.get(2)?,
+                    state: PageState::from_db_string(&row.get::<_, String>(3)?)
+                        .unwrap_or(PageState::Failed),
+                    title: row.get(4)?,
+                    status_code: row.get(5)?,
+                    content_type: row.get(6)?,
+                    last_modified: row.get(7)?,
+                    visited_at: row.get(8)?,
+                    discovered_at: row.get(9)?,
+                    discovered_run: row.get(10)?,
+                    error_message: row.get(11)?,
+                    retry_count: row.get(12)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(page_id)
+        Ok(pages)
     }
 
-    fn load_frontier(&self) -> StorageResult<Vec<(i64, u32)>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT page_id, priority FROM frontier ORDER BY priority ASC")?;
+    fn get_domain_pages_paged(
+        &self,
+        domain: &str,
+        state: Option<PageState>,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<PageRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, url, domain, state, title, status_code, content_type, last_modified,
+             visited_at, discovered_at, discovered_run, error_message, retry_count
+             FROM pages WHERE domain = ?1 AND (?2 IS NULL OR state = ?2)
+             ORDER BY id LIMIT ?3 OFFSET ?4",
+        )?;
 
-        let frontier = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        let state = state.map(|state| state.to_db_string());
+        let pages = stmt
+            .query_map(params![domain, state, limit as i64, offset as i64], |row| {
+                Ok(PageRecord {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    domain: row.get(2)?,
+                    state: PageState::from_db_string(&row.get::<_, String>(3)?)
+                        .unwrap_or(PageState::Failed),
+                    title: row.get(4)?,
+                    status_code: row.get(5)?,
+                    content_type: row.get(6)?,
+                    last_modified: row.get(7)?,
+                    visited_at: row.get(8)?,
+                    discovered_at: row.get(9)?,
+                    discovered_run: row.get(10)?,
+                    error_message: row.get(11)?,
+                    retry_count: row.get(12)?,
+                })
+            })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(frontier)
+        Ok(pages)
     }
 
-    fn clear_frontier(&mut self) -> StorageResult<()> {
-        self.conn.execute("DELETE FROM frontier", [])?;
-        Ok(())
+    fn count_domain_pages(&self, domain: &str, state: Option<PageState>) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pages WHERE domain = ?1 AND (?2 IS NULL OR state = ?2)",
+            params![domain, state.map(|state| state.to_db_string())],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
     }
 
-    // ===== Domain State Persistence =====
+    fn get_interrupted_pages(&self) -> StorageResult<Vec<PageRecord>> {
+        self.get_pages_by_state(PageState::Fetching)
+    }
 
-    fn load_domain_states(&self) -> StorageResult<HashMap<String, DomainState>> {
+    fn get_state_history(&self, page_id: i64) -> StorageResult<Vec<StateTransition>> {
         let mut stmt = self.conn.prepare(
-            "SELECT domain, request_count, rate_limited, robots_txt, robots_fetched_at, last_request_time
-             FROM domain_states"
+            "SELECT page_id, from_state, to_state, run_id, changed_at
+             FROM page_state_history WHERE page_id = ?1 ORDER BY id",
         )?;
 
-        let mut states = HashMap::new();
-        let rows = stmt.query_map([], |row| {
-            let domain: String = row.get(0)?;
-            let request_count: u32 = row.get(1)?;
-            let rate_limited_int: i32 = row.get(2)?;
-            let robots_txt: Option<String> = row.get(3)?;
-            let robots_fetched_at: Option<String> = row.get(4)?;
-            let _last_request_time: Option<String> = row.get(5)?;
-
-            let robots = if let (Some(content), Some(fetched_str)) = (robots_txt, robots_fetched_at)
-            {
-                if let Ok(fetched_at) = fetched_str.parse::<DateTime<Utc>>() {
-                    Some(CachedRobots {
-                        content,
-                        fetched_at,
-                    })
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+        let history = stmt
+            .query_map(params![page_id], |row| {
+                Ok(StateTransition {
+                    page_id: row.get(0)?,
+                    from_state: row
+                        .get::<_, Option<String>>(1)?
+                        .and_then(|state| PageState::from_db_string(&state)),
+                    to_state: PageState::from_db_string(&row.get::<_, String>(2)?)
+                        .unwrap_or(PageState::Failed),
+                    run_id: row.get(3)?,
+                    changed_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-            let state = DomainState {
-                request_count,
-                last_request_time: None, // We don't persist Instant, will be set on first use
-                rate_limited: rate_limited_int != 0,
-                robots_txt: robots.clone(),
-                robots_fetched_at: robots.as_ref().map(|r| r.fetched_at),
-            };
+        Ok(history)
+    }
 
-            Ok((domain, state))
-        })?;
+    fn record_page_observation(
+        &mut self,
+        page_id: i64,
+        run_id: i64,
+        final_url: Option<&str>,
+    ) -> StorageResult<()> {
+        let final_url = final_url.map(|url| self.stored_url(url));
+        // Bodies are only hashed when processed; an older hash is not this run's
+        self.conn.execute(
+            "INSERT OR REPLACE INTO page_observations
+             (page_id, run_id, observed_at, state, status_code, title, final_url,
+              content_hash, error_message)
+             SELECT id, ?2, ?3, state, status_code, title, NULLIF(?4, url),
+                    CASE WHEN state = 'processed' THEN content_hash END, error_message
+             FROM pages WHERE id = ?1",
+            params![page_id, run_id, Utc::now().to_rfc3339(), final_url],
+        )?;
+        Ok(())
+    }
 
-        for row in rows {
-            let (domain, state) = row?;
-            states.insert(domain, state);
-        }
+    fn get_page_observations(&self, page_id: i64) -> StorageResult<Vec<PageObservation>> {
+        query_observations(&self.conn, "o.page_id = ?1 ORDER BY o.run_id", page_id)
+    }
 
-        Ok(states)
+    fn get_run_observations(&self, run_id: i64) -> StorageResult<Vec<PageObservation>> {
+        query_observations(&self.conn, "o.run_id = ?1 ORDER BY p.url", run_id)
     }
 
-    fn save_domain_states(&mut self, states: &HashMap<String, DomainState>) -> StorageResult<()> {
-        // Clear existing domain states
-        self.conn.execute("DELETE FROM domain_states", [])?;
+    // ===== Depth Tracking =====
 
-        // Insert all current states
-        for (domain, state) in states {
-            self.update_domain_state(domain, state)?;
-        }
+    fn upsert_depth(
+        &mut self,
+        page_id: i64,
+        quality_origin: &str,
+        depth: u32,
+    ) -> StorageResult<()> {
+        self.upsert_depth_with_hops(page_id, quality_origin, depth, 0)
+    }
 
+    fn upsert_depth_with_hops(
+        &mut self,
+        page_id: i64,
+        quality_origin: &str,
+        depth: u32,
+        hops: u32,
+    ) -> StorageResult<()> {
+        // Try to insert, on conflict keep the minimum depth and hops
+        self.conn.execute(
+            "INSERT INTO page_depths (page_id, quality_origin, depth, hops) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(page_id, quality_origin) DO UPDATE SET
+                depth = MIN(depth, excluded.depth),
+                hops = MIN(hops, excluded.hops)",
+            params![page_id, quality_origin, depth, hops],
+        )?;
         Ok(())
     }
 
-    fn update_domain_state(&mut self, domain: &str, state: &DomainState) -> StorageResult<()> {
-        let rate_limited_int = if state.rate_limited { 1 } else { 0 };
+    fn get_depths(&self, page_id: i64) -> StorageResult<Vec<DepthRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT page_id, quality_origin, depth, hops FROM page_depths WHERE page_id = ?1",
+        )?;
 
-        let (robots_txt, robots_fetched_at) = if let Some(robots) = &state.robots_txt {
-            (
-                Some(robots.content.clone()),
-                Some(robots.fetched_at.to_rfc3339()),
-            )
-        } else {
-            (None, None)
-        };
+        let depths = stmt
+            .query_map(params![page_id], |row| {
+                Ok(DepthRecord {
+                    page_id: row.get(0)?,
+                    quality_origin: row.get(1)?,
+                    depth: row.get(2)?,
+                    hops: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        // Note: We don't persist last_request_time (Instant) as it's not serializable
-        // It will be reset when domain state is loaded
-        self.conn.execute(
-            "INSERT OR REPLACE INTO domain_states
-             (domain, request_count, rate_limited, robots_txt, robots_fetched_at, last_request_time)
-             VALUES (?1, ?2, ?3, ?4, ?5, NULL)",
-            params![
-                domain,
-                state.request_count,
-                rate_limited_int,
-                robots_txt,
-                robots_fetched_at,
-            ],
+        Ok(depths)
+    }
+
+    fn should_crawl(
+        &self,
+        page_id: i64,
+        max_depth: u32,
+        max_hops: Option<u32>,
+    ) -> StorageResult<bool> {
+        let within: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM page_depths
+             WHERE page_id = ?1 AND depth <= ?2 AND (?3 IS NULL OR hops <= ?3)",
+            params![page_id, max_depth, max_hops],
+            |row| row.get(0),
         )?;
 
-        Ok(())
+        Ok(within > 0)
     }
 
-    // ===== Blacklist/Stub Tracking =====
+    // ===== Link Management =====
 
-    fn record_blacklisted(&mut self, url: &str, referrer: &str, run_id: i64) -> StorageResult<()> {
-        let now = Utc::now().to_rfc3339();
+    fn insert_link(
+        &mut self,
+        from_page_id: i64,
+        to_page_id: i64,
+        run_id: i64,
+    ) -> StorageResult<()> {
+        self.insert_link_of_kind(from_page_id, to_page_id, run_id, LinkKind::Anchor)
+    }
+
+    fn insert_link_of_kind(
+        &mut self,
+        from_page_id: i64,
+        to_page_id: i64,
+        run_id: i64,
+        kind: LinkKind,
+    ) -> StorageResult<()> {
         self.conn.execute(
-            "INSERT INTO blacklisted_urls (url, referrer, discovered_run, discovered_at) VALUES (?1, ?2, ?3, ?4)",
-            params![url, referrer, run_id, now],
+            "INSERT INTO links (from_page_id, to_page_id, discovered_run, kind, last_seen_run)
+             VALUES (?1, ?2, ?3, ?4, ?3)
+             ON CONFLICT(from_page_id, to_page_id) DO UPDATE SET last_seen_run = ?3",
+            params![from_page_id, to_page_id, run_id, kind.as_str()],
         )?;
         Ok(())
     }
 
-    fn record_stubbed(&mut self, url: &str, referrer: &str, run_id: i64) -> StorageResult<()> {
-        let now = Utc::now().to_rfc3339();
-        self.conn.execute(
-            "INSERT INTO stubbed_urls (url, referrer, discovered_run, discovered_at) VALUES (?1, ?2, ?3, ?4)",
-            params![url, referrer, run_id, now],
+    fn get_disappeared_links(
+        &self,
+        run_id: i64,
+        limit: usize,
+    ) -> StorageResult<Vec<DisappearedLink>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT f.url, t.url, l.discovered_run, COALESCE(l.last_seen_run, l.discovered_run)
+             {}
+             ORDER BY f.url, t.url
+             LIMIT ?2",
+            DISAPPEARED_LINKS_FROM
+        ))?;
+        let links = stmt
+            .query_map(params![run_id, limit as i64], |row| {
+                Ok(DisappearedLink {
+                    from_url: row.get(0)?,
+                    to_url: row.get(1)?,
+                    discovered_run: row.get(2)?,
+                    last_seen_run: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(links)
+    }
+
+    fn count_disappeared_links(&self, run_id: i64) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) {}", DISAPPEARED_LINKS_FROM),
+            params![run_id],
+            |row| row.get(0),
         )?;
-        Ok(())
+        Ok(count as u64)
     }
 
-    fn get_blacklisted_urls(&self) -> StorageResult<Vec<(String, u32)>> {
+    fn get_outgoing_links(&self, page_id: i64) -> StorageResult<Vec<LinkRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT url, COUNT(*) as count FROM blacklisted_urls GROUP BY url ORDER BY count DESC",
+            "SELECT from_page_id, to_page_id, discovered_run,
+                    COALESCE(last_seen_run, discovered_run)
+             FROM links WHERE from_page_id = ?1",
         )?;
 
-        let urls = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u32)))?
+        let links = stmt
+            .query_map(params![page_id], |row| {
+                Ok(LinkRecord {
+                    from_page_id: row.get(0)?,
+                    to_page_id: row.get(1)?,
+                    discovered_run: row.get(2)?,
+                    last_seen_run: row.get(3)?,
+                })
+            })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(urls)
+        Ok(links)
     }
 
-    fn get_stubbed_urls(&self) -> StorageResult<Vec<(String, u32)>> {
+    fn get_incoming_links(&self, page_id: i64) -> StorageResult<Vec<LinkRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT url, COUNT(*) as count FROM stubbed_urls GROUP BY url ORDER BY count DESC",
+            "SELECT from_page_id, to_page_id, discovered_run,
+                    COALESCE(last_seen_run, discovered_run)
+             FROM links WHERE to_page_id = ?1",
         )?;
 
-        let urls = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u32)))?
+        let links = stmt
+            .query_map(params![page_id], |row| {
+                Ok(LinkRecord {
+                    from_page_id: row.get(0)?,
+                    to_page_id: row.get(1)?,
+                    discovered_run: row.get(2)?,
+                    last_seen_run: row.get(3)?,
+                })
+            })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(urls)
-    }
-
-    // ===== Statistics =====
-
-    fn count_pages_by_state(&self, state: PageState) -> StorageResult<u64> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM pages WHERE state = ?1",
-            params![state.to_db_string()],
-            |row| row.get(0),
-        )?;
-        Ok(count as u64)
+        Ok(links)
     }
 
-    fn count_total_pages(&self) -> StorageResult<u64> {
+    fn count_links(&self) -> StorageResult<u64> {
         let count: i64 = self
             .conn
-            .query_row("SELECT COUNT(*) FROM pages", [], |row| row.get(0))?;
-        Ok(count as u64)
-    }
-
-    fn count_unique_domains(&self) -> StorageResult<u64> {
-        let count: i64 =
-            self.conn
-                .query_row("SELECT COUNT(DISTINCT domain) FROM pages", [], |row| {
-                    row.get(0)
-                })?;
+            .query_row("SELECT COUNT(*) FROM links", [], |row| row.get(0))?;
         Ok(count as u64)
     }
 
-    fn get_error_summary(&self) -> StorageResult<HashMap<PageState, u64>> {
+    fn count_links_by_kind(&self) -> StorageResult<BTreeMap<LinkKind, u64>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT state, COUNT(*) FROM pages GROUP BY state")?;
-
-        let mut summary = HashMap::new();
+            .prepare("SELECT kind, COUNT(*) FROM links GROUP BY kind")?;
         let rows = stmt.query_map([], |row| {
-            let state_str: String = row.get(0)?;
-            let count: i64 = row.get(1)?;
-            Ok((state_str, count))
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
 
+        let mut counts = BTreeMap::new();
         for row in rows {
-            let (state_str, count) = row?;
-            if let Some(state) = PageState::from_db_string(&state_str) {
-                if state.is_error() {
-                    summary.insert(state, count as u64);
-                }
+            let (kind, count) = row?;
+            if let Some(kind) = LinkKind::from_db_string(&kind) {
+                counts.insert(kind, count as u64);
             }
         }
+        Ok(counts)
+    }
 
-        Ok(summary)
+    fn get_all_links(&self) -> StorageResult<Vec<LinkRecord>> {
+        self.query_links(
+            "SELECT from_page_id, to_page_id, discovered_run,
+                    COALESCE(last_seen_run, discovered_run)
+             FROM links ORDER BY id",
+        )
     }
 
-    fn get_rate_limited_domains(&self) -> StorageResult<Vec<String>> {
+    fn get_graph_links(&self) -> StorageResult<Vec<LinkRecord>> {
+        self.query_links(
+            "SELECT l.from_page_id, l.to_page_id, l.discovered_run,
+                    COALESCE(l.last_seen_run, l.discovered_run)
+             FROM links l
+             JOIN pages src ON src.id = l.from_page_id
+             WHERE src.error_template = 0
+             ORDER BY l.id",
+        )
+    }
+
+    // ===== Frontier Management =====
+
+    fn add_to_frontier(&mut self, page_id: i64, priority: u32) -> StorageResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO frontier (page_id, priority) VALUES (?1, ?2)",
+            params![page_id, priority],
+        )?;
+        Ok(())
+    }
+
+    fn pop_from_frontier(&mut self) -> StorageResult<Option<i64>> {
+        let page_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT page_id FROM frontier ORDER BY priority ASC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(id) = page_id {
+            self.conn
+                .execute("DELETE FROM frontier WHERE page_id = ?1", params![id])?;
+        }
+
+        Ok(page_id)
+    }
+
+    fn load_frontier(&self) -> StorageResult<Vec<(i64, u32)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT DISTINCT domain FROM pages WHERE state = ?1")?;
+            .prepare("SELECT page_id, priority FROM frontier ORDER BY priority ASC")?;
 
-        let domains = stmt
-            .query_map(params![PageState::RateLimited.to_db_string()], |row| {
-                row.get(0)
-            })?
+        let frontier = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(domains)
+        Ok(frontier)
     }
 
-    fn get_depth_breakdown(&self) -> StorageResult<HashMap<u32, usize>> {
-        let query = "
-            SELECT depth, COUNT(DISTINCT page_id) as count
-            FROM page_depths
-            GROUP BY depth
-            ORDER BY depth
-        ";
+    fn get_frontier_priority(&self, page_id: i64) -> StorageResult<Option<u32>> {
+        let priority = self
+            .conn
+            .query_row(
+                "SELECT priority FROM frontier WHERE page_id = ?1",
+                params![page_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(priority)
+    }
 
-        let mut stmt = self.conn.prepare(query)?;
+    fn clear_frontier(&mut self) -> StorageResult<()> {
+        self.conn.execute("DELETE FROM frontier", [])?;
+        Ok(())
+    }
+
+    fn remove_from_frontier(&mut self, page_id: i64) -> StorageResult<()> {
+        self.conn
+            .execute("DELETE FROM frontier WHERE page_id = ?1", params![page_id])?;
+        Ok(())
+    }
+
+    fn get_frontier_referrer_counts(&self) -> StorageResult<HashMap<i64, u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.page_id, COUNT(DISTINCT l.from_page_id)
+             FROM frontier f
+             LEFT JOIN links l ON l.to_page_id = f.page_id
+             GROUP BY f.page_id",
+        )?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(counts)
+    }
+
+    fn update_frontier_priorities(&mut self, priorities: &[(i64, u32)]) -> StorageResult<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE frontier SET priority = ?1 WHERE page_id = ?2")?;
+            for (page_id, priority) in priorities {
+                stmt.execute(params![priority, page_id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // ===== Domain State Persistence =====
+
+    fn load_domain_states(&self) -> StorageResult<HashMap<String, DomainState>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, request_count, rate_limited, last_request_time,
+                    head_failures, consecutive_failures, circuit_trips,
+                    robots_failures, robots_error, delay_multiplier
+             FROM domain_states",
+        )?;
+
+        let mut states = HashMap::new();
         let rows = stmt.query_map([], |row| {
-            Ok((row.get::<_, u32>(0)?, row.get::<_, usize>(1)?))
+            let domain: String = row.get(0)?;
+            let request_count: u32 = row.get(1)?;
+            let rate_limited_int: i32 = row.get(2)?;
+            let _last_request_time: Option<String> = row.get(3)?;
+            let head_failures: u32 = row.get(4)?;
+            let consecutive_failures: u32 = row.get(5)?;
+            let circuit_trips: u32 = row.get(6)?;
+            let robots_failures: u32 = row.get(7)?;
+            let robots_error: Option<String> = row.get(8)?;
+            let delay_multiplier: u32 = row.get(9)?;
+
+            let state = DomainState {
+                request_count,
+                last_request_time: None, // We don't persist Instant, will be set on first use
+                request_jitter: Duration::ZERO,
+                rate_limited: rate_limited_int != 0,
+                delay_multiplier,
+                visit_window: None, // Re-derived from robots.txt when the domain is next visited
+                head_failures,
+                consecutive_failures,
+                circuit_open_until: None, // A resumed crawl tries the domain again
+                circuit_trips,
+                robots_failures,
+                robots_error,
+                robots_streak: 0, // A resumed crawl fetches robots.txt again
+                robots_retry_until: None,
+                discovered: false, // Set again as the domain's URLs are queued
+            };
+
+            Ok((domain, state))
         })?;
 
-        let mut breakdown = HashMap::new();
         for row in rows {
-            let (depth, count) = row?;
-            breakdown.insert(depth, count);
+            let (domain, state) = row?;
+            states.insert(domain, state);
         }
 
-        Ok(breakdown)
+        Ok(states)
     }
 
-    fn get_discovered_domains(&self) -> StorageResult<Vec<String>> {
-        let query = "
-            SELECT DISTINCT domain
-            FROM pages
-            ORDER BY domain
-        ";
-
-        let mut stmt = self.conn.prepare(query)?;
-        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    fn save_domain_states(&mut self, states: &HashMap<String, DomainState>) -> StorageResult<()> {
+        // Clear existing domain states
+        self.conn.execute("DELETE FROM domain_states", [])?;
 
-        let mut domains = Vec::new();
-        for row in rows {
-            domains.push(row?);
+        // Insert all current states
+        for (domain, state) in states {
+            self.update_domain_state(domain, state)?;
         }
 
-        Ok(domains)
+        Ok(())
     }
-}
 
-/// Initializes or opens a database at the given path
-///
-/// # Arguments
-///
-/// * `path` - Path to the SQLite database file
-///
-/// # Returns
-///
-/// * `Ok(Connection)` - Successfully opened/created database
-/// * `Err(rusqlite::Error)` - Failed to open database
-pub fn init_database(path: &Path) -> Result<Connection, rusqlite::Error> {
-    let conn = Connection::open(path)?;
+    fn update_domain_state(&mut self, domain: &str, state: &DomainState) -> StorageResult<()> {
+        let rate_limited_int = if state.rate_limited { 1 } else { 0 };
+
+        // Note: We don't persist last_request_time (Instant) as it's not serializable
+        // It will be reset when domain state is loaded. robots.txt is not
+        // persisted either: each run fetches it again.
+        self.conn.execute(
+            "INSERT OR REPLACE INTO domain_states
+             (domain, request_count, rate_limited, last_request_time,
+              head_failures, consecutive_failures, circuit_trips,
+              robots_failures, robots_error, delay_multiplier)
+             VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                domain,
+                state.request_count,
+                rate_limited_int,
+                state.head_failures,
+                state.consecutive_failures,
+                state.circuit_trips,
+                state.robots_failures,
+                state.robots_error,
+                state.delay_multiplier,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    // ===== Blacklist/Stub Tracking =====
+
+    fn record_blacklisted(&mut self, url: &str, referrer: &str, run_id: i64) -> StorageResult<()> {
+        let (url, referrer) = (self.stored_url(url), self.stored_referrer(referrer));
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO blacklisted_urls (url, referrer, discovered_run, discovered_at) VALUES (?1, ?2, ?3, ?4)",
+            params![url, referrer, run_id, now],
+        )?;
+        Ok(())
+    }
+
+    fn record_stubbed(&mut self, url: &str, referrer: &str, run_id: i64) -> StorageResult<()> {
+        let (url, referrer) = (self.stored_url(url), self.stored_referrer(referrer));
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO stubbed_urls (url, referrer, discovered_run, discovered_at) VALUES (?1, ?2, ?3, ?4)",
+            params![url, referrer, run_id, now],
+        )?;
+        Ok(())
+    }
+
+    fn get_blacklisted_urls(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<(String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, COUNT(*) as count FROM blacklisted_urls GROUP BY url
+             ORDER BY count DESC, url LIMIT ?1 OFFSET ?2",
+        )?;
+
+        let urls = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u32))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(urls)
+    }
+
+    fn get_stubbed_urls(&self, limit: usize, offset: usize) -> StorageResult<Vec<(String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, COUNT(*) as count FROM stubbed_urls GROUP BY url
+             ORDER BY count DESC, url LIMIT ?1 OFFSET ?2",
+        )?;
+
+        let urls = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u32))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(urls)
+    }
+
+    fn get_stub_references(&self, run_id: Option<i64>) -> StorageResult<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT url, referrer FROM stubbed_urls
+             WHERE ?1 IS NULL OR discovered_run = ?1
+             ORDER BY url, referrer",
+        )?;
+        let references = stmt
+            .query_map(params![run_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(references)
+    }
+
+    fn get_filter_hits(&self, run_id: Option<i64>) -> StorageResult<Vec<FilterHit>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT 0, url, referrer, discovered_run, discovered_at, id FROM blacklisted_urls
+             WHERE ?1 IS NULL OR discovered_run = ?1
+             UNION ALL
+             SELECT 1, url, referrer, discovered_run, discovered_at, id FROM stubbed_urls
+             WHERE ?1 IS NULL OR discovered_run = ?1
+             ORDER BY 5, 1, 6",
+        )?;
+        let hits = stmt
+            .query_map(params![run_id], |row| {
+                let kind = if row.get::<_, i64>(0)? == 0 {
+                    FilterKind::Blacklisted
+                } else {
+                    FilterKind::Stubbed
+                };
+                Ok(FilterHit {
+                    kind,
+                    url: row.get(1)?,
+                    referrer: row.get(2)?,
+                    run_id: row.get(3)?,
+                    discovered_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(hits)
+    }
+
+    // ===== Statistics =====
+
+    fn count_pages_by_state(&self, state: PageState) -> StorageResult<u64> {
+        let count: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT pages FROM state_counts WHERE state = ?1",
+                params![state.to_db_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(count.unwrap_or(0) as u64)
+    }
+
+    fn get_state_counts(&self) -> StorageResult<HashMap<PageState, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT state, pages FROM state_counts WHERE pages > 0")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(state, count)| {
+                PageState::from_db_string(&state).map(|state| (state, count as u64))
+            })
+            .collect())
+    }
+
+    fn count_total_pages(&self) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(pages), 0) FROM domain_summaries",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn count_unique_domains(&self) -> StorageResult<u64> {
+        let count: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM domain_summaries", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(count as u64)
+    }
+
+    fn get_domain_summaries(&self) -> StorageResult<Vec<DomainSummary>> {
+        // A negative limit is no limit to SQLite
+        self.query_domain_summaries(-1, 0)
+    }
+
+    fn get_domain_summaries_paged(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<DomainSummary>> {
+        self.query_domain_summaries(limit as i64, offset as i64)
+    }
+
+    fn get_error_summary(&self) -> StorageResult<HashMap<PageState, u64>> {
+        let mut summary = self.get_state_counts()?;
+        summary.retain(|state, _| state.is_error());
+        Ok(summary)
+    }
+
+    fn get_rate_limited_domains(&self) -> StorageResult<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT domain FROM pages WHERE state = ?1")?;
+
+        let domains = stmt
+            .query_map(params![PageState::RateLimited.to_db_string()], |row| {
+                row.get(0)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(domains)
+    }
+
+    fn get_degraded_domains(&self) -> StorageResult<Vec<(String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, circuit_trips FROM domain_states
+             WHERE circuit_trips > 0
+             ORDER BY circuit_trips DESC, domain",
+        )?;
+
+        let domains = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(domains)
+    }
+
+    fn get_slowed_domains(&self) -> StorageResult<Vec<(String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, delay_multiplier FROM domain_states
+             WHERE delay_multiplier > 1
+             ORDER BY delay_multiplier DESC, domain",
+        )?;
+
+        let domains = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(domains)
+    }
+
+    fn get_robots_failures(&self) -> StorageResult<Vec<RobotsFailure>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, robots_failures, robots_error FROM domain_states
+             WHERE robots_failures > 0
+             ORDER BY robots_failures DESC, domain",
+        )?;
+
+        let failures = stmt
+            .query_map([], |row| {
+                Ok(RobotsFailure {
+                    domain: row.get(0)?,
+                    failures: row.get(1)?,
+                    last_error: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(failures)
+    }
+
+    fn count_pages_discovered_in_run(&self, run_id: i64) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pages WHERE discovered_run = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn count_pages_visited_in_run(&self, run_id: i64) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pages WHERE visited_run = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn count_pages_visited_in_run_by_domain(
+        &self,
+        run_id: i64,
+    ) -> StorageResult<BTreeMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT domain, COUNT(*) FROM pages WHERE visited_run = ?1 GROUP BY domain")?;
+        let counts = stmt
+            .query_map(params![run_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })?
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        Ok(counts)
+    }
+
+    fn count_links_discovered_in_run(&self, run_id: i64) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM links WHERE discovered_run = ?1",
+            params![run_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn get_run_state_counts(&self, run_id: i64) -> StorageResult<HashMap<PageState, u64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT state, COUNT(*) FROM pages
+             WHERE discovered_run = ?1 OR visited_run = ?1
+             GROUP BY state",
+        )?;
+
+        let mut counts = HashMap::new();
+        let rows = stmt.query_map(params![run_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (state_str, count) = row?;
+            if let Some(state) = PageState::from_db_string(&state_str) {
+                counts.insert(state, count as u64);
+            }
+        }
+
+        Ok(counts)
+    }
+
+    fn get_run_domains(&self, run_id: i64) -> StorageResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT domain FROM pages
+             WHERE discovered_run = ?1 OR visited_run = ?1
+             ORDER BY domain",
+        )?;
+        let domains = stmt
+            .query_map(params![run_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(domains)
+    }
+
+    fn get_depth_breakdown(&self) -> StorageResult<HashMap<u32, usize>> {
+        let query = "
+            SELECT depth, COUNT(DISTINCT page_id) as count
+            FROM page_depths
+            GROUP BY depth
+            ORDER BY depth
+        ";
+
+        let mut stmt = self.conn.prepare(query)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, usize>(1)?))
+        })?;
+
+        let mut breakdown = HashMap::new();
+        for row in rows {
+            let (depth, count) = row?;
+            breakdown.insert(depth, count);
+        }
+
+        Ok(breakdown)
+    }
+
+    fn get_run_depth_breakdown(&self, run_id: i64) -> StorageResult<HashMap<u32, usize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pd.depth, COUNT(DISTINCT pd.page_id)
+             FROM page_depths pd
+             JOIN pages p ON p.id = pd.page_id
+             WHERE p.discovered_run = ?1 OR p.visited_run = ?1
+             GROUP BY pd.depth",
+        )?;
+
+        let mut breakdown = HashMap::new();
+        let rows = stmt.query_map(params![run_id], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, usize>(1)?))
+        })?;
+        for row in rows {
+            let (depth, count) = row?;
+            breakdown.insert(depth, count);
+        }
+
+        Ok(breakdown)
+    }
+
+    fn get_discovered_domains(&self) -> StorageResult<Vec<String>> {
+        let query = "
+            SELECT DISTINCT domain
+            FROM pages
+            ORDER BY domain
+        ";
+
+        let mut stmt = self.conn.prepare(query)?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut domains = Vec::new();
+        for row in rows {
+            domains.push(row?);
+        }
+
+        Ok(domains)
+    }
+
+    fn get_quality_origins(&self) -> StorageResult<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT quality_origin FROM page_depths ORDER BY quality_origin")?;
+        let origins = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(origins)
+    }
+
+    fn get_page_fingerprints(&self) -> StorageResult<Vec<PageFingerprint>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT domain, title, content_hash FROM pages WHERE state = ?1")?;
+
+        let fingerprints = stmt
+            .query_map(params![PageState::Processed.to_db_string()], |row| {
+                Ok(PageFingerprint {
+                    domain: row.get(0)?,
+                    title: row.get(1)?,
+                    content_hash: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(fingerprints)
+    }
+
+    fn get_error_templates(&self) -> StorageResult<Vec<ErrorTemplate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, template_hash, COUNT(*), MIN(url)
+             FROM pages
+             WHERE template_hash IS NOT NULL
+             GROUP BY domain, template_hash
+             HAVING SUM(error_template) > 0
+             ORDER BY COUNT(*) DESC, domain",
+        )?;
+        let templates = stmt
+            .query_map([], |row| {
+                Ok(ErrorTemplate {
+                    domain: row.get(0)?,
+                    template_hash: row.get(1)?,
+                    pages: row.get(2)?,
+                    example_url: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(templates)
+    }
+
+    fn get_error_template_pages(&self) -> StorageResult<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM pages WHERE error_template = 1 ORDER BY id")?;
+        let pages = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(pages)
+    }
+
+    fn get_cross_domain_links(&self) -> StorageResult<Vec<(String, String)>> {
+        let query = "
+            SELECT DISTINCT src.domain, dst.url
+            FROM links l
+            JOIN pages src ON src.id = l.from_page_id
+            JOIN pages dst ON dst.id = l.to_page_id
+            WHERE src.domain != dst.domain
+            AND src.error_template = 0
+        ";
+
+        let mut stmt = self.conn.prepare(query)?;
+        let links = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(links)
+    }
+
+    fn get_domain_links(&self) -> StorageResult<Vec<(String, String, u64)>> {
+        let query = "
+            SELECT src.domain, dst.domain, COUNT(*)
+            FROM links l
+            JOIN pages src ON src.id = l.from_page_id
+            JOIN pages dst ON dst.id = l.to_page_id
+            WHERE src.domain != dst.domain
+            AND src.error_template = 0
+            GROUP BY src.domain, dst.domain
+            ORDER BY src.domain, dst.domain
+        ";
+
+        let mut stmt = self.conn.prepare(query)?;
+        let links = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(links)
+    }
+
+    fn get_domain_link_summaries(
+        &self,
+        run_id: Option<i64>,
+        examples: usize,
+    ) -> StorageResult<Vec<DomainLinkSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT src.domain, dst.domain, COUNT(*)
+             FROM links l
+             JOIN pages src ON src.id = l.from_page_id
+             JOIN pages dst ON dst.id = l.to_page_id
+             WHERE src.domain != dst.domain
+             AND (?1 IS NULL OR l.discovered_run = ?1)
+             AND src.error_template = 0
+             GROUP BY src.domain, dst.domain
+             ORDER BY src.domain, dst.domain",
+        )?;
+        let mut summaries = stmt
+            .query_map(params![run_id], |row| {
+                Ok(DomainLinkSummary {
+                    source_domain: row.get(0)?,
+                    target_domain: row.get(1)?,
+                    links: row.get(2)?,
+                    example_sources: Vec::new(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT source_domain, target_domain, url FROM (
+                 SELECT src.domain AS source_domain, dst.domain AS target_domain,
+                        src.url AS url,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY src.domain, dst.domain ORDER BY src.url
+                        ) AS n
+                 FROM links l
+                 JOIN pages src ON src.id = l.from_page_id
+                 JOIN pages dst ON dst.id = l.to_page_id
+                 WHERE src.domain != dst.domain
+                 AND (?1 IS NULL OR l.discovered_run = ?1)
+                 AND src.error_template = 0
+                 GROUP BY src.domain, dst.domain, src.url
+             )
+             WHERE n <= ?2
+             ORDER BY source_domain, target_domain, url",
+        )?;
+        let mut sources: HashMap<(String, String), Vec<String>> = HashMap::new();
+        let rows = stmt.query_map(params![run_id, examples as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (source, target, url) = row?;
+            sources.entry((source, target)).or_default().push(url);
+        }
+
+        for summary in &mut summaries {
+            let key = (summary.source_domain.clone(), summary.target_domain.clone());
+            summary.example_sources = sources.remove(&key).unwrap_or_default();
+        }
+
+        Ok(summaries)
+    }
+
+    fn get_referring_domains(&self, domain: &str) -> StorageResult<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT src.domain, COUNT(DISTINCT src.id)
+             FROM pages dst
+             JOIN links l ON l.to_page_id = dst.id
+             JOIN pages src ON src.id = l.from_page_id
+             WHERE dst.domain = ?1 AND src.domain != ?1
+             AND src.error_template = 0
+             GROUP BY src.domain
+             ORDER BY src.domain",
+        )?;
+        let domains = stmt
+            .query_map(params![domain], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(domains)
+    }
+
+    fn get_origin_stats(&self, run_id: Option<i64>) -> StorageResult<Vec<OriginStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.quality_origin, COUNT(*), SUM(p.state = ?2), MAX(d.depth),
+                    SUM(p.state = ?3)
+             FROM page_depths d
+             JOIN pages p ON p.id = d.page_id
+             WHERE ?1 IS NULL OR p.discovered_run = ?1 OR p.visited_run = ?1
+             GROUP BY d.quality_origin
+             ORDER BY d.quality_origin",
+        )?;
+        let stats = stmt
+            .query_map(
+                params![
+                    run_id,
+                    PageState::Processed.to_db_string(),
+                    PageState::DeadLink.to_db_string()
+                ],
+                |row| {
+                    Ok(OriginStats {
+                        origin: row.get(0)?,
+                        pages_reached: row.get(1)?,
+                        pages_processed: row.get(2)?,
+                        max_depth: row.get(3)?,
+                        dead_links: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    fn get_origin_link_domains(
+        &self,
+        run_id: Option<i64>,
+    ) -> StorageResult<Vec<(String, String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.quality_origin, dst.domain, COUNT(*)
+             FROM page_depths d
+             JOIN links l ON l.from_page_id = d.page_id
+             JOIN pages src ON src.id = l.from_page_id
+             JOIN pages dst ON dst.id = l.to_page_id
+             WHERE src.domain != dst.domain
+             AND (?1 IS NULL OR l.discovered_run = ?1)
+             AND src.error_template = 0
+             GROUP BY d.quality_origin, dst.domain
+             ORDER BY d.quality_origin, dst.domain",
+        )?;
+        let links = stmt
+            .query_map(params![run_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(links)
+    }
+
+    fn get_origin_dead_links(
+        &self,
+        run_id: Option<i64>,
+        limit: usize,
+    ) -> StorageResult<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT origin, url FROM (
+                 SELECT d.quality_origin AS origin, p.url AS url,
+                        ROW_NUMBER() OVER (PARTITION BY d.quality_origin ORDER BY p.url) AS n
+                 FROM page_depths d
+                 JOIN pages p ON p.id = d.page_id
+                 WHERE p.state = ?2
+                 AND (?1 IS NULL OR p.discovered_run = ?1 OR p.visited_run = ?1)
+             )
+             WHERE n <= ?3
+             ORDER BY origin, url",
+        )?;
+        let dead_links = stmt
+            .query_map(
+                params![run_id, PageState::DeadLink.to_db_string(), limit as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dead_links)
+    }
+
+    // ===== Search =====
+
+    fn search_pages(&self, query: &str, limit: usize) -> StorageResult<Vec<SearchHit>> {
+        let Some(expression) = fts_match_expression(query) else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT p.id, p.url, p.domain, p.title
+             FROM pages_fts
+             JOIN pages p ON p.id = pages_fts.rowid
+             WHERE pages_fts MATCH ?1
+             ORDER BY rank
+             LIMIT ?2",
+        )?;
+
+        let mut hits = stmt
+            .query_map(params![expression, limit as i64], |row| {
+                Ok(SearchHit {
+                    page_id: row.get(0)?,
+                    url: row.get(1)?,
+                    domain: row.get(2)?,
+                    title: row.get(3)?,
+                    depths: Vec::new(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for hit in &mut hits {
+            hit.depths = self.get_depths(hit.page_id)?;
+        }
+
+        Ok(hits)
+    }
+
+    // ===== Tags =====
+
+    fn add_domain_tag(&mut self, domain: &str, tag: &str) -> StorageResult<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT OR IGNORE INTO domain_tags (domain, tag, created_at) VALUES (?1, ?2, ?3)",
+            params![domain, tag, now],
+        )?;
+        Ok(())
+    }
+
+    fn remove_domain_tag(&mut self, domain: &str, tag: &str) -> StorageResult<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM domain_tags WHERE domain = ?1 AND tag = ?2",
+            params![domain, tag],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn get_domain_tags(&self) -> StorageResult<BTreeMap<String, Vec<String>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT domain, tag FROM domain_tags ORDER BY domain, tag")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        group_tags(rows)
+    }
+
+    fn add_page_tag(&mut self, page_id: i64, tag: &str) -> StorageResult<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT OR IGNORE INTO page_tags (page_id, tag, created_at) VALUES (?1, ?2, ?3)",
+            params![page_id, tag, now],
+        )?;
+        Ok(())
+    }
+
+    fn remove_page_tag(&mut self, page_id: i64, tag: &str) -> StorageResult<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM page_tags WHERE page_id = ?1 AND tag = ?2",
+            params![page_id, tag],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn count_domain_pages_with_tag(&self, domain: &str, tag: &str) -> StorageResult<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*)
+             FROM page_tags t
+             JOIN pages p ON p.id = t.page_id
+             WHERE p.domain = ?1 AND t.tag = ?2",
+            params![domain, tag],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    fn get_page_tags(&self) -> StorageResult<BTreeMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.url, t.tag
+             FROM page_tags t
+             JOIN pages p ON p.id = t.page_id
+             ORDER BY p.url, t.tag",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        group_tags(rows)
+    }
+
+    // ===== Technologies =====
+
+    fn record_technologies(&mut self, domain: &str, technologies: &[&str]) -> StorageResult<()> {
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO domain_technologies (domain, technology, pages, first_seen)
+                 VALUES (?1, ?2, 1, ?3)
+                 ON CONFLICT(domain, technology) DO UPDATE SET pages = pages + 1",
+            )?;
+            for technology in technologies {
+                stmt.execute(params![domain, technology, now])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_domain_technologies(&self) -> StorageResult<BTreeMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, technology FROM domain_technologies ORDER BY domain, technology",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        group_tags(rows)
+    }
+
+    // ===== Contacts =====
+
+    fn set_page_contacts(
+        &mut self,
+        page_id: i64,
+        contacts: &[(&str, String)],
+    ) -> StorageResult<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM contacts WHERE page_id = ?1", params![page_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO contacts (page_id, platform, value) VALUES (?1, ?2, ?3)",
+            )?;
+            for (platform, value) in contacts {
+                if self.scrub_pii && *platform == "email" {
+                    continue;
+                }
+                stmt.execute(params![page_id, platform, value])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_contacts(&self) -> StorageResult<Vec<ContactRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.domain, c.platform, c.value, COUNT(*)
+             FROM contacts c
+             JOIN pages p ON p.id = c.page_id
+             GROUP BY p.domain, c.platform, c.value
+             ORDER BY p.domain, c.platform, c.value",
+        )?;
+        let contacts = stmt
+            .query_map([], |row| {
+                Ok(ContactRecord {
+                    domain: row.get(0)?,
+                    platform: row.get(1)?,
+                    value: row.get(2)?,
+                    pages: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(contacts)
+    }
+
+    // ===== Domain Promotions =====
+
+    fn record_domain_promotion(&mut self, promotion: &DomainPromotion) -> StorageResult<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO domain_promotions
+             (domain, run_id, quality_referrers, extra_depth, promoted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                promotion.domain,
+                promotion.run_id,
+                promotion.quality_referrers as i64,
+                promotion.extra_depth,
+                promotion.promoted_at
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get_domain_promotions(&self) -> StorageResult<Vec<DomainPromotion>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, run_id, quality_referrers, extra_depth, promoted_at
+             FROM domain_promotions
+             ORDER BY promoted_at, domain",
+        )?;
+        let promotions = stmt
+            .query_map([], |row| {
+                Ok(DomainPromotion {
+                    domain: row.get(0)?,
+                    run_id: row.get(1)?,
+                    quality_referrers: row.get::<_, i64>(2)? as u64,
+                    extra_depth: row.get(3)?,
+                    promoted_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(promotions)
+    }
+
+    // ===== Classification Overrides =====
+
+    fn set_classification_override(
+        &mut self,
+        domain: &str,
+        classification: DomainClassification,
+    ) -> StorageResult<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO classification_overrides (domain, classification, created_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(domain) DO UPDATE SET
+                classification = excluded.classification,
+                created_at = excluded.created_at",
+            params![domain, classification.to_db_string(), now],
+        )?;
+        Ok(())
+    }
+
+    fn remove_classification_override(&mut self, domain: &str) -> StorageResult<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM classification_overrides WHERE domain = ?1",
+            params![domain],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn get_classification_overrides(&self) -> StorageResult<Vec<ClassificationOverride>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT domain, classification, created_at
+             FROM classification_overrides
+             ORDER BY domain",
+        )?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut overrides = Vec::with_capacity(rows.len());
+        for (domain, classification, created_at) in rows {
+            let classification =
+                DomainClassification::from_db_string(&classification).ok_or_else(|| {
+                    StorageError::Serialization(format!(
+                        "Unknown classification '{}' for {}",
+                        classification, domain
+                    ))
+                })?;
+            overrides.push(ClassificationOverride {
+                domain,
+                classification,
+                created_at,
+            });
+        }
+
+        Ok(overrides)
+    }
+}
+
+/// Collects (key, tag) rows into a map of key -> tags
+fn group_tags(
+    rows: impl Iterator<Item = rusqlite::Result<(String, String)>>,
+) -> StorageResult<BTreeMap<String, Vec<String>>> {
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for row in rows {
+        let (key, tag) = row?;
+        tags.entry(key).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+/// Reads page observations matching a condition on one parameter
+fn query_observations(
+    conn: &rusqlite::Connection,
+    condition: &str,
+    id: i64,
+) -> StorageResult<Vec<PageObservation>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT o.page_id, p.url, o.run_id, o.observed_at, o.state, o.status_code, o.title,
+                o.final_url, o.content_hash, o.error_message
+         FROM page_observations o
+         JOIN pages p ON p.id = o.page_id
+         WHERE {}",
+        condition
+    ))?;
+
+    let observations = stmt
+        .query_map(params![id], |row| {
+            Ok(PageObservation {
+                page_id: row.get(0)?,
+                url: row.get(1)?,
+                run_id: row.get(2)?,
+                observed_at: row.get(3)?,
+                state: PageState::from_db_string(&row.get::<_, String>(4)?)
+                    .unwrap_or(PageState::Failed),
+                status_code: row.get(5)?,
+                title: row.get(6)?,
+                final_url: row.get(7)?,
+                content_hash: row.get(8)?,
+                error_message: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(observations)
+}
+
+/// Converts a user search query into an FTS5 match expression
+///
+/// Every term is quoted so that punctuation in the query (e.g. `foo-bar` or
+/// `example.com`) is treated as text rather than FTS5 syntax. A trailing `*`
+/// is kept as a prefix match. Returns `None` if the query has no terms.
+fn fts_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter_map(|term| {
+            let (word, prefix) = match term.strip_suffix('*') {
+                Some(word) => (word, "*"),
+                None => (term, ""),
+            };
+            if word.is_empty() {
+                return None;
+            }
+            Some(format!("\"{}\"{}", word.replace('"', "\"\""), prefix))
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Initializes or opens a database at the given path
+///
+/// # Arguments
+///
+/// * `path` - Path to the SQLite database file
+///
+/// # Returns
+///
+/// * `Ok(Connection)` - Successfully opened/created database
+/// * `Err(rusqlite::Error)` - Failed to open database
+pub fn init_database(path: &Path) -> Result<Connection, rusqlite::Error> {
+    let conn = Connection::open(path)?;
 
     conn.execute_batch(
         "
@@ -708,100 +2562,1244 @@ pub fn init_database(path: &Path) -> Result<Connection, rusqlite::Error> {
 
     initialize_schema(&conn)?;
 
-    Ok(conn)
-}
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_in_memory() {
+        let storage = SqliteStorage::new_in_memory();
+        assert!(storage.is_ok());
+    }
+
+    #[test]
+    fn test_create_run() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        assert!(run_id > 0);
+    }
+
+    #[test]
+    fn test_run_config() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        assert!(storage.get_run_config(run_id).unwrap().is_none());
+
+        storage
+            .save_run_config(run_id, "test_hash", "[crawler]\nmax-depth = 3\n")
+            .unwrap();
+        let record = storage.get_run_config(run_id).unwrap().unwrap();
+        assert_eq!(record.run_id, run_id);
+        assert_eq!(record.config_hash, "test_hash");
+        assert_eq!(record.config_toml, "[crawler]\nmax-depth = 3\n");
+
+        assert!(matches!(
+            storage.save_run_config(run_id + 1, "other", ""),
+            Err(StorageError::RunNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_progress() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        let other_run = storage.create_run("test_hash").unwrap();
+
+        let snapshot = |run_id, recorded_at: &str, pages_processed| ProgressSnapshot {
+            run_id,
+            recorded_at: recorded_at.to_string(),
+            pages_processed,
+            frontier_size: 10,
+            error_count: 1,
+            eta_seconds: Some(600),
+        };
+        storage
+            .record_run_progress(&snapshot(run_id, "2024-01-01T00:05:00+00:00", 40))
+            .unwrap();
+        storage
+            .record_run_progress(&snapshot(run_id, "2024-01-01T00:00:00+00:00", 0))
+            .unwrap();
+        storage
+            .record_run_progress(&snapshot(other_run, "2024-01-01T00:00:00+00:00", 7))
+            .unwrap();
+
+        let progress = storage.get_run_progress(run_id).unwrap();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].pages_processed, 0);
+        assert_eq!(progress[1].pages_processed, 40);
+        assert_eq!(progress[1].frontier_size, 10);
+        assert_eq!(progress[1].eta_seconds, Some(600));
+    }
+
+    #[test]
+    fn test_with_config_applies_tuning() {
+        use crate::config::SynchronousMode;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = DatabaseConfig {
+            busy_timeout_ms: 250,
+            wal_autocheckpoint: 64,
+            synchronous: SynchronousMode::Full,
+            cache_size_kib: 1024,
+            scrub_pii: false,
+        };
+        let mut storage =
+            SqliteStorage::with_config(&dir.path().join("tuned.db"), &config).unwrap();
+
+        let pragma = |name: &str| -> i64 {
+            storage
+                .conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(pragma("busy_timeout"), 250);
+        assert_eq!(pragma("wal_autocheckpoint"), 64);
+        assert_eq!(pragma("synchronous"), 2);
+        assert_eq!(pragma("cache_size"), -1024);
+
+        let run_id = storage.create_run("hash").unwrap();
+        storage.complete_run(run_id).unwrap();
+        storage.checkpoint().unwrap();
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl.db");
+        let config = DatabaseConfig::default();
+
+        assert!(SqliteStorage::open_read_only(&path, &config).is_err());
+
+        let mut writer = SqliteStorage::with_config(&path, &config).unwrap();
+        let run_id = writer.create_run("hash").unwrap();
+
+        let mut reader = SqliteStorage::open_read_only(&path, &config).unwrap();
+        assert_eq!(reader.get_latest_run().unwrap().unwrap().id, run_id);
+        assert!(reader.create_run("hash").is_err());
+    }
+
+    #[test]
+    fn test_run_maintenance() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+
+        let done = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
+        let queued = storage
+            .insert_or_get_page("https://example.com/next", "example.com", run_id)
+            .unwrap();
+        storage
+            .update_page_state(done, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+        storage
+            .update_page_state(queued, PageState::Queued, None, None, None, None)
+            .unwrap();
+        storage.add_to_frontier(done, 0).unwrap();
+        storage.add_to_frontier(queued, 1).unwrap();
+        for _ in 0..3 {
+            storage
+                .record_blacklisted("https://ads.example.net/", "https://example.com/", run_id)
+                .unwrap();
+        }
+
+        let report = storage.run_maintenance().unwrap();
+        assert_eq!(report.frontier_pruned, 1);
+        assert_eq!(report.referrers_pruned, 2);
+        assert_eq!(report.history_pruned, 0);
+        assert!(report.size_before > 0);
+        assert_eq!(
+            storage.get_blacklisted_urls(10, 0).unwrap(),
+            vec![("https://ads.example.net/".to_string(), 1)]
+        );
+        assert_eq!(storage.load_frontier().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_retention() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", first)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://b.com/", "b.com", first)
+            .unwrap();
+        storage.insert_link(home, other, first).unwrap();
+        storage.record_page_observation(home, first, None).unwrap();
+        storage
+            .record_blacklisted("https://ads.net/", "https://a.com/", first)
+            .unwrap();
+        let second = storage.create_run("hash").unwrap();
+        let third = storage.create_run("hash").unwrap();
+        storage.record_page_observation(home, third, None).unwrap();
+
+        // Without limits nothing is deleted
+        assert_eq!(
+            storage
+                .apply_retention(&RetentionConfig::default())
+                .unwrap(),
+            RetentionReport::default()
+        );
+
+        let report = storage
+            .apply_retention(&RetentionConfig {
+                keep_runs: 2,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(report.runs_pruned, 1);
+        let runs: Vec<i64> = storage.list_runs().unwrap().iter().map(|r| r.id).collect();
+        assert!(runs.contains(&second) && runs.contains(&third) && !runs.contains(&first));
+
+        // The crawl state survives, attributed to the oldest kept run
+        let page = storage.get_page(home).unwrap();
+        assert_eq!(page.discovered_run, second);
+        assert_eq!(storage.get_domain_links().unwrap().len(), 1);
+        assert!(storage.get_blacklisted_urls(10, 0).unwrap().is_empty());
+        assert_eq!(storage.get_page_observations(home).unwrap().len(), 1);
+
+        // Age limits apply to whatever is older than the cutoff
+        storage
+            .conn
+            .execute(
+                "UPDATE page_observations SET observed_at = '2000-01-01T00:00:00+00:00'",
+                [],
+            )
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "UPDATE page_state_history SET changed_at = '2000-01-01T00:00:00+00:00'",
+                [],
+            )
+            .unwrap();
+        let report = storage
+            .apply_retention(&RetentionConfig {
+                observation_max_age_days: 30,
+                event_max_age_days: 30,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(report.runs_pruned, 0);
+        assert_eq!(report.observations_pruned, 1);
+        assert_eq!(report.events_pruned, 2);
+        assert!(storage.get_page_observations(home).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disappeared_links() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", first)
+            .unwrap();
+        let kept = storage
+            .insert_or_get_page("https://a.com/kept", "a.com", first)
+            .unwrap();
+        let gone = storage
+            .insert_or_get_page("https://b.com/", "b.com", first)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://a.com/other", "a.com", first)
+            .unwrap();
+        storage
+            .insert_link_of_kind(home, kept, first, LinkKind::Iframe)
+            .unwrap();
+        storage.insert_link(home, gone, first).unwrap();
+        storage.insert_link(other, gone, first).unwrap();
+
+        // The second run refetches the home page, which no longer links to b.com
+        let second = storage.create_run("hash").unwrap();
+        storage.insert_link(home, kept, second).unwrap();
+        storage
+            .update_page_state(home, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+        storage.record_page_observation(home, second, None).unwrap();
+
+        let links = storage.get_outgoing_links(home).unwrap();
+        let kept_link = links.iter().find(|l| l.to_page_id == kept).unwrap();
+        assert_eq!(kept_link.discovered_run, first);
+        assert_eq!(kept_link.last_seen_run, second);
+        assert_eq!(
+            storage
+                .count_links_by_kind()
+                .unwrap()
+                .get(&LinkKind::Iframe),
+            Some(&1)
+        );
+
+        // Links of pages the run did not fetch are not reported
+        assert_eq!(
+            storage.get_disappeared_links(second, 10).unwrap(),
+            vec![DisappearedLink {
+                from_url: "https://a.com/".to_string(),
+                to_url: "https://b.com/".to_string(),
+                discovered_run: first,
+                last_seen_run: first,
+            }]
+        );
+        assert_eq!(storage.count_disappeared_links(second).unwrap(), 1);
+        assert_eq!(storage.count_disappeared_links(first).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_page_observations() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let page = storage
+            .insert_or_get_page("https://example.com/", "example.com", first)
+            .unwrap();
+        storage
+            .update_page_state(
+                page,
+                PageState::Processed,
+                Some("Old"),
+                Some(200),
+                None,
+                None,
+            )
+            .unwrap();
+        storage.set_content_hash(page, "abc").unwrap();
+        storage
+            .record_page_observation(page, first, Some("https://example.com/"))
+            .unwrap();
+
+        // A refetch in a later run is kept alongside the first observation
+        let second = storage.create_run("hash").unwrap();
+        storage
+            .update_page_state(
+                page,
+                PageState::DeadLink,
+                None,
+                Some(404),
+                None,
+                Some("HTTP 404"),
+            )
+            .unwrap();
+        storage
+            .record_page_observation(page, second, Some("https://example.com/home"))
+            .unwrap();
+
+        let observations = storage.get_page_observations(page).unwrap();
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations[0].run_id, first);
+        assert_eq!(observations[0].title.as_deref(), Some("Old"));
+        assert_eq!(observations[0].status_code, Some(200));
+        assert_eq!(observations[0].content_hash.as_deref(), Some("abc"));
+        // Pages served from their own URL have no final URL
+        assert_eq!(observations[0].final_url, None);
+        assert_eq!(observations[1].state, PageState::DeadLink);
+        assert_eq!(observations[1].title, None);
+        assert_eq!(observations[1].content_hash, None);
+        assert_eq!(
+            observations[1].final_url.as_deref(),
+            Some("https://example.com/home")
+        );
+        assert_eq!(observations[1].error_message.as_deref(), Some("HTTP 404"));
+
+        // Within a run, the last observation wins
+        storage
+            .update_page_state(
+                page,
+                PageState::Processed,
+                Some("New"),
+                Some(200),
+                None,
+                None,
+            )
+            .unwrap();
+        storage.record_page_observation(page, second, None).unwrap();
+        let run = storage.get_run_observations(second).unwrap();
+        assert_eq!(run.len(), 1);
+        assert_eq!(run[0].url, "https://example.com/");
+        assert_eq!(run[0].title.as_deref(), Some("New"));
+        assert_eq!(storage.get_run_observations(first).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_state_history() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let page = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
+        for state in [
+            PageState::Queued,
+            PageState::Fetching,
+            PageState::Fetching,
+            PageState::RateLimited,
+        ] {
+            storage
+                .update_page_state(page, state, None, None, None, None)
+                .unwrap();
+        }
+        let second = storage.create_run("hash").unwrap();
+        storage
+            .update_page_state(page, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+
+        // Unchanged states are not recorded; each change names its run
+        let history = storage.get_state_history(page).unwrap();
+        let states: Vec<_> = history
+            .iter()
+            .map(|t| (t.from_state, t.to_state, t.run_id))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (None, PageState::Discovered, Some(run_id)),
+                (Some(PageState::Discovered), PageState::Queued, Some(run_id)),
+                (Some(PageState::Queued), PageState::Fetching, Some(run_id)),
+                (
+                    Some(PageState::Fetching),
+                    PageState::RateLimited,
+                    Some(run_id)
+                ),
+                (
+                    Some(PageState::RateLimited),
+                    PageState::Processed,
+                    Some(second)
+                ),
+            ]
+        );
+        assert!(chrono::DateTime::parse_from_rfc3339(&history[0].changed_at).is_ok());
+
+        // Maintenance keeps only the latest transitions of a flapping page
+        for i in 0..STATE_HISTORY_KEPT {
+            let state = if i % 2 == 0 {
+                PageState::Queued
+            } else {
+                PageState::Processed
+            };
+            storage
+                .update_page_state(page, state, None, None, None, None)
+                .unwrap();
+        }
+        let report = storage.run_maintenance().unwrap();
+        assert_eq!(report.history_pruned, 5);
+        let history = storage.get_state_history(page).unwrap();
+        assert_eq!(history.len(), STATE_HISTORY_KEPT);
+        assert_eq!(history.last().unwrap().to_state, PageState::Processed);
+    }
+
+    #[test]
+    fn test_list_and_delete_runs() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+
+        let home = storage
+            .insert_or_get_page("https://example.com/", "example.com", first)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://example.com/about", "example.com", second)
+            .unwrap();
+        storage.insert_link(home, about, second).unwrap();
+        storage.upsert_depth(about, "example.com", 1).unwrap();
+        storage.mark_page_visited(home, second).unwrap();
+        storage
+            .record_blacklisted("https://ads.example.net/", "https://example.com/", second)
+            .unwrap();
+        storage.save_run_config(second, "hash", "").unwrap();
+
+        let runs: Vec<i64> = storage.list_runs().unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(runs, vec![first, second]);
+
+        let deletion = storage.delete_run(second).unwrap();
+        assert_eq!(deletion.pages, 1);
+        assert_eq!(deletion.links, 1);
+        assert!(deletion.url_records >= 1);
+
+        assert_eq!(storage.list_runs().unwrap().len(), 1);
+        assert!(storage.get_run_config(second).unwrap().is_none());
+        assert_eq!(storage.count_total_pages().unwrap(), 1);
+        assert_eq!(storage.count_pages_visited_in_run(second).unwrap(), 0);
+        assert!(matches!(
+            storage.delete_run(second),
+            Err(StorageError::RunNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_scoped_queries() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+
+        let home = storage
+            .insert_or_get_page("https://example.com/", "example.com", first)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://example.com/about", "example.com", first)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://other.org/", "other.org", second)
+            .unwrap();
+        storage.insert_link(home, about, first).unwrap();
+        storage.insert_link(about, other, second).unwrap();
+
+        // The second run revisits the home page
+        storage.mark_page_visited(home, first).unwrap();
+        storage.mark_page_visited(home, second).unwrap();
+        storage
+            .update_page_state(home, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+
+        assert_eq!(storage.count_pages_discovered_in_run(first).unwrap(), 2);
+        assert_eq!(storage.count_pages_discovered_in_run(second).unwrap(), 1);
+        assert_eq!(storage.count_pages_visited_in_run(first).unwrap(), 0);
+        assert_eq!(storage.count_pages_visited_in_run(second).unwrap(), 1);
+        assert_eq!(
+            storage
+                .count_pages_visited_in_run_by_domain(second)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![("example.com".to_string(), 1)]
+        );
+        assert!(storage
+            .count_pages_visited_in_run_by_domain(first)
+            .unwrap()
+            .is_empty());
+        assert_eq!(storage.count_links_discovered_in_run(second).unwrap(), 1);
+
+        let counts = storage.get_run_state_counts(second).unwrap();
+        assert_eq!(counts.get(&PageState::Processed), Some(&1));
+        assert_eq!(counts.get(&PageState::Discovered), Some(&1));
+        assert_eq!(
+            storage.get_run_domains(second).unwrap(),
+            vec!["example.com", "other.org"]
+        );
+
+        storage.upsert_depth(home, "example.com", 0).unwrap();
+        storage.upsert_depth(about, "example.com", 1).unwrap();
+        let depths = storage.get_run_depth_breakdown(second).unwrap();
+        assert_eq!(depths.get(&0), Some(&1));
+        assert_eq!(depths.get(&1), None);
+    }
+
+    #[test]
+    fn test_insert_page() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        let page_id = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
+        assert!(page_id > 0);
+    }
+
+    #[test]
+    fn test_insert_duplicate_page() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+
+        let page_id1 = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
+        let page_id2 = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(page_id1, page_id2);
+    }
 
     #[test]
-    fn test_create_in_memory() {
-        let storage = SqliteStorage::new_in_memory();
-        assert!(storage.is_ok());
+    fn test_depth_hops() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let page = storage
+            .insert_or_get_page("https://other.org/", "other.org", run_id)
+            .unwrap();
+
+        storage
+            .upsert_depth_with_hops(page, "example.com", 1, 2)
+            .unwrap();
+        storage
+            .upsert_depth_with_hops(page, "example.com", 3, 1)
+            .unwrap();
+        let depths = storage.get_depths(page).unwrap();
+        assert_eq!(depths.len(), 1);
+        assert_eq!((depths[0].depth, depths[0].hops), (1, 1));
+
+        assert!(storage.should_crawl(page, 1, None).unwrap());
+        assert!(storage.should_crawl(page, 1, Some(1)).unwrap());
+        assert!(!storage.should_crawl(page, 1, Some(0)).unwrap());
+        assert!(!storage.should_crawl(page, 0, None).unwrap());
     }
 
     #[test]
-    fn test_create_run() {
+    fn test_update_page_state() {
         let mut storage = SqliteStorage::new_in_memory().unwrap();
         let run_id = storage.create_run("test_hash").unwrap();
-        assert!(run_id > 0);
+        let page_id = storage
+            .insert_or_get_page("https://example.com/", "example.com", run_id)
+            .unwrap();
+
+        storage
+            .update_page_state(
+                page_id,
+                PageState::Processed,
+                Some("Test Page"),
+                Some(200),
+                Some("text/html"),
+                None,
+            )
+            .unwrap();
+
+        let page = storage.get_page(page_id).unwrap();
+        assert_eq!(page.state, PageState::Processed);
+        assert_eq!(page.title, Some("Test Page".to_string()));
+    }
+
+    #[test]
+    fn test_get_domain_summaries() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        for (url, domain) in [
+            ("https://a.com/", "a.com"),
+            ("https://a.com/x", "a.com"),
+            ("https://b.com/", "b.com"),
+        ] {
+            storage.insert_or_get_page(url, domain, run_id).unwrap();
+        }
+        let page = storage.get_page_by_url("https://a.com/").unwrap().unwrap();
+        storage
+            .update_page_state(page.id, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+
+        let summaries = storage.get_domain_summaries().unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].domain, "a.com");
+        assert_eq!(summaries[0].pages, 2);
+        assert_eq!(summaries[0].pages_processed, 1);
+        assert_eq!(summaries[0].errors, 0);
+        assert!(summaries[0].last_seen >= summaries[0].first_seen);
+        assert_eq!(summaries[1].domain, "b.com");
+        let paged = storage.get_domain_summaries_paged(1, 1).unwrap();
+        assert_eq!(paged.len(), 1);
+        assert_eq!(paged[0].domain, "b.com");
+
+        assert_eq!(storage.count_total_pages().unwrap(), 3);
+        assert_eq!(storage.count_unique_domains().unwrap(), 2);
+
+        assert_eq!(storage.count_domain_pages("a.com", None).unwrap(), 2);
+        assert_eq!(
+            storage
+                .count_domain_pages("a.com", Some(PageState::Processed))
+                .unwrap(),
+            1
+        );
+        let pages = storage.get_domain_pages_paged("a.com", None, 1, 1).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].url, "https://a.com/x");
+        let processed = storage
+            .get_domain_pages_paged("a.com", Some(PageState::Processed), 10, 0)
+            .unwrap();
+        assert_eq!(processed.len(), 1);
+        assert_eq!(processed[0].id, page.id);
+    }
+
+    #[test]
+    fn test_retry_counts() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let flaky = storage
+            .insert_or_get_page("https://a.com/flaky", "a.com", run_id)
+            .unwrap();
+        let down = storage
+            .insert_or_get_page("https://a.com/down", "a.com", run_id)
+            .unwrap();
+        let recovered = storage
+            .insert_or_get_page("https://a.com/ok", "a.com", run_id)
+            .unwrap();
+
+        storage.increment_retry_count(flaky, 3).unwrap();
+        storage.increment_retry_count(flaky, 1).unwrap();
+        storage.increment_retry_count(down, 2).unwrap();
+        storage.increment_retry_count(recovered, 5).unwrap();
+        for page in [flaky, down] {
+            storage
+                .update_page_state(page, PageState::Unreachable, None, None, None, None)
+                .unwrap();
+        }
+        storage
+            .update_page_state(recovered, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+
+        assert_eq!(storage.get_page(flaky).unwrap().retry_count, 4);
+
+        // Only pages still failing are listed
+        let retried: Vec<(String, u32)> = storage
+            .get_most_retried_pages(10)
+            .unwrap()
+            .into_iter()
+            .map(|page| (page.url, page.retry_count))
+            .collect();
+        assert_eq!(
+            retried,
+            vec![
+                ("https://a.com/flaky".to_string(), 4),
+                ("https://a.com/down".to_string(), 2)
+            ]
+        );
+        assert_eq!(storage.get_most_retried_pages(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_filtered_url_paging() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        for (url, times) in [
+            ("https://a.net/", 1),
+            ("https://b.net/", 3),
+            ("https://c.net/", 2),
+        ] {
+            for _ in 0..times {
+                storage
+                    .record_stubbed(url, "https://example.com/", run_id)
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            storage.get_stubbed_urls(2, 0).unwrap(),
+            vec![
+                ("https://b.net/".to_string(), 3),
+                ("https://c.net/".to_string(), 2)
+            ]
+        );
+        assert_eq!(
+            storage.get_stubbed_urls(2, 2).unwrap(),
+            vec![("https://a.net/".to_string(), 1)]
+        );
+        assert!(storage.get_blacklisted_urls(10, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_stub_references() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+        storage
+            .record_stubbed("https://cdn.net/x", "https://a.com/", first)
+            .unwrap();
+        storage
+            .record_stubbed("https://cdn.net/x", "https://a.com/", second)
+            .unwrap();
+        storage
+            .record_stubbed("https://cdn.net/x", "https://a.com/b", second)
+            .unwrap();
+
+        assert_eq!(
+            storage.get_stub_references(None).unwrap(),
+            vec![
+                (
+                    "https://cdn.net/x".to_string(),
+                    "https://a.com/".to_string()
+                ),
+                (
+                    "https://cdn.net/x".to_string(),
+                    "https://a.com/b".to_string()
+                ),
+            ]
+        );
+        assert_eq!(storage.get_stub_references(Some(first)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_state_counts() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let a = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        storage
+            .insert_or_get_page("https://a.com/b", "a.com", run_id)
+            .unwrap();
+        storage
+            .update_page_state(a, PageState::DeadLink, None, Some(404), None, None)
+            .unwrap();
+
+        let counts = storage.get_state_counts().unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&PageState::Discovered], 1);
+        assert_eq!(counts[&PageState::DeadLink], 1);
+        assert_eq!(
+            storage.count_pages_by_state(PageState::DeadLink).unwrap(),
+            1
+        );
+        assert_eq!(storage.count_pages_by_state(PageState::Failed).unwrap(), 0);
+
+        let errors = storage.get_error_summary().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[&PageState::DeadLink], 1);
+    }
+
+    #[test]
+    fn test_frontier_reprioritization() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let page = |storage: &mut SqliteStorage, url: &str| {
+            storage.insert_or_get_page(url, "a.com", run_id).unwrap()
+        };
+        let (a, b, c, d) = (
+            page(&mut storage, "https://a.com/a"),
+            page(&mut storage, "https://a.com/b"),
+            page(&mut storage, "https://a.com/c"),
+            page(&mut storage, "https://a.com/d"),
+        );
+        storage.insert_link(a, c, run_id).unwrap();
+        storage.insert_link(b, c, run_id).unwrap();
+        storage.insert_link(a, d, run_id).unwrap();
+        storage.insert_link(a, b, run_id).unwrap();
+        storage.add_to_frontier(c, 10).unwrap();
+        storage.add_to_frontier(d, 10).unwrap();
+        storage.add_to_frontier(a, 0).unwrap();
+
+        let counts = storage.get_frontier_referrer_counts().unwrap();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&c], 2);
+        assert_eq!(counts[&d], 1);
+        assert_eq!(counts[&a], 0);
+
+        storage
+            .update_frontier_priorities(&[(c, 3), (b, 1)])
+            .unwrap();
+        let frontier = storage.load_frontier().unwrap();
+        assert_eq!(frontier, vec![(a, 0), (c, 3), (d, 10)]);
+        assert_eq!(storage.get_frontier_priority(c).unwrap(), Some(3));
+        assert_eq!(storage.get_frontier_priority(b).unwrap(), None);
+    }
+
+    #[test]
+    fn test_domain_state_persistence() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+
+        // Create a domain state
+        let mut state = DomainState::new();
+        state.request_count = 42;
+        state.rate_limited = true;
+        state.head_failures = 3;
+
+        // Save it
+        storage.update_domain_state("example.com", &state).unwrap();
+
+        // Load it back
+        let loaded_states = storage.load_domain_states().unwrap();
+        assert_eq!(loaded_states.len(), 1);
+
+        let loaded_state = loaded_states.get("example.com").unwrap();
+        assert_eq!(loaded_state.request_count, 42);
+        assert_eq!(loaded_state.rate_limited, true);
+        assert_eq!(loaded_state.head_failures, 3);
+        assert_eq!(loaded_state.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_page_headers() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let header = |name: &str, value: &str| (name.to_string(), value.to_string());
+
+        let mut pages = Vec::new();
+        for url in [
+            "https://a.com/",
+            "https://a.com/x",
+            "https://b.com/",
+            "https://c.com/",
+        ] {
+            let domain = url.split('/').nth(2).unwrap();
+            pages.push(storage.insert_or_get_page(url, domain, run_id).unwrap());
+        }
+        storage
+            .set_page_headers(pages[0], &[header("server", "nginx")])
+            .unwrap();
+        storage
+            .set_page_headers(pages[1], &[header("server", "nginx")])
+            .unwrap();
+        storage
+            .set_page_headers(pages[2], &[header("server", "nginx")])
+            .unwrap();
+        storage
+            .set_page_headers(
+                pages[3],
+                &[
+                    header("server", "Apache"),
+                    header("x-powered-by", "PHP/8.2"),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            storage.get_page_headers(pages[3]).unwrap(),
+            vec![
+                header("server", "Apache"),
+                header("x-powered-by", "PHP/8.2")
+            ]
+        );
+
+        // Refetching a page replaces its headers
+        storage.set_page_headers(pages[2], &[]).unwrap();
+        assert!(storage.get_page_headers(pages[2]).unwrap().is_empty());
+
+        let counts = storage.get_header_value_counts(10).unwrap();
+        let summary: Vec<(&str, &str, u64)> = counts
+            .iter()
+            .map(|c| (c.name.as_str(), c.value.as_str(), c.domains))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("server", "Apache", 1),
+                ("server", "nginx", 1),
+                ("x-powered-by", "PHP/8.2", 1),
+            ]
+        );
+
+        assert_eq!(storage.get_header_value_counts(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_page_anomalies() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let index = storage
+            .insert_or_get_page("https://a.com/index", "a.com", run_id)
+            .unwrap();
+        let empty = storage
+            .insert_or_get_page("https://a.com/empty", "a.com", run_id)
+            .unwrap();
+
+        storage
+            .set_page_anomalies(
+                index,
+                &[
+                    (AnomalyKind::ExcessiveLinks, "4210 links".to_string()),
+                    (AnomalyKind::HugeBody, "9000000 bytes".to_string()),
+                ],
+            )
+            .unwrap();
+        storage
+            .set_page_anomalies(empty, &[(AnomalyKind::TinyBody, "12 bytes".to_string())])
+            .unwrap();
+
+        let anomalies = storage.get_page_anomalies(10).unwrap();
+        assert_eq!(anomalies.len(), 3);
+        assert_eq!(anomalies[0].kind, AnomalyKind::ExcessiveLinks);
+        assert_eq!(anomalies[0].detail, "4210 links");
+
+        // A refetch replaces the anomalies of a page
+        storage.set_page_anomalies(index, &[]).unwrap();
+        let counts = storage.count_page_anomalies().unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[&AnomalyKind::TinyBody], 1);
+    }
+
+    #[test]
+    fn test_page_directives() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let news = storage
+            .insert_or_get_page("https://a.com/news", "a.com", run_id)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://a.com/about", "a.com", run_id)
+            .unwrap();
+        let directive = |source, name: &str, value: Option<&str>| PageDirective {
+            source,
+            name: name.to_string(),
+            value: value.map(str::to_string),
+        };
+
+        storage
+            .set_page_directives(
+                news,
+                &[
+                    directive(DirectiveSource::Header, "noarchive", None),
+                    directive(
+                        DirectiveSource::Header,
+                        "unavailable_after",
+                        Some("2027-11-03"),
+                    ),
+                    directive(DirectiveSource::Meta, "nosnippet", None),
+                ],
+            )
+            .unwrap();
+        storage
+            .set_page_directives(
+                about,
+                &[directive(DirectiveSource::Meta, "noarchive", None)],
+            )
+            .unwrap();
+
+        // Meta tags first, then headers
+        let directives = storage.get_page_directives(news).unwrap();
+        assert_eq!(directives.len(), 3);
+        assert_eq!(directives[0].name, "nosnippet");
+        assert_eq!(directives[2].name, "unavailable_after");
+        assert_eq!(directives[2].value.as_deref(), Some("2027-11-03"));
+        assert_eq!(
+            storage.get_pages_with_directive("NOARCHIVE").unwrap(),
+            vec!["https://a.com/about", "https://a.com/news"]
+        );
+
+        // A refetch replaces the directives of a page
+        storage.set_page_directives(news, &[]).unwrap();
+        assert!(storage.get_page_directives(news).unwrap().is_empty());
+        assert_eq!(
+            storage.get_pages_with_directive("noarchive").unwrap(),
+            vec!["https://a.com/about"]
+        );
+    }
+
+    #[test]
+    fn test_link_kinds() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let frame = storage
+            .insert_or_get_page("https://a.com/frame", "a.com", run_id)
+            .unwrap();
+        let next = storage
+            .insert_or_get_page("https://a.com/next", "a.com", run_id)
+            .unwrap();
+
+        storage
+            .insert_link_of_kind(home, frame, run_id, LinkKind::Iframe)
+            .unwrap();
+        storage
+            .insert_link_of_kind(home, next, run_id, LinkKind::MetaRefresh)
+            .unwrap();
+        storage.insert_link(frame, next, run_id).unwrap();
+
+        // A link found again through another element keeps its first kind
+        storage.insert_link(home, frame, run_id).unwrap();
+
+        let counts = storage.count_links_by_kind().unwrap();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&LinkKind::Anchor], 1);
+        assert_eq!(counts[&LinkKind::Iframe], 1);
+        assert_eq!(counts[&LinkKind::MetaRefresh], 1);
+    }
+
+    #[test]
+    fn test_contacts() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let about = storage
+            .insert_or_get_page("https://a.com/about", "a.com", run_id)
+            .unwrap();
+
+        let email = ("email", "info@a.com".to_string());
+        let github = ("github", "https://github.com/a".to_string());
+        storage
+            .set_page_contacts(home, &[email.clone(), github.clone()])
+            .unwrap();
+        storage.set_page_contacts(about, &[email]).unwrap();
+
+        let contacts = storage.get_contacts().unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].platform, "email");
+        assert_eq!(contacts[0].value, "info@a.com");
+        assert_eq!(contacts[0].pages, 2);
+        assert_eq!(contacts[1].domain, "a.com");
+        assert_eq!(contacts[1].pages, 1);
+
+        // A refetch replaces the contacts of a page
+        storage.set_page_contacts(home, &[]).unwrap();
+        let contacts = storage.get_contacts().unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].pages, 1);
+    }
+
+    #[test]
+    fn test_scrub_pii() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        storage.scrub_pii = true;
+        let run_id = storage.create_run("hash").unwrap();
+
+        let page = storage
+            .insert_or_get_page("https://a.com/welcome?sid=42&lang=en", "a.com", run_id)
+            .unwrap();
+        assert_eq!(
+            storage.get_page(page).unwrap().url,
+            "https://a.com/welcome?lang=en"
+        );
+        // Lookups are scrubbed the same way, so the page is found again
+        assert_eq!(
+            storage
+                .insert_or_get_page("https://a.com/welcome?sid=43&lang=en", "a.com", run_id)
+                .unwrap(),
+            page
+        );
+        let found = storage
+            .get_page_by_url("https://a.com/welcome?lang=en&token=x")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, page);
+
+        storage
+            .record_blacklisted(
+                "https://spam.com/?email=jane@example.com",
+                "https://a.com/welcome?sid=42",
+                run_id,
+            )
+            .unwrap();
+        let referrer: String = storage
+            .conn
+            .query_row("SELECT referrer FROM blacklisted_urls", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(referrer, hash_url("https://a.com/welcome?sid=42"));
+        assert_eq!(
+            storage.get_blacklisted_urls(10, 0).unwrap(),
+            vec![("https://spam.com/".to_string(), 1)]
+        );
+
+        storage
+            .set_page_contacts(
+                page,
+                &[
+                    ("email", "info@a.com".to_string()),
+                    ("github", "https://github.com/a".to_string()),
+                ],
+            )
+            .unwrap();
+        let contacts = storage.get_contacts().unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].platform, "github");
+
+        // Redirect chains in error messages are scrubbed too, also in the
+        // observations copied from the page
+        storage
+            .update_page_state(
+                page,
+                PageState::Failed,
+                None,
+                None,
+                None,
+                Some("Too many redirects: https://a.com/login -> https://sso.a.com/?token=abc&email=x"),
+            )
+            .unwrap();
+        storage.record_page_observation(page, run_id, None).unwrap();
+        let expected = "Too many redirects: https://a.com/login -> https://sso.a.com/";
+        assert_eq!(
+            storage.get_page(page).unwrap().error_message.as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            storage.get_page_observations(page).unwrap()[0]
+                .error_message
+                .as_deref(),
+            Some(expected)
+        );
     }
 
     #[test]
-    fn test_insert_page() {
+    fn test_domain_technologies() {
         let mut storage = SqliteStorage::new_in_memory().unwrap();
-        let run_id = storage.create_run("test_hash").unwrap();
-        let page_id = storage
-            .insert_or_get_page("https://example.com/", "example.com", run_id)
+        storage
+            .record_technologies("blog.com", &["WordPress", "PHP"])
             .unwrap();
-        assert!(page_id > 0);
+        storage
+            .record_technologies("blog.com", &["WordPress"])
+            .unwrap();
+        storage
+            .record_technologies("shop.com", &["Shopify"])
+            .unwrap();
+
+        let technologies = storage.get_domain_technologies().unwrap();
+        assert_eq!(technologies["blog.com"], vec!["PHP", "WordPress"]);
+        assert_eq!(technologies["shop.com"], vec!["Shopify"]);
+
+        let pages: i64 = storage
+            .conn
+            .query_row(
+                "SELECT pages FROM domain_technologies WHERE technology = 'WordPress'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(pages, 2);
     }
 
     #[test]
-    fn test_insert_duplicate_page() {
+    fn test_get_degraded_domains() {
         let mut storage = SqliteStorage::new_in_memory().unwrap();
-        let run_id = storage.create_run("test_hash").unwrap();
 
-        let page_id1 = storage
-            .insert_or_get_page("https://example.com/", "example.com", run_id)
-            .unwrap();
-        let page_id2 = storage
-            .insert_or_get_page("https://example.com/", "example.com", run_id)
-            .unwrap();
+        let mut state = DomainState::new();
+        storage.update_domain_state("healthy.com", &state).unwrap();
+        state.consecutive_failures = 5;
+        state.circuit_trips = 1;
+        storage.update_domain_state("flaky.com", &state).unwrap();
+        state.circuit_trips = 3;
+        storage.update_domain_state("down.com", &state).unwrap();
 
-        assert_eq!(page_id1, page_id2);
+        assert_eq!(
+            storage.get_degraded_domains().unwrap(),
+            vec![("down.com".to_string(), 3), ("flaky.com".to_string(), 1)]
+        );
+
+        let loaded = storage.load_domain_states().unwrap();
+        assert_eq!(loaded["down.com"].consecutive_failures, 5);
+        assert_eq!(loaded["down.com"].circuit_trips, 3);
     }
 
     #[test]
-    fn test_update_page_state() {
+    fn test_get_slowed_domains() {
         let mut storage = SqliteStorage::new_in_memory().unwrap();
-        let run_id = storage.create_run("test_hash").unwrap();
-        let page_id = storage
-            .insert_or_get_page("https://example.com/", "example.com", run_id)
-            .unwrap();
 
-        storage
-            .update_page_state(
-                page_id,
-                PageState::Processed,
-                Some("Test Page"),
-                Some(200),
-                Some("text/html"),
-                None,
-            )
-            .unwrap();
+        let mut state = DomainState::new();
+        storage.update_domain_state("calm.com", &state).unwrap();
+        state.mark_rate_limited();
+        storage.update_domain_state("busy.com", &state).unwrap();
+        state.mark_rate_limited();
+        storage.update_domain_state("swamped.com", &state).unwrap();
 
-        let page = storage.get_page(page_id).unwrap();
-        assert_eq!(page.state, PageState::Processed);
-        assert_eq!(page.title, Some("Test Page".to_string()));
+        assert_eq!(
+            storage.get_slowed_domains().unwrap(),
+            vec![("swamped.com".to_string(), 4), ("busy.com".to_string(), 2)]
+        );
+
+        // A resumed crawl keeps the domains slowed down
+        let loaded = storage.load_domain_states().unwrap();
+        assert_eq!(loaded["swamped.com"].delay_multiplier, 4);
+        assert_eq!(loaded["calm.com"].delay_multiplier, 1);
     }
 
     #[test]
-    fn test_domain_state_persistence() {
+    fn test_get_robots_failures() {
         let mut storage = SqliteStorage::new_in_memory().unwrap();
 
-        // Create a domain state
         let mut state = DomainState::new();
-        state.request_count = 42;
-        state.rate_limited = true;
-        state.update_robots("User-agent: *\nDisallow: /admin".to_string());
-
-        // Save it
-        storage.update_domain_state("example.com", &state).unwrap();
-
-        // Load it back
-        let loaded_states = storage.load_domain_states().unwrap();
-        assert_eq!(loaded_states.len(), 1);
+        storage.update_domain_state("healthy.com", &state).unwrap();
+        state.robots_failures = 2;
+        state.robots_error = Some("robots.txt unavailable (HTTP 503)".to_string());
+        storage.update_domain_state("busy.com", &state).unwrap();
 
-        let loaded_state = loaded_states.get("example.com").unwrap();
-        assert_eq!(loaded_state.request_count, 42);
-        assert_eq!(loaded_state.rate_limited, true);
-        assert!(loaded_state.robots_txt.is_some());
+        let failures = storage.get_robots_failures().unwrap();
         assert_eq!(
-            loaded_state.robots_txt.as_ref().unwrap().content,
-            "User-agent: *\nDisallow: /admin"
+            failures,
+            vec![RobotsFailure {
+                domain: "busy.com".to_string(),
+                failures: 2,
+                last_error: Some("robots.txt unavailable (HTTP 503)".to_string()),
+            }]
         );
+
+        let loaded = storage.load_domain_states().unwrap();
+        assert_eq!(loaded["busy.com"].robots_failures, 2);
+        assert_eq!(loaded["busy.com"].robots_streak, 0);
     }
 
     #[test]
@@ -860,4 +3858,460 @@ mod tests {
         assert_eq!(loaded.request_count, 20);
         assert!(loaded.rate_limited);
     }
+
+    #[test]
+    fn test_page_fingerprints_and_cross_domain_links() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+
+        let a = storage
+            .insert_or_get_page("https://a.com/", "a.com", run_id)
+            .unwrap();
+        let a2 = storage
+            .insert_or_get_page("https://a.com/about", "a.com", run_id)
+            .unwrap();
+        let b = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+
+        storage
+            .update_page_state(a, PageState::Processed, Some("Home"), Some(200), None, None)
+            .unwrap();
+        storage.set_content_hash(a, "abc123").unwrap();
+
+        storage.insert_link(a, a2, run_id).unwrap();
+        storage.insert_link(a, b, run_id).unwrap();
+
+        // Only processed pages have fingerprints
+        let fingerprints = storage.get_page_fingerprints().unwrap();
+        assert_eq!(fingerprints.len(), 1);
+        assert_eq!(fingerprints[0].domain, "a.com");
+        assert_eq!(fingerprints[0].title.as_deref(), Some("Home"));
+        assert_eq!(fingerprints[0].content_hash.as_deref(), Some("abc123"));
+
+        // Same-domain links are excluded
+        let links = storage.get_cross_domain_links().unwrap();
+        assert_eq!(
+            links,
+            vec![("a.com".to_string(), "https://b.com/".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_get_domain_links() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+
+        let a1 = storage
+            .insert_or_get_page("https://a.com/1", "a.com", run_id)
+            .unwrap();
+        let a2 = storage
+            .insert_or_get_page("https://a.com/2", "a.com", run_id)
+            .unwrap();
+        let b1 = storage
+            .insert_or_get_page("https://b.com/1", "b.com", run_id)
+            .unwrap();
+        let b2 = storage
+            .insert_or_get_page("https://b.com/2", "b.com", run_id)
+            .unwrap();
+
+        storage.insert_link(a1, b1, run_id).unwrap();
+        storage.insert_link(a2, b2, run_id).unwrap();
+        storage.insert_link(b1, a1, run_id).unwrap();
+        storage.insert_link(a1, a2, run_id).unwrap();
+
+        let links = storage.get_domain_links().unwrap();
+        assert_eq!(
+            links,
+            vec![
+                ("a.com".to_string(), "b.com".to_string(), 2),
+                ("b.com".to_string(), "a.com".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_templates() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        let target = storage
+            .insert_or_get_page("https://b.com/", "b.com", run_id)
+            .unwrap();
+
+        let mut missing = Vec::new();
+        for n in 1..=3 {
+            let page = storage
+                .insert_or_get_page(&format!("https://a.com/gone{}", n), "a.com", run_id)
+                .unwrap();
+            storage.insert_link(page, target, run_id).unwrap();
+            storage.set_template_hash(page, "404").unwrap();
+            missing.push(page);
+        }
+        // The same body on another domain is a different template
+        let other = storage
+            .insert_or_get_page("https://c.com/gone", "c.com", run_id)
+            .unwrap();
+        storage.set_template_hash(other, "404").unwrap();
+
+        assert!(!storage.flag_error_template("a.com", "404", 4).unwrap());
+        assert!(storage.get_error_template_pages().unwrap().is_empty());
+        assert_eq!(storage.get_domain_links().unwrap()[0].2, 3);
+
+        assert!(storage.flag_error_template("a.com", "404", 3).unwrap());
+        assert_eq!(
+            storage.get_error_template_pages().unwrap(),
+            vec![missing[1], missing[2]]
+        );
+        assert_eq!(storage.get_all_links().unwrap().len(), 3);
+        let graph_links = storage.get_graph_links().unwrap();
+        assert_eq!(graph_links.len(), 1);
+        assert_eq!(graph_links[0].from_page_id, missing[0]);
+        assert_eq!(
+            storage.get_error_templates().unwrap(),
+            vec![ErrorTemplate {
+                domain: "a.com".to_string(),
+                template_hash: "404".to_string(),
+                pages: 3,
+                example_url: "https://a.com/gone1".to_string(),
+            }]
+        );
+        // The first page of the template keeps its link
+        assert_eq!(
+            storage.get_domain_links().unwrap(),
+            vec![("a.com".to_string(), "b.com".to_string(), 1)]
+        );
+        assert_eq!(
+            storage.get_referring_domains("b.com").unwrap(),
+            vec![("a.com".to_string(), 1)]
+        );
+
+        // A page fetched again with another body leaves the template
+        storage.set_template_hash(missing[2], "fixed").unwrap();
+        assert_eq!(
+            storage.get_error_template_pages().unwrap(),
+            vec![missing[1]]
+        );
+    }
+
+    #[test]
+    fn test_get_filter_hits() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+
+        storage
+            .record_blacklisted("https://ads.net/x", "https://a.com/", first)
+            .unwrap();
+        storage
+            .record_stubbed("https://social.net/p", "https://a.com/about", first)
+            .unwrap();
+        storage
+            .record_blacklisted("https://ads.net/y", "https://a.com/news", second)
+            .unwrap();
+
+        let hits = storage.get_filter_hits(None).unwrap();
+        let kinds: Vec<FilterKind> = hits.iter().map(|h| h.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                FilterKind::Blacklisted,
+                FilterKind::Stubbed,
+                FilterKind::Blacklisted
+            ]
+        );
+        assert_eq!(hits[1].url, "https://social.net/p");
+        assert_eq!(hits[1].referrer, "https://a.com/about");
+        assert_eq!(hits[1].run_id, first);
+
+        let scoped = storage.get_filter_hits(Some(second)).unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].url, "https://ads.net/y");
+    }
+
+    #[test]
+    fn test_get_domain_link_summaries() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+
+        let a1 = storage
+            .insert_or_get_page("https://a.com/1", "a.com", first)
+            .unwrap();
+        let a2 = storage
+            .insert_or_get_page("https://a.com/2", "a.com", first)
+            .unwrap();
+        let b1 = storage
+            .insert_or_get_page("https://b.com/1", "b.com", first)
+            .unwrap();
+        let b2 = storage
+            .insert_or_get_page("https://b.com/2", "b.com", first)
+            .unwrap();
+        storage.insert_link(a2, b1, first).unwrap();
+        storage.insert_link(a1, b1, first).unwrap();
+        storage.insert_link(a1, b2, second).unwrap();
+        storage.insert_link(a1, a2, first).unwrap();
+
+        let summaries = storage.get_domain_link_summaries(None, 1).unwrap();
+        assert_eq!(
+            summaries,
+            vec![DomainLinkSummary {
+                source_domain: "a.com".to_string(),
+                target_domain: "b.com".to_string(),
+                links: 3,
+                example_sources: vec!["https://a.com/1".to_string()],
+            }]
+        );
+
+        let scoped = storage.get_domain_link_summaries(Some(second), 5).unwrap();
+        assert_eq!(scoped[0].links, 1);
+        assert_eq!(scoped[0].example_sources, vec!["https://a.com/1"]);
+    }
+
+    #[test]
+    fn test_origin_queries() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let first = storage.create_run("hash").unwrap();
+        let second = storage.create_run("hash").unwrap();
+
+        let home = storage
+            .insert_or_get_page("https://a.com/", "a.com", first)
+            .unwrap();
+        let gone = storage
+            .insert_or_get_page("https://a.com/gone", "a.com", first)
+            .unwrap();
+        let ext = storage
+            .insert_or_get_page("https://x.org/", "x.org", second)
+            .unwrap();
+        let wiki = storage
+            .insert_or_get_page("https://wiki.org/", "wiki.org", first)
+            .unwrap();
+        storage.insert_link(home, gone, first).unwrap();
+        storage.insert_link(home, ext, second).unwrap();
+        storage.upsert_depth(home, "a.com", 0).unwrap();
+        storage.upsert_depth(gone, "a.com", 1).unwrap();
+        storage.upsert_depth(ext, "a.com", 1).unwrap();
+        storage.upsert_depth(wiki, "wiki.org", 0).unwrap();
+        storage
+            .update_page_state(home, PageState::Processed, None, Some(200), None, None)
+            .unwrap();
+        storage
+            .update_page_state(gone, PageState::DeadLink, None, Some(404), None, None)
+            .unwrap();
+
+        let stats = storage.get_origin_stats(None).unwrap();
+        assert_eq!(
+            stats[0],
+            OriginStats {
+                origin: "a.com".to_string(),
+                pages_reached: 3,
+                pages_processed: 1,
+                max_depth: 1,
+                dead_links: 1,
+            }
+        );
+        assert_eq!(stats[1].origin, "wiki.org");
+        assert_eq!(stats[1].pages_reached, 1);
+
+        let scoped = storage.get_origin_stats(Some(second)).unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].pages_reached, 1);
+
+        assert_eq!(
+            storage.get_origin_link_domains(None).unwrap(),
+            vec![("a.com".to_string(), "x.org".to_string(), 1)]
+        );
+        assert!(storage
+            .get_origin_link_domains(Some(first))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage.get_origin_dead_links(None, 10).unwrap(),
+            vec![("a.com".to_string(), "https://a.com/gone".to_string())]
+        );
+        assert!(storage.get_origin_dead_links(None, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_pages() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+
+        let garden = storage
+            .insert_or_get_page("https://garden.example/tips", "garden.example", run_id)
+            .unwrap();
+        let other = storage
+            .insert_or_get_page("https://other.example/", "other.example", run_id)
+            .unwrap();
+        storage
+            .update_page_state(
+                garden,
+                PageState::Processed,
+                Some("Gardening Tips for Beginners"),
+                Some(200),
+                Some("text/html"),
+                None,
+            )
+            .unwrap();
+        storage
+            .update_page_state(
+                other,
+                PageState::Processed,
+                Some("Cooking"),
+                Some(200),
+                Some("text/html"),
+                None,
+            )
+            .unwrap();
+        storage.upsert_depth(garden, "garden.example", 1).unwrap();
+
+        let hits = storage.search_pages("garden*", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].domain, "garden.example");
+        assert_eq!(
+            hits[0].title.as_deref(),
+            Some("Gardening Tips for Beginners")
+        );
+        assert_eq!(hits[0].depths.len(), 1);
+        assert_eq!(hits[0].depths[0].depth, 1);
+
+        // URL text is searchable and punctuation is not treated as syntax
+        let hits = storage.search_pages("other.example", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page_id, other);
+
+        assert!(storage.search_pages("tips cooking", 10).unwrap().is_empty());
+        assert!(storage.search_pages("   ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fts_match_expression() {
+        assert_eq!(
+            fts_match_expression("foo-bar baz*").as_deref(),
+            Some("\"foo-bar\" \"baz\"*")
+        );
+        assert_eq!(
+            fts_match_expression("say \"hi\"").as_deref(),
+            Some("\"say\" \"\"\"hi\"\"\"")
+        );
+        assert_eq!(fts_match_expression(" * "), None);
+    }
+
+    #[test]
+    fn test_domain_and_page_tags() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("test_hash").unwrap();
+        let page = storage
+            .insert_or_get_page("https://a.com/about", "a.com", run_id)
+            .unwrap();
+
+        storage.add_domain_tag("a.com", "partner").unwrap();
+        storage.add_domain_tag("a.com", "partner").unwrap();
+        storage.add_domain_tag("a.com", "news").unwrap();
+        storage.add_domain_tag("b.com", "spam").unwrap();
+        storage.add_page_tag(page, "reviewed").unwrap();
+
+        let domain_tags = storage.get_domain_tags().unwrap();
+        assert_eq!(domain_tags["a.com"], vec!["news", "partner"]);
+        assert_eq!(domain_tags["b.com"], vec!["spam"]);
+
+        let page_tags = storage.get_page_tags().unwrap();
+        assert_eq!(page_tags["https://a.com/about"], vec!["reviewed"]);
+        assert_eq!(
+            storage
+                .count_domain_pages_with_tag("a.com", "reviewed")
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            storage
+                .count_domain_pages_with_tag("b.com", "reviewed")
+                .unwrap(),
+            0
+        );
+
+        assert!(storage.remove_domain_tag("b.com", "spam").unwrap());
+        assert!(!storage.remove_domain_tag("b.com", "spam").unwrap());
+        assert!(storage.remove_page_tag(page, "reviewed").unwrap());
+        assert!(!storage.get_domain_tags().unwrap().contains_key("b.com"));
+        assert!(storage.get_page_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_classification_overrides() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+
+        storage
+            .set_classification_override("spam.com", DomainClassification::Stubbed)
+            .unwrap();
+        storage
+            .set_classification_override("spam.com", DomainClassification::Blacklisted)
+            .unwrap();
+        storage
+            .set_classification_override("*.ads.net", DomainClassification::Stubbed)
+            .unwrap();
+
+        let overrides = storage.get_classification_overrides().unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].domain, "*.ads.net");
+        assert_eq!(overrides[1].domain, "spam.com");
+        assert_eq!(
+            overrides[1].classification,
+            DomainClassification::Blacklisted
+        );
+
+        assert!(storage.remove_classification_override("spam.com").unwrap());
+        assert!(!storage.remove_classification_override("spam.com").unwrap());
+        assert_eq!(storage.get_classification_overrides().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_domain_promotions() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let promotion = DomainPromotion {
+            domain: "found.net".to_string(),
+            run_id,
+            quality_referrers: 12,
+            extra_depth: 2,
+            promoted_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+
+        assert!(storage.record_domain_promotion(&promotion).unwrap());
+        assert!(!storage.record_domain_promotion(&promotion).unwrap());
+        assert_eq!(storage.get_domain_promotions().unwrap(), vec![promotion]);
+
+        // The audit entries go with their run
+        storage.delete_run(run_id).unwrap();
+        assert!(storage.get_domain_promotions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_referring_domains_and_domain_pages() {
+        let mut storage = SqliteStorage::new_in_memory().unwrap();
+        let run_id = storage.create_run("hash").unwrap();
+        let mut page =
+            |url: &str, domain: &str| storage.insert_or_get_page(url, domain, run_id).unwrap();
+        let a1 = page("https://a.com/1", "a.com");
+        let a2 = page("https://a.com/2", "a.com");
+        let b1 = page("https://b.com/1", "b.com");
+        let t1 = page("https://t.net/1", "t.net");
+        let t2 = page("https://t.net/2", "t.net");
+        for (from, to) in [(a1, t1), (a1, t2), (a2, t1), (b1, t2), (t1, t2)] {
+            storage.insert_link(from, to, run_id).unwrap();
+        }
+
+        assert_eq!(
+            storage.get_referring_domains("t.net").unwrap(),
+            vec![("a.com".to_string(), 2), ("b.com".to_string(), 1)]
+        );
+
+        storage
+            .update_page_state(t2, PageState::DepthExceeded, None, None, None, None)
+            .unwrap();
+        let pages = storage
+            .get_domain_pages_by_state("t.net", PageState::DepthExceeded)
+            .unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].id, t2);
+    }
 }
@@ -4,8 +4,15 @@
 //! associated error types.
 
 use crate::state::{DomainState, PageState};
-use crate::storage::{DepthRecord, LinkRecord, PageRecord, RunRecord, RunStatus};
-use std::collections::HashMap;
+use crate::storage::{
+    AnomalyKind, ClassificationOverride, ContactRecord, DepthRecord, DisappearedLink,
+    DomainLinkSummary, DomainPromotion, DomainSummary, ErrorTemplate, FilterHit, HeaderValueCount,
+    LinkKind, LinkRecord, OriginStats, PageAnomaly, PageDirective, PageFingerprint,
+    PageObservation, PageRecord, ProgressSnapshot, RobotsFailure, RunConfigRecord, RunDeletion,
+    RunRecord, RunStatus, SearchHit, StateTransition,
+};
+use crate::url::DomainClassification;
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 
 /// Errors that can occur during storage operations
@@ -63,12 +70,49 @@ pub trait Storage {
     /// Gets the most recent run
     fn get_latest_run(&self) -> StorageResult<Option<RunRecord>>;
 
+    /// Gets all runs, oldest first
+    fn list_runs(&self) -> StorageResult<Vec<RunRecord>>;
+
+    /// Deletes a run and the rows scoped to it
+    ///
+    /// Pages first discovered during the run are removed together with their
+    /// depths, tags, frontier entries and links. Pages discovered earlier but
+    /// fetched during the run are kept and no longer reference it.
+    ///
+    /// # Returns
+    ///
+    /// Counts of the removed rows
+    fn delete_run(&mut self, run_id: i64) -> StorageResult<RunDeletion>;
+
     /// Updates the status of a run
     fn update_run_status(&mut self, run_id: i64, status: RunStatus) -> StorageResult<()>;
 
     /// Marks a run as completed with a finish timestamp
     fn complete_run(&mut self, run_id: i64) -> StorageResult<()>;
 
+    /// Stores the effective configuration a run was started with
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The run the configuration belongs to
+    /// * `config_hash` - Hash of the serialized configuration
+    /// * `config_toml` - The serialized configuration
+    fn save_run_config(
+        &mut self,
+        run_id: i64,
+        config_hash: &str,
+        config_toml: &str,
+    ) -> StorageResult<()>;
+
+    /// Gets the configuration a run was started with, if one was stored
+    fn get_run_config(&self, run_id: i64) -> StorageResult<Option<RunConfigRecord>>;
+
+    /// Records a progress snapshot for a run
+    fn record_run_progress(&mut self, snapshot: &ProgressSnapshot) -> StorageResult<()>;
+
+    /// Gets all progress snapshots of a run, oldest first
+    fn get_run_progress(&self, run_id: i64) -> StorageResult<Vec<ProgressSnapshot>>;
+
     // ===== Page Management =====
 
     /// Inserts a new page or gets the existing page ID
@@ -106,15 +150,196 @@ pub trait Storage {
         error_message: Option<&str>,
     ) -> StorageResult<()>;
 
-    /// Increments the retry count for a page
-    fn increment_retry_count(&mut self, page_id: i64) -> StorageResult<()>;
+    /// Records that a page was fetched during a run
+    fn mark_page_visited(&mut self, page_id: i64, run_id: i64) -> StorageResult<()>;
+
+    /// Records the SHA-256 hash of a page's body
+    fn set_content_hash(&mut self, page_id: i64, content_hash: &str) -> StorageResult<()>;
+
+    /// Records the template hash of a page's body, see `crawler::template_hash`
+    ///
+    /// Clears the page's error template flag; `flag_error_template` sets it
+    /// again if the page still shares its template with enough others.
+    fn set_template_hash(&mut self, page_id: i64, template_hash: &str) -> StorageResult<()>;
+
+    /// Flags the pages of a domain sharing a template as an error template
+    ///
+    /// Nothing is flagged until at least `min_pages` pages share the template.
+    /// The first page discovered keeps its links in the graph, so the
+    /// template's navigation is counted once; the others are flagged.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - Domain of the pages
+    /// * `template_hash` - The shared template hash
+    /// * `min_pages` - Pages needed to count as a template
+    ///
+    /// # Returns
+    ///
+    /// Whether the pages count as an error template
+    fn flag_error_template(
+        &mut self,
+        domain: &str,
+        template_hash: &str,
+        min_pages: u32,
+    ) -> StorageResult<bool>;
+
+    /// Replaces the recorded response headers of a page
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was fetched
+    /// * `headers` - (name, value) pairs of the last response
+    fn set_page_headers(&mut self, page_id: i64, headers: &[(String, String)])
+        -> StorageResult<()>;
+
+    /// Gets the recorded response headers of a page, sorted by name
+    fn get_page_headers(&self, page_id: i64) -> StorageResult<Vec<(String, String)>>;
+
+    /// Counts the domains sending each recorded header value
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of values returned per header
+    ///
+    /// # Returns
+    ///
+    /// Values grouped by header name, most domains first within each header
+    fn get_header_value_counts(&self, limit: usize) -> StorageResult<Vec<HeaderValueCount>>;
+
+    /// Replaces the anomalies recorded for a page
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was fetched
+    /// * `anomalies` - (kind, detail) pairs found on its last fetch
+    fn set_page_anomalies(
+        &mut self,
+        page_id: i64,
+        anomalies: &[(AnomalyKind, String)],
+    ) -> StorageResult<()>;
+
+    /// Gets pages with anomalies
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of anomalies to return
+    ///
+    /// # Returns
+    ///
+    /// Anomalies ordered by kind, then URL
+    fn get_page_anomalies(&self, limit: usize) -> StorageResult<Vec<PageAnomaly>>;
+
+    /// Counts the pages with each kind of anomaly
+    fn count_page_anomalies(&self) -> StorageResult<BTreeMap<AnomalyKind, u64>>;
+
+    /// Replaces the robots directives recorded for a page
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was fetched
+    /// * `directives` - The directives it stated on its last fetch
+    fn set_page_directives(
+        &mut self,
+        page_id: i64,
+        directives: &[PageDirective],
+    ) -> StorageResult<()>;
+
+    /// Gets the robots directives recorded for a page
+    ///
+    /// # Returns
+    ///
+    /// Directives from meta tags, then from headers, each ordered by name
+    fn get_page_directives(&self, page_id: i64) -> StorageResult<Vec<PageDirective>>;
+
+    /// Gets the pages that stated a robots directive
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The directive, e.g. "noarchive" (case-insensitive)
+    ///
+    /// # Returns
+    ///
+    /// URLs of the pages, sorted
+    fn get_pages_with_directive(&self, name: &str) -> StorageResult<Vec<String>>;
+
+    /// Adds retries to the retry count of a page
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was retried
+    /// * `retries` - Number of retries to add
+    fn increment_retry_count(&mut self, page_id: i64, retries: u32) -> StorageResult<()>;
+
+    /// Gets the pages in an error state that were retried the most
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of pages to return
+    ///
+    /// # Returns
+    ///
+    /// Pages with at least one retry, most retries first
+    fn get_most_retried_pages(&self, limit: usize) -> StorageResult<Vec<PageRecord>>;
 
     /// Gets all pages in a specific state
     fn get_pages_by_state(&self, state: PageState) -> StorageResult<Vec<PageRecord>>;
 
+    /// Gets the pages of a domain that are in a specific state
+    fn get_domain_pages_by_state(
+        &self,
+        domain: &str,
+        state: PageState,
+    ) -> StorageResult<Vec<PageRecord>>;
+
+    /// Gets a slice of the pages of a domain, in the order they were found
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain whose pages to get
+    /// * `state` - Only pages in this state, or every page if `None`
+    /// * `limit` - Maximum number of pages to return
+    /// * `offset` - Number of pages to skip
+    fn get_domain_pages_paged(
+        &self,
+        domain: &str,
+        state: Option<PageState>,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<PageRecord>>;
+
+    /// Counts the pages of a domain, only those in `state` if it is set
+    fn count_domain_pages(&self, domain: &str, state: Option<PageState>) -> StorageResult<u64>;
+
     /// Gets pages that were being fetched (for crash recovery)
     fn get_interrupted_pages(&self) -> StorageResult<Vec<PageRecord>>;
 
+    /// Gets the states a page went through, oldest first
+    fn get_state_history(&self, page_id: i64) -> StorageResult<Vec<StateTransition>>;
+
+    /// Records what a run saw of a page, from the page as currently stored
+    ///
+    /// A later observation of the same page in the same run replaces the
+    /// earlier one.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was fetched
+    /// * `run_id` - The run it was fetched in
+    /// * `final_url` - The URL the page was served from after redirects
+    fn record_page_observation(
+        &mut self,
+        page_id: i64,
+        run_id: i64,
+        final_url: Option<&str>,
+    ) -> StorageResult<()>;
+
+    /// Gets the observations of a page, oldest run first
+    fn get_page_observations(&self, page_id: i64) -> StorageResult<Vec<PageObservation>>;
+
+    /// Gets the observations of all pages fetched during a run, by URL
+    fn get_run_observations(&self, run_id: i64) -> StorageResult<Vec<PageObservation>>;
+
     // ===== Depth Tracking =====
 
     /// Inserts or updates a depth record for a page
@@ -130,18 +355,45 @@ pub trait Storage {
     fn upsert_depth(&mut self, page_id: i64, quality_origin: &str, depth: u32)
         -> StorageResult<()>;
 
+    /// Inserts or updates a depth record for a page, with the domain
+    /// boundaries crossed to reach it
+    ///
+    /// Keeps the minimum depth and the minimum number of hops.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page ID
+    /// * `quality_origin` - The quality domain this depth is relative to
+    /// * `depth` - The depth value
+    /// * `hops` - Domain boundaries crossed from the origin
+    fn upsert_depth_with_hops(
+        &mut self,
+        page_id: i64,
+        quality_origin: &str,
+        depth: u32,
+        hops: u32,
+    ) -> StorageResult<()>;
+
     /// Gets all depth records for a page
     fn get_depths(&self, page_id: i64) -> StorageResult<Vec<DepthRecord>>;
 
     /// Checks if a page should be crawled based on depth limits
     ///
     /// Returns true if ANY depth record for this page is within max_depth
-    fn should_crawl(&self, page_id: i64, max_depth: u32) -> StorageResult<bool>;
+    /// and, if given, max_hops
+    fn should_crawl(
+        &self,
+        page_id: i64,
+        max_depth: u32,
+        max_hops: Option<u32>,
+    ) -> StorageResult<bool>;
 
     // ===== Link Management =====
 
     /// Inserts a link between two pages
     ///
+    /// A link already recorded is marked as last seen in the run.
+    ///
     /// # Arguments
     ///
     /// * `from_page_id` - The source page ID
@@ -150,6 +402,49 @@ pub trait Storage {
     fn insert_link(&mut self, from_page_id: i64, to_page_id: i64, run_id: i64)
         -> StorageResult<()>;
 
+    /// Inserts a link between two pages, recording how the source refers to
+    /// the destination
+    ///
+    /// A link already recorded keeps its original kind and discovering run,
+    /// and is marked as last seen in the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_page_id` - The source page ID
+    /// * `to_page_id` - The destination page ID
+    /// * `run_id` - The run ID that discovered this link
+    /// * `kind` - The element the link was found in
+    fn insert_link_of_kind(
+        &mut self,
+        from_page_id: i64,
+        to_page_id: i64,
+        run_id: i64,
+        kind: LinkKind,
+    ) -> StorageResult<()>;
+
+    /// Gets the links missing from their page when a run fetched it again
+    ///
+    /// A link disappeared in a run if the run processed its page but did not
+    /// find it there. Pages the run did not fetch (or failed to) are not
+    /// considered.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The run that refetched the pages
+    /// * `limit` - Maximum number of links to return
+    ///
+    /// # Returns
+    ///
+    /// Links ordered by linking page, then target
+    fn get_disappeared_links(
+        &self,
+        run_id: i64,
+        limit: usize,
+    ) -> StorageResult<Vec<DisappearedLink>>;
+
+    /// Counts the links that disappeared in a run, see `get_disappeared_links`
+    fn count_disappeared_links(&self, run_id: i64) -> StorageResult<u64>;
+
     /// Gets all outgoing links from a page
     fn get_outgoing_links(&self, page_id: i64) -> StorageResult<Vec<LinkRecord>>;
 
@@ -159,6 +454,19 @@ pub trait Storage {
     /// Counts the total number of links
     fn count_links(&self) -> StorageResult<u64>;
 
+    /// Counts links by the element they were found in
+    fn count_links_by_kind(&self) -> StorageResult<BTreeMap<LinkKind, u64>>;
+
+    /// Gets every link, in the order they were recorded
+    fn get_all_links(&self) -> StorageResult<Vec<LinkRecord>>;
+
+    /// Gets the links of the link graph, in the order they were recorded
+    ///
+    /// Like [`get_all_links`](Storage::get_all_links), but without the links
+    /// of pages flagged as copies of an error template. The graph and every
+    /// graph export read their links here.
+    fn get_graph_links(&self) -> StorageResult<Vec<LinkRecord>>;
+
     // ===== Frontier Management =====
 
     /// Adds a page to the crawl frontier
@@ -177,9 +485,33 @@ pub trait Storage {
     /// This is used for scheduler initialization
     fn load_frontier(&self) -> StorageResult<Vec<(i64, u32)>>;
 
+    /// Gets the frontier priority of a page
+    ///
+    /// # Returns
+    ///
+    /// The priority, or `None` if the page is not in the frontier
+    fn get_frontier_priority(&self, page_id: i64) -> StorageResult<Option<u32>>;
+
     /// Clears the frontier
     fn clear_frontier(&mut self) -> StorageResult<()>;
 
+    /// Removes a single page from the frontier
+    fn remove_from_frontier(&mut self, page_id: i64) -> StorageResult<()>;
+
+    /// Counts the distinct pages linking to each page in the frontier
+    ///
+    /// # Returns
+    ///
+    /// Page ID -> number of referring pages, for every frontier page
+    fn get_frontier_referrer_counts(&self) -> StorageResult<HashMap<i64, u64>>;
+
+    /// Changes the priority of frontier pages
+    ///
+    /// # Arguments
+    ///
+    /// * `priorities` - (page ID, new priority) pairs; pages not in the frontier are ignored
+    fn update_frontier_priorities(&mut self, priorities: &[(i64, u32)]) -> StorageResult<()>;
+
     // ===== Domain State Persistence =====
 
     /// Loads all domain states from the database
@@ -199,29 +531,131 @@ pub trait Storage {
     /// Records a stubbed URL with its referrer
     fn record_stubbed(&mut self, url: &str, referrer: &str, run_id: i64) -> StorageResult<()>;
 
-    /// Gets all blacklisted URLs with reference counts
-    fn get_blacklisted_urls(&self) -> StorageResult<Vec<(String, u32)>>;
+    /// Gets blacklisted URLs with reference counts, most referenced first
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of URLs to return
+    /// * `offset` - Number of URLs to skip, for paging through the list
+    fn get_blacklisted_urls(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<(String, u32)>>;
+
+    /// Gets stubbed URLs with reference counts, most referenced first
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of URLs to return
+    /// * `offset` - Number of URLs to skip, for paging through the list
+    fn get_stubbed_urls(&self, limit: usize, offset: usize) -> StorageResult<Vec<(String, u32)>>;
+
+    /// Gets every distinct (stubbed URL, referrer) pair
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Only return references recorded during this run
+    fn get_stub_references(&self, run_id: Option<i64>) -> StorageResult<Vec<(String, String)>>;
 
-    /// Gets all stubbed URLs with reference counts
-    fn get_stubbed_urls(&self) -> StorageResult<Vec<(String, u32)>>;
+    /// Gets every recorded blacklisted and stubbed URL encounter, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Only return encounters recorded during this run
+    fn get_filter_hits(&self, run_id: Option<i64>) -> StorageResult<Vec<FilterHit>>;
 
     // ===== Statistics =====
 
     /// Counts pages by state
     fn count_pages_by_state(&self, state: PageState) -> StorageResult<u64>;
 
+    /// Counts the pages in every state at once
+    ///
+    /// Read from a table maintained as pages change, without scanning pages.
+    ///
+    /// # Returns
+    ///
+    /// State -> number of pages, for states with at least one page
+    fn get_state_counts(&self) -> StorageResult<HashMap<PageState, u64>>;
+
     /// Gets total page count
     fn count_total_pages(&self) -> StorageResult<u64>;
 
     /// Gets count of unique domains discovered
     fn count_unique_domains(&self) -> StorageResult<u64>;
 
+    /// Gets the page counts of every domain, most pages first
+    ///
+    /// Read from a table maintained as pages change, without scanning pages.
+    fn get_domain_summaries(&self) -> StorageResult<Vec<DomainSummary>>;
+
+    /// Gets a slice of the domain summaries, in the order of
+    /// [`get_domain_summaries`](Storage::get_domain_summaries)
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of domains to return
+    /// * `offset` - Number of domains to skip
+    fn get_domain_summaries_paged(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> StorageResult<Vec<DomainSummary>>;
+
     /// Gets error summary (state -> count)
     fn get_error_summary(&self) -> StorageResult<HashMap<PageState, u64>>;
 
     /// Gets domains that hit the request limit
     fn get_rate_limited_domains(&self) -> StorageResult<Vec<String>>;
 
+    /// Gets the domains whose circuit breaker opened after repeated failures
+    ///
+    /// # Returns
+    ///
+    /// (domain, times its circuit opened) pairs, most often first
+    fn get_degraded_domains(&self) -> StorageResult<Vec<(String, u32)>>;
+
+    /// Gets the domains crawled more slowly after answering HTTP 429
+    ///
+    /// # Returns
+    ///
+    /// (domain, factor its delay is multiplied by) pairs, slowest first
+    fn get_slowed_domains(&self) -> StorageResult<Vec<(String, u32)>>;
+
+    /// Gets the domains whose robots.txt failed with a server error
+    ///
+    /// # Returns
+    ///
+    /// The domains, those with the most failed fetches first
+    fn get_robots_failures(&self) -> StorageResult<Vec<RobotsFailure>>;
+
+    /// Counts pages first discovered during a run
+    fn count_pages_discovered_in_run(&self, run_id: i64) -> StorageResult<u64>;
+
+    /// Counts pages last fetched during a run
+    fn count_pages_visited_in_run(&self, run_id: i64) -> StorageResult<u64>;
+
+    /// Counts pages last fetched during a run, per domain
+    fn count_pages_visited_in_run_by_domain(
+        &self,
+        run_id: i64,
+    ) -> StorageResult<BTreeMap<String, u64>>;
+
+    /// Counts links first discovered during a run
+    fn count_links_discovered_in_run(&self, run_id: i64) -> StorageResult<u64>;
+
+    /// Counts pages discovered or visited during a run, by current state
+    fn get_run_state_counts(&self, run_id: i64) -> StorageResult<HashMap<PageState, u64>>;
+
+    /// Gets the domains of pages discovered or visited during a run
+    ///
+    /// Returns a sorted list of unique domains
+    fn get_run_domains(&self, run_id: i64) -> StorageResult<Vec<String>>;
+
+    /// Gets the depth breakdown of pages discovered or visited during a run
+    fn get_run_depth_breakdown(&self, run_id: i64) -> StorageResult<HashMap<u32, usize>>;
+
     /// Gets page count breakdown by depth
     ///
     /// Returns a map of depth -> number of pages at that depth
@@ -231,4 +665,173 @@ pub trait Storage {
     ///
     /// Returns a sorted list of unique domains found during the crawl
     fn get_discovered_domains(&self) -> StorageResult<Vec<String>>;
+
+    /// Gets the quality domain patterns that page depths are recorded for
+    ///
+    /// Returns a sorted list of distinct depth origins
+    fn get_quality_origins(&self) -> StorageResult<Vec<String>>;
+
+    // ===== Analysis =====
+
+    /// Gets the title and content hash of every processed page
+    fn get_page_fingerprints(&self) -> StorageResult<Vec<PageFingerprint>>;
+
+    /// Gets the error and interstitial page templates found, largest first
+    fn get_error_templates(&self) -> StorageResult<Vec<ErrorTemplate>>;
+
+    /// Gets the pages flagged as copies of an error template
+    ///
+    /// Their links are left out of the link graph.
+    fn get_error_template_pages(&self) -> StorageResult<Vec<i64>>;
+
+    /// Gets all links that cross a domain boundary
+    ///
+    /// Links of pages flagged as error template copies are left out.
+    /// Returns (source domain, target URL) pairs
+    fn get_cross_domain_links(&self) -> StorageResult<Vec<(String, String)>>;
+
+    /// Gets the domain-level link graph
+    ///
+    /// Returns (source domain, target domain, link count) for every pair of
+    /// distinct domains connected by at least one link. Links of pages
+    /// flagged as error template copies are left out.
+    fn get_domain_links(&self) -> StorageResult<Vec<(String, String, u64)>>;
+
+    /// Gets every pair of distinct domains connected by links, with example
+    /// source pages
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Only count links first discovered during this run
+    /// * `examples` - Maximum number of source pages listed per pair
+    fn get_domain_link_summaries(
+        &self,
+        run_id: Option<i64>,
+        examples: usize,
+    ) -> StorageResult<Vec<DomainLinkSummary>>;
+
+    /// Gets the other domains linking to a domain
+    ///
+    /// Returns (source domain, distinct source pages) pairs, sorted by domain
+    fn get_referring_domains(&self, domain: &str) -> StorageResult<Vec<(String, u64)>>;
+
+    /// Gets page counts for every quality origin that page depths are recorded for
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Only count pages discovered or visited during this run
+    fn get_origin_stats(&self, run_id: Option<i64>) -> StorageResult<Vec<OriginStats>>;
+
+    /// Gets the domains linked from pages reached from each quality origin
+    ///
+    /// Returns (origin, target domain, link count) for links between distinct
+    /// domains, optionally only those first discovered during `run_id`
+    fn get_origin_link_domains(
+        &self,
+        run_id: Option<i64>,
+    ) -> StorageResult<Vec<(String, String, u64)>>;
+
+    /// Gets dead links reached from each quality origin
+    ///
+    /// Returns (origin, URL) pairs, at most `limit` per origin, ordered by URL
+    fn get_origin_dead_links(
+        &self,
+        run_id: Option<i64>,
+        limit: usize,
+    ) -> StorageResult<Vec<(String, String)>>;
+
+    // ===== Search =====
+
+    /// Searches page titles and URLs using the full-text index
+    ///
+    /// Each whitespace-separated term must match; a trailing `*` on a term
+    /// matches any word with that prefix. Results are ordered by relevance.
+    fn search_pages(&self, query: &str, limit: usize) -> StorageResult<Vec<SearchHit>>;
+
+    // ===== Tags =====
+
+    /// Attaches a tag to a domain (no-op if already present)
+    fn add_domain_tag(&mut self, domain: &str, tag: &str) -> StorageResult<()>;
+
+    /// Removes a tag from a domain
+    ///
+    /// Returns true if the tag was present
+    fn remove_domain_tag(&mut self, domain: &str, tag: &str) -> StorageResult<bool>;
+
+    /// Gets all domain tags as domain -> sorted tags
+    fn get_domain_tags(&self) -> StorageResult<BTreeMap<String, Vec<String>>>;
+
+    /// Attaches a tag to a page (no-op if already present)
+    fn add_page_tag(&mut self, page_id: i64, tag: &str) -> StorageResult<()>;
+
+    /// Removes a tag from a page
+    ///
+    /// Returns true if the tag was present
+    fn remove_page_tag(&mut self, page_id: i64, tag: &str) -> StorageResult<bool>;
+
+    /// Counts the pages of a domain carrying a tag
+    fn count_domain_pages_with_tag(&self, domain: &str, tag: &str) -> StorageResult<u64>;
+
+    /// Gets all page tags as page URL -> sorted tags
+    fn get_page_tags(&self) -> StorageResult<BTreeMap<String, Vec<String>>>;
+
+    // ===== Technologies =====
+
+    /// Records the technologies detected on a page of a domain
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain of the page
+    /// * `technologies` - The detected technology names
+    fn record_technologies(&mut self, domain: &str, technologies: &[&str]) -> StorageResult<()>;
+
+    /// Gets the detected technologies as domain -> sorted technology names
+    fn get_domain_technologies(&self) -> StorageResult<BTreeMap<String, Vec<String>>>;
+
+    // ===== Contacts =====
+
+    /// Replaces the contacts recorded for a page
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id` - The page that was fetched
+    /// * `contacts` - (platform, address or profile URL) pairs found on it
+    fn set_page_contacts(&mut self, page_id: i64, contacts: &[(&str, String)])
+        -> StorageResult<()>;
+
+    /// Gets the harvested contacts, one per domain, platform and value
+    ///
+    /// # Returns
+    ///
+    /// Contacts ordered by domain, platform and value
+    fn get_contacts(&self) -> StorageResult<Vec<ContactRecord>>;
+
+    // ===== Domain Promotions =====
+
+    /// Records the promotion of a discovered domain
+    ///
+    /// # Returns
+    ///
+    /// True if the domain was not promoted before
+    fn record_domain_promotion(&mut self, promotion: &DomainPromotion) -> StorageResult<bool>;
+
+    /// Gets every domain promotion, oldest first
+    fn get_domain_promotions(&self) -> StorageResult<Vec<DomainPromotion>>;
+
+    // ===== Classification Overrides =====
+
+    /// Sets the classification of a domain pattern, replacing any previous override
+    fn set_classification_override(
+        &mut self,
+        domain: &str,
+        classification: DomainClassification,
+    ) -> StorageResult<()>;
+
+    /// Removes the override for a domain pattern
+    ///
+    /// Returns true if an override was present
+    fn remove_classification_override(&mut self, domain: &str) -> StorageResult<bool>;
+
+    /// Gets all classification overrides, sorted by domain
+    fn get_classification_overrides(&self) -> StorageResult<Vec<ClassificationOverride>>;
 }
@@ -0,0 +1,272 @@
+//! Compiled domain classification
+//!
+//! `classify_domain` checks every blacklist, stub and quality entry in turn,
+//! which dominates a crawl once the lists hold thousands of patterns. A
+//! `DomainClassifier` compiles the lists once into a trie keyed by domain
+//! labels from the right ("com", then "example", ...), so classifying a
+//! domain costs one walk down its labels however long the lists are.
+//!
+//! The coordinator classifies through the `Classifier` trait, so library users
+//! can replace the configured lists with their own logic (database lookups,
+//! scoring models, allow/deny services).
+
+use crate::config::Config;
+use crate::url::{matches_wildcard, DomainClassification};
+use std::collections::HashMap;
+
+/// Decides how the domains found during a crawl are treated
+///
+/// `DomainClassifier` is the default, backed by the configuration's domain
+/// lists. Any `Fn(&str) -> DomainClassification` closure is a classifier too.
+pub trait Classifier: Send + Sync {
+    /// Classifies a domain
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The lowercase domain of a discovered or queued URL
+    fn classify(&self, domain: &str) -> DomainClassification;
+}
+
+impl<F> Classifier for F
+where
+    F: Fn(&str) -> DomainClassification + Send + Sync,
+{
+    fn classify(&self, domain: &str) -> DomainClassification {
+        self(domain)
+    }
+}
+
+/// Classifications of the lists, highest priority first
+const LISTS: [DomainClassification; 3] = [
+    DomainClassification::Blacklisted,
+    DomainClassification::Stubbed,
+    DomainClassification::Quality,
+];
+
+/// Domain lists compiled for fast classification
+///
+/// Classifies exactly like [`classify_domain`](crate::url::classify_domain)
+/// with the configuration it was built from; build a new one when the lists
+/// change.
+#[derive(Debug, Clone, Default)]
+pub struct DomainClassifier {
+    /// Exact and `*.domain` patterns, by reversed labels
+    root: TrieNode,
+
+    /// Patterns with other wildcards, as (pattern, index into `LISTS`),
+    /// checked one by one
+    globs: Vec<(String, usize)>,
+}
+
+/// A domain label in the trie
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: HashMap<String, TrieNode>,
+
+    /// Highest-priority list with the domain ending here as an exact entry
+    exact: Option<usize>,
+
+    /// Highest-priority list with a `*.` entry for the domain ending here
+    subtree: Option<usize>,
+}
+
+impl DomainClassifier {
+    /// Compiles the blacklist, stub and quality lists of a configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration with the domain lists
+    pub fn new(config: &Config) -> Self {
+        let mut classifier = Self::default();
+
+        let blacklist = config.blacklist.iter().map(|entry| (&entry.domain, 0));
+        let stub = config.stub.iter().map(|entry| (&entry.domain, 1));
+        let quality = config.quality.iter().map(|entry| (&entry.domain, 2));
+        for (pattern, list) in blacklist.chain(stub).chain(quality) {
+            classifier.insert(pattern, list);
+        }
+
+        classifier
+    }
+
+    /// Adds a pattern of one list
+    fn insert(&mut self, pattern: &str, list: usize) {
+        let (base, subtree) = match pattern.strip_prefix("*.") {
+            Some(base) if !base.contains('*') => (base, true),
+            _ if pattern.contains('*') => {
+                self.globs.push((pattern.to_string(), list));
+                return;
+            }
+            _ => (pattern, false),
+        };
+
+        let mut node = &mut self.root;
+        for label in base.rsplit('.') {
+            node = node.children.entry(label.to_string()).or_default();
+        }
+        let slot = if subtree {
+            &mut node.subtree
+        } else {
+            &mut node.exact
+        };
+        *slot = higher_priority(*slot, Some(list));
+    }
+}
+
+impl Classifier for DomainClassifier {
+    /// Classifies a domain
+    ///
+    /// # Returns
+    ///
+    /// The classification of the highest-priority list with a matching
+    /// entry, or `Discovered`
+    fn classify(&self, domain: &str) -> DomainClassification {
+        let mut best: Option<usize> = None;
+
+        // Every `*.` entry along the path covers the domain
+        let mut node = Some(&self.root);
+        for label in domain.rsplit('.') {
+            node = node.and_then(|node| node.children.get(label));
+            match node {
+                Some(node) => best = higher_priority(best, node.subtree),
+                None => break,
+            }
+        }
+        if let Some(node) = node {
+            best = higher_priority(best, node.exact);
+        }
+
+        for (pattern, list) in &self.globs {
+            if best.is_none_or(|current| *list < current) && matches_wildcard(pattern, domain) {
+                best = Some(*list);
+            }
+        }
+
+        best.map_or(DomainClassification::Discovered, |list| LISTS[list])
+    }
+}
+
+/// The higher-priority (lower index) of two lists, if any
+fn higher_priority(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DomainEntry, QualityEntry};
+    use crate::url::classify_domain;
+    use crate::url::tests::create_test_config;
+
+    fn config(quality: &[&str], blacklist: &[&str], stub: &[&str]) -> Config {
+        let entries = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .map(|domain| DomainEntry {
+                    domain: domain.to_string(),
+                })
+                .collect()
+        };
+
+        let mut config = create_test_config();
+        config.quality = quality
+            .iter()
+            .map(|domain| QualityEntry {
+                domain: domain.to_string(),
+                seeds: vec![],
+                seeds_file: None,
+                seeds_from_sitemap: false,
+            })
+            .collect();
+        config.blacklist = entries(blacklist);
+        config.stub = entries(stub);
+        config
+    }
+
+    #[test]
+    fn test_matches_classify_domain() {
+        let config = config(
+            &["*.example.com", "docs.other.org", "*.mirror-*.net"],
+            &["ads.example.com", "*.tracker.io", "*.ads.*"],
+            &["*.example.com", "cdn-*.other.org", "*.other.org"],
+        );
+        let classifier = DomainClassifier::new(&config);
+
+        let domains = [
+            "example.com",
+            "blog.example.com",
+            "ads.example.com",
+            "x.ads.example.com",
+            "docs.other.org",
+            "other.org",
+            "cdn-eu.other.org",
+            "tracker.io",
+            "a.b.tracker.io",
+            "mytracker.io",
+            "ads.co.uk",
+            "mirror-1.net",
+            "a.mirror-1.net",
+            "unknown.com",
+            "com",
+            "",
+        ];
+        for domain in domains {
+            assert_eq!(
+                classifier.classify(domain),
+                classify_domain(domain, &config),
+                "{}",
+                domain
+            );
+        }
+    }
+
+    #[test]
+    fn test_closure_classifier() {
+        let classifier = |domain: &str| {
+            if domain.ends_with(".gov") {
+                DomainClassification::Quality
+            } else {
+                DomainClassification::Stubbed
+            }
+        };
+        let classifier: &dyn Classifier = &classifier;
+
+        assert_eq!(
+            classifier.classify("data.gov"),
+            DomainClassification::Quality
+        );
+        assert_eq!(
+            classifier.classify("example.com"),
+            DomainClassification::Stubbed
+        );
+    }
+
+    #[test]
+    fn test_list_priority() {
+        let classifier = DomainClassifier::new(&config(
+            &["*.example.com"],
+            &["ads.example.com"],
+            &["*.cdn.example.com"],
+        ));
+
+        assert_eq!(
+            classifier.classify("www.example.com"),
+            DomainClassification::Quality
+        );
+        assert_eq!(
+            classifier.classify("ads.example.com"),
+            DomainClassification::Blacklisted
+        );
+        assert_eq!(
+            classifier.classify("img.cdn.example.com"),
+            DomainClassification::Stubbed
+        );
+        assert_eq!(
+            classifier.classify("example.org"),
+            DomainClassification::Discovered
+        );
+    }
+}
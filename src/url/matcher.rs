@@ -1,15 +1,24 @@
 /// Checks if a domain matches a wildcard pattern
 ///
-/// This function supports two types of patterns:
+/// This function supports three types of patterns:
 /// 1. Exact match: "example.com" matches only "example.com"
 /// 2. Wildcard match: "*.example.com" matches:
 ///    - "example.com" (the bare domain)
 ///    - "blog.example.com" (single subdomain)
 ///    - "api.v2.example.com" (nested subdomains)
+/// 3. Wildcards elsewhere in the pattern:
+///    - `*` within a label matches any characters of that label, so
+///      "cdn-*.example.com" matches "cdn-eu.example.com" but not
+///      "cdn.example.com" or "cdn-eu.img.example.com"
+///    - `*` as a whole label after the first matches one or more labels, so
+///      "*.example.*" matches "example.org", "blog.example.org" and
+///      "example.co.uk"
+///
+/// Patterns are anchored: the whole domain must match.
 ///
 /// # Arguments
 ///
-/// * `pattern` - The domain pattern, optionally starting with "*."
+/// * `pattern` - The domain pattern, optionally containing `*` wildcards
 /// * `candidate` - The domain to check against the pattern
 ///
 /// # Returns
@@ -31,17 +40,71 @@
 /// assert!(matches_wildcard("*.example.com", "blog.example.com"));
 /// assert!(matches_wildcard("*.example.com", "api.v2.example.com"));
 /// assert!(!matches_wildcard("*.example.com", "example.org"));
+///
+/// // Mid-pattern wildcards
+/// assert!(matches_wildcard("cdn-*.example.com", "cdn-eu.example.com"));
+/// assert!(matches_wildcard("*.example.*", "blog.example.org"));
 /// ```
 pub fn matches_wildcard(pattern: &str, candidate: &str) -> bool {
-    if let Some(base) = pattern.strip_prefix("*.") {
+    if let Some(base) = pattern
+        .strip_prefix("*.")
+        .filter(|base| !base.contains('*'))
+    {
         // Wildcard pattern: matches the base domain itself or any subdomain
         candidate == base || candidate.ends_with(&format!(".{}", base))
+    } else if pattern.contains('*') {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        let candidate: Vec<&str> = candidate.split('.').collect();
+        labels_match(&pattern, &candidate, true)
     } else {
         // Exact match only
         candidate == pattern
     }
 }
 
+/// Matches domain labels against pattern labels
+///
+/// A leading `*` label matches zero or more labels (so "*.example.com" covers
+/// the bare domain); a `*` label elsewhere matches one or more.
+fn labels_match(pattern: &[&str], candidate: &[&str], leading: bool) -> bool {
+    match pattern.split_first() {
+        None => candidate.is_empty(),
+        Some((&"*", rest)) => {
+            let min = if leading { 0 } else { 1 };
+            (min..=candidate.len()).any(|skip| labels_match(rest, &candidate[skip..], false))
+        }
+        Some((label, rest)) => match candidate.split_first() {
+            Some((first, others)) => {
+                label_matches(label, first) && labels_match(rest, others, false)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches one label against a pattern label, where `*` stands for any
+/// (possibly empty) run of characters
+fn label_matches(pattern: &str, label: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = label.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard in this label
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,4 +200,40 @@ mod tests {
         assert!(matches_wildcard(pattern, "blog.example.co.uk"));
         assert!(!matches_wildcard(pattern, "co.jp"));
     }
+
+    #[test]
+    fn test_wildcard_within_label() {
+        let pattern = "cdn-*.example.com";
+
+        assert!(matches_wildcard(pattern, "cdn-eu.example.com"));
+        assert!(matches_wildcard(pattern, "cdn-1.example.com"));
+        assert!(!matches_wildcard(pattern, "cdn.example.com"));
+        assert!(!matches_wildcard(pattern, "cdn-eu.img.example.com"));
+        assert!(!matches_wildcard(pattern, "img.cdn-eu.example.com"));
+        assert!(!matches_wildcard(pattern, "cdn-eu.example.com.evil.net"));
+
+        assert!(matches_wildcard(
+            "img*cdn.example.com",
+            "img-eu-cdn.example.com"
+        ));
+        assert!(!matches_wildcard(
+            "img*cdn.example.com",
+            "img-eu-cdn2.example.com"
+        ));
+    }
+
+    #[test]
+    fn test_wildcard_label_in_the_middle_or_end() {
+        let pattern = "*.example.*";
+
+        assert!(matches_wildcard(pattern, "example.com"));
+        assert!(matches_wildcard(pattern, "example.co.uk"));
+        assert!(matches_wildcard(pattern, "blog.example.org"));
+        assert!(!matches_wildcard(pattern, "example"));
+        assert!(!matches_wildcard(pattern, "myexample.com"));
+
+        assert!(matches_wildcard("ads.*.net", "ads.tracker.net"));
+        assert!(matches_wildcard("ads.*.net", "ads.eu.tracker.net"));
+        assert!(!matches_wildcard("ads.*.net", "ads.net"));
+    }
 }
@@ -1,8 +1,10 @@
 //! URL handling module for Sumi-Ripple
 //!
 //! This module provides URL normalization, domain extraction, wildcard matching,
-//! and domain classification functionality.
+//! and domain classification functionality (with `DomainClassifier` compiling
+//! the domain lists for repeated classification).
 
+mod classifier;
 mod domain;
 mod matcher;
 mod normalize;
@@ -10,9 +12,10 @@ mod normalize;
 use crate::config::Config;
 
 // Re-export main functions
+pub use classifier::{Classifier, DomainClassifier};
 pub use domain::{extract_domain, extract_domain_with_port};
 pub use matcher::matches_wildcard;
-pub use normalize::normalize_url;
+pub use normalize::{is_route_fragment, normalize_url, normalize_url_keeping_routes};
 
 /// Domain classification types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -37,6 +40,27 @@ impl DomainClassification {
     pub fn is_terminal(&self) -> bool {
         matches!(self, Self::Blacklisted | Self::Stubbed)
     }
+
+    /// Converts the classification to its database string representation
+    pub fn to_db_string(&self) -> &'static str {
+        match self {
+            Self::Quality => "quality",
+            Self::Blacklisted => "blacklisted",
+            Self::Stubbed => "stubbed",
+            Self::Discovered => "discovered",
+        }
+    }
+
+    /// Parses a classification from its database string representation
+    pub fn from_db_string(s: &str) -> Option<Self> {
+        match s {
+            "quality" => Some(Self::Quality),
+            "blacklisted" => Some(Self::Blacklisted),
+            "stubbed" => Some(Self::Stubbed),
+            "discovered" => Some(Self::Discovered),
+            _ => None,
+        }
+    }
 }
 
 /// Classifies a domain according to the configuration
@@ -48,6 +72,9 @@ impl DomainClassification {
 /// 3. Quality list
 /// 4. Discovered (default)
 ///
+/// Every entry is checked in turn; to classify many domains against the same
+/// lists, build a [`DomainClassifier`] once instead.
+///
 /// # Arguments
 ///
 /// * `domain` - The domain string to classify (should be lowercase)
@@ -74,29 +101,49 @@ impl DomainClassification {
 /// # }
 /// ```
 pub fn classify_domain(domain: &str, config: &Config) -> DomainClassification {
+    classify_domain_with_pattern(domain, config).0
+}
+
+/// Classifies a domain and reports the list entry that decided
+///
+/// Checks the lists in the same priority order as [`classify_domain`].
+///
+/// # Arguments
+///
+/// * `domain` - The domain string to classify (should be lowercase)
+/// * `config` - The crawler configuration
+///
+/// # Returns
+///
+/// The classification, and the matching domain pattern (`None` for
+/// discovered domains, which no list names)
+pub fn classify_domain_with_pattern<'a>(
+    domain: &str,
+    config: &'a Config,
+) -> (DomainClassification, Option<&'a str>) {
     // Priority 1: Check blacklist
     for entry in &config.blacklist {
         if matches_wildcard(&entry.domain, domain) {
-            return DomainClassification::Blacklisted;
+            return (DomainClassification::Blacklisted, Some(&entry.domain));
         }
     }
 
     // Priority 2: Check stub list
     for entry in &config.stub {
         if matches_wildcard(&entry.domain, domain) {
-            return DomainClassification::Stubbed;
+            return (DomainClassification::Stubbed, Some(&entry.domain));
         }
     }
 
     // Priority 3: Check quality list
     for entry in &config.quality {
         if matches_wildcard(&entry.domain, domain) {
-            return DomainClassification::Quality;
+            return (DomainClassification::Quality, Some(&entry.domain));
         }
     }
 
     // Default: Discovered
-    DomainClassification::Discovered
+    (DomainClassification::Discovered, None)
 }
 
 #[cfg(test)]
@@ -104,13 +151,36 @@ mod tests {
     use super::*;
     use crate::config::{CrawlerConfig, DomainEntry, OutputConfig, QualityEntry, UserAgentConfig};
 
-    fn create_test_config() -> Config {
+    pub(super) fn create_test_config() -> Config {
         Config {
             crawler: CrawlerConfig {
                 max_depth: 3,
                 max_concurrent_pages_open: 10,
-                minimum_time_on_page: 1000,
+                per_domain_delay_ms: 1000,
+                per_request_jitter_ms: 0,
+                max_cross_domain_hops: None,
                 max_domain_requests: 500,
+                max_discovered_domain_requests: 20,
+                honor_visit_time: false,
+                progress_snapshot_minutes: 5,
+                max_title_length: 512,
+                recrawl_after_days: None,
+                priority_policy: Default::default(),
+                inlink_priority_interval: 0,
+                lower_tier_fetch_interval: 10,
+                head_requests: false,
+                content_types: vec!["text/html".to_string()],
+                max_page_retries: 10,
+                circuit_breaker_threshold: 5,
+                circuit_breaker_cooldown_secs: 300,
+                max_links_per_page: 1000,
+                error_template_min_pages: 5,
+                trap_page_limit: 50,
+                embedded_links: vec![],
+                harvest_contacts: false,
+                skip_self_links: true,
+                skip_same_path_links: false,
+                hash_route_domains: vec![],
             },
             user_agent: UserAgentConfig {
                 crawler_name: "TestCrawler".to_string(),
@@ -121,10 +191,15 @@ mod tests {
             output: OutputConfig {
                 database_path: "./test.db".to_string(),
                 summary_path: "./summary.md".to_string(),
+                summary_template: None,
+                html_template: None,
+                warc_path: None,
             },
             quality: vec![QualityEntry {
                 domain: "quality.com".to_string(),
                 seeds: vec!["https://quality.com/".to_string()],
+                seeds_file: None,
+                seeds_from_sitemap: false,
             }],
             blacklist: vec![DomainEntry {
                 domain: "bad.com".to_string(),
@@ -132,6 +207,15 @@ mod tests {
             stub: vec![DomainEntry {
                 domain: "stub.com".to_string(),
             }],
+            database: Default::default(),
+            retry: Default::default(),
+            promotion: Default::default(),
+            policy: Default::default(),
+            retention: Default::default(),
+            profile: Default::default(),
+            schedule: Default::default(),
+            blacklist_files: vec![],
+            timeouts: vec![],
         }
     }
 
@@ -171,6 +255,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classify_domain_with_pattern() {
+        let mut config = create_test_config();
+        config.stub.push(DomainEntry {
+            domain: "*.cdn.net".to_string(),
+        });
+        assert_eq!(
+            classify_domain_with_pattern("img.cdn.net", &config),
+            (DomainClassification::Stubbed, Some("*.cdn.net"))
+        );
+        assert_eq!(
+            classify_domain_with_pattern("quality.com", &config),
+            (DomainClassification::Quality, Some("quality.com"))
+        );
+        assert_eq!(
+            classify_domain_with_pattern("random.com", &config),
+            (DomainClassification::Discovered, None)
+        );
+    }
+
     #[test]
     fn test_priority_blacklist_over_stub() {
         let mut config = create_test_config();
@@ -196,6 +300,8 @@ mod tests {
         config.quality.push(QualityEntry {
             domain: "conflict.com".to_string(),
             seeds: vec!["https://conflict.com/".to_string()],
+            seeds_file: None,
+            seeds_from_sitemap: false,
         });
 
         assert_eq!(
@@ -213,6 +319,8 @@ mod tests {
         config.quality.push(QualityEntry {
             domain: "conflict.com".to_string(),
             seeds: vec!["https://conflict.com/".to_string()],
+            seeds_file: None,
+            seeds_from_sitemap: false,
         });
 
         assert_eq!(
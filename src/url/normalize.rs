@@ -1,3 +1,4 @@
+use crate::url::matches_wildcard;
 use crate::UrlError;
 use url::Url;
 
@@ -108,6 +109,52 @@ pub fn normalize_url(url_str: &str) -> Result<Url, UrlError> {
     Ok(url)
 }
 
+/// Normalizes a URL, keeping client-side routes on the given domains
+///
+/// Single-page applications that route with `#!/path` or `#/path` fragments
+/// serve every view from one URL, which plain normalization would collapse
+/// into a single page. On domains matching one of `route_domains` (exact or
+/// `*.domain` patterns) such fragments are kept; other fragments are removed
+/// as usual.
+///
+/// # Arguments
+///
+/// * `url_str` - The URL string to normalize
+/// * `route_domains` - Domain patterns of sites routing with fragments
+///
+/// # Returns
+///
+/// * `Ok(Url)` - Normalized URL
+/// * `Err(UrlError)` - Failed to parse or normalize the URL
+pub fn normalize_url_keeping_routes(
+    url_str: &str,
+    route_domains: &[String],
+) -> Result<Url, UrlError> {
+    let mut url = normalize_url(url_str)?;
+    if route_domains.is_empty() {
+        return Ok(url);
+    }
+
+    let routes_by_fragment = url.host_str().is_some_and(|host| {
+        route_domains
+            .iter()
+            .any(|pattern| matches_wildcard(pattern, host))
+    });
+    if routes_by_fragment {
+        let original = Url::parse(url_str).map_err(|e| UrlError::Parse(e.to_string()))?;
+        if let Some(route) = original.fragment().filter(|f| is_route_fragment(f)) {
+            url.set_fragment(Some(route));
+        }
+    }
+
+    Ok(url)
+}
+
+/// Whether a fragment is a client-side route (`#!/path` or `#/path`)
+pub fn is_route_fragment(fragment: &str) -> bool {
+    fragment.starts_with("!/") || fragment.starts_with('/')
+}
+
 /// Normalizes a URL path by removing dot segments and trailing slashes
 fn normalize_path(path: &str) -> String {
     if path.is_empty() {
@@ -324,4 +371,23 @@ mod tests {
         let result = normalize_url("https://example.com/page?utm_custom=value").unwrap();
         assert_eq!(result.as_str(), "https://example.com/page");
     }
+
+    #[test]
+    fn test_keep_routes_on_listed_domains() {
+        let domains = vec!["*.app.com".to_string()];
+
+        let route = normalize_url_keeping_routes("https://www.app.com/#!/users/7", &domains);
+        assert_eq!(route.unwrap().as_str(), "https://app.com/#!/users/7");
+
+        let route = normalize_url_keeping_routes("https://app.com/#/settings", &domains);
+        assert_eq!(route.unwrap().as_str(), "https://app.com/#/settings");
+
+        // Plain anchors are still removed
+        let anchor = normalize_url_keeping_routes("https://app.com/docs#intro", &domains);
+        assert_eq!(anchor.unwrap().as_str(), "https://app.com/docs");
+
+        // So are routes on other domains
+        let other = normalize_url_keeping_routes("https://site.com/#!/about", &domains);
+        assert_eq!(other.unwrap().as_str(), "https://site.com/");
+    }
 }
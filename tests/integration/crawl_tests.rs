@@ -3,11 +3,22 @@
 //! These tests use wiremock to create mock HTTP servers and test
 //! the full crawl cycle end-to-end.
 
-use sumi_ripple::config::{Config, CrawlerConfig, OutputConfig, QualityEntry, UserAgentConfig};
-use sumi_ripple::crawler::Coordinator;
-use sumi_ripple::state::PageState;
-use sumi_ripple::storage::{SqliteStorage, Storage};
-use wiremock::matchers::{method, path};
+use futures::StreamExt;
+use sumi_ripple::config::{
+    Config, CrawlerConfig, EmbeddedLinkSource, OutputConfig, QualityEntry, UserAgentConfig,
+};
+use sumi_ripple::crawler::{
+    CancellationToken, Coordinator, CrawlEvent, CrawlMode, CrawlerBuilder, FetchFuture,
+    FetchOptions, FetchOutcome, FetchResult, FetchedPage, Fetcher, HeadOutcome, ProcessorError,
+    ReplayFetcher,
+};
+use sumi_ripple::output::{explain_url, load_page_changes};
+use sumi_ripple::state::{PageState, HEAD_FAILURE_LIMIT, ROBOTS_RETRY_LIMIT};
+use sumi_ripple::storage::{
+    AnomalyKind, DirectiveSource, LinkKind, RunStatus, SqliteStorage, Storage,
+};
+use sumi_ripple::DomainClassification;
+use wiremock::matchers::{method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Creates a test configuration with the given quality domain and seeds
@@ -16,8 +27,31 @@ fn create_test_config(quality_domain: &str, seeds: Vec<String>, db_path: &str) -
         crawler: CrawlerConfig {
             max_depth: 2,
             max_concurrent_pages_open: 5,
-            minimum_time_on_page: 10, // Very short for testing
+            per_domain_delay_ms: 10, // Very short for testing
+            per_request_jitter_ms: 0,
+            max_cross_domain_hops: None,
             max_domain_requests: 100,
+            max_discovered_domain_requests: 20,
+            honor_visit_time: false,
+            progress_snapshot_minutes: 5,
+            max_title_length: 512,
+            recrawl_after_days: None,
+            priority_policy: Default::default(),
+            inlink_priority_interval: 0,
+            lower_tier_fetch_interval: 10,
+            head_requests: false,
+            content_types: vec!["text/html".to_string()],
+            max_page_retries: 10,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 300,
+            max_links_per_page: 1000,
+            error_template_min_pages: 5,
+            trap_page_limit: 50,
+            embedded_links: vec![],
+            harvest_contacts: false,
+            skip_self_links: true,
+            skip_same_path_links: false,
+            hash_route_domains: vec![],
         },
         user_agent: UserAgentConfig {
             crawler_name: "TestBot".to_string(),
@@ -28,13 +62,27 @@ fn create_test_config(quality_domain: &str, seeds: Vec<String>, db_path: &str) -
         output: OutputConfig {
             database_path: db_path.to_string(),
             summary_path: "./test_summary.md".to_string(),
+            summary_template: None,
+            html_template: None,
+            warc_path: None,
         },
         quality: vec![QualityEntry {
             domain: quality_domain.to_string(),
             seeds,
+            seeds_file: None,
+            seeds_from_sitemap: false,
         }],
         blacklist: vec![],
         stub: vec![],
+        database: Default::default(),
+        retry: Default::default(),
+        promotion: Default::default(),
+        policy: Default::default(),
+        retention: Default::default(),
+        profile: Default::default(),
+        schedule: Default::default(),
+        blacklist_files: vec![],
+        timeouts: vec![],
     }
 }
 
@@ -80,17 +128,16 @@ async fn test_full_crawl_single_domain() {
     // Mock index page with links
     Mock::given(method("GET"))
         .and(path("/"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Home</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Home</title></head><body>
                     <a href="{}/page1">Page 1</a>
                     <a href="{}/page2">Page 2</a>
                     </body></html>"#,
-                    base_url, base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url, base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -99,10 +146,11 @@ async fn test_full_crawl_single_domain() {
         .and(path("/page1"))
         .respond_with(
             ResponseTemplate::new(200)
-                .set_body_string(
+                .insert_header("server", "nginx/1.24")
+                .set_body_raw(
                     r#"<html><head><title>Page 1</title></head><body>Content 1</body></html>"#,
-                )
-                .insert_header("content-type", "text/html"),
+                    "text/html",
+                ),
         )
         .mount(&mock_server)
         .await;
@@ -110,13 +158,10 @@ async fn test_full_crawl_single_domain() {
     // Mock page2
     Mock::given(method("GET"))
         .and(path("/page2"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(
-                    r#"<html><head><title>Page 2</title></head><body>Content 2</body></html>"#,
-                )
-                .insert_header("content-type", "text/html"),
-        )
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Page 2</title></head><body>Content 2</body></html>"#,
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -154,6 +199,28 @@ async fn test_full_crawl_single_domain() {
         processed
     );
 
+    // The run records the configuration it was started with
+    let run = storage
+        .get_latest_run()
+        .expect("Failed to get run")
+        .expect("No run recorded");
+    let run_config = storage
+        .get_run_config(run.id)
+        .expect("Failed to get run config")
+        .expect("No run config stored");
+    assert_eq!(run_config.config_hash, run.config_hash);
+    assert!(run_config
+        .config_toml
+        .contains(&format!("domain = \"{}\"", domain)));
+
+    // Response headers of interest are recorded per page
+    let headers = storage
+        .get_header_value_counts(10)
+        .expect("Failed to count header values");
+    assert!(headers
+        .iter()
+        .any(|count| count.name == "server" && count.value == "nginx/1.24"));
+
     // Clean up
     let _ = std::fs::remove_file(&db_path);
 }
@@ -187,43 +254,36 @@ async fn test_robots_txt_respect() {
     // Mock index page with link to admin
     Mock::given(method("GET"))
         .and(path("/"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Home</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Home</title></head><body>
                     <a href="{}/allowed">Allowed Page</a>
                     <a href="{}/admin">Admin Page</a>
                     </body></html>"#,
-                    base_url, base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url, base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
     // Mock allowed page
     Mock::given(method("GET"))
         .and(path("/allowed"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(
-                    r#"<html><head><title>Allowed</title></head><body>Allowed content</body></html>"#,
-                )
-                .insert_header("content-type", "text/html"),
-        )
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Allowed</title></head><body>Allowed content</body></html>"#,
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
     // Mock admin page (should never be called)
     Mock::given(method("GET"))
         .and(path("/admin"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(
-                    r#"<html><head><title>Admin</title></head><body>Admin content</body></html>"#,
-                )
-                .insert_header("content-type", "text/html"),
-        )
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Admin</title></head><body>Admin content</body></html>"#,
+            "text/html",
+        ))
         .expect(0) // Should never be called
         .mount(&mock_server)
         .await;
@@ -320,46 +380,43 @@ async fn test_crawl_with_depth_limit() {
     // Create a chain: / -> level1 -> level2 -> level3
     Mock::given(method("GET"))
         .and(path("/"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Root</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Root</title></head><body>
                     <a href="{}/level1">Level 1</a>
                     </body></html>"#,
-                    base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
     Mock::given(method("GET"))
         .and(path("/level1"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Level 1</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Level 1</title></head><body>
                     <a href="{}/level2">Level 2</a>
                     </body></html>"#,
-                    base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
     Mock::given(method("GET"))
         .and(path("/level2"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Level 2</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Level 2</title></head><body>
                     <a href="{}/level3">Level 3</a>
                     </body></html>"#,
-                    base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -367,13 +424,10 @@ async fn test_crawl_with_depth_limit() {
     // Wiremock will automatically verify expect(0) when the mock server drops
     Mock::given(method("GET"))
         .and(path("/level3"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(
-                    r#"<html><head><title>Level 3</title></head><body>Level 3</body></html>"#,
-                )
-                .insert_header("content-type", "text/html"),
-        )
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Level 3</title></head><body>Level 3</body></html>"#,
+            "text/html",
+        ))
         .expect(0) // Should never be called with max_depth=2
         .mount(&mock_server)
         .await;
@@ -441,16 +495,15 @@ async fn test_content_type_handling() {
     // Mock index with link to PDF
     Mock::given(method("GET"))
         .and(path("/"))
-        .respond_with(
-            ResponseTemplate::new(200)
-                .set_body_string(format!(
-                    r#"<html><head><title>Home</title></head><body>
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><head><title>Home</title></head><body>
                     <a href="{}/document.pdf">PDF Document</a>
                     </body></html>"#,
-                    base_url
-                ))
-                .insert_header("content-type", "text/html"),
-        )
+                base_url
+            ),
+            "text/html",
+        ))
         .mount(&mock_server)
         .await;
 
@@ -511,6 +564,1853 @@ async fn test_content_type_handling() {
         processed, content_mismatch, queued, failed
     );
 
+    // The Content-Type is read from the GET response; no HEAD requests are sent
+    let requests = mock_server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|request| request.method == wiremock::http::Method::Get));
+
     // Clean up
     let _ = std::fs::remove_file(&db_path);
 }
+
+#[tokio::test]
+async fn test_seeds_from_sitemap() {
+    // Start a mock server
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // The sitemap lists a page that no crawled page links to, plus an
+    // off-domain URL that must be ignored
+    Mock::given(method("GET"))
+        .and(path("/sitemap.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>{}/</loc></url>
+  <url><loc>{}/orphan</loc></url>
+  <url><loc>https://elsewhere.example/page</loc></url>
+</urlset>"#,
+                base_url, base_url
+            ),
+            "application/xml",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    for page in ["/", "/orphan"] {
+        Mock::given(method("HEAD"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-type", "text/html"))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Page</title></head><body></body></html>",
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_sitemap_seeds_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.quality[0].seeds_from_sitemap = true;
+
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+
+    let orphan = storage
+        .get_page_by_url(&format!("{}/orphan", base_url))
+        .expect("Failed to look up page")
+        .expect("Sitemap URL was not seeded");
+    assert_eq!(orphan.state, PageState::Processed);
+
+    assert!(storage
+        .get_page_by_url("https://elsewhere.example/page")
+        .expect("Failed to look up page")
+        .is_none());
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_incremental_crawl_reuses_recent_pages() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // "/" -> "/a" -> "/b"
+    for (page, next) in [("/", Some("/a")), ("/a", Some("/b")), ("/b", None)] {
+        let links = next
+            .map(|next| format!(r#"<a href="{}{}">next</a>"#, base_url, next))
+            .unwrap_or_default();
+
+        Mock::given(method("HEAD"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-type", "text/html"))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "<html><head><title>Page</title></head><body>{}</body></html>",
+                    links
+                ),
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_incremental_crawl_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    // First run stops before "/b"
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.max_depth = 1;
+    let mut coordinator =
+        Coordinator::new(config.clone(), true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let page_requests = |requests: Vec<wiremock::Request>| -> Vec<String> {
+        requests
+            .into_iter()
+            .filter(|request| request.method == wiremock::http::Method::Get)
+            .map(|request| request.url.path().to_string())
+            .filter(|path| path != "/robots.txt")
+            .collect()
+    };
+    let first_run = page_requests(mock_server.received_requests().await.unwrap());
+    assert_eq!(first_run.len(), 2);
+
+    // The incremental run reaches "/b" through the stored links of "/" and
+    // "/a" without fetching either again
+    config.crawler.max_depth = 2;
+    config.crawler.recrawl_after_days = Some(30);
+    let mut coordinator = Coordinator::with_mode(config, CrawlMode::Incremental)
+        .expect("Failed to create coordinator");
+    coordinator.run().await.expect("Incremental crawl failed");
+
+    let all_runs = page_requests(mock_server.received_requests().await.unwrap());
+    assert_eq!(&all_runs[first_run.len()..], ["/b"]);
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let page_b = storage
+        .get_page_by_url(&format!("{}/b", base_url))
+        .expect("Failed to look up page")
+        .expect("Page was not discovered");
+    assert_eq!(page_b.state, PageState::Processed);
+    assert_eq!(
+        storage.get_latest_run().unwrap().unwrap().id,
+        coordinator.run_id()
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_runs_keep_their_own_page_observations() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    let db_path = format!("/tmp/test_page_observations_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+
+    // The second run finds the page retitled
+    let mut runs = Vec::new();
+    for title in ["Old", "New"] {
+        mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!("<html><head><title>{}</title></head></html>", title),
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let mut coordinator =
+            Coordinator::new(config.clone(), true).expect("Failed to create coordinator");
+        coordinator.run().await.expect("Crawl failed");
+        runs.push(coordinator.run_id());
+    }
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let home = storage
+        .get_page_by_url(&format!("{}/", base_url))
+        .expect("Failed to look up page")
+        .expect("Seed page missing");
+    assert_eq!(home.title.as_deref(), Some("New"));
+    let observations: Vec<_> = storage
+        .get_page_observations(home.id)
+        .expect("Failed to get observations")
+        .into_iter()
+        .map(|observation| {
+            (
+                observation.run_id,
+                observation.title,
+                observation.status_code,
+            )
+        })
+        .collect();
+    assert_eq!(
+        observations,
+        vec![
+            (runs[0], Some("Old".to_string()), Some(200)),
+            (runs[1], Some("New".to_string()), Some(200)),
+        ]
+    );
+
+    let changes = load_page_changes(&storage, runs[0], runs[1]).expect("Failed to diff runs");
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].changes[0].field, "title");
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_head_skipped_after_repeated_rejections() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // The server rejects every HEAD request
+    Mock::given(method("HEAD"))
+        .respond_with(ResponseTemplate::new(405))
+        .mount(&mock_server)
+        .await;
+
+    // "/" -> "/1" -> ... -> "/4"
+    let pages = ["/", "/1", "/2", "/3", "/4"];
+    for (i, page) in pages.iter().enumerate() {
+        let links = pages
+            .get(i + 1)
+            .map(|next| format!(r#"<a href="{}{}">next</a>"#, base_url, next))
+            .unwrap_or_default();
+
+        Mock::given(method("GET"))
+            .and(path(*page))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(format!("<html><body>{}</body></html>", links), "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_head_skip_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.max_depth = 5;
+    config.crawler.head_requests = true;
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // Pages are fetched despite the rejected HEAD, which stops being sent
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    for page in pages {
+        let record = storage
+            .get_page_by_url(&format!("{}{}", base_url, page))
+            .expect("Failed to look up page")
+            .expect("Page was not discovered");
+        assert_eq!(record.state, PageState::Processed, "{}", page);
+    }
+
+    let head_requests = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.method == wiremock::http::Method::Head)
+        .count();
+    assert_eq!(head_requests, HEAD_FAILURE_LIMIT as usize);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_links_per_page_are_capped() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // A directory page linking to five pages
+    let links: String = (1..=5)
+        .map(|i| format!(r#"<a href="{}/entry{}">Entry {}</a>"#, base_url, i, i))
+        .collect();
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!("<html><body>{}{}</body></html>", links, " ".repeat(1024)),
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>Entry</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_link_cap_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.max_links_per_page = 2;
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // Only the first two links are followed
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    assert_eq!(
+        storage.count_total_pages().expect("Failed to count pages"),
+        3
+    );
+    assert!(storage
+        .get_page_by_url(&format!("{}/entry3", base_url))
+        .expect("Failed to look up page")
+        .is_none());
+
+    // The directory page is flagged, the near-empty entries too
+    let anomalies = storage
+        .count_page_anomalies()
+        .expect("Failed to count anomalies");
+    assert_eq!(anomalies.get(&AnomalyKind::ExcessiveLinks), Some(&1));
+    assert_eq!(anomalies.get(&AnomalyKind::TinyBody), Some(&2));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_soft_404_pages_form_an_error_template() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    let links: String = (1..=3)
+        .map(|i| format!(r#"<a href="{}/gone{}">Old {}</a>"#, base_url, i, i))
+        .collect();
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(format!("<html><body>{}</body></html>", links), "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    // A "not found" page served with 200 OK that repeats the requested path
+    for i in 1..=3 {
+        Mock::given(method("GET"))
+            .and(path(format!("/gone{}", i)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    r#"<html><body><a href="{}/">Home</a><p>Nothing at /gone{}</p></body></html>"#,
+                    base_url, i
+                ),
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_error_template_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.error_template_min_pages = 3;
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let templates = storage
+        .get_error_templates()
+        .expect("Failed to load templates");
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].pages, 3);
+    assert_eq!(templates[0].example_url, format!("{}/gone1", base_url));
+    // All but the first copy are left out of the link graph
+    assert_eq!(
+        storage
+            .get_error_template_pages()
+            .expect("Failed to load template pages")
+            .len(),
+        2
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_calendar_trap_is_cut_off() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><body><a href="{}/events?month=10">Events</a></body></html>"#,
+                base_url
+            ),
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    // Each month links to the one before, back to month 1
+    for month in 1..=10 {
+        Mock::given(method("GET"))
+            .and(path("/events"))
+            .and(query_param("month", month.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    r#"<html><body><a href="{}/events?month={}">Previous</a></body></html>"#,
+                    base_url,
+                    month - 1
+                ),
+                "text/html",
+            ))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_calendar_trap_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.max_depth = 20;
+    config.crawler.trap_page_limit = 2;
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // The links of the first two calendar pages are followed, not those of
+    // the third
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    assert_eq!(
+        storage.count_total_pages().expect("Failed to count pages"),
+        4
+    );
+    let tags = storage.get_page_tags().expect("Failed to load tags");
+    assert_eq!(tags.len(), 3);
+    assert!(tags
+        .values()
+        .all(|tags| tags == &vec!["calendar".to_string()]));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_local_policy_denies_urls() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><body><a href="{0}/about">About</a><a href="{0}/Admin/users">Users</a></body></html>"#,
+                base_url
+            ),
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/about"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>About</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    // Denied URLs are never requested
+    Mock::given(method("GET"))
+        .and(path("/Admin/users"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_local_policy_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.policy.deny = vec!["(?i)/admin/".to_string()];
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let denied = storage
+        .get_page_by_url(&format!("{}/Admin/users", base_url))
+        .expect("Failed to look up page")
+        .expect("Denied page not recorded");
+    assert_eq!(denied.state, PageState::PolicyDenied);
+    assert_eq!(
+        denied.error_message.as_deref(),
+        Some("Denied by local policy: (?i)/admin/")
+    );
+    let about = storage
+        .get_page_by_url(&format!("{}/about", base_url))
+        .expect("Failed to look up page")
+        .expect("Page not recorded");
+    assert_eq!(about.state, PageState::Processed);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_embedded_links_are_followed() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // A page reachable only through an iframe, an image map and a refresh
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><meta http-equiv="refresh" content="5; url=/moved"></head>
+            <body><iframe src="/framed"></iframe>
+            <map name="m"><area href="/mapped"></map></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>Inner</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_embedded_links_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.embedded_links =
+        vec![EmbeddedLinkSource::Iframe, EmbeddedLinkSource::MetaRefresh];
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    for page in ["/framed", "/moved"] {
+        let page = storage
+            .get_page_by_url(&format!("{}{}", base_url, page))
+            .expect("Failed to look up page")
+            .expect("Embedded link was not followed");
+        assert_eq!(page.state, PageState::Processed);
+    }
+
+    // Image map areas were not enabled
+    assert!(storage
+        .get_page_by_url(&format!("{}/mapped", base_url))
+        .expect("Failed to look up page")
+        .is_none());
+
+    let kinds = storage
+        .count_links_by_kind()
+        .expect("Failed to count links");
+    assert_eq!(kinds.get(&LinkKind::Iframe), Some(&1));
+    assert_eq!(kinds.get(&LinkKind::MetaRefresh), Some(&1));
+    assert_eq!(kinds.get(&LinkKind::Area), None);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_self_links_are_skipped() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // Links to the page itself, with a fragment, with a query, and elsewhere
+    Mock::given(method("GET"))
+        .and(path("/list"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><body><a href="/list">Top</a><a href="/list#end">End</a>
+            <a href="/list?sort=asc">Sort</a><a href="/other">Other</a></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>Other</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    for skip_same_path_links in [false, true] {
+        let db_path = format!(
+            "/tmp/test_self_links_{}_{}.db",
+            skip_same_path_links,
+            std::process::id()
+        );
+        let _ = std::fs::remove_file(&db_path);
+
+        let mut config = create_test_config(&domain, vec![format!("{}/list", base_url)], &db_path);
+        config.crawler.skip_same_path_links = skip_same_path_links;
+        let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+        coordinator.run().await.expect("Crawl failed");
+
+        let storage =
+            SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+        let list = storage
+            .get_page_by_url(&format!("{}/list", base_url))
+            .expect("Failed to look up page")
+            .expect("Seed page missing");
+        let outgoing = storage
+            .get_outgoing_links(list.id)
+            .expect("Failed to get links");
+        assert!(outgoing.iter().all(|link| link.to_page_id != list.id));
+        assert_eq!(outgoing.len(), if skip_same_path_links { 1 } else { 2 });
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
+
+#[tokio::test]
+async fn test_redirect_loop_records_chain() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    // /a -> /b -> /a
+    for (from, to) in [("/a", "/b"), ("/b", "/a")] {
+        Mock::given(method("GET"))
+            .and(path(from))
+            .respond_with(
+                ResponseTemplate::new(301)
+                    .insert_header("Location", format!("{}{}", base_url, to).as_str()),
+            )
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_redirect_loop_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let config = create_test_config(&domain, vec![format!("{}/a", base_url)], &db_path);
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let page = storage
+        .get_page_by_url(&format!("{}/a", base_url))
+        .expect("Failed to look up page")
+        .expect("Seed page missing");
+    assert_eq!(page.state, PageState::Failed);
+    let error = page.error_message.expect("Missing error message");
+    assert!(error.contains("Redirect loop detected"));
+    assert!(error.contains(&format!(
+        "{base}/a -> {base}/b -> {base}/a",
+        base = base_url
+    )));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_unavailable_robots_txt_is_retried() {
+    for always_failing in [false, true] {
+        let mock_server = MockServer::start().await;
+        let base_url = mock_server.uri();
+        let domain = url::Url::parse(&base_url)
+            .expect("Failed to parse base URL")
+            .host_str()
+            .expect("Failed to extract host")
+            .to_string();
+
+        // robots.txt is busy once, or for good
+        let busy = Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "1"));
+        if always_failing {
+            busy.mount(&mock_server).await;
+        } else {
+            busy.up_to_n_times(1).mount(&mock_server).await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw("<html><body>Home</body></html>", "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let db_path = format!(
+            "/tmp/test_robots_unavailable_{}_{}.db",
+            always_failing,
+            std::process::id()
+        );
+        let _ = std::fs::remove_file(&db_path);
+
+        let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+        let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+        coordinator.run().await.expect("Crawl failed");
+
+        let storage =
+            SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+        let page = storage
+            .get_page_by_url(&format!("{}/", base_url))
+            .expect("Failed to look up page")
+            .expect("Seed page missing");
+        let failures = storage
+            .get_robots_failures()
+            .expect("Failed to get robots failures");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].last_error.as_deref(),
+            Some("robots.txt unavailable (HTTP 503)")
+        );
+
+        // Waiting for robots.txt does not count as fetching the page
+        let fetching = storage
+            .get_state_history(page.id)
+            .expect("Failed to get state history")
+            .iter()
+            .filter(|transition| transition.to_state == PageState::Fetching)
+            .count();
+
+        if always_failing {
+            assert_eq!(page.state, PageState::Failed);
+            assert_eq!(
+                page.error_message.as_deref(),
+                Some("robots.txt unavailable (HTTP 503)")
+            );
+            assert_eq!(failures[0].failures, ROBOTS_RETRY_LIMIT);
+            assert_eq!(fetching, 0);
+        } else {
+            assert_eq!(page.state, PageState::Processed);
+            assert_eq!(failures[0].failures, 1);
+            assert_eq!(fetching, 1);
+        }
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_page_is_requeued() {
+    for always_limited in [false, true] {
+        let mock_server = MockServer::start().await;
+        let base_url = mock_server.uri();
+        let domain = url::Url::parse(&base_url)
+            .expect("Failed to parse base URL")
+            .host_str()
+            .expect("Failed to extract host")
+            .to_string();
+
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+            .mount(&mock_server)
+            .await;
+
+        // The page is rate limited once, or for good
+        let limited = Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(429));
+        if always_limited {
+            limited.mount(&mock_server).await;
+        } else {
+            limited.up_to_n_times(1).mount(&mock_server).await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw("<html><body>Home</body></html>", "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let db_path = format!(
+            "/tmp/test_rate_limited_{}_{}.db",
+            always_limited,
+            std::process::id()
+        );
+        let _ = std::fs::remove_file(&db_path);
+
+        let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+        config.crawler.max_page_retries = 2;
+        let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+        coordinator.run().await.expect("Crawl failed");
+
+        let storage =
+            SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+        let page = storage
+            .get_page_by_url(&format!("{}/", base_url))
+            .expect("Failed to look up page")
+            .expect("Seed page missing");
+        let fetches = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == "/")
+            .count();
+
+        if always_limited {
+            // The first fetch and both retries
+            assert_eq!(page.state, PageState::RateLimited);
+            assert_eq!(page.retry_count, 2);
+            assert_eq!(fetches, 3);
+        } else {
+            assert_eq!(page.state, PageState::Processed);
+            assert_eq!(page.retry_count, 1);
+            assert_eq!(fetches, 2);
+        }
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
+
+#[tokio::test]
+async fn test_visit_time_defers_page_untouched() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    // A Visit-time window that opens in two hours
+    let now = chrono::Utc::now();
+    let window = format!(
+        "{}-{}",
+        (now + chrono::Duration::hours(2)).format("%H%M"),
+        (now + chrono::Duration::hours(3)).format("%H%M")
+    );
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(format!("User-agent: *\nVisit-time: {}", window)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_visit_time_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.honor_visit_time = true;
+
+    let cancel = CancellationToken::new();
+    let stop = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        stop.cancel();
+    });
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator
+        .run_until_cancelled(cancel)
+        .await
+        .expect("Crawl failed");
+    drop(coordinator);
+
+    // The page was neither requested nor marked as being fetched
+    let requests = mock_server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|request| request.url.path() == "/robots.txt"));
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let page = storage
+        .get_page_by_url(&format!("{}/", base_url))
+        .unwrap()
+        .expect("Seed page missing");
+    assert!(!page.state.is_error());
+    assert_ne!(page.state, PageState::Fetching);
+    let history = storage.get_state_history(page.id).unwrap();
+    assert!(history
+        .iter()
+        .all(|transition| transition.to_state != PageState::Fetching));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_page_directives_are_recorded() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/news"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Robots-Tag", "otherbot: noindex, TestBot: noarchive")
+                .set_body_raw(
+                    r#"<html><head><meta name="robots" content="nosnippet"></head>
+                    <body><a href="/about">About</a></body></html>"#,
+                    "text/html",
+                ),
+        )
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>About</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_page_directives_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let config = create_test_config(&domain, vec![format!("{}/news", base_url)], &db_path);
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // Directives are recorded, but do not stop the crawl
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let news = storage
+        .get_page_by_url(&format!("{}/news", base_url))
+        .expect("Failed to look up page")
+        .expect("Seed page missing");
+    assert_eq!(news.state, PageState::Processed);
+    let directives: Vec<_> = storage
+        .get_page_directives(news.id)
+        .expect("Failed to get directives")
+        .into_iter()
+        .map(|directive| (directive.source, directive.name))
+        .collect();
+    assert_eq!(
+        directives,
+        vec![
+            (DirectiveSource::Meta, "nosnippet".to_string()),
+            (DirectiveSource::Header, "noarchive".to_string()),
+        ]
+    );
+    assert_eq!(
+        storage
+            .get_pages_with_directive("noarchive")
+            .expect("Failed to get pages"),
+        vec![format!("{}/news", base_url)]
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_run_stream_reports_events() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Home</title></head>
+            <body><a href="/missing">Missing</a></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_run_stream_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    let coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    let run_id = coordinator.run_id();
+    let events: Vec<CrawlEvent> = coordinator.run_stream().collect().await;
+
+    let missing = format!("{}/missing", base_url);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        CrawlEvent::PageFetched { title, body, .. }
+            if title.as_deref() == Some("Home") && body.contains("/missing")
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        CrawlEvent::LinkDiscovered { url, kind: LinkKind::Anchor, .. } if *url == missing
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        CrawlEvent::PageFailed { url, state: PageState::DeadLink, .. } if *url == missing
+    )));
+    assert_eq!(
+        events.last(),
+        Some(&CrawlEvent::Finished {
+            run_id,
+            error: None
+        })
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_finished_crawl_passes_integrity_checks() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string("User-agent: *\nDisallow: /private"),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(
+                r#"<html><body>
+                    <a href="{0}/page1">Page 1</a>
+                    <a href="{0}/missing">Missing</a>
+                    <a href="{0}/private">Private</a>
+                    </body></html>"#,
+                base_url
+            ),
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/page1"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>One</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_integrity_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // Pages that were fetched, failed or disallowed have left the frontier
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    assert_eq!(
+        storage
+            .get_pages_by_state(PageState::Processed)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        storage
+            .get_pages_by_state(PageState::DeadLink)
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        storage.get_pages_by_state(PageState::Failed).unwrap().len(),
+        1
+    );
+    assert!(storage.load_frontier().unwrap().is_empty());
+    for issue in storage.check_integrity().unwrap() {
+        assert!(issue.is_ok(), "{}: {:?}", issue.check, issue.examples);
+    }
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_explain_crawled_page() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>Home</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_explain_crawled_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    let mut coordinator =
+        Coordinator::new(config.clone(), true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // A processed page is no longer waiting in the frontier
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let explanation =
+        explain_url(&storage, &config, &format!("{}/", base_url)).expect("Explain failed");
+    assert_eq!(
+        explanation.page.map(|page| page.state),
+        Some(PageState::Processed)
+    );
+    assert_eq!(explanation.frontier_priority, None);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_cancelled_crawl_is_resumed() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>Home</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_cancelled_crawl_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+
+    // Cancelled before the first page: nothing is fetched
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let mut coordinator =
+        Coordinator::new(config.clone(), true).expect("Failed to create coordinator");
+    let run_id = coordinator.run_id();
+    coordinator
+        .run_until_cancelled(cancel)
+        .await
+        .expect("Cancelled crawl failed");
+    drop(coordinator);
+
+    {
+        let storage =
+            SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+        let run = storage.get_latest_run().unwrap().expect("Run missing");
+        assert_eq!(run.status, RunStatus::Interrupted);
+        let home = storage
+            .get_page_by_url(&format!("{}/", base_url))
+            .unwrap()
+            .expect("Seed page missing");
+        assert_eq!(home.state, PageState::Discovered);
+    }
+
+    // Resuming continues the interrupted run
+    let mut coordinator = Coordinator::new(config, false).expect("Failed to create coordinator");
+    assert_eq!(coordinator.run_id(), run_id);
+    coordinator.run().await.expect("Resumed crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let run = storage.get_latest_run().unwrap().expect("Run missing");
+    assert_eq!(run.status, RunStatus::Completed);
+    let home = storage
+        .get_page_by_url(&format!("{}/", base_url))
+        .unwrap()
+        .expect("Seed page missing");
+    assert_eq!(home.state, PageState::Processed);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_scrubbed_frontier_is_resumed() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/list"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<html><body>List</body></html>", "text/html"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_scrubbed_frontier_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let mut config = create_test_config(
+        &domain,
+        vec![format!("{}/list?sid=abc123&page=2", base_url)],
+        &db_path,
+    );
+    config.database.scrub_pii = true;
+
+    // The seed is queued under its scrubbed URL before the run is cancelled
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let mut coordinator =
+        Coordinator::new(config.clone(), true).expect("Failed to create coordinator");
+    coordinator
+        .run_until_cancelled(cancel)
+        .await
+        .expect("Cancelled crawl failed");
+    drop(coordinator);
+
+    // The resumed run fetches the scrubbed URL, without the session id
+    let mut coordinator =
+        Coordinator::with_mode(config, CrawlMode::Resume).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Resumed crawl failed");
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let list: Vec<_> = requests
+        .iter()
+        .filter(|request| request.url.path() == "/list")
+        .collect();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].url.query(), Some("page=2"));
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let page = storage
+        .get_page_by_url(&format!("{}/list?page=2", base_url))
+        .unwrap()
+        .expect("Scrubbed page missing");
+    assert_eq!(page.state, PageState::Processed);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_page_processors_see_fetched_pages() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Home</title></head>
+            <body>Tokio <a href="/about">About</a></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/about"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><head><title>About</title></head><body>Rust</body></html>",
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_page_processors_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+
+    let keywords = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorder = {
+        let keywords = keywords.clone();
+        move |page: &FetchedPage<'_>| -> Result<(), ProcessorError> {
+            let keyword = if page.body.contains("Rust") {
+                "rust"
+            } else {
+                "tokio"
+            };
+            keywords
+                .lock()
+                .unwrap()
+                .push((page.parsed.title.clone().unwrap_or_default(), keyword));
+            Ok(())
+        }
+    };
+    let failing = |_: &FetchedPage<'_>| -> Result<(), ProcessorError> { Err("unavailable".into()) };
+
+    let mut coordinator = CrawlerBuilder::new(config)
+        .mode(CrawlMode::Fresh)
+        .page_processor(failing)
+        .page_processor(recorder)
+        .build()
+        .expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // A failing processor neither stops the crawl nor the other processors
+    let mut keywords = keywords.lock().unwrap().clone();
+    keywords.sort();
+    assert_eq!(
+        keywords,
+        vec![("About".to_string(), "rust"), ("Home".to_string(), "tokio")]
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Serves pages from memory instead of over HTTP
+struct CannedFetcher {
+    pages: std::collections::HashMap<String, &'static str>,
+    requests: std::sync::Mutex<Vec<String>>,
+}
+
+impl Fetcher for CannedFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, _options: &'a FetchOptions) -> FetchFuture<'a> {
+        self.requests.lock().unwrap().push(url.to_string());
+        let result = match self.pages.get(url) {
+            Some(body) => FetchResult::Success {
+                final_url: url.to_string(),
+                status_code: 200,
+                content_type: "text/html".to_string(),
+                body: body.to_string(),
+                title: None,
+                headers: vec![],
+            },
+            None => FetchResult::HttpError {
+                status_code: 404,
+                state: PageState::DeadLink,
+            },
+        };
+        Box::pin(async move {
+            FetchOutcome {
+                result,
+                head: HeadOutcome::Skipped,
+                retries: 0,
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_custom_fetcher() {
+    // The server only answers robots.txt; pages come from the fetcher
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    let home = format!("{}/", base_url);
+    let about = format!("{}/about", base_url);
+    let fetcher = std::sync::Arc::new(CannedFetcher {
+        pages: [(
+            home.clone(),
+            r#"<html><head><title>Canned</title></head>
+            <body><a href="/about">About</a><a href="/gone">Gone</a></body></html>"#,
+        )]
+        .into_iter()
+        .chain([(about.clone(), "<html><body>About</body></html>")])
+        .collect(),
+        requests: std::sync::Mutex::new(vec![]),
+    });
+
+    let db_path = format!("/tmp/test_custom_fetcher_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(&domain, vec![home.clone()], &db_path);
+
+    let mut coordinator = CrawlerBuilder::new(config)
+        .mode(CrawlMode::Fresh)
+        .fetcher(fetcher.clone())
+        .build()
+        .expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let mut requests = fetcher.requests.lock().unwrap().clone();
+    requests.sort();
+    assert_eq!(
+        requests,
+        vec![home.clone(), about, format!("{}/gone", base_url)]
+    );
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let page = storage
+        .get_page_by_url(&home)
+        .unwrap()
+        .expect("Seed page missing");
+    assert_eq!(page.state, PageState::Processed);
+    assert_eq!(page.title.as_deref(), Some("Canned"));
+    let gone = storage
+        .get_page_by_url(&format!("{}/gone", base_url))
+        .unwrap()
+        .expect("Linked page missing");
+    assert_eq!(gone.state, PageState::DeadLink);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Formats a WARC response record for an HTTP response
+fn warc_record(uri: &str, http: &str) -> String {
+    format!(
+        "WARC/1.0\r\nWARC-Type: response\r\nWARC-Target-URI: {}\r\n\
+         Content-Type: application/http; msgtype=response\r\n\
+         Content-Length: {}\r\n\r\n{}\r\n\r\n",
+        uri,
+        http.len(),
+        http
+    )
+}
+
+#[tokio::test]
+async fn test_replay_from_warc() {
+    // Nothing is served over the network: pages and robots.txt come from the archive
+    let warc_path = format!("/tmp/test_replay_{}.warc", std::process::id());
+    let archive = [
+        warc_record(
+            "https://replay.test/robots.txt",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nUser-agent: *\nDisallow: /private",
+        ),
+        warc_record(
+            "https://replay.test/",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
+             <html><head><title>Archived</title></head><body>\
+             <a href=\"/moved\">Moved</a><a href=\"/private\">Private</a>\
+             <a href=\"/missing\">Missing</a></body></html>",
+        ),
+        warc_record(
+            "https://replay.test/moved",
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: /new\r\n\r\n",
+        ),
+        warc_record(
+            "https://replay.test/new",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<html><title>New</title></html>",
+        ),
+    ]
+    .concat();
+    std::fs::write(&warc_path, archive).expect("Failed to write WARC");
+
+    let db_path = format!("/tmp/test_replay_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let config = create_test_config(
+        "replay.test",
+        vec!["https://replay.test/".to_string()],
+        &db_path,
+    );
+
+    let fetcher =
+        ReplayFetcher::from_warc(std::path::Path::new(&warc_path)).expect("Failed to load WARC");
+    let mut coordinator = CrawlerBuilder::new(config)
+        .mode(CrawlMode::Fresh)
+        .fetcher(fetcher)
+        .build()
+        .expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let state = |url: &str| {
+        storage
+            .get_page_by_url(url)
+            .unwrap()
+            .unwrap_or_else(|| panic!("{} missing", url))
+    };
+    let home = state("https://replay.test/");
+    assert_eq!(home.state, PageState::Processed);
+    assert_eq!(home.title.as_deref(), Some("Archived"));
+    let moved = state("https://replay.test/moved");
+    assert_eq!(moved.state, PageState::Processed);
+    assert_eq!(moved.title.as_deref(), Some("New"));
+    assert_eq!(
+        state("https://replay.test/private").state,
+        PageState::Failed
+    );
+    assert_eq!(
+        state("https://replay.test/missing").state,
+        PageState::Unreachable
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+    let _ = std::fs::remove_file(&warc_path);
+}
+
+#[tokio::test]
+async fn test_warc_output() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><body><a href="/about">About</a></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/about"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<html>About</html>", "text/html"))
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_warc_output_{}.db", std::process::id());
+    let warc_path = format!("/tmp/test_warc_output_{}.warc.gz", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+    let _ = std::fs::remove_file(&warc_path);
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.output.warc_path = Some(warc_path.clone());
+
+    let mut coordinator =
+        Coordinator::with_mode(config, CrawlMode::Fresh).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    // Every fetched page can be replayed from the archive
+    let replay =
+        ReplayFetcher::from_warc(std::path::Path::new(&warc_path)).expect("Failed to load WARC");
+    for (page, body) in [
+        (
+            "/",
+            r#"<html><body><a href="/about">About</a></body></html>"#,
+        ),
+        ("/about", "<html>About</html>"),
+    ] {
+        let outcome = replay
+            .fetch(&format!("{}{}", base_url, page), &FetchOptions::default())
+            .await;
+        match outcome.result {
+            FetchResult::Success { body: archived, .. } => assert_eq!(archived, body),
+            other => panic!("{} not archived: {:?}", page, other),
+        }
+    }
+
+    let _ = std::fs::remove_file(&db_path);
+    let _ = std::fs::remove_file(&warc_path);
+}
+
+#[tokio::test]
+async fn test_custom_classifier() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><body><a href="https://denied.example/page">Elsewhere</a></body></html>"#,
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    let db_path = format!("/tmp/test_custom_classifier_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    // The configured lists know nothing about denied.example
+    let config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    let crawled = domain.clone();
+    coordinator
+        .set_classifier(move |candidate: &str| {
+            if candidate == crawled {
+                DomainClassification::Quality
+            } else {
+                DomainClassification::Blacklisted
+            }
+        })
+        .expect("Failed to set classifier");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let blacklisted = storage
+        .get_blacklisted_urls(10, 0)
+        .expect("Failed to get blacklisted URLs");
+    assert_eq!(blacklisted.len(), 1);
+    assert!(blacklisted[0].0.contains("denied.example"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_discovered_domain_promotion() {
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+    let domain = url::Url::parse(&base_url)
+        .expect("Failed to parse base URL")
+        .host_str()
+        .expect("Failed to extract host")
+        .to_string();
+    // The same server under another name is a discovered domain
+    let other_url = base_url.replace("127.0.0.1", "localhost");
+
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("User-agent: *\nAllow: /"))
+        .mount(&mock_server)
+        .await;
+
+    let pages = [
+        (
+            "/",
+            format!(r#"<a href="/a">A</a><a href="{}/x">X</a>"#, other_url),
+        ),
+        ("/a", format!(r#"<a href="{}/x">X</a>"#, other_url)),
+        ("/x", r#"<a href="/y">Y</a>"#.to_string()),
+        ("/y", r#"<a href="/z">Z</a>"#.to_string()),
+        ("/z", String::new()),
+    ];
+    for (page, links) in pages {
+        Mock::given(method("GET"))
+            .and(path(page))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(format!("<html><body>{}</body></html>", links), "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+    }
+
+    let db_path = format!("/tmp/test_promotion_{}.db", std::process::id());
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = create_test_config(&domain, vec![format!("{}/", base_url)], &db_path);
+    config.crawler.max_depth = 1;
+    config.promotion.enabled = true;
+    config.promotion.min_quality_referrers = 2;
+    config.promotion.extra_depth = 1;
+
+    let mut coordinator = Coordinator::new(config, true).expect("Failed to create coordinator");
+    coordinator.run().await.expect("Crawl failed");
+
+    let storage = SqliteStorage::new(std::path::Path::new(&db_path)).expect("Failed to open DB");
+    let promotions = storage
+        .get_domain_promotions()
+        .expect("Failed to get promotions");
+    assert_eq!(promotions.len(), 1);
+    assert_eq!(promotions[0].domain, "localhost");
+    assert_eq!(promotions[0].quality_referrers, 2);
+
+    // Depth 2 is within the promoted limit, depth 3 is not
+    let page_state = |url: String| {
+        storage
+            .get_page_by_url(&url)
+            .expect("Failed to get page")
+            .expect("Page not recorded")
+            .state
+    };
+    assert_eq!(page_state(format!("{}/y", other_url)), PageState::Processed);
+    assert_eq!(
+        page_state(format!("{}/z", other_url)),
+        PageState::DepthExceeded
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}